@@ -7,6 +7,7 @@
 
 use crate::address::{Address, NetworkUnchecked};
 use crate::prelude::String;
+use crate::script::witness_version::WitnessVersion;
 use crate::script::{witness_program, witness_version};
 use crate::Network;
 
@@ -16,6 +17,8 @@
 pub enum FromScriptError {
     /// Script is not a p2pkh, p2sh or witness program.
     UnrecognizedScript,
+    /// Script is a p2pk output, which can not be represented as an address.
+    P2pk,
     /// A witness program error.
     WitnessProgram(witness_program::Error),
     /// A witness version construction error.
@@ -34,6 +37,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             WitnessVersion(ref e) => write_err!(f, "witness version construction error"; e),
             WitnessProgram(ref e) => write_err!(f, "witness program error"; e),
             UnrecognizedScript => write!(f, "script is not a p2pkh, p2sh or witness program"),
+            P2pk => write!(f, "p2pk outputs can not be represented as an address"),
         }
     }
 }
@@ -44,7 +48,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use FromScriptError::*;
 
         match *self {
-            UnrecognizedScript => None,
+            UnrecognizedScript | P2pk => None,
             WitnessVersion(ref e) => Some(e),
             WitnessProgram(ref e) => Some(e),
         }
@@ -138,7 +142,9 @@ fn from(e: NetworkValidationError) -> Self { Self::NetworkValidation(e) }
 pub struct UnknownHrpError(pub String);
 
 impl fmt::Display for UnknownHrpError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "unknown hrp: {}", self.0) }
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown hrp: {}, expected one of bc, tb or bcrt", self.0)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -155,11 +161,34 @@ pub struct NetworkValidationError {
     pub(crate) address: Address<NetworkUnchecked>,
 }
 
+impl NetworkValidationError {
+    /// Returns the network that was required.
+    pub fn required(&self) -> Network { self.required }
+
+    /// Returns the address that failed validation.
+    pub fn address(&self) -> &Address<NetworkUnchecked> { &self.address }
+
+    /// Returns the networks the address is actually valid for.
+    pub fn valid_networks(&self) -> impl Iterator<Item = Network> + '_ {
+        self.address.valid_networks()
+    }
+}
+
 impl fmt::Display for NetworkValidationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "address ")?;
         fmt::Display::fmt(&self.address.0, f)?;
-        write!(f, " is not valid on {}", self.required)
+        if self.valid_networks().next().is_none() {
+            return write!(f, " is not valid for any network but {} was required", self.required);
+        }
+        write!(f, " is valid for ")?;
+        for (i, network) in self.valid_networks().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", network)?;
+        }
+        write!(f, " but {} was required", self.required)
     }
 }
 
@@ -172,12 +201,27 @@ impl std::error::Error for NetworkValidationError {}
 pub enum Bech32Error {
     /// Parse SegWit Bech32 error.
     ParseBech32(ParseBech32Error),
+    /// The address mixes upper and lower case characters.
+    MixedCase,
+    /// The address contains a character that is not used by bech32.
+    InvalidCharacter(char),
+    /// The checksum is invalid, most likely because the address contains a typo.
+    InvalidChecksum,
+    /// The address was encoded with the wrong checksum for its witness version.
+    WrongChecksumVariant {
+        /// The witness version of the address.
+        version: WitnessVersion,
+        /// The checksum variant that must be used for `version`.
+        expected: Bech32Variant,
+    },
     /// A witness version conversion/parsing error.
     WitnessVersion(witness_version::TryFromError),
     /// A witness program error.
     WitnessProgram(witness_program::Error),
     /// Tried to parse an unknown HRP.
     UnknownHrp(UnknownHrpError),
+    /// The HRP is not the one that was required.
+    UnexpectedHrp(UnexpectedHrpError),
 }
 
 impl From<Infallible> for Bech32Error {
@@ -190,9 +234,22 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
         match *self {
             ParseBech32(ref e) => write_err!(f, "SegWit parsing error"; e),
+            MixedCase => write!(
+                f,
+                "address mixes upper and lower case characters, use either all lowercase or all uppercase"
+            ),
+            InvalidCharacter(c) =>
+                write!(f, "invalid character {:?}, check the address for typos", c),
+            InvalidChecksum => write!(f, "invalid checksum, check the address for typos"),
+            WrongChecksumVariant { version, expected } => write!(
+                f,
+                "witness version {} addresses must be encoded using {}, re-encode the address",
+                version, expected
+            ),
             WitnessVersion(ref e) => write_err!(f, "witness version conversion/parsing error"; e),
             WitnessProgram(ref e) => write_err!(f, "witness program error"; e),
             UnknownHrp(ref e) => write_err!(f, "unknown hrp error"; e),
+            UnexpectedHrp(ref e) => write_err!(f, "unexpected hrp error"; e),
         }
     }
 }
@@ -204,9 +261,11 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 
         match *self {
             ParseBech32(ref e) => Some(e),
+            MixedCase | InvalidCharacter(_) | InvalidChecksum | WrongChecksumVariant { .. } => None,
             WitnessVersion(ref e) => Some(e),
             WitnessProgram(ref e) => Some(e),
             UnknownHrp(ref e) => Some(e),
+            UnexpectedHrp(ref e) => Some(e),
         }
     }
 }
@@ -223,6 +282,55 @@ impl From<UnknownHrpError> for Bech32Error {
     fn from(e: UnknownHrpError) -> Self { Self::UnknownHrp(e) }
 }
 
+impl From<UnexpectedHrpError> for Bech32Error {
+    fn from(e: UnexpectedHrpError) -> Self { Self::UnexpectedHrp(e) }
+}
+
+/// The checksum variants used to encode SegWit addresses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bech32Variant {
+    /// Bech32 as defined in BIP-173, used for witness version 0.
+    Bech32,
+    /// Bech32m as defined in BIP-350, used for witness versions 1 and above.
+    Bech32m,
+}
+
+impl fmt::Display for Bech32Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Bech32Variant::Bech32 => f.write_str("bech32"),
+            Bech32Variant::Bech32m => f.write_str("bech32m"),
+        }
+    }
+}
+
+/// The HRP of a SegWit address is not the one that was required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UnexpectedHrpError {
+    /// The required HRP, in lowercase.
+    pub(crate) expected: String,
+    /// The HRP of the address, in lowercase.
+    pub(crate) found: String,
+}
+
+impl UnexpectedHrpError {
+    /// Returns the required HRP, in lowercase.
+    pub fn expected(&self) -> &str { &self.expected }
+
+    /// Returns the HRP of the address, in lowercase.
+    pub fn found(&self) -> &str { &self.found }
+}
+
+impl fmt::Display for UnexpectedHrpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected hrp {} but found {}", self.expected, self.found)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnexpectedHrpError {}
+
 /// Bech32 parsing related error.
 // This wrapper exists because we do not want to expose the `bech32` crate in our public API.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -249,6 +357,15 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { Some(&self.0) }
 pub enum Base58Error {
     /// Parse legacy Base58 error.
     ParseBase58(base58::Error),
+    /// The address contains a character that is not used by base58.
+    InvalidCharacter(u8),
+    /// The checksum is invalid, most likely because the address contains a typo.
+    InvalidChecksum {
+        /// The checksum computed from the address data.
+        expected: u32,
+        /// The checksum found in the address.
+        actual: u32,
+    },
     /// Legacy address is too long.
     LegacyAddressTooLong(LegacyAddressTooLongError),
     /// Invalid base58 payload data length for legacy address.
@@ -267,6 +384,14 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
         match *self {
             ParseBase58(ref e) => write_err!(f, "legacy parsing error"; e),
+            InvalidCharacter(c) => {
+                write!(f, "invalid base58 character {:?}, check the address for typos", c as char)
+            }
+            InvalidChecksum { expected, actual } => write!(
+                f,
+                "invalid base58 checksum {:#010x} (expected {:#010x}), check the address for typos",
+                actual, expected
+            ),
             LegacyAddressTooLong(ref e) => write_err!(f, "legacy address length error"; e),
             InvalidBase58PayloadLength(ref e) => write_err!(f, "legacy payload length error"; e),
             InvalidLegacyPrefix(ref e) => write_err!(f, "legacy prefix error"; e),
@@ -281,6 +406,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 
         match *self {
             ParseBase58(ref e) => Some(e),
+            InvalidCharacter(_) | InvalidChecksum { .. } => None,
             LegacyAddressTooLong(ref e) => Some(e),
             InvalidBase58PayloadLength(ref e) => Some(e),
             InvalidLegacyPrefix(ref e) => Some(e),
@@ -289,7 +415,15 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 }
 
 impl From<base58::Error> for Base58Error {
-    fn from(e: base58::Error) -> Self { Self::ParseBase58(e) }
+    fn from(e: base58::Error) -> Self {
+        if let Some(c) = e.invalid_character() {
+            Self::InvalidCharacter(c)
+        } else if let Some((actual, expected)) = e.incorrect_checksum() {
+            Self::InvalidChecksum { expected, actual }
+        } else {
+            Self::ParseBase58(e)
+        }
+    }
 }
 
 impl From<LegacyAddressTooLongError> for Base58Error {
@@ -41,7 +41,10 @@
 
 pub mod error;
 pub mod script_pubkey;
+#[cfg(feature = "serde")]
+pub mod serde;
 
+use core::cmp::Ordering;
 use core::fmt;
 use core::marker::PhantomData;
 use core::str::FromStr;
@@ -52,20 +55,20 @@
 use internals::array::ArrayExt;
 use secp256k1::{Secp256k1, Verification, XOnlyPublicKey};
 
-use crate::address::script_pubkey::ScriptBufExt as _;
+use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
 use crate::constants::{
     PUBKEY_ADDRESS_PREFIX_MAIN, PUBKEY_ADDRESS_PREFIX_TEST, SCRIPT_ADDRESS_PREFIX_MAIN,
     SCRIPT_ADDRESS_PREFIX_TEST,
 };
 use crate::crypto::key::{
-    CompressedPublicKey, PubkeyHash, PublicKey, TweakedPublicKey, UntweakedPublicKey,
+    CompressedPublicKey, PubkeyHash, PublicKey, TapTweak as _, TweakedPublicKey, UntweakedPublicKey,
 };
-use crate::network::{Network, NetworkKind, Params};
-use crate::prelude::{String, ToOwned};
-use crate::script::witness_program::WitnessProgram;
+use crate::network::{Network, NetworkKind, Params, TestnetVersion};
+use crate::prelude::{Box, String, ToOwned, Vec};
+use crate::script::witness_program::{self, WitnessProgram};
 use crate::script::witness_version::WitnessVersion;
 use crate::script::{
-    self, RedeemScriptSizeError, Script, ScriptBuf, ScriptExt as _, ScriptHash, WScriptHash,
+    RedeemScriptSizeError, Script, ScriptBuf, ScriptExt as _, ScriptHash, WScriptHash,
     WitnessScriptSizeError,
 };
 use crate::taproot::TapNodeHash;
@@ -73,9 +76,10 @@
 #[rustfmt::skip]                // Keep public re-exports separate.
 #[doc(inline)]
 pub use self::error::{
-        Base58Error, Bech32Error, FromScriptError, InvalidBase58PayloadLengthError,
+        Base58Error, Bech32Error, Bech32Variant, FromScriptError, InvalidBase58PayloadLengthError,
         InvalidLegacyPrefixError, LegacyAddressTooLongError, NetworkValidationError,
         ParseError, UnknownAddressTypeError, UnknownHrpError, ParseBech32Error,
+        UnexpectedHrpError,
 };
 
 /// The different types of addresses.
@@ -94,6 +98,8 @@ pub enum AddressType {
     P2tr,
     /// Pay to anchor.
     P2a,
+    /// A witness program of a version not yet defined by a soft fork (v2 to v16).
+    WitnessProgram(WitnessVersion),
 }
 
 impl fmt::Display for AddressType {
@@ -105,6 +111,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             AddressType::P2wsh => "p2wsh",
             AddressType::P2tr => "p2tr",
             AddressType::P2a => "p2a",
+            AddressType::WitnessProgram(version) => return write!(f, "witness_v{}", version),
         })
     }
 }
@@ -119,7 +126,36 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
             "p2wsh" => Ok(AddressType::P2wsh),
             "p2tr" => Ok(AddressType::P2tr),
             "p2a" => Ok(AddressType::P2a),
-            _ => Err(UnknownAddressTypeError(s.to_owned())),
+            _ => s
+                .strip_prefix("witness_v")
+                .filter(|version| !version.starts_with('0') || version.len() == 1)
+                .and_then(|version| version.parse::<u8>().ok())
+                .and_then(|version| WitnessVersion::try_from(version).ok())
+                .filter(|version| version.to_num() >= 2)
+                .map(AddressType::WitnessProgram)
+                .ok_or_else(|| UnknownAddressTypeError(s.to_owned())),
+        }
+    }
+}
+
+/// The type of an address, including witness programs that are not of a known [`AddressType`].
+///
+/// Returned by [`Address::address_type_or_unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AddressTypeOrUnknown {
+    /// A known address type.
+    Known(AddressType),
+    /// A witness program that is not of any known type, for example a version 1 witness program
+    /// that is not 32 bytes long.
+    UnknownWitnessProgram(WitnessVersion),
+}
+
+impl fmt::Display for AddressTypeOrUnknown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AddressTypeOrUnknown::Known(ref t) => fmt::Display::fmt(t, f),
+            AddressTypeOrUnknown::UnknownWitnessProgram(version) =>
+                write!(f, "unknown_witness_v{}", version),
         }
     }
 }
@@ -174,11 +210,68 @@ impl NetworkValidationUnchecked for NetworkUnchecked {}
 ///
 /// This struct represents the inner representation of an address without the network validation
 /// tag, which is used to ensure that addresses are used only on the appropriate network.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum AddressInner {
-    P2pkh { hash: PubkeyHash, network: NetworkKind },
-    P2sh { hash: ScriptHash, network: NetworkKind },
-    Segwit { program: WitnessProgram, hrp: KnownHrp },
+    P2pkh { hash: PubkeyHash, network: LegacyNetwork },
+    P2sh { hash: ScriptHash, network: LegacyNetwork },
+    Segwit { program: WitnessProgram, hrp: SegwitHrp },
+}
+
+impl AddressInner {
+    /// Returns the type of this address.
+    fn address_type_or_unknown(&self) -> AddressTypeOrUnknown {
+        use AddressTypeOrUnknown::{Known, UnknownWitnessProgram};
+
+        match *self {
+            AddressInner::P2pkh { .. } => Known(AddressType::P2pkh),
+            AddressInner::P2sh { .. } => Known(AddressType::P2sh),
+            AddressInner::Segwit { ref program, hrp: _ } =>
+                if program.is_p2wpkh() {
+                    Known(AddressType::P2wpkh)
+                } else if program.is_p2wsh() {
+                    Known(AddressType::P2wsh)
+                } else if program.is_p2tr() {
+                    Known(AddressType::P2tr)
+                } else if program.is_p2a() {
+                    Known(AddressType::P2a)
+                } else if program.version().to_num() >= 2 {
+                    Known(AddressType::WitnessProgram(program.version()))
+                } else {
+                    UnknownWitnessProgram(program.version())
+                },
+        }
+    }
+
+    /// Returns the hash or witness program encoded in this address.
+    fn payload(&self) -> &[u8] {
+        match *self {
+            AddressInner::P2pkh { ref hash, network: _ } => hash.as_byte_array(),
+            AddressInner::P2sh { ref hash, network: _ } => hash.as_byte_array(),
+            AddressInner::Segwit { ref program, hrp: _ } => program.program().as_bytes(),
+        }
+    }
+}
+
+/// Orders by address type, then by payload and lastly by network, see [`Address`].
+impl Ord for AddressInner {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use AddressInner::*;
+
+        self.address_type_or_unknown()
+            .cmp(&other.address_type_or_unknown())
+            .then_with(|| self.payload().cmp(other.payload()))
+            .then_with(|| match (self, other) {
+                (P2pkh { network: a, .. }, P2pkh { network: b, .. })
+                | (P2sh { network: a, .. }, P2sh { network: b, .. }) => a.cmp(b),
+                (Segwit { hrp: a, .. }, Segwit { hrp: b, .. }) => a.cmp(b),
+                // Addresses of the same type always use the same variant.
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for AddressInner {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
 }
 
 /// Formats bech32 as upper case if alternate formatting is chosen (`{:#}`).
@@ -188,19 +281,15 @@ fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self {
             P2pkh { hash, network } => {
                 let mut prefixed = [0; 21];
-                prefixed[0] = match network {
-                    NetworkKind::Main => PUBKEY_ADDRESS_PREFIX_MAIN,
-                    NetworkKind::Test => PUBKEY_ADDRESS_PREFIX_TEST,
-                };
+                prefixed[0] =
+                    network.to_version(PUBKEY_ADDRESS_PREFIX_MAIN, PUBKEY_ADDRESS_PREFIX_TEST);
                 prefixed[1..].copy_from_slice(hash.as_byte_array());
                 base58::encode_check_to_fmt(fmt, &prefixed[..])
             }
             P2sh { hash, network } => {
                 let mut prefixed = [0; 21];
-                prefixed[0] = match network {
-                    NetworkKind::Main => SCRIPT_ADDRESS_PREFIX_MAIN,
-                    NetworkKind::Test => SCRIPT_ADDRESS_PREFIX_TEST,
-                };
+                prefixed[0] =
+                    network.to_version(SCRIPT_ADDRESS_PREFIX_MAIN, SCRIPT_ADDRESS_PREFIX_TEST);
                 prefixed[1..].copy_from_slice(hash.as_byte_array());
                 base58::encode_check_to_fmt(fmt, &prefixed[..])
             }
@@ -270,6 +359,72 @@ fn to_hrp(self) -> Hrp {
     }
 }
 
+/// The human-readable part of a SegWit address.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SegwitHrp {
+    /// One of the HRPs used by the Bitcoin networks.
+    Known(KnownHrp),
+    /// Any other HRP, for example one used by a sidechain.
+    ///
+    /// Boxed because an [`Hrp`] is large and custom HRPs are rare.
+    Custom(Box<Hrp>),
+}
+
+impl SegwitHrp {
+    /// Constructs a new [`SegwitHrp`], using [`SegwitHrp::Known`] if `hrp` is a known HRP.
+    fn from_hrp(hrp: Hrp) -> Self {
+        KnownHrp::from_hrp(hrp).map_or_else(|_| Self::Custom(Box::new(hrp)), Self::Known)
+    }
+
+    /// Converts to a [`bech32::Hrp`].
+    fn to_hrp(&self) -> Hrp {
+        match self {
+            Self::Known(hrp) => hrp.to_hrp(),
+            Self::Custom(hrp) => **hrp,
+        }
+    }
+}
+
+/// The network of a legacy (base58) address, determined by its version byte.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum LegacyNetwork {
+    /// One of the version bytes used by the Bitcoin networks.
+    Known(NetworkKind),
+    /// Any other version byte, for example one used by a fork.
+    Custom(u8),
+}
+
+impl LegacyNetwork {
+    /// Constructs a new [`LegacyNetwork`] from the version byte of an address whose type uses
+    /// `main` and `test` as the version bytes on the Bitcoin networks.
+    fn from_version(version: u8, main: u8, test: u8) -> Self {
+        if version == main {
+            Self::Known(NetworkKind::Main)
+        } else if version == test {
+            Self::Known(NetworkKind::Test)
+        } else {
+            Self::Custom(version)
+        }
+    }
+
+    /// Returns the version byte, `main` and `test` are the version bytes of the address type.
+    fn to_version(self, main: u8, test: u8) -> u8 {
+        match self {
+            Self::Known(NetworkKind::Main) => main,
+            Self::Known(NetworkKind::Test) => test,
+            Self::Custom(version) => version,
+        }
+    }
+}
+
+impl From<NetworkKind> for LegacyNetwork {
+    fn from(network: NetworkKind) -> Self { Self::Known(network) }
+}
+
+impl From<KnownHrp> for SegwitHrp {
+    fn from(hrp: KnownHrp) -> Self { Self::Known(hrp) }
+}
+
 impl From<Network> for KnownHrp {
     fn from(n: Network) -> Self { Self::from_network(n) }
 }
@@ -383,6 +538,30 @@ pub enum AddressData {
 /// assert_eq!(format!("{:?}", address), "132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM");
 /// ```
 ///
+/// ### Ordering
+///
+/// Addresses are ordered by their type first, in the order of the [`AddressType`] variants with
+/// witness programs of an unknown type last. Addresses of the same type are ordered by their
+/// payload bytes (the hash for legacy addresses and the witness program for SegWit addresses) and
+/// addresses with the same payload by network. This ordering is part of the API and will not
+/// change between releases, it is intended for producing deterministic lists of addresses.
+///
+/// ```
+/// # use bitcoin::Address;
+/// let p2wsh = "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3";
+/// let p2pkh = "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY";
+/// let p2wpkh = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+///
+/// let mut addresses = [p2wsh, p2pkh, p2wpkh]
+///     .iter()
+///     .map(|s| s.parse::<Address<_>>().unwrap().assume_checked())
+///     .collect::<Vec<_>>();
+/// addresses.sort();
+///
+/// let sorted = addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+/// assert_eq!(sorted, [p2pkh, p2wpkh, p2wsh]);
+/// ```
+///
 /// ### Relevant BIPs
 ///
 /// * [BIP13 - Address Format for pay-to-script-hash](https://github.com/bitcoin/bips/blob/master/bip-0013.mediawiki)
@@ -391,7 +570,7 @@ pub enum AddressData {
 /// * [BIP142 - Address Format for Segregated Witness](https://github.com/bitcoin/bips/blob/master/bip-0142.mediawiki)
 /// * [BIP341 - Taproot: SegWit version 1 spending rules](https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki)
 /// * [BIP350 - Bech32m format for v1+ witness addresses](https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki)
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 // The `#[repr(transparent)]` attribute is used to guarantee the layout of the `Address` struct. It
 // is an implementation detail and users should not rely on it in their code.
 #[repr(transparent)]
@@ -408,15 +587,15 @@ fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self
 }
 
 #[cfg(feature = "serde")]
-impl<'de, U: NetworkValidationUnchecked> serde::Deserialize<'de> for Address<U> {
+impl<'de, U: NetworkValidationUnchecked> ::serde::Deserialize<'de> for Address<U> {
     fn deserialize<D>(deserializer: D) -> Result<Address<U>, D::Error>
     where
-        D: serde::de::Deserializer<'de>,
+        D: ::serde::de::Deserializer<'de>,
     {
         use core::fmt::Formatter;
 
         struct Visitor<U>(PhantomData<U>);
-        impl<U> serde::de::Visitor<'_> for Visitor<U>
+        impl<U> ::serde::de::Visitor<'_> for Visitor<U>
         where
             U: NetworkValidationUnchecked + NetworkValidation,
             Address<U>: FromStr,
@@ -429,7 +608,7 @@ fn expecting(&self, f: &mut Formatter) -> core::fmt::Result {
 
             fn visit_str<E>(self, v: &str) -> core::result::Result<Self::Value, E>
             where
-                E: serde::de::Error,
+                E: ::serde::de::Error,
             {
                 // We know that `U` is only ever `NetworkUnchecked` but the compiler does not.
                 let address = v.parse::<Address<NetworkUnchecked>>().map_err(E::custom)?;
@@ -442,15 +621,23 @@ fn visit_str<E>(self, v: &str) -> core::result::Result<Self::Value, E>
 }
 
 #[cfg(feature = "serde")]
-impl<V: NetworkValidation> serde::Serialize for Address<V> {
+impl<V: NetworkValidation> ::serde::Serialize for Address<V> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        S: serde::Serializer,
+        S: ::serde::Serializer,
     {
         serializer.collect_str(&DisplayUnchecked(self))
     }
 }
 
+impl<V: NetworkValidation + Eq> Ord for Address<V> {
+    fn cmp(&self, other: &Self) -> Ordering { self.0.cmp(&other.0) }
+}
+
+impl<V: NetworkValidation + Eq> PartialOrd for Address<V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
 /// Methods on [`Address`] that can be called on both `Address<NetworkChecked>` and
 /// `Address<NetworkUnchecked>`.
 impl<V: NetworkValidation> Address<V> {
@@ -463,16 +650,32 @@ pub fn as_unchecked(&self) -> &Address<NetworkUnchecked> {
     pub fn into_unchecked(self) -> Address<NetworkUnchecked> { Address(self.0, PhantomData) }
 
     /// Returns the [`NetworkKind`] of this address.
+    ///
+    /// Addresses with a custom HRP or version byte don't belong to any Bitcoin network, as a
+    /// fallback [`NetworkKind::Test`] is returned for them even if they are used on the main
+    /// network of another chain. [`Address::valid_networks`] yields no networks for such addresses.
     pub fn network_kind(&self) -> NetworkKind {
         use AddressInner::*;
         match self.0 {
-            P2pkh { hash: _, ref network } => *network,
-            P2sh { hash: _, ref network } => *network,
-            Segwit { program: _, ref hrp } => NetworkKind::from(*hrp),
+            P2pkh { hash: _, network: LegacyNetwork::Known(network) } => network,
+            P2sh { hash: _, network: LegacyNetwork::Known(network) } => network,
+            P2pkh { hash: _, network: LegacyNetwork::Custom(_) } => NetworkKind::Test,
+            P2sh { hash: _, network: LegacyNetwork::Custom(_) } => NetworkKind::Test,
+            Segwit { program: _, hrp: SegwitHrp::Known(hrp) } => NetworkKind::from(hrp),
+            Segwit { program: _, hrp: SegwitHrp::Custom(_) } => NetworkKind::Test,
         }
     }
 }
 
+/// All the networks, in the order returned by [`Address::valid_networks`].
+const NETWORKS: [Network; 5] = [
+    Network::Bitcoin,
+    Network::Testnet(TestnetVersion::V3),
+    Network::Testnet(TestnetVersion::V4),
+    Network::Signet,
+    Network::Regtest,
+];
+
 /// Methods and functions that can be called only on `Address<NetworkChecked>`.
 impl Address {
     /// Constructs a new pay-to-public-key-hash (P2PKH) [`Address`] from a public key.
@@ -481,7 +684,26 @@ impl Address {
     #[inline]
     pub fn p2pkh(pk: impl Into<PubkeyHash>, network: impl Into<NetworkKind>) -> Address {
         let hash = pk.into();
-        Self(AddressInner::P2pkh { hash, network: network.into() }, PhantomData)
+        Self(AddressInner::P2pkh { hash, network: network.into().into() }, PhantomData)
+    }
+
+    /// Constructs a new pay-to-public-key-hash (P2PKH) [`Address`] with an arbitrary version byte.
+    ///
+    /// This is intended for chains which are not Bitcoin networks but use the same address format.
+    /// An address with a custom version byte is not valid for any [`Network`] so
+    /// [`Address::require_network`] always fails for it, use [`Address::parse_legacy_with_versions`]
+    /// to parse such an address. If `version` is one of the P2PKH version bytes used by the Bitcoin
+    /// networks the address is the same as the one returned by [`Address::p2pkh`].
+    ///
+    /// The version byte is not checked against the P2SH version bytes, care must be taken that the
+    /// resulting address is not mistaken for a P2SH address.
+    pub fn p2pkh_with_version(hash: PubkeyHash, version: u8) -> Address {
+        let network = LegacyNetwork::from_version(
+            version,
+            PUBKEY_ADDRESS_PREFIX_MAIN,
+            PUBKEY_ADDRESS_PREFIX_TEST,
+        );
+        Self(AddressInner::P2pkh { hash, network }, PhantomData)
     }
 
     /// Constructs a new pay-to-script-hash (P2SH) [`Address`] from a script.
@@ -504,12 +726,40 @@ pub fn p2sh(
     /// The `hash` pre-image (redeem script) must not exceed 520 bytes in length
     /// otherwise outputs created from the returned address will be un-spendable.
     pub fn p2sh_from_hash(hash: ScriptHash, network: impl Into<NetworkKind>) -> Address {
-        Self(AddressInner::P2sh { hash, network: network.into() }, PhantomData)
+        Self(AddressInner::P2sh { hash, network: network.into().into() }, PhantomData)
+    }
+
+    /// Constructs a new pay-to-script-hash (P2SH) [`Address`] with an arbitrary version byte.
+    ///
+    /// See [`Address::p2pkh_with_version`] for the caveats of custom version bytes.
+    pub fn p2sh_with_version(hash: ScriptHash, version: u8) -> Address {
+        let network = LegacyNetwork::from_version(
+            version,
+            SCRIPT_ADDRESS_PREFIX_MAIN,
+            SCRIPT_ADDRESS_PREFIX_TEST,
+        );
+        Self(AddressInner::P2sh { hash, network }, PhantomData)
     }
 
     /// Constructs a new pay-to-witness-public-key-hash (P2WPKH) [`Address`] from a public key.
     ///
     /// This is the native SegWit address type for an output redeemable with a single signature.
+    ///
+    /// SegWit requires compressed keys, a [`PublicKey`] can be converted using `try_from` which
+    /// fails if the key is uncompressed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitcoin::{Address, CompressedPublicKey, KnownHrp, PublicKey};
+    ///
+    /// let pk = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+    ///     .parse::<PublicKey>()
+    ///     .unwrap();
+    /// let pk = CompressedPublicKey::try_from(pk).expect("key is compressed");
+    /// let address = Address::p2wpkh(pk, KnownHrp::Mainnet);
+    /// assert_eq!(address.to_string(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+    /// ```
     pub fn p2wpkh(pk: CompressedPublicKey, hrp: impl Into<KnownHrp>) -> Self {
         let program = WitnessProgram::p2wpkh(pk);
         Address::from_witness_program(program, hrp)
@@ -520,8 +770,8 @@ pub fn p2wpkh(pk: CompressedPublicKey, hrp: impl Into<KnownHrp>) -> Self {
     ///
     /// This is a SegWit address type that looks familiar (as p2sh) to legacy clients.
     pub fn p2shwpkh(pk: CompressedPublicKey, network: impl Into<NetworkKind>) -> Address {
-        let builder = script::Builder::new().push_int_unchecked(0).push_slice(pk.wpubkey_hash());
-        let script_hash = builder.as_script().script_hash().expect("script is less than 520 bytes");
+        let redeem_script = ScriptBuf::p2wpkh_redeem_script(pk);
+        let script_hash = redeem_script.script_hash().expect("script is less than 520 bytes");
         Address::p2sh_from_hash(script_hash, network)
     }
 
@@ -548,13 +798,13 @@ pub fn p2shwsh(
         witness_script: &Script,
         network: impl Into<NetworkKind>,
     ) -> Result<Address, WitnessScriptSizeError> {
-        let hash = witness_script.wscript_hash()?;
-        let builder = script::Builder::new().push_int_unchecked(0).push_slice(hash);
-        let script_hash = builder.as_script().script_hash().expect("script is less than 520 bytes");
+        let redeem_script = ScriptBuf::p2wsh_redeem_script(witness_script)?;
+        let script_hash = redeem_script.script_hash().expect("script is less than 520 bytes");
         Ok(Address::p2sh_from_hash(script_hash, network))
     }
 
     /// Constructs a new pay-to-Taproot (P2TR) [`Address`] from an untweaked key.
+    #[doc(alias = "p2tr_untweaked")]
     pub fn p2tr<C: Verification>(
         secp: &Secp256k1<C>,
         internal_key: UntweakedPublicKey,
@@ -576,10 +826,91 @@ pub fn p2tr_tweaked(output_key: TweakedPublicKey, hrp: impl Into<KnownHrp>) -> A
     /// This only exists to support future witness versions. If you are doing normal mainnet things
     /// then you likely do not need this constructor.
     pub fn from_witness_program(program: WitnessProgram, hrp: impl Into<KnownHrp>) -> Address {
-        let inner = AddressInner::Segwit { program, hrp: hrp.into() };
+        let inner = AddressInner::Segwit { program, hrp: SegwitHrp::Known(hrp.into()) };
         Address(inner, PhantomData)
     }
 
+    /// Constructs a new SegWit [`Address`] with an arbitrary human-readable part.
+    ///
+    /// This is intended for networks which are not Bitcoin networks, for example sidechains or
+    /// custom signets. An address with a custom HRP is not valid for any [`Network`] so
+    /// [`Address::require_network`] always fails for it, use [`Address::parse_with_hrp`] to parse
+    /// such an address. If `hrp` is one of the HRPs used by the Bitcoin networks the address is the
+    /// same as the one returned by [`Address::from_witness_program`].
+    pub fn from_witness_program_with_hrp(program: WitnessProgram, hrp: Hrp) -> Address {
+        let inner = AddressInner::Segwit { program, hrp: SegwitHrp::from_hrp(hrp) };
+        Address(inner, PhantomData)
+    }
+
+    /// Parses a bech32 or bech32m encoded SegWit address which must have the human-readable part
+    /// `hrp`.
+    ///
+    /// Version 0 witness programs must use bech32 and later versions bech32m, mixed case strings
+    /// are rejected.
+    ///
+    /// # Errors
+    ///
+    /// If the string is not a valid SegWit address or its HRP is not `hrp`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitcoin::bech32::Hrp;
+    /// use bitcoin::Address;
+    ///
+    /// let hrp = Hrp::parse("ert").unwrap();
+    /// let s = "ert1qw508d6qejxtdg4y5r3zarvary0c5xw7kuu73e0";
+    /// let address = Address::parse_with_hrp(s, hrp).unwrap();
+    /// assert_eq!(address.to_string(), s);
+    /// ```
+    pub fn parse_with_hrp(s: &str, hrp: Hrp) -> Result<Address, Bech32Error> {
+        let (found, program) = decode_segwit(s)?;
+        if found != hrp {
+            return Err(UnexpectedHrpError {
+                expected: hrp.to_lowercase(),
+                found: found.to_lowercase(),
+            }
+            .into());
+        }
+        Ok(Address::from_witness_program_with_hrp(program, found))
+    }
+
+    /// Parses a legacy (base58) address which must use `pubkey_version` as its P2PKH version byte
+    /// or `script_version` as its P2SH version byte.
+    ///
+    /// This is the counterpart of [`Address::p2pkh_with_version`] and
+    /// [`Address::p2sh_with_version`]. If the two version bytes are the same the address is parsed
+    /// as P2PKH.
+    ///
+    /// # Errors
+    ///
+    /// If the string is not a valid base58 address or its version byte is neither of the versions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitcoin::Address;
+    ///
+    /// // The version bytes used by Litecoin.
+    /// let s = "LVuDpNCSSj6pQ7t9Pv6d6sUkLKoqDEVUnJ";
+    /// let address = Address::parse_legacy_with_versions(s, 0x30, 0x32).unwrap();
+    /// assert_eq!(address.to_string(), s);
+    /// ```
+    pub fn parse_legacy_with_versions(
+        s: &str,
+        pubkey_version: u8,
+        script_version: u8,
+    ) -> Result<Address, Base58Error> {
+        let (version, data) = decode_base58(s)?;
+        if version == pubkey_version {
+            Ok(Address::p2pkh_with_version(PubkeyHash::from_byte_array(data), version))
+        } else if version == script_version {
+            Ok(Address::p2sh_with_version(ScriptHash::from_byte_array(data), version))
+        } else {
+            Err(InvalidLegacyPrefixError { invalid: version }.into())
+        }
+    }
+
     /// Gets the address type of the [`Address`].
     ///
     /// # Returns
@@ -587,24 +918,21 @@ pub fn from_witness_program(program: WitnessProgram, hrp: impl Into<KnownHrp>) -
     /// None if unknown, non-standard or related to the future witness version.
     #[inline]
     pub fn address_type(&self) -> Option<AddressType> {
-        match self.0 {
-            AddressInner::P2pkh { .. } => Some(AddressType::P2pkh),
-            AddressInner::P2sh { .. } => Some(AddressType::P2sh),
-            AddressInner::Segwit { ref program, hrp: _ } =>
-                if program.is_p2wpkh() {
-                    Some(AddressType::P2wpkh)
-                } else if program.is_p2wsh() {
-                    Some(AddressType::P2wsh)
-                } else if program.is_p2tr() {
-                    Some(AddressType::P2tr)
-                } else if program.is_p2a() {
-                    Some(AddressType::P2a)
-                } else {
-                    None
-                },
+        match self.address_type_or_unknown() {
+            AddressTypeOrUnknown::Known(address_type) => Some(address_type),
+            AddressTypeOrUnknown::UnknownWitnessProgram(_) => None,
         }
     }
 
+    /// Gets the address type of the [`Address`], including witness programs of unknown type.
+    ///
+    /// Unlike [`Address::address_type`] this always returns a type, witness programs that are not
+    /// of a known type are returned as [`AddressTypeOrUnknown::UnknownWitnessProgram`].
+    #[inline]
+    pub fn address_type_or_unknown(&self) -> AddressTypeOrUnknown {
+        self.0.address_type_or_unknown()
+    }
+
     /// Gets the address data from this address.
     pub fn to_address_data(&self) -> AddressData {
         use AddressData::*;
@@ -662,6 +990,11 @@ pub fn witness_program(&self) -> Option<WitnessProgram> {
     pub fn is_spend_standard(&self) -> bool { self.address_type().is_some() }
 
     /// Constructs a new [`Address`] from an output script (`scriptPubkey`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FromScriptError::P2pk`] for pay-to-pubkey outputs since these do not have an
+    /// address and [`FromScriptError::UnrecognizedScript`] for any other non-address script.
     pub fn from_script(
         script: &Script,
         params: impl AsRef<Params>,
@@ -679,8 +1012,10 @@ pub fn from_script(
             let opcode = script.first_opcode().expect("is_witness_program guarantees len > 4");
 
             let version = WitnessVersion::try_from(opcode)?;
-            let program = WitnessProgram::new(version, &script.as_bytes()[2..])?;
+            let program = WitnessProgram::new_consensus(version, &script.as_bytes()[2..])?;
             Ok(Address::from_witness_program(program, network))
+        } else if script.is_p2pk() {
+            Err(FromScriptError::P2pk)
         } else {
             Err(FromScriptError::UnrecognizedScript)
         }
@@ -712,6 +1047,8 @@ pub fn script_pubkey(&self) -> ScriptBuf {
     /// parsed as case-insensitive many wallets got this wrong and don't parse correctly.
     /// [See compatibility table.](https://github.com/btcpayserver/btcpayserver/issues/2110)
     ///
+    /// To include an amount or other parameters in the URI use [`Bip21Uri`](crate::bip21::Bip21Uri).
+    ///
     /// If you want to avoid allocation you can use alternate display instead:
     /// ```
     /// # use core::fmt::Write;
@@ -731,56 +1068,93 @@ pub fn to_qr_uri(&self) -> String { format!("bitcoin:{:#}", self) }
 
     /// Returns true if the given pubkey is directly related to the address payload.
     ///
-    /// This is determined by directly comparing the address payload with either the
-    /// hash of the given public key or the SegWit redeem hash generated from the
-    /// given key. For Taproot addresses, the supplied key is assumed to be tweaked
+    /// The comparison depends on the address type:
+    ///
+    /// - For P2PKH addresses, the payload is compared with the hash of the given public key.
+    /// - For P2WPKH addresses, the given key must be compressed and its hash must equal the
+    ///   witness program.
+    /// - For P2SH addresses, the payload is compared with the hash of the P2WPKH script generated
+    ///   from the given (compressed) key, i.e. the address is assumed to be P2SH-P2WPKH.
+    /// - For Taproot addresses, see [`Address::is_related_to_xonly_pubkey`].
+    ///
+    /// Other address types are never related to a single public key.
+    ///
+    /// This creates a verification context to tweak the key of a Taproot address, use
+    /// [`Address::is_related_to_pubkey_with_secp`] to reuse an existing context.
     pub fn is_related_to_pubkey(&self, pubkey: PublicKey) -> bool {
-        let pubkey_hash = pubkey.pubkey_hash();
-        let payload = self.payload_as_bytes();
-        let xonly_pubkey = XOnlyPublicKey::from(pubkey.inner);
+        self.is_related_to_pubkey_with_secp(&Secp256k1::verification_only(), pubkey)
+    }
 
-        (*pubkey_hash.as_byte_array() == *payload)
-            || (xonly_pubkey.serialize() == *payload)
-            || (*segwit_redeem_hash(pubkey_hash).as_byte_array() == *payload)
+    /// Returns true if the given pubkey is directly related to the address payload.
+    ///
+    /// This is [`Address::is_related_to_pubkey`] using `secp` to tweak the key of a Taproot
+    /// address.
+    pub fn is_related_to_pubkey_with_secp<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        pubkey: PublicKey,
+    ) -> bool {
+        use AddressInner::*;
+
+        match self.0 {
+            P2pkh { ref hash, network: _ } => *hash == pubkey.pubkey_hash(),
+            P2sh { ref hash, network: _ } =>
+                pubkey.compressed
+                    && *hash.as_byte_array()
+                        == *segwit_redeem_hash(pubkey.pubkey_hash()).as_byte_array(),
+            Segwit { ref program, hrp: _ } if program.is_p2wpkh() => pubkey
+                .wpubkey_hash()
+                .map(|hash| program.program().as_bytes() == hash.as_byte_array())
+                .unwrap_or(false),
+            Segwit { ref program, hrp: _ } if program.is_p2tr() =>
+                self.is_related_to_xonly_pubkey_with_secp(secp, XOnlyPublicKey::from(pubkey.inner)),
+            Segwit { .. } => false,
+        }
     }
 
     /// Returns true if the supplied xonly public key can be used to derive the address.
     ///
-    /// This will only work for Taproot addresses. The Public Key is
-    /// assumed to have already been tweaked.
+    /// This will only work for Taproot addresses. The key matches if it is the output key, or if it
+    /// is an internal key that gives the output key when tweaked with no script tree (the BIP-86
+    /// key-path only case). Keys committing to a script tree can not be detected because the
+    /// Merkle root is not known.
+    ///
+    /// This creates a verification context to tweak the key, use
+    /// [`Address::is_related_to_xonly_pubkey_with_secp`] to reuse an existing context.
     pub fn is_related_to_xonly_pubkey(&self, xonly_pubkey: XOnlyPublicKey) -> bool {
-        xonly_pubkey.serialize() == *self.payload_as_bytes()
+        self.is_related_to_xonly_pubkey_with_secp(&Secp256k1::verification_only(), xonly_pubkey)
     }
 
-    /// Returns true if the address creates a particular script
-    /// This function doesn't make any allocations.
-    pub fn matches_script_pubkey(&self, script: &Script) -> bool {
-        use AddressInner::*;
+    /// Returns true if the supplied xonly public key can be used to derive the address.
+    ///
+    /// This is [`Address::is_related_to_xonly_pubkey`] using `secp` to tweak the key.
+    pub fn is_related_to_xonly_pubkey_with_secp<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        xonly_pubkey: XOnlyPublicKey,
+    ) -> bool {
         match self.0 {
-            P2pkh { ref hash, network: _ } if script.is_p2pkh() =>
-                &script.as_bytes()[3..23] == <PubkeyHash as AsRef<[u8; 20]>>::as_ref(hash),
-            P2sh { ref hash, network: _ } if script.is_p2sh() =>
-                &script.as_bytes()[2..22] == <ScriptHash as AsRef<[u8; 20]>>::as_ref(hash),
-            Segwit { ref program, hrp: _ } if script.is_witness_program() =>
-                &script.as_bytes()[2..] == program.program().as_bytes(),
-            P2pkh { .. } | P2sh { .. } | Segwit { .. } => false,
+            AddressInner::Segwit { ref program, hrp: _ } if program.is_p2tr() => {
+                let output_key = program.program().as_bytes();
+                let (tweaked, _parity) = xonly_pubkey.tap_tweak(secp, None);
+                xonly_pubkey.serialize() == *output_key
+                    || tweaked.to_inner().serialize() == *output_key
+            }
+            _ => false,
         }
     }
 
-    /// Returns the "payload" for this address.
-    ///
-    /// The "payload" is the useful stuff excluding serialization prefix, the exact payload is
-    /// dependent on the inner address:
-    ///
-    /// - For p2sh, the payload is the script hash.
-    /// - For p2pkh, the payload is the pubkey hash.
-    /// - For SegWit addresses, the payload is the witness program.
-    fn payload_as_bytes(&self) -> &[u8] {
-        use AddressInner::*;
-        match self.0 {
-            P2sh { ref hash, network: _ } => hash.as_ref(),
-            P2pkh { ref hash, network: _ } => hash.as_ref(),
-            Segwit { ref program, hrp: _ } => program.program().as_bytes(),
+    /// Returns true if the address creates a particular script
+    /// This function doesn't make any allocations.
+    pub fn matches_script_pubkey(&self, script: &Script) -> bool {
+        match self.to_address_data() {
+            AddressData::P2pkh { pubkey_hash } =>
+                script.is_p2pkh() && script.as_bytes()[3..23] == pubkey_hash.to_byte_array(),
+            AddressData::P2sh { script_hash } =>
+                script.is_p2sh() && script.as_bytes()[2..22] == script_hash.to_byte_array(),
+            AddressData::Segwit { witness_program } =>
+                script.witness_version() == Some(witness_program.version())
+                    && script.as_bytes()[2..] == *witness_program.program().as_bytes(),
         }
     }
 }
@@ -818,12 +1192,34 @@ pub fn assume_checked_ref(&self) -> &Address {
     pub fn is_valid_for_network(&self, n: Network) -> bool {
         use AddressInner::*;
         match self.0 {
-            P2pkh { hash: _, ref network } => *network == NetworkKind::from(n),
-            P2sh { hash: _, ref network } => *network == NetworkKind::from(n),
-            Segwit { program: _, ref hrp } => *hrp == KnownHrp::from_network(n),
+            P2pkh { hash: _, ref network } => *network == LegacyNetwork::Known(n.into()),
+            P2sh { hash: _, ref network } => *network == LegacyNetwork::Known(n.into()),
+            Segwit { program: _, ref hrp } => *hrp == SegwitHrp::Known(KnownHrp::from_network(n)),
         }
     }
 
+    /// Returns the networks this address is valid for.
+    ///
+    /// Legacy addresses with a test network prefix are valid for all test networks and bech32
+    /// addresses with the `tb` HRP for all of them except regtest, see
+    /// [`is_valid_for_network`](Address<NetworkUnchecked>::is_valid_for_network).
+    ///
+    /// ```rust
+    /// use bitcoin::{Address, Network, TestnetVersion};
+    /// use bitcoin::address::NetworkUnchecked;
+    ///
+    /// let address: Address<NetworkUnchecked> =
+    ///     "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".parse().unwrap();
+    /// let networks = address.valid_networks().collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     networks,
+    ///     [Network::Testnet(TestnetVersion::V3), Network::Testnet(TestnetVersion::V4), Network::Signet]
+    /// );
+    /// ```
+    pub fn valid_networks(&self) -> impl Iterator<Item = Network> + '_ {
+        NETWORKS.iter().copied().filter(move |network| self.is_valid_for_network(*network))
+    }
+
     /// Checks whether network of this address is as required.
     ///
     /// For details about this mechanism, see section [*Parsing addresses*](Address#parsing-addresses)
@@ -886,43 +1282,32 @@ pub fn assume_checked(self) -> Address { Address(self.0, PhantomData) }
 
     /// Parse a bech32 Address string
     pub fn from_bech32_str(s: &str) -> Result<Address<NetworkUnchecked>, Bech32Error> {
-        let (hrp, witness_version, data) =
-            bech32::segwit::decode(s).map_err(|e| Bech32Error::ParseBech32(ParseBech32Error(e)))?;
-        let version = WitnessVersion::try_from(witness_version.to_u8())?;
-        let program = WitnessProgram::new(version, &data)
-            .expect("bech32 guarantees valid program length for witness");
-
-        let hrp = KnownHrp::from_hrp(hrp)?;
+        let (hrp, program) = decode_segwit(s)?;
+        let hrp = SegwitHrp::Known(KnownHrp::from_hrp(hrp)?);
         let inner = AddressInner::Segwit { program, hrp };
         Ok(Address(inner, PhantomData))
     }
 
     /// Parse a base58 Address string
     pub fn from_base58_str(s: &str) -> Result<Address<NetworkUnchecked>, Base58Error> {
-        if s.len() > 50 {
-            return Err(LegacyAddressTooLongError { length: s.len() }.into());
-        }
-        let data = base58::decode_check(s)?;
-        let data: &[u8; 21] = (&*data).try_into().map_err(|_| InvalidBase58PayloadLengthError { length: s.len() })?;
-
-        let (prefix, &data) = data.split_first();
+        let (prefix, data) = decode_base58(s)?;
 
-        let inner = match *prefix {
+        let inner = match prefix {
             PUBKEY_ADDRESS_PREFIX_MAIN => {
                 let hash = PubkeyHash::from_byte_array(data);
-                AddressInner::P2pkh { hash, network: NetworkKind::Main }
+                AddressInner::P2pkh { hash, network: NetworkKind::Main.into() }
             }
             PUBKEY_ADDRESS_PREFIX_TEST => {
                 let hash = PubkeyHash::from_byte_array(data);
-                AddressInner::P2pkh { hash, network: NetworkKind::Test }
+                AddressInner::P2pkh { hash, network: NetworkKind::Test.into() }
             }
             SCRIPT_ADDRESS_PREFIX_MAIN => {
                 let hash = ScriptHash::from_byte_array(data);
-                AddressInner::P2sh { hash, network: NetworkKind::Main }
+                AddressInner::P2sh { hash, network: NetworkKind::Main.into() }
             }
             SCRIPT_ADDRESS_PREFIX_TEST => {
                 let hash = ScriptHash::from_byte_array(data);
-                AddressInner::P2sh { hash, network: NetworkKind::Test }
+                AddressInner::P2sh { hash, network: NetworkKind::Test.into() }
             }
             invalid => return Err(InvalidLegacyPrefixError { invalid }.into()),
         };
@@ -989,6 +1374,93 @@ fn from_str(s: &str) -> Result<Self, ParseError> {
     }
 }
 
+/// Decodes a legacy address into its version byte and hash.
+fn decode_base58(s: &str) -> Result<(u8, [u8; 20]), Base58Error> {
+    if s.len() > 50 {
+        return Err(LegacyAddressTooLongError { length: s.len() }.into());
+    }
+    let data = base58::decode_check(s)?;
+    let data: &[u8; 21] =
+        (&*data).try_into().map_err(|_| InvalidBase58PayloadLengthError { length: data.len() })?;
+
+    let (prefix, &data) = data.split_first();
+    Ok((*prefix, data))
+}
+
+/// Decodes a SegWit address, classifying the common errors so they can be reported precisely.
+fn decode_segwit(s: &str) -> Result<(Hrp, WitnessProgram), Bech32Error> {
+    use bech32::primitives::decode::{
+        CharError, ChecksumError, SegwitHrpstring, SegwitHrpstringError, UncheckedHrpstring,
+        UncheckedHrpstringError,
+    };
+    use bech32::primitives::hrp;
+    use bech32::{Bech32, Bech32m, Checksum as _};
+
+    let segwit = match SegwitHrpstring::new(s) {
+        Ok(segwit) => segwit,
+        Err(e) => {
+            return Err(match e {
+                SegwitHrpstringError::Unchecked(UncheckedHrpstringError::Char(
+                    CharError::MixedCase,
+                ))
+                | SegwitHrpstringError::Unchecked(UncheckedHrpstringError::Hrp(
+                    hrp::Error::MixedCase,
+                )) => Bech32Error::MixedCase,
+                SegwitHrpstringError::Unchecked(UncheckedHrpstringError::Char(
+                    CharError::InvalidChar(c),
+                )) => Bech32Error::InvalidCharacter(c),
+                SegwitHrpstringError::InvalidWitnessVersion(fe) =>
+                    WitnessVersion::try_from(fe.to_u8()).unwrap_err().into(),
+                SegwitHrpstringError::Checksum(ChecksumError::InvalidResidue) => {
+                    // Only reachable if `s` parsed as an unchecked string with a witness version.
+                    let unchecked = UncheckedHrpstring::new(s).expect("checked above");
+                    let version = unchecked
+                        .witness_version()
+                        .and_then(|fe| WitnessVersion::try_from(fe.to_u8()).ok())
+                        .expect("checked above");
+                    match version {
+                        // The witness version is part of the checksum, there is no data.
+                        _ if unchecked.data_part_ascii().len() <= Bech32::CHECKSUM_LENGTH =>
+                            Bech32Error::ParseBech32(ParseBech32Error(
+                                SegwitHrpstringError::NoData.into(),
+                            )),
+                        WitnessVersion::V0 if unchecked.has_valid_checksum::<Bech32m>() =>
+                            Bech32Error::WrongChecksumVariant {
+                                version,
+                                expected: Bech32Variant::Bech32,
+                            },
+                        WitnessVersion::V0 => Bech32Error::InvalidChecksum,
+                        _ if unchecked.has_valid_checksum::<Bech32>() =>
+                            Bech32Error::WrongChecksumVariant {
+                                version,
+                                expected: Bech32Variant::Bech32m,
+                            },
+                        _ => Bech32Error::InvalidChecksum,
+                    }
+                }
+                SegwitHrpstringError::WitnessLength(_) => {
+                    let unchecked = UncheckedHrpstring::new(s).expect("checked above");
+                    let data = unchecked.data_part_ascii();
+                    // Remove the witness version and the checksum, each character encodes 5 bits.
+                    let length = (data.len() - 1 - Bech32::CHECKSUM_LENGTH) * 5 / 8;
+                    if data[0].eq_ignore_ascii_case(&b'q') {
+                        witness_program::Error::InvalidSegwitV0Length(length).into()
+                    } else {
+                        witness_program::Error::InvalidLength(length).into()
+                    }
+                }
+                e => Bech32Error::ParseBech32(ParseBech32Error(e.into())),
+            });
+        }
+    };
+
+    let version = WitnessVersion::try_from(segwit.witness_version().to_u8())?;
+    let data = segwit.byte_iter().collect::<Vec<u8>>();
+    let program = WitnessProgram::new_consensus(version, &data)
+        .expect("bech32 guarantees valid program length for witness");
+    Ok((segwit.hrp(), program))
+}
+
 /// Convert a byte array of a pubkey hash into a SegWit redeem hash
 fn segwit_redeem_hash(pubkey_hash: PubkeyHash) -> hash160::Hash {
     let mut sha_engine = hash160::Hash::engine();
@@ -999,11 +1471,12 @@ fn segwit_redeem_hash(pubkey_hash: PubkeyHash) -> hash160::Hash {
 
 #[cfg(test)]
 mod tests {
+    use bech32::primitives::decode::CheckedHrpstring;
     use hex_lit::hex;
 
     use super::*;
+    use crate::network::params;
     use crate::network::Network::{Bitcoin, Testnet};
-    use crate::network::{params, TestnetVersion};
     use crate::script::ScriptBufExt as _;
 
     fn roundtrips(addr: &Address, network: Network) {
@@ -1190,7 +1663,10 @@ fn address_type() {
             // SegWit v1 and len != 32
             ("bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y", None),
             // SegWit v2
-            ("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", None),
+            (
+                "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+                Some(AddressType::WitnessProgram(WitnessVersion::V2)),
+            ),
         ];
         for (address, expected_type) in &addresses {
             let addr = address
@@ -1199,9 +1675,97 @@ fn address_type() {
                 .require_network(Network::Bitcoin)
                 .expect("mainnet");
             assert_eq!(&addr.address_type(), expected_type);
+            match addr.address_type_or_unknown() {
+                AddressTypeOrUnknown::Known(t) => assert_eq!(Some(t), *expected_type),
+                AddressTypeOrUnknown::UnknownWitnessProgram(version) => {
+                    assert_eq!(*expected_type, None);
+                    assert_eq!(version, WitnessVersion::V1);
+                }
+            }
         }
     }
 
+    #[test]
+    fn address_ordering() {
+        let sorted = [
+            "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn",
+            "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
+            "bcrt1qvzvkjn4q3nszqxrv3nraga2r822xjty3vewgs5",
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "bc1pfeessrawgf",
+            "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+            "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+        ];
+        let mut addresses = sorted
+            .iter()
+            .rev()
+            .map(|s| s.parse::<Address<_>>().unwrap().assume_checked())
+            .collect::<Vec<_>>();
+        addresses.sort();
+
+        let addresses = addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(addresses, sorted);
+    }
+
+    #[test]
+    fn payload_accessors() {
+        let addresses = [
+            "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhY",
+            "33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k",
+            "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
+            "bc1qwqdg6squsna38e46795at95yu9atm8azzmyvckulcc7kytlcckxswvvzej",
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "bc1pfeessrawgf",
+            "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+        ];
+        for address in &addresses {
+            let addr = address
+                .parse::<Address<_>>()
+                .unwrap()
+                .require_network(Network::Bitcoin)
+                .expect("mainnet");
+            let script_pubkey = addr.script_pubkey();
+
+            match addr.to_address_data() {
+                AddressData::P2pkh { pubkey_hash } => {
+                    assert_eq!(addr.pubkey_hash(), Some(pubkey_hash));
+                    assert_eq!(addr.script_hash(), None);
+                    assert_eq!(addr.witness_program(), None);
+                    assert_eq!(script_pubkey, ScriptBuf::new_p2pkh(pubkey_hash));
+                }
+                AddressData::P2sh { script_hash } => {
+                    assert_eq!(addr.pubkey_hash(), None);
+                    assert_eq!(addr.script_hash(), Some(script_hash));
+                    assert_eq!(addr.witness_program(), None);
+                    assert_eq!(script_pubkey, ScriptBuf::new_p2sh(script_hash));
+                }
+                AddressData::Segwit { witness_program } => {
+                    assert_eq!(addr.pubkey_hash(), None);
+                    assert_eq!(addr.script_hash(), None);
+                    assert_eq!(addr.witness_program(), Some(witness_program));
+                    assert_eq!(
+                        &script_pubkey.as_bytes()[2..],
+                        witness_program.program().as_bytes()
+                    );
+                }
+            }
+        }
+
+        // The accessors do not depend on the network.
+        let testnet = "2N3zXjbwdTcPsJiy8sUK9FhWJhqQCxA8Jjr"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Testnet(TestnetVersion::V3))
+            .expect("testnet");
+        let mainnet = Address::p2sh_from_hash(testnet.script_hash().unwrap(), Network::Bitcoin);
+        assert_eq!(mainnet.script_hash(), testnet.script_hash());
+        assert_ne!(mainnet, testnet);
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn json_serialize() {
@@ -1435,6 +1999,42 @@ fn is_related_to_pubkey_p2tr() {
         assert!(!address.is_related_to_pubkey(unused_pubkey));
     }
 
+    #[test]
+    fn is_related_to_pubkey_wrong_address_type() {
+        let pubkey = "0347ff3dacd07a1f43805ec6808e801505a6e18245178609972a68afbc2777ff2b"
+            .parse::<PublicKey>()
+            .expect("pubkey");
+        let uncompressed = PublicKey { compressed: false, inner: pubkey.inner };
+        let xonly_pubkey = XOnlyPublicKey::from(pubkey.inner);
+        let pubkey_hash = pubkey.pubkey_hash().to_byte_array();
+        let redeem_hash = segwit_redeem_hash(pubkey.pubkey_hash()).to_byte_array();
+        let uncompressed_redeem_hash =
+            segwit_redeem_hash(uncompressed.pubkey_hash()).to_byte_array();
+
+        // The payloads have the right length and content but the address types don't commit to
+        // the key in that way.
+        let unrelated = [
+            Address::p2sh_from_hash(ScriptHash::from_byte_array(pubkey_hash), Bitcoin),
+            Address::p2pkh(PubkeyHash::from_byte_array(redeem_hash), Bitcoin),
+            Address::p2wsh_from_hash(
+                WScriptHash::from_byte_array(xonly_pubkey.serialize()),
+                KnownHrp::Mainnet,
+            ),
+        ];
+        for address in &unrelated {
+            assert!(!address.is_related_to_pubkey(pubkey), "{}", address);
+        }
+
+        // SegWit addresses can't be related to uncompressed keys.
+        let program = WitnessProgram::p2wpkh(CompressedPublicKey(pubkey.inner));
+        let p2wpkh = Address::from_witness_program(program, KnownHrp::Mainnet);
+        assert!(p2wpkh.is_related_to_pubkey(pubkey));
+        assert!(!p2wpkh.is_related_to_pubkey(uncompressed));
+        let p2shwpkh =
+            Address::p2sh_from_hash(ScriptHash::from_byte_array(uncompressed_redeem_hash), Bitcoin);
+        assert!(!p2shwpkh.is_related_to_pubkey(uncompressed));
+    }
+
     #[test]
     fn is_related_to_xonly_pubkey() {
         let pubkey_string = "0347ff3dacd07a1f43805ec6808e801505a6e18245178609972a68afbc2777ff2b";
@@ -1456,6 +2056,44 @@ fn is_related_to_xonly_pubkey() {
         assert!(result);
     }
 
+    #[test]
+    fn is_related_to_untweaked_pubkey_p2tr() {
+        let secp = Secp256k1::verification_only();
+        // BIP-86 test vector, the address commits to the tweaked internal key.
+        let pubkey = "03cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"
+            .parse::<PublicKey>()
+            .expect("pubkey");
+        let internal_key = XOnlyPublicKey::from(pubkey.inner);
+        let address = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+            .parse::<Address<_>>()
+            .expect("address")
+            .require_network(Network::Bitcoin)
+            .expect("mainnet");
+        assert_eq!(address, Address::p2tr(&secp, internal_key, None, KnownHrp::Mainnet));
+
+        // Comparing the untweaked key with the output key, as was done previously, does not match.
+        assert_ne!(
+            internal_key.serialize(),
+            *address.witness_program().expect("segwit").program().as_bytes()
+        );
+        assert!(address.is_related_to_xonly_pubkey(internal_key));
+        assert!(address.is_related_to_pubkey(pubkey));
+        assert!(address.is_related_to_xonly_pubkey_with_secp(&secp, internal_key));
+        assert!(address.is_related_to_pubkey_with_secp(&secp, pubkey));
+
+        // Keys committing to a script tree are not detected.
+        let merkle_root = TapNodeHash::from_byte_array([0xab; 32]);
+        let address = Address::p2tr(&secp, internal_key, Some(merkle_root), KnownHrp::Mainnet);
+        assert!(!address.is_related_to_xonly_pubkey(internal_key));
+
+        // Only Taproot addresses are related to x-only keys.
+        let p2wsh = Address::p2wsh_from_hash(
+            WScriptHash::from_byte_array(internal_key.serialize()),
+            KnownHrp::Mainnet,
+        );
+        assert!(!p2wsh.is_related_to_xonly_pubkey(internal_key));
+    }
+
     #[test]
     fn fail_address_from_script() {
         use crate::witness_program;
@@ -1475,6 +2113,20 @@ fn fail_address_from_script() {
             Address::from_script(&invalid_segwitv0_script, &params::MAINNET),
             Err(FromScriptError::WitnessProgram(witness_program::Error::InvalidSegwitV0Length(17)))
         );
+
+        let p2pk = ScriptBuf::from_hex(
+            "210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac",
+        )
+        .unwrap();
+        assert_eq!(Address::from_script(&p2pk, Network::Bitcoin), Err(FromScriptError::P2pk));
+        let p2pk_uncompressed = ScriptBuf::from_hex(
+            "410479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8ac",
+        )
+        .unwrap();
+        assert_eq!(
+            Address::from_script(&p2pk_uncompressed, Network::Bitcoin),
+            Err(FromScriptError::P2pk)
+        );
     }
 
     #[test]
@@ -1483,6 +2135,42 @@ fn valid_address_parses_correctly() {
         assert_eq!(addr, AddressType::P2tr);
     }
 
+    #[test]
+    fn witness_program_address_type_round_trip() {
+        for version in 2..=16 {
+            let version = WitnessVersion::try_from(version).unwrap();
+            let address_type = AddressType::WitnessProgram(version);
+            let s = address_type.to_string();
+            assert_eq!(s, format!("witness_v{}", version.to_num()));
+            assert_eq!(s.parse::<AddressType>(), Ok(address_type));
+        }
+        for s in ["witness_v0", "witness_v1", "witness_v02", "witness_v17", "witness_v"] {
+            assert_eq!(s.parse::<AddressType>(), Err(UnknownAddressTypeError(s.to_string())));
+        }
+    }
+
+    #[test]
+    fn future_witness_version_round_trip() {
+        let programs =
+            [(WitnessVersion::V2, 2), (WitnessVersion::V2, 40), (WitnessVersion::V16, 32)];
+        for (version, len) in programs {
+            let program = WitnessProgram::new(version, &[0xab; 40][..len]).unwrap();
+            let script = ScriptBuf::new_witness_program(&program);
+            assert_eq!(script.witness_program(), Some(program));
+
+            let addr = Address::from_script(&script, Network::Bitcoin).unwrap();
+            assert_eq!(addr.address_type(), Some(AddressType::WitnessProgram(version)));
+            assert_eq!(addr.witness_program(), Some(program));
+            assert_eq!(addr.script_pubkey(), script);
+
+            // Versions above zero are encoded using bech32m.
+            let s = addr.to_string();
+            assert!(CheckedHrpstring::new::<bech32::Bech32m>(&s).is_ok());
+            assert!(CheckedHrpstring::new::<bech32::Bech32>(&s).is_err());
+            roundtrips(&addr, Network::Bitcoin);
+        }
+    }
+
     #[test]
     fn invalid_address_parses_error() {
         let got = "invalid".parse::<AddressType>();
@@ -1490,6 +2178,100 @@ fn invalid_address_parses_error() {
         assert_eq!(got, want);
     }
 
+    // Invalid addresses from BIP-173 and BIP-350.
+    #[test]
+    fn invalid_address_errors() {
+        use witness_program::Error::{InvalidLength, InvalidSegwitV0Length};
+        use Bech32Variant::{Bech32, Bech32m};
+
+        fn bech32_err(s: &str) -> Bech32Error {
+            match s.parse::<Address<NetworkUnchecked>>().unwrap_err() {
+                ParseError::Bech32(e) => e,
+                e => panic!("unexpected error for {}: {:?}", s, e),
+            }
+        }
+
+        let wrong_variant =
+            |version, expected| Bech32Error::WrongChecksumVariant { version, expected };
+
+        let cases = [
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+                wrong_variant(WitnessVersion::V1, Bech32m),
+            ),
+            (
+                "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+                wrong_variant(WitnessVersion::V2, Bech32m),
+            ),
+            (
+                "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+                wrong_variant(WitnessVersion::V16, Bech32m),
+            ),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+                wrong_variant(WitnessVersion::V0, Bech32),
+            ),
+            (
+                "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47",
+                wrong_variant(WitnessVersion::V0, Bech32),
+            ),
+            (
+                "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+                Bech32Error::InvalidCharacter('o'),
+            ),
+            ("bc1pw5dgrnzv", InvalidLength(1).into()),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+                InvalidLength(41).into(),
+            ),
+            ("BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P", InvalidSegwitV0Length(16).into()),
+            (
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+                Bech32Error::MixedCase,
+            ),
+            // BIP-173 valid address with the last character changed.
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5", Bech32Error::InvalidChecksum),
+        ];
+        for (s, want) in cases {
+            assert_eq!(bech32_err(s), want, "{}", s);
+        }
+
+        assert!(matches!(
+            bech32_err("BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R"),
+            Bech32Error::WitnessVersion(_)
+        ));
+
+        // Invalid padding and empty data are reported by `bech32`.
+        for s in [
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+            "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
+            "bc1gmk9yu",
+        ] {
+            assert!(matches!(bech32_err(s), Bech32Error::ParseBech32(_)), "{}", s);
+        }
+
+        let err = "tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut"
+            .parse::<Address<NetworkUnchecked>>()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::Bech32(Bech32Error::UnknownHrp(UnknownHrpError("tc".to_owned())))
+        );
+
+        // Valid P2PKH address with the last character changed.
+        let err =
+            "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyhZ".parse::<Address<NetworkUnchecked>>().unwrap_err();
+        match err {
+            ParseError::Base58(e @ Base58Error::InvalidChecksum { .. }) =>
+                assert!(e.to_string().contains("check the address for typos")),
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let err =
+            "1QJVDzdqb1VpbDK7uDeyVXy9mR27CJiyh0".parse::<Address<NetworkUnchecked>>().unwrap_err();
+        assert_eq!(err, ParseError::Base58(Base58Error::InvalidCharacter(b'0')));
+    }
+
     #[test]
     fn matches_script_pubkey() {
         let addresses = [
@@ -1501,6 +2283,7 @@ fn matches_script_pubkey() {
             "bc1qvzvkjn4q3nszqxrv3nraga2r822xjty3ykvkuw",
             "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
             "bc1pgllnmtxs0g058qz7c6qgaqq4qknwrqj9z7rqn9e2dzhmcfmhlu4sfadf5e",
+            "bc1pfeessrawgf",
         ];
         for addr in &addresses {
             let addr =
@@ -1514,12 +2297,21 @@ fn matches_script_pubkey() {
                 assert_eq!(addr.matches_script_pubkey(&another.script_pubkey()), addr == another);
             }
         }
+
+        // The same program with a different witness version is a different script.
+        let addr = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+            .parse::<Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let mut bytes = addr.script_pubkey().into_bytes();
+        bytes[0] = 0x00; // OP_0
+        assert!(!addr.matches_script_pubkey(&ScriptBuf::from_bytes(bytes)));
     }
 
     #[test]
     #[cfg(feature = "serde")]
     fn serde_address_usage_in_struct() {
-        use serde::{self, Deserialize, Serialize};
+        use ::serde::{Deserialize, Serialize};
 
         #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
         struct Foo<V>
@@ -1566,4 +2358,229 @@ fn pay_to_anchor_address_regtest() {
         assert!(address.is_spend_standard());
         assert_eq!(address.address_type(), Some(AddressType::P2a));
     }
+
+    #[test]
+    fn valid_networks() {
+        use Network::{Regtest, Signet};
+
+        const V3: Network = Testnet(TestnetVersion::V3);
+        const V4: Network = Testnet(TestnetVersion::V4);
+
+        let pk = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+            .parse::<CompressedPublicKey>()
+            .unwrap();
+        let regtest = Address::p2wpkh(pk, KnownHrp::Regtest).to_string();
+        assert!(regtest.starts_with("bcrt1"));
+
+        let cases: &[(&str, &[Network])] = &[
+            ("1GhQvF6dL8xa6wBxLnWmHcQsurx9RxiMc8", &[Bitcoin]),
+            ("32iVBEu4dxkUQk9dJbZUiBiQdmypcEyJRf", &[Bitcoin]),
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", &[Bitcoin]),
+            ("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", &[Bitcoin]),
+            ("mqwpxxvfv3QbM8PU8uBx2jaNt9btQqvQNx", &[V3, V4, Signet, Regtest]),
+            ("2N83imGV3gPwBzKJQvWJ7cRUY2SpUyU6A5e", &[V3, V4, Signet, Regtest]),
+            ("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", &[V3, V4, Signet]),
+            (&regtest, &[Regtest]),
+        ];
+        for (s, networks) in cases {
+            let address = s.parse::<Address<_>>().unwrap();
+            assert_eq!(address.valid_networks().collect::<Vec<_>>(), *networks, "{}", s);
+            for network in NETWORKS {
+                let valid = networks.contains(&network);
+                assert_eq!(address.is_valid_for_network(network), valid, "{} {}", s, network);
+                assert_eq!(address.clone().require_network(network).is_ok(), valid);
+            }
+        }
+    }
+
+    #[test]
+    fn network_validation_error() {
+        let address = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".parse::<Address<_>>().unwrap();
+        let err = match address.clone().require_network(Network::Regtest) {
+            Err(ParseError::NetworkValidation(e)) => e,
+            res => panic!("expected a network validation error, got {:?}", res),
+        };
+        assert_eq!(err.required(), Network::Regtest);
+        assert_eq!(err.address(), &address);
+        assert_eq!(err.valid_networks().count(), 3);
+        assert_eq!(
+            err.to_string(),
+            "address tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx is valid for \
+             testnet/testnet4/signet but regtest was required"
+        );
+
+        let address = "mqwpxxvfv3QbM8PU8uBx2jaNt9btQqvQNx".parse::<Address<_>>().unwrap();
+        let err = match address.require_network(Network::Bitcoin) {
+            Err(ParseError::NetworkValidation(e)) => e,
+            res => panic!("expected a network validation error, got {:?}", res),
+        };
+        assert_eq!(
+            err.to_string(),
+            "address mqwpxxvfv3QbM8PU8uBx2jaNt9btQqvQNx is valid for \
+             testnet/testnet4/signet/regtest but bitcoin was required"
+        );
+    }
+
+    #[test]
+    fn custom_hrp() {
+        use bech32::primitives::iter::{ByteIterExt, Fe32IterExt};
+        use bech32::Bech32m;
+
+        let program = WitnessProgram::p2wpkh(
+            CompressedPublicKey::from_slice(&hex!(
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+            ))
+            .unwrap(),
+        );
+        let p2tr = WitnessProgram::new(WitnessVersion::V1, &[0xab; 32]).unwrap();
+
+        for (hrp, p2wpkh, p2tr_str) in [
+            (
+                "ert",
+                "ert1qw508d6qejxtdg4y5r3zarvary0c5xw7kuu73e0",
+                "ert1p4w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w4sstj79r",
+            ),
+            (
+                "sidechainx",
+                "sidechainx1qw508d6qejxtdg4y5r3zarvary0c5xw7k885x5s",
+                "sidechainx1p4w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w4sw7l3fy",
+            ),
+        ] {
+            let hrp = Hrp::parse(hrp).unwrap();
+            let addr = Address::from_witness_program_with_hrp(program, hrp);
+            assert_eq!(addr.to_string(), p2wpkh);
+            assert_eq!(addr.witness_program(), Some(program));
+            assert_eq!(Address::parse_with_hrp(p2wpkh, hrp).unwrap(), addr);
+            assert_eq!(Address::parse_with_hrp(&p2wpkh.to_uppercase(), hrp).unwrap(), addr);
+            assert_eq!(format!("{:#}", addr), p2wpkh.to_uppercase());
+
+            let addr = Address::from_witness_program_with_hrp(p2tr, hrp);
+            assert_eq!(addr.to_string(), p2tr_str);
+            assert_eq!(Address::parse_with_hrp(p2tr_str, hrp).unwrap(), addr);
+
+            // Custom HRPs are not valid for any of the Bitcoin networks.
+            let unchecked = addr.into_unchecked();
+            assert_eq!(unchecked.valid_networks().count(), 0);
+            assert!(unchecked.clone().require_network(Network::Bitcoin).is_err());
+            assert!(unchecked.require_network(Network::Regtest).is_err());
+            assert!(p2tr_str.parse::<Address<NetworkUnchecked>>().is_err());
+
+            // Mixed case.
+            let mixed = p2wpkh.replacen('w', "W", 1);
+            assert_eq!(Address::parse_with_hrp(&mixed, hrp), Err(Bech32Error::MixedCase));
+
+            // Version 0 must use bech32.
+            let bech32m = program
+                .program()
+                .as_bytes()
+                .iter()
+                .copied()
+                .bytes_to_fes()
+                .with_checksum::<Bech32m>(&hrp)
+                .with_witness_version(Fe32::Q)
+                .chars()
+                .collect::<String>();
+            assert_eq!(
+                Address::parse_with_hrp(&bech32m, hrp),
+                Err(Bech32Error::WrongChecksumVariant {
+                    version: WitnessVersion::V0,
+                    expected: Bech32Variant::Bech32,
+                })
+            );
+        }
+
+        // The HRP must match.
+        let err = Address::parse_with_hrp(
+            "ert1qw508d6qejxtdg4y5r3zarvary0c5xw7kuu73e0",
+            Hrp::parse("tbs").unwrap(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            Bech32Error::UnexpectedHrp(UnexpectedHrpError {
+                expected: "tbs".to_owned(),
+                found: "ert".to_owned()
+            })
+        );
+
+        // Known HRPs give the same address as `from_witness_program`.
+        let addr = Address::from_witness_program_with_hrp(program, bech32::hrp::BC);
+        assert_eq!(addr, Address::from_witness_program(program, KnownHrp::Mainnet));
+        assert_eq!(addr.to_string(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert_eq!(
+            Address::parse_with_hrp("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", bech32::hrp::BC)
+                .unwrap(),
+            addr
+        );
+    }
+
+    #[test]
+    fn custom_legacy_versions() {
+        // Litecoin style version bytes.
+        const PUBKEY_VERSION: u8 = 0x30;
+        const SCRIPT_VERSION: u8 = 0x32;
+
+        let pubkey_hash =
+            PubkeyHash::from_byte_array(hex!("751e76e8199196d454941c45d1b3a323f1433bd6"));
+        let script_hash =
+            ScriptHash::from_byte_array(hex!("751e76e8199196d454941c45d1b3a323f1433bd6"));
+
+        let p2pkh = Address::p2pkh_with_version(pubkey_hash, PUBKEY_VERSION);
+        assert_eq!(p2pkh.to_string(), "LVuDpNCSSj6pQ7t9Pv6d6sUkLKoqDEVUnJ");
+        assert_eq!(p2pkh.address_type(), Some(AddressType::P2pkh));
+        assert_eq!(p2pkh.pubkey_hash(), Some(pubkey_hash));
+        assert_eq!(p2pkh.script_pubkey(), Address::p2pkh(pubkey_hash, Bitcoin).script_pubkey());
+        assert_eq!(
+            Address::parse_legacy_with_versions(&p2pkh.to_string(), PUBKEY_VERSION, SCRIPT_VERSION),
+            Ok(p2pkh.clone())
+        );
+
+        let p2sh = Address::p2sh_with_version(script_hash, SCRIPT_VERSION);
+        assert_eq!(p2sh.to_string(), "MJaRnao1s62a2zAKSkmG582KbLKianqb7v");
+        assert_eq!(p2sh.address_type(), Some(AddressType::P2sh));
+        assert_eq!(p2sh.script_hash(), Some(script_hash));
+        assert_eq!(
+            p2sh.script_pubkey(),
+            Address::p2sh_from_hash(script_hash, Bitcoin).script_pubkey()
+        );
+        assert_eq!(
+            Address::parse_legacy_with_versions(&p2sh.to_string(), PUBKEY_VERSION, SCRIPT_VERSION),
+            Ok(p2sh.clone())
+        );
+
+        // Custom version bytes are not valid for any of the Bitcoin networks.
+        for address in [p2pkh, p2sh] {
+            assert_eq!(address.network_kind(), NetworkKind::Test);
+            let unchecked = address.into_unchecked();
+            assert_eq!(unchecked.valid_networks().count(), 0);
+            assert!(unchecked.clone().require_network(Network::Bitcoin).is_err());
+            assert!(unchecked.require_network(Network::Regtest).is_err());
+        }
+        assert!("LVuDpNCSSj6pQ7t9Pv6d6sUkLKoqDEVUnJ".parse::<Address<NetworkUnchecked>>().is_err());
+
+        // The Bitcoin version bytes give the usual addresses.
+        let address = Address::p2pkh_with_version(pubkey_hash, PUBKEY_ADDRESS_PREFIX_MAIN);
+        assert_eq!(address, Address::p2pkh(pubkey_hash, Bitcoin));
+        assert_eq!(address.to_string(), "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+        let address = Address::p2sh_with_version(script_hash, SCRIPT_ADDRESS_PREFIX_TEST);
+        assert!(address.as_unchecked().is_valid_for_network(Network::Signet));
+        assert_eq!(
+            Address::parse_legacy_with_versions(
+                "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+                PUBKEY_ADDRESS_PREFIX_MAIN,
+                SCRIPT_ADDRESS_PREFIX_MAIN
+            ),
+            Ok(Address::p2pkh(pubkey_hash, Bitcoin))
+        );
+
+        // The version byte must be one of the expected ones.
+        assert_eq!(
+            Address::parse_legacy_with_versions(
+                "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+                PUBKEY_VERSION,
+                SCRIPT_VERSION
+            ),
+            Err(InvalidLegacyPrefixError { invalid: PUBKEY_ADDRESS_PREFIX_MAIN }.into())
+        );
+    }
 }
@@ -2,15 +2,15 @@
 
 //! Bitcoin scriptPubkey script extensions.
 
-use internals::array::ArrayExt;
 use secp256k1::{Secp256k1, Verification};
 
 use crate::internal_macros::define_extension_trait;
 use crate::key::{
-    PubkeyHash, PublicKey, TapTweak, TweakedPublicKey, UntweakedPublicKey, WPubkeyHash,
-    XOnlyPublicKey,
+    CompressedPublicKey, PubkeyHash, PublicKey, TapTweak, TweakedPublicKey, UntweakedPublicKey,
+    WPubkeyHash, XOnlyPublicKey,
 };
 use crate::opcodes::all::*;
+use crate::script::multisig::{self, Multisig, MultisigError};
 use crate::script::witness_program::{WitnessProgram, P2A_PROGRAM};
 use crate::script::witness_version::WitnessVersion;
 use crate::script::{
@@ -93,6 +93,25 @@ fn is_p2pk(&self) -> bool { self.p2pk_pubkey_bytes().is_some() }
         fn p2pk_public_key(&self) -> Option<PublicKey> {
             PublicKey::from_slice(self.p2pk_pubkey_bytes()?).ok()
         }
+
+        /// Parses this script as a multisig script.
+        ///
+        /// Both `OP_CHECKMULTISIG` and `OP_CHECKMULTISIGVERIFY` scripts are recognized. Returns
+        /// `None` if the script is not a multisig script with minimally encoded counts and valid
+        /// public keys, see [`Multisig::from_script`] for details.
+        fn multisig(&self) -> Option<Multisig<'_>> { Multisig::from_script(self) }
+
+        /// Returns the witness program wrapped in this P2SH output.
+        ///
+        /// Returns `None` unless this script is P2SH, `redeem_script` hashes to its script hash and
+        /// `redeem_script` is a witness program (e.g. a P2SH-P2WPKH or P2SH-P2WSH output).
+        fn p2sh_wrapped_witness_program(&self, redeem_script: &Script) -> Option<WitnessProgram> {
+            let script_hash = script::script_type::p2sh_hash(self)?;
+            if redeem_script.script_hash().ok()? != script_hash {
+                return None;
+            }
+            redeem_script.witness_program()
+        }
     }
 }
 
@@ -100,17 +119,7 @@ fn p2pk_public_key(&self) -> Option<PublicKey> {
     pub(crate) trait ScriptExtPrivate impl for Script {
         /// Returns the bytes of the (possibly invalid) public key if this script is P2PK.
         fn p2pk_pubkey_bytes(&self) -> Option<&[u8]> {
-            if let Ok(bytes) = <&[u8; 67]>::try_from(self.as_bytes()) {
-                let (&first, bytes) = bytes.split_first::<66>();
-                let (&last, pubkey) = bytes.split_last::<65>();
-                (first == OP_PUSHBYTES_65.to_u8() && last == OP_CHECKSIG.to_u8()).then_some(pubkey)
-            } else if let Ok(bytes) = <&[u8; 35]>::try_from(self.as_bytes()) {
-                let (&first, bytes) = bytes.split_first::<34>();
-                let (&last, pubkey) = bytes.split_last::<33>();
-                (first == OP_PUSHBYTES_33.to_u8() && last == OP_CHECKSIG.to_u8()).then_some(pubkey)
-            } else {
-                None
-            }
+            script::script_type::p2pk_pubkey_bytes(self)
         }
     }
 }
@@ -123,6 +132,30 @@ fn new_p2pk(pubkey: PublicKey) -> Self {
             Builder::new().push_key(pubkey).push_opcode(OP_CHECKSIG).into_script()
         }
 
+        /// Generates a multisig script requiring `required` signatures from `keys`.
+        ///
+        /// # Errors
+        ///
+        /// Errors unless `1 <= required <= keys.len() <= 20`.
+        fn new_multisig(required: usize, keys: &[PublicKey]) -> Result<ScriptBuf, MultisigError> {
+            multisig::check_multisig_params(required, keys.len(), multisig::MAX_PUBKEYS_PER_MULTISIG)?;
+            Ok(new_multisig_unchecked(required, keys))
+        }
+
+        /// Generates a bare multisig scriptPubkey that is standard according to Bitcoin Core policy.
+        ///
+        /// # Errors
+        ///
+        /// Errors unless `1 <= required <= keys.len() <= 3`.
+        fn new_bare_multisig(required: usize, keys: &[PublicKey]) -> Result<ScriptBuf, MultisigError> {
+            multisig::check_multisig_params(
+                required,
+                keys.len(),
+                multisig::MAX_STANDARD_BARE_MULTISIG_KEYS,
+            )?;
+            Ok(new_multisig_unchecked(required, keys))
+        }
+
         /// Generates P2PKH-type of scriptPubkey.
         fn new_p2pkh(pubkey_hash: PubkeyHash) -> Self {
             Builder::new()
@@ -143,6 +176,34 @@ fn new_p2sh(script_hash: ScriptHash) -> Self {
                 .into_script()
         }
 
+        /// Generates a P2SH scriptPubkey wrapping a P2WPKH output for `pubkey`.
+        ///
+        /// The redeem script to put in the scriptSig is [`ScriptBuf::p2wpkh_redeem_script`].
+        fn new_p2sh_p2wpkh(pubkey: CompressedPublicKey) -> Self {
+            let redeem_script = ScriptBuf::p2wpkh_redeem_script(pubkey);
+            ScriptBuf::new_p2sh(redeem_script.script_hash().expect("script is less than 520 bytes"))
+        }
+
+        /// Generates a P2SH scriptPubkey wrapping a P2WSH output for `witness_script`.
+        ///
+        /// The redeem script to put in the scriptSig is [`ScriptBuf::p2wsh_redeem_script`].
+        fn new_p2sh_p2wsh(witness_script: &Script) -> Result<ScriptBuf, WitnessScriptSizeError> {
+            let redeem_script = ScriptBuf::p2wsh_redeem_script(witness_script)?;
+            Ok(ScriptBuf::new_p2sh(
+                redeem_script.script_hash().expect("script is less than 520 bytes"),
+            ))
+        }
+
+        /// Generates the redeem script of a P2SH-P2WPKH output, i.e. the P2WPKH scriptPubkey.
+        fn p2wpkh_redeem_script(pubkey: CompressedPublicKey) -> ScriptBuf {
+            ScriptBuf::new_p2wpkh(pubkey.wpubkey_hash())
+        }
+
+        /// Generates the redeem script of a P2SH-P2WSH output, i.e. the P2WSH scriptPubkey.
+        fn p2wsh_redeem_script(witness_script: &Script) -> Result<ScriptBuf, WitnessScriptSizeError> {
+            witness_script.wscript_hash().map(ScriptBuf::new_p2wsh)
+        }
+
         /// Generates P2WPKH-type of scriptPubkey.
         fn new_p2wpkh(pubkey_hash: WPubkeyHash) -> Self {
             // pubkey hash is 20 bytes long, so it's safe to use `new_witness_program_unchecked` (Segwitv0)
@@ -185,6 +246,17 @@ fn new_witness_program(witness_program: &WitnessProgram) -> Self {
                 .push_slice(witness_program.program())
                 .into_script()
         }
+
+        /// Generates a witness program scriptPubkey without validating the program.
+        ///
+        /// The program length is not checked against the version, this is intended for
+        /// constructing test vectors. Use [`ScriptBuf::new_witness_program`] otherwise.
+        fn new_witness_program_unchecked<T: AsRef<PushBytes>>(
+            version: WitnessVersion,
+            program: T,
+        ) -> Self {
+            Builder::new().push_opcode(version.into()).push_slice(program).into_script()
+        }
     }
 }
 
@@ -203,6 +275,14 @@ pub(super) fn new_witness_program_unchecked<T: AsRef<PushBytes>>(
     Builder::new().push_opcode(version.into()).push_slice(program).into_script()
 }
 
+/// Generates a multisig script, the caller is responsible for checking the number of keys.
+fn new_multisig_unchecked(required: usize, keys: &[PublicKey]) -> ScriptBuf {
+    // Both counts are at most 20 so they always fit in an `i64`.
+    let builder = Builder::new().push_int_unchecked(required as i64);
+    let builder = keys.iter().fold(builder, |builder, key| builder.push_key(*key));
+    builder.push_int_unchecked(keys.len() as i64).push_opcode(OP_CHECKMULTISIG).into_script()
+}
+
 mod sealed {
     pub trait Sealed {}
     impl Sealed for super::Script {}
@@ -213,6 +293,54 @@ impl Sealed for super::Builder {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::script::ScriptBufExt as _;
+
+    #[test]
+    fn p2sh_p2wpkh() {
+        // From the P2SH-P2WPKH example in BIP-143.
+        let pubkey = "03ad1d8e89212f0b92c74d23bb710c00662ad1470198ac48c43f7d6f93a2a26873"
+            .parse::<CompressedPublicKey>()
+            .unwrap();
+        let redeem_script = ScriptBuf::p2wpkh_redeem_script(pubkey);
+        assert_eq!(redeem_script.to_hex_string(), "001479091972186c449eb1ded22b78e40d009bdf0089");
+        let script_pubkey = ScriptBuf::new_p2sh_p2wpkh(pubkey);
+        assert_eq!(script_pubkey.to_hex_string(), "a9144733f37cf4db86fbc2efed2500b4f4e49f31202387");
+
+        assert_eq!(
+            script_pubkey.p2sh_wrapped_witness_program(&redeem_script),
+            Some(WitnessProgram::p2wpkh(pubkey))
+        );
+        // The redeem script of another key does not match.
+        let other = ScriptBuf::p2wpkh_redeem_script(CompressedPublicKey(
+            pubkey.0.negate(&secp256k1::Secp256k1::verification_only()),
+        ));
+        assert_eq!(script_pubkey.p2sh_wrapped_witness_program(&other), None);
+        // Nor does anything that isn't P2SH.
+        assert_eq!(redeem_script.p2sh_wrapped_witness_program(&redeem_script), None);
+    }
+
+    #[test]
+    fn p2sh_p2wsh() {
+        // From the P2SH-P2WSH example in BIP-143.
+        let witness_script = ScriptBuf::from_hex("56210307b8ae49ac90a048e9b53357a2354b3334e9c8bee813ecb98e99a7e07e8c3ba32103b28f0c28bfab54554ae8c658ac5c3e0ce6e79ad336331f78c428dd43eea8449b21034b8113d703413d57761b8b9781957b8c0ac1dfe69f492580ca4195f50376ba4a21033400f6afecb833092a9a21cfdf1ed1376e58c5d1f47de74683123987e967a8f42103a6d48b1131e94ba04d9737d61acdaa1322008af9602b3b14862c07a1789aac162102d8b661b0b3302ee2f162b09e07a55ad5dfbe673a9f01d9f0c19617681024306b56ae").unwrap();
+        let redeem_script = ScriptBuf::p2wsh_redeem_script(&witness_script).unwrap();
+        assert_eq!(
+            redeem_script.to_hex_string(),
+            "0020a16b5755f7f6f96dbd65f5f0d6ab9418b89af4b1f14a1bb8a09062c35f0dcb54"
+        );
+        let script_pubkey = ScriptBuf::new_p2sh_p2wsh(&witness_script).unwrap();
+        assert_eq!(script_pubkey.to_hex_string(), "a9149993a429037b5d912407a71c252019287b8d27a587");
+
+        assert_eq!(
+            script_pubkey.p2sh_wrapped_witness_program(&redeem_script),
+            Some(WitnessProgram::p2wsh(&witness_script).unwrap())
+        );
+        // The witness script itself is not the redeem script.
+        assert_eq!(script_pubkey.p2sh_wrapped_witness_program(&witness_script), None);
+        // A P2SH output whose redeem script is not a witness program.
+        let p2sh = witness_script.to_p2sh().unwrap();
+        assert_eq!(p2sh.p2sh_wrapped_witness_program(&witness_script), None);
+    }
 
     #[test]
     fn shortest_witness_program() {
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Serde support for addresses that validates the network.
+//!
+//! Deserializing an [`Address`] directly gives an `Address<NetworkUnchecked>` which must then be
+//! checked by the caller. The modules here can instead be used with `#[serde(with = "...")]` on an
+//! `Address<NetworkChecked>` field, the string is parsed and [`Address::require_network`] is called
+//! as part of deserialization. There is one module per network, use [`deserialize`] with a type
+//! implementing [`RequiredNetwork`] to choose the network some other way.
+//!
+//! Addresses are always serialized in their string form, including in binary formats. The
+//! `script_pubkey` would be more compact but the string keeps the serialization the same as
+//! `Display` and the one used by the `Serialize` impl on [`Address`].
+//!
+//! # Examples
+//!
+//! ```
+//! use bitcoin::Address;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Payment {
+//!     #[serde(with = "bitcoin::address::serde::mainnet")]
+//!     address: Address,
+//! }
+//!
+//! let json = r#"{"address":"132F25rTsvBdp9JzLLBHP5mvGY66i1xdiM"}"#;
+//! let payment: Payment = serde_json::from_str(json).unwrap();
+//! assert_eq!(serde_json::to_string(&payment).unwrap(), json);
+//!
+//! let testnet = r#"{"address":"mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"}"#;
+//! assert!(serde_json::from_str::<Payment>(testnet).is_err());
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::{Deserializer, Serialize, Serializer};
+
+use super::{Address, NetworkUnchecked, ParseError};
+use crate::network::{Network, TestnetVersion};
+
+/// The network an address is required to be valid for when deserializing with [`deserialize`].
+pub trait RequiredNetwork {
+    /// The required network.
+    const NETWORK: Network;
+}
+
+/// Serializes an address as its string form.
+pub fn serialize<S: Serializer>(address: &Address, s: S) -> Result<S::Ok, S::Error> {
+    address.serialize(s)
+}
+
+/// Deserializes an address from its string form, requiring it to be valid for `N::NETWORK`.
+///
+/// Use as `#[serde(deserialize_with = "bitcoin::address::serde::deserialize::<_, N>")]`.
+pub fn deserialize<'de, D, N>(d: D) -> Result<Address, D::Error>
+where
+    D: Deserializer<'de>,
+    N: RequiredNetwork,
+{
+    struct Visitor<N>(PhantomData<N>);
+
+    impl<N: RequiredNetwork> serde::de::Visitor<'_> for Visitor<N> {
+        type Value = Address;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a Bitcoin address valid for {}", N::NETWORK)
+        }
+
+        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            let address = v.parse::<Address<NetworkUnchecked>>().map_err(E::custom)?;
+            address.require_network(N::NETWORK).map_err(|e| match e {
+                // Report the networks rather than the generic "validation error".
+                ParseError::NetworkValidation(e) => E::custom(e),
+                e => E::custom(e),
+            })
+        }
+    }
+
+    d.deserialize_str(Visitor::<N>(PhantomData))
+}
+
+macro_rules! network_module {
+    ($($(#[$doc:meta])* $module:ident, $marker:ident => $network:expr;)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $marker;
+
+            impl RequiredNetwork for $marker {
+                const NETWORK: Network = $network;
+            }
+
+            $(#[$doc])*
+            pub mod $module {
+                #![allow(missing_docs)]
+
+                use serde::{Deserializer, Serializer};
+
+                use crate::Address;
+
+                pub fn serialize<S: Serializer>(address: &Address, s: S) -> Result<S::Ok, S::Error> {
+                    super::serialize(address, s)
+                }
+
+                pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Address, D::Error> {
+                    super::deserialize::<D, super::$marker>(d)
+                }
+            }
+        )*
+    };
+}
+
+network_module! {
+    /// Requires addresses to be valid for Bitcoin mainnet.
+    mainnet, Mainnet => Network::Bitcoin;
+    /// Requires addresses to be valid for testnet version 3.
+    testnet3, Testnet3 => Network::Testnet(TestnetVersion::V3);
+    /// Requires addresses to be valid for testnet version 4.
+    testnet4, Testnet4 => Network::Testnet(TestnetVersion::V4);
+    /// Requires addresses to be valid for signet.
+    signet, Signet => Network::Signet;
+    /// Requires addresses to be valid for regtest.
+    regtest, Regtest => Network::Regtest;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct OnMainnet {
+        #[serde(with = "crate::address::serde::mainnet")]
+        address: Address,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct OnRegtest {
+        #[serde(with = "crate::address::serde::regtest")]
+        address: Address,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct OnTestnet4 {
+        #[serde(
+            serialize_with = "crate::address::serde::serialize",
+            deserialize_with = "crate::address::serde::deserialize::<_, Testnet4>"
+        )]
+        address: Address,
+    }
+
+    fn address(s: &str) -> Address { s.parse::<Address<_>>().unwrap().assume_checked() }
+
+    #[test]
+    fn json_round_trip() {
+        let mainnet = OnMainnet { address: address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4") };
+        let json = serde_json::to_string(&mainnet).unwrap();
+        assert_eq!(json, r#"{"address":"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"}"#);
+        assert_eq!(serde_json::from_str::<OnMainnet>(&json).unwrap(), mainnet);
+
+        let regtest =
+            OnRegtest { address: address("bcrt1q2nfxmhd4n3c8834pj72xagvyr9gl57n5r94fsl") };
+        let json = serde_json::to_string(&regtest).unwrap();
+        assert_eq!(serde_json::from_str::<OnRegtest>(&json).unwrap(), regtest);
+
+        // Base58 testnet addresses are valid for all the test networks.
+        let testnet4 = OnTestnet4 { address: address("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc") };
+        let json = serde_json::to_string(&testnet4).unwrap();
+        assert_eq!(json, r#"{"address":"2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc"}"#);
+        assert_eq!(serde_json::from_str::<OnTestnet4>(&json).unwrap(), testnet4);
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        let mainnet = OnMainnet { address: address("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k") };
+        let bytes = bincode::serialize(&mainnet).unwrap();
+        // The string form is used for binary formats too.
+        assert_eq!(bytes, bincode::serialize("33iFwdLuRpW1uK1RTRqsoi8rR4NpDzk66k").unwrap());
+        assert_eq!(bincode::deserialize::<OnMainnet>(&bytes).unwrap(), mainnet);
+
+        let regtest =
+            OnRegtest { address: address("bcrt1q2nfxmhd4n3c8834pj72xagvyr9gl57n5r94fsl") };
+        let bytes = bincode::serialize(&regtest).unwrap();
+        assert_eq!(bincode::deserialize::<OnRegtest>(&bytes).unwrap(), regtest);
+        assert!(bincode::deserialize::<OnMainnet>(&bytes).is_err());
+    }
+
+    #[test]
+    fn network_mismatch() {
+        let json = r#"{"address":"bcrt1q2nfxmhd4n3c8834pj72xagvyr9gl57n5r94fsl"}"#;
+        let err = serde_json::from_str::<OnMainnet>(json).unwrap_err();
+        assert!(err.to_string().starts_with(
+            "address bcrt1q2nfxmhd4n3c8834pj72xagvyr9gl57n5r94fsl is valid for regtest but \
+             bitcoin was required"
+        ));
+
+        let json = r#"{"address":"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"}"#;
+        let err = serde_json::from_str::<OnTestnet4>(json).unwrap_err();
+        assert!(err.to_string().starts_with(
+            "address bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 is valid for bitcoin but \
+             testnet4 was required"
+        ));
+
+        let json = r#"{"address":"not an address"}"#;
+        assert!(serde_json::from_str::<OnMainnet>(json).is_err());
+    }
+}
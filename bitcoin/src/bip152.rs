@@ -327,7 +327,8 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Erro
                     .into());
                 }
 
-                let mut indexes = Vec::with_capacity(nb_indexes);
+                let mut indexes =
+                    Vec::with_capacity(encode::preallocation_capacity::<u64>(nb_indexes as u64));
                 let mut last_index: u64 = 0;
                 for _ in 0..nb_indexes {
                     let differential = r.read_compact_size()?;
@@ -70,6 +70,11 @@
 impl_hashencode!(FilterHash);
 impl_hashencode!(FilterHeader);
 
+impl FilterHeader {
+    /// Dummy filter header used as the previous filter header of the genesis block.
+    pub const GENESIS_PREVIOUS_FILTER_HEADER: Self = Self::from_byte_array([0; 32]);
+}
+
 /// Errors for blockfilter.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -151,6 +156,28 @@ pub fn new_script_filter<M, S>(
         Ok(BlockFilter { content: out })
     }
 
+    /// Computes a basic filter from a block and the scripts of the outputs it spends.
+    ///
+    /// `prev_scripts` must yield the `script_pubkey` of every output spent by the non-coinbase
+    /// transactions of `block`, in any order. Duplicate and empty scripts are ignored, as are
+    /// `OP_RETURN` outputs of the block.
+    pub fn new_basic<I>(block: &Block<Checked>, prev_scripts: I) -> BlockFilter
+    where
+        I: IntoIterator,
+        I::Item: Borrow<Script>,
+    {
+        let mut out = Vec::new();
+        let mut writer = BlockFilterWriter::new(&mut out, block);
+
+        writer.add_output_scripts();
+        for script in prev_scripts {
+            writer.add_element(script.borrow().as_bytes());
+        }
+        writer.finish().expect("in-memory writers don't error");
+
+        BlockFilter { content: out }
+    }
+
     /// Computes this filter's ID in a chain of filters (see [BIP 157]).
     ///
     /// [BIP 157]: <https://github.com/bitcoin/bips/blob/master/bip-0157.mediawiki#Filter_Headers>
@@ -648,9 +675,46 @@ fn blockfilters() {
             }
 
             assert_eq!(filter_header, filter.filter_header(previous_filter_header));
+
+            let prev_scripts = scripts.iter().map(|s| ScriptBuf::from(hex!(s.as_str().unwrap())));
+            let basic = BlockFilter::new_basic(&block, prev_scripts.clone());
+            assert_eq!(basic, test_filter);
+
+            // Duplicate scripts are only encoded once.
+            let duplicated =
+                BlockFilter::new_basic(&block, prev_scripts.clone().chain(prev_scripts));
+            assert_eq!(duplicated, test_filter);
         }
     }
 
+    #[test]
+    fn filter_header_chain() {
+        // Basic filters of blocks 0, 2 and 3 of testnet3 from `blockfilters.json`.
+        let genesis = BlockFilter::new(&hex!("019dfca8"));
+        let header = genesis.filter_header(FilterHeader::GENESIS_PREVIOUS_FILTER_HEADER);
+        assert_eq!(
+            header.to_string(),
+            "21584579b7eb08997773e5aeff3a7f932700042d0ed2a6129012b7d7ae81b750"
+        );
+
+        let prev = "d7bdac13a59d745b1add0d2ce852f1a0442e8945fc1bf3848d3cbffd88c24fe1"
+            .parse::<FilterHeader>()
+            .unwrap();
+        let block_2 = BlockFilter::new(&hex!("0174a170")).filter_header(prev);
+        assert_eq!(
+            block_2.to_string(),
+            "186afd11ef2b5e7e3504f2e8cbf8df28a1fd251fe53d60dff8b1467d1b386cf0"
+        );
+
+        let filter = BlockFilter::new(&hex!("016cf7a0"));
+        let block_3 = filter.filter_header(block_2);
+        assert_eq!(
+            block_3.to_string(),
+            "8d63aadf5ab7257cb6d2316a57b16f517bff1c6388f124ec4c04af1212729d2a"
+        );
+        assert_eq!(filter.filter_hash().filter_header(block_2), block_3);
+    }
+
     #[test]
     fn filter() {
         let mut patterns = BTreeSet::new();
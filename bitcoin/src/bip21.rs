@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! BIP-21 payment URIs.
+//!
+//! Implementation of the `bitcoin:` URI scheme defined in [BIP-21], for example
+//! `bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.001&label=Alice`.
+//!
+//! [BIP-21]: <https://github.com/bitcoin/bips/blob/master/bip-0021.mediawiki>
+
+use core::convert::Infallible;
+use core::fmt;
+use core::str::{FromStr, Utf8Error};
+
+use internals::write_err;
+
+use crate::address::{self, Address, NetworkUnchecked};
+use crate::amount::{Amount, Denomination, ParseAmountError};
+use crate::prelude::{String, ToOwned, Vec};
+
+/// The URI scheme, matched case-insensitively when parsing.
+const SCHEME: &str = "bitcoin:";
+
+/// The prefix of parameters which must be understood by the receiver of a URI.
+const REQUIRED_PREFIX: &str = "req-";
+
+/// A BIP-21 `bitcoin:` URI.
+///
+/// The [`Display`](fmt::Display) implementation percent-encodes the label, the message and the
+/// other parameters, and writes the amount in BTC. Use the alternate form (`{:#}`) to write bech32
+/// addresses in uppercase, which allows QR codes to be encoded more compactly.
+///
+/// Parsing does not validate the network of the address, use [`Address::require_network`] on the
+/// parsed address for that.
+///
+/// # Examples
+///
+/// ```
+/// # use bitcoin::{Amount, Network};
+/// # use bitcoin::bip21::Bip21Uri;
+/// let s = "bitcoin:bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq?amount=0.001&label=Alice%20Smith";
+/// let uri = s.parse::<Bip21Uri>()?;
+///
+/// assert_eq!(uri.amount, Some(Amount::from_sat(100_000)?));
+/// assert_eq!(uri.label.as_deref(), Some("Alice Smith"));
+/// let _address = uri.address.clone().require_network(Network::Bitcoin)?;
+///
+/// assert_eq!(uri.to_string(), s);
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bip21Uri {
+    /// The address to pay to.
+    pub address: Address<NetworkUnchecked>,
+    /// The amount to pay.
+    pub amount: Option<Amount>,
+    /// A label for the address, e.g. the name of the receiver.
+    pub label: Option<String>,
+    /// A message describing the payment.
+    pub message: Option<String>,
+    /// Any other parameters as decoded `(key, value)` pairs, in the order they appear in the URI.
+    ///
+    /// Parameters with a `req-` prefix are never included since parsing fails when one is
+    /// present.
+    pub other_params: Vec<(String, String)>,
+}
+
+impl Bip21Uri {
+    /// Constructs a new URI paying to `address` without any parameters.
+    pub fn new(address: Address<NetworkUnchecked>) -> Self {
+        Bip21Uri { address, amount: None, label: None, message: None, other_params: Vec::new() }
+    }
+}
+
+impl fmt::Display for Bip21Uri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bitcoin:")?;
+        fmt::Display::fmt(self.address.assume_checked_ref(), f)?;
+
+        let mut separator = '?';
+        let mut write_param = |f: &mut fmt::Formatter, key: &str, value: &str| {
+            write!(f, "{}{}={}", separator, PercentEncode(key), PercentEncode(value))?;
+            separator = '&';
+            Ok(())
+        };
+        if let Some(amount) = self.amount {
+            write_param(f, "amount", &amount.to_string_in(Denomination::Bitcoin))?;
+        }
+        if let Some(ref label) = self.label {
+            write_param(f, "label", label)?;
+        }
+        if let Some(ref message) = self.message {
+            write_param(f, "message", message)?;
+        }
+        for (key, value) in &self.other_params {
+            write_param(f, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bip21Uri {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = match s.get(..SCHEME.len()) {
+            Some(scheme) if scheme.eq_ignore_ascii_case(SCHEME) => &s[SCHEME.len()..],
+            _ => return Err(ParseError::MissingScheme),
+        };
+        let (address, query) = match rest.split_once('?') {
+            Some((address, query)) => (address, Some(query)),
+            None => (rest, None),
+        };
+        let mut uri = Bip21Uri::new(address.parse()?);
+
+        let params = query.into_iter().flat_map(|query| query.split('&'));
+        for param in params.filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let key = percent_decode(key)?;
+            let value = percent_decode(value)?;
+            match key.as_str() {
+                "amount" if uri.amount.is_none() =>
+                    uri.amount = Some(Amount::from_str_in(&value, Denomination::Bitcoin)?),
+                "label" if uri.label.is_none() => uri.label = Some(value),
+                "message" if uri.message.is_none() => uri.message = Some(value),
+                "amount" | "label" | "message" => return Err(ParseError::DuplicateParameter(key)),
+                _ if key.starts_with(REQUIRED_PREFIX) =>
+                    return Err(ParseError::UnknownRequiredParameter(key)),
+                _ => uri.other_params.push((key, value)),
+            }
+        }
+        Ok(uri)
+    }
+}
+
+/// Writes a string with every byte except the unreserved characters of RFC 3986 percent-encoded.
+struct PercentEncode<'a>(&'a str);
+
+impl fmt::Display for PercentEncode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.bytes() {
+            if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+                write!(f, "{}", byte as char)?;
+            } else {
+                write!(f, "%{:02X}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a percent-encoded UTF-8 string.
+fn percent_decode(s: &str) -> Result<String, ParseError> {
+    if !s.contains('%') {
+        return Ok(s.to_owned());
+    }
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = iter.next().and_then(|c| (c as char).to_digit(16));
+            let low = iter.next().and_then(|c| (c as char).to_digit(16));
+            match (high, low) {
+                (Some(high), Some(low)) => bytes.push((high << 4 | low) as u8),
+                _ => return Err(ParseError::InvalidPercentEncoding),
+            }
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).map_err(|e| ParseError::InvalidUtf8(e.utf8_error()))
+}
+
+/// An error parsing a BIP-21 URI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The URI does not start with `bitcoin:`.
+    MissingScheme,
+    /// The address could not be parsed.
+    Address(address::ParseError),
+    /// The amount could not be parsed.
+    Amount(ParseAmountError),
+    /// A `%` is not followed by two hex digits.
+    InvalidPercentEncoding,
+    /// A percent-decoded parameter is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// The `amount`, `label` or `message` parameter appears more than once.
+    DuplicateParameter(String),
+    /// A parameter with a `req-` prefix which is not known.
+    UnknownRequiredParameter(String),
+}
+
+impl From<Infallible> for ParseError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ParseError::*;
+
+        match *self {
+            MissingScheme => write!(f, "URI does not start with `bitcoin:`"),
+            Address(ref e) => write_err!(f, "invalid address"; e),
+            Amount(ref e) => write_err!(f, "invalid amount"; e),
+            InvalidPercentEncoding => write!(f, "invalid percent-encoding"),
+            InvalidUtf8(ref e) => write_err!(f, "percent-decoded parameter is not valid UTF-8"; e),
+            DuplicateParameter(ref key) => write!(f, "duplicate parameter `{}`", key),
+            UnknownRequiredParameter(ref key) => write!(f, "unknown required parameter `{}`", key),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use ParseError::*;
+
+        match *self {
+            Address(ref e) => Some(e),
+            Amount(ref e) => Some(e),
+            InvalidUtf8(ref e) => Some(e),
+            MissingScheme
+            | InvalidPercentEncoding
+            | DuplicateParameter(_)
+            | UnknownRequiredParameter(_) => None,
+        }
+    }
+}
+
+impl From<address::ParseError> for ParseError {
+    fn from(e: address::ParseError) -> Self { Self::Address(e) }
+}
+
+impl From<ParseAmountError> for ParseError {
+    fn from(e: ParseAmountError) -> Self { Self::Amount(e) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Network, TestnetVersion};
+
+    const ADDRESS: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    fn uri(params: &str) -> String { format!("bitcoin:{}{}", ADDRESS, params) }
+
+    #[test]
+    fn address_only() {
+        let parsed = uri("").parse::<Bip21Uri>().unwrap();
+        assert_eq!(parsed, Bip21Uri::new(ADDRESS.parse().unwrap()));
+        assert_eq!(parsed.to_string(), uri(""));
+        assert_eq!(uri("?").parse::<Bip21Uri>().unwrap(), parsed);
+
+        let upper = format!("{:#}", parsed);
+        assert_eq!(upper, format!("bitcoin:{}", ADDRESS.to_uppercase()));
+        assert_eq!(upper.parse::<Bip21Uri>().unwrap(), parsed);
+        assert_eq!(upper.to_uppercase().parse::<Bip21Uri>().unwrap(), parsed);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut want = Bip21Uri::new(ADDRESS.parse().unwrap());
+        want.amount = Some(Amount::from_sat(150_000_000).unwrap());
+        want.label = Some("Luke Jr ⚡ café".to_owned());
+        want.message = Some("Donation for project xyz & 100% more".to_owned());
+        want.other_params.push(("somethingyoudontunderstand".to_owned(), "50".to_owned()));
+
+        let s = want.to_string();
+        assert_eq!(
+            s,
+            uri("?amount=1.5&label=Luke%20Jr%20%E2%9A%A1%20caf%C3%A9\
+                 &message=Donation%20for%20project%20xyz%20%26%20100%25%20more\
+                 &somethingyoudontunderstand=50")
+        );
+        assert_eq!(s.parse::<Bip21Uri>().unwrap(), want);
+    }
+
+    #[test]
+    fn amount_with_trailing_zeros() {
+        let amounts =
+            [("1.50000000", 150_000_000), ("20.30", 2_030_000_000), ("50", 5_000_000_000)];
+        for (amount, sat) in amounts {
+            let parsed = uri(&format!("?amount={}", amount)).parse::<Bip21Uri>().unwrap();
+            let want = Amount::from_sat(sat).unwrap();
+            assert_eq!(parsed.amount, Some(want));
+            assert_eq!(parsed.to_string().parse::<Bip21Uri>().unwrap().amount, Some(want));
+        }
+        assert!(matches!(uri("?amount=1,5").parse::<Bip21Uri>(), Err(ParseError::Amount(_))));
+        assert!(matches!(uri("?amount=").parse::<Bip21Uri>(), Err(ParseError::Amount(_))));
+    }
+
+    #[test]
+    fn other_params() {
+        let parsed = uri("?label=&foo&bar=a%3Db").parse::<Bip21Uri>().unwrap();
+        assert_eq!(parsed.label.as_deref(), Some(""));
+        assert_eq!(
+            parsed.other_params,
+            [("foo".to_owned(), String::new()), ("bar".to_owned(), "a=b".to_owned())]
+        );
+        assert_eq!(parsed.to_string(), uri("?label=&foo=&bar=a%3Db"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            uri("?req-unknown=1").parse::<Bip21Uri>(),
+            Err(ParseError::UnknownRequiredParameter("req-unknown".to_owned()))
+        );
+        assert_eq!(
+            uri("?amount=1&req-somethingyoudontunderstand=50").parse::<Bip21Uri>(),
+            Err(ParseError::UnknownRequiredParameter("req-somethingyoudontunderstand".to_owned()))
+        );
+        assert_eq!(
+            uri("?label=a&label=b").parse::<Bip21Uri>(),
+            Err(ParseError::DuplicateParameter("label".to_owned()))
+        );
+        assert_eq!(ADDRESS.parse::<Bip21Uri>(), Err(ParseError::MissingScheme));
+        assert_eq!("bitcoin".parse::<Bip21Uri>(), Err(ParseError::MissingScheme));
+        assert_eq!(uri("?label=%4").parse::<Bip21Uri>(), Err(ParseError::InvalidPercentEncoding));
+        assert_eq!(uri("?label=%zz").parse::<Bip21Uri>(), Err(ParseError::InvalidPercentEncoding));
+        assert!(matches!(uri("?label=%FF").parse::<Bip21Uri>(), Err(ParseError::InvalidUtf8(_))));
+        assert!(matches!("bitcoin:bc1qinvalid".parse::<Bip21Uri>(), Err(ParseError::Address(_))));
+    }
+
+    #[test]
+    fn network_is_unchecked() {
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let parsed = format!("bitcoin:{}", testnet).parse::<Bip21Uri>().unwrap();
+        assert!(parsed.address.clone().require_network(Network::Bitcoin).is_err());
+        assert!(parsed.address.require_network(Network::Testnet(TestnetVersion::V3)).is_ok());
+    }
+}
@@ -5,8 +5,10 @@
 //! Implementation of BIP32 hierarchical deterministic wallets, as defined
 //! at <https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki>.
 
+pub mod slip132;
+
 use core::convert::Infallible;
-use core::ops::Index;
+use core::ops::{Index, Range};
 use core::str::FromStr;
 use core::{fmt, slice};
 
@@ -53,7 +55,9 @@ fn from_hmac(hmac: Hmac<sha512::Hash>) -> Self {
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Fingerprint([u8; 4]);
 impl_array_newtype!(Fingerprint, u8, 4);
-impl_array_newtype_stringify!(Fingerprint, 4);
+impl_array_newtype_stringify!(Fingerprint, 4, no_serde);
+#[cfg(feature = "serde")]
+internals::serde_string_impl!(Fingerprint, "a BIP-32 fingerprint");
 
 hash_newtype! {
     /// Extended key identifier as defined in BIP-32.
@@ -64,6 +68,95 @@ fn from_hmac(hmac: Hmac<sha512::Hash>) -> Self {
 #[cfg(feature = "serde")]
 hashes::impl_serde_for_newtype!(XKeyIdentifier);
 
+/// Implements `Serialize` and `Deserialize` for an extended key.
+///
+/// Human-readable formats use the base58 string, binary formats use the 78 byte encoding.
+#[cfg(feature = "serde")]
+macro_rules! serde_xkey_impl {
+    ($t:ident, $expecting:literal, $other_versions:expr, $other:literal) => {
+        impl serde::Serialize for $t {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                if s.is_human_readable() {
+                    s.collect_str(self)
+                } else {
+                    s.serialize_bytes(&self.encode())
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $t {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<$t, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $t;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$t, E> {
+                        v.parse::<$t>()
+                            .map_err(|e| xkey_de_error(e, &self, $other_versions, $other))
+                    }
+
+                    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<$t, E> {
+                        $t::decode(v).map_err(|e| xkey_de_error(e, &self, $other_versions, $other))
+                    }
+                }
+
+                if d.is_human_readable() {
+                    d.deserialize_str(Visitor)
+                } else {
+                    d.deserialize_bytes(Visitor)
+                }
+            }
+        }
+    };
+}
+
+/// Converts an extended key decoding error into a serde error.
+///
+/// Finding the version bytes of the other kind of extended key (e.g. an `xprv` where an `xpub`
+/// is expected) is reported as such, other errors keep their full source chain.
+#[cfg(feature = "serde")]
+fn xkey_de_error<E: serde::de::Error>(
+    error: Error,
+    expected: &dyn serde::de::Expected,
+    other_versions: [[u8; 4]; 2],
+    other: &str,
+) -> E {
+    match error {
+        Error::UnknownVersion(version) if other_versions.contains(&version) =>
+            E::invalid_value(serde::de::Unexpected::Other(other), expected),
+        error => E::custom(DisplayFullError(error)),
+    }
+}
+
+/// Displays an error along with all its sources.
+#[cfg(feature = "serde")]
+struct DisplayFullError(Error);
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl fmt::Display for DisplayFullError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use std::error::Error as _;
+
+        fmt::Display::fmt(&self.0, f)?;
+        let mut source_opt = self.0.source();
+        while let Some(source) = source_opt {
+            write!(f, ": {}", source)?;
+            source_opt = source.source();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "serde", not(feature = "std")))]
+impl fmt::Display for DisplayFullError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self.0, f) }
+}
+
 /// Extended private key
 #[derive(Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -82,7 +175,12 @@ pub struct Xpriv {
     pub chain_code: ChainCode,
 }
 #[cfg(feature = "serde")]
-internals::serde_string_impl!(Xpriv, "a BIP-32 extended private key");
+serde_xkey_impl!(
+    Xpriv,
+    "a BIP-32 extended private key",
+    [VERSION_BYTES_MAINNET_PUBLIC, VERSION_BYTES_TESTNETS_PUBLIC],
+    "a BIP-32 extended public key"
+);
 
 #[cfg(not(feature = "std"))]
 impl fmt::Debug for Xpriv {
@@ -115,7 +213,12 @@ pub struct Xpub {
     pub chain_code: ChainCode,
 }
 #[cfg(feature = "serde")]
-internals::serde_string_impl!(Xpub, "a BIP-32 extended public key");
+serde_xkey_impl!(
+    Xpub,
+    "a BIP-32 extended public key",
+    [VERSION_BYTES_MAINNET_PRIVATE, VERSION_BYTES_TESTNETS_PRIVATE],
+    "a BIP-32 extended private key"
+);
 
 /// A child number for a derived key
 #[derive(Copy, Clone, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
@@ -275,7 +378,19 @@ pub trait IntoDerivationPath {
 pub struct DerivationPath(Vec<ChildNumber>);
 
 #[cfg(feature = "serde")]
-internals::serde_string_impl!(DerivationPath, "a BIP-32 derivation path");
+internals::serde_string_deserialize_impl!(DerivationPath, "a BIP-32 derivation path");
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DerivationPath {
+    /// Serializes the path as a string with the `m` master key prefix, e.g. `m/84'/0'/0'`.
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        if self.is_master() {
+            s.serialize_str("m")
+        } else {
+            s.collect_str(&format_args!("m/{}", self))
+        }
+    }
+}
 
 impl<I> Index<I> for DerivationPath
 where
@@ -306,6 +421,20 @@ impl IntoDerivationPath for &'_ str {
     fn into_derivation_path(self) -> Result<DerivationPath, Error> { self.parse() }
 }
 
+/// Values of 2^31 and above are hardened child numbers, see [`DerivationPath::from_u32_slice`].
+impl IntoDerivationPath for &'_ [u32] {
+    fn into_derivation_path(self) -> Result<DerivationPath, Error> {
+        Ok(DerivationPath::from_u32_slice(self))
+    }
+}
+
+/// Values of 2^31 and above are hardened child numbers, see [`DerivationPath::from_u32_slice`].
+impl<const N: usize> IntoDerivationPath for [u32; N] {
+    fn into_derivation_path(self) -> Result<DerivationPath, Error> {
+        Ok(DerivationPath::from_u32_slice(&self))
+    }
+}
+
 impl From<Vec<ChildNumber>> for DerivationPath {
     fn from(numbers: Vec<ChildNumber>) -> Self { DerivationPath(numbers) }
 }
@@ -438,12 +567,52 @@ pub fn hardened_children(&self) -> DerivationPathIterator {
     ///
     /// assert_eq!(deriv_1, deriv_2);
     /// ```
+    #[doc(alias = "extend_from_path")]
     pub fn extend<T: AsRef<[ChildNumber]>>(&self, path: T) -> DerivationPath {
         let mut new_path = self.clone();
         new_path.0.extend_from_slice(path.as_ref());
         new_path
     }
 
+    /// Returns `true` if `self` is a prefix of `other`, i.e. `other` is derived from `self`.
+    ///
+    /// Every path is a prefix of itself and the master path is a prefix of every path.
+    pub fn is_prefix_of(&self, other: &DerivationPath) -> bool { other.0.starts_with(&self.0) }
+
+    /// Returns the path relative to `prefix`, or `None` if `prefix` is not a prefix of `self`.
+    ///
+    /// ```
+    /// use bitcoin::bip32::DerivationPath;
+    ///
+    /// let account = "m/86'/0'/0'".parse::<DerivationPath>().unwrap();
+    /// let path = "m/86'/0'/0'/0/5".parse::<DerivationPath>().unwrap();
+    ///
+    /// let relative = path.strip_prefix(&account).unwrap();
+    /// assert_eq!(relative, "0/5".parse::<DerivationPath>().unwrap());
+    /// assert_eq!(account.extend(&relative), path);
+    /// ```
+    pub fn strip_prefix(&self, prefix: &DerivationPath) -> Option<DerivationPath> {
+        self.0.strip_prefix(prefix.0.as_slice()).map(DerivationPath::from)
+    }
+
+    /// Returns an iterator over the children of this path with the child numbers in `range`.
+    ///
+    /// Values of 2^31 and above are hardened child numbers, as in
+    /// [`DerivationPath::from_u32_slice`].
+    ///
+    /// ```
+    /// use bitcoin::bip32::DerivationPath;
+    ///
+    /// let receive = "m/84'/0'/0'/0".parse::<DerivationPath>().unwrap();
+    /// let mut scan = receive.range(0..100);
+    ///
+    /// assert_eq!(scan.next(), Some("m/84'/0'/0'/0/0".parse().unwrap()));
+    /// assert_eq!(scan.last(), Some("m/84'/0'/0'/0/99".parse().unwrap()));
+    /// ```
+    pub fn range(&self, range: Range<u32>) -> impl Iterator<Item = DerivationPath> + '_ {
+        range.map(move |number| self.child(ChildNumber::from(number)))
+    }
+
     /// Returns the derivation path as a vector of u32 integers.
     /// Unhardened elements are copied as is.
     /// 0x80000000 is added to the hardened elements.
@@ -489,6 +658,188 @@ impl fmt::Debug for DerivationPath {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self, f) }
 }
 
+/// A step of a [`DerivationPathTemplate`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MultipathStep {
+    /// A fixed child number, e.g. `0` or `1'`.
+    Child(ChildNumber),
+    /// One child number for each derived path, e.g. `<0;1>` ([BIP-389]).
+    ///
+    /// [BIP-389]: <https://github.com/bitcoin/bips/blob/master/bip-0389.mediawiki>
+    Multipath(Vec<ChildNumber>),
+    /// A child number substituted on expansion, `*` or `*'` if hardened.
+    Wildcard {
+        /// Whether the substituted child number is hardened.
+        hardened: bool,
+    },
+}
+
+impl fmt::Display for MultipathStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MultipathStep::Child(ref cn) => fmt::Display::fmt(cn, f),
+            MultipathStep::Multipath(ref cns) => {
+                f.write_str("<")?;
+                for (i, cn) in cns.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(";")?;
+                    }
+                    fmt::Display::fmt(cn, f)?;
+                }
+                f.write_str(">")
+            }
+            MultipathStep::Wildcard { hardened: false } => f.write_str("*"),
+            MultipathStep::Wildcard { hardened: true } =>
+                f.write_str(if f.alternate() { "*h" } else { "*'" }),
+        }
+    }
+}
+
+/// A BIP-32 derivation path as written in output script descriptors, e.g. `<0;1>/*`.
+///
+/// A template may contain a single multipath step ([BIP-389]) and a single wildcard, it is
+/// expanded into one [`DerivationPath`] for each alternative of the multipath step.
+///
+/// ```
+/// use bitcoin::bip32::{DerivationPath, DerivationPathTemplate};
+///
+/// let template = "84'/0'/0'/<0;1>/*".parse::<DerivationPathTemplate>().unwrap();
+/// let paths = template.expand(7).unwrap();
+///
+/// assert_eq!(paths, [
+///     "84'/0'/0'/0/7".parse::<DerivationPath>().unwrap(),
+///     "84'/0'/0'/1/7".parse::<DerivationPath>().unwrap(),
+/// ]);
+/// ```
+///
+/// [BIP-389]: <https://github.com/bitcoin/bips/blob/master/bip-0389.mediawiki>
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct DerivationPathTemplate(Vec<MultipathStep>);
+
+impl DerivationPathTemplate {
+    /// Returns the steps of this template.
+    pub fn steps(&self) -> &[MultipathStep] { &self.0 }
+
+    /// Returns `true` if this template contains a wildcard step.
+    pub fn has_wildcard(&self) -> bool {
+        self.0.iter().any(|step| matches!(step, MultipathStep::Wildcard { .. }))
+    }
+
+    /// Returns the number of paths this template expands to.
+    pub fn path_count(&self) -> usize {
+        self.0
+            .iter()
+            .find_map(|step| match step {
+                MultipathStep::Multipath(cns) => Some(cns.len()),
+                _ => None,
+            })
+            .unwrap_or(1)
+    }
+
+    /// Expands this template into concrete derivation paths, one per multipath alternative.
+    ///
+    /// The wildcard, if any, is substituted with the child number `wildcard_index`.
+    ///
+    /// # Errors
+    ///
+    /// If this template has a wildcard and `wildcard_index` is not within [0, 2^31 - 1].
+    pub fn expand(&self, wildcard_index: u32) -> Result<Vec<DerivationPath>, Error> {
+        let wildcard = |hardened| {
+            if hardened {
+                ChildNumber::from_hardened_idx(wildcard_index)
+            } else {
+                ChildNumber::from_normal_idx(wildcard_index)
+            }
+        };
+        (0..self.path_count())
+            .map(|alternative| {
+                self.0
+                    .iter()
+                    .map(|step| match *step {
+                        MultipathStep::Child(cn) => Ok(cn),
+                        MultipathStep::Multipath(ref cns) => Ok(cns[alternative]),
+                        MultipathStep::Wildcard { hardened } => wildcard(hardened),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl FromStr for DerivationPathTemplate {
+    type Err = ParseDerivationPathTemplateError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        use ParseDerivationPathTemplateError as E;
+
+        if path.is_empty() || path == "m" || path == "m/" {
+            return Ok(DerivationPathTemplate::default());
+        }
+        let path = path.strip_prefix("m/").unwrap_or(path);
+
+        let mut steps = Vec::new();
+        let (mut multipath, mut wildcard) = (false, false);
+        for (segment, step) in path.split('/').enumerate() {
+            let child = |s: &str| s.parse().map_err(|error| E::ChildNumber { segment, error });
+
+            if let Some(inner) = step.strip_prefix('<') {
+                let inner = inner.strip_suffix('>').ok_or(E::InvalidMultipath { segment })?;
+                if inner.contains(&['<', '>'][..]) {
+                    return Err(E::NestedMultipath { segment });
+                }
+                if multipath {
+                    return Err(E::MultipleMultipaths { segment });
+                }
+                let cns = inner.split(';').map(child).collect::<Result<Vec<ChildNumber>, _>>()?;
+                let distinct = cns.iter().enumerate().all(|(i, cn)| !cns[..i].contains(cn));
+                if cns.len() < 2 || !distinct {
+                    return Err(E::InvalidMultipath { segment });
+                }
+                multipath = true;
+                steps.push(MultipathStep::Multipath(cns));
+            } else if step.contains(&['<', '>'][..]) {
+                return Err(E::InvalidMultipath { segment });
+            } else if step.contains('*') {
+                let hardened = match step {
+                    "*" => false,
+                    "*'" | "*h" => true,
+                    _ => return Err(E::InvalidWildcard { segment }),
+                };
+                if wildcard {
+                    return Err(E::MultipleWildcards { segment });
+                }
+                wildcard = true;
+                steps.push(MultipathStep::Wildcard { hardened });
+            } else {
+                steps.push(MultipathStep::Child(child(step)?));
+            }
+        }
+        Ok(DerivationPathTemplate(steps))
+    }
+}
+
+impl From<DerivationPath> for DerivationPathTemplate {
+    fn from(path: DerivationPath) -> Self {
+        DerivationPathTemplate(path.0.into_iter().map(MultipathStep::Child).collect())
+    }
+}
+
+impl fmt::Display for DerivationPathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            fmt::Display::fmt(step, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DerivationPathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self, f) }
+}
+
 /// Full information on the used extended public key: fingerprint of the
 /// master extended public key and a derivation path from it.
 pub type KeySource = (Fingerprint, DerivationPath);
@@ -861,6 +1212,88 @@ pub fn ckd_pub<C: secp256k1::Verification>(
         })
     }
 
+    /// Derives the non-hardened children of this key with the indices in `range`.
+    ///
+    /// This is equivalent to calling [`Xpub::ckd_pub`] for each index but the work shared by the
+    /// siblings, i.e. hashing the parent key and computing its fingerprint, is only done once.
+    ///
+    /// ```
+    /// use bitcoin::bip32::{ChildNumber, Xpub};
+    /// use bitcoin::secp256k1::Secp256k1;
+    ///
+    /// let secp = Secp256k1::verification_only();
+    /// let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8".parse::<Xpub>().unwrap();
+    ///
+    /// for (index, child) in (0..20).zip(xpub.derive_children(&secp, 0..20)) {
+    ///     let child_number = ChildNumber::from_normal_idx(index).unwrap();
+    ///     assert_eq!(child.unwrap(), xpub.ckd_pub(&secp, child_number).unwrap());
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Yields [`Error::InvalidChildNumber`] for the indices of hardened children.
+    pub fn derive_children<'a, C: secp256k1::Verification>(
+        &'a self,
+        secp: &'a Secp256k1<C>,
+        range: Range<u32>,
+    ) -> impl Iterator<Item = Result<Xpub, Error>> + 'a {
+        let parent_fingerprint = self.fingerprint();
+        self.child_tweaks(range).map(move |tweak| {
+            let (child_number, sk, chain_code) = tweak?;
+            Ok(Xpub {
+                network: self.network,
+                depth: self.depth + 1,
+                parent_fingerprint,
+                child_number,
+                public_key: self.public_key.add_exp_tweak(secp, &sk.into())?,
+                chain_code,
+            })
+        })
+    }
+
+    /// Derives the public keys of the non-hardened children of this key with the indices in
+    /// `range`.
+    ///
+    /// This is faster than [`Xpub::derive_children`] if only the public keys are needed, e.g. to
+    /// scan for the scripts of a wallet.
+    ///
+    /// # Errors
+    ///
+    /// Yields [`Error::InvalidChildNumber`] for the indices of hardened children.
+    pub fn derive_pubkeys_only<'a, C: secp256k1::Verification>(
+        &'a self,
+        secp: &'a Secp256k1<C>,
+        range: Range<u32>,
+    ) -> impl Iterator<Item = Result<CompressedPublicKey, Error>> + 'a {
+        self.child_tweaks(range).map(move |tweak| {
+            let (_, sk, _) = tweak?;
+            Ok(CompressedPublicKey(self.public_key.add_exp_tweak(secp, &sk.into())?))
+        })
+    }
+
+    /// Computes the tweaks and chain codes of the non-hardened children with the indices in
+    /// `range`, see [`Xpub::ckd_pub_tweak`].
+    fn child_tweaks(
+        &self,
+        range: Range<u32>,
+    ) -> impl Iterator<Item = Result<(ChildNumber, secp256k1::SecretKey, ChainCode), Error>> {
+        let mut parent_engine = HmacEngine::<sha512::HashEngine>::new(&self.chain_code[..]);
+        parent_engine.input(&self.public_key.serialize()[..]);
+
+        range.map(move |index| {
+            let child_number = ChildNumber::from_normal_idx(index)?;
+            let mut engine = parent_engine.clone();
+            engine.input(&index.to_be_bytes());
+
+            let hmac = engine.finalize();
+            let private_key = secp256k1::SecretKey::from_byte_array(
+                hmac.as_byte_array().split_array::<32, 32>().0,
+            )?;
+            Ok((child_number, private_key, ChainCode::from_hmac(hmac)))
+        })
+    }
+
     /// Decoding extended public key from binary data according to BIP 32
     pub fn decode(data: &[u8]) -> Result<Xpub, Error> {
         let Common {
@@ -987,6 +1420,102 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 #[cfg(feature = "std")]
 impl std::error::Error for InvalidBase58PayloadLengthError {}
 
+/// Error parsing a [`DerivationPathTemplate`].
+///
+/// Segments are the `/` separated parts of the template, counted from zero after an optional
+/// `m/` prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseDerivationPathTemplateError {
+    /// A segment contains an invalid child number.
+    ChildNumber {
+        /// The index of the segment.
+        segment: usize,
+        /// The error parsing the child number.
+        error: Error,
+    },
+    /// A wildcard segment is not `*`, `*'` or `*h`.
+    InvalidWildcard {
+        /// The index of the segment.
+        segment: usize,
+    },
+    /// The template contains more than one wildcard.
+    MultipleWildcards {
+        /// The index of the second wildcard segment.
+        segment: usize,
+    },
+    /// A multipath segment is not terminated or has fewer than two or repeated child numbers.
+    InvalidMultipath {
+        /// The index of the segment.
+        segment: usize,
+    },
+    /// A multipath segment contains angle brackets.
+    NestedMultipath {
+        /// The index of the segment.
+        segment: usize,
+    },
+    /// The template contains more than one multipath segment.
+    MultipleMultipaths {
+        /// The index of the second multipath segment.
+        segment: usize,
+    },
+}
+
+impl ParseDerivationPathTemplateError {
+    /// Returns the index of the segment the error occurred in.
+    pub fn segment(&self) -> usize {
+        use ParseDerivationPathTemplateError::*;
+
+        match *self {
+            ChildNumber { segment, .. }
+            | InvalidWildcard { segment }
+            | MultipleWildcards { segment }
+            | InvalidMultipath { segment }
+            | NestedMultipath { segment }
+            | MultipleMultipaths { segment } => segment,
+        }
+    }
+}
+
+impl From<Infallible> for ParseDerivationPathTemplateError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for ParseDerivationPathTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ParseDerivationPathTemplateError::*;
+
+        match *self {
+            ChildNumber { segment, ref error } =>
+                write_err!(f, "invalid child number in segment {}", segment; error),
+            InvalidWildcard { segment } => write!(f, "invalid wildcard in segment {}", segment),
+            MultipleWildcards { segment } => write!(f, "second wildcard in segment {}", segment),
+            InvalidMultipath { segment } =>
+                write!(f, "invalid multipath specifier in segment {}", segment),
+            NestedMultipath { segment } =>
+                write!(f, "nested multipath specifier in segment {}", segment),
+            MultipleMultipaths { segment } =>
+                write!(f, "second multipath specifier in segment {}", segment),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDerivationPathTemplateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use ParseDerivationPathTemplateError::*;
+
+        match *self {
+            ChildNumber { ref error, .. } => Some(error),
+            InvalidWildcard { .. }
+            | MultipleWildcards { .. }
+            | InvalidMultipath { .. }
+            | NestedMultipath { .. }
+            | MultipleMultipaths { .. } => None,
+        }
+    }
+}
+
 // Helps unify decoding
 struct Common {
     network: [u8; 4],
@@ -1112,6 +1641,139 @@ fn derivation_path_conversion_index() {
         assert_eq!(indexed.child(ChildNumber::from_hardened_idx(2).unwrap()), path);
     }
 
+    #[test]
+    fn derivation_path_prefix_and_range() {
+        // BIP-86 account and the first receive address below it.
+        let account = "m/86'/0'/0'".parse::<DerivationPath>().unwrap();
+        let path = "m/86'/0'/0'/0/0".parse::<DerivationPath>().unwrap();
+
+        assert!(account.is_prefix_of(&path));
+        assert!(account.is_prefix_of(&account));
+        assert!(DerivationPath::master().is_prefix_of(&account));
+        assert!(!path.is_prefix_of(&account));
+        assert!(!"m/84'/0'/0'".parse::<DerivationPath>().unwrap().is_prefix_of(&path));
+
+        let relative = path.strip_prefix(&account).unwrap();
+        assert_eq!(relative, "m/0/0".parse::<DerivationPath>().unwrap());
+        assert_eq!(account.extend(&relative), path);
+        assert_eq!(account.strip_prefix(&account), Some(DerivationPath::master()));
+        assert_eq!(account.strip_prefix(&path), None);
+        assert_eq!(path.strip_prefix(&"m/86'/0'/1'".parse().unwrap()), None);
+
+        // Scan the first 100 receive addresses.
+        let receive = "m/84'/0'/0'/0".parse::<DerivationPath>().unwrap();
+        let scan = receive.range(0..100).collect::<Vec<_>>();
+        assert_eq!(scan.len(), 100);
+        for (index, path) in scan.iter().enumerate() {
+            assert_eq!(path.to_string(), format!("84'/0'/0'/0/{}", index));
+            assert!(receive.is_prefix_of(path));
+        }
+        assert_eq!(receive.range(5..5).count(), 0);
+
+        const HARDENED: u32 = 0x8000_0000;
+        let hardened = DerivationPath::master().range(HARDENED..HARDENED + 2).collect::<Vec<_>>();
+        assert_eq!(hardened, ["m/0'".parse().unwrap(), "m/1'".parse().unwrap()]);
+    }
+
+    #[test]
+    fn derivation_path_template_bip389() {
+        let paths = |template: &str, index| {
+            let template = template.parse::<DerivationPathTemplate>().unwrap();
+            template.expand(index).unwrap().iter().map(|path| path.to_string()).collect::<Vec<_>>()
+        };
+
+        // Key paths of the BIP 389 examples.
+        assert_eq!(paths("<0;1>", 0), ["0", "1"]);
+        assert_eq!(paths("<2147483647h;0>/0", 0), ["2147483647'/0", "0/0"]);
+        assert_eq!(paths("1/2/<3;4;5>/*", 7), ["1/2/3/7", "1/2/4/7", "1/2/5/7"]);
+        assert_eq!(paths("<1;0>/*", 3), ["1/3", "0/3"]);
+        assert_eq!(paths("84'/0'/0'/<0;1>/*'", 9), ["84'/0'/0'/0/9'", "84'/0'/0'/1/9'"]);
+        assert_eq!(paths("m/0/1", 5), ["0/1"]);
+        assert_eq!(paths("m", 5), [""]);
+
+        let template = "1/2/<3;4;5>/*".parse::<DerivationPathTemplate>().unwrap();
+        assert_eq!(template.to_string(), "1/2/<3;4;5>/*");
+        assert_eq!(template.path_count(), 3);
+        assert!(template.has_wildcard());
+        assert_eq!(template.steps()[3], MultipathStep::Wildcard { hardened: false });
+        assert_eq!(template.expand(1 << 31), Err(Error::InvalidChildNumber(1 << 31)));
+        let template = "<0h;1'>/*h".parse::<DerivationPathTemplate>().unwrap();
+        assert_eq!(template.to_string(), "<0';1'>/*'");
+        assert_eq!(format!("{:#}", template), "<0h;1h>/*h");
+
+        let path = "0'/1/2".parse::<DerivationPath>().unwrap();
+        let template = DerivationPathTemplate::from(path.clone());
+        assert!(!template.has_wildcard());
+        assert_eq!(template.expand(0).unwrap(), [path]);
+    }
+
+    #[test]
+    fn derivation_path_template_invalid() {
+        use ParseDerivationPathTemplateError as E;
+
+        let err = |template: &str| template.parse::<DerivationPathTemplate>().unwrap_err();
+
+        assert_eq!(err("<0;1>/*/*"), E::MultipleWildcards { segment: 2 });
+        assert_eq!(err("*/0/*'"), E::MultipleWildcards { segment: 2 });
+        assert_eq!(err("0/**"), E::InvalidWildcard { segment: 1 });
+        assert_eq!(err("0/'*"), E::InvalidWildcard { segment: 1 });
+        assert_eq!(err("0/*''"), E::InvalidWildcard { segment: 1 });
+        assert_eq!(err("0/<0;<1;2>>"), E::NestedMultipath { segment: 1 });
+        assert_eq!(err("<<0;1>>"), E::NestedMultipath { segment: 0 });
+        assert_eq!(err("<0;1>/<2;3>"), E::MultipleMultipaths { segment: 1 });
+        assert_eq!(err("<0>"), E::InvalidMultipath { segment: 0 });
+        assert_eq!(err("0/<1;1>"), E::InvalidMultipath { segment: 1 });
+        assert_eq!(err("0/1/<2;3"), E::InvalidMultipath { segment: 2 });
+        assert_eq!(err("0;1>"), E::InvalidMultipath { segment: 0 });
+        assert_eq!(
+            err("0/<1;>"),
+            E::ChildNumber { segment: 1, error: Error::InvalidChildNumberFormat }
+        );
+        assert_eq!(
+            err("m/0/2147483648"),
+            E::ChildNumber { segment: 1, error: Error::InvalidChildNumber(2147483648) }
+        );
+        assert_eq!(err("0//1").segment(), 1);
+    }
+
+    #[test]
+    fn xpub_derive_children() {
+        let secp = Secp256k1::verification_only();
+        let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8".parse::<Xpub>().unwrap();
+
+        let children = xpub.derive_children(&secp, 0..100).collect::<Result<Vec<_>, _>>().unwrap();
+        let pubkeys =
+            xpub.derive_pubkeys_only(&secp, 0..100).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(children.len(), 100);
+        for (index, (child, pubkey)) in (0..).zip(children.iter().zip(&pubkeys)) {
+            let path = [ChildNumber::from_normal_idx(index).unwrap()];
+            assert_eq!(*child, xpub.derive_xpub(&secp, &path).unwrap());
+            assert_eq!(*pubkey, child.to_public_key());
+        }
+
+        let offset = xpub.derive_children(&secp, 50..52).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(offset, children[50..52]);
+        assert_eq!(xpub.derive_children(&secp, 5..5).count(), 0);
+
+        // Hardened children can't be derived.
+        let mut last = xpub.derive_children(&secp, (1 << 31) - 1..(1 << 31) + 1);
+        assert!(last.next().unwrap().is_ok());
+        assert_eq!(last.next().unwrap(), Err(Error::InvalidChildNumber(1 << 31)));
+        let mut last = xpub.derive_pubkeys_only(&secp, 1 << 31..(1 << 31) + 1);
+        assert_eq!(last.next().unwrap(), Err(Error::InvalidChildNumber(1 << 31)));
+    }
+
+    #[test]
+    fn u32_into_derivation_path() {
+        const HARDENED: u32 = 0x8000_0000;
+        let want = "m/84'/0'/0'/0/1".parse::<DerivationPath>().unwrap();
+        let numbers = [84 | HARDENED, HARDENED, HARDENED, 0, 1];
+
+        assert_eq!(numbers.into_derivation_path().unwrap(), want);
+        assert_eq!(numbers[..].into_derivation_path().unwrap(), want);
+        assert_eq!([].into_derivation_path().unwrap(), DerivationPath::master());
+    }
+
     fn test_path<C: secp256k1::Signing + secp256k1::Verification>(
         secp: &Secp256k1<C>,
         network: NetworkKind,
@@ -1365,6 +2027,73 @@ pub fn encode_fingerprint_chaincode() {
         );
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_extended_keys_and_paths() {
+        use serde_json;
+
+        let secp = Secp256k1::new();
+        let seed = hex!("000102030405060708090a0b0c0d0e0f");
+        let xpriv = Xpriv::new_master(NetworkKind::Main, &seed).unwrap();
+        let xpub = Xpub::from_xpriv(&secp, &xpriv);
+        let path = "m/84'/0'/0'".parse::<DerivationPath>().unwrap();
+        let fp = xpub.fingerprint();
+
+        serde_round_trip!(xpriv);
+        serde_round_trip!(xpub);
+        serde_round_trip!(path);
+        serde_round_trip!(DerivationPath::master());
+        serde_round_trip!(fp);
+
+        // Human-readable formats use the canonical strings.
+        assert_eq!(serde_json::to_string(&xpriv).unwrap(), format!("\"{}\"", xpriv));
+        assert_eq!(serde_json::to_string(&xpub).unwrap(), format!("\"{}\"", xpub));
+        assert_eq!(serde_json::to_string(&path).unwrap(), "\"m/84'/0'/0'\"");
+        assert_eq!(serde_json::to_string(&fp).unwrap(), "\"3442193e\"");
+
+        // Binary formats use the raw encoding for keys but strings for paths and fingerprints.
+        let mut want = 78_u64.to_le_bytes().to_vec();
+        want.extend_from_slice(&xpub.encode());
+        assert_eq!(bincode::serialize(&xpub).unwrap(), want);
+        let mut want = 8_u64.to_le_bytes().to_vec();
+        want.extend_from_slice(b"3442193e");
+        assert_eq!(bincode::serialize(&fp).unwrap(), want);
+
+        // An extended private key where a public one is expected, and vice versa.
+        let json = serde_json::to_string(&xpriv).unwrap();
+        let err = serde_json::from_str::<Xpub>(&json).unwrap_err().to_string();
+        assert!(
+            err.contains("a BIP-32 extended private key, expected a BIP-32 extended public key")
+        );
+        let bytes = bincode::serialize(&xpriv).unwrap();
+        let err = bincode::deserialize::<Xpub>(&bytes).unwrap_err().to_string();
+        assert!(
+            err.contains("a BIP-32 extended private key, expected a BIP-32 extended public key")
+        );
+        let json = serde_json::to_string(&xpub).unwrap();
+        let err = serde_json::from_str::<Xpriv>(&json).unwrap_err().to_string();
+        assert!(
+            err.contains("a BIP-32 extended public key, expected a BIP-32 extended private key")
+        );
+
+        // Other errors keep the underlying detail.
+        let mut s = xpub.to_string();
+        s.pop();
+        s.push(if s.ends_with('1') { '2' } else { '1' });
+        let err = serde_json::from_str::<Xpub>(&format!("\"{}\"", s)).unwrap_err().to_string();
+        assert!(err.contains("checksum"), "{}", err);
+
+        let mut short = 77_u64.to_le_bytes().to_vec();
+        short.extend_from_slice(&xpub.encode()[..77]);
+        let err = bincode::deserialize::<Xpub>(&short).unwrap_err().to_string();
+        assert!(err.contains("wrong length 77"), "{}", err);
+
+        let mut unknown = bincode::serialize(&xpub).unwrap();
+        unknown[8..12].copy_from_slice(&[0, 0, 0, 0]);
+        let err = bincode::deserialize::<Xpub>(&unknown).unwrap_err().to_string();
+        assert!(err.contains("unknown version"), "{}", err);
+    }
+
     #[test]
     fn fmt_child_number() {
         assert_eq!("000005h", &format!("{:#06}", ChildNumber::from_hardened_idx(5).unwrap()));
@@ -1440,3 +2169,49 @@ fn official_vectors_5() {
         }
     }
 }
+
+#[cfg(bench)]
+mod benches {
+    use test::{black_box, Bencher};
+
+    use super::*;
+
+    const XPUB: &str = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+
+    #[bench]
+    pub fn bench_derive_xpub_10k(bh: &mut Bencher) {
+        let secp = Secp256k1::verification_only();
+        let xpub = XPUB.parse::<Xpub>().unwrap();
+
+        bh.iter(|| {
+            for index in 0..10_000 {
+                let path = [ChildNumber::from_normal_idx(index).unwrap()];
+                black_box(xpub.derive_xpub(&secp, &path).unwrap());
+            }
+        });
+    }
+
+    #[bench]
+    pub fn bench_derive_children_10k(bh: &mut Bencher) {
+        let secp = Secp256k1::verification_only();
+        let xpub = XPUB.parse::<Xpub>().unwrap();
+
+        bh.iter(|| {
+            for child in xpub.derive_children(&secp, 0..10_000) {
+                black_box(child.unwrap());
+            }
+        });
+    }
+
+    #[bench]
+    pub fn bench_derive_pubkeys_only_10k(bh: &mut Bencher) {
+        let secp = Secp256k1::verification_only();
+        let xpub = XPUB.parse::<Xpub>().unwrap();
+
+        bh.iter(|| {
+            for pubkey in xpub.derive_pubkeys_only(&secp, 0..10_000) {
+                black_box(pubkey.unwrap());
+            }
+        });
+    }
+}
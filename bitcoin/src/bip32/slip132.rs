@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! SLIP-132 extended keys.
+//!
+//! Some wallets export extended keys with version bytes hinting at the script type the keys are
+//! used with, e.g. `zpub` for P2WPKH, as registered in
+//! <https://github.com/satoshilabs/slips/blob/master/slip-0132.md>. These are rejected by
+//! [`Xpub`] and [`Xpriv`], which only accept the BIP-32 version bytes, and can be parsed with the
+//! types of this module instead.
+
+use core::fmt;
+use core::str::FromStr;
+
+use super::{
+    Error, InvalidBase58PayloadLengthError, Xpriv, Xpub, VERSION_BYTES_MAINNET_PRIVATE,
+    VERSION_BYTES_MAINNET_PUBLIC, VERSION_BYTES_TESTNETS_PRIVATE, VERSION_BYTES_TESTNETS_PUBLIC,
+};
+use crate::network::NetworkKind;
+
+/// Version bytes for `ypub` extended public keys.
+const VERSION_BYTES_YPUB: [u8; 4] = [0x04, 0x9D, 0x7C, 0xB2];
+/// Version bytes for `yprv` extended private keys.
+const VERSION_BYTES_YPRV: [u8; 4] = [0x04, 0x9D, 0x78, 0x78];
+/// Version bytes for `zpub` extended public keys.
+const VERSION_BYTES_ZPUB: [u8; 4] = [0x04, 0xB2, 0x47, 0x46];
+/// Version bytes for `zprv` extended private keys.
+const VERSION_BYTES_ZPRV: [u8; 4] = [0x04, 0xB2, 0x43, 0x0C];
+/// Version bytes for `upub` extended public keys.
+const VERSION_BYTES_UPUB: [u8; 4] = [0x04, 0x4A, 0x52, 0x62];
+/// Version bytes for `uprv` extended private keys.
+const VERSION_BYTES_UPRV: [u8; 4] = [0x04, 0x4A, 0x4E, 0x28];
+/// Version bytes for `vpub` extended public keys.
+const VERSION_BYTES_VPUB: [u8; 4] = [0x04, 0x5F, 0x1C, 0xF6];
+/// Version bytes for `vprv` extended private keys.
+const VERSION_BYTES_VPRV: [u8; 4] = [0x04, 0x5F, 0x18, 0xBC];
+
+/// Version bytes of extended public keys and the script kind and network they stand for.
+const PUBLIC_VERSIONS: [([u8; 4], ScriptKind, NetworkKind); 6] = [
+    (VERSION_BYTES_MAINNET_PUBLIC, ScriptKind::P2pkh, NetworkKind::Main),
+    (VERSION_BYTES_YPUB, ScriptKind::P2shP2wpkh, NetworkKind::Main),
+    (VERSION_BYTES_ZPUB, ScriptKind::P2wpkh, NetworkKind::Main),
+    (VERSION_BYTES_TESTNETS_PUBLIC, ScriptKind::P2pkh, NetworkKind::Test),
+    (VERSION_BYTES_UPUB, ScriptKind::P2shP2wpkh, NetworkKind::Test),
+    (VERSION_BYTES_VPUB, ScriptKind::P2wpkh, NetworkKind::Test),
+];
+
+/// Version bytes of extended private keys and the script kind and network they stand for.
+const PRIVATE_VERSIONS: [([u8; 4], ScriptKind, NetworkKind); 6] = [
+    (VERSION_BYTES_MAINNET_PRIVATE, ScriptKind::P2pkh, NetworkKind::Main),
+    (VERSION_BYTES_YPRV, ScriptKind::P2shP2wpkh, NetworkKind::Main),
+    (VERSION_BYTES_ZPRV, ScriptKind::P2wpkh, NetworkKind::Main),
+    (VERSION_BYTES_TESTNETS_PRIVATE, ScriptKind::P2pkh, NetworkKind::Test),
+    (VERSION_BYTES_UPRV, ScriptKind::P2shP2wpkh, NetworkKind::Test),
+    (VERSION_BYTES_VPRV, ScriptKind::P2wpkh, NetworkKind::Test),
+];
+
+/// The script type hinted at by the version bytes of a SLIP-132 extended key.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+pub enum ScriptKind {
+    /// P2PKH, `xpub`/`xprv` on mainnet and `tpub`/`tprv` on the testnets.
+    P2pkh,
+    /// P2WPKH nested in P2SH, `ypub`/`yprv` on mainnet and `upub`/`uprv` on the testnets.
+    P2shP2wpkh,
+    /// P2WPKH, `zpub`/`zprv` on mainnet and `vpub`/`vprv` on the testnets.
+    P2wpkh,
+}
+
+/// Returns the version bytes for `kind` and `network` in `versions`.
+fn version_bytes(
+    versions: &[([u8; 4], ScriptKind, NetworkKind)],
+    kind: ScriptKind,
+    network: NetworkKind,
+) -> [u8; 4] {
+    versions
+        .iter()
+        .find(|&&(_, k, n)| k == kind && n == network)
+        .map(|&(version, _, _)| version)
+        .expect("all script kinds are listed for both network kinds")
+}
+
+/// Replaces the SLIP-132 version bytes of the encoded key `data` with the BIP-32 ones.
+///
+/// Returns the script kind the version bytes stood for.
+fn to_bip32_version(
+    data: &mut [u8],
+    versions: &[([u8; 4], ScriptKind, NetworkKind)],
+    bip32_versions: [[u8; 4]; 2],
+) -> Result<ScriptKind, Error> {
+    if data.len() != 78 {
+        return Err(InvalidBase58PayloadLengthError { length: data.len() }.into());
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(&data[..4]);
+    let (kind, network) = versions
+        .iter()
+        .find(|&&(v, _, _)| v == version)
+        .map(|&(_, kind, network)| (kind, network))
+        .ok_or(Error::UnknownVersion(version))?;
+
+    let bip32_version = match network {
+        NetworkKind::Main => bip32_versions[0],
+        NetworkKind::Test => bip32_versions[1],
+    };
+    data[..4].copy_from_slice(&bip32_version);
+    Ok(kind)
+}
+
+/// An extended public key together with the script kind hinted at by its SLIP-132 version bytes.
+///
+/// ```
+/// use bitcoin::bip32::slip132::{ScriptKind, Slip132Xpub};
+///
+/// let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+/// let key = zpub.parse::<Slip132Xpub>().unwrap();
+/// assert_eq!(key.kind, ScriptKind::P2wpkh);
+/// assert!(key.to_xpub().to_string().starts_with("xpub"));
+/// assert_eq!(key.to_string(), zpub);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Slip132Xpub {
+    /// The extended public key.
+    pub xpub: Xpub,
+    /// The script kind hinted at by the version bytes.
+    pub kind: ScriptKind,
+}
+
+impl Slip132Xpub {
+    /// Constructs a new SLIP-132 extended public key.
+    pub fn new(xpub: Xpub, kind: ScriptKind) -> Self { Slip132Xpub { xpub, kind } }
+
+    /// Returns the extended public key without the script kind.
+    pub fn to_xpub(self) -> Xpub { self.xpub }
+
+    /// Decodes an extended public key with any of the SLIP-132 version bytes.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let mut data = data.to_vec();
+        let kind = to_bip32_version(
+            &mut data,
+            &PUBLIC_VERSIONS,
+            [VERSION_BYTES_MAINNET_PUBLIC, VERSION_BYTES_TESTNETS_PUBLIC],
+        )?;
+        Ok(Slip132Xpub { xpub: Xpub::decode(&data)?, kind })
+    }
+
+    /// Encodes the extended public key with the version bytes for its script kind and network.
+    pub fn encode(&self) -> [u8; 78] {
+        let mut ret = self.xpub.encode();
+        ret[..4].copy_from_slice(&version_bytes(&PUBLIC_VERSIONS, self.kind, self.xpub.network));
+        ret
+    }
+}
+
+impl From<Slip132Xpub> for Xpub {
+    fn from(key: Slip132Xpub) -> Self { key.xpub }
+}
+
+impl fmt::Display for Slip132Xpub {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        base58::encode_check_to_fmt(f, &self.encode()[..])
+    }
+}
+
+impl FromStr for Slip132Xpub {
+    type Err = Error;
+
+    fn from_str(inp: &str) -> Result<Self, Error> { Self::decode(&base58::decode_check(inp)?) }
+}
+
+/// An extended private key together with the script kind hinted at by its SLIP-132 version bytes.
+///
+/// The `Debug` output defers to [`Xpriv`], which doesn't print the private key.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Slip132Xpriv {
+    /// The extended private key.
+    pub xpriv: Xpriv,
+    /// The script kind hinted at by the version bytes.
+    pub kind: ScriptKind,
+}
+
+impl Slip132Xpriv {
+    /// Constructs a new SLIP-132 extended private key.
+    pub fn new(xpriv: Xpriv, kind: ScriptKind) -> Self { Slip132Xpriv { xpriv, kind } }
+
+    /// Returns the extended private key without the script kind.
+    pub fn to_xpriv(self) -> Xpriv { self.xpriv }
+
+    /// Decodes an extended private key with any of the SLIP-132 version bytes.
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let mut data = data.to_vec();
+        let kind = to_bip32_version(
+            &mut data,
+            &PRIVATE_VERSIONS,
+            [VERSION_BYTES_MAINNET_PRIVATE, VERSION_BYTES_TESTNETS_PRIVATE],
+        )?;
+        Ok(Slip132Xpriv { xpriv: Xpriv::decode(&data)?, kind })
+    }
+
+    /// Encodes the extended private key with the version bytes for its script kind and network.
+    pub fn encode(&self) -> [u8; 78] {
+        let mut ret = self.xpriv.encode();
+        ret[..4].copy_from_slice(&version_bytes(&PRIVATE_VERSIONS, self.kind, self.xpriv.network));
+        ret
+    }
+}
+
+impl From<Slip132Xpriv> for Xpriv {
+    fn from(key: Slip132Xpriv) -> Self { key.xpriv }
+}
+
+impl fmt::Display for Slip132Xpriv {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        base58::encode_check_to_fmt(f, &self.encode()[..])
+    }
+}
+
+impl FromStr for Slip132Xpriv {
+    type Err = Error;
+
+    fn from_str(inp: &str) -> Result<Self, Error> { Self::decode(&base58::decode_check(inp)?) }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::Secp256k1;
+
+    use super::*;
+    use crate::bip32::DerivationPath;
+
+    // BIP-84 test vectors, derived from the mnemonic "abandon abandon ... about".
+    const ZPRV_ROOT: &str = "zprvAWgYBBk7JR8Gjrh4UJQ2uJdG1r3WNRRfURiABBE3RvMXYSrRJL62XuezvGdPvG6GFBZduosCc1YP5wixPox7zhZLfiUm8aunE96BBa4Kei5";
+    const ZPUB_ACCOUNT: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    #[test]
+    fn bip84_vectors() {
+        let secp = Secp256k1::new();
+        let root = ZPRV_ROOT.parse::<Slip132Xpriv>().unwrap();
+        assert_eq!(root.kind, ScriptKind::P2wpkh);
+        assert_eq!(root.to_string(), ZPRV_ROOT);
+
+        let path = "m/84'/0'/0'".parse::<DerivationPath>().unwrap();
+        let account = Xpub::from_xpriv(&secp, &root.to_xpriv().derive_xpriv(&secp, &path));
+        assert_eq!(Slip132Xpub::new(account, ScriptKind::P2wpkh).to_string(), ZPUB_ACCOUNT);
+        assert_eq!(ZPUB_ACCOUNT.parse::<Slip132Xpub>().unwrap().to_xpub(), account);
+
+        // The core types stay strict.
+        assert_eq!(ZPUB_ACCOUNT.parse::<Xpub>(), Err(Error::UnknownVersion(VERSION_BYTES_ZPUB)));
+        assert_eq!(ZPRV_ROOT.parse::<Xpriv>(), Err(Error::UnknownVersion(VERSION_BYTES_ZPRV)));
+    }
+
+    #[test]
+    fn version_bytes_round_trip() {
+        let secp = Secp256k1::new();
+        let root = ZPRV_ROOT.parse::<Slip132Xpriv>().unwrap().to_xpriv();
+
+        let cases = [
+            (ScriptKind::P2pkh, NetworkKind::Main, "xpub", "xprv"),
+            (ScriptKind::P2shP2wpkh, NetworkKind::Main, "ypub", "yprv"),
+            (ScriptKind::P2wpkh, NetworkKind::Main, "zpub", "zprv"),
+            (ScriptKind::P2pkh, NetworkKind::Test, "tpub", "tprv"),
+            (ScriptKind::P2shP2wpkh, NetworkKind::Test, "upub", "uprv"),
+            (ScriptKind::P2wpkh, NetworkKind::Test, "vpub", "vprv"),
+        ];
+        for (kind, network, pub_prefix, priv_prefix) in cases {
+            let xpriv = Xpriv { network, ..root };
+            let xpub = Xpub::from_xpriv(&secp, &xpriv);
+
+            let slip132_xpub = Slip132Xpub::new(xpub, kind);
+            let encoded = slip132_xpub.encode();
+            assert_eq!(encoded[..4], version_bytes(&PUBLIC_VERSIONS, kind, network));
+            assert_eq!(Slip132Xpub::decode(&encoded), Ok(slip132_xpub));
+            let s = slip132_xpub.to_string();
+            assert!(s.starts_with(pub_prefix), "{} does not start with {}", s, pub_prefix);
+            assert_eq!(s.parse::<Slip132Xpub>(), Ok(slip132_xpub));
+            assert_eq!(Xpub::from(s.parse::<Slip132Xpub>().unwrap()), xpub);
+
+            let slip132_xpriv = Slip132Xpriv::new(xpriv, kind);
+            let encoded = slip132_xpriv.encode();
+            assert_eq!(encoded[..4], version_bytes(&PRIVATE_VERSIONS, kind, network));
+            assert_eq!(Slip132Xpriv::decode(&encoded), Ok(slip132_xpriv));
+            let s = slip132_xpriv.to_string();
+            assert!(s.starts_with(priv_prefix), "{} does not start with {}", s, priv_prefix);
+            assert_eq!(s.parse::<Slip132Xpriv>(), Ok(slip132_xpriv));
+            assert_eq!(Xpriv::from(s.parse::<Slip132Xpriv>().unwrap()), xpriv);
+
+            // Public and private version bytes are not interchangeable.
+            assert!(matches!(
+                slip132_xpub.to_string().parse::<Slip132Xpriv>(),
+                Err(Error::UnknownVersion(_))
+            ));
+            assert!(matches!(
+                slip132_xpriv.to_string().parse::<Slip132Xpub>(),
+                Err(Error::UnknownVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_length() {
+        let data = [0; 77];
+        assert_eq!(
+            Slip132Xpub::decode(&data),
+            Err(InvalidBase58PayloadLengthError { length: 77 }.into())
+        );
+        assert_eq!(
+            Slip132Xpriv::decode(&data),
+            Err(InvalidBase58PayloadLengthError { length: 77 }.into())
+        );
+    }
+}
@@ -18,6 +18,7 @@
 use super::Weight;
 use crate::consensus::encode::WriteExt as _;
 use crate::consensus::{encode, Decodable, Encodable};
+use crate::constants::WITNESS_SCALE_FACTOR;
 use crate::internal_macros::{impl_consensus_encoding, impl_hashencode};
 use crate::merkle_tree::{MerkleNode as _, TxMerkleNode, WitnessMerkleNode};
 use crate::network::Params;
@@ -56,8 +57,12 @@ fn difficulty_float(&self, params: impl AsRef<Params>) -> f64 {
         }
 
         /// Checks that the proof-of-work for the block is valid, returning the block hash.
+        ///
+        /// Headers whose `bits` field decodes to a negative, zero, or overflowing target are
+        /// rejected with [`ValidationError::InvalidCompactTarget`].
         fn validate_pow(&self, required_target: Target) -> Result<BlockHash, ValidationError> {
-            let target = self.target();
+            let target =
+                Target::from_compact_checked(self.bits).ok_or(ValidationError::InvalidCompactTarget)?;
             if target != required_target {
                 return Err(ValidationError::BadTarget);
             }
@@ -98,6 +103,46 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Erro
     }
 }
 
+/// Block locator construction.
+///
+/// A block locator is a list of block hashes, ordered newest to oldest, that describes a chain to
+/// a peer. It is used in the `getheaders` and `getblocks` messages so the peer can find the last
+/// block the chains have in common. The twelve most recent blocks are included, after that the
+/// distance between entries doubles each step. The genesis block is always the last entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Locator;
+
+impl Locator {
+    /// Returns the heights of the blocks in the locator for a chain with tip at height `tip`.
+    ///
+    /// This matches the heights used by Bitcoin Core, if `tip` is at most 12 every height is
+    /// included.
+    pub fn heights(tip: u32) -> Vec<u32> {
+        let mut heights = Vec::new();
+        let mut height = tip;
+        let mut step = 1;
+        loop {
+            heights.push(height);
+            if height == 0 {
+                break;
+            }
+            height = height.saturating_sub(step);
+            if heights.len() > 10 {
+                step = step.saturating_mul(2);
+            }
+        }
+        heights
+    }
+
+    /// Builds a block locator for a chain with tip at height `tip`.
+    ///
+    /// `known` must return the hash of the block at the given height in the chain, it is called
+    /// for each height returned by [`Locator::heights`].
+    pub fn from_heights(tip: u32, known: impl Fn(u32) -> BlockHash) -> Vec<BlockHash> {
+        Self::heights(tip).into_iter().map(known).collect()
+    }
+}
+
 /// Extension functionality for the [`Block<Unchecked>`] type.
 pub trait BlockUncheckedExt: sealed::Sealed {
     /// Validates (or checks) a block.
@@ -246,12 +291,22 @@ fn new_checked(
     /// Returns the weight of the block.
     ///
     /// > Block weight is defined as Base size * 3 + Total size.
+    #[doc(alias = "get_weight")]
     fn weight(&self) -> Weight;
 
+    /// Returns the base block size.
+    ///
+    /// > Base size is the block size in bytes with the original transaction serialization without
+    /// > any witness-related data, as seen by a non-upgraded node.
+    #[doc(alias = "stripped_size")]
+    #[doc(alias = "get_stripped_size")]
+    fn base_size(&self) -> usize;
+
     /// Returns the total block size.
     ///
     /// > Total size is the block size in bytes with transactions serialized as described in BIP144,
     /// > including base data and witness data.
+    #[doc(alias = "get_size")]
     fn total_size(&self) -> usize;
 
     /// Returns the coinbase transaction, if one is present.
@@ -259,6 +314,22 @@ fn new_checked(
 
     /// Returns the block height, as encoded in the coinbase transaction according to BIP34.
     fn bip34_block_height(&self) -> Result<u64, Bip34Error>;
+
+    /// Returns the witness commitment from the coinbase transaction, if one is present.
+    ///
+    /// The commitment is taken from the last coinbase output whose `script_pubkey` starts with
+    /// `OP_RETURN OP_PUSHBYTES_36 0xaa21a9ed` as specified in BIP-141.
+    fn witness_commitment(&self) -> Option<WitnessCommitment>;
+
+    /// Checks if the witness commitment in the coinbase matches the transaction list.
+    ///
+    /// Recomputes the witness Merkle root (using all-zeros as the coinbase wtxid), hashes it with
+    /// the witness reserved value from the coinbase input witness, and compares the result with
+    /// [`witness_commitment`]. Blocks without any witness data are not required to include a
+    /// commitment and always pass this check.
+    ///
+    /// [`witness_commitment`]: BlockCheckedExt::witness_commitment
+    fn check_witness_commitment(&self) -> bool;
 }
 
 impl BlockCheckedExt for Block<Checked> {
@@ -276,10 +347,12 @@ fn witness_root(&mut self) -> Option<WitnessMerkleNode> { self.cached_witness_ro
 
     fn weight(&self) -> Weight {
         // This is the exact definition of a weight unit, as defined by BIP-141 (quote above).
-        let wu = block_base_size(self.transactions()) * 3 + self.total_size();
+        let wu = self.base_size() * (WITNESS_SCALE_FACTOR - 1) + self.total_size();
         Weight::from_wu(wu.to_u64())
     }
 
+    fn base_size(&self) -> usize { block_base_size(self.transactions()) }
+
     fn total_size(&self) -> usize {
         let mut size = Header::SIZE;
 
@@ -322,6 +395,12 @@ fn bip34_block_height(&self) -> Result<u64, Bip34Error> {
             (None, _) => Err(Bip34Error::NotPresent),
         }
     }
+
+    fn witness_commitment(&self) -> Option<WitnessCommitment> {
+        self.coinbase().and_then(witness_commitment_from_coinbase)
+    }
+
+    fn check_witness_commitment(&self) -> bool { check_witness_commitment(self.transactions()).0 }
 }
 
 fn block_base_size(transactions: &[Transaction]) -> usize {
@@ -473,6 +552,10 @@ pub enum ValidationError {
     BadProofOfWork,
     /// The `target` field of a block header did not match the expected difficulty.
     BadTarget,
+    /// The `bits` field of a block header does not encode a valid target.
+    ///
+    /// The encoded target is either negative, zero, or does not fit in 256 bits.
+    InvalidCompactTarget,
 }
 
 impl From<Infallible> for ValidationError {
@@ -486,6 +569,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             BadProofOfWork => f.write_str("block target correct but not attained"),
             BadTarget => f.write_str("block target incorrect"),
+            InvalidCompactTarget => f.write_str("block bits do not encode a valid target"),
         }
     }
 }
@@ -496,7 +580,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use self::ValidationError::*;
 
         match *self {
-            BadProofOfWork | BadTarget => None,
+            BadProofOfWork | BadTarget | InvalidCompactTarget => None,
         }
     }
 }
@@ -509,7 +593,8 @@ mod tests {
     use super::*;
     use crate::consensus::encode::{deserialize, serialize};
     use crate::pow::test_utils::{u128_to_work, u64_to_work};
-    use crate::{block, CompactTarget, Network, TestnetVersion};
+    use crate::pow::CompactTargetExt as _;
+    use crate::{block, CompactTarget, Network, ScriptBuf, TestnetVersion};
 
     #[test]
     fn static_vector() {
@@ -678,6 +763,74 @@ fn segwit_block() {
         assert_eq!(serialize(&real_decode), segwit_block);
     }
 
+    #[test]
+    fn block_size_metrics() {
+        // The `size`, `strippedsize` and `weight` of testnet block
+        // 000000000000045e0b1660b6445b5e5c5ab63c9a4f956be7e1e69be04fa4497b as returned by `getblock`.
+        let segwit_block = include_bytes!("../../tests/data/testnet_block_000000000000045e0b1660b6445b5e5c5ab63c9a4f956be7e1e69be04fa4497b.raw");
+        let block = deserialize::<Block>(segwit_block).unwrap().assume_checked(None);
+        assert_eq!(block.total_size(), 4319);
+        assert_eq!(block.base_size(), 4283);
+        assert_eq!(block.weight(), Weight::from_wu(17168));
+
+        let big_block = include_bytes!("../../tests/data/mainnet_block_000000000000000000000c835b2adcaedc20fdf6ee440009c249452c726dafae.raw");
+        for raw in [&segwit_block[..], &big_block[..]] {
+            let (header, mut transactions) = deserialize::<Block>(raw).unwrap().into_parts();
+            let block = Block::new_unchecked(header, transactions.clone()).assume_checked(None);
+            assert_eq!(block.total_size(), raw.len());
+
+            // The base size is the size of the block serialized without witnesses.
+            transactions
+                .iter_mut()
+                .flat_map(|tx| &mut tx.input)
+                .for_each(|txin| txin.witness.clear());
+            let stripped = Block::new_unchecked(header, transactions).assume_checked(None);
+            assert_eq!(block.base_size(), serialize(&stripped).len());
+            assert!(block.base_size() < block.total_size());
+
+            let wu = block.base_size() * (WITNESS_SCALE_FACTOR - 1) + block.total_size();
+            assert_eq!(block.weight(), Weight::from_wu(wu as u64));
+        }
+    }
+
+    #[test]
+    fn witness_commitment() {
+        // testnet block 000000000000045e0b1660b6445b5e5c5ab63c9a4f956be7e1e69be04fa4497b
+        let segwit_block = include_bytes!("../../tests/data/testnet_block_000000000000045e0b1660b6445b5e5c5ab63c9a4f956be7e1e69be04fa4497b.raw");
+        let block: Block = deserialize(&segwit_block[..]).unwrap();
+        let block = block.assume_checked(None);
+
+        let commitment = block.witness_commitment().expect("segwit block has a commitment");
+        assert_eq!(
+            commitment.to_byte_array().to_vec(),
+            hex!("f91c46b49eb8a29089980f02ee6b57e7d63d33b18b4fddac2bcd7db2a3983704")
+        );
+        assert!(block.check_witness_commitment());
+
+        // Tamper with the commitment in the coinbase output.
+        let (header, mut transactions) = (*block.header(), block.transactions().to_vec());
+        let output = transactions[0]
+            .output
+            .iter_mut()
+            .rev()
+            .find(|o| o.script_pubkey.as_bytes().starts_with(&[0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed]))
+            .unwrap();
+        let mut bytes = output.script_pubkey.to_vec();
+        bytes[37] ^= 0x01;
+        output.script_pubkey = ScriptBuf::from_bytes(bytes);
+        let tampered = Block::new_unchecked(header, transactions).assume_checked(None);
+        assert!(tampered.witness_commitment().is_some());
+        assert_ne!(tampered.witness_commitment(), Some(commitment));
+        assert!(!tampered.check_witness_commitment());
+
+        // Mainnet block 00000000b0c5a240b2a61d2e75692224efd4cbecdf6eaf4cc2cf477ca7c270e7 has no witness data.
+        let block = hex!("010000004ddccd549d28f385ab457e98d1b11ce80bfea2c5ab93015ade4973e400000000bf4473e53794beae34e64fccc471dace6ae544180816f89591894e0f417a914cd74d6e49ffff001d323b3a7b0201000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0804ffff001d026e04ffffffff0100f2052a0100000043410446ef0102d1ec5240f0d061a4246c1bdef63fc3dbab7733052fbbf0ecd8f41fc26bf049ebb4f9527f374280259e7cfa99c48b0e3f39c51347a19a5819651503a5ac00000000010000000321f75f3139a013f50f315b23b0c9a2b6eac31e2bec98e5891c924664889942260000000049483045022100cb2c6b346a978ab8c61b18b5e9397755cbd17d6eb2fe0083ef32e067fa6c785a02206ce44e613f31d9a6b0517e46f3db1576e9812cc98d159bfdaf759a5014081b5c01ffffffff79cda0945903627c3da1f85fc95d0b8ee3e76ae0cfdc9a65d09744b1f8fc85430000000049483045022047957cdd957cfd0becd642f6b84d82f49b6cb4c51a91f49246908af7c3cfdf4a022100e96b46621f1bffcf5ea5982f88cef651e9354f5791602369bf5a82a6cd61a62501fffffffffe09f5fe3ffbf5ee97a54eb5e5069e9da6b4856ee86fc52938c2f979b0f38e82000000004847304402204165be9a4cbab8049e1af9723b96199bfd3e85f44c6b4c0177e3962686b26073022028f638da23fc003760861ad481ead4099312c60030d4cb57820ce4d33812a5ce01ffffffff01009d966b01000000434104ea1feff861b51fe3f5f8a3b12d0f4712db80e919548a80839fc47c6a21e66d957e9c5d8cd108c7a2d2324bad71f9904ac0ae7336507d785b17a2c115e427a32fac00000000");
+        let block: Block = deserialize(&block).unwrap();
+        let block = block.assume_checked(None);
+        assert_eq!(block.witness_commitment(), None);
+        assert!(block.check_witness_commitment());
+    }
+
     #[test]
     fn block_version() {
         let block = hex!("ffffff7f0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000");
@@ -719,6 +872,47 @@ fn validate_pow() {
         }
     }
 
+    #[test]
+    fn validate_pow_mainnet_headers() {
+        use crate::constants::genesis_block;
+
+        let params = Params::new(Network::Bitcoin);
+
+        let genesis = *genesis_block(&params).header();
+        assert_eq!(
+            genesis.validate_pow(params.max_attainable_target).unwrap().to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(genesis.target().difficulty_float(&params), 1.0);
+
+        // Mainnet block 100,000
+        let header = hex!("0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710");
+        let header: Header = deserialize(&header).unwrap();
+        let target = header.bits.to_target();
+        assert_eq!(
+            header.validate_pow(target).unwrap().to_string(),
+            "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506"
+        );
+        assert_eq!(target.difficulty(&params), 14484);
+        assert_eq!(target.difficulty_float(Network::Bitcoin), 14484.162361225399);
+        assert_eq!(header.validate_pow(genesis.target()), Err(ValidationError::BadTarget));
+    }
+
+    #[test]
+    fn validate_pow_invalid_bits() {
+        let mut header = header();
+        // Negative, zero, and overflowing targets.
+        for bits in [0x0492_3456, 0x0000_0000, 0x0100_3456, 0xff12_3456] {
+            header.bits = CompactTarget::from_consensus(bits);
+            assert_eq!(
+                header.validate_pow(header.target()),
+                Err(ValidationError::InvalidCompactTarget),
+                "bits: {:#x}",
+                bits
+            );
+        }
+    }
+
     fn header() -> Header {
         let header = hex!("010000004ddccd549d28f385ab457e98d1b11ce80bfea2c5ab93015ade4973e400000000bf4473e53794beae34e64fccc471dace6ae544180816f89591894e0f417a914cd74d6e49ffff001d323b3a7b");
         deserialize(&header).expect("can't deserialize correct block header")
@@ -757,6 +951,37 @@ fn soft_fork_signalling() {
         assert!(segwit_signal.is_signalling_soft_fork(1));
         assert!(!segwit_signal.is_signalling_soft_fork(2));
     }
+
+    #[test]
+    fn locator_heights() {
+        let want = [
+            100000, 99999, 99998, 99997, 99996, 99995, 99994, 99993, 99992, 99991, 99990, 99989,
+            99987, 99983, 99975, 99959, 99927, 99863, 99735, 99479, 98967, 97943, 95895, 91799,
+            83607, 67223, 34455, 0,
+        ];
+        assert_eq!(Locator::heights(100000), want);
+
+        assert_eq!(Locator::heights(0), [0]);
+        assert_eq!(Locator::heights(5), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(Locator::heights(12), (0..=12).rev().collect::<Vec<_>>());
+        // Genesis is included even when the step skips over it.
+        assert_eq!(Locator::heights(13), [13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 0]);
+        assert_eq!(Locator::heights(u32::MAX).last(), Some(&0));
+    }
+
+    #[test]
+    fn locator_from_heights() {
+        let hash = |height: u32| {
+            let mut bytes = [0; 32];
+            bytes[..4].copy_from_slice(&height.to_le_bytes());
+            BlockHash::from_byte_array(bytes)
+        };
+        let locator = Locator::from_heights(15, hash);
+        assert_eq!(locator.len(), 14);
+        assert_eq!(locator[0], hash(15));
+        assert_eq!(locator[12], hash(2));
+        assert_eq!(locator[13], hash(0));
+    }
 }
 
 #[cfg(bench)]
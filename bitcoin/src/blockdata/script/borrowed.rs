@@ -2,16 +2,22 @@
 
 use core::fmt;
 
-use internals::ToU64 as _;
+use internals::{compact_size, ToU64 as _};
 
+use super::op_return::{self, OpReturnData};
+use super::script_type::{self, ScriptType};
+use super::template::{self, InstructionTemplate};
+use super::timelock::LocktimeRequirements;
+use super::witness_program::WitnessProgram;
 use super::witness_version::WitnessVersion;
 use super::{
-    Builder, Instruction, InstructionIndices, Instructions, PushBytes, RedeemScriptSizeError,
-    ScriptHash, WScriptHash, WitnessScriptSizeError,
+    Builder, Instruction, InstructionIndices, InstructionIndicesExt, Instructions, PushBytes,
+    Pushes, RedeemScriptSizeError, ScriptHash, WScriptHash, WitnessScriptSizeError,
+    MAX_SCRIPT_SIZE,
 };
 use crate::consensus::Encodable;
 use crate::opcodes::all::*;
-use crate::opcodes::{self, Opcode};
+use crate::opcodes::Opcode;
 use crate::policy::{DUST_RELAY_TX_FEE, MAX_OP_RETURN_RELAY};
 use crate::prelude::{sink, DisplayHex, String, ToString};
 use crate::taproot::{LeafVersion, TapLeafHash};
@@ -32,18 +38,43 @@ fn bytes(&self) -> Bytes<'_> { Bytes(self.as_bytes().iter().copied()) }
         fn builder() -> Builder { Builder::new() }
 
         /// Returns 160-bit hash of the script for P2SH outputs.
+        ///
+        /// # Errors
+        ///
+        /// If the script is longer than 520 bytes, since P2SH outputs committing to such a redeem
+        /// script can never be spent.
         #[inline]
         fn script_hash(&self) -> Result<ScriptHash, RedeemScriptSizeError> {
             ScriptHash::from_script(self)
         }
 
+        /// Returns 160-bit hash of the script for P2SH outputs irrespective of script size.
+        ///
+        /// See [`ScriptHash::from_script_unchecked`], this is intended for test vectors.
+        #[inline]
+        fn script_hash_unchecked(&self) -> ScriptHash { ScriptHash::from_script_unchecked(self) }
+
         /// Returns 256-bit hash of the script for P2WSH outputs.
+        ///
+        /// # Errors
+        ///
+        /// If the script is longer than 10,000 bytes, since P2WSH outputs committing to such a
+        /// witness script can never be spent.
         #[inline]
         fn wscript_hash(&self) -> Result<WScriptHash, WitnessScriptSizeError> {
             WScriptHash::from_script(self)
         }
 
+        /// Returns 256-bit hash of the script for P2WSH outputs irrespective of script size.
+        ///
+        /// See [`WScriptHash::from_script_unchecked`], this is intended for test vectors.
+        #[inline]
+        fn wscript_hash_unchecked(&self) -> WScriptHash { WScriptHash::from_script_unchecked(self) }
+
         /// Computes leaf hash of tapscript.
+        ///
+        /// BIP-342 removes the script size limit for tapscript, the size of a leaf script is only
+        /// bounded by the weight of the spending transaction. Hence there is no checked variant.
         #[inline]
         fn tapscript_leaf_hash(&self) -> TapLeafHash {
             TapLeafHash::from_script(self, LeafVersion::TapScript)
@@ -80,25 +111,26 @@ fn witness_version(&self) -> Option<WitnessVersion> {
             WitnessVersion::try_from(ver_opcode).ok()
         }
 
-        /// Checks whether a script pubkey is a P2SH output.
+        /// Returns the witness program if this script conforms to the SegWit rules.
+        ///
+        /// Any version is accepted, including versions not yet defined by a soft fork. The program
+        /// is pushed using the exact size push opcode, so only minimal encodings are recognized.
+        ///
+        /// Returns `None` if the script is not a witness program or if it is a v0 program that is
+        /// neither 20 nor 32 bytes long.
         #[inline]
-        fn is_p2sh(&self) -> bool {
-            self.len() == 23
-                && self.as_bytes()[0] == OP_HASH160.to_u8()
-                && self.as_bytes()[1] == OP_PUSHBYTES_20.to_u8()
-                && self.as_bytes()[22] == OP_EQUAL.to_u8()
+        fn witness_program(&self) -> Option<WitnessProgram> {
+            let version = self.witness_version()?;
+            WitnessProgram::new_consensus(version, &self.as_bytes()[2..]).ok()
         }
 
+        /// Checks whether a script pubkey is a P2SH output.
+        #[inline]
+        fn is_p2sh(&self) -> bool { script_type::p2sh_hash(self).is_some() }
+
         /// Checks whether a script pubkey is a P2PKH output.
         #[inline]
-        fn is_p2pkh(&self) -> bool {
-            self.len() == 25
-                && self.as_bytes()[0] == OP_DUP.to_u8()
-                && self.as_bytes()[1] == OP_HASH160.to_u8()
-                && self.as_bytes()[2] == OP_PUSHBYTES_20.to_u8()
-                && self.as_bytes()[23] == OP_EQUALVERIFY.to_u8()
-                && self.as_bytes()[24] == OP_CHECKSIG.to_u8()
-        }
+        fn is_p2pkh(&self) -> bool { script_type::p2pkh_hash(self).is_some() }
 
         /// Checks whether a script is push only.
         ///
@@ -119,6 +151,33 @@ fn is_push_only(&self) -> bool {
             true
         }
 
+        /// Checks whether every push in the script uses the smallest possible encoding.
+        ///
+        /// This is the BIP-62 minimal push rule (`SCRIPT_VERIFY_MINIMALDATA` in Bitcoin Core):
+        /// data pushes must use the smallest push opcode, and empty pushes and single byte pushes
+        /// of `0x01` - `0x10` or `0x81` must use `OP_0`, `OP_PUSHNUM_1` - `OP_PUSHNUM_16`, or
+        /// `OP_PUSHNUM_NEG1`. Non-push opcodes are ignored.
+        ///
+        /// Returns `false` if the script contains a malformed push.
+        fn has_minimal_pushes(&self) -> bool {
+            self.instruction_indices_ext().all(|res| res.map_or(false, |(_, ins)| ins.is_minimal()))
+        }
+
+        /// Classifies the script by the standard output templates.
+        ///
+        /// This parses the script once, prefer it over calling several `is_*` predicates in turn.
+        #[inline]
+        fn classify(&self) -> ScriptType<'_> { ScriptType::from_script(self) }
+
+        /// Checks whether the instructions of the script match `pattern` one for one.
+        ///
+        /// See the [`template`](super::template) module for details. Returns `false` if the
+        /// script contains a malformed push.
+        #[inline]
+        fn matches(&self, pattern: &[InstructionTemplate]) -> bool {
+            template::matches(self, pattern)
+        }
+
         /// Checks whether a script pubkey is a bare multisig output.
         ///
         /// In a bare multisig pubkey script the keys are not hashed, the script
@@ -179,25 +238,19 @@ fn is_witness_program(&self) -> bool { self.witness_version().is_some() }
         /// Checks whether a script pubkey is a P2WSH output.
         #[inline]
         fn is_p2wsh(&self) -> bool {
-            self.len() == 34
-                && self.witness_version() == Some(WitnessVersion::V0)
-                && self.as_bytes()[1] == OP_PUSHBYTES_32.to_u8()
+            script_type::witness_program_of(self, WitnessVersion::V0, 32).is_some()
         }
 
         /// Checks whether a script pubkey is a P2WPKH output.
         #[inline]
         fn is_p2wpkh(&self) -> bool {
-            self.len() == 22
-                && self.witness_version() == Some(WitnessVersion::V0)
-                && self.as_bytes()[1] == OP_PUSHBYTES_20.to_u8()
+            script_type::witness_program_of(self, WitnessVersion::V0, 20).is_some()
         }
 
         /// Checks whether a script pubkey is a P2TR output.
         #[inline]
         fn is_p2tr(&self) -> bool {
-            self.len() == 34
-                && self.witness_version() == Some(WitnessVersion::V1)
-                && self.as_bytes()[1] == OP_PUSHBYTES_32.to_u8()
+            script_type::witness_program_of(self, WitnessVersion::V1, 32).is_some()
         }
 
         /// Check if this is a consensus-valid OP_RETURN output.
@@ -214,28 +267,82 @@ fn is_op_return(&self) -> bool {
 
         /// Check if this is an OP_RETURN that obeys Bitcoin Core standardness policy.
         ///
+        /// The script must consist of `OP_RETURN` followed only by pushes and be at most
+        /// [`MAX_OP_RETURN_RELAY`] bytes long, Bitcoin Core's default limit (`-datacarriersize`)
+        /// which allows 80 bytes of data in a single push. Use
+        /// [`is_standard_op_return_with_max_len()`](Self::is_standard_op_return_with_max_len) for
+        /// nodes configured with a different limit.
+        ///
         /// What this function considers to be standard may change without warning pending Bitcoin Core
         /// changes.
         #[inline]
-        fn is_standard_op_return(&self) -> bool { self.is_op_return() && self.len() <= MAX_OP_RETURN_RELAY }
+        fn is_standard_op_return(&self) -> bool {
+            self.is_standard_op_return_with_max_len(MAX_OP_RETURN_RELAY)
+        }
+
+        /// Check if this is an OP_RETURN that obeys Bitcoin Core standardness policy with a
+        /// `-datacarriersize` of `max_len` bytes.
+        ///
+        /// The script must consist of `OP_RETURN` followed only by pushes and be at most `max_len`
+        /// bytes long.
+        #[inline]
+        fn is_standard_op_return_with_max_len(&self, max_len: usize) -> bool {
+            op_return::is_standard(self, max_len)
+        }
 
-        /// Checks whether a script is trivially known to have no satisfying input.
+        /// Returns the data pushed by this script if it is an OP_RETURN script.
         ///
-        /// This method has potentially confusing semantics and an unclear purpose, so it's going to be
-        /// removed. Use `is_op_return` if you want `OP_RETURN` semantics.
-        #[deprecated(since = "0.32.0", note = "use `is_op_return` instead")]
+        /// See [`OpReturnData::from_script`] for details.
         #[inline]
+        fn op_return_data(&self) -> Option<OpReturnData<'_>> { OpReturnData::from_script(self) }
+
+        /// Checks whether a script is known to have no satisfying input when used as a script
+        /// pubkey.
+        ///
+        /// A script is unspendable if, evaluated with the pre-Taproot rules:
+        ///
+        /// - It is longer than [`MAX_SCRIPT_SIZE`].
+        /// - Its first opcode is `OP_RETURN` or any other opcode that fails the script when
+        ///   executed, e.g. `OP_RESERVED` or `OP_INVALIDOPCODE`.
+        /// - It contains a disabled opcode such as `OP_CAT`, `OP_VERIF` or `OP_VERNOTIF` anywhere.
+        ///   These fail the script even in branches that are not executed.
+        /// - It contains a malformed push.
+        ///
+        /// This is a superset of Bitcoin Core's `CScript::IsUnspendable`, which only checks the
+        /// first two. Use [`is_op_return`](Self::is_op_return) if you want `OP_RETURN` semantics.
         fn is_provably_unspendable(&self) -> bool {
             use crate::opcodes::Class::{IllegalOp, ReturnOp};
+            use crate::opcodes::ClassifyContext::Legacy;
 
+            if self.len() > MAX_SCRIPT_SIZE {
+                return true;
+            }
             match self.as_bytes().first() {
-                Some(b) => {
-                    let first = Opcode::from(*b);
-                    let class = first.classify(opcodes::ClassifyContext::Legacy);
+                Some(b) if matches!(Opcode::from(*b).classify(Legacy), ReturnOp | IllegalOp) =>
+                    return true,
+                Some(_) => {}
+                None => return false,
+            }
+            self.instructions().any(|instruction| match instruction {
+                // `OP_INVALIDOPCODE` only fails when executed.
+                Ok(Instruction::Op(op)) =>
+                    op != OP_INVALIDOPCODE && op.classify(Legacy) == IllegalOp,
+                Ok(Instruction::PushBytes(_)) => false,
+                Err(_) => true,
+            })
+        }
 
-                    class == ReturnOp || class == IllegalOp
-                }
-                None => false,
+        /// Iterates the script to find the last pushdata.
+        ///
+        /// Returns `None` if the last instruction is an opcode (including `OP_PUSHNUM_*`), if the
+        /// script is empty or if it contains a malformed push.
+        fn last_pushdata(&self) -> Option<&PushBytes> {
+            match self.instructions().last() {
+                // Handles op codes up to (but excluding) OP_PUSHNUM_NEG.
+                Some(Ok(Instruction::PushBytes(bytes))) => Some(bytes),
+                // OP_16 (0x60) and lower are considered "pushes" by Bitcoin Core (excl. OP_RESERVED).
+                // However we are only interested in the pushdata so we can ignore them.
+                _ => None,
             }
         }
 
@@ -258,6 +365,15 @@ fn redeem_script(&self) -> Option<&Script> {
             }
         }
 
+        /// Returns the number of bytes this script occupies when serialized in a transaction.
+        ///
+        /// This is the length of the script plus the length of its compact size prefix, i.e. the
+        /// same as `consensus::encode::serialize(self).len()`.
+        fn consensus_size(&self) -> usize {
+            let len = self.len();
+            compact_size::encoded_size(len) + len
+        }
+
         /// Returns the minimum value an output with this script should have in order to be
         /// broadcastable on today’s Bitcoin network.
         #[deprecated(since = "0.32.0", note = "use `minimal_non_dust` etc. instead")]
@@ -342,6 +458,13 @@ fn instructions_minimal(&self) -> Instructions {
             Instructions { data: self.as_bytes().iter(), enforce_minimal: true }
         }
 
+        /// Iterates over the values pushed by the script, skipping all other opcodes.
+        ///
+        /// `OP_0` yields an empty data push, `OP_PUSHNUM_NEG1` and `OP_PUSHNUM_1` - `OP_PUSHNUM_16`
+        /// yield [`Push::Num`](super::Push::Num). Pushes are not required to be minimal.
+        #[inline]
+        fn push_data(&self) -> Pushes<'_> { Pushes { instructions: self.instructions() } }
+
         /// Iterates over the script instructions and their indices.
         ///
         /// Unless the script contains an error, the returned item consists of an index pointing to the
@@ -363,6 +486,40 @@ fn instruction_indices_minimal(&self) -> InstructionIndices {
             InstructionIndices::from_instructions(self.instructions_minimal())
         }
 
+        /// Iterates over the script instructions, their indices and the raw opcodes used to encode
+        /// them.
+        ///
+        /// This is similar to [`instruction_indices`](Self::instruction_indices) but each item also
+        /// exposes the opcode used for a data push (e.g. `OP_PUSHDATA1`) and whether the push was
+        /// minimal. Non-minimal pushes are not treated as errors.
+        #[inline]
+        fn instruction_indices_ext(&self) -> InstructionIndicesExt<'_> {
+            InstructionIndicesExt::from_instruction_indices(self.instruction_indices())
+        }
+
+        /// Returns the byte offset just past the last `OP_CODESEPARATOR` in the script.
+        ///
+        /// Legacy sighash computation only commits to the part of the script following the last
+        /// executed `OP_CODESEPARATOR`. This method does not evaluate the script so it treats every
+        /// `OP_CODESEPARATOR` as executed. If the script contains a malformed push only the
+        /// instructions preceding it are considered.
+        ///
+        /// Returns `None` if the script does not contain `OP_CODESEPARATOR`.
+        fn last_codeseparator_pos(&self) -> Option<usize> {
+            self.instruction_indices_ext()
+                .map_while(Result::ok)
+                .filter(|(_, instruction)| instruction.opcode() == OP_CODESEPARATOR)
+                .last()
+                .map(|(pos, _)| pos + 1)
+        }
+
+        /// Returns the timelocks enforced by `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY`.
+        ///
+        /// See [`LocktimeRequirements::from_script`] for details.
+        fn locktime_requirements(&self) -> LocktimeRequirements {
+            LocktimeRequirements::from_script(self)
+        }
+
         /// Writes the human-readable assembly representation of the script to the formatter.
         #[deprecated(since = "TBD", note = "use the script's `Display` impl instead")]
         fn fmt_asm(&self, f: &mut dyn fmt::Write) -> fmt::Result {
@@ -422,13 +579,15 @@ fn count_sigops_internal(&self, accurate: bool) -> usize {
             for inst in self.instructions() {
                 match inst {
                     Ok(Instruction::Op(opcode)) => {
+                        // Like Bitcoin Core, only the opcode immediately preceding a multisig counts.
+                        let last_pushnum = pushnum_cache.take();
                         match opcode {
                             // p2pk, p2pkh
                             OP_CHECKSIG | OP_CHECKSIGVERIFY => {
                                 n += 1;
                             }
                             OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
-                                match (accurate, pushnum_cache) {
+                                match (accurate, last_pushnum) {
                                     (true, Some(pushnum)) => {
                                         // Add the number of pubkeys in the multisig as sigop count
                                         n += usize::from(pushnum);
@@ -465,19 +624,6 @@ fn last_opcode(&self) -> Option<Opcode> {
                 _ => None,
             }
         }
-
-        /// Iterates the script to find the last pushdata.
-        ///
-        /// Returns `None` if the instruction is an opcode or if the script is empty.
-        fn last_pushdata(&self) -> Option<&PushBytes> {
-            match self.instructions().last() {
-                // Handles op codes up to (but excluding) OP_PUSHNUM_NEG.
-                Some(Ok(Instruction::PushBytes(bytes))) => Some(bytes),
-                // OP_16 (0x60) and lower are considered "pushes" by Bitcoin Core (excl. OP_RESERVED).
-                // However we are only interested in the pushdata so we can ignore them.
-                _ => None,
-            }
-        }
     }
 }
 
@@ -2,8 +2,10 @@
 
 use core::fmt;
 
-use super::{opcode_to_verify, write_scriptint, Error, PushBytes, Script, ScriptBuf};
-use crate::locktime::absolute;
+use super::{
+    opcode_to_verify, write_scriptint, Error, PushBytes, PushSizeError, Script, ScriptBuf,
+};
+use crate::locktime::{absolute, relative};
 use crate::opcodes::all::*;
 use crate::opcodes::Opcode;
 use crate::prelude::Vec;
@@ -19,6 +21,15 @@ impl Builder {
     #[inline]
     pub const fn new() -> Self { Builder(ScriptBuf::new(), None) }
 
+    /// Constructs a new empty script with pre-allocated capacity.
+    ///
+    /// Use this if you know the (approximate) length of the script up front to avoid reallocating
+    /// while pushing.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Builder(ScriptBuf::with_capacity(capacity), None)
+    }
+
     /// Returns the length in bytes of the script.
     pub fn len(&self) -> usize { self.0.len() }
 
@@ -68,20 +79,44 @@ pub fn push_int_unchecked(self, n: i64) -> Builder {
 
     /// Adds instructions to push an integer onto the stack without optimization.
     ///
-    /// This uses the explicit encoding regardless of the availability of dedicated opcodes.
-    pub(in crate::blockdata) fn push_int_non_minimal(self, data: i64) -> Builder {
+    /// This uses the explicit encoding regardless of the availability of dedicated opcodes, e.g.
+    /// `1` is pushed as `OP_PUSHBYTES_1 0x01` instead of `OP_PUSHNUM_1`. Such pushes are rejected
+    /// by the `MINIMALDATA` policy and are mostly useful to construct test vectors (and the
+    /// genesis block).
+    ///
+    /// The number itself is still minimally encoded, in particular `0` is pushed as the empty
+    /// vector.
+    pub fn push_int_non_minimal(self, data: i64) -> Builder {
         let mut buf = [0u8; 8];
         let len = write_scriptint(&mut buf, data);
         self.push_slice(&<&PushBytes>::from(&buf)[..len])
     }
 
     /// Adds instructions to push some arbitrary data onto the stack.
+    ///
+    /// The size of `data` is not checked against [`MAX_STACK_ELEMENT_SIZE`], use
+    /// [`Builder::try_push_slice`] for data of untrusted length.
+    ///
+    /// [`MAX_STACK_ELEMENT_SIZE`]: crate::constants::MAX_STACK_ELEMENT_SIZE
     pub fn push_slice<T: AsRef<PushBytes>>(mut self, data: T) -> Builder {
         self.0.push_slice(data);
         self.1 = None;
         self
     }
 
+    /// Adds instructions to push some arbitrary data onto the stack.
+    ///
+    /// # Errors
+    ///
+    /// If `data` is longer than [`MAX_STACK_ELEMENT_SIZE`] bytes.
+    ///
+    /// [`MAX_STACK_ELEMENT_SIZE`]: crate::constants::MAX_STACK_ELEMENT_SIZE
+    pub fn try_push_slice<T: AsRef<[u8]>>(mut self, data: T) -> Result<Builder, PushSizeError> {
+        self.0.try_push_slice(data)?;
+        self.1 = None;
+        Ok(self)
+    }
+
     /// Adds a single opcode to the script.
     pub fn push_opcode(mut self, data: Opcode) -> Builder {
         self.0.push_opcode(data);
@@ -110,17 +145,33 @@ pub fn push_verify(mut self) -> Builder {
         }
     }
 
+    /// Adds a single opcode to the script, followed by `OP_VERIFY`.
+    ///
+    /// If `opcode` has a VERIFY form it is pushed instead, see [`Builder::push_verify`].
+    pub fn push_opcode_verify(self, opcode: Opcode) -> Builder {
+        self.push_opcode(opcode).push_verify()
+    }
+
     /// Adds instructions to push an absolute lock time onto the stack.
     pub fn push_lock_time(self, lock_time: absolute::LockTime) -> Builder {
         self.push_int_unchecked(lock_time.to_consensus_u32().into())
     }
 
+    /// Adds instructions to push a relative lock time onto the stack.
+    ///
+    /// The lock time is encoded as its sequence number, as expected by `OP_CHECKSEQUENCEVERIFY`.
+    pub fn push_relative_lock_time(self, lock_time: relative::LockTime) -> Builder {
+        self.push_int_unchecked(lock_time.to_consensus_u32().into())
+    }
+
     /// Adds instructions to push a sequence number onto the stack.
     pub fn push_sequence(self, sequence: Sequence) -> Builder {
         self.push_int_unchecked(sequence.to_consensus_u32().into())
     }
 
     /// Converts the `Builder` into `ScriptBuf`.
+    ///
+    /// This does not shrink the allocation, any unused capacity is kept in the returned script.
     pub fn into_script(self) -> ScriptBuf { self.0 }
 
     /// Converts the `Builder` into script bytes
@@ -153,3 +204,30 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self.0
 impl fmt::Debug for Builder {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> { fmt::Display::fmt(self, f) }
 }
+
+#[cfg(bench)]
+mod benches {
+    use test::{black_box, Bencher};
+
+    use super::*;
+
+    const NUM_KEYS: usize = 10_000;
+    const KEY: [u8; 33] = [0x02; 33];
+
+    #[bench]
+    pub fn bench_build_script_without_capacity(bh: &mut Bencher) {
+        bh.iter(|| {
+            let builder = (0..NUM_KEYS).fold(Builder::new(), |builder, _| builder.push_slice(KEY));
+            black_box(builder.into_script());
+        });
+    }
+
+    #[bench]
+    pub fn bench_build_script_with_capacity(bh: &mut Bencher) {
+        bh.iter(|| {
+            let builder = Builder::with_capacity(NUM_KEYS * (KEY.len() + 1));
+            let builder = (0..NUM_KEYS).fold(builder, |builder, _| builder.push_slice(KEY));
+            black_box(builder.into_script());
+        });
+    }
+}
@@ -3,6 +3,7 @@
 use internals::script::{self, PushDataLenLen};
 
 use super::{Error, PushBytes, Script, ScriptBuf, ScriptBufExtPriv as _};
+use crate::opcodes::all::*;
 use crate::opcodes::{self, Opcode};
 
 /// A "parsed opcode" which allows iterating over a [`Script`] in a more sensible way.
@@ -195,6 +196,69 @@ fn size_hint(&self) -> (usize, Option<usize>) {
 
 impl core::iter::FusedIterator for Instructions<'_> {}
 
+/// A value pushed onto the stack by a script, as returned by the [`Pushes`] iterator.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Push<'a> {
+    /// Data pushed by a push opcode, empty for `OP_0`.
+    Bytes(&'a PushBytes),
+    /// A number pushed by `OP_PUSHNUM_NEG1` or one of `OP_PUSHNUM_1` - `OP_PUSHNUM_16`.
+    Num(i8),
+}
+
+impl<'a> Push<'a> {
+    /// Returns the pushed bytes if this is a data push.
+    pub fn push_bytes(&self) -> Option<&'a PushBytes> {
+        match *self {
+            Push::Bytes(bytes) => Some(bytes),
+            Push::Num(_) => None,
+        }
+    }
+
+    /// Interprets the pushed value as a script number.
+    ///
+    /// Numbers pushed by `OP_PUSHNUM_*` are always valid, data pushes are decoded using
+    /// [`read_scriptint`](super::read_scriptint).
+    pub fn read_scriptint(&self, max_size: usize, require_minimal: bool) -> Result<i64, Error> {
+        match *self {
+            Push::Bytes(bytes) =>
+                super::read_scriptint(bytes.as_bytes(), max_size, require_minimal),
+            Push::Num(n) => Ok(n.into()),
+        }
+    }
+}
+
+/// Iterator over the values pushed by a script, skipping all other opcodes.
+///
+/// Obtained by calling `Script::push_data`. Like [`Instructions`] this returns an error and stops
+/// if the script contains a malformed push.
+#[derive(Debug, Clone)]
+pub struct Pushes<'a> {
+    pub(super) instructions: Instructions<'a>,
+}
+
+impl<'a> Iterator for Pushes<'a> {
+    type Item = Result<Push<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.instructions.next()? {
+                Ok(Instruction::PushBytes(bytes)) => return Some(Ok(Push::Bytes(bytes))),
+                Ok(Instruction::Op(OP_PUSHNUM_NEG1)) => return Some(Ok(Push::Num(-1))),
+                Ok(Instruction::Op(op)) =>
+                    if let Some(n) = op.decode_pushnum() {
+                        return Some(Ok(Push::Num(n as i8)));
+                    },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { (0, self.instructions.size_hint().1) }
+}
+
+impl core::iter::FusedIterator for Pushes<'_> {}
+
 /// Iterator over script instructions with their positions.
 ///
 /// The returned indices can be used for slicing [`Script`] [safely](Script#slicing-safety).
@@ -255,3 +319,95 @@ fn nth(&mut self, n: usize) -> Option<Self::Item> {
 }
 
 impl core::iter::FusedIterator for InstructionIndices<'_> {}
+
+/// An [`Instruction`] together with the raw opcode used to encode it.
+///
+/// This is returned by [`InstructionIndicesExt`] and allows distinguishing pushes that use
+/// `OP_PUSHBYTES_N` from those using `OP_PUSHDATA1`, `OP_PUSHDATA2` or `OP_PUSHDATA4`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct InstructionExt<'a> {
+    opcode: Opcode,
+    instruction: Instruction<'a>,
+}
+
+impl<'a> InstructionExt<'a> {
+    /// Returns the raw opcode this instruction was encoded with.
+    ///
+    /// For data pushes this is the push opcode (e.g. `OP_PUSHDATA1`), for everything else it is the
+    /// same as [`Instruction::opcode`].
+    #[inline]
+    pub fn opcode(&self) -> Opcode { self.opcode }
+
+    /// Returns the decoded instruction.
+    #[inline]
+    pub fn instruction(&self) -> Instruction<'a> { self.instruction }
+
+    /// Returns the pushed bytes if the instruction is a data push.
+    #[inline]
+    pub fn push_bytes(&self) -> Option<&'a PushBytes> {
+        match self.instruction {
+            Instruction::Op(_) => None,
+            Instruction::PushBytes(bytes) => Some(bytes),
+        }
+    }
+
+    /// Returns `true` if the instruction is minimally encoded.
+    ///
+    /// Data pushes are checked against the rules of [BIP-62]: the shortest push opcode must be used
+    /// and data that could be pushed using `OP_0`, `OP_PUSHNUM_1` - `OP_PUSHNUM_16` or
+    /// `OP_PUSHNUM_NEG1` must use those opcodes. Non-push opcodes are always minimal.
+    ///
+    /// [BIP-62]: <https://github.com/bitcoin/bips/blob/master/bip-0062.mediawiki#push-operators>
+    pub fn is_minimal(&self) -> bool {
+        let bytes = match self.instruction {
+            Instruction::Op(_) => return true,
+            Instruction::PushBytes(bytes) => bytes.as_bytes(),
+        };
+        match bytes.len() {
+            0 => self.opcode == OP_PUSHBYTES_0,
+            1 if bytes[0] == 0x81 || (1..=16).contains(&bytes[0]) => false,
+            len @ 1..=75 => usize::from(self.opcode.to_u8()) == len,
+            0x4c..=0xff => self.opcode == OP_PUSHDATA1,
+            0x100..=0xffff => self.opcode == OP_PUSHDATA2,
+            _ => self.opcode == OP_PUSHDATA4,
+        }
+    }
+}
+
+/// Iterator over script instructions with their positions and raw opcodes.
+///
+/// Unlike [`InstructionIndices`] this iterator never enforces minimal pushes, use
+/// [`InstructionExt::is_minimal`] to check individual instructions instead.
+#[derive(Debug, Clone)]
+pub struct InstructionIndicesExt<'a> {
+    inner: InstructionIndices<'a>,
+}
+
+impl<'a> InstructionIndicesExt<'a> {
+    /// Views the remaining script as a slice.
+    ///
+    /// This is analogous to what [`core::str::Chars::as_str`] does.
+    #[inline]
+    pub fn as_script(&self) -> &'a Script { self.inner.as_script() }
+
+    /// Constructs a new `Self` wrapping `inner`.
+    pub(super) fn from_instruction_indices(inner: InstructionIndices<'a>) -> Self {
+        InstructionIndicesExt { inner }
+    }
+}
+
+impl<'a> Iterator for InstructionIndicesExt<'a> {
+    /// The `usize` in the tuple represents index at which the returned instruction is located.
+    type Item = Result<(usize, InstructionExt<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = Opcode::from(*self.inner.as_script().as_bytes().first()?);
+        let item = self.inner.next()?;
+        Some(item.map(|(pos, instruction)| (pos, InstructionExt { opcode, instruction })))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl core::iter::FusedIterator for InstructionIndicesExt<'_> {}
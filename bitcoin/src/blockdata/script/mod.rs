@@ -50,10 +50,15 @@
 mod borrowed;
 mod builder;
 mod instruction;
+pub mod multisig;
+pub mod op_return;
 mod owned;
 mod push_bytes;
+pub mod script_type;
+pub mod template;
 #[cfg(test)]
 mod tests;
+pub mod timelock;
 pub mod witness_program;
 pub mod witness_version;
 
@@ -75,13 +80,14 @@
 pub use self::{
     borrowed::ScriptExt,
     builder::Builder,
-    instruction::{Instruction, Instructions, InstructionIndices},
+    instruction::{Instruction, InstructionExt, Instructions, InstructionIndices, InstructionIndicesExt, Push, Pushes},
     owned::ScriptBufExt,
-    push_bytes::{PushBytes, PushBytesBuf, PushBytesError, PushBytesErrorReport},
+    push_bytes::{PushBytes, PushBytesBuf, PushBytesError, PushBytesErrorReport, PushSizeError},
 };
 #[doc(inline)]
 pub use primitives::script::{
     RedeemScriptSizeError, Script, ScriptBuf, ScriptHash, WScriptHash, WitnessScriptSizeError,
+    MAX_REDEEM_SCRIPT_SIZE, MAX_SCRIPT_SIZE, MAX_WITNESS_SCRIPT_SIZE,
 };
 
 pub(crate) use self::borrowed::ScriptExtPriv;
@@ -112,6 +118,10 @@ pub fn p2wpkh_script_code(wpkh: WPubkeyHash) -> ScriptBuf {
 /// more than 4 bytes, this is in line with Bitcoin Core (see [`CScriptNum::serialize`]).
 ///
 /// [`CScriptNum::serialize`]: <https://github.com/bitcoin/bitcoin/blob/8ae2808a4354e8dcc697f76bacc5e2f2befe9220/src/script/script.h#L345>
+///
+/// # Panics
+///
+/// If `n` is `i64::MIN`, the encoding of which is 9 bytes long.
 pub fn write_scriptint(out: &mut [u8; 8], n: i64) -> usize {
     let mut len = 0;
     if n == 0 {
@@ -149,25 +159,58 @@ pub fn p2wpkh_script_code(wpkh: WPubkeyHash) -> ScriptBuf {
 ///
 /// See [`push_bytes::PushBytes::read_scriptint`] for a description of some subtleties of
 /// this function.
-pub fn read_scriptint_non_minimal(v: &[u8]) -> Result<i64, Error> {
-    if v.is_empty() {
-        return Ok(0);
-    }
-    if v.len() > 4 {
+pub fn read_scriptint_non_minimal(v: &[u8]) -> Result<i64, Error> { read_scriptint(v, 4, false) }
+
+/// Decodes an integer in script format, with the semantics of the `CScriptNum` constructor in
+/// Bitcoin Core.
+///
+/// Numbers are little-endian signed-magnitude, with the sign in the most significant bit of the
+/// last byte. Numeric opcodes use a `max_size` of 4 bytes, `OP_CHECKLOCKTIMEVERIFY` and
+/// `OP_CHECKSEQUENCEVERIFY` use 5 bytes. If `require_minimal` is `true` encodings with excess
+/// zero bytes, including negative zero, are rejected like they are under the `MINIMALDATA` rule.
+///
+/// # Errors
+///
+/// * [`Error::NumericOverflow`] if `v` is longer than `max_size` (or than the 8 bytes of an
+///   `i64`).
+/// * [`Error::NonMinimalPush`] if `require_minimal` is set and `v` is not minimally encoded.
+pub fn read_scriptint(v: &[u8], max_size: usize, require_minimal: bool) -> Result<i64, Error> {
+    let last = match v.last() {
+        Some(last) => last,
+        None => return Ok(0),
+    };
+    if v.len() > max_size || v.len() > 8 {
         return Err(Error::NumericOverflow);
     }
+    // Comment and code copied from Bitcoin Core:
+    // https://github.com/bitcoin/bitcoin/blob/447f50e4aed9a8b1d80e1891cda85801aeb80b4e/src/script/script.h#L247-L262
+    // If the most-significant-byte - excluding the sign bit - is zero
+    // then we're not minimal. Note how this test also rejects the
+    // negative-zero encoding, 0x80.
+    if require_minimal && (*last & 0x7f) == 0 {
+        // One exception: if there's more than one byte and the most
+        // significant bit of the second-most-significant-byte is set
+        // it would conflict with the sign bit. An example of this case
+        // is +-255, which encode to 0xff00 and 0xff80 respectively.
+        // (big-endian).
+        if v.len() <= 1 || (v[v.len() - 2] & 0x80) == 0 {
+            return Err(Error::NonMinimalPush);
+        }
+    }
 
     Ok(scriptint_parse(v))
 }
 
-// Caller to guarantee that `v` is not empty.
+// Caller to guarantee that `v` is not empty and at most 8 bytes long.
 fn scriptint_parse(v: &[u8]) -> i64 {
-    let (mut ret, sh) = v.iter().fold((0, 0), |(acc, sh), n| (acc + ((*n as i64) << sh), sh + 8));
+    let (mut ret, sh) =
+        v.iter().fold((0u64, 0), |(acc, sh), n| (acc | (u64::from(*n) << sh), sh + 8));
     if v[v.len() - 1] & 0x80 != 0 {
         ret &= (1 << (sh - 1)) - 1;
-        ret = -ret;
+        -(ret as i64)
+    } else {
+        ret as i64
     }
-    ret
 }
 
 /// Decodes a boolean.
@@ -227,7 +270,8 @@ pub enum Error {
     NonMinimalPush,
     /// Some opcode expected a parameter but it was missing or truncated.
     EarlyEndOfScript,
-    /// Tried to read an array off the stack as a number when it was more than 4 bytes.
+    /// Tried to read an array off the stack as a number when it was longer than allowed (usually
+    /// 4 bytes).
     NumericOverflow,
     /// Can not find the spent output.
     UnknownSpentOutput(OutPoint),
@@ -247,7 +291,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             NonMinimalPush => f.write_str("non-minimal datapush"),
             EarlyEndOfScript => f.write_str("unexpected end of script"),
             NumericOverflow =>
-                f.write_str("numeric overflow (number on stack larger than 4 bytes)"),
+                f.write_str("numeric overflow (number on stack larger than allowed size)"),
             UnknownSpentOutput(ref point) => write!(f, "unknown spent output: {}", point),
             Serialization =>
                 f.write_str("can not serialize the spending transaction in Transaction::verify()"),
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Bare multisig scripts.
+//!
+//! A multisig script has the form `<m> <pubkey>... <n> OP_CHECKMULTISIG` (or
+//! `OP_CHECKMULTISIGVERIFY`) and requires `m` valid signatures from the `n` listed public keys.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use super::{Instruction, Instructions, Script, ScriptExt as _};
+use crate::crypto::key::PublicKey;
+use crate::opcodes::all::*;
+
+/// The maximum number of public keys in a multisig script.
+///
+/// This is `MAX_PUBKEYS_PER_MULTISIG` in Bitcoin Core.
+pub const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+/// The maximum number of public keys in a standard bare (not script hash wrapped) multisig output.
+pub const MAX_STANDARD_BARE_MULTISIG_KEYS: usize = 3;
+
+/// A parsed multisig script.
+///
+/// Obtained by calling `Script::multisig`, all public keys are validated during parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multisig<'a> {
+    required: usize,
+    total: usize,
+    /// The part of the script containing only the public key pushes.
+    keys: &'a Script,
+    verify: bool,
+}
+
+impl<'a> Multisig<'a> {
+    /// Parses a multisig script.
+    ///
+    /// Returns `None` unless the script is exactly `<m> <pubkey>... <n> OP_CHECKMULTISIG` (or
+    /// `OP_CHECKMULTISIGVERIFY`) where:
+    ///
+    /// * `m` and `n` are minimally encoded, i.e. `OP_PUSHNUM_1` - `OP_PUSHNUM_16` for numbers up
+    ///   to 16 and a minimal data push above that,
+    /// * `1 <= m <= n <= 20` and `n` is equal to the number of public keys, and
+    /// * all public keys are valid.
+    pub fn from_script(script: &'a Script) -> Option<Self> {
+        let mut instructions = script.instruction_indices_minimal();
+
+        let (_, first) = instructions.next()?.ok()?;
+        let required = read_number(first)?;
+        let keys_start = script.len() - instructions.as_script().len();
+
+        let mut count = 0;
+        let (keys_end, total) = loop {
+            let (pos, instruction) = instructions.next()?.ok()?;
+            match instruction {
+                Instruction::PushBytes(bytes) if bytes.len() == 33 || bytes.len() == 65 => {
+                    PublicKey::from_slice(bytes.as_bytes()).ok()?;
+                    count += 1;
+                }
+                instruction => break (pos, read_number(instruction)?),
+            }
+        };
+
+        let verify = match instructions.next()?.ok()? {
+            (_, Instruction::Op(OP_CHECKMULTISIG)) => false,
+            (_, Instruction::Op(OP_CHECKMULTISIGVERIFY)) => true,
+            _ => return None,
+        };
+        if instructions.next().is_some() {
+            return None;
+        }
+
+        if required == 0 || required > total || total > MAX_PUBKEYS_PER_MULTISIG || total != count {
+            return None;
+        }
+
+        let keys = Script::from_bytes(&script.as_bytes()[keys_start..keys_end]);
+        Some(Multisig { required, total, keys, verify })
+    }
+
+    /// Returns the number of signatures required to satisfy the script (`m`).
+    #[inline]
+    pub fn required(&self) -> usize { self.required }
+
+    /// Returns the total number of public keys in the script (`n`).
+    #[inline]
+    pub fn total(&self) -> usize { self.total }
+
+    /// Returns an iterator over the public keys in the order they appear in the script.
+    #[inline]
+    pub fn keys(&self) -> MultisigKeys<'a> {
+        MultisigKeys { instructions: self.keys.instructions() }
+    }
+
+    /// Returns `true` if the script ends with `OP_CHECKMULTISIGVERIFY`.
+    #[inline]
+    pub fn is_verify(&self) -> bool { self.verify }
+}
+
+/// Iterator over the public keys of a [`Multisig`] script.
+#[derive(Debug, Clone)]
+pub struct MultisigKeys<'a> {
+    instructions: Instructions<'a>,
+}
+
+impl Iterator for MultisigKeys<'_> {
+    type Item = PublicKey;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = self.instructions.next()?.expect("validated in Multisig::from_script");
+        let bytes = instruction.push_bytes().expect("validated in Multisig::from_script");
+        Some(PublicKey::from_slice(bytes.as_bytes()).expect("validated in Multisig::from_script"))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every key push is at least 34 bytes long.
+        let len = self.instructions.as_script().len();
+        (len / 66, Some(len / 34))
+    }
+}
+
+impl core::iter::FusedIterator for MultisigKeys<'_> {}
+
+/// Checks the parameters of a multisig script about to be constructed.
+pub(crate) fn check_multisig_params(
+    required: usize,
+    total: usize,
+    max: usize,
+) -> Result<(), MultisigError> {
+    if required == 0 {
+        return Err(MultisigError::ZeroRequired);
+    }
+    if total > max {
+        return Err(MultisigError::TooManyKeys { total, max });
+    }
+    if required > total {
+        return Err(MultisigError::TooManyRequired { required, total });
+    }
+    Ok(())
+}
+
+/// Reads a minimally encoded multisig count (`m` or `n`).
+fn read_number(instruction: Instruction<'_>) -> Option<usize> {
+    let n = match instruction {
+        Instruction::Op(op) => op.decode_pushnum()?.into(),
+        // Numbers up to 16 must use `OP_PUSHNUM_N` which is enforced by minimal instructions.
+        Instruction::PushBytes(bytes) => usize::try_from(bytes.read_scriptint().ok()?).ok()?,
+    };
+    (n <= MAX_PUBKEYS_PER_MULTISIG).then_some(n)
+}
+
+/// Error constructing a multisig script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MultisigError {
+    /// The number of required signatures is zero.
+    ZeroRequired,
+    /// More signatures are required than there are public keys.
+    TooManyRequired {
+        /// The number of required signatures.
+        required: usize,
+        /// The number of public keys.
+        total: usize,
+    },
+    /// There are more public keys than allowed.
+    TooManyKeys {
+        /// The number of public keys.
+        total: usize,
+        /// The maximum allowed number of public keys.
+        max: usize,
+    },
+}
+
+impl From<Infallible> for MultisigError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for MultisigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use MultisigError::*;
+
+        match *self {
+            ZeroRequired => f.write_str("multisig must require at least one signature"),
+            TooManyRequired { required, total } => write!(
+                f,
+                "multisig requires {} signatures but only has {} public keys",
+                required, total
+            ),
+            TooManyKeys { total, max } =>
+                write!(f, "multisig has {} public keys, maximum is {}", total, max),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MultisigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use MultisigError::*;
+
+        match *self {
+            ZeroRequired | TooManyRequired { .. } | TooManyKeys { .. } => None,
+        }
+    }
+}
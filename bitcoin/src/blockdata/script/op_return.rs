@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Null data (`OP_RETURN`) scripts.
+//!
+//! An output with a script starting with `OP_RETURN` is provably unspendable and is commonly used
+//! to embed arbitrary data in the blockchain. The data is carried in pushes following the
+//! `OP_RETURN` opcode.
+
+use super::{Instruction, Instructions, PushBytes, Script, ScriptExt as _};
+use crate::opcodes::all::*;
+
+/// The data pushed by an `OP_RETURN` script.
+///
+/// Obtained by calling `Script::op_return_data`, iterates over the pushed data elements in order.
+#[derive(Debug, Clone)]
+pub struct OpReturnData<'a> {
+    instructions: Instructions<'a>,
+}
+
+impl<'a> OpReturnData<'a> {
+    /// Parses an `OP_RETURN` script.
+    ///
+    /// Returns `None` unless the script starts with `OP_RETURN` followed by zero or more data
+    /// pushes. Small number opcodes (`OP_PUSHNUM_1` etc.) do not carry data and are therefore
+    /// rejected too, as is any malformed push.
+    pub fn from_script(script: &'a Script) -> Option<Self> {
+        if !script.is_op_return() {
+            return None;
+        }
+        let data = Script::from_bytes(&script.as_bytes()[1..]);
+        let all_data = data
+            .instructions()
+            .all(|instruction| matches!(instruction, Ok(Instruction::PushBytes(_))));
+        all_data.then(|| OpReturnData { instructions: data.instructions() })
+    }
+
+    /// Returns the total length of the pushed data, excluding the push opcodes.
+    pub fn total_data_len(&self) -> usize { self.clone().map(PushBytes::len).sum() }
+
+    /// Returns the pushed data if the script contains exactly one push.
+    pub fn single(&self) -> Option<&'a [u8]> {
+        let mut iter = self.clone();
+        match (iter.next(), iter.next()) {
+            (Some(data), None) => Some(data.as_bytes()),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for OpReturnData<'a> {
+    type Item = &'a PushBytes;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.instructions.next()? {
+            Ok(Instruction::PushBytes(bytes)) => Some(bytes),
+            _ => unreachable!("validated in OpReturnData::from_script"),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every push is at least one byte long.
+        let len = self.instructions.as_script().len();
+        (len.min(1), Some(len))
+    }
+}
+
+impl core::iter::FusedIterator for OpReturnData<'_> {}
+
+/// Checks whether `script` is an `OP_RETURN` script that Bitcoin Core relays.
+///
+/// The script must start with `OP_RETURN`, be followed only by push opcodes (including the small
+/// number opcodes), and be at most `max_len` bytes long.
+pub(super) fn is_standard(script: &Script, max_len: usize) -> bool {
+    script.is_op_return()
+        && script.len() <= max_len
+        && Script::from_bytes(&script.as_bytes()[1..]).instructions().all(|instruction| {
+            match instruction {
+                Ok(Instruction::PushBytes(_)) => true,
+                // `IsPushOnly` in Bitcoin Core treats everything up to `OP_16` as a push.
+                Ok(Instruction::Op(op)) => op.to_u8() <= OP_PUSHNUM_16.to_u8(),
+                Err(_) => false,
+            }
+        })
+}
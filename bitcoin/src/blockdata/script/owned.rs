@@ -6,7 +6,11 @@
 use hex::FromHex;
 use internals::ToU64 as _;
 
-use super::{opcode_to_verify, Builder, Instruction, PushBytes, ScriptExtPriv as _};
+use super::{
+    opcode_to_verify, Builder, Instruction, PushBytes, PushSizeError, Script, ScriptExt as _,
+    ScriptExtPriv as _,
+};
+use crate::constants::MAX_STACK_ELEMENT_SIZE;
 use crate::opcodes::all::*;
 use crate::opcodes::{self, Opcode};
 use crate::prelude::Vec;
@@ -26,6 +30,13 @@ fn new_op_return<T: AsRef<PushBytes>>(data: T) -> Self {
             Builder::new().push_opcode(OP_RETURN).push_slice(data).into_script()
         }
 
+        /// Generates OP_RETURN-type of scriptPubkey pushing each of the given data elements in order.
+        fn new_op_return_multi<T: AsRef<PushBytes>>(data: &[T]) -> Self {
+            data.iter()
+                .fold(Builder::new().push_opcode(OP_RETURN), |builder, data| builder.push_slice(data))
+                .into_script()
+        }
+
         /// Constructs a new [`ScriptBuf`] from a hex string.
         fn from_hex(s: &str) -> Result<ScriptBuf, hex::HexToBytesError> {
             let v = Vec::from_hex(s)?;
@@ -36,14 +47,38 @@ fn from_hex(s: &str) -> Result<ScriptBuf, hex::HexToBytesError> {
         fn push_opcode(&mut self, data: Opcode) { self.as_byte_vec().push(data.to_u8()); }
 
         /// Adds instructions to push some arbitrary data onto the stack.
+        ///
+        /// The size of `data` is not checked against [`MAX_STACK_ELEMENT_SIZE`], use
+        /// [`ScriptBufExt::try_push_slice`] for data of untrusted length.
+        ///
+        /// [`MAX_STACK_ELEMENT_SIZE`]: crate::constants::MAX_STACK_ELEMENT_SIZE
         fn push_slice<T: AsRef<PushBytes>>(&mut self, data: T) {
             let data = data.as_ref();
             self.reserve(ScriptBuf::reserved_len_for_slice(data.len()));
             self.push_slice_no_opt(data);
         }
 
+        /// Adds instructions to push some arbitrary data onto the stack.
+        ///
+        /// # Errors
+        ///
+        /// If `data` is longer than [`MAX_STACK_ELEMENT_SIZE`] bytes, the script is left unchanged.
+        ///
+        /// [`MAX_STACK_ELEMENT_SIZE`]: crate::constants::MAX_STACK_ELEMENT_SIZE
+        fn try_push_slice<T: AsRef<[u8]>>(&mut self, data: T) -> Result<(), PushSizeError> {
+            let data = data.as_ref();
+            if data.len() > MAX_STACK_ELEMENT_SIZE {
+                return Err(PushSizeError { size: data.len() });
+            }
+            let data = <&PushBytes>::try_from(data).expect("520 bytes is below the push limit");
+            self.push_slice(data);
+            Ok(())
+        }
+
         /// Add a single instruction to the script.
         ///
+        /// Data pushes are re-encoded using the smallest push opcode for their length.
+        ///
         /// # Panics
         ///
         /// The method panics if the instruction is a data push with length greater or equal to
@@ -63,6 +98,41 @@ fn push_instruction_no_opt(&mut self, instruction: Instruction<'_>) {
             }
         }
 
+        /// Appends the bytes of `script` to this script.
+        ///
+        /// The bytes are copied verbatim, reserving the required capacity up front.
+        fn extend_from_script(&mut self, script: &Script) {
+            self.as_byte_vec().extend_from_slice(script.as_bytes());
+        }
+
+        /// Returns a copy of this script with every push encoded minimally.
+        ///
+        /// Data pushes use the smallest push opcode, and the single byte pushes `0x01` - `0x10`
+        /// and `0x81` are replaced by `OP_PUSHNUM_1` - `OP_PUSHNUM_16` and `OP_PUSHNUM_NEG1`
+        /// respectively. The pushed values and all other opcodes are left unchanged. If the script
+        /// contains a malformed push it is copied as-is, along with everything following it.
+        fn normalize_pushes(&self) -> ScriptBuf {
+            let mut script = ScriptBuf::with_capacity(self.len());
+            let mut instructions = self.instructions();
+            loop {
+                let remaining = instructions.as_script();
+                match instructions.next() {
+                    Some(Ok(Instruction::PushBytes(bytes))) => match bytes.as_bytes() {
+                        [n @ 1..=16] => script.push_opcode(Opcode::from(OP_PUSHNUM_1.to_u8() + n - 1)),
+                        [0x81] => script.push_opcode(OP_PUSHNUM_NEG1),
+                        _ => script.push_slice(bytes),
+                    },
+                    Some(Ok(Instruction::Op(op))) => script.push_opcode(op),
+                    Some(Err(_)) => {
+                        script.as_byte_vec().extend_from_slice(remaining.as_bytes());
+                        break;
+                    }
+                    None => break,
+                }
+            }
+            script
+        }
+
         /// Adds an `OP_VERIFY` to the script or replaces the last opcode with VERIFY form.
         ///
         /// Some opcodes such as `OP_CHECKSIG` have a verify variant that works as if `VERIFY` was
@@ -2,6 +2,8 @@
 
 //! Contains `PushBytes` & co
 
+use core::convert::Infallible;
+use core::fmt;
 use core::ops::{Deref, DerefMut};
 
 use crate::prelude::{Borrow, BorrowMut};
@@ -144,8 +146,9 @@ macro_rules! from_array {
             $(
                 impl<'a> From<&'a [u8; $len]> for &'a PushBytes {
                     fn from(bytes: &'a [u8; $len]) -> Self {
-                        // Check that the macro wasn't called with a wrong number.
-                        const _: () = [(); 1][($len >= 0x100000000u64) as usize];
+                        // Check that the macro wasn't called with a number larger than the
+                        // maximum stack element size.
+                        const _: () = [(); crate::constants::MAX_STACK_ELEMENT_SIZE + 1][$len];
                         PushBytes::from_slice_unchecked(bytes)
                     }
                 }
@@ -310,30 +313,7 @@ pub fn is_empty(&self) -> bool { self.as_bytes().is_empty() }
     ///
     /// This code is based on the `CScriptNum` constructor in Bitcoin Core (see `script.h`).
     pub fn read_scriptint(&self) -> Result<i64, script::Error> {
-        let last = match self.as_bytes().last() {
-            Some(last) => last,
-            None => return Ok(0),
-        };
-        if self.len() > 4 {
-            return Err(script::Error::NumericOverflow);
-        }
-        // Comment and code copied from Bitcoin Core:
-        // https://github.com/bitcoin/bitcoin/blob/447f50e4aed9a8b1d80e1891cda85801aeb80b4e/src/script/script.h#L247-L262
-        // If the most-significant-byte - excluding the sign bit - is zero
-        // then we're not minimal. Note how this test also rejects the
-        // negative-zero encoding, 0x80.
-        if (*last & 0x7f) == 0 {
-            // One exception: if there's more than one byte and the most
-            // significant bit of the second-most-significant-byte is set
-            // it would conflict with the sign bit. An example of this case
-            // is +-255, which encode to 0xff00 and 0xff80 respectively.
-            // (big-endian).
-            if self.len() <= 1 || (self[self.len() - 2] & 0x80) == 0 {
-                return Err(script::Error::NonMinimalPush);
-            }
-        }
-
-        Ok(script::scriptint_parse(self.as_bytes()))
+        script::read_scriptint(self.as_bytes(), 4, true)
     }
 }
 
@@ -461,3 +441,39 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 impl std::error::Error for PushBytesError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { None }
 }
+
+/// Error returned on attempt to push data larger than [`MAX_STACK_ELEMENT_SIZE`].
+///
+/// Such pushes can be encoded but make the script fail when executed. Unlike [`PushBytesError`]
+/// the data is a valid [`PushBytes`].
+///
+/// [`MAX_STACK_ELEMENT_SIZE`]: crate::constants::MAX_STACK_ELEMENT_SIZE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushSizeError {
+    /// Invalid push size (cannot exceed 520 bytes).
+    pub(crate) size: usize,
+}
+
+impl PushSizeError {
+    /// Returns the invalid push size.
+    pub fn invalid_size(&self) -> usize { self.size }
+}
+
+impl From<Infallible> for PushSizeError {
+    #[inline]
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for PushSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "data push size exceeds {} bytes: {}",
+            crate::constants::MAX_STACK_ELEMENT_SIZE,
+            self.size
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PushSizeError {}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Classification of scripts by template.
+//!
+//! This module contains the byte level template matching for all the standard output types.
+//! Both [`ScriptType`] and the individual `Script::is_*` predicates are implemented on top of the
+//! matchers defined here so that they always agree.
+
+use internals::array::ArrayExt as _;
+
+use super::multisig::Multisig;
+use super::witness_program::P2A_PROGRAM;
+use super::witness_version::WitnessVersion;
+use super::{op_return, Script, ScriptExt as _, ScriptHash, WScriptHash};
+use crate::crypto::key::{PubkeyHash, PublicKey, WPubkeyHash};
+use crate::opcodes::all::*;
+
+/// The type of a script, along with the data parsed from it.
+///
+/// Obtained by calling `Script::classify`. The templates follow `Solver` in Bitcoin Core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScriptType<'a> {
+    /// Pay to public key, `<pubkey> OP_CHECKSIG`.
+    ///
+    /// Scripts with an invalid public key are [`ScriptType::NonStandard`].
+    P2pk(PublicKey),
+    /// Pay to public key hash, `OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG`.
+    P2pkh(PubkeyHash),
+    /// Pay to script hash, `OP_HASH160 <hash> OP_EQUAL`.
+    P2sh(ScriptHash),
+    /// Pay to witness public key hash, `OP_0 <20 byte hash>`.
+    P2wpkh(WPubkeyHash),
+    /// Pay to witness script hash, `OP_0 <32 byte hash>`.
+    P2wsh(WScriptHash),
+    /// Pay to taproot, `OP_1 <32 byte output key>`.
+    ///
+    /// The output key is not validated.
+    P2tr([u8; 32]),
+    /// Pay to anchor, `OP_1 <0x4e73>`.
+    P2a,
+    /// Bare multisig, `<m> <pubkey>... <n> OP_CHECKMULTISIG`.
+    Multisig(Multisig<'a>),
+    /// Null data, `OP_RETURN` followed only by pushes.
+    OpReturn,
+    /// A witness program not (yet) defined by a soft fork.
+    WitnessUnknown {
+        /// The witness version.
+        version: WitnessVersion,
+        /// The witness program.
+        program: &'a [u8],
+    },
+    /// Anything else.
+    NonStandard,
+}
+
+impl<'a> ScriptType<'a> {
+    /// Classifies `script`.
+    pub fn from_script(script: &'a Script) -> Self {
+        if let Some(hash) = p2sh_hash(script) {
+            return ScriptType::P2sh(hash);
+        }
+        if let Some(version) = script.witness_version() {
+            let program = &script.as_bytes()[2..];
+            return match (version, program.len()) {
+                (WitnessVersion::V0, 20) => ScriptType::P2wpkh(WPubkeyHash::from_byte_array(
+                    program.try_into().expect("checked length"),
+                )),
+                (WitnessVersion::V0, 32) => ScriptType::P2wsh(WScriptHash::from_byte_array(
+                    program.try_into().expect("checked length"),
+                )),
+                (WitnessVersion::V0, _) => ScriptType::NonStandard,
+                (WitnessVersion::V1, 32) =>
+                    ScriptType::P2tr(program.try_into().expect("checked length")),
+                (WitnessVersion::V1, _) if program == P2A_PROGRAM => ScriptType::P2a,
+                (version, _) => ScriptType::WitnessUnknown { version, program },
+            };
+        }
+        if op_return::is_standard(script, usize::MAX) {
+            return ScriptType::OpReturn;
+        }
+        if let Some(bytes) = p2pk_pubkey_bytes(script) {
+            return match PublicKey::from_slice(bytes) {
+                Ok(key) => ScriptType::P2pk(key),
+                Err(_) => ScriptType::NonStandard,
+            };
+        }
+        if let Some(hash) = p2pkh_hash(script) {
+            return ScriptType::P2pkh(hash);
+        }
+        match Multisig::from_script(script) {
+            Some(multisig) if !multisig.is_verify() => ScriptType::Multisig(multisig),
+            _ => ScriptType::NonStandard,
+        }
+    }
+
+    /// Returns `true` if this is one of the types relayed by Bitcoin Core by default.
+    ///
+    /// Bare multisig is only standard with at most three keys and `OP_RETURN` outputs are subject
+    /// to a size limit, neither of which is checked here.
+    pub fn is_standard(&self) -> bool {
+        !matches!(self, ScriptType::WitnessUnknown { .. } | ScriptType::NonStandard)
+    }
+}
+
+/// Returns the script hash if `script` is P2SH.
+pub(crate) fn p2sh_hash(script: &Script) -> Option<ScriptHash> {
+    let bytes = <&[u8; 23]>::try_from(script.as_bytes()).ok()?;
+    (bytes[0] == OP_HASH160.to_u8()
+        && bytes[1] == OP_PUSHBYTES_20.to_u8()
+        && bytes[22] == OP_EQUAL.to_u8())
+    .then(|| ScriptHash::from_byte_array(*bytes.sub_array::<2, 20>()))
+}
+
+/// Returns the public key hash if `script` is P2PKH.
+pub(super) fn p2pkh_hash(script: &Script) -> Option<PubkeyHash> {
+    let bytes = <&[u8; 25]>::try_from(script.as_bytes()).ok()?;
+    (bytes[0] == OP_DUP.to_u8()
+        && bytes[1] == OP_HASH160.to_u8()
+        && bytes[2] == OP_PUSHBYTES_20.to_u8()
+        && bytes[23] == OP_EQUALVERIFY.to_u8()
+        && bytes[24] == OP_CHECKSIG.to_u8())
+    .then(|| PubkeyHash::from_byte_array(*bytes.sub_array::<3, 20>()))
+}
+
+/// Returns the witness program if `script` is a witness program of `version` and `len` bytes.
+pub(super) fn witness_program_of(
+    script: &Script,
+    version: WitnessVersion,
+    len: usize,
+) -> Option<&[u8]> {
+    (script.len() == len + 2 && script.witness_version() == Some(version))
+        .then(|| &script.as_bytes()[2..])
+}
+
+/// Returns the bytes of the (possibly invalid) public key if `script` is P2PK.
+pub(crate) fn p2pk_pubkey_bytes(script: &Script) -> Option<&[u8]> {
+    if let Ok(bytes) = <&[u8; 67]>::try_from(script.as_bytes()) {
+        let (&first, bytes) = bytes.split_first::<66>();
+        let (&last, pubkey) = bytes.split_last::<65>();
+        (first == OP_PUSHBYTES_65.to_u8() && last == OP_CHECKSIG.to_u8()).then_some(pubkey)
+    } else if let Ok(bytes) = <&[u8; 35]>::try_from(script.as_bytes()) {
+        let (&first, bytes) = bytes.split_first::<34>();
+        let (&last, pubkey) = bytes.split_last::<33>();
+        (first == OP_PUSHBYTES_33.to_u8() && last == OP_CHECKSIG.to_u8()).then_some(pubkey)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Opcode level script templates.
+//!
+//! A template is a sequence of [`InstructionTemplate`]s which is matched against the instructions
+//! of a script, one for one. This allows describing script types declaratively, for example a bare
+//! 2-of-3 multisig is
+//!
+//! ```
+//! use bitcoin::opcodes::all::*;
+//! use bitcoin::script::template::InstructionTemplate::{self, *};
+//!
+//! const MULTISIG_2_OF_3: &[InstructionTemplate] = &[
+//!     Op(OP_PUSHNUM_2),
+//!     PushLen(33),
+//!     PushLen(33),
+//!     PushLen(33),
+//!     Op(OP_PUSHNUM_3),
+//!     Op(OP_CHECKMULTISIG),
+//! ];
+//! ```
+
+use super::{Instruction, Script, ScriptExt as _};
+use crate::opcodes::Opcode;
+
+/// A single element of a script template.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InstructionTemplate {
+    /// Matches exactly the given opcode.
+    ///
+    /// Note that data push opcodes (including `OP_0`) never match this variant since the
+    /// instruction iterator decodes them as pushes, use [`InstructionTemplate::PushLen`] instead.
+    Op(Opcode),
+    /// Matches a data push of exactly the given number of bytes, regardless of the push opcode.
+    PushLen(usize),
+    /// Matches any data push.
+    AnyPush,
+}
+
+impl InstructionTemplate {
+    /// Returns `true` if `instruction` matches this template element.
+    pub fn matches(&self, instruction: Instruction<'_>) -> bool {
+        match (*self, instruction) {
+            (InstructionTemplate::Op(expected), Instruction::Op(op)) => op == expected,
+            (InstructionTemplate::PushLen(len), Instruction::PushBytes(bytes)) =>
+                bytes.len() == len,
+            (InstructionTemplate::AnyPush, Instruction::PushBytes(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Returns `true` if the instructions of `script` match `pattern` one for one.
+///
+/// A script containing a malformed push never matches.
+pub(super) fn matches(script: &Script, pattern: &[InstructionTemplate]) -> bool {
+    let mut instructions = script.instructions();
+    for template in pattern {
+        match instructions.next() {
+            Some(Ok(instruction)) if template.matches(instruction) => {}
+            _ => return false,
+        }
+    }
+    instructions.next().is_none()
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: CC0-1.0
 
+use hex::FromHex as _;
 use hex_lit::hex;
 
 use super::*;
@@ -8,7 +9,8 @@
 };
 use crate::consensus::encode::{deserialize, serialize};
 use crate::crypto::key::{PublicKey, XOnlyPublicKey};
-use crate::{opcodes, Amount, FeeRate};
+use crate::locktime::{absolute, relative};
+use crate::{opcodes, Amount, FeeRate, Sequence};
 
 #[test]
 #[rustfmt::skip]
@@ -273,6 +275,354 @@ fn script_builder_verify() {
     assert_eq!(trick_slice2.to_hex_string(), "01ae69");
 }
 
+#[test]
+fn script_witness_program() {
+    use crate::script::witness_program::WitnessProgram;
+    use crate::script::witness_version::WitnessVersion;
+
+    for version in 0..=16 {
+        let version = WitnessVersion::try_from(version).unwrap();
+        for len in [1, 2, 20, 32, 40, 41] {
+            let program = [0xcd; 41];
+            let push = <&PushBytes>::try_from(&program[..len]).unwrap();
+            let script = ScriptBuf::new_witness_program_unchecked(version, push);
+            let expected = WitnessProgram::new_consensus(version, &program[..len]).ok();
+            assert_eq!(script.witness_program(), expected, "{} {}", version, len);
+            assert_eq!(script.witness_version().is_some(), (2..=40).contains(&len));
+        }
+    }
+
+    // The program must be pushed with the exact size push opcode.
+    let non_minimal = ScriptBuf::from_hex("524c020000").unwrap();
+    assert_eq!(non_minimal.witness_program(), None);
+    let trailing = ScriptBuf::from_hex("5202000051").unwrap();
+    assert_eq!(trailing.witness_program(), None);
+    let not_a_version = ScriptBuf::from_hex("4f020000").unwrap();
+    assert_eq!(not_a_version.witness_program(), None);
+    let v2 = ScriptBuf::from_hex("52020000").unwrap();
+    assert_eq!(v2.witness_program(), WitnessProgram::new(WitnessVersion::V2, &[0, 0]).ok());
+}
+
+#[test]
+fn script_template_matching() {
+    use crate::script::template::InstructionTemplate::{self, *};
+
+    const MULTISIG_2_OF_3: &[InstructionTemplate] = &[
+        Op(OP_PUSHNUM_2),
+        PushLen(33),
+        PushLen(33),
+        PushLen(33),
+        Op(OP_PUSHNUM_3),
+        Op(OP_CHECKMULTISIG),
+    ];
+
+    let key: &[u8] = &[0x02; 33];
+    let multisig = |m: Opcode, keys: &[&[u8]], n: Opcode, op: Opcode| {
+        let builder = Builder::new().push_opcode(m);
+        let builder = keys.iter().fold(builder, |builder, key| {
+            builder.push_slice(<&PushBytes>::try_from(*key).unwrap())
+        });
+        builder.push_opcode(n).push_opcode(op).into_script()
+    };
+
+    let script = multisig(OP_PUSHNUM_2, &[key; 3], OP_PUSHNUM_3, OP_CHECKMULTISIG);
+    assert!(script.matches(MULTISIG_2_OF_3));
+    assert!(script.is_multisig());
+
+    // Near misses.
+    let wrong_m = multisig(OP_PUSHNUM_1, &[key; 3], OP_PUSHNUM_3, OP_CHECKMULTISIG);
+    assert!(!wrong_m.matches(MULTISIG_2_OF_3));
+    let wrong_op = multisig(OP_PUSHNUM_2, &[key; 3], OP_PUSHNUM_3, OP_CHECKMULTISIGVERIFY);
+    assert!(!wrong_op.matches(MULTISIG_2_OF_3));
+    let short_key = multisig(OP_PUSHNUM_2, &[key, &key[..32], key], OP_PUSHNUM_3, OP_CHECKMULTISIG);
+    assert!(!short_key.matches(MULTISIG_2_OF_3));
+    let two_keys = multisig(OP_PUSHNUM_2, &[key; 2], OP_PUSHNUM_3, OP_CHECKMULTISIG);
+    assert!(!two_keys.matches(MULTISIG_2_OF_3));
+    let mut trailing = script.clone();
+    trailing.push_opcode(OP_NOP);
+    assert!(!trailing.matches(MULTISIG_2_OF_3));
+    assert!(!Script::new().matches(MULTISIG_2_OF_3));
+    assert!(Script::new().matches(&[]));
+
+    // The push opcode used does not matter, only the length of the data.
+    let mut pushdata1 = vec![OP_PUSHNUM_2.to_u8()];
+    for _ in 0..3 {
+        pushdata1.extend_from_slice(&[OP_PUSHDATA1.to_u8(), 33]);
+        pushdata1.extend_from_slice(key);
+    }
+    pushdata1.extend_from_slice(&[OP_PUSHNUM_3.to_u8(), OP_CHECKMULTISIG.to_u8()]);
+    let pushdata1 = ScriptBuf::from_bytes(pushdata1);
+    assert!(pushdata1.matches(MULTISIG_2_OF_3));
+
+    // `OP_0` is an empty push and `OP_PUSHNUM_1` is not a push.
+    let script = ScriptBuf::from_hex("0051").unwrap();
+    assert!(script.matches(&[PushLen(0), Op(OP_PUSHNUM_1)]));
+    assert!(script.matches(&[AnyPush, Op(OP_PUSHNUM_1)]));
+    assert!(!script.matches(&[Op(OP_PUSHBYTES_0), Op(OP_PUSHNUM_1)]));
+    assert!(!script.matches(&[AnyPush, AnyPush]));
+
+    // Malformed pushes never match.
+    let malformed = ScriptBuf::from_hex("5104").unwrap();
+    assert!(!malformed.matches(&[Op(OP_PUSHNUM_1), AnyPush]));
+    assert!(!malformed.matches(&[Op(OP_PUSHNUM_1)]));
+}
+
+#[test]
+fn script_builder_push_int_scriptnum() {
+    // Serializations as produced by `CScriptNum::serialize` in Bitcoin Core.
+    let vectors: &[(i64, &str)] = &[
+        (0, ""),
+        (1, "01"),
+        (-1, "81"),
+        (16, "10"),
+        (17, "11"),
+        (127, "7f"),
+        (-127, "ff"),
+        (128, "8000"),
+        (-128, "8080"),
+        (255, "ff00"),
+        (-255, "ff80"),
+        (256, "0001"),
+        (-256, "0081"),
+        (32767, "ff7f"),
+        (-32767, "ffff"),
+        (32768, "008000"),
+        (-32768, "008080"),
+        (65535, "ffff00"),
+        (8388607, "ffff7f"),
+        (8388608, "00008000"),
+        (-8388608, "00008080"),
+        (2147483647, "ffffff7f"),
+        (-2147483647, "ffffffff"),
+        (2147483648, "0000008000"),
+        (-2147483648, "0000008080"),
+        (4294967295, "ffffffff00"),
+    ];
+
+    for (n, data) in vectors {
+        let data = Vec::<u8>::from_hex(data).unwrap();
+        let push = <&PushBytes>::try_from(data.as_slice()).unwrap();
+        let expected = Builder::new().push_slice(push).into_script();
+        assert_eq!(Builder::new().push_int_non_minimal(*n).into_script(), expected, "{}", n);
+
+        let minimal = Builder::new().push_int_unchecked(*n).into_script();
+        match n {
+            0 => assert_eq!(minimal.as_bytes(), [OP_PUSHBYTES_0.to_u8()]),
+            -1 => assert_eq!(minimal.as_bytes(), [OP_PUSHNUM_NEG1.to_u8()]),
+            1..=16 => assert_eq!(minimal.as_bytes(), [OP_PUSHNUM_1.to_u8() + *n as u8 - 1]),
+            _ => assert_eq!(minimal, expected, "{}", n),
+        }
+        assert!(minimal.has_minimal_pushes());
+        if let Ok(n) = i32::try_from(*n) {
+            if n != i32::MIN {
+                assert_eq!(Builder::new().push_int(n).unwrap().into_script(), minimal);
+            }
+        }
+    }
+    assert!(Builder::new().push_int(i32::MIN).is_err());
+
+    // Non-minimal pushes of small numbers are not minimal.
+    let script = Builder::new().push_int_non_minimal(1).into_script();
+    assert_eq!(script.as_bytes(), [0x01, 0x01]);
+    assert!(!script.has_minimal_pushes());
+    assert!(Builder::new().push_int_non_minimal(17).into_script().has_minimal_pushes());
+}
+
+#[test]
+fn script_builder_try_push_slice() {
+    let data = [0xab; 520];
+    let script = Builder::new().try_push_slice(data).unwrap().into_script();
+    let expected = Builder::new().push_slice(<&PushBytes>::try_from(&data[..]).unwrap());
+    assert_eq!(script, expected.into_script());
+
+    let data = vec![0xab; 600];
+    let err = Builder::new().push_opcode(OP_RETURN).try_push_slice(&data).unwrap_err();
+    assert_eq!(err.invalid_size(), 600);
+
+    let mut script = ScriptBuf::new_op_return([0x01]);
+    let before = script.clone();
+    assert!(script.try_push_slice(&data).is_err());
+    assert_eq!(script, before);
+    script.try_push_slice(&data[..10]).unwrap();
+    assert_eq!(script.len(), before.len() + 11);
+}
+
+#[test]
+fn push_bytes_from_array() {
+    let hash = [0xab; 20];
+    let key = [0x02; 33];
+    let push: &PushBytes = (&hash).into();
+    assert_eq!(push.as_bytes(), &hash);
+
+    let script = Builder::new().push_slice(hash).push_slice([0xcd; 32]).push_slice(key);
+    let mut expected = vec![20];
+    expected.extend_from_slice(&hash);
+    expected.push(32);
+    expected.extend_from_slice(&[0xcd; 32]);
+    expected.push(33);
+    expected.extend_from_slice(&key);
+    assert_eq!(script.as_bytes(), expected);
+
+    let mut script = ScriptBuf::new();
+    script.try_push_slice(key).unwrap();
+    assert_eq!(script.as_bytes(), &expected[54..]);
+}
+
+#[test]
+fn script_builder_with_capacity() {
+    let key = [0x02; 33];
+    let build = |builder: Builder| {
+        (0..100)
+            .fold(builder, |builder, i| builder.push_int_unchecked(i).push_slice(key))
+            .push_opcode(OP_CHECKMULTISIG)
+    };
+
+    let expected = build(Builder::new()).into_script();
+    let with_capacity = build(Builder::with_capacity(expected.len()));
+    assert_eq!(with_capacity.as_bytes(), expected.as_bytes());
+    let script = with_capacity.into_script();
+    assert_eq!(script, expected);
+    assert!(script.capacity() >= expected.len());
+
+    // `into_script` keeps unused capacity.
+    let script = Builder::with_capacity(1000).push_opcode(OP_NOP).into_script();
+    assert_eq!(script.as_bytes(), [OP_NOP.to_u8()]);
+    assert!(script.capacity() >= 1000);
+
+    // Rebuilding instruction by instruction into a pre-allocated script.
+    let mut script = ScriptBuf::with_capacity(expected.len());
+    for instruction in expected.instructions() {
+        script.push_instruction(instruction.unwrap());
+    }
+    assert_eq!(script, expected);
+
+    let mut script = ScriptBuf::new();
+    script.reserve(expected.len());
+    script.extend_from_script(&expected[..expected.len() / 2]);
+    script.extend_from_script(&expected[expected.len() / 2..]);
+    assert_eq!(script, expected);
+
+    // Non-minimal pushes are re-encoded by `push_instruction` but copied by `extend_from_script`.
+    let non_minimal = ScriptBuf::from_hex("4c0101").unwrap();
+    let mut script = ScriptBuf::new();
+    script.push_instruction(non_minimal.instructions().next().unwrap().unwrap());
+    assert_eq!(script.as_bytes(), [0x01, 0x01]);
+    let mut script = ScriptBuf::new();
+    script.extend_from_script(&non_minimal);
+    assert_eq!(script, non_minimal);
+}
+
+#[test]
+fn script_hash_size_limits() {
+    use hashes::{hash160, sha256};
+
+    use crate::script::{MAX_REDEEM_SCRIPT_SIZE, MAX_WITNESS_SCRIPT_SIZE};
+    use crate::{Address, Network};
+
+    // The hashes of the scripts computed directly from their bytes.
+    let script_hash = |script: &Script| hash160::Hash::hash(script.as_bytes()).to_byte_array();
+    let wscript_hash = |script: &Script| sha256::Hash::hash(script.as_bytes()).to_byte_array();
+
+    let redeem_script = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_REDEEM_SCRIPT_SIZE]);
+    let hash = redeem_script.script_hash().unwrap();
+    assert_eq!(hash.to_byte_array(), script_hash(&redeem_script));
+    assert_eq!(hash, redeem_script.script_hash_unchecked());
+    assert!(Address::p2sh(&redeem_script, Network::Bitcoin).is_ok());
+
+    let redeem_script = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_REDEEM_SCRIPT_SIZE + 1]);
+    let err = redeem_script.script_hash().unwrap_err();
+    assert_eq!(err.invalid_size(), MAX_REDEEM_SCRIPT_SIZE + 1);
+    assert_eq!(Address::p2sh(&redeem_script, Network::Bitcoin).unwrap_err(), err);
+    assert_eq!(redeem_script.script_hash_unchecked().to_byte_array(), script_hash(&redeem_script));
+
+    let witness_script = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_WITNESS_SCRIPT_SIZE]);
+    let hash = witness_script.wscript_hash().unwrap();
+    assert_eq!(hash.to_byte_array(), wscript_hash(&witness_script));
+    assert_eq!(hash, witness_script.wscript_hash_unchecked());
+    assert!(Address::p2wsh(&witness_script, Network::Bitcoin).is_ok());
+
+    let witness_script = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_WITNESS_SCRIPT_SIZE + 1]);
+    let err = witness_script.wscript_hash().unwrap_err();
+    assert_eq!(err.invalid_size(), MAX_WITNESS_SCRIPT_SIZE + 1);
+    assert_eq!(Address::p2wsh(&witness_script, Network::Bitcoin).unwrap_err(), err);
+    assert_eq!(Address::p2shwsh(&witness_script, Network::Bitcoin).unwrap_err(), err);
+    assert_eq!(
+        witness_script.wscript_hash_unchecked().to_byte_array(),
+        wscript_hash(&witness_script)
+    );
+}
+
+#[test]
+fn script_consensus_size() {
+    // Lengths on both sides of every compact size boundary.
+    for len in [0, 1, 75, 252, 253, 254, 0xffff, 0x1_0000] {
+        let script = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); len]);
+        assert_eq!(script.consensus_size(), serialize(&script).len(), "length {}", len);
+    }
+    assert_eq!(ScriptBuf::new().consensus_size(), 1);
+    assert_eq!(ScriptBuf::from_bytes(vec![0; 253]).consensus_size(), 256);
+}
+
+#[test]
+fn script_builder_opcode_verify() {
+    let checksig = Builder::new().push_opcode_verify(OP_CHECKSIG).into_script();
+    assert_eq!(checksig.to_hex_string(), "ad");
+    let csv = Builder::new().push_opcode_verify(OP_CSV).into_script();
+    assert_eq!(csv.to_hex_string(), "b269");
+
+    // Once folded, extending the builder appends a plain `OP_VERIFY`.
+    let twice = Builder::new().push_opcode_verify(OP_EQUAL).push_verify().into_script();
+    assert_eq!(twice.to_hex_string(), "8869");
+    let after_push =
+        Builder::new().push_opcode(OP_CHECKSIG).push_int_unchecked(1).push_verify().into_script();
+    assert_eq!(after_push.to_hex_string(), "ac5169");
+    let after_slice = Builder::new().push_opcode_verify(OP_EQUAL).push_slice([0x87]).push_verify();
+    assert_eq!(after_slice.into_script().to_hex_string(), "88018769");
+}
+
+#[test]
+fn script_builder_timelocks() {
+    fn csv(sequence: Sequence) -> String {
+        Builder::new().push_sequence(sequence).push_opcode(OP_CSV).into_script().to_hex_string()
+    }
+
+    // Zero is pushed as the empty vector.
+    assert_eq!(csv(Sequence::ZERO), "00b2");
+    assert_eq!(csv(Sequence::from_consensus(16)), "60b2");
+    assert_eq!(csv(Sequence::from_consensus(17)), "0111b2");
+    // Values with the high bit of the most significant byte set need an extra byte
+    // so they are not decoded as negative numbers.
+    assert_eq!(csv(Sequence::from_consensus(0x7f)), "017fb2");
+    assert_eq!(csv(Sequence::from_consensus(0x80)), "028000b2");
+    assert_eq!(csv(Sequence::from_consensus(0x81)), "028100b2");
+    assert_eq!(csv(Sequence::from_height(144)), "029000b2");
+    assert_eq!(csv(Sequence::from_512_second_intervals(1)), "03010040b2");
+    assert_eq!(csv(Sequence::MAX), "05ffffffff00b2");
+    // -1 has both a dedicated opcode and a single byte encoding.
+    assert_eq!(Builder::new().push_int_unchecked(-1).into_script().to_hex_string(), "4f");
+    assert_eq!(Builder::new().push_int_non_minimal(-1).into_script().to_hex_string(), "0181");
+
+    let relative = relative::LockTime::from_height(144);
+    let script = Builder::new().push_relative_lock_time(relative).push_opcode(OP_CSV);
+    assert_eq!(script.into_script().to_hex_string(), "029000b2");
+    let relative = relative::LockTime::from_512_second_intervals(0x80);
+    let script = Builder::new().push_relative_lock_time(relative).push_opcode(OP_CSV);
+    assert_eq!(script.into_script().to_hex_string(), "03800040b2");
+    assert_eq!(
+        Builder::new().push_relative_lock_time(relative).into_script(),
+        Builder::new().push_sequence(relative.to_sequence()).into_script(),
+    );
+
+    let cltv = |n| {
+        let lock_time = absolute::LockTime::from_consensus(n);
+        Builder::new().push_lock_time(lock_time).push_opcode(OP_CLTV).into_script().to_hex_string()
+    };
+    assert_eq!(cltv(0), "00b1");
+    assert_eq!(cltv(500_000), "0320a107b1");
+    assert_eq!(cltv(1_700_000_000), "0400f15365b1");
+    assert_eq!(cltv(0x8000_0000), "050000008000b1");
+}
+
 #[test]
 fn script_serialize() {
     let hex_script = hex!("6c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52");
@@ -337,6 +687,112 @@ fn build_scriptint(n: i64) -> Vec<u8> {
     assert!(read_scriptint_non_minimal(&build_scriptint(-(1 << 31))).is_err());
 }
 
+#[test]
+fn read_scriptint_core_vectors() {
+    // Encodings from `CScriptNum::serialize` in Bitcoin Core.
+    let vectors: &[(i64, &str)] = &[
+        (0, ""),
+        (1, "01"),
+        (-1, "81"),
+        (127, "7f"),
+        (-127, "ff"),
+        (128, "8000"),
+        (-128, "8080"),
+        (255, "ff00"),
+        (-255, "ff80"),
+        (256, "0001"),
+        (-256, "0081"),
+        (32767, "ff7f"),
+        (-32767, "ffff"),
+        (32768, "008000"),
+        (-32768, "008080"),
+        (0x7fffffff, "ffffff7f"),
+        (-0x7fffffff, "ffffffff"),
+        (0x80000000, "0000008000"),
+        (-0x80000000, "0000008080"),
+        (0xffffffff, "ffffffff00"),
+        (0x7fffffffff, "ffffffff7f"),
+        (i64::MAX, "ffffffffffffff7f"),
+        (-i64::MAX, "ffffffffffffffff"),
+    ];
+    for &(n, hex) in vectors {
+        let bytes = Vec::<u8>::from_hex(hex).unwrap();
+        let mut buf = [0u8; 8];
+        let len = write_scriptint(&mut buf, n);
+        assert_eq!(&buf[..len], &bytes[..], "{}", n);
+
+        assert_eq!(read_scriptint(&bytes, 8, true), Ok(n), "{}", n);
+        assert_eq!(read_scriptint(&bytes, 8, false), Ok(n), "{}", n);
+        if bytes.len() <= 4 {
+            assert_eq!(read_scriptint(&bytes, 4, true), Ok(n), "{}", n);
+        } else {
+            assert_eq!(read_scriptint(&bytes, 4, true), Err(Error::NumericOverflow), "{}", n);
+        }
+        if bytes.len() <= 5 {
+            assert_eq!(read_scriptint(&bytes, 5, true), Ok(n), "{}", n);
+        }
+    }
+
+    // Excess zero bytes, including negative zero, are only accepted if not requiring minimal.
+    for (hex, n) in [("00", 0), ("80", 0), ("0080", 0), ("0100", 1), ("0180", -1), ("7f00", 127)] {
+        let bytes = Vec::<u8>::from_hex(hex).unwrap();
+        assert_eq!(read_scriptint(&bytes, 4, true), Err(Error::NonMinimalPush), "{}", hex);
+        assert_eq!(read_scriptint(&bytes, 4, false), Ok(n), "{}", hex);
+    }
+    // Longer than an `i64` is always an overflow.
+    assert_eq!(read_scriptint(&[0x01; 9], usize::MAX, false), Err(Error::NumericOverflow));
+}
+
+#[test]
+fn script_last_pushdata() {
+    let script = ScriptBuf::from_hex("0001ab02abcd").unwrap();
+    assert_eq!(script.last_pushdata().unwrap().as_bytes(), [0xab, 0xcd]);
+    let script = ScriptBuf::from_hex("02abcd00").unwrap();
+    assert_eq!(script.last_pushdata(), Some(PushBytes::empty()));
+
+    // Numbers, other opcodes and malformed pushes are not pushdata.
+    assert_eq!(ScriptBuf::from_hex("02abcd51").unwrap().last_pushdata(), None);
+    assert_eq!(ScriptBuf::from_hex("02abcdac").unwrap().last_pushdata(), None);
+    assert_eq!(ScriptBuf::from_hex("02abcd02ab").unwrap().last_pushdata(), None);
+    assert_eq!(ScriptBuf::new().last_pushdata(), None);
+}
+
+#[test]
+fn script_push_data() {
+    let script = Builder::new()
+        .push_opcode(OP_PUSHBYTES_0)
+        .push_int_unchecked(-1)
+        .push_int_unchecked(16)
+        .push_opcode(OP_DUP)
+        .push_slice([0xab; 3])
+        .push_opcode(OP_CHECKSIG)
+        .push_int_unchecked(0x1234)
+        .into_script();
+
+    let pushes = script.push_data().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(pushes.len(), 5);
+    assert_eq!(pushes[0], Push::Bytes(PushBytes::empty()));
+    assert_eq!(pushes[1], Push::Num(-1));
+    assert_eq!(pushes[2], Push::Num(16));
+    assert_eq!(pushes[3].push_bytes().unwrap().as_bytes(), [0xab; 3]);
+    assert_eq!(pushes[1].push_bytes(), None);
+
+    let numbers = pushes.iter().map(|push| push.read_scriptint(4, true)).collect::<Vec<_>>();
+    // The most significant bit of the last byte is the sign.
+    assert_eq!(numbers, [Ok(0), Ok(-1), Ok(16), Ok(-0x2babab), Ok(0x1234)]);
+
+    // Non-push opcodes are skipped, `OP_RESERVED` is not a number.
+    let script = ScriptBuf::from_bytes(vec![OP_RESERVED.to_u8(), OP_NOP.to_u8()]);
+    assert_eq!(script.push_data().count(), 0);
+
+    // A malformed push ends the iteration with an error.
+    let script = ScriptBuf::from_hex("51024d").unwrap();
+    let mut pushes = script.push_data();
+    assert_eq!(pushes.next(), Some(Ok(Push::Num(1))));
+    assert_eq!(pushes.next(), Some(Err(Error::EarlyEndOfScript)));
+    assert_eq!(pushes.next(), None);
+}
+
 #[test]
 fn non_minimal_scriptints() {
     assert_eq!(
@@ -394,6 +850,34 @@ fn provably_unspendable() {
     assert!(ScriptBuf::from_hex("6aa9149eb21980dc9d413d8eac27314938b9da920ee53e87")
         .unwrap()
         .is_op_return());
+
+    let p2pkh = ScriptBuf::from_hex("76a914ee61d57ab51b9d212335b1dba62794ac20d2bcf988ac").unwrap();
+    assert!(!p2pkh.is_provably_unspendable());
+    assert!(!ScriptBuf::new().is_provably_unspendable());
+    assert!(ScriptBuf::from_bytes(vec![OP_RETURN.to_u8()]).is_provably_unspendable());
+    assert!(ScriptBuf::from_bytes(vec![OP_RESERVED.to_u8()]).is_provably_unspendable());
+
+    let oversized = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_SCRIPT_SIZE + 1]);
+    assert!(oversized.is_provably_unspendable());
+    let max_size = ScriptBuf::from_bytes(vec![OP_NOP.to_u8(); MAX_SCRIPT_SIZE]);
+    assert!(!max_size.is_provably_unspendable());
+
+    // Disabled opcodes fail even in unexecuted branches, `OP_RETURN` and `OP_INVALIDOPCODE` don't.
+    let branch = |op: Opcode| {
+        Builder::new()
+            .push_opcode(OP_PUSHBYTES_0)
+            .push_opcode(OP_IF)
+            .push_opcode(op)
+            .push_opcode(OP_ENDIF)
+            .into_script()
+    };
+    assert!(branch(OP_CAT).is_provably_unspendable());
+    assert!(branch(OP_VERIF).is_provably_unspendable());
+    assert!(!branch(OP_RETURN).is_provably_unspendable());
+    assert!(!branch(OP_INVALIDOPCODE).is_provably_unspendable());
+
+    let malformed_push = ScriptBuf::from_bytes(vec![OP_PUSHNUM_1.to_u8(), 0x02, 0x01]);
+    assert!(malformed_push.is_provably_unspendable());
 }
 
 #[test]
@@ -409,18 +893,70 @@ fn op_return() {
 
 #[test]
 fn standard_op_return() {
-    assert!(ScriptBuf::from_hex("6aa9149eb21980dc9d413d8eac27314938b9da920ee53e87")
+    // `OP_RETURN OP_HASH160 <20 bytes> OP_EQUAL` contains non-push opcodes.
+    // The text scripts start with `OP_PUSHBYTES_72`, the trailing bytes are not valid pushes.
+    assert!(!ScriptBuf::from_hex("6aa9149eb21980dc9d413d8eac27314938b9da920ee53e87")
         .unwrap()
         .is_standard_op_return());
-    assert!(ScriptBuf::from_hex("6a48656c6c6f2c2074686973206973206d7920666972737420636f6e747269627574696f6e20746f207275737420626974636f696e2e20506c6561736520617070726f7665206d79205052206672656e")
+    assert!(!ScriptBuf::from_hex("6a48656c6c6f2c2074686973206973206d7920666972737420636f6e747269627574696f6e20746f207275737420626974636f696e2e20506c6561736520617070726f7665206d79205052206672656e")
         .unwrap()
         .is_standard_op_return());
-    assert!(ScriptBuf::from_hex("6a48656c6c6f2c2074686973206973206d7920666972737420636f6e747269627574696f6e20746f207275737420626974636f696e2e20506c6561736520617070726f7665206d79205052206672656e21")
+    assert!(!ScriptBuf::from_hex("6a48656c6c6f2c2074686973206973206d7920666972737420636f6e747269627574696f6e20746f207275737420626974636f696e2e20506c6561736520617070726f7665206d79205052206672656e21")
         .unwrap()
         .is_standard_op_return());
     assert!(!ScriptBuf::from_hex("6a48656c6c6f2c2074686973206973206d7920666972737420636f6e747269627574696f6e20746f207275737420626974636f696e2e20506c6561736520617070726f7665206d79205052206672656e21524f42")
         .unwrap()
         .is_standard_op_return());
+
+    // 80 bytes of data is the default limit, 81 bytes is not standard.
+    assert!(ScriptBuf::new_op_return(<&PushBytes>::try_from(&[0xab; 80][..]).unwrap())
+        .is_standard_op_return());
+    assert!(!ScriptBuf::new_op_return(<&PushBytes>::try_from(&[0xab; 81][..]).unwrap())
+        .is_standard_op_return());
+    assert!(ScriptBuf::new_op_return(<&PushBytes>::try_from(&[0xab; 81][..]).unwrap())
+        .is_standard_op_return_with_max_len(84));
+    assert!(!ScriptBuf::new_op_return(<&PushBytes>::try_from(&[0xab; 20][..]).unwrap())
+        .is_standard_op_return_with_max_len(20));
+    // Small number opcodes are pushes, a bare OP_RETURN is standard.
+    assert!(ScriptBuf::from_hex("6a5160").unwrap().is_standard_op_return());
+    assert!(ScriptBuf::from_hex("6a").unwrap().is_standard_op_return());
+    // Malformed push.
+    assert!(!ScriptBuf::from_hex("6a05abab").unwrap().is_standard_op_return());
+    assert!(!ScriptBuf::from_hex("").unwrap().is_standard_op_return());
+}
+
+#[test]
+fn op_return_data() {
+    let empty = ScriptBuf::from_hex("6a").unwrap();
+    let data = empty.op_return_data().unwrap();
+    assert_eq!(data.clone().count(), 0);
+    assert_eq!(data.total_data_len(), 0);
+    assert_eq!(data.single(), None);
+
+    let single = ScriptBuf::new_op_return(<&PushBytes>::try_from(&[0xab; 81][..]).unwrap());
+    let data = single.op_return_data().unwrap();
+    assert_eq!(data.clone().count(), 1);
+    assert_eq!(data.total_data_len(), 81);
+    assert_eq!(data.single(), Some(&[0xab; 81][..]));
+
+    let parts: [&PushBytes; 3] =
+        [[1, 2, 3].as_ref(), PushBytes::empty(), <&PushBytes>::try_from(&[4; 100][..]).unwrap()];
+    let multi = ScriptBuf::new_op_return_multi(&parts);
+    assert_eq!(multi.to_hex_string(), format!("6a03010203004c64{}", "04".repeat(100)));
+    let data = multi.op_return_data().unwrap();
+    assert_eq!(data.clone().collect::<Vec<_>>(), parts);
+    assert_eq!(data.total_data_len(), 103);
+    assert_eq!(data.single(), None);
+    assert!(!multi.is_standard_op_return());
+    assert_eq!(ScriptBuf::new_op_return_multi::<&PushBytes>(&[]), empty);
+
+    // OP_RETURN must be the first opcode.
+    assert!(ScriptBuf::from_hex("006a").unwrap().op_return_data().is_none());
+    assert!(ScriptBuf::from_hex("").unwrap().op_return_data().is_none());
+    // Non-data opcodes and malformed pushes are rejected.
+    assert!(ScriptBuf::from_hex("6a01ab87").unwrap().op_return_data().is_none());
+    assert!(ScriptBuf::from_hex("6a51").unwrap().op_return_data().is_none());
+    assert!(ScriptBuf::from_hex("6a05abab").unwrap().op_return_data().is_none());
 }
 
 #[test]
@@ -470,6 +1006,131 @@ fn multisig() {
         .is_multisig());
 }
 
+fn multisig_test_keys(n: usize) -> Vec<PublicKey> {
+    let secp = secp256k1::Secp256k1::signing_only();
+    (1..=n as u8)
+        .map(|i| {
+            let sk = secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap();
+            PublicKey::new(secp256k1::PublicKey::from_secret_key(&secp, &sk))
+        })
+        .collect()
+}
+
+#[test]
+fn new_multisig() {
+    use multisig::MultisigError;
+
+    let keys = multisig_test_keys(21);
+
+    let script = ScriptBuf::new_multisig(2, &keys[..3]).unwrap();
+    assert!(script.is_multisig());
+    assert_eq!(script.as_bytes()[0], OP_PUSHNUM_2.to_u8());
+    assert_eq!(script.as_bytes()[script.len() - 2], OP_PUSHNUM_3.to_u8());
+    assert_eq!(script.as_bytes()[script.len() - 1], OP_CHECKMULTISIG.to_u8());
+    assert_eq!(ScriptBuf::new_bare_multisig(2, &keys[..3]).unwrap(), script);
+
+    // 15-of-20 uses a data push for 20.
+    let script = ScriptBuf::new_multisig(15, &keys[..20]).unwrap();
+    assert_eq!(script.as_bytes()[0], OP_PUSHNUM_15.to_u8());
+    assert_eq!(&script.as_bytes()[script.len() - 3..], &[0x01, 0x14, OP_CHECKMULTISIG.to_u8()]);
+
+    assert_eq!(ScriptBuf::new_multisig(0, &keys[..2]), Err(MultisigError::ZeroRequired));
+    assert_eq!(
+        ScriptBuf::new_multisig(3, &keys[..2]),
+        Err(MultisigError::TooManyRequired { required: 3, total: 2 })
+    );
+    assert_eq!(
+        ScriptBuf::new_multisig(1, &keys),
+        Err(MultisigError::TooManyKeys { total: 21, max: 20 })
+    );
+    assert_eq!(
+        ScriptBuf::new_bare_multisig(1, &keys[..4]),
+        Err(MultisigError::TooManyKeys { total: 4, max: 3 })
+    );
+}
+
+#[test]
+fn parse_multisig() {
+    let keys = multisig_test_keys(20);
+
+    for (m, n) in [(1, 1), (1, 3), (2, 3), (16, 16), (15, 20), (17, 20), (20, 20)] {
+        let script = ScriptBuf::new_multisig(m, &keys[..n]).unwrap();
+        let multisig = script.multisig().unwrap();
+        assert_eq!(multisig.required(), m);
+        assert_eq!(multisig.total(), n);
+        assert!(multisig.keys().eq(keys[..n].iter().copied()));
+        assert!(!multisig.is_verify());
+    }
+
+    // First multisig? 1-of-2, uncompressed keys.
+    // In block 164467, txid 60a20bd93aa49ab4b28d514ec10b06e1829ce6818ec06cd3aabd013ebcdc4bb1
+    let script = ScriptBuf::from_hex("514104cc71eb30d653c0c3163990c47b976f3fb3f37cccdcbedb169a1dfef58bbfbfaff7d8a473e7e2e6d317b87bafe8bde97e3cf8f065dec022b51d11fcdd0d348ac4410461cbdcc5409fb4b4d42b51d33381354d80e550078cb532a34bfa2fcfdeb7d76519aecc62770f5b0e4ef8551946d8a540911abe3e7854a26f39f58b25c15342af52ae").unwrap();
+    let multisig = script.multisig().unwrap();
+    assert_eq!((multisig.required(), multisig.total()), (1, 2));
+    assert!(multisig.keys().all(|key| !key.compressed));
+
+    // CHECKMULTISIGVERIFY variant.
+    let mut bytes = ScriptBuf::new_multisig(2, &keys[..3]).unwrap().into_bytes();
+    *bytes.last_mut().unwrap() = OP_CHECKMULTISIGVERIFY.to_u8();
+    let script = ScriptBuf::from_bytes(bytes);
+    assert!(script.multisig().unwrap().is_verify());
+
+    let valid = ScriptBuf::new_multisig(2, &keys[..3]).unwrap().into_bytes();
+    let key_len = 34;
+
+    // Invalid public key in the middle (bad prefix byte).
+    let mut bytes = valid.clone();
+    bytes[1 + key_len + 1] = 0x05;
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // Invalid public key in the middle (not on the curve).
+    let mut bytes = valid.clone();
+    bytes[1 + key_len + 2..1 + 2 * key_len].copy_from_slice(&[0xff; 32]);
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // Non-minimal `m`, pushed as data instead of OP_PUSHNUM_2.
+    let mut bytes = vec![0x01, 0x02];
+    bytes.extend_from_slice(&valid[1..]);
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // Non-minimal `n`, pushed using OP_PUSHDATA1.
+    let mut bytes = valid[..valid.len() - 2].to_vec();
+    bytes.extend_from_slice(&[OP_PUSHDATA1.to_u8(), 0x01, 0x03, OP_CHECKMULTISIG.to_u8()]);
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // Missing trailing OP_CHECKMULTISIG.
+    assert!(ScriptBuf::from_bytes(valid[..valid.len() - 1].to_vec()).multisig().is_none());
+
+    // Wrong trailing opcode.
+    let mut bytes = valid.clone();
+    *bytes.last_mut().unwrap() = OP_CHECKSIG.to_u8();
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // Extra opcode after OP_CHECKMULTISIG.
+    let mut bytes = valid.clone();
+    bytes.push(OP_PUSHNUM_1.to_u8());
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // `n` doesn't match the number of keys.
+    let mut bytes = valid.clone();
+    let n_pos = bytes.len() - 2;
+    bytes[n_pos] = OP_PUSHNUM_2.to_u8();
+    assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+
+    // `m` of zero or greater than `n`.
+    for m in [OP_PUSHBYTES_0, OP_PUSHNUM_4] {
+        let mut bytes = valid.clone();
+        bytes[0] = m.to_u8();
+        assert!(ScriptBuf::from_bytes(bytes).multisig().is_none());
+    }
+
+    assert!(ScriptBuf::new().multisig().is_none());
+    assert!(ScriptBuf::from_hex("6aa9149eb21980dc9d413d8eac27314938b9da920ee53e87")
+        .unwrap()
+        .multisig()
+        .is_none());
+}
+
 #[test]
 #[cfg(feature = "serde")]
 fn script_json_serialize() {
@@ -551,6 +1212,105 @@ fn script_p2pk() {
     assert!(ScriptBuf::from_hex("410496b538e853519c726a2c91e61ec11600ae1390813a627c66fb8be7947be63c52da7589379515d4e0a604f8141781e62294721166bf621e73a82cbf2342c858eeac").unwrap().is_p2pk());
 }
 
+#[test]
+fn script_classify() {
+    use crate::key::PubkeyHash;
+    use crate::script::script_type::ScriptType;
+    use crate::script::witness_version::WitnessVersion;
+
+    let script = |hex: &str| ScriptBuf::from_hex(hex).unwrap();
+    let pk = "021aeaf2f8638a129a3156fbe7e5ef635226b0bafd495ff03afe2c843d7e3a4b51";
+    let key = pk.parse::<PublicKey>().unwrap();
+    let hash = "acc91e6fef5c7f24e5c8b3f11a664aa8f1352ffd";
+    let hash_bytes = <[u8; 20]>::from_hex(hash).unwrap();
+    let hash32 = "b95237b48faaa69eb078e1170be3b5cbb3fddf16d0a991e14ad274f7b33a4f64";
+    let hash32_bytes = <[u8; 32]>::from_hex(hash32).unwrap();
+
+    let p2pk = script(&format!("21{}ac", pk));
+    assert_eq!(p2pk.classify(), ScriptType::P2pk(key));
+    let p2pkh = script(&format!("76a914{}88ac", hash));
+    assert_eq!(p2pkh.classify(), ScriptType::P2pkh(PubkeyHash::from_byte_array(hash_bytes)));
+    let p2sh = script(&format!("a914{}87", hash));
+    assert_eq!(p2sh.classify(), ScriptType::P2sh(ScriptHash::from_byte_array(hash_bytes)));
+    let p2wpkh = script(&format!("0014{}", hash));
+    assert_eq!(p2wpkh.classify(), ScriptType::P2wpkh(WPubkeyHash::from_byte_array(hash_bytes)));
+    let p2wsh = script(&format!("0020{}", hash32));
+    assert_eq!(p2wsh.classify(), ScriptType::P2wsh(WScriptHash::from_byte_array(hash32_bytes)));
+    let p2tr = script(&format!("5120{}", hash32));
+    assert_eq!(p2tr.classify(), ScriptType::P2tr(hash32_bytes));
+    assert_eq!(ScriptBuf::new_p2a().classify(), ScriptType::P2a);
+    let v2 = script("52020000");
+    let unknown = ScriptType::WitnessUnknown { version: WitnessVersion::V2, program: &[0, 0] };
+    assert_eq!(v2.classify(), unknown);
+    let v1 = script(&format!("5114{}", hash));
+    let program = &v1.as_bytes()[2..];
+    assert_eq!(v1.classify(), ScriptType::WitnessUnknown { version: WitnessVersion::V1, program });
+    let op_return = ScriptBuf::new_op_return([0xde, 0xad]);
+    assert_eq!(op_return.classify(), ScriptType::OpReturn);
+    assert_eq!(script("6a").classify(), ScriptType::OpReturn);
+    let multisig = ScriptBuf::new_multisig(1, &[key, key]).unwrap();
+    assert_eq!(multisig.classify(), ScriptType::Multisig(multisig.multisig().unwrap()));
+
+    for (script, script_type) in [&p2pk, &p2wpkh, &p2tr, &v2, &op_return, &multisig]
+        .iter()
+        .zip([true, true, true, false, true, true])
+    {
+        assert_eq!(script.classify().is_standard(), script_type);
+    }
+
+    // Near misses.
+    let near_misses = [
+        // P2PKH with a 21 byte hash.
+        format!("76a915{}0088ac", hash),
+        // P2PKH with a 19 byte hash.
+        format!("76a913{}88ac", &hash[..38]),
+        // P2PKH with OP_EQUAL instead of OP_EQUALVERIFY.
+        format!("76a914{}87ac", hash),
+        // P2PKH with a trailing opcode.
+        format!("76a914{}88ac61", hash),
+        // P2SH with a 21 byte hash.
+        format!("a915{}0087", hash),
+        // P2SH with OP_PUSHDATA1.
+        format!("a94c14{}87", hash),
+        // P2WPKH with a 21 byte program.
+        format!("0015{}00", hash),
+        // P2WSH missing a byte.
+        format!("001f{}", &hash32[..62]),
+        // P2WPKH pushed with OP_PUSHDATA1.
+        format!("004c14{}", hash),
+        // P2PK with an invalid public key.
+        format!("2104{}ac", &pk[2..]),
+        // P2PK with OP_CHECKSIGVERIFY.
+        format!("21{}ad", pk),
+        // OP_RETURN followed by a non-push opcode.
+        "6a0102ac".to_owned(),
+        // Multisig with OP_CHECKMULTISIGVERIFY.
+        format!("5121{}51af", pk),
+        // Multisig with the wrong number of keys.
+        format!("5121{}52ae", pk),
+        // A single byte witness program.
+        "510101".to_owned(),
+    ];
+    for hex in &near_misses {
+        let script = script(hex);
+        assert_eq!(script.classify(), ScriptType::NonStandard, "{}", hex);
+        assert!(!script.is_p2pkh() && !script.is_p2sh() && !script.is_p2wpkh(), "{}", hex);
+        assert!(!script.is_p2wsh() && !script.is_p2tr(), "{}", hex);
+    }
+
+    // The predicates agree with the classifier.
+    for script in [&p2pk, &p2pkh, &p2sh, &p2wpkh, &p2wsh, &p2tr, &v1, &v2, &op_return, &multisig] {
+        let script_type = script.classify();
+        assert_eq!(script.is_p2pk(), matches!(script_type, ScriptType::P2pk(_)));
+        assert_eq!(script.is_p2pkh(), matches!(script_type, ScriptType::P2pkh(_)));
+        assert_eq!(script.is_p2sh(), matches!(script_type, ScriptType::P2sh(_)));
+        assert_eq!(script.is_p2wpkh(), matches!(script_type, ScriptType::P2wpkh(_)));
+        assert_eq!(script.is_p2wsh(), matches!(script_type, ScriptType::P2wsh(_)));
+        assert_eq!(script.is_p2tr(), matches!(script_type, ScriptType::P2tr(_)));
+        assert_eq!(script.is_multisig(), matches!(script_type, ScriptType::Multisig(_)));
+    }
+}
+
 #[test]
 fn p2sh_p2wsh_conversion() {
     // Test vectors taken from Core tests/data/script_tests.json
@@ -647,6 +1407,99 @@ fn iterator() {
     assert_eq!(v_nonmin_alt, slop_v_nonmin_alt);
 }
 
+#[test]
+fn instruction_indices_ext() {
+    use crate::opcodes::all::*;
+
+    // OP_0, OP_PUSHBYTES_1, OP_PUSHDATA1 (non-minimal), OP_PUSHDATA2 (non-minimal), OP_PUSHDATA4
+    // (non-minimal), OP_PUSHBYTES_1 0x05 (should be OP_PUSHNUM_5), OP_CHECKSIG
+    let script = ScriptBuf::from_hex("0001694c016a4d01006b4e010000006c0105ac").unwrap();
+    let v: Vec<_> = script.instruction_indices_ext().collect::<Result<_, _>>().unwrap();
+
+    let positions: Vec<_> = v.iter().map(|(pos, _)| *pos).collect();
+    assert_eq!(positions, vec![0, 1, 3, 6, 10, 16, 18]);
+
+    let opcodes: Vec<_> = v.iter().map(|(_, ins)| ins.opcode()).collect();
+    assert_eq!(
+        opcodes,
+        vec![
+            OP_PUSHBYTES_0,
+            OP_PUSHBYTES_1,
+            OP_PUSHDATA1,
+            OP_PUSHDATA2,
+            OP_PUSHDATA4,
+            OP_PUSHBYTES_1,
+            OP_CHECKSIG
+        ]
+    );
+
+    let minimal: Vec<_> = v.iter().map(|(_, ins)| ins.is_minimal()).collect();
+    assert_eq!(minimal, vec![true, true, false, false, false, false, true]);
+
+    let data: Vec<_> = v.iter().map(|(_, ins)| ins.push_bytes().map(|b| b.as_bytes())).collect();
+    assert_eq!(
+        data,
+        vec![
+            Some(&[][..]),
+            Some(&[0x69][..]),
+            Some(&[0x6a][..]),
+            Some(&[0x6b][..]),
+            Some(&[0x6c][..]),
+            Some(&[0x05][..]),
+            None
+        ]
+    );
+    assert_eq!(v[6].1.instruction(), Instruction::Op(OP_CHECKSIG));
+
+    // Minimal uses of each PUSHDATA form.
+    let mut bytes = vec![0x4c, 76];
+    bytes.extend([0u8; 76]);
+    bytes.extend([0x4d, 0x00, 0x01]);
+    bytes.extend([0u8; 256]);
+    let script = ScriptBuf::from_bytes(bytes);
+    let v: Vec<_> = script.instruction_indices_ext().collect::<Result<_, _>>().unwrap();
+    assert_eq!(v.len(), 2);
+    assert_eq!((v[0].0, v[0].1.opcode()), (0, OP_PUSHDATA1));
+    assert_eq!((v[1].0, v[1].1.opcode()), (78, OP_PUSHDATA2));
+    assert!(v.iter().all(|(_, ins)| ins.is_minimal()));
+
+    // Truncated pushes.
+    for hex in ["4c", "4c02ff", "4d01", "4d0300ffff", "4e010000", "4e02000000ff", "02ff"] {
+        let script = ScriptBuf::from_hex(hex).unwrap();
+        let mut iter = script.instruction_indices_ext();
+        assert_eq!(iter.next(), Some(Err(Error::EarlyEndOfScript)), "script: {}", hex);
+        assert_eq!(iter.next(), None);
+    }
+
+    // Instructions before the error are still returned.
+    let script = ScriptBuf::from_hex("ac4c05ff").unwrap();
+    let mut iter = script.instruction_indices_ext();
+    assert_eq!(iter.next().unwrap().unwrap().1.opcode(), OP_CHECKSIG);
+    assert_eq!(iter.next(), Some(Err(Error::EarlyEndOfScript)));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn last_codeseparator_pos() {
+    assert_eq!(ScriptBuf::new().last_codeseparator_pos(), None);
+    assert_eq!(ScriptBuf::from_hex("51ac").unwrap().last_codeseparator_pos(), None);
+
+    // OP_CODESEPARATOR OP_CHECKSIG
+    assert_eq!(ScriptBuf::from_hex("abac").unwrap().last_codeseparator_pos(), Some(1));
+
+    // OP_CODESEPARATOR, a push containing 0xab, OP_CODESEPARATOR, OP_CHECKSIG
+    let script = ScriptBuf::from_hex("ab02abab51abac").unwrap();
+    assert_eq!(script.last_codeseparator_pos(), Some(6));
+
+    // A 0xab byte inside a push is not an opcode.
+    let script = ScriptBuf::from_hex("ab4c02ababac").unwrap();
+    assert_eq!(script.last_codeseparator_pos(), Some(1));
+
+    // Parsing stops at a truncated push.
+    let script = ScriptBuf::from_hex("51ab4c05abab").unwrap();
+    assert_eq!(script.last_codeseparator_pos(), Some(2));
+}
+
 #[test]
 fn script_ord() {
     let script_1 = Builder::new().push_slice([1, 2, 3, 4]).into_script();
@@ -781,6 +1634,52 @@ fn script_get_sigop_count() {
     assert_eq!(multi_nopushnum_op.count_sigops_legacy(), 20);
 }
 
+#[test]
+fn script_get_sigop_count_core_vectors() {
+    // Vectors from Bitcoin Core's `GetSigOpCount` tests (src/test/sigopcount_tests.cpp) and
+    // edge cases of its parsing loop.
+    let count = |hex: &str| {
+        let script = ScriptBuf::from_hex(hex).unwrap();
+        (script.count_sigops(), script.count_sigops_legacy())
+    };
+
+    // Empty script.
+    assert_eq!(count(""), (0, 0));
+    // OP_1 <33 bytes> <33 bytes> OP_2 OP_CHECKMULTISIG
+    let two_keys = format!("5121{}21{}52ae", "02".repeat(33), "03".repeat(33));
+    assert_eq!(count(&two_keys), (2, 20));
+    // OP_16 OP_CHECKMULTISIGVERIFY
+    assert_eq!(count("60af"), (16, 20));
+    // OP_0 OP_CHECKMULTISIG: OP_0 is not OP_1..OP_16 so the maximum is assumed.
+    assert_eq!(count("00ae"), (20, 20));
+    // OP_1NEGATE OP_CHECKMULTISIG
+    assert_eq!(count("4fae"), (20, 20));
+    // OP_3 OP_CHECKSIG OP_CHECKMULTISIG: the pushnum must immediately precede the multisig.
+    assert_eq!(count("53acae"), (21, 21));
+    // OP_3 OP_CHECKMULTISIG OP_CHECKMULTISIG
+    assert_eq!(count("53aeae"), (23, 40));
+
+    // Opcodes embedded in pushed data are not counted.
+    // <ac ae ad af> (OP_PUSHBYTES_4)
+    assert_eq!(count("04acaeadaf"), (0, 0));
+    // OP_PUSHDATA1 <ac ac>
+    assert_eq!(count("4c02acac"), (0, 0));
+    // OP_PUSHDATA2 <ae>
+    assert_eq!(count("4d0100ae"), (0, 0));
+    // OP_PUSHDATA4 <ac> OP_CHECKSIG
+    assert_eq!(count("4e01000000acac"), (1, 1));
+    // A data push of `0x53` is not OP_3.
+    assert_eq!(count("0153ae"), (20, 20));
+
+    // Parsing stops at the first error, counting what was seen so far.
+    // OP_CHECKSIG OP_PUSHBYTES_2 <ac> (truncated)
+    assert_eq!(count("ac02ac"), (1, 1));
+    // OP_CHECKSIG OP_PUSHDATA1 (missing length)
+    assert_eq!(count("ac4c"), (1, 1));
+    // OP_2 OP_CHECKMULTISIG OP_PUSHDATA2 0xffff <ac> (truncated)
+    assert_eq!(count("52ae4dffffac"), (2, 20));
+}
+
 #[test]
 #[cfg(feature = "serde")]
 fn script_serde_human_and_not() {
@@ -920,3 +1819,206 @@ fn script_push_int_overflow() {
     // Only errors if `data == i32::MIN` (CScriptNum cannot have value -2^31).
     assert_eq!(Builder::new().push_int(i32::MIN), Err(Error::NumericOverflow));
 }
+
+#[test]
+fn locktime_requirements() {
+    use crate::locktime::{absolute, relative};
+
+    let key = [0x02; 33];
+
+    // BOLT-3 `to_local` output with `to_self_delay` of 144 blocks.
+    let to_local = Builder::new()
+        .push_opcode(OP_IF)
+        .push_slice(key)
+        .push_opcode(OP_ELSE)
+        .push_int_unchecked(144)
+        .push_opcode(OP_CSV)
+        .push_opcode(OP_DROP)
+        .push_slice(key)
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_CHECKSIG)
+        .into_script();
+    let requirements = to_local.locktime_requirements();
+    assert!(requirements.absolute().is_empty());
+    assert_eq!(requirements.relative(), [relative::LockTime::from_height(144)]);
+    assert!(!requirements.has_dynamic());
+    assert!(!requirements.is_empty());
+
+    // BOLT-3 received HTLC output (with anchors) expiring at block 500.
+    let received_htlc = Builder::new()
+        .push_opcode(OP_DUP)
+        .push_opcode(OP_HASH160)
+        .push_slice([0x11; 20])
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_opcode(OP_CHECKSIG)
+        .push_opcode(OP_ELSE)
+        .push_slice(key)
+        .push_opcode(OP_SWAP)
+        .push_opcode(OP_SIZE)
+        .push_int_unchecked(32)
+        .push_opcode(OP_EQUAL)
+        .push_opcode(OP_IF)
+        .push_opcode(OP_HASH160)
+        .push_slice([0x22; 20])
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_PUSHNUM_2)
+        .push_opcode(OP_SWAP)
+        .push_slice(key)
+        .push_opcode(OP_PUSHNUM_2)
+        .push_opcode(OP_CHECKMULTISIG)
+        .push_opcode(OP_ELSE)
+        .push_opcode(OP_DROP)
+        .push_int_unchecked(500)
+        .push_opcode(OP_CLTV)
+        .push_opcode(OP_DROP)
+        .push_opcode(OP_CHECKSIG)
+        .push_opcode(OP_ENDIF)
+        .push_opcode(OP_PUSHNUM_1)
+        .push_opcode(OP_CSV)
+        .push_opcode(OP_DROP)
+        .push_opcode(OP_ENDIF)
+        .into_script();
+    let requirements = received_htlc.locktime_requirements();
+    assert_eq!(requirements.absolute(), [absolute::LockTime::from_height(500).unwrap()]);
+    assert_eq!(requirements.relative(), [relative::LockTime::from_height(1)]);
+    assert!(!requirements.has_dynamic());
+
+    // `0xffffffff` requires a 5 byte CScriptNum.
+    let five_bytes =
+        Builder::new().push_int_unchecked(0xffff_ffff).push_opcode(OP_CLTV).into_script();
+    assert_eq!(five_bytes.as_bytes()[0], 5);
+    assert_eq!(
+        five_bytes.locktime_requirements().absolute(),
+        [absolute::LockTime::from_consensus(u32::MAX)]
+    );
+
+    // The disable flag makes `OP_CSV` a NOP, negative numbers and 6 byte pushes are unsatisfiable.
+    let invalid = Builder::new()
+        .push_int_unchecked(1 << 31)
+        .push_opcode(OP_CSV)
+        .push_opcode(OP_PUSHNUM_NEG1)
+        .push_opcode(OP_CLTV)
+        .push_slice([1; 6])
+        .push_opcode(OP_CLTV)
+        .into_script();
+    assert_eq!(invalid.locktime_requirements(), Default::default());
+    assert!(invalid.locktime_requirements().is_empty());
+
+    // The argument is not a literal.
+    let dynamic = Builder::new()
+        .push_opcode(OP_DUP)
+        .push_opcode(OP_CSV)
+        .push_int_unchecked(10)
+        .push_opcode(OP_CLTV)
+        .into_script();
+    let requirements = dynamic.locktime_requirements();
+    assert_eq!(requirements.absolute(), [absolute::LockTime::from_consensus(10)]);
+    assert!(requirements.relative().is_empty());
+    assert!(requirements.has_dynamic());
+    assert!(ScriptBuf::from_hex("b1").unwrap().locktime_requirements().has_dynamic());
+}
+
+#[test]
+fn minimal_pushes() {
+    let minimal = |hex: &str| ScriptBuf::from_hex(hex).unwrap().has_minimal_pushes();
+    let normalized =
+        |hex: &str| ScriptBuf::from_hex(hex).unwrap().normalize_pushes().to_hex_string();
+
+    assert!(minimal(""));
+    assert!(minimal("00"));
+    assert!(minimal("60"));
+    assert!(minimal("4f"));
+    assert!(minimal("0111"));
+    assert!(minimal("0180"));
+    assert!(minimal("76a914000000000000000000000000000000000000000088ac"));
+    // `0x10` should be pushed using `OP_16`.
+    assert!(!minimal("0110"));
+    assert_eq!(normalized("0110"), "60");
+    // A 5 byte push should use `OP_PUSHBYTES_5`.
+    assert!(!minimal("4c050102030405"));
+    assert_eq!(normalized("4c050102030405"), "050102030405");
+    assert!(!minimal("0181"));
+    assert_eq!(normalized("0181"), "4f");
+    assert!(!minimal("4c00"));
+    assert_eq!(normalized("4c00"), "00");
+    assert!(!minimal("4d0100ff"));
+    assert_eq!(normalized("4d0100ff"), "01ff");
+    // Non-push opcodes are kept, a malformed push is copied verbatim.
+    assert_eq!(normalized("ac4e0100000001a9"), "ac51a9");
+    assert!(!minimal("ac4e01"));
+    assert_eq!(normalized("01014e01"), "514e01");
+}
+
+#[test]
+fn normalize_pushes_properties() {
+    use crate::opcodes::all::*;
+
+    // Pushes `data` using every encoding that is valid for it.
+    fn encodings(data: &[u8]) -> Vec<Vec<u8>> {
+        let len = data.len() as u32;
+        let mut encodings = vec![];
+        if data.len() < 0x4c {
+            encodings.push([&[data.len() as u8][..], data].concat());
+        }
+        if data.len() <= 0xff {
+            encodings.push([&[OP_PUSHDATA1.to_u8(), data.len() as u8][..], data].concat());
+        }
+        encodings.push([&[OP_PUSHDATA2.to_u8()][..], &(len as u16).to_le_bytes(), data].concat());
+        encodings.push([&[OP_PUSHDATA4.to_u8()][..], &len.to_le_bytes(), data].concat());
+        match data {
+            [n @ 1..=16] => encodings.push(vec![OP_PUSHNUM_1.to_u8() + n - 1]),
+            [0x81] => encodings.push(vec![OP_PUSHNUM_NEG1.to_u8()]),
+            _ => {}
+        }
+        encodings
+    }
+
+    // The values pushed onto the stack, and the non-push opcodes.
+    fn items(script: &Script) -> Vec<Result<Vec<u8>, Opcode>> {
+        script
+            .instructions()
+            .map(|instruction| match instruction.unwrap() {
+                Instruction::PushBytes(bytes) => Ok(bytes.as_bytes().to_vec()),
+                Instruction::Op(OP_PUSHNUM_NEG1) => Ok(vec![0x81]),
+                Instruction::Op(op) => match op.decode_pushnum() {
+                    Some(n) => Ok(vec![n]),
+                    None => Err(op),
+                },
+            })
+            .collect()
+    }
+
+    let data: Vec<Vec<u8>> = vec![
+        vec![],
+        vec![0],
+        vec![1],
+        vec![16],
+        vec![17],
+        vec![0x80],
+        vec![0x81],
+        vec![1, 2],
+        vec![7; 75],
+        vec![7; 76],
+        vec![7; 255],
+        vec![7; 256],
+    ];
+    let mut pushes: Vec<Vec<u8>> = data.iter().flat_map(|data| encodings(data)).collect();
+    pushes.push(vec![OP_CHECKSIG.to_u8()]);
+    pushes.push(vec![OP_RESERVED.to_u8()]);
+
+    for first in &pushes {
+        for second in &pushes {
+            let script = ScriptBuf::from_bytes([&first[..], &[OP_DUP.to_u8()], second].concat());
+            let normalized = script.normalize_pushes();
+
+            assert!(normalized.has_minimal_pushes(), "{:x}", script);
+            assert_eq!(normalized.normalize_pushes(), normalized, "{:x}", script);
+            assert_eq!(items(&normalized), items(&script), "{:x}", script);
+            assert!(normalized.len() <= script.len());
+            if script.has_minimal_pushes() {
+                assert_eq!(normalized, script);
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Timelocks enforced by scripts.
+//!
+//! Scripts enforce timelocks with `<n> OP_CHECKLOCKTIMEVERIFY` (BIP-65) and
+//! `<n> OP_CHECKSEQUENCEVERIFY` (BIP-112). This module extracts these requirements without
+//! executing the script.
+
+use super::{scriptint_parse, Instruction, Script, ScriptExt as _};
+use crate::locktime::{absolute, relative};
+use crate::opcodes::all::*;
+use crate::prelude::Vec;
+
+/// The maximum length of the number argument to `OP_CHECKLOCKTIMEVERIFY` and
+/// `OP_CHECKSEQUENCEVERIFY`.
+///
+/// Unlike other numeric opcodes these accept 5 byte numbers so that the full range of `u32` can
+/// be expressed.
+const MAX_LOCKTIME_NUM_LEN: usize = 5;
+
+/// The timelock requirements of a script.
+///
+/// Obtained by calling `Script::locktime_requirements`. The script is not executed so the
+/// requirements from all branches are collected, in the order they appear in the script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocktimeRequirements {
+    absolute: Vec<absolute::LockTime>,
+    relative: Vec<relative::LockTime>,
+    dynamic: bool,
+}
+
+impl LocktimeRequirements {
+    /// Scans the script for `<n> OP_CHECKLOCKTIMEVERIFY` and `<n> OP_CHECKSEQUENCEVERIFY`.
+    ///
+    /// A timelock is recorded if the opcode immediately follows a literal number push. Numbers
+    /// that can never be satisfied (negative or too large) are skipped, as are
+    /// `OP_CHECKSEQUENCEVERIFY` arguments with the disable flag set since these make the opcode
+    /// behave as a NOP.
+    ///
+    /// If the script contains a malformed push only the instructions preceding it are considered.
+    pub fn from_script(script: &Script) -> Self {
+        let mut requirements = LocktimeRequirements::default();
+        let mut last = None;
+
+        for instruction in script.instructions().map_while(Result::ok) {
+            let previous = last.take();
+            if is_number_push(instruction) {
+                last = Some(instruction);
+            }
+
+            let is_cltv = match instruction {
+                Instruction::Op(OP_CLTV) => true,
+                Instruction::Op(OP_CSV) => false,
+                _ => continue,
+            };
+            let n = match previous {
+                Some(push) => read_locktime_int(push).and_then(|n| u32::try_from(n).ok()),
+                None => {
+                    requirements.dynamic = true;
+                    continue;
+                }
+            };
+            match n {
+                Some(n) if is_cltv =>
+                    requirements.absolute.push(absolute::LockTime::from_consensus(n)),
+                Some(n) =>
+                    if let Ok(lock_time) = relative::LockTime::from_consensus(n) {
+                        requirements.relative.push(lock_time);
+                    },
+                None => {}
+            }
+        }
+
+        requirements
+    }
+
+    /// Returns the absolute timelocks required by `OP_CHECKLOCKTIMEVERIFY`.
+    #[inline]
+    pub fn absolute(&self) -> &[absolute::LockTime] { &self.absolute }
+
+    /// Returns the relative timelocks required by `OP_CHECKSEQUENCEVERIFY`.
+    #[inline]
+    pub fn relative(&self) -> &[relative::LockTime] { &self.relative }
+
+    /// Returns `true` if the script contains `OP_CHECKLOCKTIMEVERIFY` or
+    /// `OP_CHECKSEQUENCEVERIFY` not preceded by a literal number push.
+    ///
+    /// The argument of such an opcode is computed at runtime (e.g. taken from the witness) so
+    /// its requirement could not be determined.
+    #[inline]
+    pub fn has_dynamic(&self) -> bool { self.dynamic }
+
+    /// Returns `true` if the script does not enforce any timelock.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.absolute.is_empty() && self.relative.is_empty() && !self.dynamic
+    }
+}
+
+/// Returns `true` if the instruction pushes a number literal.
+fn is_number_push(instruction: Instruction<'_>) -> bool {
+    match instruction {
+        Instruction::PushBytes(_) => true,
+        Instruction::Op(op) => op == OP_PUSHNUM_NEG1 || op.decode_pushnum().is_some(),
+    }
+}
+
+/// Decodes the argument of `OP_CHECKLOCKTIMEVERIFY` or `OP_CHECKSEQUENCEVERIFY`.
+///
+/// Caller to guarantee that `instruction` is a number push.
+fn read_locktime_int(instruction: Instruction<'_>) -> Option<i64> {
+    match instruction {
+        Instruction::Op(OP_PUSHNUM_NEG1) => Some(-1),
+        Instruction::Op(op) => op.decode_pushnum().map(i64::from),
+        Instruction::PushBytes(bytes) if bytes.is_empty() => Some(0),
+        Instruction::PushBytes(bytes) if bytes.len() <= MAX_LOCKTIME_NUM_LEN =>
+            Some(scriptint_parse(bytes.as_bytes())),
+        Instruction::PushBytes(_) => None,
+    }
+}
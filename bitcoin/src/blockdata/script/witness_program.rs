@@ -43,7 +43,23 @@ pub struct WitnessProgram {
 
 impl WitnessProgram {
     /// Constructs a new witness program, copying the content from the given byte slice.
+    ///
+    /// In addition to the consensus rules checked by [`WitnessProgram::new_consensus`] this
+    /// enforces that a v1 program is either a 32 byte Taproot output key or the pay to anchor
+    /// program, since other v1 programs are not spendable under current standardness rules.
     pub fn new(version: WitnessVersion, bytes: &[u8]) -> Result<Self, Error> {
+        let program = WitnessProgram::new_consensus(version, bytes)?;
+        if version == WitnessVersion::V1 && !program.is_p2tr() && !program.is_p2a() {
+            return Err(Error::NonStandardSegwitV1(bytes.len()));
+        }
+        Ok(program)
+    }
+
+    /// Constructs a new witness program, copying the content from the given byte slice.
+    ///
+    /// Only checks the rules enforced by consensus (and BIP-173/BIP-350 addresses): the program
+    /// must be between 2 and 40 bytes long and a v0 program must be either 20 or 32 bytes long.
+    pub fn new_consensus(version: WitnessVersion, bytes: &[u8]) -> Result<Self, Error> {
         use Error::*;
 
         let program_len = bytes.len();
@@ -151,6 +167,8 @@ pub enum Error {
     InvalidLength(usize),
     /// A v0 witness program must be either of length 20 or 32.
     InvalidSegwitV0Length(usize),
+    /// A standard v1 witness program must be of length 32 (or the pay to anchor program).
+    NonStandardSegwitV1(usize),
 }
 
 impl From<Infallible> for Error {
@@ -166,6 +184,8 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 write!(f, "witness program must be between 2 and 40 bytes: length={}", len),
             InvalidSegwitV0Length(len) =>
                 write!(f, "a v0 witness program must be either 20 or 32 bytes: length={}", len),
+            NonStandardSegwitV1(len) =>
+                write!(f, "a standard v1 witness program must be 32 bytes: length={}", len),
         }
     }
 }
@@ -176,7 +196,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use Error::*;
 
         match *self {
-            InvalidLength(_) | InvalidSegwitV0Length(_) => None,
+            InvalidLength(_) | InvalidSegwitV0Length(_) | NonStandardSegwitV1(_) => None,
         }
     }
 }
@@ -223,4 +243,51 @@ fn valid_v1_witness_programs() {
             .expect("valid witness program")
             .is_p2tr());
     }
+
+    #[test]
+    fn witness_program_length_boundaries() {
+        use Error::*;
+
+        let arbitrary_bytes = [0x00; MAX_SIZE + 1];
+        let versions =
+            [WitnessVersion::V0, WitnessVersion::V1, WitnessVersion::V2, WitnessVersion::V16];
+
+        for version in versions {
+            for len in [0, 1, 2, 19, 20, 21, 31, 32, 33, 39, 40, 41] {
+                let bytes = &arbitrary_bytes[..len];
+                let consensus = match (version, len) {
+                    (_, 0..=1) | (_, 41) => Err(InvalidLength(len)),
+                    (WitnessVersion::V0, 20) | (WitnessVersion::V0, 32) => Ok(()),
+                    (WitnessVersion::V0, _) => Err(InvalidSegwitV0Length(len)),
+                    _ => Ok(()),
+                };
+                let standard = match (version, len, consensus.clone()) {
+                    (WitnessVersion::V1, 32, _) => Ok(()),
+                    (WitnessVersion::V1, _, Ok(())) => Err(NonStandardSegwitV1(len)),
+                    (_, _, consensus) => consensus,
+                };
+
+                let program = WitnessProgram::new_consensus(version, bytes);
+                assert_eq!(program.clone().map(|_| ()), consensus, "{} {}", version, len);
+                if let Ok(program) = program {
+                    assert_eq!(program.version(), version);
+                    assert_eq!(program.program().as_bytes(), bytes);
+                }
+                assert_eq!(
+                    WitnessProgram::new(version, bytes).map(|_| ()),
+                    standard,
+                    "{} {}",
+                    version,
+                    len
+                );
+            }
+        }
+
+        // Pay to anchor is the only standard v1 program that isn't 32 bytes long.
+        assert!(WitnessProgram::new(WitnessVersion::V1, &P2A_PROGRAM).unwrap().is_p2a());
+        assert_eq!(
+            WitnessProgram::new(WitnessVersion::V1, &[0x4e, 0x74]),
+            Err(NonStandardSegwitV1(2))
+        );
+    }
 }
@@ -22,10 +22,11 @@
 use crate::internal_macros::{impl_consensus_encoding, impl_hashencode};
 use crate::locktime::absolute::{self, Height, Time};
 use crate::prelude::{Borrow, Vec};
-use crate::script::{Script, ScriptBuf, ScriptExt as _, ScriptExtPriv as _};
+use crate::script::script_type::ScriptType;
+use crate::script::{Script, ScriptBuf, ScriptExt as _};
 #[cfg(doc)]
 use crate::sighash::{EcdsaSighashType, TapSighashType};
-use crate::witness::Witness;
+use crate::witness::{Witness, WitnessExt as _};
 use crate::{Amount, FeeRate, SignedAmount};
 
 #[rustfmt::skip]            // Keep public re-exports separate.
@@ -73,11 +74,6 @@ pub trait OutPointExt impl for OutPoint {
         #[deprecated(since = "TBD", note = "use struct initialization syntax instead")]
         #[allow(clippy::new-ret-no-self)]
         fn new(txid: Txid, vout: u32) -> Self { OutPoint { txid, vout } }
-
-        /// Checks if an `OutPoint` is "null".
-        #[inline]
-        #[deprecated(since = "TBD", note = "use `outpoint == OutPoint::COINBASE_PREVOUT` instead")]
-        fn is_null(&self) -> bool { *self == OutPoint::COINBASE_PREVOUT }
     }
 }
 
@@ -135,18 +131,46 @@ fn segwit_weight(&self) -> Weight {
         ///
         /// Base size excludes the witness data (see [`Self::total_size`]).
         fn base_size(&self) -> usize {
-            let mut size = OutPoint::SIZE;
-
-            size += compact_size::encoded_size(self.script_sig.len());
-            size += self.script_sig.len();
-
-            size + Sequence::SIZE
+            OutPoint::SIZE + self.script_sig.consensus_size() + Sequence::SIZE
         }
 
         /// Returns the total number of bytes that this input contributes to a transaction.
         ///
         /// Total size includes the witness data (for base size see [`Self::base_size`]).
         fn total_size(&self) -> usize { self.base_size() + self.witness.size() }
+
+        /// Returns the redeem script if this input spends the P2SH output `script_pubkey`.
+        ///
+        /// This is the last push of the `script_sig`, for a P2SH-wrapped SegWit output it is the
+        /// witness program. Returns `None` if `script_pubkey` is not P2SH or the `script_sig` is
+        /// not push only, in particular if it is empty as for native SegWit spends.
+        fn redeem_script(&self, script_pubkey: &Script) -> Option<&Script> {
+            if !script_pubkey.is_p2sh() {
+                return None;
+            }
+            self.script_sig.redeem_script()
+        }
+
+        /// Returns the witness script if this input spends the P2WSH or P2SH-P2WSH output
+        /// `script_pubkey`.
+        ///
+        /// This is the last element of the witness. Returns `None` for all other output types,
+        /// if the `script_sig` of a native P2WSH spend is not empty or if the witness is empty.
+        fn witness_script(&self, script_pubkey: &Script) -> Option<&Script> {
+            let witness_program = if script_pubkey.is_p2wsh() {
+                if !self.script_sig.is_empty() {
+                    return None;
+                }
+                script_pubkey
+            } else {
+                self.redeem_script(script_pubkey)?
+            };
+            if witness_program.is_p2wsh() {
+                self.witness.witness_script()
+            } else {
+                None
+            }
+        }
     }
 }
 
@@ -174,6 +198,12 @@ fn weight(&self) -> Weight {
         /// There is no difference between base size vs total size for outputs.
         fn size(&self) -> usize { size_from_script_pubkey(&self.script_pubkey) }
 
+        /// Classifies the script pubkey of this output by the standard output templates.
+        ///
+        /// See [`ScriptType`] for the templates.
+        #[inline]
+        fn script_type(&self) -> ScriptType<'_> { self.script_pubkey.classify() }
+
         /// Constructs a new `TxOut` with given script and the smallest possible `value` that is **not** dust
         /// per current Core policy.
         ///
@@ -206,8 +236,7 @@ fn minimal_non_dust_custom(script_pubkey: ScriptBuf, dust_relay_fee: FeeRate) ->
 
 /// Returns the total number of bytes that this script pubkey would contribute to a transaction.
 fn size_from_script_pubkey(script_pubkey: &Script) -> usize {
-    let len = script_pubkey.len();
-    Amount::SIZE + compact_size::encoded_size(len) + len
+    Amount::SIZE + script_pubkey.consensus_size()
 }
 
 /// Extension functionality for the [`Transaction`] type.
@@ -255,6 +284,24 @@ pub trait TransactionExt: sealed::Sealed {
     /// > including base data and witness data.
     fn total_size(&self) -> usize;
 
+    /// Consensus encodes the transaction without the SegWit marker, flag and witness data.
+    ///
+    /// This is the legacy serialization which is hashed to compute the [`Txid`], it is used by
+    /// [`Encodable::consensus_encode`] for transactions without witness data. The number of bytes
+    /// written is [`Self::base_size`].
+    ///
+    /// Note that the serialization of a transaction with no inputs is ambiguous without the SegWit
+    /// marker, see [`Self::weight`].
+    fn consensus_encode_without_witness<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+    ) -> Result<usize, io::Error>;
+
+    /// Serializes the transaction without the SegWit marker, flag and witness data.
+    ///
+    /// See [`Self::consensus_encode_without_witness`].
+    fn serialize_without_witness(&self) -> Vec<u8>;
+
     /// Returns the "virtual size" (vsize) of this transaction.
     ///
     /// Will be `ceil(weight / 4.0)`. Note this implements the virtual size as per [`BIP141`], which
@@ -297,9 +344,19 @@ pub trait TransactionExt: sealed::Sealed {
     /// transaction from being mined immediately.
     fn is_absolute_timelock_satisfied(&self, height: Height, time: Time) -> bool;
 
+    /// Returns `true` if this [`Transaction`] is final on top of a chain tip at `height` with
+    /// median time past `mtp`, i.e. it may be included in the next block.
+    ///
+    /// This is the consensus rule implemented by `IsFinalTx` in Bitcoin Core: a height based lock
+    /// time must be less than the height of the next block and a time based lock time must be
+    /// strictly less than the median time past of the tip (BIP-113). A transaction is always
+    /// final if the lock time is not enabled because all input sequence numbers are final.
+    fn is_final(&self, height: Height, mtp: Time) -> bool;
+
     /// Returns `true` if this transactions nLockTime is enabled ([BIP-65]).
     ///
     /// [BIP-65]: https://github.com/bitcoin/bips/blob/master/bip-0065.mediawiki
+    #[doc(alias = "is_absolute_timelock_enabled")]
     fn is_lock_time_enabled(&self) -> bool;
 
     /// Returns an iterator over lengths of `script_pubkey`s in the outputs.
@@ -325,9 +382,35 @@ fn total_sigop_cost<S>(&self, spent: S) -> usize
     where
         S: FnMut(&OutPoint) -> Option<TxOut>;
 
+    /// Counts the total sigop cost, requiring the output spent by every input.
+    ///
+    /// This is `GetTransactionSigOpCost` from Bitcoin Core: legacy sigops in the scriptSigs,
+    /// scriptPubkeys and P2SH redeem scripts are scaled by [`WITNESS_SCALE_FACTOR`], witness sigops
+    /// (including P2SH wrapped SegWit) are not. See [`Self::total_sigop_cost`] for details.
+    ///
+    /// The spent outputs of a coinbase transaction are never looked up.
+    ///
+    /// # Errors
+    ///
+    /// If `spent` returns `None` for any of the inputs.
+    ///
+    /// [`WITNESS_SCALE_FACTOR`]: crate::constants::WITNESS_SCALE_FACTOR
+    fn total_sigop_cost_checked<S>(&self, spent: S) -> Result<usize, MissingPrevoutError>
+    where
+        S: FnMut(&OutPoint) -> Option<TxOut>;
+
     /// Returns a reference to the input at `input_index` if it exists.
     fn tx_in(&self, input_index: usize) -> Result<&TxIn, InputsIndexError>;
 
+    /// Returns the weight prediction of the input at `input_index` as it is currently signed.
+    ///
+    /// This is useful when replacing a transaction that spends the same inputs, e.g. to bump its
+    /// fee, the predictions can be passed to [`predict_weight`] together with the new outputs.
+    fn input_weight_prediction(
+        &self,
+        input_index: usize,
+    ) -> Result<InputWeightPrediction, InputsIndexError>;
+
     /// Returns a reference to the output at `output_index` if it exists.
     fn tx_out(&self, output_index: usize) -> Result<&TxOut, OutputsIndexError>;
 }
@@ -380,6 +463,24 @@ fn total_size(&self) -> usize {
         size + absolute::LockTime::SIZE
     }
 
+    fn consensus_encode_without_witness<W: Write + ?Sized>(
+        &self,
+        w: &mut W,
+    ) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.version.consensus_encode(w)?;
+        len += self.input.consensus_encode(w)?;
+        len += self.output.consensus_encode(w)?;
+        len += self.lock_time.consensus_encode(w)?;
+        Ok(len)
+    }
+
+    fn serialize_without_witness(&self) -> Vec<u8> {
+        let mut encoder = Vec::with_capacity(self.base_size());
+        self.consensus_encode_without_witness(&mut encoder).expect("in-memory writers don't error");
+        encoder
+    }
+
     #[inline]
     fn vsize(&self) -> usize {
         // No overflow because it's computed from data in memory
@@ -388,7 +489,7 @@ fn vsize(&self) -> usize {
 
     #[doc(alias = "is_coin_base")] // method previously had this name
     fn is_coinbase(&self) -> bool {
-        self.input.len() == 1 && self.input[0].previous_output == OutPoint::COINBASE_PREVOUT
+        self.input.len() == 1 && self.input[0].previous_output.is_null()
     }
 
     fn is_explicitly_rbf(&self) -> bool { self.input.iter().any(|input| input.sequence.is_rbf()) }
@@ -400,6 +501,16 @@ fn is_absolute_timelock_satisfied(&self, height: Height, time: Time) -> bool {
         self.lock_time.is_satisfied_by(height, time)
     }
 
+    fn is_final(&self, height: Height, mtp: Time) -> bool {
+        if !self.is_lock_time_enabled() {
+            return true;
+        }
+        match self.lock_time {
+            absolute::LockTime::Blocks(n) => n <= height,
+            absolute::LockTime::Seconds(n) => n < mtp,
+        }
+    }
+
     fn is_lock_time_enabled(&self) -> bool { self.input.iter().any(|i| i.enables_lock_time()) }
 
     fn script_pubkey_lens(&self) -> TxOutToScriptPubkeyLengthIter {
@@ -417,6 +528,28 @@ fn total_sigop_cost<S>(&self, mut spent: S) -> usize
         cost.saturating_add(self.count_witness_sigops(spent))
     }
 
+    fn total_sigop_cost_checked<S>(&self, mut spent: S) -> Result<usize, MissingPrevoutError>
+    where
+        S: FnMut(&OutPoint) -> Option<TxOut>,
+    {
+        if self.is_coinbase() {
+            return Ok(self.count_p2pk_p2pkh_sigops().saturating_mul(4));
+        }
+
+        let mut missing = None;
+        let cost = self.total_sigop_cost(|outpoint: &OutPoint| {
+            let prevout = spent(outpoint);
+            if prevout.is_none() && missing.is_none() {
+                missing = Some(*outpoint);
+            }
+            prevout
+        });
+        match missing {
+            Some(outpoint) => Err(MissingPrevoutError(outpoint)),
+            None => Ok(cost),
+        }
+    }
+
     #[inline]
     fn tx_in(&self, input_index: usize) -> Result<&TxIn, InputsIndexError> {
         self.input
@@ -424,6 +557,15 @@ fn tx_in(&self, input_index: usize) -> Result<&TxIn, InputsIndexError> {
             .ok_or(IndexOutOfBoundsError { index: input_index, length: self.input.len() }.into())
     }
 
+    fn input_weight_prediction(
+        &self,
+        input_index: usize,
+    ) -> Result<InputWeightPrediction, InputsIndexError> {
+        let input = self.tx_in(input_index)?;
+        let witness_element_lengths = input.witness.iter().map(|element| element.len());
+        Ok(InputWeightPrediction::new(input.script_sig.len(), witness_element_lengths))
+    }
+
     #[inline]
     fn tx_out(&self, output_index: usize) -> Result<&TxOut, OutputsIndexError> {
         self.output
@@ -606,6 +748,21 @@ impl From<IndexOutOfBoundsError> for OutputsIndexError {
     fn from(e: IndexOutOfBoundsError) -> Self { Self(e) }
 }
 
+/// The output spent by a transaction input could not be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPrevoutError(pub OutPoint);
+
+impl fmt::Display for MissingPrevoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the output spent by an input is missing: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingPrevoutError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { None }
+}
+
 /// Error attempting to do an out of bounds access on a vector.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
@@ -693,22 +850,20 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Erro
 
 impl Encodable for Transaction {
     fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
-        let mut len = 0;
-        len += self.version.consensus_encode(w)?;
-
         // Legacy transaction serialization format only includes inputs and outputs.
         if !self.uses_segwit_serialization() {
-            len += self.input.consensus_encode(w)?;
-            len += self.output.consensus_encode(w)?;
-        } else {
-            // BIP-141 (SegWit) transaction serialization also includes marker, flag, and witness data.
-            len += SEGWIT_MARKER.consensus_encode(w)?;
-            len += SEGWIT_FLAG.consensus_encode(w)?;
-            len += self.input.consensus_encode(w)?;
-            len += self.output.consensus_encode(w)?;
-            for input in &self.input {
-                len += input.witness.consensus_encode(w)?;
-            }
+            return self.consensus_encode_without_witness(w);
+        }
+
+        // BIP-141 (SegWit) transaction serialization also includes marker, flag, and witness data.
+        let mut len = 0;
+        len += self.version.consensus_encode(w)?;
+        len += SEGWIT_MARKER.consensus_encode(w)?;
+        len += SEGWIT_FLAG.consensus_encode(w)?;
+        len += self.input.consensus_encode(w)?;
+        len += self.output.consensus_encode(w)?;
+        for input in &self.input {
+            len += input.witness.consensus_encode(w)?;
         }
         len += self.lock_time.consensus_encode(w)?;
         Ok(len)
@@ -988,6 +1143,22 @@ impl InputWeightPrediction {
     /// [`InputWeightPrediction::new`].
     pub const P2TR_KEY_NON_DEFAULT_SIGHASH: Self = InputWeightPrediction::from_slice(0, &[65]);
 
+    /// Input weight prediction corresponding to spending of Taproot output using the key and
+    /// default sighash, with an annex of `annex_len` bytes.
+    ///
+    /// `annex_len` includes the `0x50` prefix of the annex.
+    pub const fn p2tr_key_default_sighash_with_annex(annex_len: usize) -> Self {
+        InputWeightPrediction::from_slice(0, &[64, annex_len])
+    }
+
+    /// Input weight prediction corresponding to spending of Taproot output using the key and
+    /// **non**-default sighash, with an annex of `annex_len` bytes.
+    ///
+    /// `annex_len` includes the `0x50` prefix of the annex.
+    pub const fn p2tr_key_non_default_sighash_with_annex(annex_len: usize) -> Self {
+        InputWeightPrediction::from_slice(0, &[65, annex_len])
+    }
+
     /// Input weight prediction corresponding to spending of P2WPKH output using [signature
     /// grinding].
     ///
@@ -1143,8 +1314,11 @@ mod tests {
     use units::parse;
 
     use super::*;
+    use crate::address::script_pubkey::ScriptBufExt as _;
     use crate::consensus::encode::{deserialize, serialize};
     use crate::constants::WITNESS_SCALE_FACTOR;
+    use crate::key::{PubkeyHash, WPubkeyHash};
+    use crate::script::ScriptBufExt as _;
     use crate::sighash::EcdsaSighashType;
 
     const SOME_TX: &str = "0100000001a15d57094aa7a21a28cb20b59aab8fc7d1149a3bdbcddba9c622e4f5f6a99ece010000006c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52ffffffff0100e1f505000000001976a9140389035a9225b3839e2bbf32d826a1e222031fd888ac00000000";
@@ -1337,6 +1511,43 @@ fn segwit_transaction() {
         assert_eq!(tx_without_witness.total_size(), expected_strippedsize);
     }
 
+    #[test]
+    fn serialize_without_witness() {
+        let tx_bytes = hex!(
+            "02000000000101595895ea20179de87052b4046dfe6fd515860505d6511a9004cf12a1f93cac7c01000000\
+            00ffffffff01deb807000000000017a9140f3444e271620c736808aa7b33e370bd87cb5a078702483045022\
+            100fb60dad8df4af2841adc0346638c16d0b8035f5e3f3753b88db122e70c79f9370220756e6633b17fd271\
+            0e626347d28d60b0a2d6cbb41de51740644b9fb3ba7751040121028fa937ca8cba2197a37c007176ed89410\
+            55d3bcb8627d085e94553e62f057dcc00000000"
+        );
+        // The same transaction in the legacy layout: no marker, flag or witness.
+        let legacy_bytes = hex!(
+            "0200000001595895ea20179de87052b4046dfe6fd515860505d6511a9004cf12a1f93cac7c0100000000ff\
+            ffffff01deb807000000000017a9140f3444e271620c736808aa7b33e370bd87cb5a078700000000"
+        );
+        let tx: Transaction = deserialize(&tx_bytes).unwrap();
+
+        let serialized = tx.serialize_without_witness();
+        assert_eq!(serialized, legacy_bytes);
+        assert_eq!(serialized.len(), tx.base_size());
+        let mut encoded = Vec::new();
+        assert_eq!(tx.consensus_encode_without_witness(&mut encoded).unwrap(), legacy_bytes.len());
+        assert_eq!(encoded, legacy_bytes);
+        assert_eq!(
+            sha256d::Hash::hash(&serialized).to_byte_array(),
+            tx.compute_txid().to_byte_array()
+        );
+
+        // The witness is still included by the default encoding.
+        assert_eq!(serialize(&tx), tx_bytes);
+
+        // Without a witness both encodings are the same.
+        let mut stripped = tx;
+        stripped.input.iter_mut().for_each(|input| input.witness.clear());
+        assert_eq!(serialize(&stripped), legacy_bytes);
+        assert_eq!(stripped.serialize_without_witness(), legacy_bytes);
+    }
+
     // We temporarily abuse `Transaction` for testing consensus serde adapter.
     #[test]
     #[cfg(feature = "serde")]
@@ -1397,6 +1608,39 @@ fn ntxid() {
         assert!(old_ntxid != tx.compute_ntxid());
     }
 
+    #[test]
+    fn ntxid_malleated_transactions() {
+        let tx_bytes = hex!("0100000001a15d57094aa7a21a28cb20b59aab8fc7d1149a3bdbcddba9c622e4f5f6a99ece010000006c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52ffffffff0100e1f505000000001976a9140389035a9225b3839e2bbf32d826a1e222031fd888ac00000000");
+        let tx: Transaction = deserialize(&tx_bytes).unwrap();
+
+        // Re-encode the scriptSig pushes with OP_PUSHDATA1, the spend is still valid.
+        let mut malleated = tx.clone();
+        let mut script_sig = Vec::new();
+        for instruction in tx.input[0].script_sig.instructions() {
+            let push = instruction.unwrap().push_bytes().unwrap().as_bytes().to_vec();
+            script_sig.push(0x4c);
+            script_sig.push(push.len() as u8);
+            script_sig.extend(push);
+        }
+        malleated.input[0].script_sig = ScriptBuf::from_bytes(script_sig);
+
+        assert_ne!(serialize(&malleated), tx_bytes);
+        assert_ne!(malleated.compute_txid(), tx.compute_txid());
+        assert_eq!(malleated.compute_ntxid(), tx.compute_ntxid());
+
+        // Malleating the witness of a SegWit transaction only changes its wtxid.
+        let mut segwit = tx.clone();
+        segwit.input[0].script_sig = ScriptBuf::new();
+        segwit.input[0].witness = Witness::from_slice(&[&[0x01][..]]);
+        let mut malleated = segwit.clone();
+        malleated.input[0].witness = Witness::from_slice(&[&[0x01][..], &[]]);
+
+        assert_eq!(malleated.compute_txid(), segwit.compute_txid());
+        assert_ne!(malleated.compute_wtxid(), segwit.compute_wtxid());
+        assert_eq!(malleated.compute_ntxid(), segwit.compute_ntxid());
+        assert_eq!(segwit.compute_ntxid(), tx.compute_ntxid());
+    }
+
     #[test]
     fn txid() {
         // SegWit tx from Liquid integration tests, txid/hash from Core decoderawtransaction
@@ -1616,6 +1860,85 @@ fn sequence_number() {
         assert!(!lock_time_disabled.is_relative_lock_time());
     }
 
+    #[test]
+    fn transaction_rbf_and_lock_time_signals() {
+        let tx = |sequences: &[u32]| Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::from_consensus(800_000),
+            input: sequences
+                .iter()
+                .map(|&n| TxIn { sequence: Sequence::from_consensus(n), ..TxIn::EMPTY_COINBASE })
+                .collect(),
+            output: vec![],
+        };
+
+        // (sequences, explicitly RBF, lock time enabled)
+        let cases: &[(&[u32], bool, bool)] = &[
+            (&[], false, false),
+            (&[0xffffffff], false, false),
+            (&[0xfffffffe], false, true),
+            (&[0xfffffffd], true, true),
+            (&[0], true, true),
+            (&[0xffffffff, 0xffffffff], false, false),
+            (&[0xffffffff, 0xfffffffe], false, true),
+            (&[0xffffffff, 0xfffffffd], true, true),
+            (&[0xfffffffe, 0x0040ffff], true, true),
+        ];
+        for (sequences, rbf, lock_time_enabled) in cases {
+            let tx = tx(sequences);
+            assert_eq!(tx.is_explicitly_rbf(), *rbf, "{:x?}", sequences);
+            assert_eq!(tx.is_lock_time_enabled(), *lock_time_enabled, "{:x?}", sequences);
+        }
+
+        // A disabled lock time is always satisfied.
+        let height = Height::from_consensus(1).unwrap();
+        let time = Time::from_consensus(500_000_000).unwrap();
+        assert!(tx(&[0xffffffff]).is_absolute_timelock_satisfied(height, time));
+        assert!(!tx(&[0xfffffffe]).is_absolute_timelock_satisfied(height, time));
+    }
+
+    #[test]
+    fn transaction_is_final() {
+        let tx = |lock_time: u32, sequence: Sequence| Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::from_consensus(lock_time),
+            input: vec![TxIn { sequence, ..TxIn::EMPTY_COINBASE }],
+            output: vec![],
+        };
+        let height = |n| Height::from_consensus(n).unwrap();
+        let time = |n| Time::from_consensus(n).unwrap();
+
+        let tip = height(800_000);
+        let mtp = time(1_700_000_000);
+
+        // A height lock is final if it is below the height of the next block.
+        assert!(tx(799_999, Sequence::ZERO).is_final(tip, mtp));
+        assert!(tx(800_000, Sequence::ZERO).is_final(tip, mtp));
+        assert!(!tx(800_001, Sequence::ZERO).is_final(tip, mtp));
+        assert!(tx(0, Sequence::ZERO).is_final(height(0), mtp));
+
+        // A time lock is final only if it is strictly below the median time past.
+        assert!(tx(1_699_999_999, Sequence::ZERO).is_final(tip, mtp));
+        assert!(!tx(1_700_000_000, Sequence::ZERO).is_final(tip, mtp));
+        assert!(!tx(1_700_000_001, Sequence::ZERO).is_final(tip, mtp));
+
+        // The lock time of a transaction with only final sequence numbers is ignored.
+        assert!(tx(800_001, Sequence::MAX).is_final(tip, mtp));
+        assert!(tx(1_700_000_001, Sequence::MAX).is_final(tip, mtp));
+
+        // Height is not compared against time and vice versa.
+        assert!(!tx(800_001, Sequence::ZERO).is_final(tip, time(u32::MAX)));
+        assert!(!tx(1_700_000_001, Sequence::ZERO).is_final(height(499_999_999), mtp));
+
+        // The lock time itself is satisfied at the boundary.
+        let lock_time = absolute::LockTime::from_consensus(1_700_000_000);
+        assert!(lock_time.is_satisfied_by(tip, mtp));
+        assert!(!lock_time.is_satisfied_by(tip, time(1_699_999_999)));
+        let lock_time = absolute::LockTime::from_consensus(800_000);
+        assert!(lock_time.is_satisfied_by(tip, mtp));
+        assert!(!lock_time.is_satisfied_by(height(799_999), mtp));
+    }
+
     #[test]
     fn sequence_from_hex_lower() {
         let sequence = Sequence::from_hex("0xffffffff").unwrap();
@@ -1717,6 +2040,90 @@ fn txin_txout_weight() {
         }
     }
 
+    #[test]
+    fn txin_txout_size() {
+        for len in [0, 1, 252, 253, 0xffff, 0x1_0000] {
+            let mut txin =
+                TxIn { script_sig: ScriptBuf::from_bytes(vec![0; len]), ..TxIn::EMPTY_COINBASE };
+            txin.witness.push(vec![0; len]);
+            txin.witness.push([]);
+            assert_eq!(txin.base_size(), serialize(&txin).len(), "length {}", len);
+            assert_eq!(
+                txin.total_size(),
+                serialize(&txin).len() + serialize(&txin.witness).len(),
+                "length {}",
+                len
+            );
+
+            let txout = TxOut { value: Amount::ONE_SAT, script_pubkey: txin.script_sig };
+            assert_eq!(txout.size(), serialize(&txout).len(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn txin_redeem_and_witness_script() {
+        use crate::key::{CompressedPublicKey, PublicKey};
+        use crate::opcodes::all::OP_PUSHBYTES_0;
+        use crate::script::{Builder, PushBytes};
+
+        let pk = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let pk = pk.parse::<CompressedPublicKey>().unwrap();
+        let multisig = ScriptBuf::new_multisig(1, &[PublicKey::from(pk)]).unwrap();
+        let signature = [0x30; 71];
+
+        // P2SH multisig.
+        let script_pubkey = ScriptBuf::new_p2sh(multisig.script_hash().unwrap());
+        let txin = TxIn {
+            script_sig: Builder::new()
+                .push_opcode(OP_PUSHBYTES_0)
+                .push_slice(signature)
+                .push_slice(<&PushBytes>::try_from(multisig.as_bytes()).unwrap())
+                .into_script(),
+            ..TxIn::EMPTY_COINBASE
+        };
+        assert_eq!(txin.redeem_script(&script_pubkey), Some(multisig.as_script()));
+        assert_eq!(txin.witness_script(&script_pubkey), None);
+        // The last push is only a redeem script if the spent output is P2SH.
+        let p2wsh = ScriptBuf::new_p2wsh(multisig.wscript_hash().unwrap());
+        assert_eq!(txin.redeem_script(&p2wsh), None);
+        assert_eq!(txin.witness_script(&p2wsh), None);
+
+        // P2SH-P2WPKH.
+        let redeem_script = ScriptBuf::p2wpkh_redeem_script(pk);
+        let script_pubkey = ScriptBuf::new_p2sh_p2wpkh(pk);
+        let txin = TxIn {
+            script_sig: Builder::new()
+                .push_slice(<&PushBytes>::try_from(redeem_script.as_bytes()).unwrap())
+                .into_script(),
+            witness: Witness::from_slice(&[&signature[..], &pk.to_bytes()]),
+            ..TxIn::EMPTY_COINBASE
+        };
+        assert_eq!(txin.redeem_script(&script_pubkey), Some(redeem_script.as_script()));
+        assert_eq!(txin.witness_script(&script_pubkey), None);
+
+        // P2SH-P2WSH.
+        let redeem_script = ScriptBuf::p2wsh_redeem_script(&multisig).unwrap();
+        let script_pubkey = ScriptBuf::new_p2sh_p2wsh(&multisig).unwrap();
+        let witness = Witness::from_slice(&[&[][..], &signature, multisig.as_bytes()]);
+        let txin = TxIn {
+            script_sig: Builder::new()
+                .push_slice(<&PushBytes>::try_from(redeem_script.as_bytes()).unwrap())
+                .into_script(),
+            witness: witness.clone(),
+            ..TxIn::EMPTY_COINBASE
+        };
+        assert_eq!(txin.redeem_script(&script_pubkey), Some(redeem_script.as_script()));
+        assert_eq!(txin.witness_script(&script_pubkey), Some(multisig.as_script()));
+
+        // Native P2WSH has an empty `script_sig`.
+        let txin = TxIn { witness, ..TxIn::EMPTY_COINBASE };
+        assert_eq!(txin.redeem_script(&p2wsh), None);
+        assert_eq!(txin.redeem_script(&script_pubkey), None);
+        assert_eq!(txin.witness_script(&p2wsh), Some(multisig.as_script()));
+        assert_eq!(txin.witness_script(&script_pubkey), None);
+        assert_eq!(TxIn::EMPTY_COINBASE.witness_script(&p2wsh), None);
+    }
+
     #[test]
     fn tx_sigop_count() {
         let tx_hexes = [
@@ -1862,9 +2269,76 @@ fn return_none(_outpoint: &OutPoint) -> Option<TxOut> { None }
             let tx: Transaction = deserialize(&tx_bytes).unwrap();
             assert_eq!(tx.total_sigop_cost(spent_fn), *expected);
             assert_eq!(tx.total_sigop_cost(return_none), *expected_none);
+
+            let outpoint = tx.input[0].previous_output;
+            assert_eq!(
+                tx.total_sigop_cost_checked(return_none),
+                Err(MissingPrevoutError(outpoint))
+            );
         }
     }
 
+    #[test]
+    fn txout_script_type() {
+        let txout = |script_pubkey| TxOut { value: Amount::ONE_SAT, script_pubkey };
+
+        let hash = PubkeyHash::from_byte_array([0xab; 20]);
+        assert_eq!(txout(ScriptBuf::new_p2pkh(hash)).script_type(), ScriptType::P2pkh(hash));
+        let hash = WPubkeyHash::from_byte_array([0xab; 20]);
+        assert_eq!(txout(ScriptBuf::new_p2wpkh(hash)).script_type(), ScriptType::P2wpkh(hash));
+        assert_eq!(txout(ScriptBuf::new_p2a()).script_type(), ScriptType::P2a);
+        assert_eq!(txout(ScriptBuf::new_op_return([0x01])).script_type(), ScriptType::OpReturn);
+        assert_eq!(txout(ScriptBuf::new()).script_type(), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn tx_sigop_cost_checked() {
+        // P2WPKH and P2PKH inputs, the P2WPKH output is only known for the first input.
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE, TxIn::EMPTY_COINBASE],
+            output: vec![],
+        };
+        tx.input[0].previous_output = OutPoint { txid: Txid::all_zeros(), vout: 1 };
+        tx.input[0].witness.push([0x30; 72]);
+        tx.input[0].witness.push([0x02; 33]);
+        tx.input[1].previous_output = OutPoint { txid: Txid::all_zeros(), vout: 2 };
+        let p2wpkh = TxOut {
+            value: Amount::ONE_SAT,
+            script_pubkey: ScriptBuf::new_p2wpkh(WPubkeyHash::from_byte_array([0; 20])),
+        };
+        let p2pkh = TxOut {
+            value: Amount::ONE_SAT,
+            script_pubkey: ScriptBuf::new_p2pkh(PubkeyHash::from_byte_array([0; 20])),
+        };
+
+        let first = |outpoint: &OutPoint| (outpoint.vout == 1).then(|| p2wpkh.clone());
+        assert_eq!(tx.total_sigop_cost(first), 1);
+        assert_eq!(
+            tx.total_sigop_cost_checked(first),
+            Err(MissingPrevoutError(tx.input[1].previous_output))
+        );
+
+        let both = |outpoint: &OutPoint| {
+            Some(if outpoint.vout == 1 { p2wpkh.clone() } else { p2pkh.clone() })
+        };
+        assert_eq!(tx.total_sigop_cost_checked(both), Ok(1));
+
+        // Legacy sigops are scaled, adding a P2PKH output costs 4.
+        tx.output.push(p2pkh.clone());
+        assert_eq!(tx.total_sigop_cost_checked(both), Ok(5));
+
+        // The previous outputs of a coinbase are never looked up.
+        let coinbase = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE],
+            output: vec![p2pkh],
+        };
+        assert_eq!(coinbase.total_sigop_cost_checked(|_| panic!("looked up")), Ok(4));
+    }
+
     #[test]
     fn weight_predictions() {
         // TXID 3d3381f968e3a73841cba5e73bf47dcea9f25a9f7663c51c81f1db8229a309a0
@@ -1934,6 +2408,109 @@ fn weight_prediction_const_from_slices() {
         assert_eq!(weight, Weight::from_wu(2493));
     }
 
+    #[test]
+    fn input_weight_predictions_taproot() {
+        // The fully signed transaction from the BIP-341 key path spending test vectors.
+        let tx_raw = hex!(
+            "020000000001097de20cbff686da83a54981d2b9bab3586f4ca7e48f\
+             57f5b55963115f3b334e9c010000000000000000d7b7cab57b1393ac\
+             e2d064f4d4a2cb8af6def61273e127517d44759b6dafdd9900000000\
+             00fffffffff8e1f583384333689228c5d28eac13366be082dc574417\
+             60d957275419a41842000000006b4830450221008f3b8f8f0537c420\
+             654d2283673a761b7ee2ea3c130753103e08ce79201cf32a022079e7\
+             ab904a1980ef1c5890b648c8783f4d10103dd62f740d13daa79e298d\
+             50c201210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28\
+             d959f2815b16f81798fffffffff0689180aa63b30cb162a73c6d2a38\
+             b7eeda2a83ece74310fda0843ad604853b0100000000feffffffaa52\
+             02bdf6d8ccd2ee0f0202afbbb7461d9264a25e5bfd3c5a52ee1239e0\
+             ba6c0000000000feffffff956149bdc66faa968eb2be2d2faa29718a\
+             cbfe3941215893a2a3446d32acd050000000000000000000e664b977\
+             3b88c09c32cb70a2a3e4da0ced63b7ba3b22f848531bbb1d5d5f4c94\
+             010000000000000000e9aa6b8e6c9de67619e6a3924ae25696bb7b69\
+             4bb677a632a74ef7eadfd4eabf0000000000ffffffffa778eb6a263d\
+             c090464cd125c466b5a99667720b1c110468831d058aa1b82af10100\
+             000000ffffffff0200ca9a3b000000001976a91406afd46bcdfd22ef\
+             94ac122aa11f241244a37ecc88ac807840cb0000000020ac9a87f559\
+             4be208f8532db38cff670c450ed2fea8fcdefcc9a663f78bab962b01\
+             41ed7c1647cb97379e76892be0cacff57ec4a7102aa24296ca39af75\
+             41246d8ff14d38958d4cc1e2e478e4d4a764bbfd835b16d4e314b729\
+             37b29833060b87276c030141052aedffc554b41f52b521071793a6b8\
+             8d6dbca9dba94cf34c83696de0c1ec35ca9c5ed4ab28059bd606a4f3\
+             a657eec0bb96661d42921b5f50a95ad33675b54f83000141ff45f742\
+             a876139946a149ab4d9185574b98dc919d2eb6754f8abaa59d18b025\
+             637a3aa043b91817739554f4ed2026cf8022dbd83e351ce1fabc2728\
+             41d2510a010140b4010dd48a617db09926f729e79c33ae0b4e94b79f\
+             04a1ae93ede6315eb3669de185a17d2b0ac9ee09fd4c64b678a0b61a\
+             0a86fa888a273c8511be83bfd6810f0247304402202b795e4de72646\
+             d76eab3f0ab27dfa30b810e856ff3a46c9a702df53bb0d8cc302203c\
+             cc4d822edab5f35caddb10af1be93583526ccfbade4b4ead350781e2\
+             f8adcd012102f9308a019258c31049344f85f89d5229b531c845836f\
+             99b08601f113bce036f90141a3785919a2ce3c4ce26f298c3d51619b\
+             c474ae24014bcdd31328cd8cfbab2eff3395fa0a16fe5f486d12f22a\
+             9cedded5ae74feb4bbe5351346508c5405bcfee0020141ea0c6ba907\
+             63c2d3a296ad82ba45881abb4f426b3f87af162dd24d5109edc1cdd1\
+             1915095ba47c3a9963dc1e6c432939872bc49212fe34c632cd3ab9fe\
+             d429c4820141bbc9584a11074e83bc8c6759ec55401f0ae7b03ef290\
+             c3139814f545b58a9f8127258000874f44bc46db7646322107d4d86a\
+             ec8e73b8719a61fff761d75b5dd9810065cd1d"
+        );
+        let tx: Transaction = deserialize(&tx_raw).unwrap();
+        let predictions =
+            (0..tx.input.len()).map(|i| tx.input_weight_prediction(i).unwrap()).collect::<Vec<_>>();
+        assert_eq!(predict_weight(predictions, tx.script_pubkey_lens()), tx.weight());
+
+        let weight = |i| tx.input_weight_prediction(i).unwrap().total_weight();
+        assert_eq!(weight(1), InputWeightPrediction::P2TR_KEY_NON_DEFAULT_SIGHASH.total_weight());
+        assert_eq!(weight(2), InputWeightPrediction::P2PKH_COMPRESSED_MAX.total_weight());
+        assert_eq!(weight(4), InputWeightPrediction::P2TR_KEY_DEFAULT_SIGHASH.total_weight());
+        assert_eq!(weight(5), InputWeightPrediction::ground_p2wpkh(1).total_weight());
+        assert!(tx.input_weight_prediction(tx.input.len()).is_err());
+
+        // Adding an annex to a key path spend.
+        let mut tx = tx;
+        let annex = [0x50; 300];
+        tx.input[4].witness.push(annex);
+        let mut predictions =
+            (0..tx.input.len()).map(|i| tx.input_weight_prediction(i).unwrap()).collect::<Vec<_>>();
+        assert_eq!(predict_weight(predictions.clone(), tx.script_pubkey_lens()), tx.weight());
+        predictions[4] = InputWeightPrediction::p2tr_key_default_sighash_with_annex(annex.len());
+        assert_eq!(predict_weight(predictions, tx.script_pubkey_lens()), tx.weight());
+        assert_eq!(
+            InputWeightPrediction::p2tr_key_non_default_sighash_with_annex(1).witness_weight(),
+            InputWeightPrediction::P2TR_KEY_NON_DEFAULT_SIGHASH.witness_weight()
+                + Weight::from_wu(2)
+        );
+    }
+
+    #[test]
+    fn input_weight_predictions_mainnet_block() {
+        let raw_block = include_bytes!("../../tests/data/mainnet_block_000000000000000000000c835b2adcaedc20fdf6ee440009c249452c726dafae.raw");
+        let block: crate::Block = deserialize(&raw_block[..]).unwrap();
+        let (_, txs) = block.into_parts();
+
+        // Includes transactions with more than 252 inputs, i.e. a three byte input count.
+        assert!(txs.iter().any(|tx| tx.input.len() > 252));
+        for tx in &txs {
+            let predictions = (0..tx.input.len()).map(|i| tx.input_weight_prediction(i).unwrap());
+            assert_eq!(predict_weight(predictions, tx.script_pubkey_lens()), tx.weight());
+
+            for (i, input) in tx.input.iter().enumerate() {
+                let witness = input.witness.iter().map(|element| element.len()).collect::<Vec<_>>();
+                let expected = match (input.script_sig.len(), &witness[..]) {
+                    (0, [72, 33]) => InputWeightPrediction::P2WPKH_MAX,
+                    (0, [71, 33]) => InputWeightPrediction::ground_p2wpkh(1),
+                    (23, [72, 33]) => InputWeightPrediction::NESTED_P2WPKH_MAX,
+                    (23, [71, 33]) => InputWeightPrediction::ground_nested_p2wpkh(1),
+                    (107, []) => InputWeightPrediction::P2PKH_COMPRESSED_MAX,
+                    (106, []) => InputWeightPrediction::ground_p2pkh_compressed(1),
+                    _ => continue,
+                };
+                let predicted = tx.input_weight_prediction(i).unwrap();
+                assert_eq!(predicted.total_weight(), expected.total_weight());
+            }
+        }
+    }
+
     #[test]
     fn sequence_debug_output() {
         let seq = Sequence::from_seconds_floor(1000);
@@ -7,9 +7,12 @@
 use internals::compact_size;
 use io::{BufRead, Write};
 
-use crate::consensus::encode::{self, Error, ReadExt, WriteExt, MAX_VEC_SIZE};
+use crate::consensus::encode::{
+    self, Error, ReadBytesFromFiniteReaderOpts, ReadExt, WriteExt, MAX_VEC_SIZE,
+};
 use crate::consensus::{Decodable, Encodable};
 use crate::crypto::ecdsa;
+use crate::crypto::key::{CompressedPublicKey, XOnlyPublicKey};
 use crate::prelude::Vec;
 #[cfg(doc)]
 use crate::script::ScriptExt as _;
@@ -38,31 +41,26 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
         if witness_elements == 0 {
             Ok(Witness::default())
         } else {
-            // Leave space at the head for element positions.
-            // We will rotate them to the end of the Vec later.
-            let witness_index_space = witness_elements * 4;
-            let mut cursor = witness_index_space;
-
-            // this number should be determined as high enough to cover most witness, and low enough
-            // to avoid wasting space without reallocating
-            let mut content = vec![0u8; cursor + 128];
-
-            for i in 0..witness_elements {
+            // Only allocate for the elements as they are read, the element positions are
+            // appended after the content once all of them are known.
+            let mut content = Vec::new();
+            let mut positions = Vec::with_capacity(encode::preallocation_capacity::<usize>(
+                witness_elements as u64,
+            ));
+
+            for _ in 0..witness_elements {
                 let element_size = r.read_compact_size()? as usize;
                 let element_size_len = compact_size::encoded_size(element_size);
-                let required_len = cursor
+                let required_len = content
+                    .len()
                     .checked_add(element_size)
-                    .ok_or(encode::Error::Parse(encode::ParseError::OversizedVectorAllocation {
-                        requested: usize::MAX,
-                        max: MAX_VEC_SIZE,
-                    }))?
-                    .checked_add(element_size_len)
+                    .and_then(|len| len.checked_add(element_size_len))
                     .ok_or(encode::Error::Parse(encode::ParseError::OversizedVectorAllocation {
                         requested: usize::MAX,
                         max: MAX_VEC_SIZE,
                     }))?;
 
-                if required_len > MAX_VEC_SIZE + witness_index_space {
+                if required_len > MAX_VEC_SIZE {
                     return Err(encode::ParseError::OversizedVectorAllocation {
                         requested: required_len,
                         max: MAX_VEC_SIZE,
@@ -70,35 +68,24 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
                     .into());
                 }
 
-                // We will do content.rotate_left(witness_index_space) later.
-                // Encode the position's value AFTER we rotate left.
-                encode_cursor(&mut content, 0, i, cursor - witness_index_space);
-
-                resize_if_needed(&mut content, required_len);
-                cursor += (&mut content[cursor..cursor + element_size_len])
-                    .emit_compact_size(element_size)?;
-                r.read_exact(&mut content[cursor..cursor + element_size])?;
-                cursor += element_size;
+                positions.push(content.len());
+                content.emit_compact_size(element_size)?;
+                let opts = ReadBytesFromFiniteReaderOpts {
+                    len: element_size,
+                    chunk_size: encode::MAX_PREALLOCATION,
+                };
+                encode::append_bytes_from_finite_reader(r, &mut content, opts)?;
+            }
+            let indices_start = content.len();
+            content.resize(indices_start + witness_elements * 4, 0);
+            for (i, position) in positions.into_iter().enumerate() {
+                encode_cursor(&mut content, indices_start, i, position);
             }
-            content.truncate(cursor);
-            // Index space is now at the end of the Vec
-            content.rotate_left(witness_index_space);
-            let indices_start = cursor - witness_index_space;
             Ok(Witness::from_parts__unstable(content, witness_elements, indices_start))
         }
     }
 }
 
-fn resize_if_needed(vec: &mut Vec<u8>, required_len: usize) {
-    if required_len >= vec.len() {
-        let mut new_len = vec.len().max(1);
-        while new_len <= required_len {
-            new_len *= 2;
-        }
-        vec.resize(new_len, 0);
-    }
-}
-
 impl Encodable for Witness {
     // `self.content` includes the varints so encoding here includes them, as expected.
     fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
@@ -121,24 +108,23 @@ pub trait WitnessExt impl for Witness {
         /// serialized public key. Also useful for spending a P2SH-P2WPKH output.
         ///
         /// It is expected that `pubkey` is related to the secret key used to create `signature`.
-        fn p2wpkh(signature: ecdsa::Signature, pubkey: secp256k1::PublicKey) -> Witness {
+        fn p2wpkh(signature: &ecdsa::Signature, pubkey: &CompressedPublicKey) -> Witness {
             let mut witness = Witness::new();
-            witness.push(signature.serialize());
-            witness.push(pubkey.serialize());
+            witness.push_p2wpkh(signature, pubkey);
             witness
         }
 
         /// Constructs a new witness required to do a key path spend of a P2TR output.
         fn p2tr_key_spend(signature: &taproot::Signature) -> Witness {
             let mut witness = Witness::new();
-            witness.push(signature.serialize());
+            witness.push_taproot_signature(signature);
             witness
         }
 
         /// Finishes constructing the P2TR script spend witness by pushing the required items.
         fn push_p2tr_script_spend(&mut self, script: &Script, control_block: &ControlBlock<impl AsRef<TaprootMerkleBranch>>, annex: Option<&[u8]>) {
-            self.push(script.as_bytes());
-            self.push(&*control_block.encode_to_arrayvec());
+            self.push_script(script);
+            self.push_control_block(control_block);
             if let Some(annex) = annex {
                 self.push(annex);
             }
@@ -147,10 +133,59 @@ fn push_p2tr_script_spend(&mut self, script: &Script, control_block: &ControlBlo
         /// Pushes, as a new element on the witness, an ECDSA signature.
         ///
         /// Pushes the DER encoded signature + sighash_type, requires an allocation.
-        fn push_ecdsa_signature(&mut self, signature: ecdsa::Signature) {
+        fn push_ecdsa_signature(&mut self, signature: &ecdsa::Signature) {
+            self.push(signature.serialize())
+        }
+
+        /// Pushes the elements required to spend a P2WPKH output.
+        ///
+        /// Pushes the DER encoded signature + sighash_type followed by the compressed public key,
+        /// see [`Witness::p2wpkh`].
+        fn push_p2wpkh(&mut self, signature: &ecdsa::Signature, pubkey: &CompressedPublicKey) {
+            self.push_ecdsa_signature(signature);
+            self.push_public_key(pubkey);
+        }
+
+        /// Pushes, as a new element on the witness, a Taproot Schnorr signature.
+        ///
+        /// Pushes the 64 byte signature, followed by the sighash type unless it is `Default`.
+        fn push_taproot_signature(&mut self, signature: &taproot::Signature) {
             self.push(signature.serialize())
         }
 
+        /// Pushes, as a new element on the witness, a 33 byte compressed public key.
+        fn push_public_key(&mut self, pubkey: &CompressedPublicKey) {
+            self.push(pubkey.to_bytes())
+        }
+
+        /// Pushes, as a new element on the witness, a 32 byte x-only public key.
+        fn push_x_only_public_key(&mut self, pubkey: &XOnlyPublicKey) {
+            self.push(pubkey.serialize())
+        }
+
+        /// Pushes, as a new element on the witness, the bytes of `script`.
+        ///
+        /// Use this for the witness script of a P2WSH spend or the leaf script of a P2TR script
+        /// spend.
+        fn push_script(&mut self, script: &Script) {
+            self.push(script.as_bytes())
+        }
+
+        /// Pushes, as a new element on the witness, a serialized Taproot control block.
+        fn push_control_block(&mut self, control_block: &ControlBlock<impl AsRef<TaprootMerkleBranch>>) {
+            self.push(&*control_block.encode_to_arrayvec())
+        }
+
+        /// Returns the number of bytes `element` would occupy once pushed onto a witness.
+        ///
+        /// This is the length of the element plus the length of its compact size prefix, i.e. the
+        /// amount by which [`Witness::size`] grows when `element` is pushed. Accepts
+        /// anything that can be passed to [`Witness::push`], e.g. a `&PushBytes`.
+        fn element_consensus_size<T: AsRef<[u8]>>(element: T) -> usize {
+            let len = element.as_ref().len();
+            compact_size::encoded_size(len) + len
+        }
+
         /// Get Tapscript following BIP341 rules regarding accounting for an annex.
         ///
         /// This does not guarantee that this represents a P2TR [`Witness`]. It
@@ -200,13 +235,36 @@ fn taproot_control_block(&self) -> Option<&[u8]> {
 
         /// Get the taproot annex following BIP341 rules.
         ///
+        /// The last element is the annex if there are at least two elements and it starts with
+        /// `0x50`. A single element starting with `0x50` is a signature, not an annex. Use
+        /// [`taproot::Annex::from_witness`] for the typed version.
+        ///
         /// This does not guarantee that this represents a P2TR [`Witness`].
         ///
         /// See [`Script::is_p2tr`] to check whether this is actually a Taproot witness.
+        #[doc(alias = "annex")]
         fn taproot_annex(&self) -> Option<&[u8]> {
             P2TrSpend::from_witness(self)?.annex()
         }
 
+        /// Returns the last element, skipping the annex if there is one.
+        ///
+        /// See [`Witness::taproot_annex`] for when the last element is an annex.
+        fn last_ignoring_annex(&self) -> Option<&[u8]> {
+            match self.taproot_annex() {
+                Some(_) => self.get_back(1),
+                None => self.last(),
+            }
+        }
+
+        /// Returns an iterator over the elements, skipping the annex if there is one.
+        ///
+        /// See [`Witness::taproot_annex`] for when the last element is an annex.
+        fn without_annex(&self) -> core::iter::Take<Iter<'_>> {
+            let len = self.len() - usize::from(self.taproot_annex().is_some());
+            self.iter().take(len)
+        }
+
         /// Get the p2wsh witness script following BIP141 rules.
         ///
         /// This does not guarantee that this represents a P2WS [`Witness`].
@@ -214,9 +272,139 @@ fn taproot_annex(&self) -> Option<&[u8]> {
         /// See [`Script::is_p2wsh`] to check whether this is actually a P2WSH witness.
         fn witness_script(&self) -> Option<&Script> { self.last().map(Script::from_bytes) }
 
+        /// Classifies the witness by the type of spend it satisfies.
+        ///
+        /// Without the spent output some witnesses are ambiguous, see [`WitnessSpend`] for the
+        /// rules used. Malformed elements never cause a panic, they lead to a more generic type.
+        fn classify(&self) -> WitnessSpend<'_> { WitnessSpend::from_witness(self) }
     }
 }
 
+/// The type of spend a [`Witness`] satisfies, along with the data parsed from it.
+///
+/// Obtained by calling `Witness::classify`. Since the spent output is not known,
+/// the first of the following that matches is returned:
+///
+/// 1. [`WitnessSpend::P2wpkh`] for exactly two elements, a valid ECDSA signature (including the
+///    standard sighash type suffix) and a compressed public key.
+/// 2. [`WitnessSpend::TaprootKeySpend`] for a single valid Schnorr signature, optionally followed
+///    by an annex.
+/// 3. [`WitnessSpend::TaprootScriptSpend`] if the last element (excluding the annex) is a valid
+///    control block.
+/// 4. [`WitnessSpend::Unknown`] if there is an annex or if the witness is empty.
+/// 5. [`WitnessSpend::P2wsh`] otherwise.
+///
+/// As per BIP-341 the annex is the last element if there are at least two elements and it starts
+/// with `0x50`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WitnessSpend<'a> {
+    /// Spend of a P2WPKH (or P2SH-P2WPKH) output.
+    P2wpkh {
+        /// The signature.
+        signature: ecdsa::Signature,
+        /// The public key.
+        pubkey: CompressedPublicKey,
+    },
+    /// Spend of a P2WSH (or P2SH-P2WSH) output.
+    P2wsh {
+        /// The initial stack the witness script is executed with.
+        stack: WitnessStack<'a>,
+        /// The witness script.
+        witness_script: &'a Script,
+    },
+    /// Key path spend of a P2TR output.
+    TaprootKeySpend {
+        /// The signature.
+        signature: taproot::Signature,
+        /// The annex, including the `0x50` prefix.
+        annex: Option<&'a [u8]>,
+    },
+    /// Script path spend of a P2TR output.
+    TaprootScriptSpend {
+        /// The initial stack the leaf script is executed with.
+        stack: WitnessStack<'a>,
+        /// The leaf script.
+        script: &'a Script,
+        /// The control block.
+        control_block: ControlBlock,
+        /// The annex, including the `0x50` prefix.
+        annex: Option<&'a [u8]>,
+    },
+    /// Either an empty witness or a Taproot witness with an annex that could not be parsed.
+    Unknown,
+}
+
+impl<'a> WitnessSpend<'a> {
+    /// Classifies `witness`.
+    pub fn from_witness(witness: &'a Witness) -> Self {
+        if witness.len() == 2 {
+            let signature = ecdsa::Signature::from_slice(&witness[0]);
+            let pubkey = CompressedPublicKey::from_slice(&witness[1]);
+            if let (Ok(signature), Ok(pubkey)) = (signature, pubkey) {
+                return WitnessSpend::P2wpkh { signature, pubkey };
+            }
+        }
+
+        let annex = match P2TrSpend::from_witness(witness) {
+            None => return WitnessSpend::Unknown,
+            Some(P2TrSpend::Key { annex }) => {
+                if let Ok(signature) = taproot::Signature::from_slice(&witness[0]) {
+                    return WitnessSpend::TaprootKeySpend { signature, annex };
+                }
+                annex
+            }
+            Some(P2TrSpend::Script { leaf_script, control_block, annex }) => {
+                if let Ok(control_block) = ControlBlock::decode(control_block) {
+                    let len = witness.len() - 2 - usize::from(annex.is_some());
+                    let stack = WitnessStack { witness, len };
+                    return WitnessSpend::TaprootScriptSpend {
+                        stack,
+                        script: leaf_script,
+                        control_block,
+                        annex,
+                    };
+                }
+                annex
+            }
+        };
+        if annex.is_some() {
+            return WitnessSpend::Unknown;
+        }
+
+        let witness_script = witness.witness_script().expect("witness is not empty");
+        let stack = WitnessStack { witness, len: witness.len() - 1 };
+        WitnessSpend::P2wsh { stack, witness_script }
+    }
+}
+
+/// The leading elements of a [`Witness`] which make up the initial stack of a script.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WitnessStack<'a> {
+    witness: &'a Witness,
+    len: usize,
+}
+
+impl<'a> WitnessStack<'a> {
+    /// Returns the number of elements on the stack.
+    pub fn len(&self) -> usize { self.len }
+
+    /// Returns `true` if the stack has no elements.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    /// Returns the element at `index`, where 0 is the first element of the witness.
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        if index < self.len {
+            self.witness.get(index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the elements of the stack.
+    pub fn iter(&self) -> core::iter::Take<Iter<'a>> { self.witness.iter().take(self.len) }
+}
+
 /// Represents a possible Taproot spend.
 ///
 /// Taproot can be spent as key spend or script spend and, depending on which it is, different data
@@ -323,7 +511,8 @@ mod test {
     use super::*;
     use crate::consensus::{deserialize, encode, serialize};
     use crate::hex::DisplayHex;
-    use crate::sighash::EcdsaSighashType;
+    use crate::script::PushBytesBuf;
+    use crate::sighash::{EcdsaSighashType, TapSighashType};
     use crate::Transaction;
 
     #[test]
@@ -348,13 +537,131 @@ fn push_ecdsa_sig() {
         let signature = secp256k1::ecdsa::Signature::from_der(&sig_bytes).unwrap();
         let mut witness = Witness::default();
         let signature = crate::ecdsa::Signature { signature, sighash_type: EcdsaSighashType::All };
-        witness.push_ecdsa_signature(signature);
+        witness.push_ecdsa_signature(&signature);
         let expected_witness = vec![hex!(
             "304402207c800d698f4b0298c5aac830b822f011bb02df41eb114ade9a6702f364d5e39c0220366900d2a60cab903e77ef7dd415d46509b1f78ac78906e3296f495aa1b1b54101")
             ];
         assert_eq!(witness.to_vec(), expected_witness);
     }
 
+    #[test]
+    fn push_p2wpkh() {
+        // First input of the transaction in `tx()` below.
+        let sig_bytes = hex!("304502210084622878c94f4c356ce49c8e33a063ec90f6ee9c0208540888cfab056cd1fca9022014e8dbfdfa46d318c6887afd92dcfa54510e057565e091d64d2ee3a66488f82c");
+        let pk_bytes = hex!("026e181ffb98ebfe5a64c983073398ea4bcd1548e7b971b4c175346a25a1c12e95");
+        let signature = secp256k1::ecdsa::Signature::from_der(&sig_bytes).unwrap();
+        let signature = crate::ecdsa::Signature { signature, sighash_type: EcdsaSighashType::All };
+        let pubkey = CompressedPublicKey::from_slice(&pk_bytes).unwrap();
+
+        let mut witness = Witness::new();
+        witness.push_p2wpkh(&signature, &pubkey);
+        assert_eq!(witness, Witness::p2wpkh(&signature, &pubkey));
+
+        let expected = hex!("0248304502210084622878c94f4c356ce49c8e33a063ec90f6ee9c0208540888cfab056cd1fca9022014e8dbfdfa46d318c6887afd92dcfa54510e057565e091d64d2ee3a66488f82c0121026e181ffb98ebfe5a64c983073398ea4bcd1548e7b971b4c175346a25a1c12e95");
+        assert_eq!(serialize(&witness), expected);
+    }
+
+    #[test]
+    fn push_taproot_sig() {
+        let sig_bytes = hex!("8b1f6f3a6a2f4bd2a31a3b8ab11b07cdbdba3d24b31a6de73ae97c515bc5a4f8ed4f431a966c0dc0fb1decfc6cc7c3b88d114f1c3b9cd557431a2fe0328e7d0a");
+        let signature =
+            secp256k1::schnorr::Signature::from_byte_array(sig_bytes[..].try_into().unwrap());
+
+        let default = taproot::Signature { signature, sighash_type: TapSighashType::Default };
+        let mut witness = Witness::new();
+        witness.push_taproot_signature(&default);
+        assert_eq!(witness.to_vec(), vec![sig_bytes.clone()]);
+        assert_eq!(witness, Witness::p2tr_key_spend(&default));
+
+        let all = taproot::Signature { signature, sighash_type: TapSighashType::All };
+        let mut witness = Witness::new();
+        witness.push_taproot_signature(&all);
+        let mut expected = sig_bytes;
+        expected.push(0x01);
+        assert_eq!(witness.to_vec(), vec![expected]);
+    }
+
+    #[test]
+    fn push_typed_elements() {
+        let pk_bytes = hex!("026e181ffb98ebfe5a64c983073398ea4bcd1548e7b971b4c175346a25a1c12e95");
+        let x_only_bytes = hex!("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+        let script_bytes =
+            hex!("5121026e181ffb98ebfe5a64c983073398ea4bcd1548e7b971b4c175346a25a1c12e9551ae");
+        let mut control_block_bytes = vec![0xc1];
+        control_block_bytes.extend_from_slice(&x_only_bytes);
+        control_block_bytes.extend_from_slice(&[0xab; 32]);
+
+        let pubkey = CompressedPublicKey::from_slice(&pk_bytes).unwrap();
+        let x_only =
+            XOnlyPublicKey::from_byte_array(&x_only_bytes[..].try_into().unwrap()).unwrap();
+        let script = Script::from_bytes(&script_bytes);
+        let control_block = ControlBlock::decode(&control_block_bytes).unwrap();
+
+        let mut witness = Witness::new();
+        witness.push_public_key(&pubkey);
+        witness.push_x_only_public_key(&x_only);
+        witness.push_script(script);
+        witness.push_control_block(&control_block);
+        assert_eq!(
+            witness.to_vec(),
+            vec![pk_bytes.clone(), x_only_bytes.clone(), script_bytes.clone(), control_block_bytes]
+        );
+
+        let mut expected = vec![0x04, 0x21];
+        expected.extend_from_slice(&pk_bytes);
+        expected.push(0x20);
+        expected.extend_from_slice(&x_only_bytes);
+        expected.push(0x25);
+        expected.extend_from_slice(&script_bytes);
+        expected.push(0x41);
+        expected.push(0xc1);
+        expected.extend_from_slice(&x_only_bytes);
+        expected.extend_from_slice(&[0xab; 32]);
+        assert_eq!(serialize(&witness), expected);
+
+        let mut script_spend = Witness::new();
+        script_spend.push_p2tr_script_spend(script, &control_block, None);
+        assert_eq!(
+            script_spend.iter().collect::<Vec<_>>(),
+            witness.iter().skip(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn p2tr_key_spend() {
+        let sig_bytes = hex!("8b1f6f3a6a2f4bd2a31a3b8ab11b07cdbdba3d24b31a6de73ae97c515bc5a4f8ed4f431a966c0dc0fb1decfc6cc7c3b88d114f1c3b9cd557431a2fe0328e7d0a");
+        let signature =
+            secp256k1::schnorr::Signature::from_byte_array(sig_bytes[..].try_into().unwrap());
+
+        let default = taproot::Signature { signature, sighash_type: TapSighashType::Default };
+        let mut expected = vec![0x01, 0x40];
+        expected.extend_from_slice(&sig_bytes);
+        assert_eq!(serialize(&Witness::p2tr_key_spend(&default)), expected);
+
+        let single =
+            taproot::Signature { signature, sighash_type: TapSighashType::SinglePlusAnyoneCanPay };
+        let mut expected = vec![0x01, 0x41];
+        expected.extend_from_slice(&sig_bytes);
+        expected.push(0x83);
+        assert_eq!(serialize(&Witness::p2tr_key_spend(&single)), expected);
+    }
+
+    #[test]
+    fn element_consensus_size() {
+        let mut witness = Witness::new();
+        for len in [0, 1, 72, 252, 253, 254, 0xffff, 0x1_0000] {
+            let element = vec![0xab; len];
+            let before = witness.size();
+            witness.push(&element);
+            assert_eq!(witness.size(), serialize(&witness).len(), "length {}", len);
+            assert_eq!(witness.size() - before, Witness::element_consensus_size(&element));
+            assert_eq!(Witness::element_consensus_size(&element), serialize(&element).len());
+        }
+
+        let push_bytes = PushBytesBuf::try_from(vec![0; 33]).unwrap();
+        assert_eq!(Witness::element_consensus_size(push_bytes.as_push_bytes()), 34);
+    }
+
     #[test]
     fn consensus_serialize() {
         let el_0 = hex!("03d2e15674941bad4a996372cb87e1856d3652606d98562fe39c5e9e7e413f2105");
@@ -380,6 +687,181 @@ fn consensus_serialize() {
         assert_eq!(rinsed, want_witness)
     }
 
+    #[test]
+    fn classify_mainnet() {
+        // Witnesses of the mainnet transactions used in `transaction::tests::txin_txout_weight`.
+        let signature = hex!("3044022016243edad96b18c78b545325aaff80131689f681079fb107a67018cb7fb7830e02205520dae761d89728f73f1a7182157f6b5aecf653525855adb7ccb998c8e6143b01");
+        let pubkey = hex!("03b9489bde92afbcfa85129a82ffa512897105d1a27ad9806bded27e0532fc84e7");
+        let witness = Witness::from_slice(&[&signature, &pubkey]);
+        match witness.classify() {
+            WitnessSpend::P2wpkh { signature: sig, pubkey: pk } => {
+                assert_eq!(sig.sighash_type, EcdsaSighashType::All);
+                assert_eq!(sig.to_vec(), signature);
+                assert_eq!(pk.to_bytes()[..], pubkey);
+            }
+            spend => panic!("expected P2WPKH, got {:?}", spend),
+        }
+
+        let sig_1 = hex!("30440220548f11130353b3a8f943d2f14260345fc7c20bde91704c9f1cbb5456355078cd0220383ed4ed39b079b618bcb279bbc1f2ca18cb028c4641cb522c9c5868c52a0dc201");
+        let sig_2 = hex!("304402203c332ecccb3181ca82c0600520ee51fee80d3b4a6ab110945e59475ec71e44ac0220679a11f3ca9993b04ccebda3c834876f353b065bb08f50076b25f5bb93c72ae101");
+        let witness_script = hex!("52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae");
+        let witness = Witness::from_slice(&[&[][..], &sig_1, &sig_2, &witness_script]);
+        match witness.classify() {
+            WitnessSpend::P2wsh { stack, witness_script: script } => {
+                assert_eq!(script.as_bytes(), witness_script);
+                assert_eq!(stack.len(), 3);
+                assert_eq!(stack.iter().collect::<Vec<_>>(), [&[][..], &sig_1, &sig_2]);
+                assert_eq!(stack.get(2), Some(&sig_2[..]));
+                assert_eq!(stack.get(3), None);
+            }
+            spend => panic!("expected P2WSH, got {:?}", spend),
+        }
+
+        let signature = hex!("df9d1bfce71f90d68bf9e9461910b3716466bfe035c7dbabaa7791383af6c7ef405a3a1f481488a91d33cd90b098d13cb904323a3e215523aceaa04e1bb35cdb01");
+        let witness = Witness::from_slice(&[&signature]);
+        let expected = taproot::Signature::from_slice(&signature).unwrap();
+        assert_eq!(expected.sighash_type, TapSighashType::All);
+        assert_eq!(
+            witness.classify(),
+            WitnessSpend::TaprootKeySpend { signature: expected, annex: None }
+        );
+
+        // The annex is skipped for key spends.
+        let annex = hex!("50ab");
+        let witness = Witness::from_slice(&[&signature, &annex]);
+        assert_eq!(
+            witness.classify(),
+            WitnessSpend::TaprootKeySpend { signature: expected, annex: Some(&annex) }
+        );
+    }
+
+    #[test]
+    fn classify_taproot_script_spend() {
+        use crate::opcodes::all::OP_CHECKSIG;
+        use crate::script::{Builder, ScriptBuf};
+        use crate::taproot::TaprootBuilder;
+        use crate::XOnlyPublicKey;
+
+        let secp = secp256k1::Secp256k1::verification_only();
+        let internal_key = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+            .parse::<XOnlyPublicKey>()
+            .unwrap();
+        let script = Builder::new().push_slice(internal_key.serialize()).push_opcode(OP_CHECKSIG);
+        let script = script.into_script();
+        let other = ScriptBuf::from_bytes(vec![0x51]);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, script.clone())
+            .unwrap()
+            .add_leaf(1, other)
+            .unwrap()
+            .finalize(&secp, internal_key)
+            .unwrap();
+        let control_block = spend_info.control_block(&(script.clone(), LeafVersion::TapScript));
+        let control_block = control_block.unwrap();
+        let signature = [0xab; 64];
+
+        let serialized = control_block.serialize();
+        let witness = Witness::from_slice(&[&signature[..], script.as_bytes(), &serialized]);
+        match witness.classify() {
+            WitnessSpend::TaprootScriptSpend { stack, script: leaf, control_block: cb, annex } => {
+                assert_eq!(stack.iter().collect::<Vec<_>>(), [&signature[..]]);
+                assert_eq!(leaf, script.as_script());
+                assert_eq!(cb, control_block);
+                assert_eq!(annex, None);
+            }
+            spend => panic!("expected script spend, got {:?}", spend),
+        }
+
+        let annex = [0x50];
+        let witness =
+            Witness::from_slice(&[&signature[..], script.as_bytes(), &serialized, &annex]);
+        match witness.classify() {
+            WitnessSpend::TaprootScriptSpend { stack, annex: Some(a), .. } => {
+                assert_eq!(stack.len(), 1);
+                assert_eq!(a, annex);
+            }
+            spend => panic!("expected script spend with annex, got {:?}", spend),
+        }
+
+        // A control block with an invalid length is not mistaken for a script spend.
+        let truncated = &serialized[..serialized.len() - 1];
+        let witness = Witness::from_slice(&[&signature[..], script.as_bytes(), truncated, &annex]);
+        assert_eq!(witness.classify(), WitnessSpend::Unknown);
+        let witness = Witness::from_slice(&[script.as_bytes(), truncated]);
+        assert!(matches!(witness.classify(), WitnessSpend::P2wsh { .. }));
+    }
+
+    #[test]
+    fn classify_edge_cases() {
+        assert_eq!(Witness::new().classify(), WitnessSpend::Unknown);
+
+        // Invalid sighash types are not signatures.
+        let mut signature = vec![0xab; 65];
+        signature[64] = 0x04;
+        let witness = Witness::from_slice(&[&signature]);
+        match witness.classify() {
+            WitnessSpend::P2wsh { stack, witness_script } => {
+                assert!(stack.is_empty());
+                assert_eq!(witness_script.as_bytes(), signature);
+            }
+            spend => panic!("expected P2WSH, got {:?}", spend),
+        }
+        let witness = Witness::from_slice(&[&signature[..], &[0x50]]);
+        assert_eq!(witness.classify(), WitnessSpend::Unknown);
+
+        // A lone annex prefix is not an annex.
+        let witness = Witness::from_slice(&[&[0x50]]);
+        assert!(matches!(witness.classify(), WitnessSpend::P2wsh { .. }));
+    }
+
+    #[test]
+    fn annex_accessors() {
+        let sig = [0x50, 0xab, 0xcd];
+        let element = [0x51, 0xab];
+        let annex = [0x50, 0x01];
+
+        // A single element is never an annex, even if it starts with 0x50.
+        for witness in [Witness::from_slice(&[&sig]), Witness::from_slice(&[&element])] {
+            assert_eq!(witness.taproot_annex(), None);
+            assert_eq!(witness.last_ignoring_annex(), witness.last());
+            assert_eq!(witness.without_annex().collect::<Vec<_>>(), witness.to_vec());
+            assert_eq!(taproot::Annex::from_witness(&witness), None);
+        }
+
+        let witness = Witness::from_slice(&[&sig[..], &element]);
+        assert_eq!(witness.taproot_annex(), None);
+        assert_eq!(witness.last_ignoring_annex(), Some(&element[..]));
+        assert_eq!(witness.without_annex().collect::<Vec<_>>(), [&sig[..], &element]);
+
+        let witness = Witness::from_slice(&[&sig[..], &annex]);
+        assert_eq!(witness.taproot_annex(), Some(&annex[..]));
+        assert_eq!(witness.last_ignoring_annex(), Some(&sig[..]));
+        assert_eq!(witness.without_annex().collect::<Vec<_>>(), [&sig[..]]);
+        let typed = taproot::Annex::from_witness(&witness).unwrap();
+        assert_eq!(typed, taproot::Annex::new(&annex).unwrap());
+        assert_eq!(typed.as_bytes(), annex);
+
+        let witness = Witness::from_slice(&[&sig[..], &element, &element]);
+        assert_eq!(witness.taproot_annex(), None);
+        assert_eq!(witness.last_ignoring_annex(), Some(&element[..]));
+        assert_eq!(witness.without_annex().count(), 3);
+
+        let witness = Witness::from_slice(&[&sig[..], &element, &annex]);
+        assert_eq!(witness.taproot_annex(), Some(&annex[..]));
+        assert_eq!(witness.last_ignoring_annex(), Some(&element[..]));
+        assert_eq!(witness.without_annex().collect::<Vec<_>>(), [&sig[..], &element]);
+
+        // An empty last element does not start with the prefix.
+        let witness = Witness::from_slice(&[&sig[..], &[]]);
+        assert_eq!(witness.taproot_annex(), None);
+        assert_eq!(witness.last_ignoring_annex(), Some(&[][..]));
+
+        let witness = Witness::new();
+        assert_eq!(witness.taproot_annex(), None);
+        assert_eq!(witness.last_ignoring_annex(), None);
+        assert_eq!(witness.without_annex().next(), None);
+    }
+
     #[test]
     fn get_tapscript() {
         let tapscript = hex!("deadbeef");
@@ -530,4 +1012,46 @@ pub fn bench_witness_to_vec(bh: &mut Bencher) {
             black_box(witness.to_vec());
         });
     }
+
+    #[bench]
+    pub fn bench_1000_element_witness_push(bh: &mut Bencher) {
+        let element = [1u8; 72];
+
+        bh.iter(|| {
+            let mut witness = Witness::new();
+            for _ in 0..1000 {
+                witness.push(element);
+            }
+            black_box(witness);
+        });
+    }
+
+    #[bench]
+    pub fn bench_1000_element_witness_with_capacity(bh: &mut Bencher) {
+        let element = [1u8; 72];
+
+        bh.iter(|| {
+            let mut witness = Witness::with_capacity(1000, 1000 * element.len());
+            witness.extend(core::iter::repeat(element).take(1000));
+            black_box(witness);
+        });
+    }
+
+    #[bench]
+    pub fn bench_1000_element_witness_from_iter(bh: &mut Bencher) {
+        let element = [1u8; 72];
+
+        bh.iter(|| {
+            black_box(core::iter::repeat(element).take(1000).collect::<Witness>());
+        });
+    }
+
+    #[bench]
+    pub fn bench_1000_element_witness_from_slice(bh: &mut Bencher) {
+        let elements = vec![[1u8; 72]; 1000];
+
+        bh.iter(|| {
+            black_box(Witness::from_slice(&elements));
+        });
+    }
 }
@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Coin selection.
+//!
+//! This module implements the branch and bound coin selection algorithm used by Bitcoin Core's
+//! wallet. It searches for a set of UTXOs that pays for a target without creating a change output.
+//!
+//! # Examples
+//!
+//! ```
+//! use bitcoin::coinselection::{select_coins_bnb, Utxo};
+//! use bitcoin::transaction::InputWeightPrediction;
+//! use bitcoin::{Amount, FeeRate};
+//!
+//! let utxo = |sat| Utxo {
+//!     value: Amount::from_sat_u32(sat),
+//!     weight_prediction: InputWeightPrediction::P2WPKH_MAX,
+//! };
+//! let utxos = [utxo(100_000), utxo(200_000), utxo(350_000)];
+//!
+//! // Spending the 350,000 sat output would need a change output.
+//! let target = Amount::from_sat_u32(300_000);
+//! let cost_of_change = Amount::from_sat_u32(10_000);
+//! let selected = select_coins_bnb(target, cost_of_change, FeeRate::ZERO, &utxos).unwrap();
+//! assert_eq!(selected.len(), 2);
+//! assert_eq!(selected[0].value, Amount::from_sat_u32(200_000));
+//! assert_eq!(selected[1].value, Amount::from_sat_u32(100_000));
+//! ```
+
+use core::convert::Infallible;
+use core::{cmp, fmt};
+
+use crate::prelude::Vec;
+use crate::transaction::InputWeightPrediction;
+use crate::{Amount, FeeRate};
+
+/// The maximum number of branches visited before giving up the search.
+const TOTAL_TRIES: usize = 100_000;
+
+/// An unspent output that can be selected to fund a transaction.
+#[derive(Copy, Clone, Debug)]
+pub struct Utxo {
+    /// The value of the output.
+    pub value: Amount,
+    /// The predicted weight of the input spending the output.
+    pub weight_prediction: InputWeightPrediction,
+}
+
+impl Utxo {
+    /// Returns the value of the output minus the fee to spend it at `fee_rate`.
+    ///
+    /// Returns [`None`] if spending the output costs at least as much as its value.
+    fn effective_value(&self, fee_rate: FeeRate) -> Option<(u64, u64)> {
+        let fee = fee_rate.to_fee(self.weight_prediction.total_weight())?.to_sat();
+        let value = self.value.to_sat().checked_sub(fee).filter(|&v| v > 0)?;
+        Some((value, fee))
+    }
+}
+
+/// Selects UTXOs whose effective values sum to at least `target` and at most
+/// `target + cost_of_change`.
+///
+/// This is the branch and bound algorithm from Bitcoin Core. The effective value of a UTXO is its
+/// value minus the fee to spend it at `fee_rate`, UTXOs with a non-positive effective value are
+/// never selected. `target` should include the fees for the rest of the transaction and
+/// `cost_of_change` is the cost of creating and later spending a change output. Selections with the
+/// least excess over `target` are preferred, the search stops as soon as an exact match is found.
+///
+/// # Returns
+///
+/// The selected UTXOs ordered by descending effective value.
+///
+/// # Errors
+///
+/// - [`SelectionError::InsufficientFunds`] if the UTXOs can not pay for `target`.
+/// - [`SelectionError::NoChangelessSolution`] if no selection was found within the search limit.
+/// - [`SelectionError::Overflow`] if the sum of the effective values overflows.
+pub fn select_coins_bnb(
+    target: Amount,
+    cost_of_change: Amount,
+    fee_rate: FeeRate,
+    utxos: &[Utxo],
+) -> Result<Vec<Utxo>, SelectionError> {
+    let target = target.to_sat();
+    let upper_bound =
+        target.checked_add(cost_of_change.to_sat()).ok_or(SelectionError::Overflow)?;
+
+    let mut pool = utxos
+        .iter()
+        .filter_map(|utxo| utxo.effective_value(fee_rate).map(|(value, fee)| (value, fee, utxo)))
+        .collect::<Vec<_>>();
+    pool.sort_by_key(|&(value, _, _)| cmp::Reverse(value));
+
+    // No overflow below because all sums of values are at most `available`.
+    let mut available = pool
+        .iter()
+        .try_fold(0_u64, |sum, &(value, _, _)| sum.checked_add(value))
+        .ok_or(SelectionError::Overflow)?;
+    if available < target {
+        return Err(SelectionError::InsufficientFunds);
+    }
+
+    let mut value = 0;
+    let mut selection: Vec<usize> = Vec::new();
+    let mut best: Option<(u64, Vec<usize>)> = None;
+
+    let mut index = 0;
+    for _ in 0..TOTAL_TRIES {
+        let backtrack = if value + available < target || value > upper_bound {
+            // Can not reach the target or already overshot it.
+            true
+        } else if value >= target {
+            let excess = value - target;
+            if best.as_ref().map_or(true, |(best_excess, _)| excess <= *best_excess) {
+                best = Some((excess, selection.clone()));
+                if excess == 0 {
+                    break;
+                }
+            }
+            true
+        } else {
+            false
+        };
+
+        if backtrack {
+            let last = match selection.last() {
+                Some(&last) => last,
+                None => break,
+            };
+            // Put the UTXOs skipped since the last selected one back before excluding it.
+            index -= 1;
+            while index > last {
+                available += pool[index].0;
+                index -= 1;
+            }
+            value -= pool[index].0;
+            selection.pop();
+        } else {
+            let (utxo_value, fee, _) = pool[index];
+            available -= utxo_value;
+            // Excluding a UTXO and then including an identical one explores the same branch.
+            let skip = index > 0
+                && selection.last() != Some(&(index - 1))
+                && (utxo_value, fee) == (pool[index - 1].0, pool[index - 1].1);
+            if !skip {
+                selection.push(index);
+                value += utxo_value;
+            }
+        }
+        index += 1;
+    }
+
+    let (_, selection) = best
+        .filter(|(_, selection)| !selection.is_empty())
+        .ok_or(SelectionError::NoChangelessSolution)?;
+    Ok(selection.into_iter().map(|i| *pool[i].2).collect())
+}
+
+/// Error returned by [`select_coins_bnb`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SelectionError {
+    /// The effective values of all UTXOs sum to less than the target.
+    InsufficientFunds,
+    /// No selection paying for the target without a change output was found.
+    NoChangelessSolution,
+    /// The sum of the effective values of the UTXOs overflowed.
+    Overflow,
+}
+
+impl From<Infallible> for SelectionError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SelectionError::*;
+
+        match *self {
+            InsufficientFunds => f.write_str("insufficient funds to pay for the target"),
+            NoChangelessSolution => f.write_str("no selection without change found"),
+            Overflow => f.write_str("the sum of the UTXO values overflowed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use SelectionError::*;
+
+        match *self {
+            InsufficientFunds | NoChangelessSolution | Overflow => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENT: u64 = 1_000_000;
+
+    fn utxo(sat: u64) -> Utxo {
+        Utxo {
+            value: Amount::from_sat(sat).unwrap(),
+            weight_prediction: InputWeightPrediction::P2WPKH_MAX,
+        }
+    }
+
+    fn select(target: u64, cost_of_change: u64, utxos: &[Utxo]) -> Option<Vec<u64>> {
+        let target = Amount::from_sat(target).unwrap();
+        let cost_of_change = Amount::from_sat(cost_of_change).unwrap();
+        let selected = select_coins_bnb(target, cost_of_change, FeeRate::ZERO, utxos).ok()?;
+        Some(selected.iter().map(|utxo| utxo.value.to_sat()).collect())
+    }
+
+    // Pool of UTXOs where the search has to visit `2^n` branches to find the solution.
+    fn make_hard_case(n: u32) -> (u64, Vec<Utxo>) {
+        let mut target = 0;
+        let mut utxos = Vec::new();
+        for i in 0..n {
+            target += 1 << (n + i);
+            utxos.push(utxo(1 << (n + i)));
+            utxos.push(utxo((1 << (n + i)) + (1 << (n - 1 - i))));
+        }
+        (target, utxos)
+    }
+
+    // Test cases from Bitcoin Core `src/wallet/test/coinselector_tests.cpp`.
+    #[test]
+    fn bnb_search() {
+        let mut pool = (1..=4).map(|c| utxo(c * CENT)).collect::<Vec<_>>();
+
+        assert_eq!(select(CENT, CENT / 2, &pool), Some(vec![CENT]));
+        assert_eq!(select(2 * CENT, CENT / 2, &pool), Some(vec![2 * CENT]));
+        assert_eq!(select(5 * CENT, CENT / 2, &pool), Some(vec![4 * CENT, CENT]));
+        // Insufficient funds.
+        assert_eq!(select(11 * CENT, CENT / 2, &pool), None);
+        // Cost of change is greater than the difference between target and selection.
+        assert_eq!(select(CENT * 9 / 10, CENT / 2, &pool), Some(vec![CENT]));
+        // Cost of change is less than the difference between target and selection.
+        assert_eq!(select(CENT * 9 / 10, 0, &pool), None);
+
+        pool.push(utxo(5 * CENT));
+        assert_eq!(select(10 * CENT, CENT / 2, &pool), Some(vec![5 * CENT, 4 * CENT, CENT]));
+        assert_eq!(select(CENT / 4, CENT / 2, &pool), None);
+
+        // Never finds a solution when all UTXOs are too big.
+        let pool = (5..=20).map(|c| utxo(c * CENT)).collect::<Vec<_>>();
+        assert_eq!(select(CENT, 2 * CENT, &pool), None);
+    }
+
+    #[test]
+    fn bnb_search_iteration_limit() {
+        let (target, pool) = make_hard_case(17);
+        assert_eq!(select(target, 0, &pool), None);
+
+        let (target, pool) = make_hard_case(14);
+        assert_eq!(select(target, 0, &pool).unwrap().iter().sum::<u64>(), target);
+
+        // Identical UTXOs do not exhaust the search.
+        let mut pool = vec![utxo(7 * CENT); 4];
+        pool.push(utxo(2 * CENT));
+        pool.extend(core::iter::repeat(utxo(5 * CENT)).take(50_000));
+        assert_eq!(
+            select(30 * CENT, 5_000, &pool),
+            Some(vec![7 * CENT, 7 * CENT, 7 * CENT, 7 * CENT, 2 * CENT])
+        );
+    }
+
+    #[test]
+    fn bnb_search_effective_value() {
+        let fee_rate = FeeRate::from_sat_per_vb_unchecked(10);
+        // P2WPKH_MAX inputs weigh 272 wu, 680 sats at 10 sat/vB.
+        let fee = 680;
+        let pool = [utxo(5 * CENT + fee), utxo(3 * CENT + fee), utxo(2 * CENT + fee), utxo(fee)];
+
+        let selected = select_coins_bnb(
+            Amount::from_sat(10 * CENT).unwrap(),
+            Amount::from_sat_u32(5_000),
+            fee_rate,
+            &pool,
+        )
+        .unwrap();
+        let values = selected.iter().map(|utxo| utxo.value.to_sat()).collect::<Vec<_>>();
+        assert_eq!(values, [5 * CENT + fee, 3 * CENT + fee, 2 * CENT + fee]);
+
+        // UTXOs that cost more to spend than they are worth are ignored.
+        let pool = [utxo(fee), utxo(fee / 2)];
+        assert_eq!(
+            select_coins_bnb(Amount::ZERO, Amount::ZERO, fee_rate, &pool).unwrap_err(),
+            SelectionError::NoChangelessSolution
+        );
+        assert_eq!(
+            select_coins_bnb(Amount::ONE_SAT, Amount::MAX, fee_rate, &pool).unwrap_err(),
+            SelectionError::InsufficientFunds
+        );
+    }
+
+    #[test]
+    fn bnb_search_errors() {
+        let pool = (1..=4).map(|c| utxo(c * CENT)).collect::<Vec<_>>();
+        let select = |target, cost_of_change, pool: &[Utxo]| {
+            select_coins_bnb(
+                Amount::from_sat(target).unwrap(),
+                Amount::from_sat(cost_of_change).unwrap(),
+                FeeRate::ZERO,
+                pool,
+            )
+        };
+
+        assert_eq!(select(11 * CENT, 0, &pool).unwrap_err(), SelectionError::InsufficientFunds);
+        assert_eq!(
+            select(CENT * 9 / 10, 0, &pool).unwrap_err(),
+            SelectionError::NoChangelessSolution
+        );
+
+        // 8,785 times the maximum amount does not fit in a `u64`.
+        let pool = vec![utxo(Amount::MAX.to_sat()); 8_785];
+        assert_eq!(select(CENT, 0, &pool).unwrap_err(), SelectionError::Overflow);
+    }
+}
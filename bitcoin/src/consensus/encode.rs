@@ -14,7 +14,7 @@
 //! scripts come with an opcode decode, hashes are big-endian, numbers are
 //! typically big-endian decimals, etc.)
 
-use core::mem;
+use core::{cmp, mem};
 
 use hashes::{sha256, sha256d, Hash};
 use hex::DisplayHex as _;
@@ -31,7 +31,7 @@
     address::{AddrV2Message, Address},
     message_blockdata::Inventory,
 };
-use crate::prelude::{rc, sync, Box, Cow, String, Vec};
+use crate::prelude::{rc, sync, BTreeMap, Box, Cow, String, Vec};
 use crate::taproot::TapLeafHash;
 use crate::transaction::{Transaction, TxIn, TxOut};
 
@@ -74,6 +74,9 @@ pub fn deserialize_hex<T: Decodable>(hex: &str) -> Result<T, FromHexError> {
 
 /// Deserializes an object from a vector, but will not report an error if said deserialization
 /// doesn't consume the entire vector.
+///
+/// Returns the object together with the number of bytes consumed, which allows decoding
+/// concatenated objects one after the other.
 pub fn deserialize_partial<T: Decodable>(data: &[u8]) -> Result<(T, usize), ParseError> {
     let mut decoder = Cursor::new(data);
 
@@ -259,6 +262,21 @@ fn read_compact_size(&mut self) -> Result<u64, Error> {
 /// Maximum size, in bytes, of a vector we are allowed to decode.
 pub const MAX_VEC_SIZE: usize = 4_000_000;
 
+/// Maximum number of bytes allocated for a collection before any of its elements are decoded.
+///
+/// Lengths are read from untrusted data so a few bytes of input could otherwise claim megabytes of
+/// memory. Past this limit a collection only grows as its elements are read, which keeps the
+/// allocation proportional to the data the reader actually supplied.
+pub(crate) const MAX_PREALLOCATION: usize = 128 * 1024;
+
+/// Returns the capacity to allocate for `len` elements of type `T` before decoding them.
+///
+/// The capacity is capped to [`MAX_PREALLOCATION`] bytes.
+pub(crate) fn preallocation_capacity<T>(len: u64) -> usize {
+    let max = MAX_PREALLOCATION / cmp::max(mem::size_of::<T>(), 1);
+    cmp::min(len, max.to_u64()) as usize
+}
+
 /// Data which can be encoded in a consensus-consistent way.
 pub trait Encodable {
     /// Encodes an object with a well-defined format.
@@ -294,7 +312,9 @@ pub trait Decodable: Sized {
     ///     protection might lead to resource exhaustion vulnerability.
     ///   * Put a max cap on things like `Vec::with_capacity` to avoid oversized allocations, and
     ///     rely on the reader running out of data, and collections reallocating on a legitimately
-    ///     oversized input data, instead of trying to enforce arbitrary length limits.
+    ///     oversized input data, instead of trying to enforce arbitrary length limits. No more
+    ///     than 128 KiB should be allocated for a length that has not been backed by input data
+    ///     yet, so a truncated input fails with an error before using much memory.
     /// * Types that contain other types that implement custom
     ///   `consensus_decode_from_finite_reader`, should also implement it applying same rules, and
     ///   in addition make sure to call `consensus_decode_from_finite_reader` on all members, to
@@ -324,6 +344,56 @@ fn consensus_decode<R: BufRead + ?Sized>(reader: &mut R) -> Result<Self, Error>
     }
 }
 
+/// A variable length integer, A.K.A. [`CompactSize`].
+///
+/// Bitcoin uses this encoding for lengths and counts, it can be used to prefix custom data with
+/// its length the same way. Values are encoded in 1, 3, 5 or 9 bytes and decoding rejects
+/// encodings that are longer than necessary.
+///
+/// [`CompactSize`]: <https://en.bitcoin.it/wiki/Protocol_documentation#Variable_length_integer>
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactSize(pub u64);
+
+impl CompactSize {
+    /// Encodes `value` without allocating.
+    pub fn encode(value: u64) -> impl ExactSizeIterator<Item = u8> + Clone {
+        let encoded = compact_size::encode(value);
+        (0..encoded.len()).map(move |i| encoded[i])
+    }
+
+    /// Decodes a value from the start of `bytes`.
+    ///
+    /// Returns the value and the number of bytes it was encoded in, trailing bytes are ignored.
+    ///
+    /// # Errors
+    ///
+    /// - [`ParseError::MissingData`] if `bytes` ends before the encoding.
+    /// - [`ParseError::NonMinimalVarInt`] if the value could have been encoded in fewer bytes.
+    pub fn decode(bytes: &[u8]) -> Result<(u64, usize), ParseError> {
+        let (CompactSize(value), consumed) = deserialize_partial(bytes)?;
+        Ok((value, consumed))
+    }
+
+    /// Returns the number of bytes `value` is encoded in.
+    ///
+    /// This is 1 for values up to 0xFC, 3 up to 0xFFFF, 5 up to 0xFFFF_FFFF and 9 otherwise.
+    pub const fn encoded_len(value: u64) -> usize { compact_size::encoded_size_const(value) }
+}
+
+impl Encodable for CompactSize {
+    #[inline]
+    fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        w.emit_compact_size(self.0)
+    }
+}
+
+impl Decodable for CompactSize {
+    #[inline]
+    fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
+        r.read_compact_size().map(CompactSize)
+    }
+}
+
 /// Data and a 4-byte checksum.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct CheckedData {
@@ -348,6 +418,30 @@ pub fn into_data(self) -> Vec<u8> { self.data }
     pub fn checksum(&self) -> [u8; 4] { self.checksum }
 }
 
+/// A key-value map encoded with its entries in ascending key order.
+///
+/// The entries are prefixed with their count as a [`CompactSize`] and each key is followed by its
+/// value. The encoding does not depend on the order the entries were inserted in, which makes it
+/// suitable for commitments. Decoding rejects keys that are out of order or duplicated so every
+/// map has exactly one valid encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SortedMap<K, V>(pub BTreeMap<K, V>);
+
+impl<K, V> From<BTreeMap<K, V>> for SortedMap<K, V> {
+    fn from(map: BTreeMap<K, V>) -> Self { Self(map) }
+}
+
+#[cfg(feature = "std")]
+impl<K: Ord, V, S> From<std::collections::HashMap<K, V, S>> for SortedMap<K, V> {
+    fn from(map: std::collections::HashMap<K, V, S>) -> Self { map.into_iter().collect() }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SortedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 // Primitive types
 macro_rules! impl_int_encodable {
     ($ty:ident, $meth_dec:ident, $meth_enc:ident) => {
@@ -522,14 +616,11 @@ fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(
                 r: &mut R,
             ) -> core::result::Result<Self, Error> {
                 let len = r.read_compact_size()?;
-                // Do not allocate upfront more items than if the sequence of type
-                // occupied roughly quarter a block. This should never be the case
-                // for normal data, but even if that's not true - `push` will just
-                // reallocate.
+                // Do not allocate upfront more than `MAX_PREALLOCATION` bytes, `push` will just
+                // reallocate for longer sequences.
                 // Note: OOM protection relies on reader eventually running out of
                 // data to feed us.
-                let max_capacity = MAX_VEC_SIZE / 4 / mem::size_of::<$type>();
-                let mut ret = Vec::with_capacity(core::cmp::min(len as usize, max_capacity));
+                let mut ret = Vec::with_capacity(preallocation_capacity::<$type>(len));
                 for _ in 0..len {
                     ret.push(Decodable::consensus_decode_from_finite_reader(r)?);
                 }
@@ -566,9 +657,9 @@ pub(crate) fn consensus_encode_with_size<W: Write + ?Sized>(
     Ok(w.emit_compact_size(data.len())? + w.emit_slice(data)?)
 }
 
-struct ReadBytesFromFiniteReaderOpts {
-    len: usize,
-    chunk_size: usize,
+pub(crate) struct ReadBytesFromFiniteReaderOpts {
+    pub(crate) len: usize,
+    pub(crate) chunk_size: usize,
 }
 
 /// Read `opts.len` bytes from reader, where `opts.len` could potentially be malicious.
@@ -578,10 +669,22 @@ struct ReadBytesFromFiniteReaderOpts {
 #[inline]
 fn read_bytes_from_finite_reader<D: Read + ?Sized>(
     d: &mut D,
-    mut opts: ReadBytesFromFiniteReaderOpts,
+    opts: ReadBytesFromFiniteReaderOpts,
 ) -> Result<Vec<u8>, Error> {
     let mut ret = vec![];
+    append_bytes_from_finite_reader(d, &mut ret, opts)?;
+    Ok(ret)
+}
 
+/// Read `opts.len` bytes from reader and append them to `ret`.
+///
+/// See [`read_bytes_from_finite_reader`].
+#[inline]
+pub(crate) fn append_bytes_from_finite_reader<D: Read + ?Sized>(
+    d: &mut D,
+    ret: &mut Vec<u8>,
+    mut opts: ReadBytesFromFiniteReaderOpts,
+) -> Result<(), Error> {
     assert_ne!(opts.chunk_size, 0);
 
     while opts.len > 0 {
@@ -593,7 +696,7 @@ fn read_bytes_from_finite_reader<D: Read + ?Sized>(
         opts.len -= chunk_size;
     }
 
-    Ok(ret)
+    Ok(())
 }
 
 impl Encodable for Vec<u8> {
@@ -608,7 +711,7 @@ impl Decodable for Vec<u8> {
     fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
         let len = r.read_compact_size()? as usize;
         // most real-world vec of bytes data, wouldn't be larger than 128KiB
-        let opts = ReadBytesFromFiniteReaderOpts { len, chunk_size: 128 * 1024 };
+        let opts = ReadBytesFromFiniteReaderOpts { len, chunk_size: MAX_PREALLOCATION };
         read_bytes_from_finite_reader(r, opts)
     }
 }
@@ -651,7 +754,7 @@ fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(r: &mut R) -> Result
         let len = u32::consensus_decode_from_finite_reader(r)? as usize;
 
         let checksum = <[u8; 4]>::consensus_decode_from_finite_reader(r)?;
-        let opts = ReadBytesFromFiniteReaderOpts { len, chunk_size: MAX_VEC_SIZE };
+        let opts = ReadBytesFromFiniteReaderOpts { len, chunk_size: MAX_PREALLOCATION };
         let data = read_bytes_from_finite_reader(r, opts)?;
         let expected_checksum = sha2_checksum(&data);
         if expected_checksum != checksum {
@@ -663,6 +766,37 @@ fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(r: &mut R) -> Result
     }
 }
 
+impl<K: Encodable, V: Encodable> Encodable for SortedMap<K, V> {
+    #[inline]
+    fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = w.emit_compact_size(self.0.len())?;
+        for (key, value) in &self.0 {
+            len += key.consensus_encode(w)?;
+            len += value.consensus_encode(w)?;
+        }
+        Ok(len)
+    }
+}
+
+impl<K: Decodable + Ord, V: Decodable> Decodable for SortedMap<K, V> {
+    #[inline]
+    fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
+        let len = r.read_compact_size()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let key = K::consensus_decode_from_finite_reader(r)?;
+            if map.keys().next_back().map_or(false, |last| *last >= key) {
+                return Err(
+                    ParseError::ParseFailed("map keys not in strictly ascending order").into()
+                );
+            }
+            let value = V::consensus_decode_from_finite_reader(r)?;
+            map.insert(key, value);
+        }
+        Ok(SortedMap(map))
+    }
+}
+
 impl<T: Encodable> Encodable for &'_ T {
     fn consensus_encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
         (**self).consensus_encode(w)
@@ -764,6 +898,8 @@ mod tests {
     use core::fmt;
     use core::mem::discriminant;
 
+    use hex::test_hex_unwrap as hex;
+
     use super::*;
     #[cfg(feature = "std")]
     use crate::p2p::{message_blockdata::Inventory, Address};
@@ -858,6 +994,71 @@ fn test_varint_len(varint: u64, expected: usize) {
         test_varint_len(u64::MAX, 9);
     }
 
+    #[test]
+    fn compact_size() {
+        let boundaries = [
+            (0xFC, &[0xFC][..]),
+            (0xFD, &[0xFD, 0xFD, 0x00]),
+            (0xFFFF, &[0xFD, 0xFF, 0xFF]),
+            (0x1_0000, &[0xFE, 0x00, 0x00, 0x01, 0x00]),
+            (0xFFFF_FFFF, &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF]),
+            (0x1_0000_0000, &[0xFF, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]),
+        ];
+        for (value, encoding) in boundaries {
+            assert_eq!(CompactSize::encode(value).collect::<Vec<_>>(), encoding);
+            assert_eq!(CompactSize::encoded_len(value), encoding.len());
+            assert_eq!(serialize(&CompactSize(value)), encoding);
+
+            // Trailing bytes are not consumed.
+            let mut data = encoding.to_vec();
+            data.push(0xAB);
+            assert_eq!(CompactSize::decode(&data), Ok((value, encoding.len())));
+            assert_eq!(
+                CompactSize::decode(&encoding[..encoding.len() - 1]),
+                Err(ParseError::MissingData)
+            );
+        }
+
+        // One less than each boundary would fit in a shorter encoding.
+        let non_minimal: [&[u8]; 3] = [
+            &[0xFD, 0xFC, 0x00],
+            &[0xFE, 0xFF, 0xFF, 0x00, 0x00],
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00],
+        ];
+        for encoding in non_minimal {
+            assert_eq!(CompactSize::decode(encoding), Err(ParseError::NonMinimalVarInt));
+        }
+        assert_eq!(CompactSize::decode(&[]), Err(ParseError::MissingData));
+    }
+
+    #[test]
+    fn sorted_map() {
+        let entries = [(3u32, vec![0xCCu8]), (1, vec![]), (2, vec![0xAA, 0xBB])];
+        let map = entries.iter().cloned().collect::<SortedMap<_, _>>();
+        let reversed = entries.iter().rev().cloned().collect::<SortedMap<_, _>>();
+        let expected = hex!("0301000000000200000002aabb0300000001cc");
+        assert_eq!(serialize(&map), expected);
+        assert_eq!(serialize(&reversed), expected);
+        #[cfg(feature = "std")]
+        {
+            let hash_map = entries.iter().cloned().collect::<std::collections::HashMap<_, _>>();
+            assert_eq!(serialize(&SortedMap::from(hash_map)), expected);
+        }
+        assert_eq!(deserialize::<SortedMap<u32, Vec<u8>>>(&expected).unwrap(), map);
+        assert_eq!(serialize(&SortedMap::<u32, u8>::default()), [0x00]);
+
+        let out_of_order = hex!("020200000000010000000a");
+        let duplicate = hex!("020100000000010000000a");
+        for data in [&out_of_order[..], &duplicate[..]] {
+            let err = deserialize::<SortedMap<u32, u8>>(data).unwrap_err();
+            assert!(
+                matches!(err, DeserializeError::Parse(ParseError::ParseFailed(_))),
+                "{:?}",
+                err
+            );
+        }
+    }
+
     #[test]
     fn deserialize_nonminimal_vec() {
         // Check the edges for variant int
@@ -1156,6 +1357,26 @@ fn deserialize_tx_hex() {
         assert!(deserialize_hex::<Transaction>(hex).is_ok())
     }
 
+    #[test]
+    fn deserialize_partial_concatenated_txs() {
+        let txs = [
+            include_str!("../../tests/data/previous_tx_0_hex"),
+            include_str!("../../tests/data/previous_tx_1_hex"),
+        ]
+        .map(|hex| deserialize_hex::<Transaction>(hex).unwrap());
+        let mut data = serialize(&txs[0]);
+        data.extend(serialize(&txs[1]));
+
+        let (first, consumed) = deserialize_partial::<Transaction>(&data).unwrap();
+        assert_eq!(first, txs[0]);
+        assert_eq!(consumed, serialize(&txs[0]).len());
+        let (second, rest) = deserialize_partial::<Transaction>(&data[consumed..]).unwrap();
+        assert_eq!(second, txs[1]);
+        assert_eq!(consumed + rest, data.len());
+
+        assert_eq!(deserialize::<Transaction>(&data), Err(DeserializeError::Unconsumed));
+    }
+
     #[test]
     fn deserialize_tx_hex_too_many_bytes() {
         use crate::consensus::DecodeError;
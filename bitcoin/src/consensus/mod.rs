@@ -19,7 +19,10 @@
 #[rustfmt::skip]                // Keep public re-exports separate.
 #[doc(inline)]
 pub use self::{
-    encode::{deserialize, deserialize_partial, serialize, Decodable, Encodable, ReadExt, WriteExt},
+    encode::{
+        deserialize, deserialize_partial, serialize, CompactSize, Decodable, Encodable, ReadExt,
+        SortedMap, WriteExt,
+    },
     error::{Error, FromHexError, DecodeError, ParseError, DeserializeError},
 };
 pub(crate) use self::error::parse_failed_error;
@@ -13,7 +13,9 @@
 use hex::FromHex;
 use internals::{impl_to_hex_from_lower_hex, write_err};
 use io::Write;
+use secp256k1::{Message, Secp256k1};
 
+use crate::crypto::key::PublicKey;
 use crate::prelude::{DisplayHex, Vec};
 use crate::script::PushBytes;
 #[cfg(doc)]
@@ -95,6 +97,25 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
     }
 }
 
+/// Verifies a batch of ECDSA signatures.
+///
+/// Each item is a message, the signature and the public key it is expected to be valid for. The
+/// sighash types of the signatures are ignored. The signatures are verified one by one and
+/// verification stops at the first invalid one.
+///
+/// # Errors
+///
+/// Returns the index of the first item with an invalid signature.
+pub fn verify_batch<C: secp256k1::Verification>(
+    secp: &Secp256k1<C>,
+    items: &[(Message, Signature, PublicKey)],
+) -> Result<(), usize> {
+    match items.iter().position(|(msg, sig, pk)| pk.verify(secp, *msg, *sig).is_err()) {
+        Some(index) => Err(index),
+        None => Ok(()),
+    }
+}
+
 /// Holds signature serialized in-line (not in `Vec`).
 ///
 /// This avoids allocation and allows proving maximum size of the signature (73 bytes).
@@ -335,6 +356,40 @@ fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn verify_batch_reports_first_invalid() {
+        let secp = Secp256k1::new();
+        let mut items = (1..=10u8)
+            .map(|i| {
+                let sk = secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap();
+                let msg = Message::from_digest([i ^ 0xff; 32]);
+                let sig = Signature::sighash_all(secp.sign_ecdsa(&msg, &sk));
+                (msg, sig, PublicKey::new(sk.public_key(&secp)))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(verify_batch(&secp, &items), Ok(()));
+        assert_eq!(verify_batch(&secp, &[]), Ok(()));
+
+        // Use the signature of item 6 for item 5.
+        let valid = items[5].1;
+        items[5].1 = items[6].1;
+        assert_eq!(verify_batch(&secp, &items), Err(5));
+
+        // Only the first failure is reported.
+        items[8].0 = items[7].0;
+        assert_eq!(verify_batch(&secp, &items), Err(5));
+        items[5].1 = valid;
+        assert_eq!(verify_batch(&secp, &items), Err(8));
+
+        // The sighash type is not part of the verified data.
+        items[8].0 = items[9].0;
+        items[8].1 = items[9].1;
+        items[8].2 = items[9].2;
+        items[9].1.sighash_type = EcdsaSighashType::None;
+        assert_eq!(verify_batch(&secp, &items), Ok(()));
+    }
+
     #[test]
     fn write_serialized_signature() {
         let hex = "3046022100839c1fbc5304de944f697c9f4b1d01d1faeba32d751c0f7acb21ac8a0f436a72022100e89bd46bb3a5a62adc679f659b7ce876d83ee297c7a5587b2011c4fcc72eab45";
@@ -534,6 +534,24 @@ pub fn from_wif(wif: &str) -> Result<PrivateKey, FromWifError> {
         })
     }
 
+    /// Parses the WIF encoded private key, requiring it to be for `network`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FromWifError::NetworkMismatch`] if `wif` is a valid key for a different network,
+    /// as well as all the errors returned by [`PrivateKey::from_wif`].
+    pub fn from_wif_for_network(
+        wif: &str,
+        network: impl Into<NetworkKind>,
+    ) -> Result<PrivateKey, FromWifError> {
+        let expected = network.into();
+        let key = PrivateKey::from_wif(wif)?;
+        if key.network != expected {
+            return Err(WifNetworkMismatchError { expected, found: key.network }.into());
+        }
+        Ok(key)
+    }
+
     /// Returns a new private key with the negated secret value.
     ///
     /// The resulting key corresponds to the same x-only public key (identical x-coordinate)
@@ -986,6 +1004,9 @@ fn from(e: secp256k1::Error) -> Self { Self::Secp256k1(e) }
 #[non_exhaustive]
 pub enum FromWifError {
     /// A base58 decoding error.
+    ///
+    /// Use [`base58::Error::incorrect_checksum`] to check whether the checksum was wrong, which
+    /// usually indicates a typo.
     Base58(base58::Error),
     /// Base58 decoded data was an invalid length.
     InvalidBase58PayloadLength(InvalidBase58PayloadLengthError),
@@ -995,6 +1016,8 @@ pub enum FromWifError {
     Secp256k1(secp256k1::Error),
     /// Invalid WIF compression flag.
     InvalidWifCompressionFlag(InvalidWifCompressionFlagError),
+    /// The key is valid but for a different network than required.
+    NetworkMismatch(WifNetworkMismatchError),
 }
 
 impl From<Infallible> for FromWifError {
@@ -1013,6 +1036,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 write_err!(f, "decoded base58 data contained an invalid address version byte"; e),
             Secp256k1(ref e) => write_err!(f, "private key validation failed"; e),
             InvalidWifCompressionFlag(ref e) => write_err!(f, "invalid WIF compression flag";e),
+            NetworkMismatch(ref e) => write_err!(f, "WIF private key network mismatch"; e),
         }
     }
 }
@@ -1028,6 +1052,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             InvalidAddressVersion(ref e) => Some(e),
             Secp256k1(ref e) => Some(e),
             InvalidWifCompressionFlag(ref e) => Some(e),
+            NetworkMismatch(ref e) => Some(e),
         }
     }
 }
@@ -1056,6 +1081,10 @@ fn from(e: InvalidWifCompressionFlagError) -> FromWifError {
     }
 }
 
+impl From<WifNetworkMismatchError> for FromWifError {
+    fn from(e: WifNetworkMismatchError) -> FromWifError { Self::NetworkMismatch(e) }
+}
+
 /// Error returned while constructing public key from string.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParsePublicKeyError {
@@ -1222,10 +1251,140 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 #[cfg(feature = "std")]
 impl std::error::Error for InvalidWifCompressionFlagError {}
 
+/// A WIF private key was for a different network than required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifNetworkMismatchError {
+    /// The required network.
+    pub(crate) expected: NetworkKind,
+    /// The network the key was encoded for.
+    pub(crate) found: NetworkKind,
+}
+
+impl WifNetworkMismatchError {
+    /// Returns the required network.
+    pub fn expected(&self) -> NetworkKind { self.expected }
+
+    /// Returns the network the key was encoded for.
+    pub fn found(&self) -> NetworkKind { self.found }
+}
+
+impl fmt::Display for WifNetworkMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |network: NetworkKind| match network {
+            NetworkKind::Main => "mainnet",
+            NetworkKind::Test => "testnet",
+        };
+        write!(f, "expected a {} key but found a {} key", name(self.expected), name(self.found))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WifNetworkMismatchError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::address::Address;
+    use crate::address::{Address, KnownHrp};
+    use crate::network::Network;
+
+    #[test]
+    fn wif_errors() {
+        let wif = "cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpy";
+        let sk = PrivateKey::from_wif_for_network(wif, NetworkKind::Test).unwrap();
+        assert_eq!(sk, PrivateKey::from_wif(wif).unwrap());
+        assert_eq!(sk.to_wif(), wif);
+
+        // A testnet key used where a mainnet key is required.
+        let err = PrivateKey::from_wif_for_network(wif, Network::Bitcoin).unwrap_err();
+        let expected =
+            WifNetworkMismatchError { expected: NetworkKind::Main, found: NetworkKind::Test };
+        assert_eq!(err, FromWifError::NetworkMismatch(expected.clone()));
+        assert_eq!(expected.expected(), NetworkKind::Main);
+        assert_eq!(expected.found(), NetworkKind::Test);
+        assert_eq!(expected.to_string(), "expected a mainnet key but found a testnet key");
+
+        // Last character changed.
+        let err = PrivateKey::from_wif("cVt4o7BGAig1UXywgGSmARhxMdzP5qvQsxKkSsc1XEkw3tDTQFpz")
+            .unwrap_err();
+        match err {
+            FromWifError::Base58(ref e) => assert!(e.incorrect_checksum().is_some()),
+            e => panic!("expected a checksum error, got {:?}", e),
+        }
+
+        let mut payload = vec![0xef];
+        payload.extend_from_slice(&[0x11; 32]);
+
+        // Unknown version byte.
+        let mut data = payload.clone();
+        data[0] = 0x81;
+        let err = PrivateKey::from_wif(&base58::encode_check(&data)).unwrap_err();
+        assert_eq!(
+            err,
+            FromWifError::InvalidAddressVersion(InvalidAddressVersionError { invalid: 0x81 })
+        );
+
+        // Invalid payload length.
+        let err = PrivateKey::from_wif(&base58::encode_check(&payload[..32])).unwrap_err();
+        assert_eq!(
+            err,
+            FromWifError::InvalidBase58PayloadLength(InvalidBase58PayloadLengthError {
+                length: 32
+            })
+        );
+
+        // Invalid compression flag.
+        let mut data = payload.clone();
+        data.push(0x02);
+        let err = PrivateKey::from_wif(&base58::encode_check(&data)).unwrap_err();
+        assert_eq!(
+            err,
+            FromWifError::InvalidWifCompressionFlag(InvalidWifCompressionFlagError { invalid: 2 })
+        );
+
+        // Out of range secret key, errors other than the network are reported first.
+        let mut data = vec![0x80];
+        data.extend_from_slice(&[0xff; 32]);
+        let err = PrivateKey::from_wif_for_network(&base58::encode_check(&data), NetworkKind::Test)
+            .unwrap_err();
+        assert!(matches!(err, FromWifError::Secp256k1(_)));
+
+        // Uncompressed and compressed testnet keys.
+        let sk = PrivateKey::from_wif(&base58::encode_check(&payload)).unwrap();
+        assert!(!sk.compressed);
+        assert_eq!(sk.network, NetworkKind::Test);
+        payload.push(0x01);
+        let sk = PrivateKey::from_wif(&base58::encode_check(&payload)).unwrap();
+        assert!(sk.compressed);
+    }
+
+    #[test]
+    fn compressed_public_key_rejects_uncompressed() {
+        let secp = Secp256k1::new();
+        let sk =
+            PrivateKey::from_wif("5JYkZjmN7PVMjJUfJWfRFwtuXTGB439XV6faajeHPAM9Z2PT2R3").unwrap();
+        let uncompressed = sk.public_key(&secp);
+        assert!(!uncompressed.compressed);
+
+        assert_eq!(CompressedPublicKey::try_from(uncompressed), Err(UncompressedPublicKeyError));
+        assert_eq!(
+            CompressedPublicKey::from_private_key(&secp, sk),
+            Err(UncompressedPublicKeyError)
+        );
+        assert_eq!(uncompressed.wpubkey_hash(), Err(UncompressedPublicKeyError));
+        assert!(uncompressed.to_string().parse::<CompressedPublicKey>().is_err());
+
+        let sk = PrivateKey { compressed: true, ..sk };
+        let compressed = sk.public_key(&secp);
+        let pk = CompressedPublicKey::try_from(compressed).unwrap();
+        assert_eq!(pk, CompressedPublicKey::from_private_key(&secp, sk).unwrap());
+        assert_eq!(pk.to_bytes()[..], compressed.to_vec());
+        assert_eq!(PublicKey::from(pk), compressed);
+        assert_eq!(pk.wpubkey_hash(), compressed.wpubkey_hash().unwrap());
+        assert_eq!(
+            Address::p2wpkh(pk, KnownHrp::Mainnet),
+            Address::p2wpkh(compressed.try_into().unwrap(), KnownHrp::Mainnet)
+        );
+    }
 
     #[test]
     fn key_derivation() {
@@ -25,7 +25,7 @@
 use crate::prelude::{Borrow, BorrowMut, String, ToOwned};
 use crate::taproot::{LeafVersion, TapLeafHash, TapLeafTag, TAPROOT_ANNEX_PREFIX};
 use crate::transaction::TransactionExt as _;
-use crate::witness::Witness;
+use crate::witness::{Witness, WitnessExt as _};
 use crate::{transaction, Amount, Script, Sequence, Transaction, TxOut};
 
 /// Used for signature hash for invalid use of SIGHASH_SINGLE.
@@ -1186,6 +1186,11 @@ pub fn new(annex_bytes: &'a [u8]) -> Result<Self, AnnexError> {
         }
     }
 
+    /// Returns the annex of a Taproot `witness`, if it has one.
+    ///
+    /// See [`Witness::taproot_annex`] for the rules used.
+    pub fn from_witness(witness: &'a Witness) -> Option<Self> { witness.taproot_annex().map(Annex) }
+
     /// Returns the Annex bytes data (including first byte `0x50`).
     pub fn as_bytes(&self) -> &[u8] { self.0 }
 }
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Output descriptor checksums.
+//!
+//! Implementation of the checksum defined in [BIP-380]. The checksum is an eight character code
+//! appended to a descriptor after a `#`, for example `raw(deadbeef)#89f8spxm`. It is computed
+//! without parsing the descriptor so it can be used to validate descriptor strings even though
+//! this crate does not support descriptors otherwise.
+//!
+//! [BIP-380]: <https://github.com/bitcoin/bips/blob/master/bip-0380.mediawiki>
+
+use core::convert::Infallible;
+use core::fmt;
+
+use crate::prelude::String;
+
+/// The characters allowed in a descriptor, ordered so that the ones most likely to be confused
+/// with each other are in the same group of 32.
+const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+
+/// The characters used to encode the checksum, the same as bech32.
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// The generator of the BCH code used by the checksum.
+const GENERATOR: [u64; 5] = [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd];
+
+/// The number of characters in a checksum.
+pub const CHECKSUM_LENGTH: usize = 8;
+
+/// Computes the checksum of `descriptor`.
+///
+/// `descriptor` must not already have a checksum appended, use [`verify_checksum`] for that.
+///
+/// # Errors
+///
+/// If `descriptor` contains a character which is not allowed in a descriptor.
+pub fn compute_checksum(descriptor: &str) -> Result<String, Error> {
+    let mut engine = Engine::new();
+    for (position, character) in descriptor.chars().enumerate() {
+        let value =
+            INPUT_CHARSET.find(character).ok_or(Error::InvalidCharacter { character, position })?;
+        engine.input(value as u64);
+    }
+    Ok(engine.checksum())
+}
+
+/// Returns `true` if `descriptor` ends with a `#` followed by its correct checksum.
+///
+/// Returns `false` if the checksum is missing, has the wrong length, or if the descriptor contains
+/// more than one `#` or a character which is not allowed in a descriptor.
+pub fn verify_checksum(descriptor: &str) -> bool {
+    match descriptor.split_once('#') {
+        Some((descriptor, checksum)) if checksum.len() == CHECKSUM_LENGTH =>
+            compute_checksum(descriptor).map(|computed| computed == checksum).unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Incremental computation of the descriptor checksum.
+struct Engine {
+    /// The polymod state.
+    c: u64,
+    /// The group numbers (index divided by 32) of the last up to three characters.
+    class: u64,
+    /// The number of group numbers in `class`.
+    class_count: usize,
+}
+
+impl Engine {
+    fn new() -> Self { Engine { c: 1, class: 0, class_count: 0 } }
+
+    /// Feeds the index of a character in [`INPUT_CHARSET`].
+    fn input(&mut self, value: u64) {
+        self.polymod(value & 31);
+        self.class = self.class * 3 + (value >> 5);
+        self.class_count += 1;
+        if self.class_count == 3 {
+            self.polymod(self.class);
+            self.class = 0;
+            self.class_count = 0;
+        }
+    }
+
+    fn checksum(mut self) -> String {
+        if self.class_count > 0 {
+            self.polymod(self.class);
+        }
+        for _ in 0..CHECKSUM_LENGTH {
+            self.polymod(0);
+        }
+        let c = self.c ^ 1;
+        (0..CHECKSUM_LENGTH)
+            .map(|i| CHECKSUM_CHARSET[((c >> (5 * (7 - i))) & 31) as usize] as char)
+            .collect()
+    }
+
+    fn polymod(&mut self, value: u64) {
+        let c0 = self.c >> 35;
+        self.c = ((self.c & 0x7_ffff_ffff) << 5) ^ value;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (c0 >> i) & 1 == 1 {
+                self.c ^= generator;
+            }
+        }
+    }
+}
+
+/// An error computing a descriptor checksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The descriptor contains a character which is not allowed in descriptors.
+    InvalidCharacter {
+        /// The invalid character.
+        character: char,
+        /// The position of the character, counted in characters.
+        position: usize,
+    },
+}
+
+impl From<Infallible> for Error {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::InvalidCharacter { character, position } =>
+                write!(f, "invalid descriptor character {:?} at position {}", character, position),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::InvalidCharacter { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From BIP-380 and the descriptor tests in Bitcoin Core.
+    const VALID: &[(&str, &str)] = &[
+        ("raw(deadbeef)", "89f8spxm"),
+        ("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)", "02wpgw69"),
+        (
+            "sh(multi(2,[00000000/111'/222]xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc,xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L/0))",
+            "ggrsrxfy",
+        ),
+        (
+            "sh(multi(2,[00000000/111'/222]xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL,xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y/0))",
+            "tjg09x5t",
+        ),
+    ];
+
+    #[test]
+    fn compute_valid() {
+        for (descriptor, checksum) in VALID {
+            assert_eq!(compute_checksum(descriptor).unwrap(), *checksum);
+            assert!(verify_checksum(&format!("{}#{}", descriptor, checksum)));
+        }
+    }
+
+    #[test]
+    fn verify_invalid() {
+        // Missing checksum.
+        assert!(!verify_checksum("raw(deadbeef)"));
+        assert!(!verify_checksum("raw(deadbeef)#"));
+        // Too long.
+        assert!(!verify_checksum("raw(deadbeef)#89f8spxmx"));
+        // Too short.
+        assert!(!verify_checksum("raw(deadbeef)#89f8spx"));
+        // Error in the payload.
+        assert!(!verify_checksum("raw(deadbeef)#89f8spxn"));
+        assert!(!verify_checksum("raw(deedbeef)#89f8spxm"));
+        // Multiple checksums.
+        assert!(!verify_checksum("raw(deadbeef)#89f8spxm#89f8spxm"));
+        // Invalid characters.
+        assert!(!verify_checksum("raw(Ü)#00000000"));
+    }
+
+    #[test]
+    fn invalid_character() {
+        assert_eq!(
+            compute_checksum("raw(Ü)"),
+            Err(Error::InvalidCharacter { character: 'Ü', position: 4 })
+        );
+        assert_eq!(
+            compute_checksum("raw(\n)"),
+            Err(Error::InvalidCharacter { character: '\n', position: 4 })
+        );
+    }
+}
@@ -48,11 +48,11 @@ fn consensus_decode<R: $crate::io::BufRead + ?Sized>(
 /// - `fmt::Display` and `str::FromStr` (using lowercase hex)
 /// - `fmt::LowerHex` and `UpperHex`
 /// - `fmt::Debug` (using `LowerHex`)
-/// - `serde::Serialize` and `Deserialize` (using lowercase hex)
+/// - `serde::Serialize` and `Deserialize` (using lowercase hex), unless `no_serde` is passed
 ///
 /// As well as an inherent `from_hex` method.
 macro_rules! impl_array_newtype_stringify {
-    ($t:ident, $len:literal) => {
+    ($t:ident, $len:literal, no_serde) => {
         impl $t {
             /// Constructs a new `Self` from a hex string.
             pub fn from_hex(s: &str) -> Result<Self, hex::HexToArrayError> {
@@ -90,6 +90,9 @@ impl core::str::FromStr for $t {
             type Err = $crate::hex::HexToArrayError;
             fn from_str(s: &str) -> core::result::Result<Self, Self::Err> { Self::from_hex(s) }
         }
+    };
+    ($t:ident, $len:literal) => {
+        $crate::internal_macros::impl_array_newtype_stringify!($t, $len, no_serde);
 
         #[cfg(feature = "serde")]
         impl $crate::serde::Serialize for $t {
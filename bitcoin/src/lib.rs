@@ -99,13 +99,16 @@
 pub mod address;
 pub mod bip152;
 pub mod bip158;
+pub mod bip21;
 pub mod bip32;
 pub mod blockdata;
+pub mod coinselection;
 pub mod consensus;
 #[cfg(feature = "bitcoinconsensus")]
 pub mod consensus_validation;
 // Private until we either make this a crate or flatten it - still to be decided.
 pub(crate) mod crypto;
+pub mod descriptor_checksum;
 pub mod hash_types;
 pub mod merkle_tree;
 pub mod network;
@@ -113,6 +116,7 @@
 pub mod pow;
 pub mod psbt;
 pub mod sign_message;
+pub mod silentpayments;
 pub mod taproot;
 
 // Re-export the type from where it is defined but the module from the highest place up the stack
@@ -73,6 +73,7 @@ impl MerkleBlock {
     /// assert!(mb.extract_matches(&mut matches, &mut index).is_ok());
     /// assert_eq!(txid, matches[0]);
     /// ```
+    #[doc(alias = "from_block_with_filter")]
     pub fn from_block_with_predicate<F>(block: &Block<Checked>, match_txids: F) -> Self
     where
         F: Fn(&Txid) -> bool,
@@ -435,12 +436,12 @@ fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(
             }
             .into());
         }
-        let mut bits = vec![false; nb_bytes_for_bits * 8];
-        for chunk in bits.chunks_mut(8) {
+        let mut bits = Vec::with_capacity(encode::preallocation_capacity::<bool>(
+            nb_bytes_for_bits as u64 * 8,
+        ));
+        for _ in 0..nb_bytes_for_bits {
             let byte = u8::consensus_decode(r)?;
-            for (i, bit) in chunk.iter_mut().enumerate() {
-                *bit = (byte & (1 << i)) != 0;
-            }
+            bits.extend((0..8).map(|i| (byte & (1 << i)) != 0));
         }
 
         Ok(PartialMerkleTree { num_transactions, hashes, bits })
@@ -722,6 +723,29 @@ fn merkleblock_construct_from_txids_not_found() {
         assert_eq!(index.len(), 0);
     }
 
+    #[test]
+    fn merkleblock_extracts_exactly_matched_txids() {
+        let block = get_block_13b8a();
+        let txids = block.transactions().iter().map(Transaction::compute_txid).collect::<Vec<_>>();
+
+        for matched in [vec![], vec![0], vec![8], vec![0, 3, 4, 7], (0..9).collect()] {
+            let mut want = matched.iter().map(|&i| txids[i]).collect::<Vec<_>>();
+            let merkle_block = MerkleBlock::from_block_with_predicate(&block, |t| want.contains(t));
+
+            // The Merkle block is still valid after a round trip, even without matches.
+            let merkle_block: MerkleBlock =
+                encode::deserialize(&encode::serialize(&merkle_block)).expect("valid merkle block");
+            let mut matches = vec![];
+            let mut indexes = vec![];
+            merkle_block.extract_matches(&mut matches, &mut indexes).unwrap();
+
+            assert_eq!(indexes, matched.iter().map(|&i| i as u32).collect::<Vec<_>>());
+            matches.sort();
+            want.sort();
+            assert_eq!(matches, want);
+        }
+    }
+
     #[cfg(feature = "rand-std")]
     impl PartialMerkleTree {
         /// Flip one bit in one of the hashes - this should break the authentication
@@ -551,4 +551,32 @@ fn addrv2message() {
 
         assert_eq!(serialize(&addresses), raw);
     }
+
+    #[test]
+    fn torv3_round_trip() {
+        use crate::p2p::message::{NetworkMessage, RawNetworkMessage};
+        use crate::p2p::Magic;
+
+        let pubkey = <[u8; 32]>::from_hex(
+            "53cd5648488c4707914182655b7664034e09e66f7e8cbf1084e654eb56c5bd88",
+        )
+        .unwrap();
+        let addr = AddrV2::TorV3(pubkey);
+        assert_eq!(deserialize::<AddrV2>(&serialize(&addr)).unwrap(), addr);
+
+        let msg = AddrV2Message {
+            time: 0x4966bc61,
+            services: ServiceFlags::NETWORK | ServiceFlags::P2P_V2,
+            addr,
+            port: 9050,
+        };
+        let raw = serialize(&msg);
+        assert_eq!(deserialize::<AddrV2Message>(&raw).unwrap(), msg);
+        assert!(msg.socket_addr().is_err());
+
+        let network_msg = RawNetworkMessage::new(Magic::BITCOIN, NetworkMessage::AddrV2(vec![msg]));
+        let raw = serialize(&network_msg);
+        assert_eq!(&raw[4..11], b"addrv2\0");
+        assert_eq!(deserialize::<RawNetworkMessage>(&raw).unwrap(), network_msg);
+    }
 }
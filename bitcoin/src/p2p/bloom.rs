@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! BIP-37 Connection Bloom filters.
+//!
+//! This module implements construction of the Bloom filters sent in the `filterload` message, as
+//! defined in [BIP-37].
+//!
+//! [BIP-37]: <https://github.com/bitcoin/bips/blob/master/bip-0037.mediawiki>
+//!
+//! # Examples
+//!
+//! ```
+//! use bitcoin::p2p::bloom::BloomFilter;
+//! use bitcoin::p2p::message_bloom::BloomFlags;
+//!
+//! let mut filter = BloomFilter::new(10, 0.0001, 0, BloomFlags::None);
+//! filter.insert(b"some data");
+//! assert!(filter.contains(b"some data"));
+//!
+//! let filter_load = filter.to_filter_load();
+//! ```
+
+use core::f64::consts::LN_2;
+
+use crate::p2p::message_bloom::{BloomFlags, FilterLoad};
+
+/// The maximum size of a Bloom filter in bytes.
+pub const MAX_BLOOM_FILTER_SIZE: usize = 36_000;
+
+/// The maximum number of hash functions used by a Bloom filter.
+pub const MAX_HASH_FUNCS: u32 = 50;
+
+/// Multiplier of the hash function number in the MurmurHash3 seed.
+const SEED_MULTIPLIER: u32 = 0xFBA4_C795;
+
+/// A BIP-37 Bloom filter.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BloomFilter {
+    data: Vec<u8>,
+    hash_funcs: u32,
+    tweak: u32,
+    flags: BloomFlags,
+}
+
+impl BloomFilter {
+    /// Constructs a new empty filter for `n_elements` elements with false positive rate `fp_rate`.
+    ///
+    /// The filter size and number of hash functions are chosen as described in BIP-37, clamped to
+    /// [`MAX_BLOOM_FILTER_SIZE`] and [`MAX_HASH_FUNCS`]. `tweak` is a random value used to seed the
+    /// hash functions.
+    pub fn new(n_elements: u32, fp_rate: f64, tweak: u32, flags: BloomFlags) -> Self {
+        let n_elements = f64::from(n_elements.max(1));
+        let bits = -1.0 / (LN_2 * LN_2) * n_elements * fp_rate.ln();
+        // Float to int casts saturate, this also maps NaN to zero.
+        let bits = (bits as usize).min(MAX_BLOOM_FILTER_SIZE * 8);
+        let size = bits / 8;
+        let hash_funcs = ((size * 8) as f64 / n_elements * LN_2) as u32;
+
+        BloomFilter {
+            data: vec![0; size],
+            hash_funcs: hash_funcs.min(MAX_HASH_FUNCS),
+            tweak,
+            flags,
+        }
+    }
+
+    /// Returns the filter data.
+    pub fn data(&self) -> &[u8] { &self.data }
+
+    /// Returns the number of hash functions used.
+    pub fn hash_funcs(&self) -> u32 { self.hash_funcs }
+
+    /// Returns the tweak used to seed the hash functions.
+    pub fn tweak(&self) -> u32 { self.tweak }
+
+    /// Returns the filter update flags.
+    pub fn flags(&self) -> BloomFlags { self.flags }
+
+    /// Inserts `data` into the filter.
+    pub fn insert(&mut self, data: &[u8]) {
+        if self.data.is_empty() {
+            return;
+        }
+        for n in 0..self.hash_funcs {
+            let index = self.bit_index(n, data);
+            self.data[index >> 3] |= 1 << (index & 7);
+        }
+    }
+
+    /// Returns true if `data` may have been inserted into the filter.
+    ///
+    /// False positives are possible, false negatives are not.
+    pub fn contains(&self, data: &[u8]) -> bool {
+        if self.data.is_empty() {
+            return true;
+        }
+        (0..self.hash_funcs).all(|n| {
+            let index = self.bit_index(n, data);
+            self.data[index >> 3] & (1 << (index & 7)) != 0
+        })
+    }
+
+    /// Constructs the `filterload` message that loads this filter.
+    pub fn to_filter_load(&self) -> FilterLoad {
+        FilterLoad {
+            filter: self.data.clone(),
+            hash_funcs: self.hash_funcs,
+            tweak: self.tweak,
+            flags: self.flags,
+        }
+    }
+
+    fn bit_index(&self, n: u32, data: &[u8]) -> usize {
+        let seed = n.wrapping_mul(SEED_MULTIPLIER).wrapping_add(self.tweak);
+        murmur3(seed, data) as usize % (self.data.len() * 8)
+    }
+}
+
+impl From<BloomFilter> for FilterLoad {
+    fn from(filter: BloomFilter) -> Self {
+        FilterLoad {
+            filter: filter.data,
+            hash_funcs: filter.hash_funcs,
+            tweak: filter.tweak,
+            flags: filter.flags,
+        }
+    }
+}
+
+/// 32 bit MurmurHash3 (x86 variant).
+fn murmur3(seed: u32, data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut h1 = seed;
+    let mut blocks = data.chunks_exact(4);
+    for block in &mut blocks {
+        let mut k1 = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        k1 = k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h1 ^= k1;
+        h1 = h1.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k1 = 0u32;
+        for (i, &byte) in tail.iter().enumerate() {
+            k1 ^= u32::from(byte) << (8 * i);
+        }
+        k1 = k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h1 ^= k1;
+    }
+
+    h1 ^= data.len() as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85eb_ca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2_ae35);
+    h1 ^= h1 >> 16;
+    h1
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::test_hex_unwrap as hex;
+
+    use super::*;
+    use crate::consensus::encode::serialize;
+
+    // Test vectors from Bitcoin Core `src/test/hash_tests.cpp`.
+    #[test]
+    fn murmur3_vectors() {
+        assert_eq!(murmur3(0x0000_0000, &[]), 0x0000_0000);
+        assert_eq!(murmur3(0xFBA4_C795, &[]), 0x6a39_6f08);
+        assert_eq!(murmur3(0xffff_ffff, &[]), 0x81f1_6f39);
+        assert_eq!(murmur3(0x0000_0000, &hex!("00")), 0x514e_28b7);
+        assert_eq!(murmur3(0xFBA4_C795, &hex!("00")), 0xea3f_0b17);
+        assert_eq!(murmur3(0x0000_0000, &hex!("ff")), 0xfd6c_f10d);
+        assert_eq!(murmur3(0x0000_0000, &hex!("0011")), 0x16c6_b7ab);
+        assert_eq!(murmur3(0x0000_0000, &hex!("001122")), 0x8eb5_1c3d);
+        assert_eq!(murmur3(0x0000_0000, &hex!("00112233")), 0xb447_1bf8);
+        assert_eq!(murmur3(0x0000_0000, &hex!("0011223344")), 0xe230_1fa8);
+        assert_eq!(murmur3(0x0000_0000, &hex!("001122334455")), 0xfc2e_4a15);
+        assert_eq!(murmur3(0x0000_0000, &hex!("00112233445566")), 0xb074_502c);
+        assert_eq!(murmur3(0x0000_0000, &hex!("0011223344556677")), 0x8034_d2a0);
+        assert_eq!(murmur3(0x0000_0000, &hex!("001122334455667788")), 0xb469_8def);
+    }
+
+    // Test vectors from Bitcoin Core `src/test/bloom_tests.cpp`.
+    #[test]
+    fn bloom_create_insert_serialize() {
+        let mut filter = BloomFilter::new(3, 0.01, 0, BloomFlags::All);
+        assert_eq!(filter.data().len(), 3);
+        assert_eq!(filter.hash_funcs(), 5);
+
+        filter.insert(&hex!("99108ad8ed9bb6274d3980bab5a85c048f0950c8"));
+        assert!(filter.contains(&hex!("99108ad8ed9bb6274d3980bab5a85c048f0950c8")));
+        // One bit different in first byte.
+        assert!(!filter.contains(&hex!("19108ad8ed9bb6274d3980bab5a85c048f0950c8")));
+
+        filter.insert(&hex!("b5a2c786d9ef4658287ced5914b37a1b4aa32eee"));
+        assert!(filter.contains(&hex!("b5a2c786d9ef4658287ced5914b37a1b4aa32eee")));
+
+        filter.insert(&hex!("b9300670b4c5366e95b2699e8b18bc75e5f729c5"));
+        assert!(filter.contains(&hex!("b9300670b4c5366e95b2699e8b18bc75e5f729c5")));
+
+        assert_eq!(serialize(&filter.to_filter_load()), hex!("03614e9b050000000000000001"));
+        assert_eq!(FilterLoad::from(filter.clone()), filter.to_filter_load());
+    }
+
+    #[test]
+    fn bloom_create_insert_serialize_with_tweak() {
+        let mut filter = BloomFilter::new(3, 0.01, 2147483649, BloomFlags::All);
+
+        filter.insert(&hex!("99108ad8ed9bb6274d3980bab5a85c048f0950c8"));
+        assert!(filter.contains(&hex!("99108ad8ed9bb6274d3980bab5a85c048f0950c8")));
+        assert!(!filter.contains(&hex!("19108ad8ed9bb6274d3980bab5a85c048f0950c8")));
+        filter.insert(&hex!("b5a2c786d9ef4658287ced5914b37a1b4aa32eee"));
+        filter.insert(&hex!("b9300670b4c5366e95b2699e8b18bc75e5f729c5"));
+
+        assert_eq!(serialize(&filter.to_filter_load()), hex!("03ce4299050000000100008001"));
+    }
+
+    #[test]
+    fn bloom_clamps_size() {
+        let filter = BloomFilter::new(1_000_000, 0.000_001, 0, BloomFlags::None);
+        assert_eq!(filter.data().len(), MAX_BLOOM_FILTER_SIZE);
+        assert!(filter.hash_funcs() <= MAX_HASH_FUNCS);
+
+        let filter = BloomFilter::new(1, 1e-20, 0, BloomFlags::None);
+        assert_eq!(filter.hash_funcs(), MAX_HASH_FUNCS);
+
+        // An empty filter matches everything.
+        let mut filter = BloomFilter::new(1, 1.0, 0, BloomFlags::None);
+        assert!(filter.data().is_empty());
+        filter.insert(b"data");
+        assert!(filter.contains(b"other"));
+    }
+}
@@ -304,17 +304,26 @@ pub fn command(&self) -> CommandString {
             _ => CommandString::try_from_static(self.cmd()).expect("cmd returns valid commands"),
         }
     }
+
+    /// Returns the checksum of the encoded message, as used in the v1 message header.
+    ///
+    /// This is the first four bytes of the `sha256d` of the consensus encoded payload.
+    pub fn checksum(&self) -> [u8; 4] { self.len_and_checksum().1 }
+
+    /// Returns the length and checksum of the encoded message.
+    fn len_and_checksum(&self) -> (u32, [u8; 4]) {
+        let mut engine = sha256d::Hash::engine();
+        let len = self.consensus_encode(&mut engine).expect("engine doesn't error");
+        let len = u32::try_from(len).expect("network message use u32 as length");
+        let checksum = sha256d::Hash::from_engine(engine).to_byte_array();
+        (len, [checksum[0], checksum[1], checksum[2], checksum[3]])
+    }
 }
 
 impl RawNetworkMessage {
     /// Constructs a new [RawNetworkMessage]
     pub fn new(magic: Magic, payload: NetworkMessage) -> Self {
-        let mut engine = sha256d::Hash::engine();
-        let payload_len = payload.consensus_encode(&mut engine).expect("engine doesn't error");
-        let payload_len = u32::try_from(payload_len).expect("network message use u32 as length");
-        let checksum = sha256d::Hash::from_engine(engine);
-        let checksum = checksum.to_byte_array();
-        let checksum = [checksum[0], checksum[1], checksum[2], checksum[3]];
+        let (payload_len, checksum) = payload.len_and_checksum();
         Self { magic, payload, payload_len, checksum }
     }
 
@@ -495,9 +504,7 @@ fn consensus_decode_from_finite_reader<R: BufRead + ?Sized>(
         r: &mut R,
     ) -> Result<Self, encode::Error> {
         let len = r.read_compact_size()?;
-        // should be above usual number of items to avoid
-        // allocation
-        let mut ret = Vec::with_capacity(core::cmp::min(1024 * 16, len as usize));
+        let mut ret = Vec::with_capacity(encode::preallocation_capacity::<block::Header>(len));
         for _ in 0..len {
             ret.push(Decodable::consensus_decode(r)?);
             if u8::consensus_decode(r)? != 0u8 {
@@ -974,6 +981,45 @@ fn deserialize_v2_getaddr() {
         assert_eq!(preimage, msg);
     }
 
+    #[test]
+    fn checksum() {
+        let msg = NetworkMessage::Ping(100);
+        assert_eq!(msg.checksum(), [0x24, 0x67, 0xf1, 0x1d]);
+        assert_eq!(NetworkMessage::Verack.checksum(), [0x5d, 0xf6, 0xe0, 0xe2]);
+
+        let raw = serialize(&RawNetworkMessage::new(Magic::BITCOIN, msg));
+        assert_eq!(raw[20..24], NetworkMessage::Ping(100).checksum());
+    }
+
+    #[test]
+    fn deserialize_invalid_checksum() {
+        use crate::consensus::encode::{DeserializeError, ParseError};
+
+        let raw = serialize(&RawNetworkMessage::new(Magic::BITCOIN, NetworkMessage::Ping(100)));
+        assert!(deserialize::<RawNetworkMessage>(&raw).is_ok());
+
+        for i in 20..24 {
+            let mut corrupted = raw.clone();
+            corrupted[i] ^= 0x01;
+            let err = deserialize::<RawNetworkMessage>(&corrupted).unwrap_err();
+            match err {
+                DeserializeError::Parse(ParseError::InvalidChecksum { expected, actual }) => {
+                    assert_eq!(expected, [0x24, 0x67, 0xf1, 0x1d]);
+                    assert_eq!(actual[..], corrupted[20..24]);
+                }
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        // Corrupting the payload also gives a checksum mismatch.
+        let mut corrupted = raw;
+        corrupted[24] ^= 0x01;
+        assert!(matches!(
+            deserialize::<RawNetworkMessage>(&corrupted),
+            Err(DeserializeError::Parse(ParseError::InvalidChecksum { .. }))
+        ));
+    }
+
     #[test]
     fn deserialize_version() {
         #[rustfmt::skip]
@@ -7,7 +7,7 @@
 
 use io::{BufRead, Write};
 
-use crate::block::BlockHash;
+use crate::block::{BlockHash, Locator};
 use crate::consensus::encode::{self, Decodable, Encodable};
 use crate::internal_macros::impl_consensus_encoding;
 use crate::p2p;
@@ -138,6 +138,18 @@ impl GetHeadersMessage {
     pub fn new(locator_hashes: Vec<BlockHash>, stop_hash: BlockHash) -> GetHeadersMessage {
         GetHeadersMessage { version: p2p::PROTOCOL_VERSION, locator_hashes, stop_hash }
     }
+
+    /// Construct a new `getheaders` message with a block locator for a chain with tip at `tip`.
+    ///
+    /// `known` must return the hash of the block at the given height, see [`Locator`] for the
+    /// heights that are included.
+    pub fn from_locator(
+        tip: u32,
+        known: impl Fn(u32) -> BlockHash,
+        stop_hash: BlockHash,
+    ) -> GetHeadersMessage {
+        GetHeadersMessage::new(Locator::from_heights(tip, known), stop_hash)
+    }
 }
 
 impl_consensus_encoding!(GetHeadersMessage, version, locator_hashes, stop_hash);
@@ -180,4 +192,18 @@ fn getheaders_message() {
 
         assert_eq!(serialize(&real_decode), from_sat);
     }
+
+    #[test]
+    fn getheaders_from_locator() {
+        let genesis = BlockHash::from_byte_array([0xab; 32]);
+        let hash =
+            |height| if height == 0 { genesis } else { BlockHash::from_byte_array([0x01; 32]) };
+
+        let message =
+            GetHeadersMessage::from_locator(1000, hash, BlockHash::GENESIS_PREVIOUS_BLOCK_HASH);
+        assert_eq!(message.version, p2p::PROTOCOL_VERSION);
+        assert_eq!(message.locator_hashes.len(), Locator::heights(1000).len());
+        assert_eq!(message.locator_hashes.last(), Some(&genesis));
+        assert_eq!(message.stop_hash, BlockHash::GENESIS_PREVIOUS_BLOCK_HASH);
+    }
 }
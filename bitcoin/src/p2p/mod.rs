@@ -8,6 +8,8 @@
 #[cfg(feature = "std")]
 pub mod address;
 #[cfg(feature = "std")]
+pub mod bloom;
+#[cfg(feature = "std")]
 pub mod message;
 #[cfg(feature = "std")]
 pub mod message_blockdata;
@@ -19,6 +21,7 @@
 pub mod message_filter;
 #[cfg(feature = "std")]
 pub mod message_network;
+pub mod v2;
 
 use core::str::FromStr;
 use core::{fmt, ops};
@@ -0,0 +1,287 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! BIP-324 version 2 transport handshake primitives.
+//!
+//! This module implements the key exchange of the encrypted transport protocol defined in
+//! [BIP-324]. Each party sends an ElligatorSwift encoded public key, both parties perform an x-only
+//! ECDH over the encodings and derive the session keys from the shared secret.
+//!
+//! [BIP-324]: <https://github.com/bitcoin/bips/blob/master/bip-0324.mediawiki>
+
+use core::fmt;
+
+use hashes::{hkdf, sha256};
+use hex::DisplayHex;
+#[doc(inline)]
+pub use secp256k1::ellswift::ElligatorSwift;
+use secp256k1::ellswift::ElligatorSwiftParty;
+use secp256k1::{PublicKey, Secp256k1, SecretKey, Verification};
+
+use crate::p2p::Magic;
+
+/// Length of an ElligatorSwift encoded public key in bytes.
+pub const ELLSWIFT_ENCODING_SIZE: usize = 64;
+
+/// Length of a garbage terminator in bytes.
+pub const GARBAGE_TERMINATOR_SIZE: usize = 16;
+
+/// Prefix of the HKDF salt, the network magic is appended to it.
+const SALT_PREFIX: &[u8] = b"bitcoin_v2_shared_secret";
+
+/// Encodes `sk`'s public key using ElligatorSwift.
+///
+/// This is preferred over [`ellswift_encode`] because the secret key adds extra entropy to the
+/// encoding. `aux_rand` should be 32 bytes of fresh randomness if available.
+pub fn ellswift_from_secret_key<C: Verification>(
+    secp: &Secp256k1<C>,
+    sk: SecretKey,
+    aux_rand: Option<[u8; 32]>,
+) -> ElligatorSwift {
+    ElligatorSwift::from_seckey(secp, sk, aux_rand)
+}
+
+/// Encodes a public key using ElligatorSwift.
+pub fn ellswift_encode(pk: PublicKey) -> ElligatorSwift { ElligatorSwift::from_pubkey(pk) }
+
+/// Decodes an ElligatorSwift encoding into the public key it represents.
+///
+/// Every 64 byte string is a valid encoding so this can not fail.
+pub fn ellswift_decode(ellswift: ElligatorSwift) -> PublicKey { PublicKey::from_ellswift(ellswift) }
+
+/// The role of a peer in the handshake.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    /// The peer that opened the connection.
+    Initiator,
+    /// The peer that accepted the connection.
+    Responder,
+}
+
+impl Role {
+    fn to_party(self) -> ElligatorSwiftParty {
+        match self {
+            Role::Initiator => ElligatorSwiftParty::A,
+            Role::Responder => ElligatorSwiftParty::B,
+        }
+    }
+}
+
+/// Computes the BIP-324 x-only ECDH shared secret.
+///
+/// `ours` must be the ElligatorSwift encoding of `sk`'s public key that was sent to the peer and
+/// `theirs` the encoding received from the peer. Both parties compute the same secret.
+pub fn shared_secret(
+    sk: SecretKey,
+    ours: ElligatorSwift,
+    theirs: ElligatorSwift,
+    role: Role,
+) -> [u8; 32] {
+    let (a, b) = match role {
+        Role::Initiator => (ours, theirs),
+        Role::Responder => (theirs, ours),
+    };
+    ElligatorSwift::shared_secret(a, b, sk, role.to_party(), None).to_secret_bytes()
+}
+
+/// The keys and identifiers of a BIP-324 session.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SessionKeys {
+    initiator_length_key: [u8; 32],
+    initiator_packet_key: [u8; 32],
+    responder_length_key: [u8; 32],
+    responder_packet_key: [u8; 32],
+    initiator_garbage_terminator: [u8; GARBAGE_TERMINATOR_SIZE],
+    responder_garbage_terminator: [u8; GARBAGE_TERMINATOR_SIZE],
+    session_id: [u8; 32],
+}
+
+impl SessionKeys {
+    /// Performs the ECDH and derives the session keys for the network identified by `magic`.
+    ///
+    /// See [`shared_secret`] for the requirements on the arguments.
+    pub fn new(
+        sk: SecretKey,
+        ours: ElligatorSwift,
+        theirs: ElligatorSwift,
+        role: Role,
+        magic: Magic,
+    ) -> Self {
+        Self::from_shared_secret(&shared_secret(sk, ours, theirs, role), magic)
+    }
+
+    /// Derives the session keys from an ECDH shared secret.
+    pub fn from_shared_secret(secret: &[u8; 32], magic: Magic) -> Self {
+        let mut salt = [0u8; SALT_PREFIX.len() + 4];
+        salt[..SALT_PREFIX.len()].copy_from_slice(SALT_PREFIX);
+        salt[SALT_PREFIX.len()..].copy_from_slice(&magic.to_bytes());
+
+        let hkdf = hkdf::Hkdf::<sha256::HashEngine>::new(&salt, secret);
+        let expand = |info: &[u8]| {
+            let mut okm = [0u8; 32];
+            hkdf.expand(info, &mut okm).expect("32 bytes is less than the maximum output length");
+            okm
+        };
+
+        let garbage_terminators = expand(b"garbage_terminators");
+        let mut initiator_garbage_terminator = [0u8; GARBAGE_TERMINATOR_SIZE];
+        let mut responder_garbage_terminator = [0u8; GARBAGE_TERMINATOR_SIZE];
+        initiator_garbage_terminator
+            .copy_from_slice(&garbage_terminators[..GARBAGE_TERMINATOR_SIZE]);
+        responder_garbage_terminator
+            .copy_from_slice(&garbage_terminators[GARBAGE_TERMINATOR_SIZE..]);
+
+        Self {
+            initiator_length_key: expand(b"initiator_L"),
+            initiator_packet_key: expand(b"initiator_P"),
+            responder_length_key: expand(b"responder_L"),
+            responder_packet_key: expand(b"responder_P"),
+            initiator_garbage_terminator,
+            responder_garbage_terminator,
+            session_id: expand(b"session_id"),
+        }
+    }
+
+    /// Returns the key used to encrypt the length of packets sent by the initiator.
+    pub fn initiator_length_key(&self) -> &[u8; 32] { &self.initiator_length_key }
+
+    /// Returns the key used to encrypt the contents of packets sent by the initiator.
+    pub fn initiator_packet_key(&self) -> &[u8; 32] { &self.initiator_packet_key }
+
+    /// Returns the key used to encrypt the length of packets sent by the responder.
+    pub fn responder_length_key(&self) -> &[u8; 32] { &self.responder_length_key }
+
+    /// Returns the key used to encrypt the contents of packets sent by the responder.
+    pub fn responder_packet_key(&self) -> &[u8; 32] { &self.responder_packet_key }
+
+    /// Returns the garbage terminator sent by the initiator.
+    pub fn initiator_garbage_terminator(&self) -> &[u8; GARBAGE_TERMINATOR_SIZE] {
+        &self.initiator_garbage_terminator
+    }
+
+    /// Returns the garbage terminator sent by the responder.
+    pub fn responder_garbage_terminator(&self) -> &[u8; GARBAGE_TERMINATOR_SIZE] {
+        &self.responder_garbage_terminator
+    }
+
+    /// Returns the garbage terminator that `role` sends.
+    pub fn send_garbage_terminator(&self, role: Role) -> &[u8; GARBAGE_TERMINATOR_SIZE] {
+        match role {
+            Role::Initiator => &self.initiator_garbage_terminator,
+            Role::Responder => &self.responder_garbage_terminator,
+        }
+    }
+
+    /// Returns the garbage terminator that `role` expects to receive.
+    pub fn recv_garbage_terminator(&self, role: Role) -> &[u8; GARBAGE_TERMINATOR_SIZE] {
+        match role {
+            Role::Initiator => &self.responder_garbage_terminator,
+            Role::Responder => &self.initiator_garbage_terminator,
+        }
+    }
+
+    /// Returns the session ID, used to detect man-in-the-middle attacks.
+    pub fn session_id(&self) -> &[u8; 32] { &self.session_id }
+}
+
+impl fmt::Debug for SessionKeys {
+    // Only the session ID is printed so that keys do not leak into logs.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SessionKeys")
+            .field("session_id", &self.session_id.as_hex())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::str::FromStr;
+
+    use hex_lit::hex;
+
+    use super::*;
+
+    #[test]
+    fn ellswift_round_trip() {
+        let secp = Secp256k1::new();
+        let sk = SecretKey::from_byte_array(&[0x42; 32]).unwrap();
+        let pk = PublicKey::from_secret_key(&secp, &sk);
+
+        let ellswift = ellswift_from_secret_key(&secp, sk, Some([0x01; 32]));
+        assert_eq!(ellswift.to_array().len(), ELLSWIFT_ENCODING_SIZE);
+        assert_eq!(ellswift_decode(ellswift), pk);
+        assert_eq!(ellswift_decode(ellswift_encode(pk)), pk);
+    }
+
+    #[test]
+    fn both_sides_derive_same_keys() {
+        let secp = Secp256k1::new();
+        let initiator_sk = SecretKey::from_byte_array(&[0x11; 32]).unwrap();
+        let responder_sk = SecretKey::from_byte_array(&[0x22; 32]).unwrap();
+        let initiator = ellswift_from_secret_key(&secp, initiator_sk, None);
+        let responder = ellswift_from_secret_key(&secp, responder_sk, None);
+
+        let a =
+            SessionKeys::new(initiator_sk, initiator, responder, Role::Initiator, Magic::BITCOIN);
+        let b =
+            SessionKeys::new(responder_sk, responder, initiator, Role::Responder, Magic::BITCOIN);
+        assert_eq!(a, b);
+        assert_eq!(
+            a.send_garbage_terminator(Role::Initiator),
+            b.recv_garbage_terminator(Role::Responder)
+        );
+        assert_eq!(
+            a.recv_garbage_terminator(Role::Initiator),
+            b.send_garbage_terminator(Role::Responder)
+        );
+
+        let c =
+            SessionKeys::new(initiator_sk, initiator, responder, Role::Initiator, Magic::SIGNET);
+        assert_ne!(a.session_id(), c.session_id());
+    }
+
+    // Test vectors from BIP-324 `packet_encoding_test_vectors.csv`.
+    #[test]
+    fn bip324_key_derivation() {
+        let sk =
+            SecretKey::from_str("61062ea5071d800bbfd59e2e8b53d47d194b095ae5a4df04936b49772ef0d4d7")
+                .unwrap();
+        let ours = ElligatorSwift::from_array(hex!("ec0adff257bbfe500c188c80b4fdd640f6b45a482bbc15fc7cef5931deff0aa186f6eb9bba7b85dc4dcc28b28722de1e3d9108b985e2967045668f66098e475b"));
+        let theirs = ElligatorSwift::from_array(hex!("a4a94dfce69b4a2a0a099313d10f9f7e7d649d60501c9e1d274c300e0d89aafaffffffffffffffffffffffffffffffffffffffffffffffffffffffff8faf88d5"));
+
+        let secret = shared_secret(sk, ours, theirs, Role::Initiator);
+        assert_eq!(
+            secret,
+            hex!("c6992a117f5edbea70c3f511d32d26b9798be4b81a62eaee1a5acaa8459a3592")
+        );
+
+        let keys = SessionKeys::new(sk, ours, theirs, Role::Initiator, Magic::BITCOIN);
+        assert_eq!(
+            keys.initiator_length_key(),
+            &hex!("9a6478b5fbab1f4dd2f78994b774c03211c78312786e602da75a0d1767fb55cf")
+        );
+        assert_eq!(
+            keys.initiator_packet_key(),
+            &hex!("7d0c7820ba6a4d29ce40baf2caa6035e04f1e1cefd59f3e7e59e9e5af84f1f51")
+        );
+        assert_eq!(
+            keys.responder_length_key(),
+            &hex!("17bc726421e4054ac6a1d54915085aaa766f4d3cf67bbd168e6080eac289d15e")
+        );
+        assert_eq!(
+            keys.responder_packet_key(),
+            &hex!("9f0fc1c0e85fd9a8eee07e6fc41dba2ff54c7729068a239ac97c37c524cca1c0")
+        );
+        assert_eq!(
+            keys.send_garbage_terminator(Role::Initiator),
+            &hex!("faef555dfcdb936425d84aba524758f3")
+        );
+        assert_eq!(
+            keys.recv_garbage_terminator(Role::Initiator),
+            &hex!("02cb8ff24307a6e27de3b4e7ea3fa65b")
+        );
+        assert_eq!(
+            keys.session_id(),
+            &hex!("ce72dffb015da62b0d0f5474cab8bc72605225b0cee3f62312ec680ec5f41ba5")
+        );
+    }
+}
@@ -14,6 +14,8 @@
 use core::cmp;
 
 use super::constants::{MAX_BLOCK_SIGOPS_COST, WITNESS_SCALE_FACTOR};
+use crate::transaction::{Transaction, TransactionExt as _};
+use crate::{Amount, FeeRate, Weight};
 
 /// Maximum weight of a transaction for it to be relayed by most nodes on the network
 pub const MAX_STANDARD_TX_WEIGHT: u32 = 400_000;
@@ -43,11 +45,91 @@
 /// mempools.
 pub const DEFAULT_MEMPOOL_EXPIRY: u32 = 336;
 
-// 80 bytes of data, +1 for OP_RETURN, +2 for the pushdata opcodes.
-pub(crate) const MAX_OP_RETURN_RELAY: usize = 83;
+/// Default maximum size, in bytes, of an `OP_RETURN` output script relayed by most nodes on the network.
+///
+/// 80 bytes of data, +1 for OP_RETURN, +2 for the pushdata opcodes.
+pub const MAX_OP_RETURN_RELAY: usize = 83;
 
 /// The virtual transaction size, as computed by default by bitcoind node.
 pub fn get_virtual_tx_size(weight: i64, n_sigops: i64) -> i64 {
     (cmp::max(weight, n_sigops * DEFAULT_BYTES_PER_SIGOP as i64) + WITNESS_SCALE_FACTOR as i64 - 1)
         / WITNESS_SCALE_FACTOR as i64
 }
+
+/// Computes the combined fee rate of a package of transactions, e.g. a parent and a child paying
+/// for it (CPFP).
+///
+/// `txs` contains each transaction together with the fee it pays. The package fee rate is the sum
+/// of the fees divided by the sum of the weights, rounded down. A package of a single transaction
+/// has that transaction's fee rate.
+///
+/// Returns `None` if:
+///
+/// - `txs` is empty, a package without transactions has no weight to divide the fees by.
+/// - The sum of the fees or the sum of the weights overflows.
+/// - Computing the fee rate from the sums overflows.
+pub fn package_feerate(txs: &[(Transaction, Amount)]) -> Option<FeeRate> {
+    if txs.is_empty() {
+        return None;
+    }
+    let mut fee = Amount::ZERO;
+    let mut weight = Weight::ZERO;
+    for (tx, tx_fee) in txs {
+        fee = fee.checked_add(*tx_fee)?;
+        weight = weight.checked_add(tx.weight())?;
+    }
+    fee.checked_div_by_weight_floor(weight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locktime::absolute;
+    use crate::transaction::{self, OutPoint, TxIn, TxOut};
+    use crate::{ScriptBuf, Witness};
+
+    fn tx_with_outputs(n: usize) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::COINBASE_PREVOUT,
+                script_sig: ScriptBuf::new(),
+                sequence: crate::Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut { value: Amount::ONE_SAT, script_pubkey: ScriptBuf::new() }; n],
+        }
+    }
+
+    #[test]
+    fn package_feerate_cpfp() {
+        let parent = tx_with_outputs(10);
+        let child = tx_with_outputs(1);
+        let parent_fee = Amount::from_sat_u32(1).checked_mul(parent.vsize() as u64).unwrap();
+        let child_fee = Amount::from_sat_u32(50).checked_mul(child.vsize() as u64).unwrap();
+
+        // A single transaction reduces to its own fee rate.
+        let parent_only = [(parent.clone(), parent_fee)];
+        assert_eq!(package_feerate(&parent_only), Some(FeeRate::from_sat_per_vb_unchecked(1)));
+
+        let package = [(parent.clone(), parent_fee), (child.clone(), child_fee)];
+        let expected = FeeRate::from_sat_per_kwu(
+            (parent_fee.to_sat() + child_fee.to_sat()) * 1000
+                / (parent.weight() + child.weight()).to_wu(),
+        );
+        let feerate = package_feerate(&package).unwrap();
+        assert_eq!(feerate, expected);
+        assert!(feerate > FeeRate::from_sat_per_vb_unchecked(1));
+        assert!(feerate < FeeRate::from_sat_per_vb_unchecked(50));
+    }
+
+    #[test]
+    fn package_feerate_edge_cases() {
+        assert_eq!(package_feerate(&[]), None);
+
+        let tx = tx_with_outputs(1);
+        assert_eq!(package_feerate(&[(tx.clone(), Amount::ZERO)]), Some(FeeRate::ZERO));
+        assert_eq!(package_feerate(&[(tx.clone(), Amount::MAX), (tx, Amount::ONE_SAT)]), None);
+    }
+}
@@ -4,6 +4,8 @@
 //!
 //! Provides the [`Work`] and [`Target`] types that are used in proof-of-work calculations. The
 //! functions here are designed to be fast, by that we mean it is safe to use them to check headers.
+//!
+//! The [`U256`] integer type underlying both is exposed for custom target and work arithmetic.
 
 use core::ops::{Add, Div, Mul, Not, Rem, Shl, Shr, Sub};
 use core::{cmp, fmt};
@@ -90,6 +92,11 @@ fn fmt(&self, f: &mut fmt::Formatter) -> core::fmt::Result {
 /// A 256 bit integer representing work.
 ///
 /// Work is a measure of how difficult it is to find a hash below a given [`Target`].
+///
+/// The work of a sequence of headers, the chainwork, can be accumulated with [`Add`] or [`Sum`].
+/// Accumulation saturates at the maximum value instead of overflowing.
+///
+/// [`Sum`]: core::iter::Sum
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Work(U256);
@@ -111,7 +118,15 @@ pub fn log2(self) -> f64 { self.0.to_f64().log2() }
 
 impl Add for Work {
     type Output = Work;
-    fn add(self, rhs: Self) -> Self { Work(self.0 + rhs.0) }
+    fn add(self, rhs: Self) -> Self { Work(self.0.saturating_add(rhs.0)) }
+}
+
+impl core::iter::Sum for Work {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Work(U256::ZERO), Add::add) }
+}
+
+impl<'a> core::iter::Sum<&'a Work> for Work {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self { iter.copied().sum() }
 }
 
 impl Sub for Work {
@@ -191,6 +206,30 @@ pub fn from_compact(c: CompactTarget) -> Target {
         }
     }
 
+    /// Computes the [`Target`] value from a compact representation, rejecting invalid encodings.
+    ///
+    /// In line with Bitcoin Core's `CheckProofOfWork` this returns `None` if the compact value
+    /// encodes a negative number, zero, or a number that does not fit in 256 bits.
+    pub(crate) fn from_compact_checked(c: CompactTarget) -> Option<Target> {
+        let bits = c.to_consensus();
+        let size = bits >> 24;
+        let word = bits & 0x007F_FFFF;
+
+        let negative = word != 0 && (bits & 0x0080_0000) != 0;
+        let overflow =
+            word != 0 && (size > 34 || (word > 0xFF && size > 33) || (word > 0xFFFF && size > 32));
+        if negative || overflow {
+            return None;
+        }
+
+        let target = Target::from_compact(c);
+        if target == Target::ZERO {
+            None
+        } else {
+            Some(target)
+        }
+    }
+
     /// Computes the compact value from a [`Target`] representation.
     ///
     /// The compact form is by definition lossy, this means that
@@ -347,6 +386,11 @@ fn from_unprefixed_hex(s: &str) -> Result<CompactTarget, UnprefixedHexError> {
             Ok(Self::from_consensus(target))
         }
 
+        /// Computes the [`Target`] value from this compact representation.
+        ///
+        /// This is the same as [`Target::from_compact`].
+        fn to_target(self) -> Target { Target::from_compact(self) }
+
         /// Computes the [`CompactTarget`] from a difficulty adjustment.
         ///
         /// ref: <https://github.com/bitcoin/bitcoin/blob/0503cbea9aab47ec0a87d34611e5453158727169/src/pow.cpp>
@@ -368,6 +412,10 @@ fn from_unprefixed_hex(s: &str) -> Result<CompactTarget, UnprefixedHexError> {
         ///
         /// To calculate the timespan, users should first convert their u32 timestamps to i64s before subtracting them
         ///
+        /// This only implements the base retarget rule. Testnet's minimum difficulty rule (which
+        /// allows a block to use the maximum target if it is more than 20 minutes after its parent)
+        /// is not applied and must be handled by the caller.
+        ///
         /// # Returns
         ///
         /// The expected [`CompactTarget`] recalculation.
@@ -450,18 +498,34 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Erro
     }
 }
 
-/// Big-endian 256 bit integer type.
+/// Big-endian 256 bit unsigned integer type.
+///
+/// This is a minimal integer type for [`Target`] and [`Work`] arithmetic. The arithmetic
+/// operators panic on overflow in debug builds and wrap in release builds, like the primitive
+/// integer types; use the `checked_` methods to detect overflow.
+///
+/// ```
+/// use bitcoin::pow::{Target, U256};
+///
+/// // The work of a target is 2^256 / (target + 1).
+/// let target = U256::from(Target::MAX);
+/// let work = (!target / (target + U256::ONE)) + U256::ONE;
+/// assert_eq!(work, U256::from(Target::MAX.to_work()));
+/// ```
 // (high, low): u.0 contains the high bits, u.1 contains the low bits.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-struct U256(u128, u128);
+pub struct U256(u128, u128);
 
 impl U256 {
-    const MAX: U256 =
+    /// The maximum value, 2^256 - 1.
+    pub const MAX: U256 =
         U256(0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff);
 
-    const ZERO: U256 = U256(0, 0);
+    /// Zero.
+    pub const ZERO: U256 = U256(0, 0);
 
-    const ONE: U256 = U256(0, 1);
+    /// One.
+    pub const ONE: U256 = U256(0, 1);
 
     /// Constructs a new `U256` from a prefixed hex string.
     fn from_hex(s: &str) -> Result<Self, PrefixedHexError> {
@@ -494,7 +558,7 @@ fn from_hex_internal(s: &str) -> Result<Self, ParseIntError> {
     }
 
     /// Constructs a new `U256` from a big-endian array of `u8`s.
-    fn from_be_bytes(a: [u8; 32]) -> U256 {
+    pub fn from_be_bytes(a: [u8; 32]) -> U256 {
         let (high, low) = split_in_half(a);
         let big = u128::from_be_bytes(high);
         let little = u128::from_be_bytes(low);
@@ -510,7 +574,7 @@ fn from_hex_internal(s: &str) -> Result<Self, ParseIntError> {
     }
 
     /// Converts `U256` to a big-endian array of `u8`s.
-    fn to_be_bytes(self) -> [u8; 32] {
+    pub fn to_be_bytes(self) -> [u8; 32] {
         let mut out = [0; 32];
         out[..16].copy_from_slice(&self.0.to_be_bytes());
         out[16..].copy_from_slice(&self.1.to_be_bytes());
@@ -650,6 +714,43 @@ fn div_rem(self, rhs: Self) -> (Self, Self) {
         (U256(ret[0], ret[1]), sub_copy)
     }
 
+    /// Checked addition. Returns `None` if overflow occurred.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_add(rhs) {
+            (_, true) => None,
+            (ret, false) => Some(ret),
+        }
+    }
+
+    /// Checked subtraction. Returns `None` if overflow occurred.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (_, true) => None,
+            (ret, false) => Some(ret),
+        }
+    }
+
+    /// Checked multiplication. Returns `None` if overflow occurred.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        match self.overflowing_mul(rhs) {
+            (_, true) => None,
+            (ret, false) => Some(ret),
+        }
+    }
+
+    /// Checked division. Returns `None` if `rhs` is zero.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_zero() {
+            None
+        } else {
+            Some(self.div_rem(rhs).0)
+        }
+    }
+
     /// Calculates `self` + `rhs`
     ///
     /// Returns a tuple of the addition along with a boolean indicating whether an arithmetic
@@ -695,19 +796,19 @@ fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
         let mut ret = U256::ZERO;
         let mut ret_overflow = false;
 
-        for i in 0..3 {
+        for i in 0..4 {
             let to_mul = (rhs >> (64 * i)).low_u64();
-            let (mul_res, _) = self.mul_u64(to_mul);
-            ret = ret.wrapping_add(mul_res << (64 * i));
+            let (mul_res, overflow) = self.mul_u64(to_mul);
+            ret_overflow |= overflow;
+            // The bits shifted out of the partial product overflow too.
+            if i > 0 && !(mul_res >> (256 - 64 * i)).is_zero() {
+                ret_overflow = true;
+            }
+            let (sum, overflow) = ret.overflowing_add(mul_res << (64 * i));
+            ret = sum;
+            ret_overflow |= overflow;
         }
 
-        let to_mul = (rhs >> 192).low_u64();
-        let (mul_res, overflow) = self.mul_u64(to_mul);
-        ret_overflow |= overflow;
-        let (sum, overflow) = ret.overflowing_add(mul_res);
-        ret = sum;
-        ret_overflow |= overflow;
-
         (ret, ret_overflow)
     }
 
@@ -719,6 +820,16 @@ fn wrapping_add(self, rhs: Self) -> Self {
         ret
     }
 
+    /// Saturating addition. Computes `self + rhs`, saturating at the numeric bounds instead of
+    /// overflowing.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    fn saturating_add(self, rhs: Self) -> Self {
+        match self.overflowing_add(rhs) {
+            (_, true) => U256::MAX,
+            (ret, false) => ret,
+        }
+    }
+
     /// Wrapping (modular) subtraction. Computes `self - rhs`, wrapping around at the boundary of
     /// the type.
     #[must_use = "this returns the result of the operation, without modifying the original"]
@@ -865,6 +976,22 @@ impl<T: Into<u128>> From<T> for U256 {
     fn from(x: T) -> Self { U256(0, x.into()) }
 }
 
+impl From<Target> for U256 {
+    fn from(target: Target) -> Self { target.0 }
+}
+
+impl From<U256> for Target {
+    fn from(x: U256) -> Self { Target(x) }
+}
+
+impl From<Work> for U256 {
+    fn from(work: Work) -> Self { work.0 }
+}
+
+impl From<U256> for Work {
+    fn from(x: U256) -> Self { Work(x) }
+}
+
 impl Add for U256 {
     type Output = Self;
     fn add(self, rhs: Self) -> Self {
@@ -1546,11 +1673,12 @@ fn u256_multiplication_bits_in_each_word() {
 
         let (got, overflow) = x.overflowing_mul(y);
 
+        // The full product does not fit in 256 bits, only the low words are kept.
         let want = U256(
-            0x0000_0000_0000_0008_0000_0000_0000_0008,
-            0x0000_0000_0000_0006_0000_0000_0000_0004,
+            0x0000_0000_0000_0008_0000_0000_0000_0006,
+            0x0000_0000_0000_0004_0000_0000_0000_0002,
         );
-        assert!(!overflow);
+        assert!(overflow);
         assert_eq!(got, want)
     }
 
@@ -1750,6 +1878,30 @@ fn compact_target_from_downwards_difficulty_adjustment() {
         assert_eq!(adjustment, adjustment_bits);
     }
 
+    #[test]
+    fn compact_target_from_mainnet_difficulty_adjustments() {
+        // Test vectors from Bitcoin Core's `pow_tests.cpp`.
+        // (last bits, epoch start time, epoch end time, next bits)
+        let tests = [
+            // Block 2016, limited by the maximum target.
+            (0x1d00_ffff, 1231006505, 1233061996, 0x1d00_ffff),
+            // Block 32256, the first difficulty increase.
+            (0x1d00_ffff, 1261130161, 1262152739, 0x1d00_d86a),
+            // Block 68544, limited by the minimum transition threshold.
+            (0x1c05_a3f4, 1279008237, 1279297671, 0x1c01_68fd),
+            // Block 48384, limited by the maximum transition threshold.
+            (0x1c38_7f6f, 1263163443, 1269211443, 0x1d00_e1fd),
+        ];
+
+        for (last, start_time, end_time, want) in tests {
+            let last = CompactTarget::from_consensus(last);
+            let timespan = i64::from(end_time) - i64::from(start_time);
+            let got =
+                CompactTarget::from_next_work_required(last, timespan, crate::Network::Bitcoin);
+            assert_eq!(got, CompactTarget::from_consensus(want));
+        }
+    }
+
     #[test]
     fn compact_target_from_upwards_difficulty_adjustment_using_headers() {
         use crate::block::Version;
@@ -1867,6 +2019,43 @@ fn target_from_compact() {
         }
     }
 
+    #[test]
+    fn target_from_compact_checked() {
+        let valid = CompactTarget::from_consensus(0x1d00_ffff);
+        assert_eq!(Target::from_compact_checked(valid), Some(Target::MAX));
+
+        // (nBits, reason)
+        let invalid = [
+            (0x0000_0000_u32, "zero"),
+            (0x0100_3456_u32, "zero after shifting"),
+            (0x0492_3456_u32, "negative"),
+            (0x04ff_ffff_u32, "negative"),
+            (0x2301_0000_u32, "overflow"),
+            (0x2201_0000_u32, "overflow"),
+            (0x2200_0100_u32, "overflow"),
+            (0x2101_0000_u32, "overflow"),
+            (0xff01_0000_u32, "overflow"),
+        ];
+        for (n_bits, reason) in invalid {
+            let compact = CompactTarget::from_consensus(n_bits);
+            assert_eq!(Target::from_compact_checked(compact), None, "{:#x}: {}", n_bits, reason);
+        }
+
+        // Largest values that don't overflow.
+        for n_bits in [0x2200_00ff_u32, 0x2100_ffff_u32, 0x2000_ffff_u32] {
+            let compact = CompactTarget::from_consensus(n_bits);
+            assert!(Target::from_compact_checked(compact).is_some(), "{:#x}", n_bits);
+        }
+    }
+
+    #[test]
+    fn compact_target_to_target() {
+        for n_bits in [0x1d00_ffff_u32, 0x1b04_864c, 0x0492_3456, 0x1712_3456] {
+            let compact = CompactTarget::from_consensus(n_bits);
+            assert_eq!(compact.to_target(), Target::from_compact(compact));
+        }
+    }
+
     #[test]
     fn target_is_met_by_for_target_equals_hash() {
         let hash = "ef537f25c895bfa782526529a9b63d97aa631564d5d789c2b765448c8635fb6c"
@@ -2006,9 +2195,84 @@ fn mul_u64_overflows() {
     fn u256_multiplication_by_max_panics() { let _ = U256::MAX * U256::MAX; }
 
     #[test]
-    #[cfg(debug_assertions)]
-    #[should_panic]
-    fn work_overflowing_addition_panics() { let _ = Work(U256::MAX) + Work(U256::ONE); }
+    fn u256_work_division() {
+        // 2^256 / (target + 1) computed with the public operations.
+        let work = |target: Target| {
+            let target = U256::from(target);
+            let quotient = (!target).checked_div(target.checked_add(U256::ONE).unwrap()).unwrap();
+            quotient.checked_add(U256::ONE).unwrap()
+        };
+
+        // The minimum difficulty, e.g. the genesis block.
+        let target = Target::from_compact(CompactTarget::from_consensus(0x1d00ffff));
+        assert_eq!(work(target), U256::from(0x1_0001_0001_u64));
+        assert_eq!(work(target), U256::from(target.to_work()));
+        // Block 840,000.
+        let target = Target::from_compact(CompactTarget::from_consensus(0x17034219));
+        assert_eq!(work(target), U256::from(0x4e92_35f0_4363_4662_e0cb_u128));
+        assert_eq!(Work::from(work(target)), target.to_work());
+        assert_eq!(Target::from(U256::from(target)), target);
+    }
+
+    #[test]
+    fn u256_public_api() {
+        let bytes = {
+            let mut bytes = [0; 32];
+            bytes[0] = 0x01;
+            bytes[31] = 0xff;
+            bytes
+        };
+        let x = U256::from_be_bytes(bytes);
+        assert_eq!(x.to_be_bytes(), bytes);
+        assert_eq!(x, (U256::ONE << 248) + U256::from(0xff_u8));
+        assert!(x > U256::from(u128::MAX));
+        assert!(U256::ZERO < U256::ONE && U256::ONE < U256::MAX);
+
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(U256::MAX.checked_add(U256::ZERO), Some(U256::MAX));
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(x.checked_sub(x), Some(U256::ZERO));
+        assert_eq!(x.checked_mul(U256::from(0x100_u16)), None);
+        assert_eq!(
+            U256::from(u128::MAX).checked_mul(U256::from(u128::MAX)),
+            Some(U256::MAX - (U256::from(u128::MAX) << 1))
+        );
+        assert_eq!(x.checked_div(U256::ZERO), None);
+        assert_eq!(x.checked_div(x), Some(U256::ONE));
+        assert_eq!(x * U256::from(2_u8) / U256::from(2_u8), x);
+    }
+
+    #[test]
+    fn work_addition_saturates() {
+        assert_eq!(Work(U256::MAX) + Work(U256::ONE), Work(U256::MAX));
+        assert_eq!(Work(U256::MAX - U256::ONE) + Work(U256::ONE), Work(U256::MAX));
+        assert_eq!([Work(U256::MAX); 3].iter().sum::<Work>(), Work(U256::MAX));
+    }
+
+    #[test]
+    fn chainwork() {
+        use crate::block::HeaderExt as _;
+        use crate::constants::genesis_block;
+
+        let params = Params::new(crate::Network::Bitcoin);
+        let genesis = *genesis_block(&params).header();
+
+        // Every header at the minimum difficulty adds 2^32 + 2^16 + 1 work.
+        assert_eq!(genesis.work(), u128_to_work(0x1_0001_0001));
+        let headers = [genesis; 3];
+        let chainwork = headers.iter().map(|h| h.work()).sum::<Work>();
+        assert_eq!(chainwork, u128_to_work(0x3_0003_0003));
+        assert_eq!(chainwork, headers.iter().fold(Work(U256::ZERO), |acc, h| acc + h.work()));
+
+        // A header at a thousand times the difficulty adds a thousand times more work.
+        let target = Target(Target::MAX.0 / U256::from(1000_u32));
+        let harder = Header { bits: target.to_compact_lossy(), ..genesis };
+        let chainwork = chainwork + harder.work();
+        assert!(chainwork > u128_to_work(1000 * 0x1_0001_0001));
+        assert!(chainwork < u128_to_work(1004 * 0x1_0001_0001));
+        #[cfg(feature = "std")]
+        assert!((chainwork.log2() - 41.97).abs() < 0.01);
+    }
 
     #[test]
     #[cfg(debug_assertions)]
@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Strict PSBT combining.
+//!
+//! BIP 174 allows the Combiner to pick arbitrarily when two PSBTs have different values for the
+//! same key, which is what [`Psbt::combine`] does. A difference usually means that the signers
+//! disagree about the transaction, so [`Psbt::combine_strict`] reports them instead.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use super::{Input, MapLocation, Output, Psbt};
+use crate::prelude::{BTreeMap, Box, String, Vec};
+use crate::transaction::Transaction;
+
+impl Psbt {
+    /// Combines this [`Psbt`] with `other` PSBT, failing if any of their values conflict.
+    ///
+    /// Values are only merged if they are the same in both PSBTs or missing from one of them. The
+    /// PSBT version is the greatest of the two, as for [`Psbt::combine`]. `self` is not modified
+    /// if an error is returned.
+    ///
+    /// # Errors
+    ///
+    /// - [`CombineError::UnexpectedUnsignedTx`] if the unsigned transactions differ.
+    /// - [`CombineError::Conflicts`] listing every field with different values in the two PSBTs,
+    ///   including the entries of maps such as partial signatures that have the same key but
+    ///   different values.
+    pub fn combine_strict(&mut self, other: Self) -> Result<(), CombineError> {
+        if self.unsigned_tx != other.unsigned_tx {
+            return Err(CombineError::UnexpectedUnsignedTx {
+                expected: Box::new(self.unsigned_tx.clone()),
+                actual: Box::new(other.unsigned_tx),
+            });
+        }
+
+        let mut conflicts = Conflicts { map: MapLocation::Global, list: Vec::new() };
+        conflicts.map("xpub", &self.xpub, &other.xpub);
+        conflicts.map("proprietary", &self.proprietary, &other.proprietary);
+        conflicts.map("unknown", &self.unknown, &other.unknown);
+        for (index, (ours, theirs)) in self.inputs.iter().zip(&other.inputs).enumerate() {
+            conflicts.map = MapLocation::Input(index);
+            conflicts.input(ours, theirs);
+        }
+        for (index, (ours, theirs)) in self.outputs.iter().zip(&other.outputs).enumerate() {
+            conflicts.map = MapLocation::Output(index);
+            conflicts.output(ours, theirs);
+        }
+        if !conflicts.list.is_empty() {
+            return Err(CombineError::Conflicts(conflicts.list));
+        }
+
+        self.combine(other).expect("unsigned transactions and xpubs checked above");
+        Ok(())
+    }
+}
+
+/// Collects the conflicting fields of the maps at `map`.
+struct Conflicts {
+    map: MapLocation,
+    list: Vec<FieldConflict>,
+}
+
+impl Conflicts {
+    fn field<T: PartialEq + fmt::Debug>(
+        &mut self,
+        field: &'static str,
+        ours: &Option<T>,
+        theirs: &Option<T>,
+    ) {
+        if let (Some(ours), Some(theirs)) = (ours, theirs) {
+            if ours != theirs {
+                self.push(field, None, ours, theirs);
+            }
+        }
+    }
+
+    fn map<K: Ord + fmt::Debug, V: PartialEq + fmt::Debug>(
+        &mut self,
+        field: &'static str,
+        ours: &BTreeMap<K, V>,
+        theirs: &BTreeMap<K, V>,
+    ) {
+        for (key, theirs) in theirs {
+            match ours.get(key) {
+                Some(ours) if ours != theirs =>
+                    self.push(field, Some(debug_string(key)), ours, theirs),
+                _ => {}
+            }
+        }
+    }
+
+    fn push<T: fmt::Debug>(
+        &mut self,
+        field: &'static str,
+        key: Option<String>,
+        ours: &T,
+        theirs: &T,
+    ) {
+        self.list.push(FieldConflict {
+            map: self.map,
+            field,
+            key,
+            ours: debug_string(ours),
+            theirs: debug_string(theirs),
+        });
+    }
+
+    fn input(&mut self, ours: &Input, theirs: &Input) {
+        self.field("non_witness_utxo", &ours.non_witness_utxo, &theirs.non_witness_utxo);
+        self.field("witness_utxo", &ours.witness_utxo, &theirs.witness_utxo);
+        self.map("partial_sigs", &ours.partial_sigs, &theirs.partial_sigs);
+        self.field("sighash_type", &ours.sighash_type, &theirs.sighash_type);
+        self.field("redeem_script", &ours.redeem_script, &theirs.redeem_script);
+        self.field("witness_script", &ours.witness_script, &theirs.witness_script);
+        self.map("bip32_derivation", &ours.bip32_derivation, &theirs.bip32_derivation);
+        self.field("final_script_sig", &ours.final_script_sig, &theirs.final_script_sig);
+        self.field(
+            "final_script_witness",
+            &ours.final_script_witness,
+            &theirs.final_script_witness,
+        );
+        self.map("ripemd160_preimages", &ours.ripemd160_preimages, &theirs.ripemd160_preimages);
+        self.map("sha256_preimages", &ours.sha256_preimages, &theirs.sha256_preimages);
+        self.map("hash160_preimages", &ours.hash160_preimages, &theirs.hash160_preimages);
+        self.map("hash256_preimages", &ours.hash256_preimages, &theirs.hash256_preimages);
+        self.field("tap_key_sig", &ours.tap_key_sig, &theirs.tap_key_sig);
+        self.map("tap_script_sigs", &ours.tap_script_sigs, &theirs.tap_script_sigs);
+        self.map("tap_scripts", &ours.tap_scripts, &theirs.tap_scripts);
+        self.map("tap_key_origins", &ours.tap_key_origins, &theirs.tap_key_origins);
+        self.field("tap_internal_key", &ours.tap_internal_key, &theirs.tap_internal_key);
+        self.field("tap_merkle_root", &ours.tap_merkle_root, &theirs.tap_merkle_root);
+        self.map(
+            "musig2_participant_pubkeys",
+            &ours.musig2_participant_pubkeys,
+            &theirs.musig2_participant_pubkeys,
+        );
+        self.map("musig2_pub_nonces", &ours.musig2_pub_nonces, &theirs.musig2_pub_nonces);
+        self.map("musig2_partial_sigs", &ours.musig2_partial_sigs, &theirs.musig2_partial_sigs);
+        self.map("proprietary", &ours.proprietary, &theirs.proprietary);
+        self.map("unknown", &ours.unknown, &theirs.unknown);
+    }
+
+    fn output(&mut self, ours: &Output, theirs: &Output) {
+        self.field("redeem_script", &ours.redeem_script, &theirs.redeem_script);
+        self.field("witness_script", &ours.witness_script, &theirs.witness_script);
+        self.map("bip32_derivation", &ours.bip32_derivation, &theirs.bip32_derivation);
+        self.field("tap_internal_key", &ours.tap_internal_key, &theirs.tap_internal_key);
+        self.field("tap_tree", &ours.tap_tree, &theirs.tap_tree);
+        self.map("tap_key_origins", &ours.tap_key_origins, &theirs.tap_key_origins);
+        self.map(
+            "musig2_participant_pubkeys",
+            &ours.musig2_participant_pubkeys,
+            &theirs.musig2_participant_pubkeys,
+        );
+        self.map("proprietary", &ours.proprietary, &theirs.proprietary);
+        self.map("unknown", &ours.unknown, &theirs.unknown);
+    }
+}
+
+fn debug_string<T: fmt::Debug>(value: &T) -> String {
+    use core::fmt::Write as _;
+
+    let mut s = String::new();
+    write!(s, "{:?}", value).expect("writing to a string can not fail");
+    s
+}
+
+/// A field that has different values in two PSBTs being combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldConflict {
+    /// The map containing the field.
+    pub map: MapLocation,
+    /// The name of the field, e.g. `sighash_type`.
+    pub field: &'static str,
+    /// The debug representation of the key of the entry, for fields that are maps.
+    pub key: Option<String>,
+    /// The debug representation of the value in the PSBT being combined into.
+    pub ours: String,
+    /// The debug representation of the value in the other PSBT.
+    pub theirs: String,
+}
+
+impl fmt::Display for FieldConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {}", self.field, self.map)?;
+        if let Some(ref key) = self.key {
+            write!(f, " for key {}", key)?;
+        }
+        write!(f, " is {} and {}", self.ours, self.theirs)
+    }
+}
+
+/// Error combining two PSBTs with [`Psbt::combine_strict`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CombineError {
+    /// The PSBTs are for different unsigned transactions.
+    UnexpectedUnsignedTx {
+        /// The unsigned transaction of the PSBT being combined into.
+        expected: Box<Transaction>,
+        /// The unsigned transaction of the other PSBT.
+        actual: Box<Transaction>,
+    },
+    /// Fields have different values in the two PSBTs.
+    Conflicts(Vec<FieldConflict>),
+}
+
+impl From<Infallible> for CombineError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for CombineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use CombineError::*;
+
+        match *self {
+            UnexpectedUnsignedTx { ref expected, ref actual } => write!(
+                f,
+                "different unsigned transaction: expected {}, actual {}",
+                expected.compute_txid(),
+                actual.compute_txid()
+            ),
+            Conflicts(ref conflicts) => {
+                write!(f, "conflicting values:")?;
+                for (i, conflict) in conflicts.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, conflict)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CombineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use CombineError::*;
+
+        match *self {
+            UnexpectedUnsignedTx { .. } | Conflicts(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::FromHex as _;
+
+    use super::*;
+    use crate::sighash::{EcdsaSighashType, TapSighashType};
+    use crate::Amount;
+
+    fn psbt(hex: &str) -> Psbt { Psbt::deserialize(&Vec::from_hex(hex).unwrap()).unwrap() }
+
+    // PSBTs taken from BIP 174 test vectors.
+    #[test]
+    fn combine_strict() {
+        let mut psbt1 = psbt(include_str!("../../tests/data/psbt1.hex"));
+        let psbt2 = psbt(include_str!("../../tests/data/psbt2.hex"));
+
+        let mut combined = psbt1.clone();
+        combined.combine(psbt2.clone()).unwrap();
+        psbt1.combine_strict(psbt2).unwrap();
+        assert_eq!(psbt1, combined);
+    }
+
+    #[test]
+    fn combine_strict_sighash_type() {
+        let mut ours = psbt(include_str!("../../tests/data/psbt2.hex"));
+        let mut theirs = ours.clone();
+
+        // Values missing from one side are merged.
+        theirs.inputs[0].sighash_type = Some(EcdsaSighashType::All.into());
+        ours.combine_strict(theirs.clone()).unwrap();
+        assert_eq!(ours.inputs[0].sighash_type, Some(EcdsaSighashType::All.into()));
+
+        theirs.inputs[0].sighash_type = Some(TapSighashType::Default.into());
+        let before = ours.clone();
+        let err = ours.combine_strict(theirs).unwrap_err();
+        assert_eq!(
+            err,
+            CombineError::Conflicts(vec![FieldConflict {
+                map: MapLocation::Input(0),
+                field: "sighash_type",
+                key: None,
+                ours: String::from("PsbtSighashType { inner: 1 }"),
+                theirs: String::from("PsbtSighashType { inner: 0 }"),
+            }])
+        );
+        assert_eq!(ours, before);
+    }
+
+    #[test]
+    fn combine_strict_witness_utxo_and_partial_sig() {
+        let mut ours = psbt(include_str!("../../tests/data/psbt2.hex"));
+        let mut theirs = ours.clone();
+
+        let utxo = theirs.inputs[1].witness_utxo.as_mut().unwrap();
+        utxo.value = (utxo.value + Amount::ONE_SAT).unwrap();
+        let (key, sig) = theirs.inputs[1].partial_sigs.iter_mut().next().unwrap();
+        let key = *key;
+        sig.sighash_type = EcdsaSighashType::None;
+
+        let err = ours.combine_strict(theirs.clone()).unwrap_err();
+        let conflicts = match err {
+            CombineError::Conflicts(conflicts) => conflicts,
+            e => panic!("unexpected error: {}", e),
+        };
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!((conflicts[0].map, conflicts[0].field), (MapLocation::Input(1), "witness_utxo"));
+        assert_eq!(
+            conflicts[0].theirs,
+            debug_string(theirs.inputs[1].witness_utxo.as_ref().unwrap())
+        );
+        assert_eq!((conflicts[1].map, conflicts[1].field), (MapLocation::Input(1), "partial_sigs"));
+        assert_eq!(conflicts[1].key, Some(debug_string(&key)));
+
+        theirs.unsigned_tx.lock_time = crate::absolute::LockTime::from_consensus(1);
+        assert!(matches!(
+            ours.combine_strict(theirs),
+            Err(CombineError::UnexpectedUnsignedTx { .. })
+        ));
+    }
+}
@@ -0,0 +1,579 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! PSBT key origin maintenance.
+//!
+//! PSBTs assembled from several sources can contain key origins that are redundant, that
+//! contradict each other or that belong to keys the spending scripts do not use. The functions
+//! here clean up the `bip32_derivation` and `tap_key_origins` maps of inputs and outputs and
+//! report everything they find.
+
+use core::fmt;
+
+use hashes::hash160;
+use secp256k1::XOnlyPublicKey;
+
+use super::{Input, Output, Psbt};
+use crate::bip32::{DerivationPath, Fingerprint, KeySource};
+use crate::prelude::{BTreeMap, BTreeSet, Vec};
+use crate::script::{Instruction, Script, ScriptBuf, ScriptExt as _};
+use crate::taproot::TapLeafHash;
+
+impl Psbt {
+    /// Deduplicates and validates the key origins of all inputs and outputs of this PSBT.
+    ///
+    /// For each input and output:
+    ///
+    /// - Repeated leaf hashes in `tap_key_origins` are removed.
+    /// - Keys that have the same origin in `bip32_derivation` and `tap_key_origins` of a Taproot
+    ///   input or output are removed from `bip32_derivation`.
+    /// - Keys that have different origins in the two maps are reported, neither entry is changed.
+    /// - Keys that can not be used by the scripts are removed. `bip32_derivation` keys are checked
+    ///   against the witness script, the redeem script or the script pubkey, whichever holds the
+    ///   keys and is known. `tap_key_origins` keys are checked against the internal key and the
+    ///   scripts of the leaves they list, if the scripts are known. Origins are kept if there is
+    ///   nothing to check them against.
+    ///
+    /// # Returns
+    ///
+    /// Everything that was found, in order of inputs then outputs.
+    pub fn sanitize_derivations(&mut self) -> Vec<DerivationIssue> {
+        let mut issues = Vec::new();
+
+        for index in 0..self.inputs.len() {
+            let map = MapLocation::Input(index);
+            let utxo_spk = self.spend_utxo(index).ok().map(|utxo| utxo.script_pubkey.clone());
+            let input = &mut self.inputs[index];
+
+            let leaves = input
+                .tap_scripts
+                .values()
+                .map(|(script, ver)| (TapLeafHash::from_script(script, *ver), script.clone()))
+                .collect::<BTreeMap<_, _>>();
+            let is_taproot = input.tap_internal_key.is_some()
+                || utxo_spk.as_ref().map_or(false, |spk| spk.is_p2tr());
+            let key_script = key_script(
+                input.witness_script.as_deref(),
+                input.redeem_script.as_deref(),
+                utxo_spk.as_deref(),
+            );
+
+            sanitize_map(
+                map,
+                &mut input.bip32_derivation,
+                &mut input.tap_key_origins,
+                is_taproot,
+                key_script,
+                input.tap_internal_key,
+                &leaves,
+                &mut issues,
+            );
+        }
+
+        for index in 0..self.outputs.len() {
+            let map = MapLocation::Output(index);
+            let spk = self.unsigned_tx.output.get(index).map(|txout| &txout.script_pubkey);
+            let output = &mut self.outputs[index];
+
+            let leaves = output
+                .tap_tree
+                .iter()
+                .flat_map(|tree| tree.script_leaves())
+                .map(|leaf| {
+                    let script = leaf.script();
+                    (TapLeafHash::from_script(script, leaf.version()), ScriptBuf::from(script))
+                })
+                .collect::<BTreeMap<_, _>>();
+            let is_taproot =
+                output.tap_internal_key.is_some() || spk.map_or(false, |spk| spk.is_p2tr());
+            let key_script = key_script(
+                output.witness_script.as_deref(),
+                output.redeem_script.as_deref(),
+                spk.map(|spk| spk.as_script()),
+            );
+
+            sanitize_map(
+                map,
+                &mut output.bip32_derivation,
+                &mut output.tap_key_origins,
+                is_taproot,
+                key_script,
+                output.tap_internal_key,
+                &leaves,
+                &mut issues,
+            );
+        }
+
+        issues
+    }
+}
+
+impl Input {
+    /// Returns the keys of this input derived from the master key with `fingerprint`.
+    ///
+    /// Yields the keys in `bip32_derivation` followed by the keys in `tap_key_origins`, each with
+    /// its derivation path from the master key.
+    pub fn derivation_for(
+        &self,
+        fingerprint: Fingerprint,
+    ) -> impl Iterator<Item = (OriginKey, &DerivationPath)> + '_ {
+        origins_for(&self.bip32_derivation, &self.tap_key_origins, fingerprint)
+    }
+}
+
+impl Output {
+    /// Returns the keys of this output derived from the master key with `fingerprint`.
+    ///
+    /// Yields the keys in `bip32_derivation` followed by the keys in `tap_key_origins`, each with
+    /// its derivation path from the master key.
+    pub fn derivation_for(
+        &self,
+        fingerprint: Fingerprint,
+    ) -> impl Iterator<Item = (OriginKey, &DerivationPath)> + '_ {
+        origins_for(&self.bip32_derivation, &self.tap_key_origins, fingerprint)
+    }
+}
+
+/// A key from the key origin maps of a PSBT input or output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OriginKey {
+    /// A key from `bip32_derivation`.
+    Bip32(secp256k1::PublicKey),
+    /// A key from `tap_key_origins`.
+    Taproot(XOnlyPublicKey),
+}
+
+/// The location of a map in a PSBT.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapLocation {
+    /// The global map.
+    Global,
+    /// The input at the index.
+    Input(usize),
+    /// The output at the index.
+    Output(usize),
+}
+
+impl fmt::Display for MapLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MapLocation::Global => f.write_str("the global map"),
+            MapLocation::Input(index) => write!(f, "input {}", index),
+            MapLocation::Output(index) => write!(f, "output {}", index),
+        }
+    }
+}
+
+/// An issue found in the key origins of a PSBT by [`Psbt::sanitize_derivations`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DerivationIssue {
+    /// Repeated leaf hashes were removed from the Taproot origin of the key.
+    DuplicateLeafHashes {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: XOnlyPublicKey,
+    },
+    /// The key has the same origin in both maps of a Taproot input or output, the
+    /// `bip32_derivation` entry was removed.
+    DuplicateOrigin {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: XOnlyPublicKey,
+    },
+    /// The key has different origins in the two maps, neither was changed.
+    ConflictingOrigins {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: XOnlyPublicKey,
+        /// The origin in `bip32_derivation`.
+        bip32: KeySource,
+        /// The origin in `tap_key_origins`.
+        taproot: KeySource,
+    },
+    /// The key does not appear in the script and its `bip32_derivation` entry was removed.
+    UnusedKey {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: secp256k1::PublicKey,
+        /// The removed origin.
+        origin: KeySource,
+    },
+    /// The key is neither the internal key nor in any of its leaf scripts and its
+    /// `tap_key_origins` entry was removed.
+    UnusedTapKey {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: XOnlyPublicKey,
+        /// The removed origin.
+        origin: KeySource,
+    },
+    /// The leaf scripts do not contain the key and were removed from its Taproot origin.
+    UnusedTapLeaves {
+        /// The input or output of the key.
+        map: MapLocation,
+        /// The key.
+        key: XOnlyPublicKey,
+        /// The removed leaf hashes.
+        leaf_hashes: Vec<TapLeafHash>,
+    },
+}
+
+fn origins_for<'a>(
+    bip32_derivation: &'a BTreeMap<secp256k1::PublicKey, KeySource>,
+    tap_key_origins: &'a BTreeMap<XOnlyPublicKey, (Vec<TapLeafHash>, KeySource)>,
+    fingerprint: Fingerprint,
+) -> impl Iterator<Item = (OriginKey, &'a DerivationPath)> {
+    let bip32 = bip32_derivation
+        .iter()
+        .filter(move |(_, (fp, _))| *fp == fingerprint)
+        .map(|(key, (_, path))| (OriginKey::Bip32(*key), path));
+    let taproot = tap_key_origins
+        .iter()
+        .filter(move |(_, (_, (fp, _)))| *fp == fingerprint)
+        .map(|(key, (_, (_, path)))| (OriginKey::Taproot(*key), path));
+    bip32.chain(taproot)
+}
+
+/// Returns the script that holds the ECDSA keys of an input or output, if known.
+fn key_script<'a>(
+    witness_script: Option<&'a Script>,
+    redeem_script: Option<&'a Script>,
+    script_pubkey: Option<&'a Script>,
+) -> Option<&'a Script> {
+    match (witness_script, redeem_script, script_pubkey) {
+        (Some(witness_script), _, _) => Some(witness_script),
+        (None, Some(redeem_script), _) if !redeem_script.is_p2wsh() => Some(redeem_script),
+        (None, None, Some(spk)) if !(spk.is_p2sh() || spk.is_p2wsh() || spk.is_p2tr()) => Some(spk),
+        _ => None,
+    }
+}
+
+/// Returns true if `script` pushes `key` or its hash.
+fn contains_key(script: &Script, key: &secp256k1::PublicKey) -> bool {
+    let compressed = key.serialize();
+    let uncompressed = key.serialize_uncompressed();
+    let hashes = [hash160::Hash::hash(&compressed), hash160::Hash::hash(&uncompressed)];
+
+    script.instructions().filter_map(Result::ok).any(|instruction| match instruction {
+        Instruction::PushBytes(push) => {
+            let push = push.as_bytes();
+            push == compressed
+                || push == uncompressed
+                || hashes.iter().any(|hash| push == hash.as_byte_array())
+        }
+        Instruction::Op(_) => false,
+    })
+}
+
+/// Returns true if `script` pushes the x-only `key`.
+fn contains_x_only_key(script: &Script, key: &XOnlyPublicKey) -> bool {
+    let key = key.serialize();
+    script.instructions().filter_map(Result::ok).any(|instruction| match instruction {
+        Instruction::PushBytes(push) => push.as_bytes() == key,
+        Instruction::Op(_) => false,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sanitize_map(
+    map: MapLocation,
+    bip32_derivation: &mut BTreeMap<secp256k1::PublicKey, KeySource>,
+    tap_key_origins: &mut BTreeMap<XOnlyPublicKey, (Vec<TapLeafHash>, KeySource)>,
+    is_taproot: bool,
+    key_script: Option<&Script>,
+    internal_key: Option<XOnlyPublicKey>,
+    leaves: &BTreeMap<TapLeafHash, ScriptBuf>,
+    issues: &mut Vec<DerivationIssue>,
+) {
+    for (key, (leaf_hashes, _)) in tap_key_origins.iter_mut() {
+        let mut seen = BTreeSet::new();
+        let len = leaf_hashes.len();
+        leaf_hashes.retain(|leaf_hash| seen.insert(*leaf_hash));
+        if leaf_hashes.len() != len {
+            issues.push(DerivationIssue::DuplicateLeafHashes { map, key: *key });
+        }
+    }
+
+    bip32_derivation.retain(|key, bip32| {
+        let x_only = key.x_only_public_key().0;
+        match tap_key_origins.get(&x_only) {
+            Some((_, taproot)) if taproot == bip32 && is_taproot => {
+                issues.push(DerivationIssue::DuplicateOrigin { map, key: x_only });
+                return false;
+            }
+            Some((_, taproot)) if taproot == bip32 => {}
+            Some((_, taproot)) => issues.push(DerivationIssue::ConflictingOrigins {
+                map,
+                key: x_only,
+                bip32: bip32.clone(),
+                taproot: taproot.clone(),
+            }),
+            None => {}
+        }
+        true
+    });
+
+    if let Some(script) = key_script {
+        bip32_derivation.retain(|key, origin| {
+            let used = contains_key(script, key);
+            if !used {
+                issues.push(DerivationIssue::UnusedKey { map, key: *key, origin: origin.clone() });
+            }
+            used
+        });
+    }
+
+    tap_key_origins.retain(|key, (leaf_hashes, origin)| {
+        if leaf_hashes.is_empty() {
+            // An origin without leaf hashes is for the internal key.
+            let used = internal_key.map_or(true, |internal_key| internal_key == *key);
+            if !used {
+                issues.push(DerivationIssue::UnusedTapKey {
+                    map,
+                    key: *key,
+                    origin: origin.clone(),
+                });
+            }
+            return used;
+        }
+
+        let (unused, used): (Vec<_>, Vec<_>) = leaf_hashes.iter().copied().partition(|leaf_hash| {
+            leaves.get(leaf_hash).map_or(false, |script| !contains_x_only_key(script, key))
+        });
+        if unused.is_empty() {
+            return true;
+        }
+        if used.is_empty() && internal_key != Some(*key) {
+            issues.push(DerivationIssue::UnusedTapKey { map, key: *key, origin: origin.clone() });
+            return false;
+        }
+        *leaf_hashes = used;
+        issues.push(DerivationIssue::UnusedTapLeaves { map, key: *key, leaf_hashes: unused });
+        true
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{Secp256k1, SecretKey};
+
+    use super::*;
+    use crate::address::script_pubkey::{BuilderExt as _, ScriptBufExt as _};
+    use crate::crypto::key::{CompressedPublicKey, PublicKey};
+    use crate::opcodes::all::{OP_CHECKMULTISIG, OP_CHECKSIG, OP_PUSHNUM_1, OP_PUSHNUM_2};
+    use crate::script::Builder;
+    use crate::taproot::{LeafVersion, TaprootBuilder};
+    use crate::transaction::{self, Transaction, TxIn, TxOut};
+    use crate::{absolute, Amount};
+
+    fn key(i: u8) -> secp256k1::PublicKey {
+        let secp = Secp256k1::signing_only();
+        SecretKey::from_byte_array(&[i; 32]).unwrap().public_key(&secp)
+    }
+
+    fn x_only_key(i: u8) -> XOnlyPublicKey { key(i).x_only_public_key().0 }
+
+    fn origin(fingerprint: u8, path: &str) -> KeySource {
+        (Fingerprint::from([fingerprint; 4]), path.parse().unwrap())
+    }
+
+    fn psbt(inputs: usize, outputs: Vec<ScriptBuf>) -> Psbt {
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE; inputs],
+            output: outputs
+                .into_iter()
+                .map(|script_pubkey| TxOut { value: Amount::ZERO, script_pubkey })
+                .collect(),
+        };
+        Psbt::from_unsigned_tx(tx).unwrap()
+    }
+
+    #[test]
+    fn sanitize_conflicting_and_redundant_origins() {
+        let secp = Secp256k1::verification_only();
+        let leaf_script = Builder::new().push_x_only_key(x_only_key(1)).push_opcode(OP_CHECKSIG);
+        let leaf_script = leaf_script.into_script();
+        let leaf = TapLeafHash::from_script(&leaf_script, LeafVersion::TapScript);
+
+        let mut psbt = psbt(1, vec![]);
+        let input = &mut psbt.inputs[0];
+        input.witness_utxo = Some(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::new_p2tr(&secp, x_only_key(3), None),
+        });
+        input.tap_key_origins.insert(x_only_key(1), (vec![leaf, leaf], origin(1, "m/86'/0'/0'")));
+        input.bip32_derivation.insert(key(1), origin(1, "m/86'/0'/0'"));
+        input.tap_key_origins.insert(x_only_key(2), (vec![leaf], origin(1, "m/86'/0'/1'")));
+        input.bip32_derivation.insert(key(2), origin(2, "m/86'/0'/1'"));
+
+        let issues = psbt.sanitize_derivations();
+        let map = MapLocation::Input(0);
+        let expected = [
+            DerivationIssue::DuplicateLeafHashes { map, key: x_only_key(1) },
+            DerivationIssue::DuplicateOrigin { map, key: x_only_key(1) },
+            DerivationIssue::ConflictingOrigins {
+                map,
+                key: x_only_key(2),
+                bip32: origin(2, "m/86'/0'/1'"),
+                taproot: origin(1, "m/86'/0'/1'"),
+            },
+        ];
+        assert_eq!(issues.len(), expected.len());
+        assert!(expected.iter().all(|issue| issues.contains(issue)));
+
+        let input = &psbt.inputs[0];
+        assert_eq!(input.tap_key_origins[&x_only_key(1)].0, [leaf]);
+        assert!(!input.bip32_derivation.contains_key(&key(1)));
+        // Conflicts are left for the caller to resolve.
+        assert!(input.bip32_derivation.contains_key(&key(2)));
+        assert!(input.tap_key_origins.contains_key(&x_only_key(2)));
+
+        assert_eq!(psbt.sanitize_derivations(), [expected[2].clone()]);
+    }
+
+    #[test]
+    fn sanitize_unused_keys() {
+        let secp = Secp256k1::verification_only();
+        let multisig = Builder::new()
+            .push_opcode(OP_PUSHNUM_1)
+            .push_key(PublicKey::new(key(1)))
+            .push_key(PublicKey::new(key(2)))
+            .push_opcode(OP_PUSHNUM_2)
+            .push_opcode(OP_CHECKMULTISIG)
+            .into_script();
+        let wpkh = CompressedPublicKey(key(1)).wpubkey_hash();
+
+        let leaf_script = Builder::new().push_x_only_key(x_only_key(1)).push_opcode(OP_CHECKSIG);
+        let leaf_script = leaf_script.into_script();
+        let leaf = TapLeafHash::from_script(&leaf_script, LeafVersion::TapScript);
+        let unknown_leaf = TapLeafHash::from_script(&multisig, LeafVersion::TapScript);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, leaf_script.clone())
+            .unwrap()
+            .finalize(&secp, x_only_key(4))
+            .unwrap();
+
+        let mut psbt = psbt(4, vec![ScriptBuf::new_p2wpkh(wpkh)]);
+        let source = origin(1, "m/0");
+
+        // P2WSH multisig of keys 1 and 2.
+        let input = &mut psbt.inputs[0];
+        input.witness_script = Some(multisig.clone());
+        for i in 1..=3 {
+            input.bip32_derivation.insert(key(i), source.clone());
+        }
+        // P2WPKH of key 1.
+        let input = &mut psbt.inputs[1];
+        input.witness_utxo =
+            Some(TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new_p2wpkh(wpkh) });
+        input.bip32_derivation.insert(key(1), source.clone());
+        input.bip32_derivation.insert(key(2), source.clone());
+        // P2TR with internal key 4 and a leaf for key 1.
+        let input = &mut psbt.inputs[2];
+        input.tap_internal_key = Some(x_only_key(4));
+        let script_ver = (leaf_script, LeafVersion::TapScript);
+        input.tap_scripts.insert(spend_info.control_block(&script_ver).unwrap(), script_ver);
+        input.tap_key_origins.insert(x_only_key(4), (vec![], source.clone()));
+        input.tap_key_origins.insert(x_only_key(1), (vec![leaf, unknown_leaf], source.clone()));
+        input.tap_key_origins.insert(x_only_key(2), (vec![leaf], source.clone()));
+        input.tap_key_origins.insert(x_only_key(3), (vec![], source.clone()));
+        // Nothing to check the key against.
+        psbt.inputs[3].bip32_derivation.insert(key(3), source.clone());
+        // P2WPKH output of key 1.
+        psbt.outputs[0].bip32_derivation.insert(key(1), source.clone());
+        psbt.outputs[0].bip32_derivation.insert(key(3), source.clone());
+
+        let issues = psbt.sanitize_derivations();
+        let unused_key =
+            |map, i| DerivationIssue::UnusedKey { map, key: key(i), origin: source.clone() };
+        let unused_tap_key = |i| DerivationIssue::UnusedTapKey {
+            map: MapLocation::Input(2),
+            key: x_only_key(i),
+            origin: source.clone(),
+        };
+        let expected = [
+            unused_key(MapLocation::Input(0), 3),
+            unused_key(MapLocation::Input(1), 2),
+            unused_tap_key(2),
+            unused_tap_key(3),
+            unused_key(MapLocation::Output(0), 3),
+        ];
+        assert_eq!(issues.len(), expected.len());
+        assert!(expected.iter().all(|issue| issues.contains(issue)));
+
+        assert_eq!(psbt.inputs[0].bip32_derivation.len(), 2);
+        assert_eq!(psbt.inputs[1].bip32_derivation.len(), 1);
+        let tap_key_origins = &psbt.inputs[2].tap_key_origins;
+        assert_eq!(tap_key_origins.len(), 2);
+        assert_eq!(tap_key_origins[&x_only_key(1)].0, [leaf, unknown_leaf]);
+        assert!(tap_key_origins.contains_key(&x_only_key(4)));
+        assert_eq!(psbt.inputs[3].bip32_derivation.len(), 1);
+        assert_eq!(psbt.outputs[0].bip32_derivation.len(), 1);
+    }
+
+    #[test]
+    fn sanitize_unused_tap_leaves() {
+        let secp = Secp256k1::verification_only();
+        let script = |i| {
+            Builder::new().push_x_only_key(x_only_key(i)).push_opcode(OP_CHECKSIG).into_script()
+        };
+        let leaves = [script(1), script(2)];
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, leaves[0].clone())
+            .unwrap()
+            .add_leaf(1, leaves[1].clone())
+            .unwrap()
+            .finalize(&secp, x_only_key(3))
+            .unwrap();
+        let leaf_hashes =
+            leaves.iter().map(|s| TapLeafHash::from_script(s, LeafVersion::TapScript)).collect();
+
+        let mut psbt = psbt(1, vec![]);
+        let input = &mut psbt.inputs[0];
+        input.tap_internal_key = Some(x_only_key(3));
+        for script in leaves {
+            let script_ver = (script, LeafVersion::TapScript);
+            input.tap_scripts.insert(spend_info.control_block(&script_ver).unwrap(), script_ver);
+        }
+        input.tap_key_origins.insert(x_only_key(1), (leaf_hashes, origin(1, "m/0")));
+
+        let leaf_hashes = input.tap_key_origins[&x_only_key(1)].0.clone();
+        assert_eq!(
+            psbt.sanitize_derivations(),
+            [DerivationIssue::UnusedTapLeaves {
+                map: MapLocation::Input(0),
+                key: x_only_key(1),
+                leaf_hashes: vec![leaf_hashes[1]],
+            }]
+        );
+        assert_eq!(psbt.inputs[0].tap_key_origins[&x_only_key(1)].0, [leaf_hashes[0]]);
+    }
+
+    #[test]
+    fn derivation_for() {
+        let mut psbt = psbt(1, vec![]);
+        let input = &mut psbt.inputs[0];
+        input.bip32_derivation.insert(key(1), origin(1, "m/84'/0'/0'/0/0"));
+        input.bip32_derivation.insert(key(2), origin(2, "m/84'/0'/0'/0/0"));
+        input.tap_key_origins.insert(x_only_key(3), (vec![], origin(1, "m/86'/0'/0'/0/0")));
+
+        let input = &psbt.inputs[0];
+        let keys = input.derivation_for(Fingerprint::from([1; 4])).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                (OriginKey::Bip32(key(1)), &"m/84'/0'/0'/0/0".parse().unwrap()),
+                (OriginKey::Taproot(x_only_key(3)), &"m/86'/0'/0'/0/0".parse().unwrap()),
+            ]
+        );
+        assert_eq!(input.derivation_for(Fingerprint::from([2; 4])).count(), 1);
+        assert_eq!(input.derivation_for(Fingerprint::from([3; 4])).count(), 0);
+    }
+}
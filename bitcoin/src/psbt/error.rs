@@ -7,6 +7,7 @@
 
 use crate::bip32::Xpub;
 use crate::consensus::encode;
+use crate::locktime::absolute;
 use crate::prelude::Box;
 use crate::psbt::raw;
 use crate::transaction::Transaction;
@@ -32,6 +33,10 @@ pub enum Error {
     InvalidSeparator,
     /// Returned when output index is out of bounds in relation to the output in non-witness UTXO.
     PsbtUtxoOutOfbounds,
+    /// The witness and non-witness UTXO of the input at this index describe different outputs.
+    InconsistentUtxo(usize),
+    /// The input at this index is not finalized and the weight of its satisfaction is unknown.
+    UnknownInputWeight(usize),
     /// Known keys must be according to spec.
     InvalidKey(raw::Key),
     /// Non-proprietary key type found when proprietary key was expected
@@ -104,6 +109,10 @@ pub enum Error {
     Version(&'static str),
     /// PSBT data is not consumed entirely
     PartialDataConsumption,
+    /// A field required by the PSBT version is missing.
+    MissingRequiredField(&'static str),
+    /// A required lock time of a version 2 PSBT input has the wrong type.
+    InvalidRequiredLockTime(absolute::ConversionError),
     /// I/O error.
     Io(io::Error),
 }
@@ -122,6 +131,10 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             InvalidSeparator => f.write_str("invalid separator"),
             PsbtUtxoOutOfbounds =>
                 f.write_str("output index is out of bounds of non witness script output array"),
+            InconsistentUtxo(index) =>
+                write!(f, "the witness and non-witness UTXO of input {} are inconsistent", index),
+            UnknownInputWeight(index) =>
+                write!(f, "can not predict the weight of non-finalized input {}", index),
             InvalidKey(ref rkey) => write!(f, "invalid key: {}", rkey),
             InvalidProprietaryKey =>
                 write!(f, "non-proprietary key type found when proprietary key was expected"),
@@ -167,6 +180,8 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             Version(s) => write!(f, "version error {}", s),
             PartialDataConsumption =>
                 f.write_str("data not consumed entirely when explicitly deserializing"),
+            MissingRequiredField(s) => write!(f, "missing required field: {}", s),
+            InvalidRequiredLockTime(ref e) => write_err!(f, "invalid required lock time"; e),
             Io(ref e) => write_err!(f, "I/O error"; e),
         }
     }
@@ -183,10 +198,13 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             ConsensusDeserialize(ref e) => Some(e),
             ConsensusParse(ref e) => Some(e),
             Io(ref e) => Some(e),
+            InvalidRequiredLockTime(ref e) => Some(e),
             InvalidMagic
             | MissingUtxo
             | InvalidSeparator
             | PsbtUtxoOutOfbounds
+            | InconsistentUtxo(_)
+            | UnknownInputWeight(_)
             | InvalidKey(_)
             | InvalidProprietaryKey
             | DuplicateKey(_)
@@ -211,7 +229,8 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             | TapTree(_)
             | XPubKey(_)
             | Version(_)
-            | PartialDataConsumption => None,
+            | PartialDataConsumption
+            | MissingRequiredField(_) => None,
         }
     }
 }
@@ -0,0 +1,650 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! PSBT input finalizer.
+//!
+//! Implements the Input Finalizer role of BIP-174 for the standard script types. The signatures and
+//! scripts of each input are assembled into the final `scriptSig` and witness, after which all the
+//! other fields used while signing are cleared.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use internals::write_err;
+
+use super::{IndexOutOfBoundsError, Input, Psbt};
+use crate::address::script_pubkey::BuilderExt as _;
+use crate::crypto::ecdsa::SerializedSignature;
+use crate::crypto::key::{CompressedPublicKey, XOnlyPublicKey};
+use crate::opcodes::all::*;
+use crate::opcodes::OP_0;
+use crate::prelude::Vec;
+use crate::script::multisig::Multisig;
+use crate::script::script_type::ScriptType;
+use crate::script::{Builder, Instruction, PushBytes, Script, ScriptBuf, ScriptExt as _};
+use crate::taproot::{LeafVersion, TapLeafHash};
+use crate::witness::{Witness, WitnessExt as _};
+
+impl Psbt {
+    /// Finalizes all inputs of this PSBT, see [`Psbt::finalize_input`].
+    ///
+    /// Every input is attempted even if finalizing an earlier one fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of all inputs that could not be finalized.
+    pub fn finalize(&mut self) -> Result<(), Vec<FinalizeError>> {
+        let errors = (0..self.inputs.len())
+            .filter_map(|index| self.finalize_input(index).err())
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Finalizes the input at `index` as described by the Input Finalizer role in BIP-174.
+    ///
+    /// Constructs the final `scriptSig` and witness from the partial signatures and scripts of the
+    /// input. Supported are P2PK, P2PKH, bare multisig, P2WPKH and multisig wrapped in P2SH, P2WSH
+    /// or both, P2SH-P2WPKH and P2TR. Multisig signatures are ordered to match the public keys in
+    /// the script. Taproot inputs use the key path signature if present, otherwise the one script
+    /// leaf that has all its signatures. Leaves of the form `<key> OP_CHECKSIG` and
+    /// `<key> OP_CHECKSIG <key> OP_CHECKSIGADD ... <m> OP_NUMEQUAL` are supported.
+    ///
+    /// On success all fields except the UTXOs, the final `scriptSig` and witness, and the
+    /// proprietary and unknown fields are cleared. Inputs that are already final are left as is.
+    ///
+    /// # Errors
+    ///
+    /// If the input is missing data required to finalize it or its script type is unsupported.
+    pub fn finalize_input(&mut self, index: usize) -> Result<(), FinalizeError> {
+        let input = self.checked_input(index)?;
+        if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+            return Ok(());
+        }
+
+        let utxo = match (&input.witness_utxo, &input.non_witness_utxo) {
+            (Some(witness_utxo), _) => Some(witness_utxo),
+            (None, Some(tx)) =>
+                tx.output.get(self.unsigned_tx.input[index].previous_output.vout as usize),
+            (None, None) => None,
+        };
+        let script_pubkey = &utxo.ok_or(FinalizeError::MissingUtxo { input: index })?.script_pubkey;
+
+        let finalizer = Finalizer { index, input };
+        let (script_sig, witness) = finalizer.finalize(script_pubkey)?;
+
+        let input = &mut self.inputs[index];
+        *input = Input {
+            non_witness_utxo: input.non_witness_utxo.take(),
+            witness_utxo: input.witness_utxo.take(),
+            final_script_sig: (!script_sig.is_empty()).then_some(script_sig),
+            final_script_witness: (!witness.is_empty()).then_some(witness),
+            proprietary: core::mem::take(&mut input.proprietary),
+            unknown: core::mem::take(&mut input.unknown),
+            ..Default::default()
+        };
+        Ok(())
+    }
+}
+
+/// Constructs the final `scriptSig` and witness of a single input.
+struct Finalizer<'a> {
+    index: usize,
+    input: &'a Input,
+}
+
+impl Finalizer<'_> {
+    fn finalize(&self, script_pubkey: &Script) -> Result<(ScriptBuf, Witness), FinalizeError> {
+        match script_pubkey.classify() {
+            ScriptType::P2pk(pubkey) => {
+                let sig = self.input.partial_sigs.get(&pubkey).ok_or(self.missing_signature())?;
+                Ok((Builder::new().push_slice(sig.serialize()).into_script(), Witness::new()))
+            }
+            ScriptType::P2pkh(hash) => {
+                let (pubkey, sig) = self
+                    .input
+                    .partial_sigs
+                    .iter()
+                    .find(|(pubkey, _)| pubkey.pubkey_hash() == hash)
+                    .ok_or(self.missing_signature())?;
+                let script_sig = Builder::new().push_slice(sig.serialize()).push_key(*pubkey);
+                Ok((script_sig.into_script(), Witness::new()))
+            }
+            ScriptType::Multisig(multisig) =>
+                Ok((self.multisig_script_sig(&multisig)?.into_script(), Witness::new())),
+            ScriptType::P2sh(hash) => {
+                let redeem_script = self
+                    .input
+                    .redeem_script
+                    .as_ref()
+                    .ok_or(FinalizeError::MissingRedeemScript { input: self.index })?;
+                if redeem_script.script_hash_unchecked() != hash {
+                    return Err(FinalizeError::ScriptMismatch { input: self.index });
+                }
+                let (builder, witness) = match redeem_script.classify() {
+                    ScriptType::P2wpkh(_) | ScriptType::P2wsh(_) =>
+                        (Builder::new(), self.finalize(redeem_script)?.1),
+                    ScriptType::Multisig(multisig) =>
+                        (self.multisig_script_sig(&multisig)?, Witness::new()),
+                    _ => return Err(self.unsupported()),
+                };
+                let redeem_script = <&PushBytes>::try_from(redeem_script.as_bytes())
+                    .map_err(|_| self.unsupported())?;
+                Ok((builder.push_slice(redeem_script).into_script(), witness))
+            }
+            ScriptType::P2wpkh(hash) => {
+                let (pubkey, sig) = self
+                    .input
+                    .partial_sigs
+                    .iter()
+                    .filter_map(|(pubkey, sig)| {
+                        CompressedPublicKey::try_from(*pubkey).ok().map(|pubkey| (pubkey, sig))
+                    })
+                    .find(|(pubkey, _)| pubkey.wpubkey_hash() == hash)
+                    .ok_or(self.missing_signature())?;
+                Ok((ScriptBuf::new(), Witness::p2wpkh(sig, &pubkey)))
+            }
+            ScriptType::P2wsh(hash) => {
+                let witness_script = self
+                    .input
+                    .witness_script
+                    .as_ref()
+                    .ok_or(FinalizeError::MissingWitnessScript { input: self.index })?;
+                if witness_script.wscript_hash_unchecked() != hash {
+                    return Err(FinalizeError::ScriptMismatch { input: self.index });
+                }
+                let multisig = match Multisig::from_script(witness_script) {
+                    Some(multisig) if !multisig.is_verify() => multisig,
+                    _ => return Err(self.unsupported()),
+                };
+                let mut witness = Witness::new();
+                // `OP_CHECKMULTISIG` pops one element more than it uses.
+                witness.push([]);
+                for sig in self.multisig_signatures(&multisig)? {
+                    witness.push(sig);
+                }
+                witness.push(witness_script.as_bytes());
+                Ok((ScriptBuf::new(), witness))
+            }
+            ScriptType::P2tr(_) => Ok((ScriptBuf::new(), self.taproot_witness()?)),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Returns a `scriptSig` builder with the pushes satisfying a bare or P2SH `multisig`.
+    fn multisig_script_sig(&self, multisig: &Multisig) -> Result<Builder, FinalizeError> {
+        // `OP_CHECKMULTISIG` pops one element more than it uses.
+        let builder = Builder::new().push_opcode(OP_0);
+        Ok(self.multisig_signatures(multisig)?.into_iter().fold(builder, Builder::push_slice))
+    }
+
+    /// Returns the serialized signatures satisfying `multisig` in script order.
+    fn multisig_signatures(
+        &self,
+        multisig: &Multisig,
+    ) -> Result<Vec<SerializedSignature>, FinalizeError> {
+        let sigs = multisig
+            .keys()
+            .filter_map(|pubkey| self.input.partial_sigs.get(&pubkey))
+            .map(|sig| sig.serialize())
+            .collect::<Vec<_>>();
+        if sigs.len() < multisig.required() {
+            return Err(FinalizeError::NotEnoughSignatures {
+                input: self.index,
+                present: sigs.len(),
+                required: multisig.required(),
+            });
+        }
+        Ok(sigs.into_iter().take(multisig.required()).collect())
+    }
+
+    fn taproot_witness(&self) -> Result<Witness, FinalizeError> {
+        if let Some(sig) = self.input.tap_key_sig {
+            return Ok(Witness::p2tr_key_spend(&sig));
+        }
+
+        let mut satisfied =
+            self.input.tap_scripts.iter().filter_map(|(control_block, (script, ver))| {
+                if *ver != LeafVersion::TapScript {
+                    return None;
+                }
+                let (keys, required) = tap_leaf_keys(script)?;
+                let leaf_hash = TapLeafHash::from_script(script, *ver);
+                let mut present = 0;
+                // Signatures are consumed from the top of the stack, i.e. in reverse order.
+                let sigs = keys
+                    .iter()
+                    .map(|key| match self.input.tap_script_sigs.get(&(*key, leaf_hash)) {
+                        Some(sig) if present < required => {
+                            present += 1;
+                            sig.to_vec()
+                        }
+                        _ => Vec::new(),
+                    })
+                    .collect::<Vec<_>>();
+                if present < required {
+                    return None;
+                }
+
+                let mut witness = Witness::new();
+                for sig in sigs.iter().rev() {
+                    witness.push(sig);
+                }
+                witness.push_p2tr_script_spend(script, control_block, None);
+                Some(witness)
+            });
+
+        match (satisfied.next(), satisfied.next()) {
+            (Some(witness), None) => Ok(witness),
+            (Some(_), Some(_)) => Err(FinalizeError::MultipleTapLeaves { input: self.index }),
+            (None, _) if self.input.tap_scripts.is_empty() => Err(self.missing_signature()),
+            (None, _) => Err(FinalizeError::NoSatisfiedTapLeaf { input: self.index }),
+        }
+    }
+
+    fn missing_signature(&self) -> FinalizeError {
+        FinalizeError::MissingSignature { input: self.index }
+    }
+
+    fn unsupported(&self) -> FinalizeError {
+        FinalizeError::UnsupportedScript { input: self.index }
+    }
+}
+
+/// Returns the keys of a single key or `multi_a` Taproot leaf script and the number of signatures
+/// it requires.
+fn tap_leaf_keys(script: &Script) -> Option<(Vec<XOnlyPublicKey>, usize)> {
+    let instructions = script.instructions_minimal().collect::<Result<Vec<_>, _>>().ok()?;
+    let key = |instruction: &Instruction| {
+        XOnlyPublicKey::from_byte_array(instruction.push_bytes()?.as_bytes().try_into().ok()?).ok()
+    };
+
+    match instructions[..] {
+        [pubkey, Instruction::Op(OP_CHECKSIG)] => Some((vec![key(&pubkey)?], 1)),
+        [pubkey, Instruction::Op(OP_CHECKSIG), ref rest @ .., required, Instruction::Op(OP_NUMEQUAL)] =>
+        {
+            let mut keys = vec![key(&pubkey)?];
+            for pair in rest.chunks(2) {
+                match *pair {
+                    [pubkey, Instruction::Op(OP_CHECKSIGADD)] => keys.push(key(&pubkey)?),
+                    _ => return None,
+                }
+            }
+            let required = usize::try_from(required.read_int()?).ok()?;
+            (1..=keys.len()).contains(&required).then_some((keys, required))
+        }
+        _ => None,
+    }
+}
+
+/// Error finalizing a PSBT input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FinalizeError {
+    /// Input index out of bounds.
+    IndexOutOfBounds(IndexOutOfBoundsError),
+    /// The input has no UTXO information.
+    MissingUtxo {
+        /// The index of the input.
+        input: usize,
+    },
+    /// The input spends a P2SH output but has no redeem script.
+    MissingRedeemScript {
+        /// The index of the input.
+        input: usize,
+    },
+    /// The input spends a P2WSH output but has no witness script.
+    MissingWitnessScript {
+        /// The index of the input.
+        input: usize,
+    },
+    /// The redeem or witness script does not hash to the spent output.
+    ScriptMismatch {
+        /// The index of the input.
+        input: usize,
+    },
+    /// There is no signature for the key of a single key input.
+    MissingSignature {
+        /// The index of the input.
+        input: usize,
+    },
+    /// A multisig script has fewer signatures than it requires.
+    NotEnoughSignatures {
+        /// The index of the input.
+        input: usize,
+        /// The number of signatures present.
+        present: usize,
+        /// The number of signatures required.
+        required: usize,
+    },
+    /// There is no Taproot key path signature and no script leaf has all its signatures.
+    NoSatisfiedTapLeaf {
+        /// The index of the input.
+        input: usize,
+    },
+    /// There is no Taproot key path signature and more than one script leaf has all its signatures.
+    MultipleTapLeaves {
+        /// The index of the input.
+        input: usize,
+    },
+    /// The script being spent is not one of the supported types.
+    UnsupportedScript {
+        /// The index of the input.
+        input: usize,
+    },
+}
+
+impl FinalizeError {
+    /// Returns the index of the input that could not be finalized.
+    pub fn input(&self) -> usize {
+        use FinalizeError::*;
+
+        match *self {
+            IndexOutOfBounds(IndexOutOfBoundsError::Inputs { index, .. })
+            | IndexOutOfBounds(IndexOutOfBoundsError::TxInput { index, .. }) => index,
+            MissingUtxo { input }
+            | MissingRedeemScript { input }
+            | MissingWitnessScript { input }
+            | ScriptMismatch { input }
+            | MissingSignature { input }
+            | NotEnoughSignatures { input, .. }
+            | NoSatisfiedTapLeaf { input }
+            | MultipleTapLeaves { input }
+            | UnsupportedScript { input } => input,
+        }
+    }
+}
+
+impl From<Infallible> for FinalizeError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl From<IndexOutOfBoundsError> for FinalizeError {
+    fn from(e: IndexOutOfBoundsError) -> Self { FinalizeError::IndexOutOfBounds(e) }
+}
+
+impl fmt::Display for FinalizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FinalizeError::*;
+
+        match *self {
+            IndexOutOfBounds(ref e) => write_err!(f, "index out of bounds"; e),
+            MissingUtxo { input } => write!(f, "input {}: missing UTXO", input),
+            MissingRedeemScript { input } => write!(f, "input {}: missing redeem script", input),
+            MissingWitnessScript { input } => write!(f, "input {}: missing witness script", input),
+            ScriptMismatch { input } => write!(
+                f,
+                "input {}: redeem or witness script does not match the spent output",
+                input
+            ),
+            MissingSignature { input } => write!(f, "input {}: missing signature", input),
+            NotEnoughSignatures { input, present, required } =>
+                write!(f, "input {}: {} of {} signatures present", input, present, required),
+            NoSatisfiedTapLeaf { input } => write!(
+                f,
+                "input {}: no Taproot key signature and no script leaf with all its signatures",
+                input
+            ),
+            MultipleTapLeaves { input } => write!(
+                f,
+                "input {}: more than one Taproot script leaf has all its signatures",
+                input
+            ),
+            UnsupportedScript { input } => write!(f, "input {}: unsupported script type", input),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FinalizeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use FinalizeError::*;
+
+        match *self {
+            IndexOutOfBounds(ref e) => Some(e),
+            MissingUtxo { .. }
+            | MissingRedeemScript { .. }
+            | MissingWitnessScript { .. }
+            | ScriptMismatch { .. }
+            | MissingSignature { .. }
+            | NotEnoughSignatures { .. }
+            | NoSatisfiedTapLeaf { .. }
+            | MultipleTapLeaves { .. }
+            | UnsupportedScript { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{Keypair, Message, Secp256k1, SecretKey};
+
+    use super::*;
+    use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
+    use crate::crypto::{ecdsa, taproot};
+    use crate::key::{PublicKey, TapTweak as _};
+    use crate::script::ScriptBufExt as _;
+    use crate::sighash::{EcdsaSighashType, TapSighashType};
+    use crate::taproot::TaprootBuilder;
+    use crate::transaction::{self, Transaction, TxIn, TxOut};
+    use crate::{absolute, Amount, OutPoint, Sequence};
+
+    fn secret_key(i: u8) -> SecretKey { SecretKey::from_byte_array(&[i; 32]).unwrap() }
+
+    fn public_key(i: u8) -> PublicKey {
+        PublicKey::new(secp256k1::PublicKey::from_secret_key(&Secp256k1::new(), &secret_key(i)))
+    }
+
+    fn x_only_key(i: u8) -> XOnlyPublicKey { public_key(i).inner.x_only_public_key().0 }
+
+    fn ecdsa_sig(i: u8) -> ecdsa::Signature {
+        let msg = Message::from_digest([0xab; 32]);
+        let signature = Secp256k1::new().sign_ecdsa(&msg, &secret_key(i));
+        ecdsa::Signature { signature, sighash_type: EcdsaSighashType::All }
+    }
+
+    fn schnorr_sig(i: u8) -> taproot::Signature {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, &secret_key(i));
+        let signature = secp.sign_schnorr_with_aux_rand(&[0xab; 32], &keypair, &[0; 32]);
+        taproot::Signature { signature, sighash_type: TapSighashType::Default }
+    }
+
+    /// Returns a PSBT with a single input spending `script_pubkey`.
+    fn psbt_spending(script_pubkey: ScriptBuf) -> Psbt {
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::COINBASE_PREVOUT,
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(90_000),
+                script_pubkey: ScriptBuf::new_op_return([]),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo =
+            Some(TxOut { value: Amount::from_sat_u32(100_000), script_pubkey });
+        psbt
+    }
+
+    /// Asserts that only the final fields and the UTXO remain.
+    #[track_caller]
+    fn assert_finalized(input: &Input) {
+        let remaining = Input {
+            witness_utxo: input.witness_utxo.clone(),
+            final_script_sig: input.final_script_sig.clone(),
+            final_script_witness: input.final_script_witness.clone(),
+            ..Default::default()
+        };
+        assert_eq!(*input, remaining);
+    }
+
+    #[test]
+    fn finalize_p2pkh() {
+        let pk = public_key(1);
+        let mut psbt = psbt_spending(ScriptBuf::new_p2pkh(pk.pubkey_hash()));
+        psbt.inputs[0].partial_sigs.insert(public_key(2), ecdsa_sig(2));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::MissingSignature { input: 0 }));
+
+        psbt.inputs[0].partial_sigs.insert(pk, ecdsa_sig(1));
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::All.into());
+        psbt.finalize().unwrap();
+
+        let expected =
+            Builder::new().push_slice(ecdsa_sig(1).serialize()).push_key(pk).into_script();
+        assert_eq!(psbt.inputs[0].final_script_sig, Some(expected));
+        assert_eq!(psbt.inputs[0].final_script_witness, None);
+        assert_finalized(&psbt.inputs[0]);
+    }
+
+    #[test]
+    fn finalize_p2wpkh_and_p2sh_p2wpkh() {
+        let pk = CompressedPublicKey::try_from(public_key(1)).unwrap();
+        let expected = Witness::p2wpkh(&ecdsa_sig(1), &pk);
+
+        let mut psbt = psbt_spending(ScriptBuf::new_p2wpkh(pk.wpubkey_hash()));
+        psbt.inputs[0].partial_sigs.insert(public_key(1), ecdsa_sig(1));
+        psbt.finalize().unwrap();
+        assert_eq!(psbt.inputs[0].final_script_sig, None);
+        assert_eq!(psbt.inputs[0].final_script_witness.as_ref(), Some(&expected));
+        assert_finalized(&psbt.inputs[0]);
+
+        let redeem_script = ScriptBuf::new_p2wpkh(pk.wpubkey_hash());
+        let mut psbt = psbt_spending(ScriptBuf::new_p2sh_p2wpkh(pk));
+        psbt.inputs[0].partial_sigs.insert(public_key(1), ecdsa_sig(1));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::MissingRedeemScript { input: 0 }));
+
+        psbt.inputs[0].redeem_script = Some(ScriptBuf::new_p2pk(public_key(1)));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::ScriptMismatch { input: 0 }));
+
+        psbt.inputs[0].redeem_script = Some(redeem_script.clone());
+        psbt.finalize_input(0).unwrap();
+        let script_sig = Builder::new()
+            .push_slice(<&PushBytes>::try_from(redeem_script.as_bytes()).unwrap())
+            .into_script();
+        assert_eq!(psbt.inputs[0].final_script_sig, Some(script_sig));
+        assert_eq!(psbt.inputs[0].final_script_witness, Some(expected));
+        assert_finalized(&psbt.inputs[0]);
+    }
+
+    #[test]
+    fn finalize_p2wsh_multisig() {
+        let keys = [public_key(1), public_key(2), public_key(3)];
+        let witness_script = ScriptBuf::new_multisig(2, &keys).unwrap();
+        let mut psbt = psbt_spending(witness_script.to_p2wsh().unwrap());
+        psbt.inputs[0].witness_script = Some(witness_script.clone());
+        psbt.inputs[0].partial_sigs.insert(keys[2], ecdsa_sig(3));
+
+        let err = psbt.finalize().unwrap_err();
+        assert_eq!(
+            err,
+            vec![FinalizeError::NotEnoughSignatures { input: 0, present: 1, required: 2 }]
+        );
+        assert_eq!(err[0].to_string(), "input 0: 1 of 2 signatures present");
+
+        psbt.inputs[0].partial_sigs.insert(keys[0], ecdsa_sig(1));
+        psbt.finalize().unwrap();
+
+        // The signatures are ordered like the keys in the script.
+        let mut expected = Witness::new();
+        expected.push([]);
+        expected.push(ecdsa_sig(1).serialize());
+        expected.push(ecdsa_sig(3).serialize());
+        expected.push(witness_script.as_bytes());
+        assert_eq!(psbt.inputs[0].final_script_witness, Some(expected));
+        assert_finalized(&psbt.inputs[0]);
+    }
+
+    #[test]
+    fn finalize_p2tr_key_spend() {
+        let secp = Secp256k1::new();
+        let (output_key, _) = x_only_key(1).tap_tweak(&secp, None);
+        let mut psbt = psbt_spending(ScriptBuf::new_p2tr_tweaked(output_key));
+        psbt.inputs[0].tap_internal_key = Some(x_only_key(1));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::MissingSignature { input: 0 }));
+
+        psbt.inputs[0].tap_key_sig = Some(schnorr_sig(1));
+        psbt.finalize().unwrap();
+        assert_eq!(
+            psbt.inputs[0].final_script_witness,
+            Some(Witness::p2tr_key_spend(&schnorr_sig(1)))
+        );
+        assert_finalized(&psbt.inputs[0]);
+    }
+
+    #[test]
+    fn finalize_p2tr_script_spend() {
+        let secp = Secp256k1::new();
+        let single =
+            Builder::new().push_x_only_key(x_only_key(2)).push_opcode(OP_CHECKSIG).into_script();
+        let multi_a = Builder::new()
+            .push_x_only_key(x_only_key(3))
+            .push_opcode(OP_CHECKSIG)
+            .push_x_only_key(x_only_key(4))
+            .push_opcode(OP_CHECKSIGADD)
+            .push_int_unchecked(2)
+            .push_opcode(OP_NUMEQUAL)
+            .into_script();
+        assert_eq!(tap_leaf_keys(&single), Some((vec![x_only_key(2)], 1)));
+        assert_eq!(tap_leaf_keys(&multi_a), Some((vec![x_only_key(3), x_only_key(4)], 2)));
+
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, single.clone())
+            .unwrap()
+            .add_leaf(1, multi_a.clone())
+            .unwrap()
+            .finalize(&secp, x_only_key(1))
+            .unwrap();
+        let mut psbt = psbt_spending(ScriptBuf::new_p2tr_tweaked(spend_info.output_key()));
+        for script in [&single, &multi_a] {
+            let leaf = (script.clone(), LeafVersion::TapScript);
+            let control_block = spend_info.control_block(&leaf).unwrap();
+            psbt.inputs[0].tap_scripts.insert(control_block, leaf);
+        }
+        let single_hash = TapLeafHash::from_script(&single, LeafVersion::TapScript);
+        let multi_a_hash = TapLeafHash::from_script(&multi_a, LeafVersion::TapScript);
+
+        psbt.inputs[0].tap_script_sigs.insert((x_only_key(3), multi_a_hash), schnorr_sig(3));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::NoSatisfiedTapLeaf { input: 0 }));
+
+        psbt.inputs[0].tap_script_sigs.insert((x_only_key(4), multi_a_hash), schnorr_sig(4));
+        psbt.inputs[0].tap_script_sigs.insert((x_only_key(2), single_hash), schnorr_sig(2));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::MultipleTapLeaves { input: 0 }));
+
+        psbt.inputs[0].tap_script_sigs.remove(&(x_only_key(2), single_hash));
+        psbt.finalize().unwrap();
+
+        let leaf = (multi_a.clone(), LeafVersion::TapScript);
+        let mut expected = Witness::new();
+        expected.push(schnorr_sig(4).to_vec());
+        expected.push(schnorr_sig(3).to_vec());
+        expected.push_p2tr_script_spend(&multi_a, &spend_info.control_block(&leaf).unwrap(), None);
+        assert_eq!(psbt.inputs[0].final_script_witness, Some(expected));
+        assert_finalized(&psbt.inputs[0]);
+    }
+
+    #[test]
+    fn finalize_errors() {
+        let mut psbt = psbt_spending(ScriptBuf::new_p2pk(public_key(1)));
+        psbt.inputs[0].witness_utxo = None;
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::MissingUtxo { input: 0 }));
+
+        let err = psbt.finalize_input(1).unwrap_err();
+        assert!(matches!(err, FinalizeError::IndexOutOfBounds(_)));
+        assert_eq!(err.input(), 1);
+
+        let mut psbt = psbt_spending(ScriptBuf::new_op_return([]));
+        assert_eq!(psbt.finalize_input(0), Err(FinalizeError::UnsupportedScript { input: 0 }));
+
+        // Inputs that are already final are left as is.
+        psbt.inputs[0].final_script_sig = Some(ScriptBuf::new());
+        psbt.finalize().unwrap();
+    }
+}
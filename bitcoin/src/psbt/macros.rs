@@ -30,6 +30,18 @@ macro_rules! impl_psbt_serialize {
     ($thing:ty) => {
         impl $crate::psbt::serialize::Serialize for $thing {
             fn serialize(&self) -> $crate::prelude::Vec<u8> { $crate::consensus::serialize(self) }
+
+            fn serialized_len(&self) -> usize {
+                $crate::consensus::Encodable::consensus_encode(self, &mut $crate::io::sink())
+                    .expect("sinks don't error")
+            }
+
+            fn serialize_to<W: $crate::io::Write + ?Sized>(
+                &self,
+                w: &mut W,
+            ) -> core::result::Result<usize, $crate::io::Error> {
+                $crate::consensus::Encodable::consensus_encode(self, w)
+            }
         }
     };
 }
@@ -136,27 +148,23 @@ macro_rules! psbt_insert_hash_pair {
 }
 
 #[rustfmt::skip]
-macro_rules! impl_psbt_get_pair {
-    ($rv:ident.push($slf:ident.$unkeyed_name:ident, $unkeyed_typeval:ident)) => {
+macro_rules! impl_psbt_encode_pair {
+    ($len:ident += $w:ident.push($slf:ident.$unkeyed_name:ident, $unkeyed_typeval:ident)) => {
         if let Some(ref $unkeyed_name) = $slf.$unkeyed_name {
-            $rv.push($crate::psbt::raw::Pair {
-                key: $crate::psbt::raw::Key {
-                    type_value: $unkeyed_typeval,
-                    key_data: vec![],
-                },
-                value: $crate::psbt::serialize::Serialize::serialize($unkeyed_name),
-            });
+            let key = $crate::psbt::raw::Key {
+                type_value: $unkeyed_typeval,
+                key_data: vec![],
+            };
+            $len += $crate::psbt::map::encode_pair($w, &key, $unkeyed_name)?;
         }
     };
-    ($rv:ident.push_map($slf:ident.$keyed_name:ident, $keyed_typeval:ident)) => {
+    ($len:ident += $w:ident.push_map($slf:ident.$keyed_name:ident, $keyed_typeval:ident)) => {
         for (key, val) in &$slf.$keyed_name {
-            $rv.push($crate::psbt::raw::Pair {
-                key: $crate::psbt::raw::Key {
-                    type_value: $keyed_typeval,
-                    key_data: $crate::psbt::serialize::Serialize::serialize(key),
-                },
-                value: $crate::psbt::serialize::Serialize::serialize(val),
-            });
+            let key = $crate::psbt::raw::Key {
+                type_value: $keyed_typeval,
+                key_data: $crate::psbt::serialize::Serialize::serialize(key),
+            };
+            $len += $crate::psbt::map::encode_pair($w, &key, val)?;
         }
     };
 }
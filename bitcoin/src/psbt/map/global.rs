@@ -1,72 +1,50 @@
 // SPDX-License-Identifier: CC0-1.0
 
 use internals::ToU64 as _;
-use io::{BufRead, Cursor, Read};
+use io::{BufRead, Cursor, Read, Write};
 
-use crate::bip32::{ChildNumber, DerivationPath, Fingerprint, Xpub};
+use crate::bip32::{ChildNumber, DerivationPath, Fingerprint, KeySource, Xpub};
 use crate::consensus::encode::MAX_VEC_SIZE;
-use crate::consensus::{encode, Decodable};
+use crate::consensus::{Decodable, Encodable};
 use crate::prelude::{btree_map, BTreeMap, Vec};
-use crate::psbt::map::Map;
-use crate::psbt::{raw, Error, Psbt};
+use crate::psbt::map::{encode_pair, Map};
+use crate::psbt::serialize::Serialize;
+use crate::psbt::{raw, v2, Error, Psbt};
 use crate::transaction::Transaction;
 
 /// Type: Unsigned Transaction PSBT_GLOBAL_UNSIGNED_TX = 0x00
-const PSBT_GLOBAL_UNSIGNED_TX: u64 = 0x00;
+pub(in crate::psbt) const PSBT_GLOBAL_UNSIGNED_TX: u64 = 0x00;
 /// Type: Extended Public Key PSBT_GLOBAL_XPUB = 0x01
-const PSBT_GLOBAL_XPUB: u64 = 0x01;
+pub(in crate::psbt) const PSBT_GLOBAL_XPUB: u64 = 0x01;
 /// Type: Version Number PSBT_GLOBAL_VERSION = 0xFB
-const PSBT_GLOBAL_VERSION: u64 = 0xFB;
+pub(in crate::psbt) const PSBT_GLOBAL_VERSION: u64 = 0xFB;
 /// Type: Proprietary Use Type PSBT_GLOBAL_PROPRIETARY = 0xFC
-const PSBT_GLOBAL_PROPRIETARY: u64 = 0xFC;
+pub(in crate::psbt) const PSBT_GLOBAL_PROPRIETARY: u64 = 0xFC;
 
 impl Map for Psbt {
-    fn get_pairs(&self) -> Vec<raw::Pair> {
-        let mut rv: Vec<raw::Pair> = Default::default();
-
-        rv.push(raw::Pair {
-            key: raw::Key { type_value: PSBT_GLOBAL_UNSIGNED_TX, key_data: vec![] },
-            value: {
-                // Manually serialized to ensure 0-input txs are serialized
-                // without witnesses.
-                let mut ret = Vec::new();
-                ret.extend(encode::serialize(&self.unsigned_tx.version));
-                ret.extend(encode::serialize(&self.unsigned_tx.input));
-                ret.extend(encode::serialize(&self.unsigned_tx.output));
-                ret.extend(encode::serialize(&self.unsigned_tx.lock_time));
-                ret
-            },
-        });
-
-        for (xpub, (fingerprint, derivation)) in &self.xpub {
-            rv.push(raw::Pair {
-                key: raw::Key { type_value: PSBT_GLOBAL_XPUB, key_data: xpub.encode().to_vec() },
-                value: {
-                    let mut ret = Vec::with_capacity(4 + derivation.len() * 4);
-                    ret.extend(fingerprint.as_bytes());
-                    derivation.into_iter().for_each(|n| ret.extend(&u32::from(*n).to_le_bytes()));
-                    ret
-                },
-            });
-        }
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        let key = raw::Key { type_value: PSBT_GLOBAL_UNSIGNED_TX, key_data: vec![] };
+        len += encode_pair(w, &key, &UnsignedTx(&self.unsigned_tx))?;
+
+        len += encode_xpubs(w, &self.xpub)?;
 
         // Serializing version only for non-default value; otherwise test vectors fail
         if self.version > 0 {
-            rv.push(raw::Pair {
-                key: raw::Key { type_value: PSBT_GLOBAL_VERSION, key_data: vec![] },
-                value: self.version.to_le_bytes().to_vec(),
-            });
+            let key = raw::Key { type_value: PSBT_GLOBAL_VERSION, key_data: vec![] };
+            len += encode_pair(w, &key, &self.version)?;
         }
 
         for (key, value) in self.proprietary.iter() {
-            rv.push(raw::Pair { key: key.to_key(), value: value.clone() });
+            len += encode_pair(w, &key.to_key(), value)?;
         }
 
         for (key, value) in self.unknown.iter() {
-            rv.push(raw::Pair { key: key.clone(), value: value.clone() });
+            len += encode_pair(w, key, value)?;
         }
 
-        rv
+        Ok(len)
     }
 }
 
@@ -112,40 +90,10 @@ pub(crate) fn decode_global<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Erro
                             }
                         }
                         PSBT_GLOBAL_XPUB => {
-                            if !pair.key.key_data.is_empty() {
-                                let xpub = Xpub::decode(&pair.key.key_data)
-                                    .map_err(|_| Error::XPubKey(
-                                        "can't deserialize ExtendedPublicKey from global XPUB key data"
-                                    ))?;
-
-                                if pair.value.is_empty() || pair.value.len() % 4 != 0 {
-                                    return Err(Error::XPubKey(
-                                        "incorrect length of global xpub derivation data",
-                                    ));
-                                }
-
-                                let child_count = pair.value.len() / 4 - 1;
-                                let mut decoder = Cursor::new(pair.value);
-                                let mut fingerprint = [0u8; 4];
-                                decoder.read_exact(&mut fingerprint[..]).map_err(|_| {
-                                    Error::XPubKey("can't read global xpub fingerprint")
-                                })?;
-                                let mut path = Vec::<ChildNumber>::with_capacity(child_count);
-                                while let Ok(index) = u32::consensus_decode(&mut decoder) {
-                                    path.push(ChildNumber::from(index))
-                                }
-                                let derivation = DerivationPath::from(path);
-                                // Keys, according to BIP-174, must be unique
-                                if xpub_map
-                                    .insert(xpub, (Fingerprint::from(fingerprint), derivation))
-                                    .is_some()
-                                {
-                                    return Err(Error::XPubKey("repeated global xpub key"));
-                                }
-                            } else {
-                                return Err(Error::XPubKey(
-                                    "Xpub global key must contain serialized Xpub data",
-                                ));
+                            let (xpub, key_source) = decode_xpub(pair)?;
+                            // Keys, according to BIP-174, must be unique
+                            if xpub_map.insert(xpub, key_source).is_some() {
+                                return Err(Error::XPubKey("repeated global xpub key"));
                             }
                         }
                         PSBT_GLOBAL_VERSION => {
@@ -175,6 +123,14 @@ pub(crate) fn decode_global<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Erro
                                 return Err(Error::InvalidKey(pair.key));
                             }
                         }
+                        // Fields only allowed in version 2 PSBTs.
+                        v2::PSBT_GLOBAL_TX_VERSION
+                        | v2::PSBT_GLOBAL_FALLBACK_LOCKTIME
+                        | v2::PSBT_GLOBAL_INPUT_COUNT
+                        | v2::PSBT_GLOBAL_OUTPUT_COUNT
+                        | v2::PSBT_GLOBAL_TX_MODIFIABLE
+                            if pair.key.key_data.is_empty() =>
+                            return Err(Error::InvalidKey(pair.key)),
                         PSBT_GLOBAL_PROPRIETARY => match proprietary
                             .entry(raw::ProprietaryKey::try_from(pair.key.clone())?)
                         {
@@ -213,3 +169,68 @@ pub(crate) fn decode_global<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Erro
         }
     }
 }
+
+/// Encodes the key-value pairs of the global xpub map, returning the number of bytes written.
+pub(in crate::psbt) fn encode_xpubs<W: Write + ?Sized>(
+    w: &mut W,
+    xpub: &BTreeMap<Xpub, KeySource>,
+) -> Result<usize, io::Error> {
+    let mut len = 0;
+    for (xpub, key_source) in xpub {
+        let key = raw::Key { type_value: PSBT_GLOBAL_XPUB, key_data: xpub.encode().to_vec() };
+        len += encode_pair(w, &key, key_source)?;
+    }
+    Ok(len)
+}
+
+/// The unsigned transaction of a PSBT, serialized without witnesses.
+///
+/// Manually serialized to ensure 0-input txs are serialized without witnesses.
+struct UnsignedTx<'a>(&'a Transaction);
+
+impl Serialize for UnsignedTx<'_> {
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.serialize_to(&mut buf).expect("in-memory writers don't error");
+        buf
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.serialize_to(&mut io::sink()).expect("sinks don't error")
+    }
+
+    fn serialize_to<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = self.0.version.consensus_encode(w)?;
+        len += self.0.input.consensus_encode(w)?;
+        len += self.0.output.consensus_encode(w)?;
+        len += self.0.lock_time.consensus_encode(w)?;
+        Ok(len)
+    }
+}
+
+/// Decodes a global xpub key-value pair.
+pub(in crate::psbt) fn decode_xpub(pair: raw::Pair) -> Result<(Xpub, KeySource), Error> {
+    if pair.key.key_data.is_empty() {
+        return Err(Error::XPubKey("Xpub global key must contain serialized Xpub data"));
+    }
+
+    let xpub = Xpub::decode(&pair.key.key_data).map_err(|_| {
+        Error::XPubKey("can't deserialize ExtendedPublicKey from global XPUB key data")
+    })?;
+
+    if pair.value.is_empty() || pair.value.len() % 4 != 0 {
+        return Err(Error::XPubKey("incorrect length of global xpub derivation data"));
+    }
+
+    let child_count = pair.value.len() / 4 - 1;
+    let mut decoder = Cursor::new(pair.value);
+    let mut fingerprint = [0u8; 4];
+    decoder
+        .read_exact(&mut fingerprint[..])
+        .map_err(|_| Error::XPubKey("can't read global xpub fingerprint"))?;
+    let mut path = Vec::<ChildNumber>::with_capacity(child_count);
+    while let Ok(index) = u32::consensus_decode(&mut decoder) {
+        path.push(ChildNumber::from(index))
+    }
+    Ok((xpub, (Fingerprint::from(fingerprint), DerivationPath::from(path))))
+}
@@ -4,15 +4,17 @@
 use core::str::FromStr;
 
 use hashes::{hash160, ripemd160, sha256, sha256d};
+use io::Write;
 use secp256k1::XOnlyPublicKey;
 
 use crate::bip32::KeySource;
 use crate::crypto::key::PublicKey;
 use crate::crypto::{ecdsa, taproot};
+use crate::internal_macros::{impl_array_newtype, impl_array_newtype_stringify};
 use crate::prelude::{btree_map, BTreeMap, Borrow, Box, ToOwned, Vec};
-use crate::psbt::map::Map;
+use crate::psbt::map::{encode_pair, proprietary_get, proprietary_insert, proprietary_range, Map};
 use crate::psbt::serialize::Deserialize;
-use crate::psbt::{error, raw, Error};
+use crate::psbt::{error, raw, v2, Error};
 use crate::script::ScriptBuf;
 use crate::sighash::{
     EcdsaSighashType, InvalidSighashTypeError, NonStandardSighashTypeError, SighashTypeParseError,
@@ -60,6 +62,12 @@
 const PSBT_IN_TAP_INTERNAL_KEY: u64 = 0x17;
 /// Type: Taproot Merkle Root PSBT_IN_TAP_MERKLE_ROOT = 0x18
 const PSBT_IN_TAP_MERKLE_ROOT: u64 = 0x18;
+/// Type: MuSig2 Participant Public Keys PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS = 0x1a
+const PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS: u64 = 0x1a;
+/// Type: MuSig2 Public Nonce PSBT_IN_MUSIG2_PUB_NONCE = 0x1b
+const PSBT_IN_MUSIG2_PUB_NONCE: u64 = 0x1b;
+/// Type: MuSig2 Participant Partial Signature PSBT_IN_MUSIG2_PARTIAL_SIG = 0x1c
+const PSBT_IN_MUSIG2_PARTIAL_SIG: u64 = 0x1c;
 /// Type: Proprietary Use Type PSBT_IN_PROPRIETARY = 0xFC
 const PSBT_IN_PROPRIETARY: u64 = 0xFC;
 
@@ -123,6 +131,21 @@ pub struct Input {
     pub tap_internal_key: Option<XOnlyPublicKey>,
     /// Taproot Merkle root.
     pub tap_merkle_root: Option<TapNodeHash>,
+    /// Map of MuSig2 aggregate keys to the keys of the participants, as defined by BIP 373.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq"))]
+    pub musig2_participant_pubkeys: BTreeMap<secp256k1::PublicKey, Vec<secp256k1::PublicKey>>,
+    /// Map of `<participant key>|<aggregate key>|<leaf hash>` to the MuSig2 public nonce of the
+    /// participant. The leaf hash is omitted for key path spends.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq"))]
+    pub musig2_pub_nonces:
+        BTreeMap<(secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>), Musig2PubNonce>,
+    /// Map of `<participant key>|<aggregate key>|<leaf hash>` to the MuSig2 partial signature of
+    /// the participant. The leaf hash is omitted for key path spends.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq"))]
+    pub musig2_partial_sigs: BTreeMap<
+        (secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>),
+        Musig2PartialSig,
+    >,
     /// Proprietary key-value pairs for this input.
     #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
     pub proprietary: BTreeMap<raw::ProprietaryKey, Vec<u8>>,
@@ -241,6 +264,22 @@ pub fn from_u32(n: u32) -> PsbtSighashType { PsbtSighashType { inner: n } }
     pub fn to_u32(self) -> u32 { self.inner }
 }
 
+/// A MuSig2 public nonce, as created by the `NonceGen` algorithm of BIP 327.
+///
+/// The nonce is transported as is, the two points it consists of are not validated.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Musig2PubNonce([u8; 66]);
+impl_array_newtype!(Musig2PubNonce, u8, 66);
+impl_array_newtype_stringify!(Musig2PubNonce, 66);
+
+/// A MuSig2 partial signature, as created by the `Sign` algorithm of BIP 327.
+///
+/// The signature is transported as is, it is not validated.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Musig2PartialSig([u8; 32]);
+impl_array_newtype!(Musig2PartialSig, u8, 32);
+impl_array_newtype_stringify!(Musig2PartialSig, 32);
+
 impl Input {
     /// Obtains the [`EcdsaSighashType`] for this input if one is specified. If no sighash type is
     /// specified, returns [`EcdsaSighashType::All`].
@@ -266,7 +305,57 @@ pub fn taproot_hash_ty(&self) -> Result<TapSighashType, InvalidSighashTypeError>
             .unwrap_or(Ok(TapSighashType::Default))
     }
 
-    pub(super) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
+    /// Returns the value of the proprietary key with `prefix`, `subtype` and `key` in this input.
+    pub fn proprietary_get(&self, prefix: &[u8], subtype: u64, key: &[u8]) -> Option<&[u8]> {
+        proprietary_get(&self.proprietary, prefix, subtype, key)
+    }
+
+    /// Inserts a proprietary key-value pair in this input.
+    ///
+    /// Returns the previous value if the key was already present.
+    pub fn proprietary_insert(
+        &mut self,
+        prefix: &[u8],
+        subtype: u64,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        proprietary_insert(&mut self.proprietary, prefix, subtype, key, value)
+    }
+
+    /// Returns an iterator over the proprietary key-value pairs with `prefix` in this input.
+    ///
+    /// The pairs are ordered by subtype and then by key.
+    pub fn proprietary_range<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = (&'a raw::ProprietaryKey, &'a [u8])> + 'a {
+        proprietary_range(&self.proprietary, prefix)
+    }
+
+    /// Returns an iterator over the MuSig2 public nonces for `aggregate_key`.
+    ///
+    /// Yields the key of the participant that provided the nonce, the leaf hash for script path
+    /// spends and the nonce.
+    pub fn musig2_pub_nonces_for(
+        &self,
+        aggregate_key: secp256k1::PublicKey,
+    ) -> impl Iterator<Item = (secp256k1::PublicKey, Option<TapLeafHash>, &Musig2PubNonce)> {
+        musig2_entries_for(&self.musig2_pub_nonces, aggregate_key)
+    }
+
+    /// Returns an iterator over the MuSig2 partial signatures for `aggregate_key`.
+    ///
+    /// Yields the key of the participant that provided the signature, the leaf hash for script
+    /// path spends and the partial signature.
+    pub fn musig2_partial_sigs_for(
+        &self,
+        aggregate_key: secp256k1::PublicKey,
+    ) -> impl Iterator<Item = (secp256k1::PublicKey, Option<TapLeafHash>, &Musig2PartialSig)> {
+        musig2_entries_for(&self.musig2_partial_sigs, aggregate_key)
+    }
+
+    pub(in crate::psbt) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
         let raw::Pair { key: raw_key, value: raw_value } = pair;
 
         match raw_key.type_value {
@@ -365,6 +454,21 @@ pub(super) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
                     self.tap_merkle_root <= <raw_key: _>|< raw_value: TapNodeHash>
                 }
             }
+            PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS => {
+                impl_psbt_insert_pair! {
+                    self.musig2_participant_pubkeys <= <raw_key: secp256k1::PublicKey>|<raw_value: Vec<secp256k1::PublicKey>>
+                }
+            }
+            PSBT_IN_MUSIG2_PUB_NONCE => {
+                impl_psbt_insert_pair! {
+                    self.musig2_pub_nonces <= <raw_key: (secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>)>|<raw_value: Musig2PubNonce>
+                }
+            }
+            PSBT_IN_MUSIG2_PARTIAL_SIG => {
+                impl_psbt_insert_pair! {
+                    self.musig2_partial_sigs <= <raw_key: (secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>)>|<raw_value: Musig2PartialSig>
+                }
+            }
             PSBT_IN_PROPRIETARY => {
                 let key = raw::ProprietaryKey::try_from(raw_key.clone())?;
                 match self.proprietary.entry(key) {
@@ -374,6 +478,14 @@ pub(super) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
                     btree_map::Entry::Occupied(_) => return Err(Error::DuplicateKey(raw_key)),
                 }
             }
+            // Fields of version 2 PSBTs, `InputV2` decodes these before calling this method.
+            v2::PSBT_IN_PREVIOUS_TXID
+            | v2::PSBT_IN_OUTPUT_INDEX
+            | v2::PSBT_IN_SEQUENCE
+            | v2::PSBT_IN_REQUIRED_TIME_LOCKTIME
+            | v2::PSBT_IN_REQUIRED_HEIGHT_LOCKTIME
+                if raw_key.key_data.is_empty() =>
+                return Err(Error::InvalidKey(raw_key)),
             _ => match self.unknown.entry(raw_key) {
                 btree_map::Entry::Vacant(empty_key) => {
                     empty_key.insert(raw_value);
@@ -403,9 +515,13 @@ pub fn combine(&mut self, other: Self) {
         self.tap_script_sigs.extend(other.tap_script_sigs);
         self.tap_scripts.extend(other.tap_scripts);
         self.tap_key_origins.extend(other.tap_key_origins);
+        self.musig2_participant_pubkeys.extend(other.musig2_participant_pubkeys);
+        self.musig2_pub_nonces.extend(other.musig2_pub_nonces);
+        self.musig2_partial_sigs.extend(other.musig2_partial_sigs);
         self.proprietary.extend(other.proprietary);
         self.unknown.extend(other.unknown);
 
+        combine!(sighash_type, self, other);
         combine!(redeem_script, self, other);
         combine!(witness_script, self, other);
         combine!(final_script_sig, self, other);
@@ -417,98 +533,121 @@ pub fn combine(&mut self, other: Self) {
 }
 
 impl Map for Input {
-    fn get_pairs(&self) -> Vec<raw::Pair> {
-        let mut rv: Vec<raw::Pair> = Default::default();
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        impl_psbt_encode_pair! {
+            len += w.push(self.non_witness_utxo, PSBT_IN_NON_WITNESS_UTXO)
+        }
+
+        impl_psbt_encode_pair! {
+            len += w.push(self.witness_utxo, PSBT_IN_WITNESS_UTXO)
+        }
+
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.partial_sigs, PSBT_IN_PARTIAL_SIG)
+        }
 
-        impl_psbt_get_pair! {
-            rv.push(self.non_witness_utxo, PSBT_IN_NON_WITNESS_UTXO)
+        impl_psbt_encode_pair! {
+            len += w.push(self.sighash_type, PSBT_IN_SIGHASH_TYPE)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.witness_utxo, PSBT_IN_WITNESS_UTXO)
+        impl_psbt_encode_pair! {
+            len += w.push(self.redeem_script, PSBT_IN_REDEEM_SCRIPT)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.partial_sigs, PSBT_IN_PARTIAL_SIG)
+        impl_psbt_encode_pair! {
+            len += w.push(self.witness_script, PSBT_IN_WITNESS_SCRIPT)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.sighash_type, PSBT_IN_SIGHASH_TYPE)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.bip32_derivation, PSBT_IN_BIP32_DERIVATION)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.redeem_script, PSBT_IN_REDEEM_SCRIPT)
+        impl_psbt_encode_pair! {
+            len += w.push(self.final_script_sig, PSBT_IN_FINAL_SCRIPTSIG)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.witness_script, PSBT_IN_WITNESS_SCRIPT)
+        impl_psbt_encode_pair! {
+            len += w.push(self.final_script_witness, PSBT_IN_FINAL_SCRIPTWITNESS)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.bip32_derivation, PSBT_IN_BIP32_DERIVATION)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.ripemd160_preimages, PSBT_IN_RIPEMD160)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.final_script_sig, PSBT_IN_FINAL_SCRIPTSIG)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.sha256_preimages, PSBT_IN_SHA256)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.final_script_witness, PSBT_IN_FINAL_SCRIPTWITNESS)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.hash160_preimages, PSBT_IN_HASH160)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.ripemd160_preimages, PSBT_IN_RIPEMD160)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.hash256_preimages, PSBT_IN_HASH256)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.sha256_preimages, PSBT_IN_SHA256)
+        impl_psbt_encode_pair! {
+            len += w.push(self.tap_key_sig, PSBT_IN_TAP_KEY_SIG)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.hash160_preimages, PSBT_IN_HASH160)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.tap_script_sigs, PSBT_IN_TAP_SCRIPT_SIG)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.hash256_preimages, PSBT_IN_HASH256)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.tap_scripts, PSBT_IN_TAP_LEAF_SCRIPT)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.tap_key_sig, PSBT_IN_TAP_KEY_SIG)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.tap_key_origins, PSBT_IN_TAP_BIP32_DERIVATION)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.tap_script_sigs, PSBT_IN_TAP_SCRIPT_SIG)
+        impl_psbt_encode_pair! {
+            len += w.push(self.tap_internal_key, PSBT_IN_TAP_INTERNAL_KEY)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.tap_scripts, PSBT_IN_TAP_LEAF_SCRIPT)
+        impl_psbt_encode_pair! {
+            len += w.push(self.tap_merkle_root, PSBT_IN_TAP_MERKLE_ROOT)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.tap_key_origins, PSBT_IN_TAP_BIP32_DERIVATION)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.musig2_participant_pubkeys, PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.tap_internal_key, PSBT_IN_TAP_INTERNAL_KEY)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.musig2_pub_nonces, PSBT_IN_MUSIG2_PUB_NONCE)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.tap_merkle_root, PSBT_IN_TAP_MERKLE_ROOT)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.musig2_partial_sigs, PSBT_IN_MUSIG2_PARTIAL_SIG)
         }
         for (key, value) in self.proprietary.iter() {
-            rv.push(raw::Pair { key: key.to_key(), value: value.clone() });
+            len += encode_pair(w, &key.to_key(), value)?;
         }
 
         for (key, value) in self.unknown.iter() {
-            rv.push(raw::Pair { key: key.clone(), value: value.clone() });
+            len += encode_pair(w, key, value)?;
         }
 
-        rv
+        Ok(len)
     }
 }
 
 impl_psbtmap_ser_de_serialize!(Input);
 
+/// Returns the entries of a MuSig2 map keyed by `<participant key>|<aggregate key>|<leaf hash>`
+/// for `aggregate_key`.
+fn musig2_entries_for<V>(
+    map: &BTreeMap<(secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>), V>,
+    aggregate_key: secp256k1::PublicKey,
+) -> impl Iterator<Item = (secp256k1::PublicKey, Option<TapLeafHash>, &V)> {
+    map.iter()
+        .filter(move |((_, aggregate, _), _)| *aggregate == aggregate_key)
+        .map(|(&(participant, _, leaf_hash), value)| (participant, leaf_hash, value))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -562,6 +701,97 @@ fn psbt_sighash_type_notstd() {
         assert_eq!(back.taproot_hash_ty(), Err(InvalidSighashTypeError(nonstd)));
     }
 
+    fn musig2_keys() -> (secp256k1::PublicKey, secp256k1::PublicKey, secp256k1::PublicKey) {
+        let key = |s: &str| s.parse::<secp256k1::PublicKey>().unwrap();
+        (
+            key("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+            key("02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"),
+            key("02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"),
+        )
+    }
+
+    #[test]
+    fn musig2_fields_serialize_roundtrip() {
+        use crate::psbt::serialize::{Deserialize, Serialize};
+
+        let (alice, bob, aggregate) = musig2_keys();
+        let leaf_hash = TapLeafHash::from_byte_array([7; 32]);
+
+        let mut input = Input::default();
+        input.musig2_participant_pubkeys.insert(aggregate, vec![alice, bob]);
+        input.musig2_pub_nonces.insert((alice, aggregate, None), Musig2PubNonce([1; 66]));
+        input.musig2_pub_nonces.insert((bob, aggregate, Some(leaf_hash)), Musig2PubNonce([2; 66]));
+        input.musig2_partial_sigs.insert((alice, aggregate, None), Musig2PartialSig([3; 32]));
+
+        let pairs = input.get_pairs();
+        let pair = |type_value: u64| pairs.iter().filter(move |p| p.key.type_value == type_value);
+
+        let participants = pair(PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS).next().unwrap();
+        assert_eq!(participants.key.key_data, aggregate.serialize().to_vec());
+        assert_eq!(participants.value, [alice.serialize(), bob.serialize()].concat());
+
+        let key_sizes = pair(PSBT_IN_MUSIG2_PUB_NONCE).map(|p| p.key.key_data.len());
+        assert_eq!(key_sizes.collect::<Vec<_>>(), vec![66, 98]);
+        let sig = pair(PSBT_IN_MUSIG2_PARTIAL_SIG).next().unwrap();
+        assert_eq!(sig.key.key_data, [alice.serialize(), aggregate.serialize()].concat());
+        assert_eq!(sig.value, vec![3; 32]);
+
+        let decoded = Input::deserialize(&input.serialize()).unwrap();
+        assert_eq!(decoded, input);
+    }
+
+    #[test]
+    fn musig2_fields_invalid_length() {
+        let (alice, _, aggregate) = musig2_keys();
+        let nonce_key = [alice.serialize(), aggregate.serialize()].concat();
+
+        let insert = |type_value: u64, key_data: Vec<u8>, value: Vec<u8>| {
+            let pair = raw::Pair { key: raw::Key { type_value, key_data }, value };
+            Input::default().insert_pair(pair)
+        };
+
+        assert!(insert(PSBT_IN_MUSIG2_PUB_NONCE, nonce_key.clone(), vec![1; 66]).is_ok());
+        assert!(insert(PSBT_IN_MUSIG2_PUB_NONCE, nonce_key.clone(), vec![1; 65]).is_err());
+        assert!(insert(PSBT_IN_MUSIG2_PUB_NONCE, nonce_key[..65].to_vec(), vec![1; 66]).is_err());
+        assert!(insert(PSBT_IN_MUSIG2_PARTIAL_SIG, nonce_key.clone(), vec![1; 33]).is_err());
+        let mut bad_leaf = nonce_key;
+        bad_leaf.extend([0; 31]);
+        assert!(insert(PSBT_IN_MUSIG2_PARTIAL_SIG, bad_leaf, vec![1; 32]).is_err());
+        let participants = [alice.serialize().to_vec(), vec![2; 5]].concat();
+        assert!(insert(
+            PSBT_IN_MUSIG2_PARTICIPANT_PUBKEYS,
+            aggregate.serialize().to_vec(),
+            participants
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn musig2_entries_for_aggregate_key() {
+        let (alice, bob, aggregate) = musig2_keys();
+        let leaf_hash = TapLeafHash::from_byte_array([7; 32]);
+
+        let mut ours = Input::default();
+        ours.musig2_pub_nonces.insert((alice, aggregate, None), Musig2PubNonce([1; 66]));
+        ours.musig2_pub_nonces.insert((alice, bob, None), Musig2PubNonce([4; 66]));
+        let mut theirs = Input::default();
+        theirs.musig2_pub_nonces.insert((bob, aggregate, Some(leaf_hash)), Musig2PubNonce([2; 66]));
+        theirs.musig2_partial_sigs.insert((bob, aggregate, None), Musig2PartialSig([3; 32]));
+        ours.combine(theirs);
+
+        let nonces = ours.musig2_pub_nonces_for(aggregate).collect::<Vec<_>>();
+        assert_eq!(
+            nonces,
+            vec![
+                (alice, None, &Musig2PubNonce([1; 66])),
+                (bob, Some(leaf_hash), &Musig2PubNonce([2; 66])),
+            ]
+        );
+        let sigs = ours.musig2_partial_sigs_for(aggregate).collect::<Vec<_>>();
+        assert_eq!(sigs, vec![(bob, None, &Musig2PartialSig([3; 32]))]);
+        assert_eq!(ours.musig2_partial_sigs_for(bob).count(), 0);
+    }
+
     #[test]
     fn psbt_sighash_const_all() {
         assert_eq!(PsbtSighashType::ALL.to_u32(), 0x01);
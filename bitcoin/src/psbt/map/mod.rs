@@ -4,21 +4,43 @@
 mod input;
 mod output;
 
-use crate::prelude::Vec;
+use io::Write;
+
+use crate::consensus::encode::WriteExt as _;
+use crate::prelude::{BTreeMap, Vec};
 use crate::psbt::raw;
 use crate::psbt::serialize::Serialize;
 
 #[rustfmt::skip]                // Keep public re-exports separate.
 #[doc(inline)]
 pub use self::{
-    input::{Input, PsbtSighashType},
+    input::{Input, Musig2PartialSig, Musig2PubNonce, PsbtSighashType},
     output::Output,
 };
+pub(super) use self::global::{
+    decode_xpub, encode_xpubs, PSBT_GLOBAL_PROPRIETARY, PSBT_GLOBAL_UNSIGNED_TX,
+    PSBT_GLOBAL_VERSION, PSBT_GLOBAL_XPUB,
+};
 
 /// A trait that describes a PSBT key-value map.
 pub(super) trait Map {
+    /// Encodes all key-value pairs into a writer, returning the number of bytes written.
+    ///
+    /// Keys and values are written straight into `w`, see [`encode_pair`].
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error>;
+
     /// Attempt to get all key-value pairs.
-    fn get_pairs(&self) -> Vec<raw::Pair>;
+    #[cfg(test)]
+    fn get_pairs(&self) -> Vec<raw::Pair> {
+        let mut buf = Vec::new();
+        self.encode_pairs(&mut buf).expect("in-memory writers don't error");
+        let mut pairs = Vec::new();
+        let mut r = buf.as_slice();
+        while !r.is_empty() {
+            pairs.push(raw::Pair::decode(&mut r).expect("encoded pairs can be decoded"));
+        }
+        pairs
+    }
 
     /// Serialize Psbt binary map data according to BIP-174 specification.
     ///
@@ -29,10 +51,63 @@ pub(super) trait Map {
     /// actual keys. It can thus be used as a separator and allow for easier unserializer implementation.
     fn serialize_map(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        for pair in Map::get_pairs(self) {
-            buf.extend(&pair.serialize());
-        }
-        buf.push(0x00_u8);
+        self.encode_map(&mut buf).expect("in-memory writers don't error");
         buf
     }
+
+    /// Encodes the map into a writer, returning the number of bytes written.
+    ///
+    /// Same encoding as [`Map::serialize_map`] without buffering the map or its values.
+    fn encode_map<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let len = self.encode_pairs(w)?;
+        w.write_all(&[0x00_u8])?;
+        Ok(len + 1)
+    }
+}
+
+/// Encodes a key-value pair into a writer, returning the number of bytes written.
+///
+/// The value is serialized straight into `w` rather than into a [`raw::Pair`].
+pub(super) fn encode_pair<W, V>(w: &mut W, key: &raw::Key, value: &V) -> Result<usize, io::Error>
+where
+    W: Write + ?Sized,
+    V: Serialize + ?Sized,
+{
+    let mut len = key.encode(w)?;
+    len += w.emit_compact_size(value.serialized_len())?;
+    len += value.serialize_to(w)?;
+    Ok(len)
+}
+
+/// Returns the value of the proprietary key with `prefix`, `subtype` and `key` in `map`.
+pub(super) fn proprietary_get<'a>(
+    map: &'a BTreeMap<raw::ProprietaryKey, Vec<u8>>,
+    prefix: &[u8],
+    subtype: u64,
+    key: &[u8],
+) -> Option<&'a [u8]> {
+    map.get(&raw::ProprietaryKey::new(prefix, subtype, key)).map(Vec::as_slice)
+}
+
+/// Inserts a proprietary key-value pair in `map`, returning the previous value of the key.
+pub(super) fn proprietary_insert(
+    map: &mut BTreeMap<raw::ProprietaryKey, Vec<u8>>,
+    prefix: &[u8],
+    subtype: u64,
+    key: &[u8],
+    value: Vec<u8>,
+) -> Option<Vec<u8>> {
+    map.insert(raw::ProprietaryKey::new(prefix, subtype, key), value)
+}
+
+/// Returns an iterator over the pairs in a proprietary map that have `prefix`.
+pub(super) fn proprietary_range<'a>(
+    map: &'a BTreeMap<raw::ProprietaryKey, Vec<u8>>,
+    prefix: &'a [u8],
+) -> impl Iterator<Item = (&'a raw::ProprietaryKey, &'a [u8])> + 'a {
+    // Keys are ordered by prefix first, all keys with `prefix` follow the one with the smallest
+    // subtype and key.
+    map.range(raw::ProprietaryKey::new(prefix, 0, [])..)
+        .take_while(move |(key, _)| key.prefix == prefix)
+        .map(|(key, value)| (key, value.as_slice()))
 }
@@ -1,11 +1,12 @@
 // SPDX-License-Identifier: CC0-1.0
 
+use io::Write;
 use secp256k1::XOnlyPublicKey;
 
 use crate::bip32::KeySource;
 use crate::prelude::{btree_map, BTreeMap, Vec};
-use crate::psbt::map::Map;
-use crate::psbt::{raw, Error};
+use crate::psbt::map::{encode_pair, proprietary_get, proprietary_insert, proprietary_range, Map};
+use crate::psbt::{raw, v2, Error};
 use crate::script::ScriptBuf;
 use crate::taproot::{TapLeafHash, TapTree};
 
@@ -21,6 +22,8 @@
 const PSBT_OUT_TAP_TREE: u64 = 0x06;
 /// Type: Taproot Key BIP 32 Derivation Path PSBT_OUT_TAP_BIP32_DERIVATION = 0x07
 const PSBT_OUT_TAP_BIP32_DERIVATION: u64 = 0x07;
+/// Type: MuSig2 Participant Public Keys PSBT_OUT_MUSIG2_PARTICIPANT_PUBKEYS = 0x08
+const PSBT_OUT_MUSIG2_PARTICIPANT_PUBKEYS: u64 = 0x08;
 /// Type: Proprietary Use Type PSBT_IN_PROPRIETARY = 0xFC
 const PSBT_OUT_PROPRIETARY: u64 = 0xFC;
 
@@ -44,6 +47,9 @@ pub struct Output {
     /// Map of tap root x only keys to origin info and leaf hashes contained in it.
     #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq"))]
     pub tap_key_origins: BTreeMap<XOnlyPublicKey, (Vec<TapLeafHash>, KeySource)>,
+    /// Map of MuSig2 aggregate keys to the keys of the participants, as defined by BIP 373.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq"))]
+    pub musig2_participant_pubkeys: BTreeMap<secp256k1::PublicKey, Vec<secp256k1::PublicKey>>,
     /// Proprietary key-value pairs for this output.
     #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
     pub proprietary: BTreeMap<raw::ProprietaryKey, Vec<u8>>,
@@ -53,7 +59,35 @@ pub struct Output {
 }
 
 impl Output {
-    pub(super) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
+    /// Returns the value of the proprietary key with `prefix`, `subtype` and `key` in this output.
+    pub fn proprietary_get(&self, prefix: &[u8], subtype: u64, key: &[u8]) -> Option<&[u8]> {
+        proprietary_get(&self.proprietary, prefix, subtype, key)
+    }
+
+    /// Inserts a proprietary key-value pair in this output.
+    ///
+    /// Returns the previous value if the key was already present.
+    pub fn proprietary_insert(
+        &mut self,
+        prefix: &[u8],
+        subtype: u64,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        proprietary_insert(&mut self.proprietary, prefix, subtype, key, value)
+    }
+
+    /// Returns an iterator over the proprietary key-value pairs with `prefix` in this output.
+    ///
+    /// The pairs are ordered by subtype and then by key.
+    pub fn proprietary_range<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = (&'a raw::ProprietaryKey, &'a [u8])> + 'a {
+        proprietary_range(&self.proprietary, prefix)
+    }
+
+    pub(in crate::psbt) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
         let raw::Pair { key: raw_key, value: raw_value } = pair;
 
         match raw_key.type_value {
@@ -96,6 +130,14 @@ pub(super) fn insert_pair(&mut self, pair: raw::Pair) -> Result<(), Error> {
                     self.tap_key_origins <= <raw_key: XOnlyPublicKey>|< raw_value: (Vec<TapLeafHash>, KeySource)>
                 }
             }
+            PSBT_OUT_MUSIG2_PARTICIPANT_PUBKEYS => {
+                impl_psbt_insert_pair! {
+                    self.musig2_participant_pubkeys <= <raw_key: secp256k1::PublicKey>|<raw_value: Vec<secp256k1::PublicKey>>
+                }
+            }
+            // Fields of version 2 PSBTs, `OutputV2` decodes these before calling this method.
+            v2::PSBT_OUT_AMOUNT | v2::PSBT_OUT_SCRIPT if raw_key.key_data.is_empty() =>
+                return Err(Error::InvalidKey(raw_key)),
             _ => match self.unknown.entry(raw_key) {
                 btree_map::Entry::Vacant(empty_key) => {
                     empty_key.insert(raw_value);
@@ -113,6 +155,7 @@ pub fn combine(&mut self, other: Self) {
         self.proprietary.extend(other.proprietary);
         self.unknown.extend(other.unknown);
         self.tap_key_origins.extend(other.tap_key_origins);
+        self.musig2_participant_pubkeys.extend(other.musig2_participant_pubkeys);
 
         combine!(redeem_script, self, other);
         combine!(witness_script, self, other);
@@ -122,42 +165,46 @@ pub fn combine(&mut self, other: Self) {
 }
 
 impl Map for Output {
-    fn get_pairs(&self) -> Vec<raw::Pair> {
-        let mut rv: Vec<raw::Pair> = Default::default();
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        impl_psbt_encode_pair! {
+            len += w.push(self.redeem_script, PSBT_OUT_REDEEM_SCRIPT)
+        }
 
-        impl_psbt_get_pair! {
-            rv.push(self.redeem_script, PSBT_OUT_REDEEM_SCRIPT)
+        impl_psbt_encode_pair! {
+            len += w.push(self.witness_script, PSBT_OUT_WITNESS_SCRIPT)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.witness_script, PSBT_OUT_WITNESS_SCRIPT)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.bip32_derivation, PSBT_OUT_BIP32_DERIVATION)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.bip32_derivation, PSBT_OUT_BIP32_DERIVATION)
+        impl_psbt_encode_pair! {
+            len += w.push(self.tap_internal_key, PSBT_OUT_TAP_INTERNAL_KEY)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.tap_internal_key, PSBT_OUT_TAP_INTERNAL_KEY)
+        impl_psbt_encode_pair! {
+            len += w.push(self.tap_tree, PSBT_OUT_TAP_TREE)
         }
 
-        impl_psbt_get_pair! {
-            rv.push(self.tap_tree, PSBT_OUT_TAP_TREE)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.tap_key_origins, PSBT_OUT_TAP_BIP32_DERIVATION)
         }
 
-        impl_psbt_get_pair! {
-            rv.push_map(self.tap_key_origins, PSBT_OUT_TAP_BIP32_DERIVATION)
+        impl_psbt_encode_pair! {
+            len += w.push_map(self.musig2_participant_pubkeys, PSBT_OUT_MUSIG2_PARTICIPANT_PUBKEYS)
         }
 
         for (key, value) in self.proprietary.iter() {
-            rv.push(raw::Pair { key: key.to_key(), value: value.clone() });
+            len += encode_pair(w, &key.to_key(), value)?;
         }
 
         for (key, value) in self.unknown.iter() {
-            rv.push(raw::Pair { key: key.clone(), value: value.clone() });
+            len += encode_pair(w, key, value)?;
         }
 
-        rv
+        Ok(len)
     }
 }
 
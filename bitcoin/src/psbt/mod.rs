@@ -5,13 +5,20 @@
 //! Implementation of BIP174 Partially Signed Bitcoin Transaction Format as
 //! defined at <https://github.com/bitcoin/bips/blob/master/bip-0174.mediawiki>
 //! except we define PSBTs containing non-standard sighash types as invalid.
+//!
+//! Version 2 PSBTs as defined in BIP370 are represented by [`PsbtV2`].
 
 #[macro_use]
 mod macros;
+mod combine;
+mod derivation;
 mod error;
+mod finalize;
 mod map;
 pub mod raw;
 pub mod serialize;
+mod v2;
+mod verify;
 
 use core::convert::Infallible;
 use core::{cmp, fmt};
@@ -25,22 +32,40 @@
 use crate::crypto::key::{PrivateKey, PublicKey};
 use crate::crypto::{ecdsa, taproot};
 use crate::key::{TapTweak, XOnlyPublicKey};
+use crate::locktime::absolute;
+use crate::opcodes::OP_0;
 use crate::prelude::{btree_map, BTreeMap, BTreeSet, Borrow, Box, Vec};
-use crate::script::ScriptExt as _;
-use crate::sighash::{self, EcdsaSighashType, Prevouts, SighashCache};
-use crate::transaction::{self, Transaction, TransactionExt as _, TxOut};
-use crate::{Amount, FeeRate, TapLeafHash, TapSighashType};
+use crate::script::multisig::Multisig;
+use crate::script::script_type::ScriptType;
+use crate::script::{self, PushBytes, PushBytesBuf, Script, ScriptBuf, ScriptExt as _};
+use crate::sighash::{
+    self, EcdsaSighashType, LegacySighash, Prevouts, SegwitV0Sighash, SighashCache, TapSighash,
+};
+use crate::transaction::{
+    self, InputWeightPrediction, OutPoint, Transaction, TransactionExt as _, TxOut,
+};
+use crate::{Amount, FeeRate, TapLeafHash, TapSighashType, Txid, Weight};
 
 #[rustfmt::skip]                // Keep public re-exports separate.
 #[doc(inline)]
 pub use self::{
-    map::{Input, Output, PsbtSighashType},
+    map::{Input, Musig2PartialSig, Musig2PubNonce, Output, PsbtSighashType},
+    combine::{CombineError, FieldConflict},
+    derivation::{DerivationIssue, MapLocation, OriginKey},
     error::Error,
+    finalize::FinalizeError,
+    verify::SigVerificationError,
+    v2::{IncompatibleLockTimeError, InputV2, OutputV2, PsbtV2},
 };
 
+/// The maximum length of a DER-encoded ECDSA signature with the sighash type byte.
+const MAX_ECDSA_SIG_LEN: usize = 72;
+
 /// A Partially Signed Transaction.
+///
+/// With the `serde` feature the PSBT is serialized as its base64 string, the same as `Display`, in
+/// human-readable formats and as the raw BIP-174 serialization otherwise.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Psbt {
     /// The unsigned transaction, scriptSigs and witnesses for each input must be empty.
     pub unsigned_tx: Transaction,
@@ -50,10 +75,8 @@ pub struct Psbt {
     /// derivation path as defined by BIP 32.
     pub xpub: BTreeMap<Xpub, KeySource>,
     /// Global proprietary key-value pairs.
-    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
     pub proprietary: BTreeMap<raw::ProprietaryKey, Vec<u8>>,
     /// Unknown global key-value pairs.
-    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
     pub unknown: BTreeMap<raw::Key, Vec<u8>>,
 
     /// The corresponding key-value map for each input in the unsigned transaction.
@@ -77,16 +100,65 @@ impl Psbt {
     /// The function panics if the length of transaction inputs is not equal to the length of PSBT inputs.
     pub fn iter_funding_utxos(&self) -> impl Iterator<Item = Result<&TxOut, Error>> {
         assert_eq!(self.inputs.len(), self.unsigned_tx.input.len());
-        self.unsigned_tx.input.iter().zip(&self.inputs).map(|(tx_input, psbt_input)| {
-            match (&psbt_input.witness_utxo, &psbt_input.non_witness_utxo) {
-                (Some(witness_utxo), _) => Ok(witness_utxo),
-                (None, Some(non_witness_utxo)) => {
-                    let vout = tx_input.previous_output.vout as usize;
-                    non_witness_utxo.output.get(vout).ok_or(Error::PsbtUtxoOutOfbounds)
-                }
-                (None, None) => Err(Error::MissingUtxo),
-            }
-        })
+        (0..self.inputs.len()).map(|index| self.funding_utxo(index).map_err(Error::from))
+    }
+
+    /// Returns the funding UTXO of the input at `index`, see [`Psbt::iter_funding_utxos`].
+    ///
+    /// If both the witness and non-witness UTXO are present they must describe the same output.
+    fn funding_utxo(&self, index: usize) -> Result<&TxOut, EstimateError> {
+        let psbt_input = &self.inputs[index];
+        let vout = self.unsigned_tx.input[index].previous_output.vout as usize;
+        let non_witness_utxo = psbt_input
+            .non_witness_utxo
+            .as_ref()
+            .map(|tx| tx.output.get(vout).ok_or(EstimateError::UtxoOutOfBounds(index)))
+            .transpose()?;
+
+        match (&psbt_input.witness_utxo, non_witness_utxo) {
+            (Some(witness_utxo), Some(utxo)) if witness_utxo != utxo =>
+                Err(EstimateError::InconsistentUtxo(index)),
+            (Some(witness_utxo), _) => Ok(witness_utxo),
+            (None, Some(utxo)) => Ok(utxo),
+            (None, None) => Err(EstimateError::MissingUtxo(index)),
+        }
+    }
+
+    /// Checks that the UTXO information of every input is consistent with the unsigned transaction.
+    ///
+    /// For each input this verifies that the `non_witness_utxo`, if present, is the transaction
+    /// spent by the input and has an output at the spent index, and that the `witness_utxo`, if
+    /// also present, is exactly that output. A signer relying on a `witness_utxo` that disagrees
+    /// with the previous transaction could be tricked into paying an excessive fee.
+    ///
+    /// This is called by [`Psbt::sign`].
+    pub fn validate_utxos(&self) -> Result<(), UtxoConsistencyError> {
+        (0..self.inputs.len().min(self.unsigned_tx.input.len()))
+            .try_for_each(|index| self.validate_input_utxo(index))
+    }
+
+    /// Checks the UTXO information of the input at `index`, see [`Psbt::validate_utxos`].
+    fn validate_input_utxo(&self, index: usize) -> Result<(), UtxoConsistencyError> {
+        let psbt_input = &self.inputs[index];
+        let outpoint = self.unsigned_tx.input[index].previous_output;
+        let prev_tx = match psbt_input.non_witness_utxo {
+            Some(ref tx) => tx,
+            None => return Ok(()),
+        };
+
+        let txid = prev_tx.compute_txid();
+        if txid != outpoint.txid {
+            return Err(UtxoConsistencyError::TxidMismatch { input: index, outpoint, txid });
+        }
+        let utxo = prev_tx
+            .output
+            .get(outpoint.vout as usize)
+            .ok_or(UtxoConsistencyError::VoutOutOfRange { input: index, outpoint })?;
+        match psbt_input.witness_utxo {
+            Some(ref witness_utxo) if witness_utxo != utxo =>
+                Err(UtxoConsistencyError::WitnessUtxoMismatch { input: index, outpoint }),
+            _ => Ok(()),
+        }
     }
 
     /// Checks that unsigned transaction does not have scriptSig's or witness data.
@@ -124,6 +196,84 @@ pub fn from_unsigned_tx(tx: Transaction) -> Result<Self, Error> {
         Ok(psbt)
     }
 
+    /// Constructs a new PSBT from an unsigned transaction, populating the inputs with the UTXOs
+    /// returned by `lookup`.
+    ///
+    /// `lookup` is called once for the outpoint spent by each input, in input order. As BIP 174
+    /// recommends, the full previous transaction is required for non-SegWit inputs and is stored
+    /// as their `non_witness_utxo`. SegWit inputs get a `witness_utxo` and also keep the previous
+    /// transaction if one was provided. An input is considered SegWit if the output it spends is a
+    /// witness program, or a P2SH output whose redeem script is one.
+    ///
+    /// # Errors
+    ///
+    /// If the transaction is not unsigned, if `lookup` returns [`None`] for any outpoint (all
+    /// missing outpoints are reported together) or if the returned information is inconsistent
+    /// with the outpoint.
+    pub fn from_unsigned_tx_with_utxos<F>(
+        tx: Transaction,
+        mut lookup: F,
+    ) -> Result<Self, UtxoLookupError>
+    where
+        F: FnMut(&OutPoint) -> Option<UtxoInfo>,
+    {
+        let mut psbt = Psbt::from_unsigned_tx(tx).map_err(UtxoLookupError::UnsignedTx)?;
+
+        let utxos = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|txin| lookup(&txin.previous_output))
+            .collect::<Vec<_>>();
+        let missing = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .zip(&utxos)
+            .filter(|(_, utxo)| utxo.is_none())
+            .map(|(txin, _)| txin.previous_output)
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Err(UtxoLookupError::MissingUtxos(missing));
+        }
+
+        for ((txin, input), utxo) in
+            psbt.unsigned_tx.input.iter().zip(&mut psbt.inputs).zip(utxos.into_iter().flatten())
+        {
+            let outpoint = txin.previous_output;
+            let txout = match utxo.prev_out {
+                PrevOut::Transaction(ref tx) => {
+                    let txid = tx.compute_txid();
+                    if txid != outpoint.txid {
+                        return Err(UtxoLookupError::TxidMismatch { outpoint, txid });
+                    }
+                    tx.output
+                        .get(outpoint.vout as usize)
+                        .ok_or(UtxoLookupError::MissingOutput(outpoint))?
+                }
+                PrevOut::TxOut(ref txout) => txout,
+            };
+
+            let script_pubkey = &txout.script_pubkey;
+            let is_segwit = script_pubkey.is_witness_program()
+                || (script_pubkey.is_p2sh()
+                    && utxo.redeem_script.as_ref().map_or(false, |s| s.is_witness_program()));
+            if is_segwit {
+                input.witness_utxo = Some(txout.clone());
+            }
+            match utxo.prev_out {
+                PrevOut::Transaction(tx) => input.non_witness_utxo = Some(tx),
+                PrevOut::TxOut(_) if !is_segwit =>
+                    return Err(UtxoLookupError::MissingPrevTx(outpoint)),
+                PrevOut::TxOut(_) => {}
+            }
+            input.redeem_script = utxo.redeem_script;
+            input.witness_script = utxo.witness_script;
+            input.bip32_derivation = utxo.bip32_derivation;
+        }
+        Ok(psbt)
+    }
+
     /// The default `max_fee_rate` value used for extracting transactions with [`extract_tx`]
     ///
     /// As of 2023, even the biggest overpayers during the highest fee markets only paid around
@@ -147,6 +297,8 @@ pub fn extract_tx(self) -> Result<Transaction, ExtractTxError> {
     /// [`ExtractTxError`] variants will contain either the [`Psbt`] itself or the [`Transaction`]
     /// that was extracted. These can be extracted from the Errors in order to recover.
     /// See the error documentation for info on the variants. In general, it covers large fees.
+    /// Inputs that are not finalized are reported with [`ExtractTxError::NotReady`], see
+    /// [`Psbt::extraction_readiness`].
     #[allow(clippy::result_large_err)] // The PSBT returned in `SendingToomuch` is large.
     pub fn extract_tx_fee_rate_limit(self) -> Result<Transaction, ExtractTxError> {
         self.internal_extract_tx_with_fee_rate_limit(Self::DEFAULT_MAX_FEE_RATE)
@@ -174,6 +326,32 @@ pub fn extract_tx_with_fee_rate_limit(
     /// [`extract_tx_fee_rate_limit`]: Psbt::extract_tx_fee_rate_limit
     pub fn extract_tx_unchecked_fee_rate(self) -> Transaction { self.internal_extract_tx() }
 
+    /// Returns the [`InputReadiness`] of each input, in input order.
+    ///
+    /// A transaction can only be extracted once all of its inputs are
+    /// [`InputReadiness::Finalized`], the inputs that are not show what is still missing.
+    pub fn extraction_readiness(&self) -> Vec<InputReadiness> {
+        (0..self.inputs.len()).map(|index| self.input_readiness(index)).collect()
+    }
+
+    fn input_readiness(&self, index: usize) -> InputReadiness {
+        let input = &self.inputs[index];
+        let utxo = match self.funding_utxo(index) {
+            Err(EstimateError::MissingUtxo(_)) | Err(EstimateError::UtxoOutOfBounds(_)) =>
+                return InputReadiness::MissingUtxoInfo,
+            utxo => utxo.ok(),
+        };
+        if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+            return InputReadiness::Finalized;
+        }
+
+        let has_partial_sigs = input.partial_sigs.len()
+            + usize::from(input.tap_key_sig.is_some())
+            + input.tap_script_sigs.len();
+        let needs = utxo.and_then(|utxo| signatures_needed(input, &utxo.script_pubkey));
+        InputReadiness::MissingFinalScript { has_partial_sigs, needs }
+    }
+
     #[inline]
     fn internal_extract_tx(self) -> Transaction {
         let mut tx: Transaction = self.unsigned_tx;
@@ -192,14 +370,23 @@ fn internal_extract_tx_with_fee_rate_limit(
         self,
         max_fee_rate: FeeRate,
     ) -> Result<Transaction, ExtractTxError> {
+        let inputs = self.extraction_readiness();
+        if inputs.iter().any(|readiness| *readiness != InputReadiness::Finalized) {
+            return Err(ExtractTxError::NotReady { inputs, psbt: self });
+        }
+
         let fee = match self.fee() {
             Ok(fee) => fee,
-            Err(Error::MissingUtxo) =>
+            Err(Error::MissingUtxo) | Err(Error::PsbtUtxoOutOfbounds) =>
                 return Err(ExtractTxError::MissingInputValue { tx: self.internal_extract_tx() }),
+            Err(Error::InconsistentUtxo(input)) =>
+                return Err(ExtractTxError::InconsistentUtxo { input, psbt: self }),
             Err(Error::NegativeFee) => return Err(ExtractTxError::SendingTooMuch { psbt: self }),
             Err(Error::FeeOverflow) =>
                 return Err(ExtractTxError::AbsurdFeeRate {
+                    fee: Amount::MAX,
                     fee_rate: FeeRate::MAX,
+                    max_fee_rate,
                     tx: self.internal_extract_tx(),
                 }),
             _ => unreachable!(),
@@ -213,7 +400,7 @@ fn internal_extract_tx_with_fee_rate_limit(
             FeeRate::from_sat_per_kwu(fee.to_sat().saturating_mul(1000) / tx.weight().to_wu());
         // Prefer to return an AbsurdFeeRate error when both trigger.
         if fee_rate > max_fee_rate {
-            return Err(ExtractTxError::AbsurdFeeRate { fee_rate, tx });
+            return Err(ExtractTxError::AbsurdFeeRate { fee, fee_rate, max_fee_rate, tx });
         }
 
         Ok(tx)
@@ -236,8 +423,92 @@ pub fn combine(&mut self, other: Self) -> Result<(), Error> {
         // Keeping the highest version
         self.version = cmp::max(self.version, other.version);
 
-        // Merging xpubs
-        for (xpub, (fingerprint1, derivation1)) in other.xpub {
+        self.merge_xpubs(other.xpub)
+            .map_err(|xpub| Error::CombineInconsistentKeySources(Box::new(xpub)))?;
+
+        self.proprietary.extend(other.proprietary);
+        self.unknown.extend(other.unknown);
+
+        for (self_input, other_input) in self.inputs.iter_mut().zip(other.inputs.into_iter()) {
+            self_input.combine(other_input);
+        }
+
+        for (self_output, other_output) in self.outputs.iter_mut().zip(other.outputs.into_iter()) {
+            self_output.combine(other_output);
+        }
+
+        Ok(())
+    }
+
+    /// Joins this [`Psbt`] with `other`, a PSBT for a different transaction, as described by
+    /// BIP 174 for the Joiner role.
+    ///
+    /// The unsigned transaction of the result spends the inputs and pays the outputs of both
+    /// transactions, those of `self` first. The input and output maps are moved along with their
+    /// inputs and outputs and the global maps are merged.
+    ///
+    /// The transactions must have the same version. The lock time of the result is the greatest
+    /// of the two lock times, a lock time of zero is compatible with any other but block heights
+    /// and timestamps can not be mixed. The PSBT version of the result is the greatest of the two.
+    ///
+    /// Joining changes the transaction, so any signature made for one of the PSBTs would be
+    /// invalid for the result. PSBTs with signed or finalized inputs are rejected rather than
+    /// having their signatures silently dropped.
+    pub fn join(mut self, other: Psbt) -> Result<Psbt, JoinError> {
+        for (txin, input) in self
+            .unsigned_tx
+            .input
+            .iter()
+            .zip(&self.inputs)
+            .chain(other.unsigned_tx.input.iter().zip(&other.inputs))
+        {
+            if !input.partial_sigs.is_empty()
+                || input.tap_key_sig.is_some()
+                || !input.tap_script_sigs.is_empty()
+                || input.final_script_sig.is_some()
+                || input.final_script_witness.is_some()
+            {
+                return Err(JoinError::SignedInput(txin.previous_output));
+            }
+        }
+
+        for txin in &other.unsigned_tx.input {
+            if self.unsigned_tx.input.iter().any(|i| i.previous_output == txin.previous_output) {
+                return Err(JoinError::DuplicateInput(txin.previous_output));
+            }
+        }
+
+        let (ours, theirs) = (&self.unsigned_tx, &other.unsigned_tx);
+        if ours.version != theirs.version {
+            return Err(JoinError::VersionMismatch { ours: ours.version, theirs: theirs.version });
+        }
+        let lock_time = match (ours.lock_time, theirs.lock_time) {
+            (absolute::LockTime::ZERO, lock_time) | (lock_time, absolute::LockTime::ZERO) =>
+                lock_time,
+            (a, b) if a.is_same_unit(b) => cmp::max_by_key(a, b, |l| l.to_consensus_u32()),
+            (a, b) => return Err(JoinError::LockTimeUnitMismatch { ours: a, theirs: b }),
+        };
+
+        self.merge_xpubs(other.xpub)
+            .map_err(|xpub| JoinError::InconsistentKeySources(Box::new(xpub)))?;
+        self.proprietary.extend(other.proprietary);
+        self.unknown.extend(other.unknown);
+        self.version = cmp::max(self.version, other.version);
+
+        self.unsigned_tx.lock_time = lock_time;
+        self.unsigned_tx.input.extend(other.unsigned_tx.input);
+        self.unsigned_tx.output.extend(other.unsigned_tx.output);
+        self.inputs.extend(other.inputs);
+        self.outputs.extend(other.outputs);
+
+        Ok(self)
+    }
+
+    /// Merges `xpubs` into the global map of extended public keys.
+    ///
+    /// Returns the conflicting key if its key sources can not be reconciled.
+    fn merge_xpubs(&mut self, xpubs: BTreeMap<Xpub, KeySource>) -> Result<(), Xpub> {
+        for (xpub, (fingerprint1, derivation1)) in xpubs {
             match self.xpub.entry(xpub) {
                 btree_map::Entry::Vacant(entry) => {
                     entry.insert((fingerprint1, derivation1));
@@ -266,23 +537,39 @@ pub fn combine(&mut self, other: Self) -> Result<(), Error> {
                         entry.insert((fingerprint1, derivation1));
                         continue;
                     }
-                    return Err(Error::CombineInconsistentKeySources(Box::new(xpub)));
+                    return Err(xpub);
                 }
             }
         }
+        Ok(())
+    }
 
-        self.proprietary.extend(other.proprietary);
-        self.unknown.extend(other.unknown);
-
-        for (self_input, other_input) in self.inputs.iter_mut().zip(other.inputs.into_iter()) {
-            self_input.combine(other_input);
-        }
+    /// Returns the value of the proprietary key with `prefix`, `subtype` and `key` in the global map.
+    pub fn proprietary_get(&self, prefix: &[u8], subtype: u64, key: &[u8]) -> Option<&[u8]> {
+        map::proprietary_get(&self.proprietary, prefix, subtype, key)
+    }
 
-        for (self_output, other_output) in self.outputs.iter_mut().zip(other.outputs.into_iter()) {
-            self_output.combine(other_output);
-        }
+    /// Inserts a proprietary key-value pair in the global map.
+    ///
+    /// Returns the previous value if the key was already present.
+    pub fn proprietary_insert(
+        &mut self,
+        prefix: &[u8],
+        subtype: u64,
+        key: &[u8],
+        value: Vec<u8>,
+    ) -> Option<Vec<u8>> {
+        map::proprietary_insert(&mut self.proprietary, prefix, subtype, key, value)
+    }
 
-        Ok(())
+    /// Returns an iterator over the proprietary key-value pairs with `prefix` in the global map.
+    ///
+    /// The pairs are ordered by subtype and then by key.
+    pub fn proprietary_range<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> impl Iterator<Item = (&'a raw::ProprietaryKey, &'a [u8])> + 'a {
+        map::proprietary_range(&self.proprietary, prefix)
     }
 
     /// Attempts to create _all_ the required signatures for this PSBT using `k`.
@@ -296,11 +583,44 @@ pub fn combine(&mut self, other: Self) -> Result<(), Error> {
     ///
     /// If an error is returned some signatures may already have been added to the PSBT. Since
     /// `partial_sigs` is a [`BTreeMap`] it is safe to retry, previous sigs will be overwritten.
+    ///
+    /// Inputs whose UTXO information fails [`Psbt::validate_utxos`] are not signed and get a
+    /// [`SignError::InconsistentUtxo`] error.
     pub fn sign<C, K>(
         &mut self,
         k: &K,
         secp: &Secp256k1<C>,
     ) -> Result<SigningKeysMap, (SigningKeysMap, SigningErrors)>
+    where
+        C: Signing + Verification,
+        K: GetKey,
+    {
+        self.sign_inner(k, secp, true)
+    }
+
+    /// Attempts to create _all_ the required signatures for this PSBT using `k`, without
+    /// validating the UTXO information of the inputs first.
+    ///
+    /// Only use this if the inputs are known to be consistent by other means, otherwise prefer
+    /// [`Psbt::sign`].
+    pub fn sign_without_utxo_validation<C, K>(
+        &mut self,
+        k: &K,
+        secp: &Secp256k1<C>,
+    ) -> Result<SigningKeysMap, (SigningKeysMap, SigningErrors)>
+    where
+        C: Signing + Verification,
+        K: GetKey,
+    {
+        self.sign_inner(k, secp, false)
+    }
+
+    fn sign_inner<C, K>(
+        &mut self,
+        k: &K,
+        secp: &Secp256k1<C>,
+        validate_utxos: bool,
+    ) -> Result<SigningKeysMap, (SigningKeysMap, SigningErrors)>
     where
         C: Signing + Verification,
         K: GetKey,
@@ -312,6 +632,12 @@ pub fn sign<C, K>(
         let mut errors = BTreeMap::new();
 
         for i in 0..self.inputs.len() {
+            if validate_utxos && i < tx.input.len() {
+                if let Err(e) = self.validate_input_utxo(i) {
+                    errors.insert(i, SignError::InconsistentUtxo(e));
+                    continue;
+                }
+            }
             match self.signing_algorithm(i) {
                 Ok(SigningAlgorithm::Ecdsa) =>
                     match self.bip32_sign_ecdsa(k, i, &mut cache, secp) {
@@ -445,7 +771,8 @@ fn bip32_sign_schnorr<C, K, T>(
                 // Based on input.tap_internal_key.is_some() alone, it is not sufficient to determine whether it is a key path spend.
                 // According to BIP 371, we also need to consider the condition leaf_hashes.is_empty() for a more accurate determination.
                 if internal_key == xonly && leaf_hashes.is_empty() && input.tap_key_sig.is_none() {
-                    let (msg, sighash_type) = self.sighash_taproot(input_index, cache, None)?;
+                    let (msg, sighash_type) =
+                        self.sighash_taproot(input_index, cache, None, None)?;
                     let key_pair = Keypair::from_secret_key(secp, &sk.inner)
                         .tap_tweak(secp, input.tap_merkle_root)
                         .to_inner();
@@ -464,9 +791,16 @@ fn bip32_sign_schnorr<C, K, T>(
 
             // script path spend
             if let Some((leaf_hashes, _)) = input.tap_key_origins.get(&xonly) {
+                // Only sign leaves we have the script for, the finalizer can not use the others.
                 let leaf_hashes = leaf_hashes
                     .iter()
                     .filter(|lh| !input.tap_script_sigs.contains_key(&(xonly, **lh)))
+                    .filter(|lh| {
+                        input
+                            .tap_scripts
+                            .values()
+                            .any(|(script, ver)| TapLeafHash::from_script(script, *ver) == **lh)
+                    })
                     .cloned()
                     .collect::<Vec<_>>();
 
@@ -475,7 +809,7 @@ fn bip32_sign_schnorr<C, K, T>(
 
                     for lh in leaf_hashes {
                         let (msg, sighash_type) =
-                            self.sighash_taproot(input_index, cache, Some(lh))?;
+                            self.sighash_taproot(input_index, cache, Some(lh), None)?;
 
                         #[cfg(feature = "rand-std")]
                         let signature = secp.sign_schnorr(msg.as_ref(), &key_pair);
@@ -506,6 +840,31 @@ pub fn sighash_ecdsa<T: Borrow<Transaction>>(
         input_index: usize,
         cache: &mut SighashCache<T>,
     ) -> Result<(Message, EcdsaSighashType), SignError> {
+        self.sighash_ecdsa_with_type(input_index, cache, None)
+    }
+
+    /// Returns the sighash message to sign an ECDSA input with `hash_ty`.
+    ///
+    /// Uses the sighash type of the input as [`Psbt::sighash_ecdsa`] does if `hash_ty` is `None`.
+    fn sighash_ecdsa_with_type<T: Borrow<Transaction>>(
+        &self,
+        input_index: usize,
+        cache: &mut SighashCache<T>,
+        hash_ty: Option<EcdsaSighashType>,
+    ) -> Result<(Message, EcdsaSighashType), SignError> {
+        let (msg, hash_ty) = self.sighash_msg_ecdsa(input_index, cache, hash_ty)?;
+        Ok((msg.into(), hash_ty))
+    }
+
+    /// Returns the sighash of an ECDSA input along with the sighash type.
+    ///
+    /// Uses the sighash type of the input as [`Psbt::sighash_ecdsa`] does if `hash_ty` is `None`.
+    fn sighash_msg_ecdsa<T: Borrow<Transaction>>(
+        &self,
+        input_index: usize,
+        cache: &mut SighashCache<T>,
+        hash_ty: Option<EcdsaSighashType>,
+    ) -> Result<(PsbtSighashMsg, EcdsaSighashType), SignError> {
         use OutputType::*;
 
         if self.signing_algorithm(input_index)? != SigningAlgorithm::Ecdsa {
@@ -516,14 +875,18 @@ pub fn sighash_ecdsa<T: Borrow<Transaction>>(
         let utxo = self.spend_utxo(input_index)?;
         let spk = &utxo.script_pubkey; // scriptPubkey for input spend utxo.
 
-        let hash_ty = input.ecdsa_hash_ty().map_err(|_| SignError::InvalidSighashType)?; // Only support standard sighash types.
+        let hash_ty = match hash_ty {
+            Some(hash_ty) => hash_ty,
+            // Only support standard sighash types.
+            None => input.ecdsa_hash_ty().map_err(|_| SignError::InvalidSighashType)?,
+        };
 
         match self.output_type(input_index)? {
             Bare => {
                 let sighash = cache
                     .legacy_signature_hash(input_index, spk, hash_ty.to_u32())
                     .expect("input checked above");
-                Ok((Message::from(sighash), hash_ty))
+                Ok((PsbtSighashMsg::Legacy(sighash), hash_ty))
             }
             Sh => {
                 let script_code =
@@ -531,17 +894,17 @@ pub fn sighash_ecdsa<T: Borrow<Transaction>>(
                 let sighash = cache
                     .legacy_signature_hash(input_index, script_code, hash_ty.to_u32())
                     .expect("input checked above");
-                Ok((Message::from(sighash), hash_ty))
+                Ok((PsbtSighashMsg::Legacy(sighash), hash_ty))
             }
             Wpkh => {
                 let sighash = cache.p2wpkh_signature_hash(input_index, spk, utxo.value, hash_ty)?;
-                Ok((Message::from(sighash), hash_ty))
+                Ok((PsbtSighashMsg::SegwitV0(sighash), hash_ty))
             }
             ShWpkh => {
                 let redeem_script = input.redeem_script.as_ref().expect("checked above");
                 let sighash =
                     cache.p2wpkh_signature_hash(input_index, redeem_script, utxo.value, hash_ty)?;
-                Ok((Message::from(sighash), hash_ty))
+                Ok((PsbtSighashMsg::SegwitV0(sighash), hash_ty))
             }
             Wsh | ShWsh => {
                 let witness_script =
@@ -549,7 +912,7 @@ pub fn sighash_ecdsa<T: Borrow<Transaction>>(
                 let sighash = cache
                     .p2wsh_signature_hash(input_index, witness_script, utxo.value, hash_ty)
                     .map_err(SignError::SegwitV0Sighash)?;
-                Ok((Message::from(sighash), hash_ty))
+                Ok((PsbtSighashMsg::SegwitV0(sighash), hash_ty))
             }
             Tr => {
                 // This PSBT signing API is WIP, Taproot to come shortly.
@@ -560,14 +923,30 @@ pub fn sighash_ecdsa<T: Borrow<Transaction>>(
 
     /// Returns the sighash message to sign an SCHNORR input along with the sighash type.
     ///
-    /// Uses the [`TapSighashType`] from this input if one is specified. If no sighash type is
-    /// specified uses [`TapSighashType::Default`].
+    /// Uses `hash_ty` if given, otherwise the [`TapSighashType`] from this input if one is
+    /// specified. If no sighash type is specified uses [`TapSighashType::Default`].
     fn sighash_taproot<T: Borrow<Transaction>>(
         &self,
         input_index: usize,
         cache: &mut SighashCache<T>,
         leaf_hash: Option<TapLeafHash>,
+        hash_ty: Option<TapSighashType>,
     ) -> Result<(Message, TapSighashType), SignError> {
+        let (msg, hash_ty) = self.sighash_msg_taproot(input_index, cache, leaf_hash, hash_ty)?;
+        Ok((msg.into(), hash_ty))
+    }
+
+    /// Returns the sighash of a Taproot input along with the sighash type.
+    ///
+    /// Uses the sighash type as [`Psbt::sighash_taproot`] does. Computes the sighash of the script
+    /// path spend of the leaf with `leaf_hash` if given, otherwise the key path spend.
+    fn sighash_msg_taproot<T: Borrow<Transaction>>(
+        &self,
+        input_index: usize,
+        cache: &mut SighashCache<T>,
+        leaf_hash: Option<TapLeafHash>,
+        hash_ty: Option<TapSighashType>,
+    ) -> Result<(PsbtSighashMsg, TapSighashType), SignError> {
         use OutputType::*;
 
         if self.signing_algorithm(input_index)? != SigningAlgorithm::Schnorr {
@@ -578,11 +957,14 @@ fn sighash_taproot<T: Borrow<Transaction>>(
 
         match self.output_type(input_index)? {
             Tr => {
-                let hash_ty = input
-                    .sighash_type
-                    .unwrap_or_else(|| TapSighashType::Default.into())
-                    .taproot_hash_ty()
-                    .map_err(|_| SignError::InvalidSighashType)?;
+                let hash_ty = match hash_ty {
+                    Some(hash_ty) => hash_ty,
+                    None => input
+                        .sighash_type
+                        .unwrap_or_else(|| TapSighashType::Default.into())
+                        .taproot_hash_ty()
+                        .map_err(|_| SignError::InvalidSighashType)?,
+                };
 
                 let spend_utxos =
                     (0..self.inputs.len()).map(|i| self.spend_utxo(i).ok()).collect::<Vec<_>>();
@@ -603,21 +985,63 @@ fn sighash_taproot<T: Borrow<Transaction>>(
                 };
 
                 let sighash = if let Some(leaf_hash) = leaf_hash {
-                    cache.taproot_script_spend_signature_hash(
+                    PsbtSighashMsg::TapScriptSpend(cache.taproot_script_spend_signature_hash(
                         input_index,
                         &prev_outs,
                         leaf_hash,
                         hash_ty,
-                    )?
+                    )?)
                 } else {
-                    cache.taproot_key_spend_signature_hash(input_index, &prev_outs, hash_ty)?
+                    PsbtSighashMsg::TapKeySpend(cache.taproot_key_spend_signature_hash(
+                        input_index,
+                        &prev_outs,
+                        hash_ty,
+                    )?)
                 };
-                Ok((Message::from(sighash), hash_ty))
+                Ok((sighash, hash_ty))
             }
             _ => Err(SignError::Unsupported),
         }
     }
 
+    /// Returns the sighash of this PSBT's input at `input_index`.
+    ///
+    /// This is the digest a signer, e.g. a hardware wallet, signs for the input. The sighash
+    /// algorithm and the scripts committed to are selected from the spent output type and the
+    /// input maps, and the input's sighash type is used, all as in [`Psbt::sign`].
+    ///
+    /// For Taproot inputs the sighash of the script path spend of the leaf with `leaf_hash` is
+    /// returned if given, otherwise that of the key path spend. `leaf_hash` must be `None` for
+    /// other inputs. Like [`Psbt::sighash_ecdsa`] this does not support `OP_CODESEPARATOR`.
+    ///
+    /// # Errors
+    ///
+    /// - [`SignError::WrongSigningAlgorithm`] if `leaf_hash` is given for a non-Taproot input.
+    /// - Any error encountered computing the sighash.
+    pub fn sighash_msg<T: Borrow<Transaction>>(
+        &self,
+        input_index: usize,
+        cache: &mut SighashCache<T>,
+        leaf_hash: Option<TapLeafHash>,
+    ) -> Result<PsbtSighashMsg, SignError> {
+        match (self.signing_algorithm(input_index)?, leaf_hash) {
+            (SigningAlgorithm::Ecdsa, None) =>
+                self.sighash_msg_ecdsa(input_index, cache, None).map(|(msg, _)| msg),
+            (SigningAlgorithm::Ecdsa, Some(_)) => Err(SignError::WrongSigningAlgorithm),
+            (SigningAlgorithm::Schnorr, leaf_hash) =>
+                self.sighash_msg_taproot(input_index, cache, leaf_hash, None).map(|(msg, _)| msg),
+        }
+    }
+
+    /// Returns an iterator over the sighashes of all inputs of this PSBT.
+    ///
+    /// Yields the result of [`Psbt::sighash_msg`] without a leaf hash for each input in order,
+    /// i.e. Taproot inputs yield the sighash of the key path spend.
+    pub fn iter_sighashes(&self) -> impl Iterator<Item = Result<PsbtSighashMsg, SignError>> + '_ {
+        let mut cache = SighashCache::new(&self.unsigned_tx);
+        (0..self.inputs.len()).map(move |i| self.sighash_msg(i, &mut cache, None))
+    }
+
     /// Returns the spending utxo for this PSBT's input at `input_index`.
     pub fn spend_utxo(&self, input_index: usize) -> Result<&TxOut, SignError> {
         let input = self.checked_input(input_index)?;
@@ -718,16 +1142,178 @@ fn output_type(&self, input_index: usize) -> Result<OutputType, SignError> {
     /// - [`Error::MissingUtxo`] when UTXO information for any input is not present or is invalid.
     /// - [`Error::NegativeFee`] if calculated value is negative.
     /// - [`Error::FeeOverflow`] if an integer overflow occurs.
-    pub fn fee(&self) -> Result<Amount, Error> {
+    pub fn fee(&self) -> Result<Amount, Error> { Ok(self.checked_fee()?) }
+
+    /// Calculates the transaction fee, see [`Psbt::fee`].
+    fn checked_fee(&self) -> Result<Amount, EstimateError> {
         let mut inputs = Amount::ZERO;
-        for utxo in self.iter_funding_utxos() {
-            inputs = inputs.checked_add(utxo?.value).ok_or(Error::FeeOverflow)?;
+        for index in 0..self.inputs.len() {
+            let utxo = self.funding_utxo(index)?;
+            inputs = inputs.checked_add(utxo.value).ok_or(EstimateError::FeeOverflow)?;
         }
         let mut outputs = Amount::ZERO;
         for out in &self.unsigned_tx.output {
-            outputs = outputs.checked_add(out.value).ok_or(Error::FeeOverflow)?;
+            outputs = outputs.checked_add(out.value).ok_or(EstimateError::FeeOverflow)?;
+        }
+        inputs.checked_sub(outputs).ok_or(EstimateError::NegativeFee)
+    }
+
+    /// Calculates the fee rate of the transaction.
+    ///
+    /// This is [`Psbt::estimated_fee_rate`] assuming the largest possible ECDSA signatures, so
+    /// the fee rate of a PSBT that is not finalized may be slightly underestimated. See
+    /// [`Psbt::estimated_weight`] for the prediction of Taproot inputs that are not finalized.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`Psbt::estimated_fee_rate`], converted into an [`Error`].
+    pub fn fee_rate(&self) -> Result<FeeRate, Error> {
+        Ok(self.estimated_fee_rate(MAX_ECDSA_SIG_LEN)?)
+    }
+
+    /// Estimates the fee rate of the transaction once all inputs are finalized.
+    ///
+    /// The weight of the transaction is estimated by [`Psbt::estimated_weight`].
+    ///
+    /// # Errors
+    ///
+    /// - [`EstimateError::MissingUtxo`], [`EstimateError::UtxoOutOfBounds`] or
+    ///   [`EstimateError::InconsistentUtxo`] if the UTXO of an input is missing or invalid.
+    /// - [`EstimateError::NegativeFee`] or [`EstimateError::FeeOverflow`] if the fee is negative
+    ///   or overflows.
+    /// - [`EstimateError::UnknownInputWeight`] if an input is not finalized and its weight can not
+    ///   be predicted.
+    pub fn estimated_fee_rate(&self, ecdsa_sig_len: usize) -> Result<FeeRate, EstimateError> {
+        let fee = self.checked_fee()?;
+        let weight = self.estimated_weight(ecdsa_sig_len)?;
+        fee.checked_div_by_weight_floor(weight).ok_or(EstimateError::FeeOverflow)
+    }
+
+    /// Estimates the weight of the transaction once all inputs are finalized.
+    ///
+    /// The weight of finalized inputs is computed from their final `scriptSig` and witness. The
+    /// weight of the other inputs is predicted from the type of the output they spend, with ECDSA
+    /// signatures of `ecdsa_sig_len` bytes including the sighash type byte. Predictions are
+    /// supported for P2PKH, P2WPKH, P2SH-P2WPKH, multisig in P2SH, P2WSH or P2SH-P2WSH and Taproot
+    /// key path spends.
+    ///
+    /// Taproot inputs that are not finalized are predicted as key path spends if they have a key
+    /// path signature or no leaf scripts. Otherwise the input may be spent through a script, whose
+    /// witness can't be predicted, so [`EstimateError::UnknownInputWeight`] is returned. Finalize script
+    /// path inputs before estimating, their weight is then computed from the final witness.
+    ///
+    /// DER-encoded low S signatures, as created by this library and Bitcoin Core, are at most 72
+    /// bytes long including the sighash type byte. Signers that grind for low R create signatures
+    /// of at most 71 bytes. Signatures may be shorter than `ecdsa_sig_len`, in which case the
+    /// weight of the final transaction is lower by up to one weight unit per byte in a witness and
+    /// four per byte in a `scriptSig` for every ECDSA signature. The prediction for Schnorr
+    /// signatures is exact.
+    ///
+    /// # Errors
+    ///
+    /// - [`EstimateError::MissingUtxo`], [`EstimateError::UtxoOutOfBounds`] or
+    ///   [`EstimateError::InconsistentUtxo`] if the UTXO of a non-finalized input is missing or
+    ///   invalid.
+    /// - [`EstimateError::UnknownInputWeight`] if an input is not finalized and its weight can not
+    ///   be predicted.
+    pub fn estimated_weight(&self, ecdsa_sig_len: usize) -> Result<Weight, EstimateError> {
+        let inputs = (0..self.inputs.len())
+            .map(|index| self.input_weight_prediction(index, ecdsa_sig_len))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(transaction::predict_weight(inputs, self.unsigned_tx.script_pubkey_lens()))
+    }
+
+    /// Estimates the virtual size of the transaction once all inputs are finalized.
+    ///
+    /// This is the weight estimated by [`Psbt::estimated_weight`] in virtual bytes, rounded up.
+    ///
+    /// # Errors
+    ///
+    /// See [`Psbt::estimated_weight`].
+    pub fn estimated_vsize(&self, ecdsa_sig_len: usize) -> Result<usize, EstimateError> {
+        // No overflow because it's computed from data in memory
+        Ok(self.estimated_weight(ecdsa_sig_len)?.to_vbytes_ceil() as usize)
+    }
+
+    /// Returns the weight prediction of the input at `index`, see [`Psbt::estimated_weight`].
+    fn input_weight_prediction(
+        &self,
+        index: usize,
+        ecdsa_sig_len: usize,
+    ) -> Result<InputWeightPrediction, EstimateError> {
+        let input = &self.inputs[index];
+        if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+            let script_sig_len = input.final_script_sig.as_ref().map_or(0, |script| script.len());
+            let witness = input.final_script_witness.iter().flat_map(|witness| witness.iter());
+            return Ok(InputWeightPrediction::new(script_sig_len, witness.map(|elem| elem.len())));
         }
-        inputs.checked_sub(outputs).ok_or(Error::NegativeFee)
+
+        // The `scriptSig` pushes and the witness elements satisfying a multisig script.
+        let multisig = |script: &Script| {
+            let multisig =
+                Multisig::from_script(script).filter(|multisig| !multisig.is_verify())?;
+            let sigs = core::iter::repeat(ecdsa_sig_len).take(multisig.required());
+            let script_sig = sigs.clone().try_fold(
+                script::Builder::new().push_opcode(OP_0),
+                |builder, len| {
+                    let sig = PushBytesBuf::try_from(vec![0; len]).ok()?;
+                    Some(builder.push_slice(sig))
+                },
+            )?;
+            let witness = core::iter::once(0).chain(sigs).chain(core::iter::once(script.len()));
+            Some((script_sig, witness.collect::<Vec<_>>()))
+        };
+        let unknown = || EstimateError::UnknownInputWeight(index);
+        // The length of a `scriptSig` push of `len` bytes.
+        let push_len = |len: usize| {
+            let data = PushBytesBuf::try_from(vec![0; len]).map_err(|_| unknown())?;
+            Ok::<_, EstimateError>(script::Builder::new().push_slice(data).into_script().len())
+        };
+
+        let prediction = match self.funding_utxo(index)?.script_pubkey.classify() {
+            ScriptType::P2pkh(_) => {
+                let compressed = input.partial_sigs.keys().all(|key| key.compressed);
+                let key_len = if compressed { 33 } else { 65 };
+                let script_sig_len = push_len(ecdsa_sig_len)? + push_len(key_len)?;
+                InputWeightPrediction::new(script_sig_len, [0; 0])
+            }
+            ScriptType::P2wpkh(_) => InputWeightPrediction::new(0, [ecdsa_sig_len, 33]),
+            // Unless signed for the key path, the input may be spent through one of its scripts.
+            ScriptType::P2tr(_) if input.tap_key_sig.is_none() && !input.tap_scripts.is_empty() =>
+                return Err(unknown()),
+            ScriptType::P2tr(_) => match input.taproot_hash_ty() {
+                Ok(TapSighashType::Default) => InputWeightPrediction::P2TR_KEY_DEFAULT_SIGHASH,
+                _ => InputWeightPrediction::P2TR_KEY_NON_DEFAULT_SIGHASH,
+            },
+            ScriptType::P2wsh(_) => {
+                let witness_script = input.witness_script.as_ref().ok_or_else(unknown)?;
+                let (_, witness) = multisig(witness_script).ok_or_else(unknown)?;
+                InputWeightPrediction::new(0, witness)
+            }
+            ScriptType::P2sh(_) => {
+                let redeem_script = input.redeem_script.as_ref().ok_or_else(unknown)?;
+                let redeem_script_push_len = redeem_script.len() + 1;
+                match redeem_script.classify() {
+                    ScriptType::P2wpkh(_) =>
+                        InputWeightPrediction::new(redeem_script_push_len, [ecdsa_sig_len, 33]),
+                    ScriptType::P2wsh(_) => {
+                        let witness_script = input.witness_script.as_ref().ok_or_else(unknown)?;
+                        let (_, witness) = multisig(witness_script).ok_or_else(unknown)?;
+                        InputWeightPrediction::new(redeem_script_push_len, witness)
+                    }
+                    ScriptType::Multisig(_) => {
+                        let (script_sig, _) = multisig(redeem_script).ok_or_else(unknown)?;
+                        let redeem_script = <&PushBytes>::try_from(redeem_script.as_bytes())
+                            .map_err(|_| unknown())?;
+                        let script_sig = script_sig.push_slice(redeem_script).into_script();
+                        InputWeightPrediction::new(script_sig.len(), [0; 0])
+                    }
+                    _ => return Err(unknown()),
+                }
+            }
+            _ => return Err(unknown()),
+        };
+        Ok(prediction)
     }
 }
 
@@ -985,6 +1571,32 @@ pub fn signing_algorithm(&self) -> SigningAlgorithm {
     }
 }
 
+/// The sighash of a PSBT input, returned by [`Psbt::sighash_msg`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum PsbtSighashMsg {
+    /// The sighash of a legacy input, signed with ECDSA.
+    Legacy(LegacySighash),
+    /// The sighash of a SegWit v0 input, signed with ECDSA.
+    SegwitV0(SegwitV0Sighash),
+    /// The sighash of a Taproot key path spend, signed with Schnorr.
+    TapKeySpend(TapSighash),
+    /// The sighash of a Taproot script path spend, signed with Schnorr.
+    TapScriptSpend(TapSighash),
+}
+
+impl From<PsbtSighashMsg> for Message {
+    fn from(msg: PsbtSighashMsg) -> Self {
+        use PsbtSighashMsg::*;
+
+        match msg {
+            Legacy(hash) => hash.into(),
+            SegwitV0(hash) => hash.into(),
+            TapKeySpend(hash) | TapScriptSpend(hash) => hash.into(),
+        }
+    }
+}
+
 /// Signing algorithms supported by the Bitcoin network.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SigningAlgorithm {
@@ -1034,6 +1646,8 @@ pub enum SignError {
     WrongSigningAlgorithm,
     /// Signing request currently unsupported.
     Unsupported,
+    /// The UTXO information of the input is inconsistent.
+    InconsistentUtxo(UtxoConsistencyError),
 }
 
 impl From<Infallible> for SignError {
@@ -1062,6 +1676,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             WrongSigningAlgorithm =>
                 write!(f, "attempt to sign an input with the wrong signing algorithm"),
             Unsupported => write!(f, "signing request currently unsupported"),
+            InconsistentUtxo(ref e) => write_err!(f, "inconsistent UTXO"; e),
         }
     }
 }
@@ -1076,6 +1691,7 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
             P2wpkhSighash(ref e) => Some(e),
             TaprootError(ref e) => Some(e),
             IndexOutOfBounds(ref e) => Some(e),
+            InconsistentUtxo(ref e) => Some(e),
             InvalidSighashType
             | MissingInputUtxo
             | MissingRedeemScript
@@ -1104,69 +1720,474 @@ impl From<sighash::TaprootError> for SignError {
     fn from(e: sighash::TaprootError) -> Self { SignError::TaprootError(e) }
 }
 
-/// This error is returned when extracting a [`Transaction`] from a [`Psbt`].
+/// Error returned by [`Psbt::join`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
-pub enum ExtractTxError {
-    /// The [`FeeRate`] is too high
-    AbsurdFeeRate {
-        /// The [`FeeRate`]
-        fee_rate: FeeRate,
-        /// The extracted [`Transaction`] (use this to ignore the error)
-        tx: Transaction,
-    },
-    /// One or more of the inputs lacks value information (witness_utxo or non_witness_utxo)
-    MissingInputValue {
-        /// The extracted [`Transaction`] (use this to ignore the error)
-        tx: Transaction,
+pub enum JoinError {
+    /// Both PSBTs spend the same outpoint.
+    DuplicateInput(transaction::OutPoint),
+    /// An input of one of the PSBTs is signed or finalized.
+    SignedInput(transaction::OutPoint),
+    /// The unsigned transactions have different versions.
+    VersionMismatch {
+        /// The version of the transaction being joined to.
+        ours: transaction::Version,
+        /// The version of the other transaction.
+        theirs: transaction::Version,
     },
-    /// Input value is less than Output Value, and the [`Transaction`] would be invalid.
-    SendingTooMuch {
-        /// The original [`Psbt`] is returned untouched.
-        psbt: Psbt,
+    /// One of the lock times is a block height and the other a timestamp.
+    LockTimeUnitMismatch {
+        /// The lock time of the transaction being joined to.
+        ours: absolute::LockTime,
+        /// The lock time of the other transaction.
+        theirs: absolute::LockTime,
     },
+    /// The key sources of an extended public key in the global maps are inconsistent.
+    InconsistentKeySources(Box<Xpub>),
 }
 
-impl From<Infallible> for ExtractTxError {
+impl From<Infallible> for JoinError {
     fn from(never: Infallible) -> Self { match never {} }
 }
 
-impl fmt::Display for ExtractTxError {
+impl fmt::Display for JoinError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use ExtractTxError::*;
+        use JoinError::*;
 
         match *self {
-            AbsurdFeeRate { fee_rate, .. } =>
-                write!(f, "an absurdly high fee rate of {}", fee_rate),
-            MissingInputValue { .. } => write!(
-                f,
-                "one of the inputs lacked value information (witness_utxo or non_witness_utxo)"
-            ),
-            SendingTooMuch { .. } => write!(
-                f,
-                "transaction would be invalid due to output value being greater than input value."
-            ),
+            DuplicateInput(ref outpoint) => write!(f, "both PSBTs spend {}", outpoint),
+            SignedInput(ref outpoint) => write!(f, "the input spending {} is signed", outpoint),
+            VersionMismatch { ours, theirs } =>
+                write!(f, "transaction versions {} and {} differ", ours, theirs),
+            LockTimeUnitMismatch { ours, theirs } =>
+                write!(f, "lock times {} and {} have different units", ours, theirs),
+            InconsistentKeySources(ref xpub) =>
+                write!(f, "inconsistent key sources for xpub {}", xpub),
         }
     }
 }
 
 #[cfg(feature = "std")]
-impl std::error::Error for ExtractTxError {
+impl std::error::Error for JoinError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        use ExtractTxError::*;
+        use JoinError::*;
 
         match *self {
-            AbsurdFeeRate { .. } | MissingInputValue { .. } | SendingTooMuch { .. } => None,
+            DuplicateInput(_)
+            | SignedInput(_)
+            | VersionMismatch { .. }
+            | LockTimeUnitMismatch { .. }
+            | InconsistentKeySources(_) => None,
         }
     }
 }
 
-/// Input index out of bounds (actual index, maximum index allowed).
+/// The UTXO spent by an input, see [`UtxoInfo`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum IndexOutOfBoundsError {
-    /// The index is out of bounds for the `psbt.inputs` vector.
-    Inputs {
+pub enum PrevOut {
+    /// The transaction that created the output.
+    Transaction(Transaction),
+    /// The output itself, only sufficient for SegWit inputs.
+    TxOut(TxOut),
+}
+
+/// The UTXO spent by an input and the information needed to sign for it.
+///
+/// See [`Psbt::from_unsigned_tx_with_utxos`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoInfo {
+    /// The output being spent, or the transaction that created it.
+    pub prev_out: PrevOut,
+    /// The redeem script of a P2SH output.
+    pub redeem_script: Option<ScriptBuf>,
+    /// The witness script of a P2WSH output.
+    pub witness_script: Option<ScriptBuf>,
+    /// A map from public keys needed to sign the input to their corresponding master key
+    /// fingerprints and derivation paths.
+    pub bip32_derivation: BTreeMap<secp256k1::PublicKey, KeySource>,
+}
+
+impl UtxoInfo {
+    /// Constructs a new `UtxoInfo` from the transaction that created the output.
+    pub fn from_tx(tx: Transaction) -> Self { Self::new(PrevOut::Transaction(tx)) }
+
+    /// Constructs a new `UtxoInfo` from the output being spent.
+    pub fn from_txout(txout: TxOut) -> Self { Self::new(PrevOut::TxOut(txout)) }
+
+    fn new(prev_out: PrevOut) -> Self {
+        UtxoInfo {
+            prev_out,
+            redeem_script: None,
+            witness_script: None,
+            bip32_derivation: BTreeMap::new(),
+        }
+    }
+}
+
+/// Error returned by [`Psbt::from_unsigned_tx_with_utxos`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum UtxoLookupError {
+    /// The transaction is not unsigned, see [`Psbt::from_unsigned_tx`].
+    UnsignedTx(Error),
+    /// No UTXO was found for these outpoints.
+    MissingUtxos(Vec<transaction::OutPoint>),
+    /// The transaction provided for an outpoint has a different txid.
+    TxidMismatch {
+        /// The outpoint spent by the input.
+        outpoint: transaction::OutPoint,
+        /// The txid of the transaction provided.
+        txid: Txid,
+    },
+    /// The transaction provided for an outpoint has no output at its index.
+    MissingOutput(transaction::OutPoint),
+    /// Only the output was provided for an outpoint that is not spent by a SegWit input.
+    MissingPrevTx(transaction::OutPoint),
+}
+
+impl From<Infallible> for UtxoLookupError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for UtxoLookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use UtxoLookupError::*;
+
+        match *self {
+            UnsignedTx(ref e) => write_err!(f, "invalid unsigned transaction"; e),
+            MissingUtxos(ref outpoints) => {
+                f.write_str("no UTXO found for")?;
+                for (i, outpoint) in outpoints.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", separator, outpoint)?;
+                }
+                Ok(())
+            }
+            TxidMismatch { ref outpoint, ref txid } =>
+                write!(f, "transaction {} provided to spend {}", txid, outpoint),
+            MissingOutput(ref outpoint) =>
+                write!(f, "the transaction provided has no output {}", outpoint),
+            MissingPrevTx(ref outpoint) =>
+                write!(f, "the non-SegWit output {} requires the previous transaction", outpoint),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UtxoLookupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use UtxoLookupError::*;
+
+        match *self {
+            UnsignedTx(ref e) => Some(e),
+            MissingUtxos(_) | TxidMismatch { .. } | MissingOutput(_) | MissingPrevTx(_) => None,
+        }
+    }
+}
+
+/// Error returned by [`Psbt::validate_utxos`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UtxoConsistencyError {
+    /// The `non_witness_utxo` is not the transaction spent by the input.
+    TxidMismatch {
+        /// The index of the input.
+        input: usize,
+        /// The outpoint spent by the input.
+        outpoint: transaction::OutPoint,
+        /// The txid of the `non_witness_utxo`.
+        txid: Txid,
+    },
+    /// The `non_witness_utxo` has no output at the index spent by the input.
+    VoutOutOfRange {
+        /// The index of the input.
+        input: usize,
+        /// The outpoint spent by the input.
+        outpoint: transaction::OutPoint,
+    },
+    /// The `witness_utxo` differs from the spent output of the `non_witness_utxo`.
+    WitnessUtxoMismatch {
+        /// The index of the input.
+        input: usize,
+        /// The outpoint spent by the input.
+        outpoint: transaction::OutPoint,
+    },
+}
+
+impl UtxoConsistencyError {
+    /// Returns the index of the input with inconsistent UTXO information.
+    pub fn input_index(&self) -> usize {
+        use UtxoConsistencyError::*;
+
+        match *self {
+            TxidMismatch { input, .. }
+            | VoutOutOfRange { input, .. }
+            | WitnessUtxoMismatch { input, .. } => input,
+        }
+    }
+}
+
+impl From<Infallible> for UtxoConsistencyError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for UtxoConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use UtxoConsistencyError::*;
+
+        match *self {
+            TxidMismatch { input, ref outpoint, ref txid } => write!(
+                f,
+                "non-witness UTXO of input {} is transaction {} but the input spends {}",
+                input, txid, outpoint
+            ),
+            VoutOutOfRange { input, ref outpoint } =>
+                write!(f, "non-witness UTXO of input {} has no output {}", input, outpoint),
+            WitnessUtxoMismatch { input, ref outpoint } => write!(
+                f,
+                "witness UTXO of input {} does not match the output {} of the non-witness UTXO",
+                input, outpoint
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UtxoConsistencyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { None }
+}
+
+/// Returns the number of signatures needed to spend `script_pubkey`, if it is known.
+///
+/// Taproot outputs are counted as key path spends.
+fn signatures_needed(input: &Input, script_pubkey: &Script) -> Option<usize> {
+    let required = |script: Option<&Script>| {
+        Multisig::from_script(script?).map(|multisig| multisig.required())
+    };
+    match script_pubkey.classify() {
+        ScriptType::P2a => Some(0),
+        ScriptType::P2pk(_)
+        | ScriptType::P2pkh(_)
+        | ScriptType::P2wpkh(_)
+        | ScriptType::P2tr(_) => Some(1),
+        ScriptType::Multisig(multisig) => Some(multisig.required()),
+        ScriptType::P2wsh(_) => required(input.witness_script.as_deref()),
+        ScriptType::P2sh(_) => match input.redeem_script.as_ref()?.classify() {
+            ScriptType::P2wpkh(_) => Some(1),
+            ScriptType::P2wsh(_) => required(input.witness_script.as_deref()),
+            _ => required(input.redeem_script.as_deref()),
+        },
+        _ => None,
+    }
+}
+
+/// How close an input of a [`Psbt`] is to being ready for extraction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InputReadiness {
+    /// The input has a final `scriptSig` or witness.
+    Finalized,
+    /// The input has not been finalized yet.
+    MissingFinalScript {
+        /// The number of partial signatures collected, ECDSA and Taproot ones alike.
+        has_partial_sigs: usize,
+        /// The number of signatures needed to spend the input, if it can be determined from
+        /// the scripts in the PSBT.
+        needs: Option<usize>,
+    },
+    /// The input lacks the UTXO it spends (`witness_utxo` or `non_witness_utxo`).
+    MissingUtxoInfo,
+}
+
+impl fmt::Display for InputReadiness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InputReadiness::*;
+
+        match *self {
+            Finalized => f.write_str("finalized"),
+            MissingFinalScript { has_partial_sigs, needs: Some(needs) } =>
+                write!(f, "not finalized, has {} of {} signatures", has_partial_sigs, needs),
+            MissingFinalScript { has_partial_sigs, needs: None } =>
+                write!(f, "not finalized, has {} signatures", has_partial_sigs),
+            MissingUtxoInfo => f.write_str("missing the UTXO being spent"),
+        }
+    }
+}
+
+/// This error is returned when extracting a [`Transaction`] from a [`Psbt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtractTxError {
+    /// The [`FeeRate`] is too high
+    AbsurdFeeRate {
+        /// The fee paid by the transaction.
+        fee: Amount,
+        /// The [`FeeRate`]
+        fee_rate: FeeRate,
+        /// The maximum fee rate that was exceeded.
+        max_fee_rate: FeeRate,
+        /// The extracted [`Transaction`] (use this to ignore the error)
+        tx: Transaction,
+    },
+    /// One or more of the inputs lacks value information (witness_utxo or non_witness_utxo)
+    ///
+    /// Inputs without UTXO information are not ready for extraction, these are normally reported
+    /// with [`ExtractTxError::NotReady`] before the fee is computed.
+    MissingInputValue {
+        /// The extracted [`Transaction`] (use this to ignore the error)
+        tx: Transaction,
+    },
+    /// Input value is less than Output Value, and the [`Transaction`] would be invalid.
+    SendingTooMuch {
+        /// The original [`Psbt`] is returned untouched.
+        psbt: Psbt,
+    },
+    /// The witness and non-witness UTXO of an input describe different outputs.
+    InconsistentUtxo {
+        /// The index of the input.
+        input: usize,
+        /// The original [`Psbt`] is returned untouched.
+        psbt: Psbt,
+    },
+    /// Some inputs are not finalized or lack their UTXO.
+    NotReady {
+        /// The readiness of every input, see [`Psbt::extraction_readiness`].
+        inputs: Vec<InputReadiness>,
+        /// The original [`Psbt`] is returned untouched.
+        psbt: Psbt,
+    },
+}
+
+impl From<Infallible> for ExtractTxError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for ExtractTxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ExtractTxError::*;
+
+        match *self {
+            AbsurdFeeRate { fee, fee_rate, max_fee_rate, .. } => write!(
+                f,
+                "an absurdly high fee rate of {} (fee {}) exceeding the maximum of {}",
+                fee_rate, fee, max_fee_rate
+            ),
+            MissingInputValue { .. } => write!(
+                f,
+                "one of the inputs lacked value information (witness_utxo or non_witness_utxo)"
+            ),
+            SendingTooMuch { .. } => write!(
+                f,
+                "transaction would be invalid due to output value being greater than input value."
+            ),
+            InconsistentUtxo { input, .. } =>
+                write!(f, "the witness and non-witness UTXO of input {} are inconsistent", input),
+            NotReady { ref inputs, .. } => {
+                f.write_str("inputs not ready for extraction:")?;
+                let not_ready = inputs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, readiness)| **readiness != InputReadiness::Finalized);
+                for (i, (index, readiness)) in not_ready.enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{} ({})", separator, index, readiness)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ExtractTxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use ExtractTxError::*;
+
+        match *self {
+            AbsurdFeeRate { .. }
+            | MissingInputValue { .. }
+            | SendingTooMuch { .. }
+            | InconsistentUtxo { .. }
+            | NotReady { .. } => None,
+        }
+    }
+}
+
+/// Error estimating the weight or fee rate of a [`Psbt`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EstimateError {
+    /// The input at this index has neither a witness nor a non-witness UTXO.
+    MissingUtxo(usize),
+    /// The non-witness UTXO of the input at this index lacks the spent output.
+    UtxoOutOfBounds(usize),
+    /// The witness and non-witness UTXO of the input at this index are inconsistent.
+    InconsistentUtxo(usize),
+    /// The input at this index is not finalized and the weight of its satisfaction is unknown.
+    UnknownInputWeight(usize),
+    /// The value of the outputs exceeds the value of the inputs.
+    NegativeFee,
+    /// Integer overflow in fee calculation.
+    FeeOverflow,
+}
+
+impl From<Infallible> for EstimateError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for EstimateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use EstimateError::*;
+
+        match *self {
+            MissingUtxo(index) => write!(f, "input {} has no UTXO information", index),
+            UtxoOutOfBounds(index) =>
+                write!(f, "the non-witness UTXO of input {} lacks the spent output", index),
+            InconsistentUtxo(index) =>
+                write!(f, "the witness and non-witness UTXO of input {} are inconsistent", index),
+            UnknownInputWeight(index) =>
+                write!(f, "can not predict the weight of non-finalized input {}", index),
+            NegativeFee => f.write_str("PSBT has a negative fee which is not allowed"),
+            FeeOverflow => f.write_str("integer overflow in fee calculation"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EstimateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use EstimateError::*;
+
+        match *self {
+            MissingUtxo(_)
+            | UtxoOutOfBounds(_)
+            | InconsistentUtxo(_)
+            | UnknownInputWeight(_)
+            | NegativeFee
+            | FeeOverflow => None,
+        }
+    }
+}
+
+impl From<EstimateError> for Error {
+    fn from(e: EstimateError) -> Self {
+        match e {
+            EstimateError::MissingUtxo(_) => Error::MissingUtxo,
+            EstimateError::UtxoOutOfBounds(_) => Error::PsbtUtxoOutOfbounds,
+            EstimateError::InconsistentUtxo(index) => Error::InconsistentUtxo(index),
+            EstimateError::UnknownInputWeight(index) => Error::UnknownInputWeight(index),
+            EstimateError::NegativeFee => Error::NegativeFee,
+            EstimateError::FeeOverflow => Error::FeeOverflow,
+        }
+    }
+}
+
+/// Input index out of bounds (actual index, maximum index allowed).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IndexOutOfBoundsError {
+    /// The index is out of bounds for the `psbt.inputs` vector.
+    Inputs {
         /// Attempted index access.
         index: usize,
         /// Length of the PBST inputs vector.
@@ -1218,14 +2239,15 @@ fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 #[cfg(feature = "base64")]
 mod display_from_str {
     use core::convert::Infallible;
-    use core::fmt;
     use core::str::FromStr;
+    use core::{cmp, fmt};
 
     use base64::display::Base64Display;
     use base64::prelude::{Engine as _, BASE64_STANDARD};
     use internals::write_err;
+    use io::Write;
 
-    use super::{Error, Psbt};
+    use super::{Error, Psbt, PsbtV2};
 
     /// Error encountered during PSBT decoding from Base64 string.
     #[derive(Debug)]
@@ -1270,6 +2292,82 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         }
     }
 
+    impl Psbt {
+        /// Serializes the PSBT as base64 into a writer.
+        ///
+        /// The PSBT is base64 encoded as it is serialized with [`Psbt::serialize_to_writer`],
+        /// neither the binary nor the base64 encoding is held in memory. Returns the number of
+        /// bytes written, the output is the same as the `Display` implementation.
+        pub fn serialize_base64_to_writer(&self, w: &mut impl Write) -> io::Result<usize> {
+            let mut encoder = Base64Writer { inner: w, pending: [0; 3], pending_len: 0, len: 0 };
+            self.serialize_to_writer(&mut encoder)?;
+            encoder.finish()
+        }
+    }
+
+    /// Base64 encodes the data written to it into `inner`.
+    ///
+    /// Data is encoded in groups of three bytes, [`Base64Writer::finish`] must be called to write
+    /// the final, padded, group.
+    struct Base64Writer<'a, W: ?Sized> {
+        inner: &'a mut W,
+        /// Bytes not yet encoded because they do not make up a whole group.
+        pending: [u8; 3],
+        pending_len: usize,
+        /// Number of bytes written to `inner`.
+        len: usize,
+    }
+
+    /// The number of bytes encoded at once, a multiple of three so that no padding is added.
+    const BASE64_CHUNK_LEN: usize = 768;
+
+    impl<W: Write + ?Sized> Base64Writer<'_, W> {
+        fn encode(&mut self, data: &[u8]) -> io::Result<()> {
+            let mut buf = [0_u8; BASE64_CHUNK_LEN / 3 * 4];
+            let len = BASE64_STANDARD.encode_slice(data, &mut buf).expect("buffer is big enough");
+            self.inner.write_all(&buf[..len])?;
+            self.len += len;
+            Ok(())
+        }
+
+        fn finish(mut self) -> io::Result<usize> {
+            let pending = self.pending;
+            self.encode(&pending[..self.pending_len])?;
+            Ok(self.len)
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for Base64Writer<'_, W> {
+        fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+            let written = buf.len();
+
+            if self.pending_len > 0 {
+                let n = cmp::min(3 - self.pending_len, buf.len());
+                self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&buf[..n]);
+                self.pending_len += n;
+                buf = &buf[n..];
+                if self.pending_len < 3 {
+                    return Ok(written);
+                }
+                let pending = self.pending;
+                self.encode(&pending)?;
+                self.pending_len = 0;
+            }
+
+            let whole = buf.len() - buf.len() % 3;
+            for chunk in buf[..whole].chunks(BASE64_CHUNK_LEN) {
+                self.encode(chunk)?;
+            }
+            let rest = &buf[whole..];
+            self.pending[..rest.len()].copy_from_slice(rest);
+            self.pending_len = rest.len();
+
+            Ok(written)
+        }
+
+        fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
+    }
+
     impl FromStr for Psbt {
         type Err = PsbtParseError;
 
@@ -1278,6 +2376,72 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
             Psbt::deserialize(&data).map_err(PsbtParseError::PsbtEncoding)
         }
     }
+
+    #[cfg(feature = "serde")]
+    impl serde::Serialize for Psbt {
+        fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            if s.is_human_readable() {
+                // Base64 encodes straight into the serializer.
+                s.collect_str(self)
+            } else {
+                s.serialize_bytes(&self.serialize())
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> serde::Deserialize<'de> for Psbt {
+        fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Psbt, D::Error> {
+            if d.is_human_readable() {
+                struct Base64Visitor;
+
+                impl serde::de::Visitor<'_> for Base64Visitor {
+                    type Value = Psbt;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("a base64 encoded PSBT")
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Psbt, E> {
+                        v.parse::<Psbt>().map_err(E::custom)
+                    }
+                }
+
+                d.deserialize_str(Base64Visitor)
+            } else {
+                struct BytesVisitor;
+
+                impl serde::de::Visitor<'_> for BytesVisitor {
+                    type Value = Psbt;
+
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("a BIP-174 serialized PSBT")
+                    }
+
+                    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Psbt, E> {
+                        Psbt::deserialize(v).map_err(|e| E::custom(PsbtParseError::PsbtEncoding(e)))
+                    }
+                }
+
+                d.deserialize_bytes(BytesVisitor)
+            }
+        }
+    }
+
+    impl fmt::Display for PsbtV2 {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", Base64Display::new(&self.serialize(), &BASE64_STANDARD))
+        }
+    }
+
+    impl FromStr for PsbtV2 {
+        type Err = PsbtParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let data = BASE64_STANDARD.decode(s).map_err(PsbtParseError::Base64Encoding)?;
+            PsbtV2::deserialize(&data).map_err(PsbtParseError::PsbtEncoding)
+        }
+    }
 }
 #[cfg(feature = "base64")]
 pub use self::display_from_str::PsbtParseError;
@@ -1390,6 +2554,12 @@ fn psbt_uncompressed_key() {
 
     #[test]
     fn psbt_high_fee_checks() {
+        // An empty final scriptSig does not change the weight.
+        let psbt_with_values = |input, output| {
+            let mut psbt = psbt_with_values(input, output);
+            psbt.inputs[0].final_script_sig = Some(ScriptBuf::new());
+            psbt
+        };
         let psbt = psbt_with_values(5_000_000_000_000, 1000);
         assert_eq!(
             psbt.clone().extract_tx().map_err(|e| match e {
@@ -1432,6 +2602,478 @@ fn psbt_high_fee_checks() {
         assert!(psbt_with_values(2076000, 1000).extract_tx().is_ok());
     }
 
+    #[test]
+    fn psbt_fee_rate() {
+        use crate::address::script_pubkey::ScriptBufExt as _;
+        use crate::key::WPubkeyHash;
+
+        let p2wpkh = ScriptBuf::new_p2wpkh(WPubkeyHash::from_byte_array([1; 20]));
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::COINBASE_PREVOUT,
+                ..TxIn::EMPTY_COINBASE
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(10_000),
+                script_pubkey: p2wpkh.clone(),
+            }],
+        };
+        let predicted = transaction::predict_weight(
+            [InputWeightPrediction::P2WPKH_MAX],
+            tx.script_pubkey_lens(),
+        );
+
+        // Pays 10,000 sat/vB before signing.
+        let fee_rate = FeeRate::from_sat_per_vb_unchecked(10_000);
+        let fee = fee_rate.to_fee(predicted).unwrap();
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat_u32(10_000).checked_add(fee).unwrap(),
+            script_pubkey: p2wpkh,
+        });
+        assert_eq!(psbt.fee_rate().unwrap(), fee_rate);
+
+        // The final witness is used once present, the signature is one byte shorter.
+        let mut witness = Witness::new();
+        witness.push([0x30; 71]);
+        witness.push([0x02; 33]);
+        psbt.inputs[0].final_script_witness = Some(witness);
+        assert!(psbt.fee_rate().unwrap() > fee_rate);
+
+        let max_fee_rate = FeeRate::from_sat_per_vb_unchecked(1_000);
+        match psbt.clone().extract_tx_with_fee_rate_limit(max_fee_rate) {
+            Err(ExtractTxError::AbsurdFeeRate {
+                fee: f,
+                fee_rate: rate,
+                max_fee_rate: max,
+                tx,
+            }) => {
+                assert_eq!(f, fee);
+                assert_eq!(rate, psbt.fee_rate().unwrap());
+                assert_eq!(max, max_fee_rate);
+                assert_eq!(tx.input[0].witness.len(), 2);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(psbt.clone().extract_tx_with_fee_rate_limit(FeeRate::MAX).is_ok());
+
+        // A non-witness UTXO with a different value for the same outpoint.
+        let mut prev_tx = psbt.unsigned_tx.clone();
+        prev_tx.output[0] =
+            TxOut { value: Amount::ONE_SAT, ..psbt.inputs[0].witness_utxo.clone().unwrap() };
+        psbt.unsigned_tx.input[0].previous_output =
+            OutPoint { txid: prev_tx.compute_txid(), vout: 0 };
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx);
+        assert!(matches!(psbt.fee(), Err(Error::InconsistentUtxo(0))));
+        assert!(matches!(psbt.fee_rate(), Err(Error::InconsistentUtxo(0))));
+        assert_eq!(
+            psbt.estimated_fee_rate(MAX_ECDSA_SIG_LEN),
+            Err(EstimateError::InconsistentUtxo(0))
+        );
+        assert!(matches!(
+            psbt.extract_tx(),
+            Err(ExtractTxError::InconsistentUtxo { input: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn psbt_fee_rate_multisig_prediction() {
+        use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
+
+        let keys = (1..=3u8)
+            .map(|i| {
+                let sk = secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap();
+                PublicKey::new(secp256k1::PublicKey::from_secret_key(&Secp256k1::new(), &sk))
+            })
+            .collect::<Vec<_>>();
+        let witness_script = ScriptBuf::new_multisig(2, &keys).unwrap();
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::COINBASE_PREVOUT,
+                ..TxIn::EMPTY_COINBASE
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(10_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat_u32(20_000),
+            script_pubkey: witness_script.to_p2wsh().unwrap(),
+        });
+        assert!(matches!(psbt.fee_rate(), Err(Error::UnknownInputWeight(0))));
+        assert_eq!(
+            psbt.estimated_weight(MAX_ECDSA_SIG_LEN),
+            Err(EstimateError::UnknownInputWeight(0))
+        );
+
+        psbt.inputs[0].witness_script = Some(witness_script.clone());
+        let predicted = psbt.fee_rate().unwrap();
+
+        // The prediction matches a witness with two maximum length signatures.
+        let mut witness = Witness::new();
+        witness.push([]);
+        witness.push([0x30; 72]);
+        witness.push([0x30; 72]);
+        witness.push(witness_script.as_bytes());
+        psbt.inputs[0].final_script_witness = Some(witness);
+        assert_eq!(psbt.fee_rate().unwrap(), predicted);
+    }
+
+    #[test]
+    fn from_unsigned_tx_with_utxos() {
+        use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
+
+        let secp = Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_byte_array(&[1; 32]).unwrap();
+        let key = PublicKey::new(secp256k1::PublicKey::from_secret_key(&secp, &sk));
+        let p2wpkh = ScriptBuf::new_p2wpkh(key.wpubkey_hash().unwrap());
+        let witness_script = ScriptBuf::new_multisig(1, &[key]).unwrap();
+        let key_source = (bip32::Fingerprint::from([1; 4]), DerivationPath::default());
+
+        // A previous transaction paying to legacy, nested and native SegWit outputs.
+        let prev_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE],
+            output: [
+                ScriptBuf::new_p2pkh(key.pubkey_hash()),
+                p2wpkh.to_p2sh().unwrap(),
+                witness_script.to_p2wsh().unwrap(),
+                p2wpkh.clone(),
+            ]
+            .into_iter()
+            .map(|script_pubkey| TxOut { value: Amount::from_sat_u32(10_000), script_pubkey })
+            .collect(),
+        };
+        let txid = prev_tx.compute_txid();
+        let outpoint = |vout| OutPoint { txid, vout };
+        let unsigned_tx = |vouts: &[u32]| Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vouts
+                .iter()
+                .map(|&vout| TxIn { previous_output: outpoint(vout), ..TxIn::EMPTY_COINBASE })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(30_000),
+                script_pubkey: p2wpkh.clone(),
+            }],
+        };
+        let lookup = |outpoint: &OutPoint| {
+            let txout = prev_tx.output.get(outpoint.vout as usize)?.clone();
+            let info = match outpoint.vout {
+                0 => UtxoInfo::from_tx(prev_tx.clone()),
+                1 => UtxoInfo {
+                    redeem_script: Some(p2wpkh.clone()),
+                    ..UtxoInfo::from_tx(prev_tx.clone())
+                },
+                2 => UtxoInfo {
+                    witness_script: Some(witness_script.clone()),
+                    bip32_derivation: [(key.inner, key_source.clone())].into_iter().collect(),
+                    ..UtxoInfo::from_txout(txout)
+                },
+                _ => UtxoInfo::from_txout(txout),
+            };
+            Some(info)
+        };
+
+        let psbt = Psbt::from_unsigned_tx_with_utxos(unsigned_tx(&[0, 1, 2, 3]), lookup).unwrap();
+        let utxos = psbt
+            .inputs
+            .iter()
+            .map(|input| (input.non_witness_utxo.is_some(), input.witness_utxo.as_ref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            utxos,
+            vec![
+                (true, None),
+                (true, Some(&prev_tx.output[1])),
+                (false, Some(&prev_tx.output[2])),
+                (false, Some(&prev_tx.output[3])),
+            ]
+        );
+        assert_eq!(psbt.inputs[0].non_witness_utxo.as_ref(), Some(&prev_tx));
+        assert_eq!(psbt.inputs[1].redeem_script.as_ref(), Some(&p2wpkh));
+        assert_eq!(psbt.inputs[2].witness_script.as_ref(), Some(&witness_script));
+        assert_eq!(psbt.inputs[2].bip32_derivation.get(&key.inner), Some(&key_source));
+        assert_eq!(psbt.fee().unwrap(), Amount::from_sat_u32(10_000));
+
+        // All missing outpoints are reported.
+        let missing = OutPoint { txid: Txid::from_byte_array([2; 32]), vout: 0 };
+        let mut tx = unsigned_tx(&[4, 0, 5]);
+        tx.input[0].previous_output = missing;
+        let err = Psbt::from_unsigned_tx_with_utxos(tx, |outpoint| {
+            if outpoint.txid == txid {
+                lookup(outpoint)
+            } else {
+                None
+            }
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, UtxoLookupError::MissingUtxos(ref outpoints) if *outpoints == [missing, outpoint(5)])
+        );
+
+        // A legacy output needs the previous transaction.
+        let err = Psbt::from_unsigned_tx_with_utxos(unsigned_tx(&[0]), |outpoint| {
+            Some(UtxoInfo::from_txout(prev_tx.output[outpoint.vout as usize].clone()))
+        })
+        .unwrap_err();
+        assert!(matches!(err, UtxoLookupError::MissingPrevTx(o) if o == outpoint(0)));
+
+        // The previous transaction must match the outpoint.
+        let mut other_tx = prev_tx.clone();
+        other_tx.lock_time = absolute::LockTime::from_consensus(1);
+        let err = Psbt::from_unsigned_tx_with_utxos(unsigned_tx(&[3]), |_| {
+            Some(UtxoInfo::from_tx(other_tx.clone()))
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            UtxoLookupError::TxidMismatch { outpoint: o, txid: t }
+                if o == outpoint(3) && t == other_tx.compute_txid()
+        ));
+        let err = Psbt::from_unsigned_tx_with_utxos(unsigned_tx(&[4]), |_| {
+            Some(UtxoInfo::from_tx(prev_tx.clone()))
+        })
+        .unwrap_err();
+        assert!(matches!(err, UtxoLookupError::MissingOutput(o) if o == outpoint(4)));
+
+        let mut signed = unsigned_tx(&[3]);
+        signed.input[0].script_sig = ScriptBuf::from_bytes(vec![0x51]);
+        let err = Psbt::from_unsigned_tx_with_utxos(signed, lookup).unwrap_err();
+        assert!(matches!(err, UtxoLookupError::UnsignedTx(Error::UnsignedTxHasScriptSigs)));
+    }
+
+    #[test]
+    fn psbt_extraction_readiness() {
+        use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
+
+        let secp = Secp256k1::new();
+        let secret_keys = (1..=3u8)
+            .map(|i| secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap())
+            .collect::<Vec<_>>();
+        let keys = secret_keys
+            .iter()
+            .map(|sk| PublicKey::new(secp256k1::PublicKey::from_secret_key(&secp, sk)))
+            .collect::<Vec<_>>();
+        let sign = |sk| {
+            let msg = Message::from_digest([1; 32]);
+            ecdsa::Signature::sighash_all(secp.sign_ecdsa(&msg, sk))
+        };
+        let witness_script = ScriptBuf::new_multisig(2, &keys).unwrap();
+        let p2wpkh = ScriptBuf::new_p2wpkh(keys[0].wpubkey_hash().unwrap());
+
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: (0..3u32)
+                .map(|vout| TxIn {
+                    previous_output: OutPoint { txid: crate::Txid::from_byte_array([1; 32]), vout },
+                    ..TxIn::EMPTY_COINBASE
+                })
+                .collect(),
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(10_000),
+                script_pubkey: p2wpkh.clone(),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        let utxo = |script_pubkey| TxOut { value: Amount::from_sat_u32(5_000), script_pubkey };
+
+        // A finalized P2WPKH input.
+        let mut witness = Witness::new();
+        witness.push(sign(&secret_keys[0]).to_vec());
+        witness.push(keys[0].to_vec());
+        psbt.inputs[0].witness_utxo = Some(utxo(p2wpkh));
+        psbt.inputs[0].final_script_witness = Some(witness);
+        // A 2-of-3 multisig input signed by one cosigner.
+        psbt.inputs[1].witness_utxo = Some(utxo(witness_script.to_p2wsh().unwrap()));
+        psbt.inputs[1].witness_script = Some(witness_script.clone());
+        psbt.inputs[1].partial_sigs.insert(keys[1], sign(&secret_keys[1]));
+        // An input without its UTXO.
+        psbt.inputs[2].partial_sigs.insert(keys[2], sign(&secret_keys[2]));
+
+        let expected = vec![
+            InputReadiness::Finalized,
+            InputReadiness::MissingFinalScript { has_partial_sigs: 1, needs: Some(2) },
+            InputReadiness::MissingUtxoInfo,
+        ];
+        assert_eq!(psbt.extraction_readiness(), expected);
+
+        let err = psbt.clone().extract_tx().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "inputs not ready for extraction: 1 (not finalized, has 1 of 2 signatures), \
+             2 (missing the UTXO being spent)"
+        );
+        match err {
+            ExtractTxError::NotReady { inputs, psbt: returned } => {
+                assert_eq!(inputs, expected);
+                assert_eq!(returned, psbt);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            psbt.clone().extract_tx_with_fee_rate_limit(FeeRate::MAX),
+            Err(ExtractTxError::NotReady { .. })
+        ));
+
+        // Without a witness script the number of signatures needed is unknown.
+        let mut unknown = psbt.clone();
+        unknown.inputs[1].witness_script = None;
+        assert_eq!(
+            unknown.extraction_readiness()[1],
+            InputReadiness::MissingFinalScript { has_partial_sigs: 1, needs: None }
+        );
+
+        // Finalizing the remaining inputs makes the transaction extractable.
+        let mut witness = Witness::new();
+        witness.push([]);
+        witness.push(sign(&secret_keys[0]).to_vec());
+        witness.push(sign(&secret_keys[1]).to_vec());
+        witness.push(witness_script.as_bytes());
+        psbt.inputs[1].final_script_witness = Some(witness);
+        psbt.inputs[2].witness_utxo =
+            Some(utxo(ScriptBuf::new_p2wpkh(keys[2].wpubkey_hash().unwrap())));
+        let mut witness = Witness::new();
+        witness.push(sign(&secret_keys[2]).to_vec());
+        witness.push(keys[2].to_vec());
+        psbt.inputs[2].final_script_witness = Some(witness);
+        assert!(psbt.extraction_readiness().iter().all(|r| *r == InputReadiness::Finalized));
+        assert!(psbt.extract_tx().is_ok());
+    }
+
+    #[test]
+    fn psbt_estimated_weight() {
+        use crate::address::script_pubkey::{ScriptBufExt as _, ScriptExt as _};
+
+        let secp = Secp256k1::new();
+        let priv_keys = (1..=2u8)
+            .map(|i| {
+                let sk = secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap();
+                PrivateKey::new(sk, NetworkKind::Test)
+            })
+            .collect::<Vec<_>>();
+        let keys = priv_keys.iter().map(|sk| sk.public_key(&secp)).collect::<Vec<PublicKey>>();
+        let key_map = keys.iter().copied().zip(priv_keys).collect::<BTreeMap<_, _>>();
+        let internal_key = XOnlyPublicKey::from(keys[0].inner);
+        let multisig = ScriptBuf::new_multisig(2, &keys).unwrap();
+        let p2wpkh = ScriptBuf::new_p2wpkh(keys[0].wpubkey_hash().unwrap());
+        let key_source = (bip32::Fingerprint::default(), DerivationPath::default());
+
+        // The script pubkey, redeem script, witness script, number of ECDSA signatures and the
+        // weight of a signature byte of each input type.
+        let cases = [
+            (ScriptBuf::new_p2pkh(keys[0].pubkey_hash()), None, None, 1, 4),
+            (p2wpkh.clone(), None, None, 1, 1),
+            (p2wpkh.to_p2sh().unwrap(), Some(p2wpkh), None, 1, 1),
+            (multisig.to_p2wsh().unwrap(), None, Some(multisig.clone()), 2, 1),
+            (multisig.to_p2sh().unwrap(), Some(multisig), None, 2, 4),
+            (ScriptBuf::new_p2tr(&secp, internal_key, None), None, None, 0, 0),
+        ];
+        for (script_pubkey, redeem_script, witness_script, sigs, sig_byte_weight) in cases {
+            let tx = Transaction {
+                version: transaction::Version::TWO,
+                lock_time: absolute::LockTime::ZERO,
+                input: vec![TxIn::EMPTY_COINBASE],
+                output: vec![TxOut {
+                    value: Amount::from_sat_u32(10_000),
+                    script_pubkey: ScriptBuf::new(),
+                }],
+            };
+            let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+            let input = &mut psbt.inputs[0];
+            input.witness_utxo = Some(TxOut { value: Amount::from_sat_u32(20_000), script_pubkey });
+            input.redeem_script = redeem_script;
+            input.witness_script = witness_script;
+            input.bip32_derivation =
+                keys.iter().map(|key| (key.inner, key_source.clone())).collect();
+            input.tap_internal_key = Some(internal_key);
+            input.tap_key_origins.insert(internal_key, (vec![], key_source.clone()));
+
+            let max_weight = psbt.estimated_weight(MAX_ECDSA_SIG_LEN).unwrap();
+            let weight = psbt.estimated_weight(MAX_ECDSA_SIG_LEN - 1).unwrap();
+            assert_eq!(max_weight - weight, Weight::from_wu(sigs * sig_byte_weight));
+            assert_eq!(
+                psbt.estimated_vsize(MAX_ECDSA_SIG_LEN).unwrap(),
+                max_weight.to_vbytes_ceil() as usize
+            );
+            assert_eq!(
+                psbt.fee_rate().unwrap(),
+                psbt.estimated_fee_rate(MAX_ECDSA_SIG_LEN).unwrap()
+            );
+
+            psbt.sign(&key_map, &secp).unwrap();
+            psbt.finalize().unwrap();
+            let actual = psbt.extract_tx().unwrap().weight();
+            // Exact for Schnorr signatures and within one byte per ECDSA signature.
+            assert!(actual <= max_weight);
+            let tolerance = Weight::from_wu(sigs * sig_byte_weight);
+            assert!(actual >= weight - tolerance && actual <= weight + tolerance);
+        }
+    }
+
+    #[test]
+    fn psbt_estimated_weight_taproot_script_path() {
+        use crate::address::script_pubkey::{BuilderExt as _, ScriptBufExt as _};
+        use crate::opcodes::all::OP_CHECKSIG;
+        use crate::taproot::{LeafVersion, TaprootBuilder};
+
+        let secp = Secp256k1::new();
+        let [internal_key, key] = [1u8, 2].map(|i| {
+            let sk = secp256k1::SecretKey::from_byte_array(&[i; 32]).unwrap();
+            XOnlyPublicKey::from(secp256k1::PublicKey::from_secret_key(&secp, &sk))
+        });
+        let script = script::Builder::new().push_x_only_key(key).push_opcode(OP_CHECKSIG);
+        let script_ver = (script.into_script(), LeafVersion::TapScript);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, script_ver.0.clone())
+            .unwrap()
+            .finalize(&secp, internal_key)
+            .unwrap();
+
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE],
+            output: vec![TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new() }],
+        };
+        let key_path = transaction::predict_weight(
+            [InputWeightPrediction::P2TR_KEY_DEFAULT_SIGHASH],
+            tx.script_pubkey_lens(),
+        );
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        let input = &mut psbt.inputs[0];
+        input.witness_utxo = Some(TxOut {
+            value: Amount::from_sat_u32(10_000),
+            script_pubkey: ScriptBuf::new_p2tr(&secp, internal_key, spend_info.merkle_root()),
+        });
+        input.tap_internal_key = Some(internal_key);
+        input.tap_merkle_root = spend_info.merkle_root();
+        assert_eq!(psbt.estimated_weight(MAX_ECDSA_SIG_LEN).unwrap(), key_path);
+
+        // The input may be spent through its leaf script.
+        let control_block = spend_info.control_block(&script_ver).unwrap();
+        psbt.inputs[0].tap_scripts.insert(control_block, script_ver);
+        assert!(matches!(
+            psbt.estimated_weight(MAX_ECDSA_SIG_LEN),
+            Err(EstimateError::UnknownInputWeight(0))
+        ));
+
+        // A key path signature settles the spending path.
+        let signature = secp256k1::schnorr::Signature::from_byte_array([1; 64]);
+        let signature = taproot::Signature { signature, sighash_type: TapSighashType::Default };
+        psbt.inputs[0].tap_key_sig = Some(signature);
+        assert_eq!(psbt.estimated_weight(MAX_ECDSA_SIG_LEN).unwrap(), key_path);
+    }
+
     #[test]
     fn serialize_then_deserialize_output() {
         let secp = &Secp256k1::new();
@@ -1522,6 +3164,155 @@ fn serialize_then_deserialize_global() {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn join() {
+        let mut ours = psbt_with_values(100_000, 90_000);
+        ours.unsigned_tx.lock_time = absolute::LockTime::from_consensus(800_000);
+        ours.outputs.push(Output::default());
+        ours.unknown.insert(raw::Key { type_value: 0xf0, key_data: vec![0] }, vec![0]);
+
+        let mut theirs = psbt_with_values(200_000, 150_000);
+        theirs.unsigned_tx.input[0].previous_output.vout = 1;
+        theirs.unsigned_tx.lock_time = absolute::LockTime::from_consensus(800_001);
+        let mut output = Output::default();
+        output.unknown.insert(raw::Key { type_value: 0xf0, key_data: vec![1] }, vec![1]);
+        theirs.outputs.push(output.clone());
+        theirs.unknown.insert(raw::Key { type_value: 0xf0, key_data: vec![1] }, vec![1]);
+
+        let joined = ours.clone().join(theirs.clone()).unwrap();
+
+        let tx = &joined.unsigned_tx;
+        assert_eq!(tx.lock_time, absolute::LockTime::from_consensus(800_001));
+        assert_eq!(tx.input.len(), 2);
+        assert_eq!(tx.input[0], ours.unsigned_tx.input[0]);
+        assert_eq!(tx.input[1], theirs.unsigned_tx.input[0]);
+        assert_eq!(
+            tx.output,
+            [ours.unsigned_tx.output[0].clone(), theirs.unsigned_tx.output[0].clone()]
+        );
+        assert_eq!(joined.inputs, [ours.inputs[0].clone(), theirs.inputs[0].clone()]);
+        assert_eq!(joined.outputs, [Output::default(), output]);
+        assert_eq!(joined.unknown.len(), 2);
+        assert_eq!(joined.fee().unwrap(), Amount::from_sat_u32(60_000));
+
+        // The result is a valid PSBT.
+        assert_eq!(Psbt::deserialize(&joined.serialize()).unwrap(), joined);
+
+        // A lock time of zero does not constrain the result.
+        let mut zero = theirs.clone();
+        zero.unsigned_tx.lock_time = absolute::LockTime::ZERO;
+        let joined = ours.clone().join(zero).unwrap();
+        assert_eq!(joined.unsigned_tx.lock_time, ours.unsigned_tx.lock_time);
+    }
+
+    #[test]
+    fn join_errors() {
+        let ours = psbt_with_values(100_000, 90_000);
+        let outpoint = ours.unsigned_tx.input[0].previous_output;
+        assert_eq!(ours.clone().join(ours.clone()), Err(JoinError::DuplicateInput(outpoint)));
+
+        let mut theirs = ours.clone();
+        theirs.unsigned_tx.input[0].previous_output.vout = 1;
+        let their_outpoint = theirs.unsigned_tx.input[0].previous_output;
+
+        let mut signed = theirs.clone();
+        signed.inputs[0].final_script_witness = Some(Witness::from_slice(&[[0x01]]));
+        assert_eq!(ours.clone().join(signed), Err(JoinError::SignedInput(their_outpoint)));
+
+        let mut version = theirs.clone();
+        version.unsigned_tx.version = transaction::Version::ONE;
+        assert!(matches!(ours.clone().join(version), Err(JoinError::VersionMismatch { .. })));
+
+        let mut ours = ours;
+        ours.unsigned_tx.lock_time = absolute::LockTime::from_consensus(800_000);
+        theirs.unsigned_tx.lock_time = absolute::LockTime::from_consensus(1_700_000_000);
+        assert!(matches!(ours.join(theirs), Err(JoinError::LockTimeUnitMismatch { .. })));
+    }
+
+    #[test]
+    fn serialize_to_writer() {
+        let mut psbt = psbt_with_values(100_000, 90_000);
+        // Vary the length so that every remainder is hit when base64 encoding.
+        for i in 0..3 {
+            let key = raw::Key { type_value: 0xf0, key_data: vec![i] };
+            psbt.unknown.insert(key, vec![i; usize::from(i)]);
+
+            let mut buf = Vec::new();
+            let len = psbt.serialize_to_writer(&mut buf).unwrap();
+            assert_eq!(buf, psbt.serialize());
+            assert_eq!(len, buf.len());
+
+            #[cfg(feature = "base64")]
+            {
+                let mut base64 = Vec::new();
+                let len = psbt.serialize_base64_to_writer(&mut base64).unwrap();
+                assert_eq!(base64, psbt.to_string().as_bytes());
+                assert_eq!(len, base64.len());
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_from_reader() {
+        let mut psbt = psbt_with_values(100_000, 90_000);
+        psbt.outputs.push(Output::default());
+
+        // Data following the PSBT is left in the reader.
+        let mut buf = psbt.serialize();
+        buf.extend_from_slice(b"trailing");
+        let mut reader = buf.as_slice();
+        assert_eq!(Psbt::deserialize_from_reader(&mut reader).unwrap(), psbt);
+        assert_eq!(reader, b"trailing");
+
+        let mut reader = &b"psbx\xff"[..];
+        assert!(matches!(Psbt::deserialize_from_reader(&mut reader), Err(Error::InvalidMagic)));
+
+        let pair =
+            raw::Pair { key: raw::Key { type_value: 0xf0, key_data: vec![] }, value: vec![] };
+        let mut buf = psbt.serialize();
+        // Insert the same global pair twice after the magic and separator.
+        buf.splice(5..5, pair.serialize().into_iter().chain(pair.serialize()));
+        let mut reader = buf.as_slice();
+        assert!(matches!(Psbt::deserialize_from_reader(&mut reader), Err(Error::DuplicateKey(_))));
+    }
+
+    #[test]
+    fn serialize_large_psbt_to_hash_engine() {
+        // A non-witness UTXO of over 3MB.
+        let output = TxOut {
+            value: Amount::ONE_SAT,
+            script_pubkey: ScriptBuf::from_hex("a9143545e6e33b832c47050f24d3eeb93c9c03948bc787")
+                .unwrap(),
+        };
+        let mut psbt = psbt_with_values(100_000, 90_000);
+        psbt.outputs.push(Output::default());
+        psbt.inputs[0].non_witness_utxo = Some(Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![output; 100_000],
+        });
+
+        // Hash the output rather than collect it.
+        let mut engine = sha256::Hash::engine();
+        let len = psbt.serialize_to_writer(&mut engine).unwrap();
+        let serialized = psbt.serialize();
+        assert_eq!(len, serialized.len());
+        assert_eq!(sha256::Hash::from_engine(engine), sha256::Hash::hash(&serialized));
+
+        #[cfg(feature = "base64")]
+        {
+            let mut engine = sha256::Hash::engine();
+            let len = psbt.serialize_base64_to_writer(&mut engine).unwrap();
+            let base64 = psbt.to_string();
+            assert_eq!(len, base64.len());
+            assert_eq!(sha256::Hash::from_engine(engine), sha256::Hash::hash(base64.as_bytes()));
+        }
+
+        let mut reader = serialized.as_slice();
+        assert_eq!(Psbt::deserialize_from_reader(&mut reader).unwrap(), psbt);
+    }
+
     #[test]
     fn serialize_then_deserialize_psbtkvpair() {
         let expected = raw::Pair {
@@ -1576,7 +3367,7 @@ fn serde_psbt() {
             }],
         };
         let unknown: BTreeMap<raw::Key, Vec<u8>> =
-            vec![(raw::Key { type_value: 1, key_data: vec![0, 1] }, vec![3, 4, 5])]
+            vec![(raw::Key { type_value: 9, key_data: vec![0, 1] }, vec![3, 4, 5])]
                 .into_iter()
                 .collect();
         let key_source = ("deadbeef".parse().unwrap(), "0'/1".parse().unwrap());
@@ -1631,10 +3422,10 @@ fn serde_psbt() {
                     )].into_iter().collect(),
                     bip32_derivation: keypaths.clone(),
                     final_script_witness: Some(Witness::from_slice(&[vec![1, 3], vec![5]])),
-                    ripemd160_preimages: vec![(ripemd160::Hash::hash(&[]), vec![1, 2])].into_iter().collect(),
-                    sha256_preimages: vec![(sha256::Hash::hash(&[]), vec![1, 2])].into_iter().collect(),
-                    hash160_preimages: vec![(hash160::Hash::hash(&[]), vec![1, 2])].into_iter().collect(),
-                    hash256_preimages: vec![(sha256d::Hash::hash(&[]), vec![1, 2])].into_iter().collect(),
+                    ripemd160_preimages: vec![(ripemd160::Hash::hash(&[1, 2]), vec![1, 2])].into_iter().collect(),
+                    sha256_preimages: vec![(sha256::Hash::hash(&[1, 2]), vec![1, 2])].into_iter().collect(),
+                    hash160_preimages: vec![(hash160::Hash::hash(&[1, 2]), vec![1, 2])].into_iter().collect(),
+                    hash256_preimages: vec![(sha256d::Hash::hash(&[1, 2]), vec![1, 2])].into_iter().collect(),
                     proprietary: proprietary.clone(),
                     unknown: unknown.clone(),
                     ..Default::default()
@@ -1654,6 +3445,32 @@ fn serde_psbt() {
         assert_eq!(psbt, decoded);
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_psbt_base64() {
+        use serde_test::{assert_tokens, Configure as _, Token};
+
+        // BIP-174 test vector created by Bitcoin Core.
+        let base64 = "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+        let psbt = base64.parse::<Psbt>().unwrap();
+
+        let json = serde_json::to_string(&psbt).unwrap();
+        assert_eq!(json, format!("\"{}\"", base64));
+        assert_eq!(serde_json::from_str::<Psbt>(&json).unwrap(), psbt);
+
+        assert_tokens(&psbt.clone().readable(), &[Token::Str(base64)]);
+
+        // Binary formats use the BIP-174 serialization, bincode prefixes it with the length.
+        let bincode = bincode::serialize(&psbt).unwrap();
+        assert_eq!(bincode[8..], psbt.serialize());
+        assert_eq!(bincode::deserialize::<Psbt>(&bincode).unwrap(), psbt);
+
+        let err = serde_json::from_str::<Psbt>("\"cHNidP8B!\"").unwrap_err();
+        assert!(err.to_string().contains("error in PSBT base64 encoding"));
+        let err = serde_json::from_str::<Psbt>("\"cHNidP8A\"").unwrap_err();
+        assert!(err.to_string().contains("error in internal PSBT data structure"));
+    }
+
     mod bip_vectors {
         use super::*;
         use crate::psbt::map::Map;
@@ -2047,6 +3864,119 @@ fn valid_psbt_vectors() {
         }
     }
 
+    mod musig2_fields {
+        use super::*;
+
+        // The points G, 2G and 3G stand in for the participant and aggregate keys, the public
+        // nonces are two participant keys concatenated. Parsing doesn't check the aggregation.
+        const ALICE: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        const BOB: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        const AGGREGATE: &str =
+            "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+        const LEAF_HASH: &str = "0707070707070707070707070707070707070707070707070707070707070707";
+
+        const GLOBAL: &str = "01005e02000000019bd48765230bf9a72e662001f972556e54f0c6f97feb56bcb5600d817f6995260100000000ffffffff0148e6052a01000000225120030da4fce4f7db28c2cb2951631e003713856597fe963882cb500e68112cca6300000000";
+        const WITNESS_UTXO: &str = "01012b00f2052a01000000225120c2247efbfd92ac47f6f40b8d42d169175a19fa9fa10e4a25d7f35eb4dd85b692";
+
+        const IN_PARTICIPANT_PUBKEYS: &str = "221a02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        const IN_PUB_NONCE: &str = "431b0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+        const IN_PUB_NONCE_LEAF: &str = "631b02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee502f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f907070707070707070707070707070707070707070707070707070707070707074202c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee50279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        const IN_PARTIAL_SIG: &str = "431c0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9200303030303030303030303030303030303030303030303030303030303030303";
+        const IN_PARTIAL_SIG_LEAF: &str = "631c02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee502f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f90707070707070707070707070707070707070707070707070707070707070707200404040404040404040404040404040404040404040404040404040404040404";
+        const OUT_PARTICIPANT_PUBKEYS: &str = "220802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+
+        /// Returns the hex of a PSBT with the given input and output key-value pairs.
+        fn psbt(input: &[&str], output: &[&str]) -> String {
+            let mut s = format!("70736274ff{}00{}", GLOBAL, WITNESS_UTXO);
+            s.extend(input.iter().copied());
+            s.push_str("00");
+            s.extend(output.iter().copied());
+            s.push_str("00");
+            s
+        }
+
+        fn key(s: &str) -> secp256k1::PublicKey { s.parse().unwrap() }
+
+        #[test]
+        fn valid() {
+            let input = [
+                IN_PARTICIPANT_PUBKEYS,
+                IN_PUB_NONCE,
+                IN_PUB_NONCE_LEAF,
+                IN_PARTIAL_SIG,
+                IN_PARTIAL_SIG_LEAF,
+            ];
+            let hex = psbt(&input, &[OUT_PARTICIPANT_PUBKEYS]);
+            let psbt = hex_psbt(&hex).unwrap();
+
+            let (alice, bob, aggregate) = (key(ALICE), key(BOB), key(AGGREGATE));
+            let leaf_hash = Some(LEAF_HASH.parse::<TapLeafHash>().unwrap());
+            let nonce = |s: String| Musig2PubNonce::from_hex(&s).unwrap();
+            let sig = |s: &str| Musig2PartialSig::from_hex(&s.repeat(32)).unwrap();
+
+            let input = &psbt.inputs[0];
+            assert_eq!(
+                input.musig2_participant_pubkeys,
+                BTreeMap::from([(aggregate, vec![alice, bob])])
+            );
+            assert_eq!(
+                input.musig2_pub_nonces,
+                BTreeMap::from([
+                    ((alice, aggregate, None), nonce(format!("{}{}", ALICE, BOB))),
+                    ((bob, aggregate, leaf_hash), nonce(format!("{}{}", BOB, ALICE))),
+                ])
+            );
+            assert_eq!(
+                input.musig2_partial_sigs,
+                BTreeMap::from([
+                    ((alice, aggregate, None), sig("03")),
+                    ((bob, aggregate, leaf_hash), sig("04")),
+                ])
+            );
+            assert_eq!(
+                psbt.outputs[0].musig2_participant_pubkeys,
+                BTreeMap::from([(aggregate, vec![alice, bob])])
+            );
+
+            assert_eq!(psbt.serialize_hex(), hex);
+        }
+
+        #[test]
+        fn invalid() {
+            assert!(hex_psbt(&psbt(&[], &[])).is_ok());
+
+            let inputs = [
+                // The aggregate key is x-only.
+                "211af9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+                // There are no participant keys.
+                "221a02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f900",
+                // The last participant key is truncated.
+                "221a02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9410279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709e",
+                // The public nonce is 65 bytes.
+                "431b0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9410279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709e",
+                // The leaf hash of the public nonce key is 31 bytes.
+                "621b0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f907070707070707070707070707070707070707070707070707070707070707420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+                // The partial signature is 33 bytes.
+                "431c0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f921030303030303030303030303030303030303030303030303030303030303030303",
+            ];
+            for pair in inputs {
+                assert!(hex_psbt(&psbt(&[pair], &[])).is_err(), "input pair {}", pair);
+            }
+
+            let outputs = [
+                // The aggregate key is x-only.
+                "2108f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9420279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+                // There are no participant keys.
+                "220802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f900",
+                // The last participant key is truncated.
+                "220802f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9410279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179802c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709e",
+            ];
+            for pair in outputs {
+                assert!(hex_psbt(&psbt(&[], &[pair])).is_err(), "output pair {}", pair);
+            }
+        }
+    }
+
     #[test]
     fn serialize_and_deserialize_preimage_psbt() {
         // create a sha preimage map
@@ -2173,6 +4103,74 @@ fn serialize_and_deserialize_proprietary() {
         assert!(!rtt.proprietary.is_empty());
     }
 
+    #[test]
+    fn proprietary_key_encoding() {
+        // `0xFC <compact size prefix len> <prefix> <compact size subtype> <key>`
+        let key = raw::ProprietaryKey::new(b"abc", 0xFC, [0x01]);
+        assert_eq!(key.to_key().type_value, 0xFC);
+        assert_eq!(key.to_key().key_data, hex!("03616263fc01"));
+        let key = raw::ProprietaryKey::new(b"abc", 0xFD, []);
+        assert_eq!(key.to_key().key_data, hex!("03616263fdfd00"));
+        let key = raw::ProprietaryKey::new(b"abc", 0x1_0000, b"k");
+        assert_eq!(key.to_key().key_data, hex!("03616263fe000001006b"));
+        let key = raw::ProprietaryKey::new([], 0x1_0000_0000, []);
+        assert_eq!(key.to_key().key_data, hex!("00ff0000000001000000"));
+
+        for key in [
+            raw::ProprietaryKey::new(b"abc", 0xFC, [0x01]),
+            raw::ProprietaryKey::new(b"abc", 0xFD, []),
+            raw::ProprietaryKey::new(b"abc", 0x1_0000, b"k"),
+            raw::ProprietaryKey::new([], 0x1_0000_0000, []),
+        ] {
+            assert_eq!(raw::ProprietaryKey::try_from(key.to_key()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn proprietary_accessors() {
+        let mut psbt = hex_psbt("70736274ff0100a00200000002ab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40000000000feffffffab0949a08c5af7c49b8212f417e2f15ab3f5c33dcf153821a8139f877a5b7be40100000000feffffff02603bea0b000000001976a914768a40bbd740cbe81d988e71de2a4d5c71396b1d88ac8e240000000000001976a9146f4620b553fa095e721b9ee0efe9fa039cca459788ac000000000001076a47304402204759661797c01b036b25928948686218347d89864b719e1f7fcf57d1e511658702205309eabf56aa4d8891ffd111fdf1336f3a29da866d7f8486d75546ceedaf93190121035cdc61fc7ba971c0b501a646a2a83b102cb43881217ca682dc86e2d73fa882920001012000e1f5050000000017a9143545e6e33b832c47050f24d3eeb93c9c03948bc787010416001485d13537f2e265405a34dbafa9e3dda01fb82308000000").unwrap();
+
+        // Prefixes where one is a prefix of the other, with overlapping subtypes.
+        assert_eq!(psbt.proprietary_insert(b"ab", 1, b"", vec![1]), None);
+        assert_eq!(psbt.proprietary_insert(b"ab", 1, b"key", vec![2]), None);
+        assert_eq!(psbt.proprietary_insert(b"ab", 0xFFFF, b"", vec![3]), None);
+        assert_eq!(psbt.proprietary_insert(b"abc", 1, b"", vec![4]), None);
+        assert_eq!(psbt.proprietary_insert(b"a", 2, b"", vec![5]), None);
+        assert_eq!(psbt.proprietary_insert(b"ab", 1, b"", vec![6]), Some(vec![1]));
+
+        assert_eq!(psbt.proprietary_get(b"ab", 1, b""), Some(&[6][..]));
+        assert_eq!(psbt.proprietary_get(b"ab", 1, b"key"), Some(&[2][..]));
+        assert_eq!(psbt.proprietary_get(b"abc", 1, b""), Some(&[4][..]));
+        assert_eq!(psbt.proprietary_get(b"ab", 2, b""), None);
+        assert_eq!(psbt.proprietary_get(b"b", 1, b""), None);
+
+        let range = psbt
+            .proprietary_range(b"ab")
+            .map(|(key, value)| (key.subtype, key.key.clone(), value.to_vec()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            range,
+            vec![(1, vec![], vec![6]), (1, b"key".to_vec(), vec![2]), (0xFFFF, vec![], vec![3])]
+        );
+        assert_eq!(psbt.proprietary_range(b"a").count(), 1);
+        assert_eq!(psbt.proprietary_range(b"abcd").count(), 0);
+
+        psbt.inputs[0].proprietary_insert(b"ab", 1, b"", vec![7]);
+        psbt.inputs[1].proprietary_insert(b"ab", 1, b"", vec![8]);
+        psbt.inputs[1].proprietary_insert(b"xyz", 1, b"", vec![9]);
+        psbt.outputs[1].proprietary_insert(b"ab", 0xFD, b"\x00", vec![]);
+        assert_eq!(psbt.inputs[0].proprietary_get(b"ab", 1, b""), Some(&[7][..]));
+        assert_eq!(psbt.inputs[1].proprietary_range(b"ab").count(), 1);
+        assert_eq!(psbt.outputs[0].proprietary_range(b"ab").count(), 0);
+        assert_eq!(psbt.outputs[1].proprietary_get(b"ab", 0xFD, b"\x00"), Some(&[][..]));
+
+        // All proprietary keys survive a round trip exactly.
+        let serialized = psbt.serialize();
+        let rtt = Psbt::deserialize(&serialized).unwrap();
+        assert_eq!(rtt, psbt);
+        assert_eq!(rtt.serialize(), serialized);
+    }
+
     // PSBTs taken from BIP 174 test vectors.
     #[test]
     fn combine_psbts() {
@@ -2439,6 +4437,89 @@ fn xonly_hashmap_can_sign_taproot() {
         assert_eq!(signing_keys[&0], SigningKeys::Schnorr(vec![internal_key]));
     }
 
+    #[test]
+    #[cfg(feature = "rand-std")]
+    fn sign_taproot_script_path() {
+        use crate::address::script_pubkey::BuilderExt as _;
+        use crate::opcodes::all::{OP_CHECKSIG, OP_CHECKSIGVERIFY};
+        use crate::taproot::{LeafVersion, TaprootBuilder};
+
+        let (_, internal_pk, secp) = gen_keys();
+        let (priv_key, pk, _) = gen_keys();
+        let (_, other_pk, _) = gen_keys();
+        let internal_key: XOnlyPublicKey = internal_pk.inner.into();
+        let key: XOnlyPublicKey = pk.inner.into();
+        let other_key: XOnlyPublicKey = other_pk.inner.into();
+
+        let checksig = |k| script::Builder::new().push_x_only_key(k).push_opcode(OP_CHECKSIG);
+        let script = checksig(key).into_script();
+        let other_script = checksig(other_key).into_script();
+        // A leaf for `key` that is not in `tap_scripts`.
+        let unknown_script = script::Builder::new()
+            .push_x_only_key(key)
+            .push_opcode(OP_CHECKSIGVERIFY)
+            .into_script();
+
+        let leaf = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+        let other_leaf = TapLeafHash::from_script(&other_script, LeafVersion::TapScript);
+        let unknown_leaf = TapLeafHash::from_script(&unknown_script, LeafVersion::TapScript);
+
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, script.clone())
+            .unwrap()
+            .add_leaf(1, other_script.clone())
+            .unwrap()
+            .finalize(&secp, internal_key)
+            .unwrap();
+        let utxo = TxOut {
+            value: Amount::from_sat_u32(10_000),
+            script_pubkey: ScriptBuf::new_p2tr(&secp, internal_key, spend_info.merkle_root()),
+        };
+
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: locktime::absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE],
+            output: vec![TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new() }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx.clone()).unwrap();
+        let input = &mut psbt.inputs[0];
+        input.witness_utxo = Some(utxo.clone());
+        input.tap_internal_key = Some(internal_key);
+        input.tap_merkle_root = spend_info.merkle_root();
+        input.sighash_type = Some(TapSighashType::SinglePlusAnyoneCanPay.into());
+        for script in [script, other_script] {
+            let script_ver = (script, LeafVersion::TapScript);
+            input.tap_scripts.insert(spend_info.control_block(&script_ver).unwrap(), script_ver);
+        }
+        let key_source = (Fingerprint::default(), DerivationPath::default());
+        input.tap_key_origins.insert(key, (vec![leaf, unknown_leaf], key_source.clone()));
+        input.tap_key_origins.insert(other_key, (vec![other_leaf], key_source));
+
+        // Only the secret key for one of the leaves is available.
+        let mut key_map: BTreeMap<XOnlyPublicKey, PrivateKey> = BTreeMap::new();
+        key_map.insert(key, priv_key);
+
+        let signing_keys = psbt.sign(&key_map, &secp).unwrap();
+        assert_eq!(signing_keys[&0], SigningKeys::Schnorr(vec![key]));
+
+        let input = &psbt.inputs[0];
+        assert!(input.tap_key_sig.is_none());
+        assert_eq!(input.tap_script_sigs.len(), 1);
+        let sig = input.tap_script_sigs[&(key, leaf)];
+        assert_eq!(sig.sighash_type, TapSighashType::SinglePlusAnyoneCanPay);
+
+        let msg = SighashCache::new(&tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[utxo]),
+                leaf,
+                TapSighashType::SinglePlusAnyoneCanPay,
+            )
+            .unwrap();
+        secp.verify_schnorr(&sig.signature, msg.as_ref(), &key).unwrap();
+    }
+
     #[test]
     #[cfg(feature = "rand-std")]
     fn sign_psbt() {
@@ -2481,4 +4562,153 @@ fn sign_psbt() {
         assert_eq!(signing_keys.len(), 1);
         assert_eq!(signing_keys[&0], SigningKeys::Ecdsa(vec![pk]));
     }
+
+    #[test]
+    fn validate_utxos() {
+        use crate::address::script_pubkey::ScriptBufExt as _;
+        use crate::key::WPubkeyHash;
+
+        let prev_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(10_000),
+                script_pubkey: ScriptBuf::new_p2wpkh(WPubkeyHash::from_byte_array([1; 20])),
+            }],
+        };
+        let outpoint = OutPoint { txid: prev_tx.compute_txid(), vout: 0 };
+        let unsigned_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn { previous_output: outpoint, ..TxIn::EMPTY_COINBASE }],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(9_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx).unwrap();
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx.clone());
+        psbt.inputs[0].witness_utxo = Some(prev_tx.output[0].clone());
+        assert_eq!(psbt.validate_utxos(), Ok(()));
+
+        // The witness UTXO claims a different value than the previous transaction.
+        let mut mismatched_value = psbt.clone();
+        mismatched_value.inputs[0].witness_utxo.as_mut().unwrap().value =
+            Amount::from_sat_u32(100_000);
+        let err = mismatched_value.validate_utxos().unwrap_err();
+        assert_eq!(err, UtxoConsistencyError::WitnessUtxoMismatch { input: 0, outpoint });
+
+        // Signing refuses the input unless validation is skipped.
+        let secp = Secp256k1::new();
+        let key_map = BTreeMap::<PublicKey, PrivateKey>::new();
+        let (_, errors) = mismatched_value.sign(&key_map, &secp).unwrap_err();
+        assert_eq!(errors[&0], SignError::InconsistentUtxo(err));
+        assert!(mismatched_value.sign_without_utxo_validation(&key_map, &secp).is_ok());
+
+        // The previous transaction is not the one spent by the input.
+        let mut wrong_txid = psbt.clone();
+        let other_tx = Transaction { lock_time: absolute::LockTime::from_consensus(1), ..prev_tx };
+        let txid = other_tx.compute_txid();
+        wrong_txid.inputs[0].non_witness_utxo = Some(other_tx);
+        assert_eq!(
+            wrong_txid.validate_utxos(),
+            Err(UtxoConsistencyError::TxidMismatch { input: 0, outpoint, txid })
+        );
+
+        // The previous transaction has no output at the spent index.
+        let mut out_of_range = psbt;
+        out_of_range.unsigned_tx.input[0].previous_output.vout = 1;
+        let err = out_of_range.validate_utxos().unwrap_err();
+        assert_eq!(err.input_index(), 0);
+        assert!(matches!(err, UtxoConsistencyError::VoutOutOfRange { .. }));
+    }
+
+    #[test]
+    #[cfg(feature = "rand-std")]
+    fn sighash_msg() {
+        use crate::address::script_pubkey::BuilderExt as _;
+        use crate::opcodes::all::OP_CHECKSIG;
+        use crate::taproot::{LeafVersion, TaprootBuilder};
+
+        let (priv_key, pk, secp) = gen_keys();
+        let (_, other_pk, _) = gen_keys();
+        let key: XOnlyPublicKey = pk.inner.into();
+        let other_key: XOnlyPublicKey = other_pk.inner.into();
+
+        let script = script::Builder::new().push_x_only_key(key).push_opcode(OP_CHECKSIG);
+        let script = script.into_script();
+        let leaf = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, script.clone())
+            .unwrap()
+            .finalize(&secp, other_key)
+            .unwrap();
+
+        let unsigned_tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::EMPTY_COINBASE; 4],
+            output: vec![TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::new() }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx).unwrap();
+        let key_source = (Fingerprint::default(), DerivationPath::default());
+        let utxo = |script_pubkey| TxOut { value: Amount::from_sat_u32(10_000), script_pubkey };
+
+        // P2PKH, P2WPKH, P2TR key path and P2TR script path.
+        for (input, spk) in psbt.inputs[..2].iter_mut().zip([
+            ScriptBuf::new_p2pkh(pk.pubkey_hash()),
+            ScriptBuf::new_p2wpkh(pk.wpubkey_hash().unwrap()),
+        ]) {
+            input.witness_utxo = Some(utxo(spk));
+            input.bip32_derivation.insert(pk.inner, key_source.clone());
+        }
+        let input = &mut psbt.inputs[2];
+        input.witness_utxo = Some(utxo(ScriptBuf::new_p2tr(&secp, key, None)));
+        input.tap_internal_key = Some(key);
+        input.tap_key_origins.insert(key, (vec![], key_source.clone()));
+        let input = &mut psbt.inputs[3];
+        input.witness_utxo =
+            Some(utxo(ScriptBuf::new_p2tr(&secp, other_key, spend_info.merkle_root())));
+        input.tap_internal_key = Some(other_key);
+        input.tap_merkle_root = spend_info.merkle_root();
+        let script_ver = (script, LeafVersion::TapScript);
+        input.tap_scripts.insert(spend_info.control_block(&script_ver).unwrap(), script_ver);
+        input.tap_key_origins.insert(key, (vec![leaf], key_source));
+
+        let mut key_map = BTreeMap::new();
+        key_map.insert(pk, priv_key);
+        psbt.sign(&key_map, &secp).unwrap();
+
+        let mut cache = SighashCache::new(&psbt.unsigned_tx);
+        let msgs = (0..4)
+            .map(|i| psbt.sighash_msg(i, &mut cache, if i == 3 { Some(leaf) } else { None }))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert!(matches!(msgs[0], PsbtSighashMsg::Legacy(_)));
+        assert!(matches!(msgs[1], PsbtSighashMsg::SegwitV0(_)));
+        assert!(matches!(msgs[2], PsbtSighashMsg::TapKeySpend(_)));
+        assert!(matches!(msgs[3], PsbtSighashMsg::TapScriptSpend(_)));
+
+        for (msg, input) in msgs.iter().zip(&psbt.inputs[..2]) {
+            pk.verify(&secp, (*msg).into(), input.partial_sigs[&pk]).unwrap();
+        }
+        let output_key = key.tap_tweak(&secp, None).0.to_inner();
+        let sig = psbt.inputs[2].tap_key_sig.unwrap();
+        secp.verify_schnorr(&sig.signature, Message::from(msgs[2]).as_ref(), &output_key).unwrap();
+        let sig = psbt.inputs[3].tap_script_sigs[&(key, leaf)];
+        secp.verify_schnorr(&sig.signature, Message::from(msgs[3]).as_ref(), &key).unwrap();
+
+        // Without a leaf hash the key path sighash is yielded for the last input.
+        let key_spend = psbt.sighash_msg(3, &mut cache, None).unwrap();
+        assert!(matches!(key_spend, PsbtSighashMsg::TapKeySpend(_)));
+        let sighashes = psbt.iter_sighashes().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(sighashes, [msgs[0], msgs[1], msgs[2], key_spend]);
+
+        assert!(matches!(
+            psbt.sighash_msg(1, &mut cache, Some(leaf)),
+            Err(SignError::WrongSigningAlgorithm)
+        ));
+    }
 }
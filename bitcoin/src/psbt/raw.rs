@@ -91,7 +91,7 @@ pub(crate) fn decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
 
         let type_value = r.read_compact_size()?;
 
-        let mut key_data = Vec::with_capacity(key_byte_size as usize);
+        let mut key_data = Vec::with_capacity(encode::preallocation_capacity::<u8>(key_byte_size));
         for _ in 0..key_byte_size {
             key_data.push(Decodable::consensus_decode(r)?);
         }
@@ -100,17 +100,20 @@ pub(crate) fn decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
     }
 }
 
+impl Key {
+    pub(crate) fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = w.emit_compact_size(self.key_data.len() + 1)?;
+        len += w.emit_compact_size(self.type_value)?;
+        w.write_all(&self.key_data)?;
+        len += self.key_data.len();
+        Ok(len)
+    }
+}
+
 impl Serialize for Key {
     fn serialize(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        buf.emit_compact_size(self.key_data.len() + 1).expect("in-memory writers don't error");
-
-        buf.emit_compact_size(self.type_value).expect("in-memory writers don't error");
-
-        for key in &self.key_data {
-            key.consensus_encode(&mut buf).expect("in-memory writers don't error");
-        }
-
+        self.encode(&mut buf).expect("in-memory writers don't error");
         buf
     }
 }
@@ -118,9 +121,7 @@ fn serialize(&self) -> Vec<u8> {
 impl Serialize for Pair {
     fn serialize(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        buf.extend(self.key.serialize());
-        // <value> := <valuelen> <valuedata>
-        self.value.consensus_encode(&mut buf).unwrap();
+        self.encode(&mut buf).expect("in-memory writers don't error");
         buf
     }
 }
@@ -136,6 +137,12 @@ impl Pair {
     pub(crate) fn decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
         Ok(Pair { key: Key::decode(r)?, value: Decodable::consensus_decode(r)? })
     }
+
+    pub(crate) fn encode<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let len = self.key.encode(w)?;
+        // <value> := <valuelen> <valuedata>
+        Ok(len + self.value.consensus_encode(w)?)
+    }
 }
 
 impl<Subtype> Encodable for ProprietaryKey<Subtype>
@@ -168,6 +175,22 @@ fn consensus_decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, encode::Erro
     }
 }
 
+impl ProprietaryKey {
+    /// Constructs a new proprietary key from its `prefix`, `subtype` and `key` data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bitcoin::psbt::raw::ProprietaryKey;
+    ///
+    /// let key = ProprietaryKey::new(b"payjoin", 0x01, []);
+    /// assert_eq!(key.to_key().key_data, b"\x07payjoin\x01");
+    /// ```
+    pub fn new(prefix: impl AsRef<[u8]>, subtype: ProprietaryType, key: impl AsRef<[u8]>) -> Self {
+        ProprietaryKey { prefix: prefix.as_ref().to_vec(), subtype, key: key.as_ref().to_vec() }
+    }
+}
+
 impl<Subtype> ProprietaryKey<Subtype>
 where
     Subtype: Copy + From<u64> + Into<u64>,
@@ -11,26 +11,40 @@
 use internals::slice::SliceExt;
 use secp256k1::XOnlyPublicKey;
 
-use super::map::{Input, Map, Output, PsbtSighashType};
+use super::map::{Input, Map, Musig2PartialSig, Musig2PubNonce, Output, PsbtSighashType};
 use crate::bip32::{ChildNumber, Fingerprint, KeySource};
 use crate::consensus::encode::{self, deserialize_partial, serialize, Decodable, Encodable};
 use crate::crypto::key::PublicKey;
 use crate::crypto::{ecdsa, taproot};
 use crate::io::Write;
+use crate::locktime::absolute;
 use crate::prelude::{DisplayHex, String, Vec};
 use crate::psbt::{Error, Psbt};
 use crate::script::ScriptBuf;
 use crate::taproot::{
     ControlBlock, LeafVersion, TapLeafHash, TapNodeHash, TapTree, TaprootBuilder,
 };
-use crate::transaction::{Transaction, TxOut};
+use crate::transaction::{self, Transaction, TxOut, Txid};
 use crate::witness::Witness;
+use crate::{Amount, Sequence};
 
 /// A trait for serializing a value as raw data for insertion into PSBT
 /// key-value maps.
 pub(crate) trait Serialize {
     /// Serialize a value as raw data.
     fn serialize(&self) -> Vec<u8>;
+
+    /// Returns the length of the raw data, the same as `self.serialize().len()`.
+    fn serialized_len(&self) -> usize { self.serialize().len() }
+
+    /// Serialize a value as raw data into a writer, returning the number of bytes written.
+    ///
+    /// Values that may be large override this to avoid collecting the raw data in memory.
+    fn serialize_to<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let data = self.serialize();
+        w.write_all(&data)?;
+        Ok(data.len())
+    }
 }
 
 /// A trait for deserializing a value from raw data in PSBT key-value maps.
@@ -51,6 +65,10 @@ pub fn serialize(&self) -> Vec<u8> {
     }
 
     /// Serialize the PSBT into a writer.
+    ///
+    /// Keys and values are written straight into `w`, neither the PSBT nor any of its values are
+    /// serialized into a buffer first. Returns the number of bytes written, the output is the same
+    /// as [`Psbt::serialize`].
     pub fn serialize_to_writer(&self, w: &mut impl Write) -> io::Result<usize> {
         let mut written_len = 0;
 
@@ -63,26 +81,32 @@ fn write_all(w: &mut impl Write, data: &[u8]) -> io::Result<usize> {
         // separator
         written_len += write_all(w, &[0xff])?;
 
-        written_len += write_all(w, &self.serialize_map())?;
+        written_len += self.encode_map(w)?;
 
         for i in &self.inputs {
-            written_len += write_all(w, &i.serialize_map())?;
+            written_len += i.encode_map(w)?;
         }
 
         for i in &self.outputs {
-            written_len += write_all(w, &i.serialize_map())?;
+            written_len += i.encode_map(w)?;
         }
 
         Ok(written_len)
     }
 
     /// Deserialize a value from raw binary data.
-    pub fn deserialize(mut bytes: &[u8]) -> Result<Self, Error> {
-        Self::deserialize_from_reader(&mut bytes)
+    pub fn deserialize(mut bytes: &[u8]) -> Result<Self, Error> { Self::decode(&mut bytes) }
+
+    /// Deserialize a value from raw binary data read from a `Read` object.
+    ///
+    /// Performs the same checks as [`Psbt::deserialize`]. Reading stops after the last output
+    /// map, any data following the PSBT is left in the reader. The reader is not buffered, wrap
+    /// it in a buffered reader if individual reads are expensive.
+    pub fn deserialize_from_reader<R: io::Read + ?Sized>(r: &mut R) -> Result<Self, Error> {
+        Self::decode(&mut ByteBufReader { inner: r, byte: [0], buffered: false })
     }
 
-    /// Deserialize a value from raw binary data read from a `BufRead` object.
-    pub fn deserialize_from_reader<R: io::BufRead>(r: &mut R) -> Result<Self, Error> {
+    fn decode<R: io::BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
         const MAGIC_BYTES: &[u8] = b"psbt";
 
         let magic: [u8; 4] = Decodable::consensus_decode(r)?;
@@ -128,9 +152,53 @@ pub fn deserialize_from_reader<R: io::BufRead>(r: &mut R) -> Result<Self, Error>
         Ok(global)
     }
 }
+/// A [`io::BufRead`] adapter buffering at most a single byte of a reader.
+///
+/// Buffering any more could consume data following the PSBT from the reader.
+struct ByteBufReader<'a, R: io::Read + ?Sized> {
+    inner: &'a mut R,
+    byte: [u8; 1],
+    buffered: bool,
+}
+
+impl<R: io::Read + ?Sized> io::Read for ByteBufReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match buf.first_mut() {
+            Some(first) if self.buffered => {
+                *first = self.byte[0];
+                self.buffered = false;
+                Ok(1)
+            }
+            _ => self.inner.read(buf),
+        }
+    }
+}
+
+impl<R: io::Read + ?Sized> io::BufRead for ByteBufReader<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if !self.buffered {
+            self.buffered = self.inner.read(&mut self.byte)? == 1;
+        }
+        Ok(if self.buffered { &self.byte } else { &[] })
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if amount > 0 {
+            self.buffered = false;
+        }
+    }
+}
+
 impl_psbt_de_serialize!(Transaction);
 impl_psbt_de_serialize!(TxOut);
 impl_psbt_de_serialize!(Witness);
+impl_psbt_de_serialize!(u8);
+impl_psbt_de_serialize!(u32);
+impl_psbt_de_serialize!(Amount);
+impl_psbt_de_serialize!(Sequence);
+impl_psbt_de_serialize!(Txid);
+impl_psbt_de_serialize!(transaction::Version);
+impl_psbt_de_serialize!(absolute::LockTime);
 impl_psbt_hash_de_serialize!(ripemd160::Hash);
 impl_psbt_hash_de_serialize!(sha256::Hash);
 impl_psbt_hash_de_serialize!(TapLeafHash);
@@ -143,6 +211,13 @@ pub fn deserialize_from_reader<R: io::BufRead>(r: &mut R) -> Result<Self, Error>
 
 impl Serialize for ScriptBuf {
     fn serialize(&self) -> Vec<u8> { self.to_vec() }
+
+    fn serialized_len(&self) -> usize { self.len() }
+
+    fn serialize_to<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        w.write_all(self.as_bytes())?;
+        Ok(self.len())
+    }
 }
 
 impl Deserialize for ScriptBuf {
@@ -236,6 +311,13 @@ fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
 // partial sigs
 impl Serialize for Vec<u8> {
     fn serialize(&self) -> Vec<u8> { self.clone() }
+
+    fn serialized_len(&self) -> usize { self.len() }
+
+    fn serialize_to<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        w.write_all(self)?;
+        Ok(self.len())
+    }
 }
 
 impl Deserialize for Vec<u8> {
@@ -253,6 +335,66 @@ fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
     }
 }
 
+// MuSig2 participants, the compressed keys are concatenated
+impl Serialize for Vec<secp256k1::PublicKey> {
+    fn serialize(&self) -> Vec<u8> { self.iter().flat_map(|key| key.serialize()).collect() }
+}
+
+impl Deserialize for Vec<secp256k1::PublicKey> {
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        // A MuSig2 aggregate key always has at least one participant.
+        if bytes.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        bytes.chunks(secp256k1::constants::PUBLIC_KEY_SIZE).map(Deserialize::deserialize).collect()
+    }
+}
+
+// MuSig2 `<participant key>|<aggregate key>|<leaf hash>`, the leaf hash is omitted if `None`
+impl Serialize for (secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>) {
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 * secp256k1::constants::PUBLIC_KEY_SIZE + 32);
+        buf.extend(self.0.serialize());
+        buf.extend(self.1.serialize());
+        if let Some(leaf_hash) = self.2 {
+            buf.extend(leaf_hash.as_byte_array());
+        }
+        buf
+    }
+}
+
+impl Deserialize for (secp256k1::PublicKey, secp256k1::PublicKey, Option<TapLeafHash>) {
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        const KEY_SIZE: usize = secp256k1::constants::PUBLIC_KEY_SIZE;
+        if bytes.len() < 2 * KEY_SIZE {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let participant = Deserialize::deserialize(&bytes[..KEY_SIZE])?;
+        let aggregate = Deserialize::deserialize(&bytes[KEY_SIZE..2 * KEY_SIZE])?;
+        let leaf_hash = match &bytes[2 * KEY_SIZE..] {
+            [] => None,
+            leaf_hash => Some(Deserialize::deserialize(leaf_hash)?),
+        };
+        Ok((participant, aggregate, leaf_hash))
+    }
+}
+
+impl Serialize for Musig2PubNonce {
+    fn serialize(&self) -> Vec<u8> { self.to_vec() }
+}
+
+impl Deserialize for Musig2PubNonce {
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> { Ok(Self::try_from(bytes)?) }
+}
+
+impl Serialize for Musig2PartialSig {
+    fn serialize(&self) -> Vec<u8> { self.to_vec() }
+}
+
+impl Deserialize for Musig2PartialSig {
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> { Ok(Self::try_from(bytes)?) }
+}
+
 // Taproot related ser/deser
 impl Serialize for XOnlyPublicKey {
     fn serialize(&self) -> Vec<u8> { XOnlyPublicKey::serialize(self).to_vec() }
@@ -320,6 +462,14 @@ fn serialize(&self) -> Vec<u8> {
         buf.push(self.1.to_consensus());
         buf
     }
+
+    fn serialized_len(&self) -> usize { self.0.len() + 1 }
+
+    fn serialize_to<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        w.write_all(self.0.as_bytes())?;
+        w.write_all(&[self.1.to_consensus()])?;
+        Ok(self.0.len() + 1)
+    }
 }
 
 impl Deserialize for (ScriptBuf, LeafVersion) {
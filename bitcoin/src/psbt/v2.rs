@@ -0,0 +1,1042 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! Version 2 Partially Signed Bitcoin Transactions.
+//!
+//! Implementation of [BIP-370]. Instead of embedding an unsigned transaction a version 2 PSBT
+//! stores the transaction fields in the global, input and output maps. This allows inputs and
+//! outputs to be added after the PSBT has been created.
+//!
+//! [BIP-370]: <https://github.com/bitcoin/bips/blob/master/bip-0370.mediawiki>
+
+use core::fmt;
+
+use internals::{compact_size, ToU64 as _};
+use io::{BufRead, Write};
+
+use crate::bip32::{KeySource, Xpub};
+use crate::consensus::encode::{ReadExt as _, MAX_VEC_SIZE};
+use crate::consensus::Decodable;
+use crate::locktime::absolute::{self, Height, Time};
+use crate::prelude::{btree_map, BTreeMap, DisplayHex, String, Vec};
+use crate::psbt::map::{self, Map};
+use crate::psbt::serialize::{Deserialize, Serialize};
+use crate::psbt::{raw, Error, Input, Output, Psbt};
+use crate::transaction::{self, OutPoint, Transaction, TxIn, TxOut, Txid};
+use crate::{Amount, ScriptBuf, Sequence, Witness};
+
+/// Type: Transaction Version PSBT_GLOBAL_TX_VERSION = 0x02
+pub(in crate::psbt) const PSBT_GLOBAL_TX_VERSION: u64 = 0x02;
+/// Type: Fallback Locktime PSBT_GLOBAL_FALLBACK_LOCKTIME = 0x03
+pub(in crate::psbt) const PSBT_GLOBAL_FALLBACK_LOCKTIME: u64 = 0x03;
+/// Type: Input Count PSBT_GLOBAL_INPUT_COUNT = 0x04
+pub(in crate::psbt) const PSBT_GLOBAL_INPUT_COUNT: u64 = 0x04;
+/// Type: Output Count PSBT_GLOBAL_OUTPUT_COUNT = 0x05
+pub(in crate::psbt) const PSBT_GLOBAL_OUTPUT_COUNT: u64 = 0x05;
+/// Type: Transaction Modifiable Flags PSBT_GLOBAL_TX_MODIFIABLE = 0x06
+pub(in crate::psbt) const PSBT_GLOBAL_TX_MODIFIABLE: u64 = 0x06;
+
+/// Type: Previous TXID PSBT_IN_PREVIOUS_TXID = 0x0e
+pub(in crate::psbt) const PSBT_IN_PREVIOUS_TXID: u64 = 0x0e;
+/// Type: Spent Output Index PSBT_IN_OUTPUT_INDEX = 0x0f
+pub(in crate::psbt) const PSBT_IN_OUTPUT_INDEX: u64 = 0x0f;
+/// Type: Sequence Number PSBT_IN_SEQUENCE = 0x10
+pub(in crate::psbt) const PSBT_IN_SEQUENCE: u64 = 0x10;
+/// Type: Required Time-based Locktime PSBT_IN_REQUIRED_TIME_LOCKTIME = 0x11
+pub(in crate::psbt) const PSBT_IN_REQUIRED_TIME_LOCKTIME: u64 = 0x11;
+/// Type: Required Height-based Locktime PSBT_IN_REQUIRED_HEIGHT_LOCKTIME = 0x12
+pub(in crate::psbt) const PSBT_IN_REQUIRED_HEIGHT_LOCKTIME: u64 = 0x12;
+
+/// Type: Output Amount PSBT_OUT_AMOUNT = 0x03
+pub(in crate::psbt) const PSBT_OUT_AMOUNT: u64 = 0x03;
+/// Type: Output Script PSBT_OUT_SCRIPT = 0x04
+pub(in crate::psbt) const PSBT_OUT_SCRIPT: u64 = 0x04;
+
+/// Inputs can be added or removed, bit 0 of `PSBT_GLOBAL_TX_MODIFIABLE`.
+const TX_MODIFIABLE_INPUTS: u8 = 1 << 0;
+/// Outputs can be added or removed, bit 1 of `PSBT_GLOBAL_TX_MODIFIABLE`.
+const TX_MODIFIABLE_OUTPUTS: u8 = 1 << 1;
+/// An input has a `SIGHASH_SINGLE` signature, bit 2 of `PSBT_GLOBAL_TX_MODIFIABLE`.
+const TX_MODIFIABLE_SIGHASH_SINGLE: u8 = 1 << 2;
+
+/// A version 2 Partially Signed Transaction.
+///
+/// The transaction lock time is not stored directly, it is determined from the inputs' required
+/// lock times and the fallback lock time by [`PsbtV2::lock_time`]. Transactions are extracted by
+/// converting to version 0 with [`PsbtV2::to_v0`], which fills in the determined lock time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PsbtV2 {
+    /// The version of the transaction being created.
+    pub tx_version: transaction::Version,
+    /// The lock time to use if no inputs require a particular lock time.
+    pub fallback_lock_time: Option<absolute::LockTime>,
+    /// Flags indicating which parts of the transaction can be modified, if present.
+    pub tx_modifiable: Option<u8>,
+    /// A global map from extended public keys to the used key fingerprint and
+    /// derivation path as defined by BIP 32.
+    pub xpub: BTreeMap<Xpub, KeySource>,
+    /// Global proprietary key-value pairs.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
+    pub proprietary: BTreeMap<raw::ProprietaryKey, Vec<u8>>,
+    /// Unknown global key-value pairs.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_utils::btreemap_as_seq_byte_values"))]
+    pub unknown: BTreeMap<raw::Key, Vec<u8>>,
+
+    /// The key-value maps of the transaction inputs.
+    pub inputs: Vec<InputV2>,
+    /// The key-value maps of the transaction outputs.
+    pub outputs: Vec<OutputV2>,
+}
+
+/// A key-value map for an input of a version 2 PSBT.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InputV2 {
+    /// The txid of the transaction this input spends from.
+    pub previous_txid: Txid,
+    /// The index of the output this input spends.
+    pub spent_output_index: u32,
+    /// The sequence number of this input, if omitted it is `0xffffffff`.
+    pub sequence: Option<Sequence>,
+    /// The minimum time based lock time this input requires.
+    pub required_time_lock_time: Option<Time>,
+    /// The minimum height based lock time this input requires.
+    pub required_height_lock_time: Option<Height>,
+    /// The fields shared with version 0 PSBT inputs.
+    pub input: Input,
+}
+
+/// A key-value map for an output of a version 2 PSBT.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutputV2 {
+    /// The value of this output.
+    #[cfg_attr(feature = "serde", serde(with = "crate::amount::serde::as_sat"))]
+    pub amount: Amount,
+    /// The script of this output.
+    pub script_pubkey: ScriptBuf,
+    /// The fields shared with version 0 PSBT outputs.
+    pub output: Output,
+}
+
+impl PsbtV2 {
+    /// Constructs a new empty version 2 PSBT for a transaction with version `tx_version`.
+    pub fn new(tx_version: transaction::Version) -> Self {
+        PsbtV2 {
+            tx_version,
+            fallback_lock_time: None,
+            tx_modifiable: None,
+            xpub: Default::default(),
+            proprietary: Default::default(),
+            unknown: Default::default(),
+            inputs: vec![],
+            outputs: vec![],
+        }
+    }
+
+    /// Returns true if inputs can be added or removed.
+    pub fn inputs_modifiable(&self) -> bool { self.tx_modifiable_flag(TX_MODIFIABLE_INPUTS) }
+
+    /// Returns true if outputs can be added or removed.
+    pub fn outputs_modifiable(&self) -> bool { self.tx_modifiable_flag(TX_MODIFIABLE_OUTPUTS) }
+
+    /// Returns true if an input has a signature using `SIGHASH_SINGLE`.
+    pub fn has_sighash_single(&self) -> bool {
+        self.tx_modifiable_flag(TX_MODIFIABLE_SIGHASH_SINGLE)
+    }
+
+    fn tx_modifiable_flag(&self, flag: u8) -> bool {
+        self.tx_modifiable.map_or(false, |flags| flags & flag != 0)
+    }
+
+    /// Determines the lock time of the transaction as described in BIP-370.
+    ///
+    /// If no input requires a lock time the fallback lock time is used, or zero if there is none.
+    /// Otherwise the lock time type supported by every input that has a requirement is chosen,
+    /// preferring height over time if both are, and the maximum required value of that type is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// If the inputs have no lock time type in common.
+    pub fn lock_time(&self) -> Result<absolute::LockTime, IncompatibleLockTimeError> {
+        let mut constrained = false;
+        let mut height = Some(Height::ZERO);
+        let mut time = Some(Time::MIN);
+
+        for input in &self.inputs {
+            if input.required_height_lock_time.is_none() && input.required_time_lock_time.is_none()
+            {
+                continue;
+            }
+            constrained = true;
+            height = height.and_then(|h| input.required_height_lock_time.map(|r| h.max(r)));
+            time = time.and_then(|t| input.required_time_lock_time.map(|r| t.max(r)));
+        }
+
+        if !constrained {
+            return Ok(self.fallback_lock_time.unwrap_or(absolute::LockTime::ZERO));
+        }
+        match (height, time) {
+            (Some(height), _) => Ok(absolute::LockTime::Blocks(height)),
+            (None, Some(time)) => Ok(absolute::LockTime::Seconds(time)),
+            (None, None) => Err(IncompatibleLockTimeError),
+        }
+    }
+
+    /// Returns the unsigned transaction described by this PSBT.
+    ///
+    /// # Errors
+    ///
+    /// If the lock time can not be determined, see [`PsbtV2::lock_time`].
+    pub fn unsigned_tx(&self) -> Result<Transaction, IncompatibleLockTimeError> {
+        Ok(Transaction {
+            version: self.tx_version,
+            lock_time: self.lock_time()?,
+            input: self
+                .inputs
+                .iter()
+                .map(|input| TxIn {
+                    previous_output: OutPoint {
+                        txid: input.previous_txid,
+                        vout: input.spent_output_index,
+                    },
+                    script_sig: ScriptBuf::new(),
+                    sequence: input.sequence.unwrap_or(Sequence::MAX),
+                    witness: Witness::default(),
+                })
+                .collect(),
+            output: self
+                .outputs
+                .iter()
+                .map(|output| TxOut {
+                    value: output.amount,
+                    script_pubkey: output.script_pubkey.clone(),
+                })
+                .collect(),
+        })
+    }
+
+    /// Converts this PSBT to version 0.
+    ///
+    /// The transaction fields are moved into the unsigned transaction, the lock time is determined
+    /// with [`PsbtV2::lock_time`].
+    ///
+    /// # Errors
+    ///
+    /// If the lock time can not be determined.
+    pub fn to_v0(&self) -> Result<Psbt, IncompatibleLockTimeError> {
+        Ok(Psbt {
+            unsigned_tx: self.unsigned_tx()?,
+            version: 0,
+            xpub: self.xpub.clone(),
+            proprietary: self.proprietary.clone(),
+            unknown: self.unknown.clone(),
+            inputs: self.inputs.iter().map(|input| input.input.clone()).collect(),
+            outputs: self.outputs.iter().map(|output| output.output.clone()).collect(),
+        })
+    }
+
+    /// Serialize a value as bytes in hex.
+    pub fn serialize_hex(&self) -> String { self.serialize().to_lower_hex_string() }
+
+    /// Serialize as raw binary data.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = b"psbt\xff".to_vec();
+        buf.extend(self.serialize_map());
+        for input in &self.inputs {
+            buf.extend(input.serialize_map());
+        }
+        for output in &self.outputs {
+            buf.extend(output.serialize_map());
+        }
+        buf
+    }
+
+    /// Deserialize a value from raw binary data.
+    pub fn deserialize(mut bytes: &[u8]) -> Result<Self, Error> {
+        Self::deserialize_from_reader(&mut bytes)
+    }
+
+    /// Deserialize a value from raw binary data read from a `BufRead` object.
+    pub fn deserialize_from_reader<R: BufRead>(r: &mut R) -> Result<Self, Error> {
+        let magic: [u8; 4] = Decodable::consensus_decode(r)?;
+        if &magic != b"psbt" {
+            return Err(Error::InvalidMagic);
+        }
+        let separator: u8 = Decodable::consensus_decode(r)?;
+        if separator != 0xff {
+            return Err(Error::InvalidSeparator);
+        }
+
+        let (mut psbt, input_count, output_count) = Self::decode_global(r)?;
+        for _ in 0..input_count {
+            psbt.inputs.push(InputV2::decode(r)?);
+        }
+        for _ in 0..output_count {
+            psbt.outputs.push(OutputV2::decode(r)?);
+        }
+        Ok(psbt)
+    }
+
+    /// Decodes the global map, returning the PSBT without inputs and outputs and their counts.
+    fn decode_global<R: BufRead + ?Sized>(r: &mut R) -> Result<(Self, u64, u64), Error> {
+        let mut r = r.take(MAX_VEC_SIZE.to_u64());
+        let mut version: Option<u32> = None;
+        let mut tx_version: Option<transaction::Version> = None;
+        let mut fallback_lock_time: Option<absolute::LockTime> = None;
+        let mut input_count: Option<u64> = None;
+        let mut output_count: Option<u64> = None;
+        let mut tx_modifiable: Option<u8> = None;
+        let mut xpub: BTreeMap<Xpub, KeySource> = Default::default();
+        let mut proprietary: BTreeMap<raw::ProprietaryKey, Vec<u8>> = Default::default();
+        let mut unknown: BTreeMap<raw::Key, Vec<u8>> = Default::default();
+
+        loop {
+            let pair = match raw::Pair::decode(&mut r) {
+                Ok(pair) => pair,
+                Err(Error::NoMorePairs) => break,
+                Err(e) => return Err(e),
+            };
+            match pair.key.type_value {
+                // Version 2 PSBTs must not have an unsigned transaction.
+                map::PSBT_GLOBAL_UNSIGNED_TX => return Err(Error::InvalidKey(pair.key)),
+                map::PSBT_GLOBAL_XPUB => {
+                    let (key, key_source) = map::decode_xpub(pair)?;
+                    if xpub.insert(key, key_source).is_some() {
+                        return Err(Error::XPubKey("repeated global xpub key"));
+                    }
+                }
+                PSBT_GLOBAL_TX_VERSION => insert_unkeyed(&mut tx_version, pair)?,
+                PSBT_GLOBAL_FALLBACK_LOCKTIME => insert_unkeyed(&mut fallback_lock_time, pair)?,
+                PSBT_GLOBAL_INPUT_COUNT => insert_count(&mut input_count, pair)?,
+                PSBT_GLOBAL_OUTPUT_COUNT => insert_count(&mut output_count, pair)?,
+                PSBT_GLOBAL_TX_MODIFIABLE => insert_unkeyed(&mut tx_modifiable, pair)?,
+                map::PSBT_GLOBAL_VERSION => insert_unkeyed(&mut version, pair)?,
+                map::PSBT_GLOBAL_PROPRIETARY =>
+                    match proprietary.entry(raw::ProprietaryKey::try_from(pair.key.clone())?) {
+                        btree_map::Entry::Vacant(empty_key) => {
+                            empty_key.insert(pair.value);
+                        }
+                        btree_map::Entry::Occupied(_) => return Err(Error::DuplicateKey(pair.key)),
+                    },
+                _ => match unknown.entry(pair.key) {
+                    btree_map::Entry::Vacant(empty_key) => {
+                        empty_key.insert(pair.value);
+                    }
+                    btree_map::Entry::Occupied(k) =>
+                        return Err(Error::DuplicateKey(k.key().clone())),
+                },
+            }
+        }
+
+        match version {
+            Some(2) => {}
+            Some(_) => return Err(Error::Version("only version 2 PSBTs are supported")),
+            None => return Err(Error::MissingRequiredField("PSBT_GLOBAL_VERSION")),
+        }
+        let psbt = PsbtV2 {
+            tx_version: tx_version.ok_or(Error::MissingRequiredField("PSBT_GLOBAL_TX_VERSION"))?,
+            fallback_lock_time,
+            tx_modifiable,
+            xpub,
+            proprietary,
+            unknown,
+            inputs: vec![],
+            outputs: vec![],
+        };
+        let input_count =
+            input_count.ok_or(Error::MissingRequiredField("PSBT_GLOBAL_INPUT_COUNT"))?;
+        let output_count =
+            output_count.ok_or(Error::MissingRequiredField("PSBT_GLOBAL_OUTPUT_COUNT"))?;
+        Ok((psbt, input_count, output_count))
+    }
+}
+
+impl Map for PsbtV2 {
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        len += encode_unkeyed(w, PSBT_GLOBAL_TX_VERSION, &self.tx_version)?;
+        if let Some(ref lock_time) = self.fallback_lock_time {
+            len += encode_unkeyed(w, PSBT_GLOBAL_FALLBACK_LOCKTIME, lock_time)?;
+        }
+        let input_count = compact_size::encode(self.inputs.len()).to_vec();
+        len += encode_unkeyed(w, PSBT_GLOBAL_INPUT_COUNT, &input_count)?;
+        let output_count = compact_size::encode(self.outputs.len()).to_vec();
+        len += encode_unkeyed(w, PSBT_GLOBAL_OUTPUT_COUNT, &output_count)?;
+        if let Some(ref flags) = self.tx_modifiable {
+            len += encode_unkeyed(w, PSBT_GLOBAL_TX_MODIFIABLE, flags)?;
+        }
+        len += map::encode_xpubs(w, &self.xpub)?;
+        len += encode_unkeyed(w, map::PSBT_GLOBAL_VERSION, &2u32)?;
+
+        for (key, value) in self.proprietary.iter() {
+            len += map::encode_pair(w, &key.to_key(), value)?;
+        }
+
+        for (key, value) in self.unknown.iter() {
+            len += map::encode_pair(w, key, value)?;
+        }
+
+        Ok(len)
+    }
+}
+
+impl InputV2 {
+    /// Constructs a new input spending `previous_output` with no other fields set.
+    pub fn new(previous_output: OutPoint) -> Self {
+        InputV2 {
+            previous_txid: previous_output.txid,
+            spent_output_index: previous_output.vout,
+            sequence: None,
+            required_time_lock_time: None,
+            required_height_lock_time: None,
+            input: Input::default(),
+        }
+    }
+
+    /// Returns the outpoint this input spends.
+    pub fn previous_output(&self) -> OutPoint {
+        OutPoint { txid: self.previous_txid, vout: self.spent_output_index }
+    }
+
+    fn decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
+        let mut previous_txid: Option<Txid> = None;
+        let mut spent_output_index: Option<u32> = None;
+        let mut sequence: Option<Sequence> = None;
+        let mut required_time: Option<u32> = None;
+        let mut required_height: Option<u32> = None;
+        let mut input = Input::default();
+
+        loop {
+            let pair = match raw::Pair::decode(r) {
+                Ok(pair) => pair,
+                Err(Error::NoMorePairs) => break,
+                Err(e) => return Err(e),
+            };
+            match pair.key.type_value {
+                PSBT_IN_PREVIOUS_TXID => insert_unkeyed(&mut previous_txid, pair)?,
+                PSBT_IN_OUTPUT_INDEX => insert_unkeyed(&mut spent_output_index, pair)?,
+                PSBT_IN_SEQUENCE => insert_unkeyed(&mut sequence, pair)?,
+                PSBT_IN_REQUIRED_TIME_LOCKTIME => insert_unkeyed(&mut required_time, pair)?,
+                PSBT_IN_REQUIRED_HEIGHT_LOCKTIME => insert_unkeyed(&mut required_height, pair)?,
+                _ => input.insert_pair(pair)?,
+            }
+        }
+
+        Ok(InputV2 {
+            previous_txid: previous_txid
+                .ok_or(Error::MissingRequiredField("PSBT_IN_PREVIOUS_TXID"))?,
+            spent_output_index: spent_output_index
+                .ok_or(Error::MissingRequiredField("PSBT_IN_OUTPUT_INDEX"))?,
+            sequence,
+            required_time_lock_time: required_time
+                .map(Time::from_consensus)
+                .transpose()
+                .map_err(Error::InvalidRequiredLockTime)?,
+            required_height_lock_time: required_height
+                .map(Height::from_consensus)
+                .transpose()
+                .map_err(Error::InvalidRequiredLockTime)?,
+            input,
+        })
+    }
+}
+
+impl Map for InputV2 {
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        len += encode_unkeyed(w, PSBT_IN_PREVIOUS_TXID, &self.previous_txid)?;
+        len += encode_unkeyed(w, PSBT_IN_OUTPUT_INDEX, &self.spent_output_index)?;
+        if let Some(ref sequence) = self.sequence {
+            len += encode_unkeyed(w, PSBT_IN_SEQUENCE, sequence)?;
+        }
+        if let Some(time) = self.required_time_lock_time {
+            len += encode_unkeyed(w, PSBT_IN_REQUIRED_TIME_LOCKTIME, &time.to_consensus_u32())?;
+        }
+        if let Some(height) = self.required_height_lock_time {
+            let height = height.to_consensus_u32();
+            len += encode_unkeyed(w, PSBT_IN_REQUIRED_HEIGHT_LOCKTIME, &height)?;
+        }
+        len += self.input.encode_pairs(w)?;
+
+        Ok(len)
+    }
+}
+
+impl OutputV2 {
+    /// Constructs a new output paying `amount` to `script_pubkey` with no other fields set.
+    pub fn new(amount: Amount, script_pubkey: ScriptBuf) -> Self {
+        OutputV2 { amount, script_pubkey, output: Output::default() }
+    }
+
+    fn decode<R: BufRead + ?Sized>(r: &mut R) -> Result<Self, Error> {
+        let mut amount: Option<Amount> = None;
+        let mut script_pubkey: Option<ScriptBuf> = None;
+        let mut output = Output::default();
+
+        loop {
+            let pair = match raw::Pair::decode(r) {
+                Ok(pair) => pair,
+                Err(Error::NoMorePairs) => break,
+                Err(e) => return Err(e),
+            };
+            match pair.key.type_value {
+                PSBT_OUT_AMOUNT => insert_unkeyed(&mut amount, pair)?,
+                PSBT_OUT_SCRIPT => insert_unkeyed(&mut script_pubkey, pair)?,
+                _ => output.insert_pair(pair)?,
+            }
+        }
+
+        Ok(OutputV2 {
+            amount: amount.ok_or(Error::MissingRequiredField("PSBT_OUT_AMOUNT"))?,
+            script_pubkey: script_pubkey.ok_or(Error::MissingRequiredField("PSBT_OUT_SCRIPT"))?,
+            output,
+        })
+    }
+}
+
+impl Map for OutputV2 {
+    fn encode_pairs<W: Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+
+        len += encode_unkeyed(w, PSBT_OUT_AMOUNT, &self.amount)?;
+        len += encode_unkeyed(w, PSBT_OUT_SCRIPT, &self.script_pubkey)?;
+        len += self.output.encode_pairs(w)?;
+
+        Ok(len)
+    }
+}
+
+impl Psbt {
+    /// Converts this PSBT to version 2.
+    ///
+    /// The lock time of the unsigned transaction becomes the fallback lock time, the PSBT version
+    /// field is ignored.
+    pub fn to_v2(&self) -> PsbtV2 {
+        let tx = &self.unsigned_tx;
+        PsbtV2 {
+            tx_version: tx.version,
+            fallback_lock_time: Some(tx.lock_time),
+            tx_modifiable: None,
+            xpub: self.xpub.clone(),
+            proprietary: self.proprietary.clone(),
+            unknown: self.unknown.clone(),
+            inputs: tx
+                .input
+                .iter()
+                .zip(&self.inputs)
+                .map(|(txin, input)| InputV2 {
+                    sequence: Some(txin.sequence),
+                    input: input.clone(),
+                    ..InputV2::new(txin.previous_output)
+                })
+                .collect(),
+            outputs: tx
+                .output
+                .iter()
+                .zip(&self.outputs)
+                .map(|(txout, output)| OutputV2 {
+                    output: output.clone(),
+                    ..OutputV2::new(txout.value, txout.script_pubkey.clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Encodes a pair with an empty key of type `type_value`, returning the number of bytes written.
+fn encode_unkeyed<W: Write + ?Sized, T: Serialize>(
+    w: &mut W,
+    type_value: u64,
+    value: &T,
+) -> Result<usize, io::Error> {
+    map::encode_pair(w, &raw::Key { type_value, key_data: vec![] }, value)
+}
+
+/// Deserializes the value of a pair with an empty key into `field`.
+fn insert_unkeyed<T: Deserialize>(field: &mut Option<T>, pair: raw::Pair) -> Result<(), Error> {
+    if !pair.key.key_data.is_empty() {
+        return Err(Error::InvalidKey(pair.key));
+    }
+    if field.is_some() {
+        return Err(Error::DuplicateKey(pair.key));
+    }
+    *field = Some(T::deserialize(&pair.value)?);
+    Ok(())
+}
+
+/// Decodes the compact size value of an input or output count pair into `field`.
+fn insert_count(field: &mut Option<u64>, pair: raw::Pair) -> Result<(), Error> {
+    if !pair.key.key_data.is_empty() {
+        return Err(Error::InvalidKey(pair.key));
+    }
+    if field.is_some() {
+        return Err(Error::DuplicateKey(pair.key));
+    }
+    let mut value = &pair.value[..];
+    let count = value.read_compact_size()?;
+    if !value.is_empty() {
+        return Err(Error::PartialDataConsumption);
+    }
+    *field = Some(count);
+    Ok(())
+}
+
+/// The inputs of a version 2 PSBT have no lock time type in common.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IncompatibleLockTimeError;
+
+impl fmt::Display for IncompatibleLockTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "inputs require both a height based and a time based lock time")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IncompatibleLockTimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex::test_hex_unwrap as hex;
+
+    use super::*;
+    use crate::script::ScriptBufExt as _;
+
+    fn outpoint(vout: u32) -> OutPoint {
+        OutPoint {
+            txid: "f61b1742ca13176464adb3cb66050c00787bb3a4eead37e985f2df1e37718126"
+                .parse()
+                .unwrap(),
+            vout,
+        }
+    }
+
+    fn input(height: Option<u32>, time: Option<u32>) -> InputV2 {
+        InputV2 {
+            required_height_lock_time: height.map(|h| Height::from_consensus(h).unwrap()),
+            required_time_lock_time: time.map(|t| Time::from_consensus(t).unwrap()),
+            ..InputV2::new(outpoint(0))
+        }
+    }
+
+    fn psbt_with_inputs(inputs: Vec<InputV2>) -> PsbtV2 {
+        PsbtV2 { inputs, ..PsbtV2::new(transaction::Version::TWO) }
+    }
+
+    fn sample_psbt() -> PsbtV2 {
+        let mut psbt = PsbtV2::new(transaction::Version::TWO);
+        psbt.fallback_lock_time = Some(absolute::LockTime::from_consensus(800_000));
+        psbt.tx_modifiable = Some(TX_MODIFIABLE_INPUTS | TX_MODIFIABLE_SIGHASH_SINGLE);
+        psbt.inputs.push(InputV2 {
+            sequence: Some(Sequence::ENABLE_LOCKTIME_AND_RBF),
+            input: Input {
+                witness_utxo: Some(TxOut {
+                    value: Amount::from_sat_u32(100_000_000),
+                    script_pubkey: ScriptBuf::from_hex(
+                        "0014d85c2b71d0060b09c9886aeb815e50991dda124d",
+                    )
+                    .unwrap(),
+                }),
+                ..Default::default()
+            },
+            ..input(Some(800_010), None)
+        });
+        psbt.inputs.push(input(Some(800_020), Some(1_700_000_000)));
+        psbt.outputs.push(OutputV2::new(
+            Amount::from_sat_u32(99_990_000),
+            ScriptBuf::from_hex("0014a4b4ca48de0b3fffc15404a1acdc8dbaae226955").unwrap(),
+        ));
+        psbt
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let psbt = sample_psbt();
+        let bytes = psbt.serialize();
+        assert_eq!(PsbtV2::deserialize(&bytes).unwrap(), psbt);
+        assert_eq!(psbt.serialize_hex(), bytes.to_lower_hex_string());
+
+        assert!(psbt.inputs_modifiable());
+        assert!(!psbt.outputs_modifiable());
+        assert!(psbt.has_sighash_single());
+
+        // Version 2 PSBTs are rejected by the version 0 parser.
+        assert!(matches!(Psbt::deserialize(&bytes), Err(Error::InvalidKey(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "base64")]
+    fn base64_round_trip() {
+        let psbt = sample_psbt();
+        assert_eq!(psbt.to_string().parse::<PsbtV2>().unwrap(), psbt);
+    }
+
+    #[test]
+    fn deserialize_minimal() {
+        let bytes = hex!(concat!(
+            "70736274ff",
+            "01020402000000", // tx version 2
+            "01040101",       // one input
+            "01050101",       // one output
+            "01fb0402000000", // PSBT version 2
+            "00",
+            "010e20268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6", // previous txid
+            "010f0401000000", // output index 1
+            "00",
+            "0103088096980000000000", // amount 10000000
+            "0104160014a4b4ca48de0b3fffc15404a1acdc8dbaae226955", // script
+            "00",
+        ));
+        let psbt = PsbtV2::deserialize(&bytes).unwrap();
+        assert_eq!(psbt.tx_version, transaction::Version::TWO);
+        assert_eq!(psbt.fallback_lock_time, None);
+        assert_eq!(psbt.inputs.len(), 1);
+        assert_eq!(psbt.inputs[0].previous_output(), outpoint(1));
+        assert_eq!(psbt.inputs[0].sequence, None);
+        assert_eq!(psbt.outputs.len(), 1);
+        assert_eq!(psbt.outputs[0].amount, Amount::from_sat_u32(10_000_000));
+
+        // Serializing writes the same fields in the same order.
+        assert_eq!(psbt.serialize(), bytes);
+
+        let tx = psbt.unsigned_tx().unwrap();
+        assert_eq!(tx.lock_time, absolute::LockTime::ZERO);
+        assert_eq!(tx.input[0].sequence, Sequence::MAX);
+    }
+
+    #[test]
+    fn deserialize_invalid() {
+        let global = "01020402000000010401000105010001fb040200000000";
+        assert!(PsbtV2::deserialize(&hex!(&format!("70736274ff{}", global))).is_ok());
+
+        // Missing PSBT version.
+        let bytes = hex!("70736274ff01020402000000010401000105010000");
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::MissingRequiredField(_))));
+        // PSBT version 0.
+        let bytes = hex!("70736274ff01020402000000010401000105010001fb040000000000");
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::Version(_))));
+        // Missing input count.
+        let bytes = hex!("70736274ff010204020000000105010001fb040200000000");
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::MissingRequiredField(_))));
+        // Unsigned transaction is not allowed.
+        let bytes = hex!(
+            "70736274ff01000a0200000000000000000001020402000000010401000105010001fb040200000000"
+        );
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::InvalidKey(_))));
+
+        // Input missing the output index.
+        let bytes = hex!(&format!(
+            "70736274ff{}{}",
+            "01020402000000010401010105010001fb040200000000",
+            "010e20268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf600"
+        ));
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::MissingRequiredField(_))));
+        // Required height lock time above the threshold.
+        let bytes = hex!(&format!(
+            "70736274ff{}{}{}",
+            "01020402000000010401010105010001fb040200000000",
+            "010e20268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6010f0400000000",
+            "01120400659d6500"
+        ));
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::InvalidRequiredLockTime(_))));
+        // Required time lock time below the threshold.
+        let bytes = hex!(&format!(
+            "70736274ff{}{}{}",
+            "01020402000000010401010105010001fb040200000000",
+            "010e20268171371edff285e937adeea4b37b78000c0566cbb3ad64641713ca42171bf6010f0400000000",
+            "0111041027000000"
+        ));
+        assert!(matches!(PsbtV2::deserialize(&bytes), Err(Error::InvalidRequiredLockTime(_))));
+    }
+
+    // Lock time determination cases from BIP-370.
+    #[test]
+    fn determine_lock_time() {
+        let height = |h| absolute::LockTime::Blocks(Height::from_consensus(h).unwrap());
+        let time = |t| absolute::LockTime::Seconds(Time::from_consensus(t).unwrap());
+
+        // No inputs require a lock time, the fallback or zero is used.
+        let mut psbt = psbt_with_inputs(vec![input(None, None)]);
+        assert_eq!(psbt.lock_time(), Ok(absolute::LockTime::ZERO));
+        psbt.fallback_lock_time = Some(height(10_000));
+        assert_eq!(psbt.lock_time(), Ok(height(10_000)));
+
+        // The fallback is ignored once an input has a requirement.
+        psbt.inputs.push(input(Some(20_000), None));
+        assert_eq!(psbt.lock_time(), Ok(height(20_000)));
+
+        // Heights are preferred when every input supports both.
+        let psbt = psbt_with_inputs(vec![
+            input(Some(10_000), Some(1_657_048_460)),
+            input(Some(20_000), Some(1_657_048_470)),
+        ]);
+        assert_eq!(psbt.lock_time(), Ok(height(20_000)));
+
+        // Times are used if one input only supports time.
+        let psbt = psbt_with_inputs(vec![
+            input(Some(10_000), Some(1_657_048_460)),
+            input(None, Some(1_657_048_470)),
+            input(None, None),
+        ]);
+        assert_eq!(psbt.lock_time(), Ok(time(1_657_048_470)));
+
+        // Heights are used if one input only supports height.
+        let psbt = psbt_with_inputs(vec![
+            input(Some(10_000), Some(1_657_048_460)),
+            input(Some(20_000), None),
+        ]);
+        assert_eq!(psbt.lock_time(), Ok(height(20_000)));
+
+        // One input requires a height and another a time.
+        let psbt =
+            psbt_with_inputs(vec![input(Some(10_000), None), input(None, Some(1_657_048_460))]);
+        assert_eq!(psbt.lock_time(), Err(IncompatibleLockTimeError));
+        assert!(psbt.to_v0().is_err());
+    }
+
+    // Valid and invalid PSBTs modeled on the cases listed by BIP-370, built from key-value pairs
+    // written for these tests. They are not the serialized PSBTs published in the BIP.
+    mod field_cases {
+        use super::*;
+
+        const TX_VERSION: &str = "01020402000000";
+        const FALLBACK_LOCKTIME: &str = "01030400000000";
+        const INPUT_COUNT: &str = "01040101";
+        const OUTPUT_COUNT: &str = "01050102";
+        const PSBT_VERSION: &str = "01fb0402000000";
+        const UNSIGNED_TX: &str = "01000a02000000000000000000";
+
+        const PREVIOUS_TXID: &str =
+            "010e200b0ad921419c1c8719735d72dc739f9ea9e0638d1fe4c1eef0f9944084815fc8";
+        const OUTPUT_INDEX: &str = "010f0400000000";
+        const SEQUENCE: &str = "011004feffffff";
+        const REQUIRED_TIME_LOCKTIME: &str = "0111048c8dc462";
+        const REQUIRED_HEIGHT_LOCKTIME: &str = "01120410270000";
+
+        const AMOUNT: &str = "0103083f9c000000000000";
+        const SCRIPT: &str = "0104160014a4b4ca48de0b3fffc15404a1acdc8dbaae226955";
+        const AMOUNT_2: &str = "0103088096980000000000";
+        const SCRIPT_2: &str = "0104160014d85c2b71d0060b09c9886aeb815e50991dda124d";
+
+        /// Serializes a PSBT with the given global, input and output key-value pairs.
+        fn psbt(global: &[&str], input: &[&str], outputs: [&[&str]; 2]) -> Vec<u8> {
+            let mut s = String::from("70736274ff");
+            for map in [global, input].iter().chain(outputs.iter()) {
+                s.extend(map.iter().copied());
+                s.push_str("00");
+            }
+            hex!(&s)
+        }
+
+        fn global() -> Vec<&'static str> {
+            vec![TX_VERSION, INPUT_COUNT, OUTPUT_COUNT, PSBT_VERSION]
+        }
+
+        fn input() -> Vec<&'static str> { vec![PREVIOUS_TXID, OUTPUT_INDEX] }
+
+        fn outputs() -> [&'static [&'static str]; 2] { [&[AMOUNT_2, SCRIPT_2], &[AMOUNT, SCRIPT]] }
+
+        #[test]
+        fn valid() {
+            let mut cases = vec![
+                ("required fields only", global(), input()),
+                ("with sequence", global(), vec![PREVIOUS_TXID, OUTPUT_INDEX, SEQUENCE]),
+                (
+                    "with required time lock time",
+                    global(),
+                    vec![PREVIOUS_TXID, OUTPUT_INDEX, REQUIRED_TIME_LOCKTIME],
+                ),
+                (
+                    "with required height lock time",
+                    global(),
+                    vec![PREVIOUS_TXID, OUTPUT_INDEX, REQUIRED_HEIGHT_LOCKTIME],
+                ),
+                (
+                    "with both required lock times",
+                    global(),
+                    vec![
+                        PREVIOUS_TXID,
+                        OUTPUT_INDEX,
+                        REQUIRED_TIME_LOCKTIME,
+                        REQUIRED_HEIGHT_LOCKTIME,
+                    ],
+                ),
+                (
+                    "with fallback lock time",
+                    vec![TX_VERSION, FALLBACK_LOCKTIME, INPUT_COUNT, OUTPUT_COUNT, PSBT_VERSION],
+                    input(),
+                ),
+            ];
+            for flags in ["01060101", "01060102", "01060104", "01060108", "01060107", "010601ff"] {
+                let global = vec![TX_VERSION, INPUT_COUNT, OUTPUT_COUNT, flags, PSBT_VERSION];
+                cases.push(("with modifiable flags", global, input()));
+            }
+
+            for (case, global, input) in cases {
+                let bytes = psbt(&global, &input, outputs());
+                let psbt =
+                    PsbtV2::deserialize(&bytes).unwrap_or_else(|e| panic!("case {}: {}", case, e));
+                assert_eq!(psbt.serialize(), bytes, "case {}", case);
+                assert!(psbt.unsigned_tx().is_ok(), "case {}", case);
+                #[cfg(feature = "base64")]
+                assert_eq!(psbt.to_string().parse::<PsbtV2>().unwrap(), psbt, "case {}", case);
+            }
+
+            let bytes = psbt(
+                &global(),
+                &[
+                    PREVIOUS_TXID,
+                    OUTPUT_INDEX,
+                    SEQUENCE,
+                    REQUIRED_TIME_LOCKTIME,
+                    REQUIRED_HEIGHT_LOCKTIME,
+                ],
+                outputs(),
+            );
+            let psbt = PsbtV2::deserialize(&bytes).unwrap();
+            let input = &psbt.inputs[0];
+            assert_eq!(
+                input.previous_txid.to_string(),
+                "c85f81844094f9f0eec1e41f8d63e0a99e9f73dc725d7319871c9c4121d90a0b"
+            );
+            assert_eq!(input.spent_output_index, 0);
+            assert_eq!(input.sequence, Some(Sequence::ENABLE_LOCKTIME_NO_RBF));
+            assert_eq!(
+                input.required_time_lock_time,
+                Some(Time::from_consensus(1_657_048_460).unwrap())
+            );
+            assert_eq!(
+                input.required_height_lock_time,
+                Some(Height::from_consensus(10_000).unwrap())
+            );
+            assert_eq!(psbt.outputs[0].amount, Amount::from_sat_u32(10_000_000));
+            assert_eq!(psbt.outputs[1].amount, Amount::from_sat_u32(39_999));
+            // Heights are preferred when the input supports both.
+            assert_eq!(psbt.lock_time(), Ok(absolute::LockTime::from_consensus(10_000)));
+        }
+
+        #[test]
+        fn invalid_v2() {
+            let without = |fields: Vec<&'static str>, field| {
+                fields.into_iter().filter(|f| *f != field).collect::<Vec<_>>()
+            };
+            let cases = [
+                ("missing tx version", without(global(), TX_VERSION), input(), outputs()),
+                ("missing input count", without(global(), INPUT_COUNT), input(), outputs()),
+                ("missing output count", without(global(), OUTPUT_COUNT), input(), outputs()),
+                (
+                    "with unsigned tx",
+                    vec![UNSIGNED_TX, TX_VERSION, INPUT_COUNT, OUTPUT_COUNT, PSBT_VERSION],
+                    input(),
+                    outputs(),
+                ),
+                ("missing previous txid", global(), without(input(), PREVIOUS_TXID), outputs()),
+                ("missing output index", global(), without(input(), OUTPUT_INDEX), outputs()),
+                ("missing output amount", global(), input(), [&[SCRIPT_2], &[AMOUNT, SCRIPT]]),
+                ("missing output script", global(), input(), [&[AMOUNT_2], &[AMOUNT, SCRIPT]]),
+                (
+                    "required time lock time less than 500000000",
+                    global(),
+                    vec![PREVIOUS_TXID, OUTPUT_INDEX, "011104ff64cd1d"],
+                    outputs(),
+                ),
+                (
+                    "required height lock time greater than or equal to 500000000",
+                    global(),
+                    vec![PREVIOUS_TXID, OUTPUT_INDEX, "0112040065cd1d"],
+                    outputs(),
+                ),
+            ];
+
+            for (case, global, input, outputs) in cases {
+                let bytes = psbt(&global, &input, outputs);
+                assert!(PsbtV2::deserialize(&bytes).is_err(), "case {}", case);
+                assert!(Psbt::deserialize(&bytes).is_err(), "case {}", case);
+            }
+        }
+
+        #[test]
+        fn invalid_v0_with_v2_fields() {
+            let bytes = psbt(&global(), &input(), outputs());
+            let v0 = PsbtV2::deserialize(&bytes).unwrap().to_v0().unwrap();
+            assert!(Psbt::deserialize(&v0.serialize()).is_ok());
+
+            let pair = |field: &str| {
+                let pair = raw::Pair::deserialize(&hex!(field)).unwrap();
+                (pair.key, pair.value)
+            };
+
+            for field in [TX_VERSION, FALLBACK_LOCKTIME, INPUT_COUNT, OUTPUT_COUNT, "01060100"] {
+                let mut psbt = v0.clone();
+                psbt.unknown.extend([pair(field)]);
+                assert!(
+                    matches!(Psbt::deserialize(&psbt.serialize()), Err(Error::InvalidKey(_))),
+                    "global field {}",
+                    field
+                );
+            }
+            for field in [
+                PREVIOUS_TXID,
+                OUTPUT_INDEX,
+                SEQUENCE,
+                REQUIRED_TIME_LOCKTIME,
+                REQUIRED_HEIGHT_LOCKTIME,
+            ] {
+                let mut psbt = v0.clone();
+                psbt.inputs[0].unknown.extend([pair(field)]);
+                assert!(
+                    matches!(Psbt::deserialize(&psbt.serialize()), Err(Error::InvalidKey(_))),
+                    "input field {}",
+                    field
+                );
+            }
+            for field in [AMOUNT, SCRIPT] {
+                let mut psbt = v0.clone();
+                psbt.outputs[0].unknown.extend([pair(field)]);
+                assert!(
+                    matches!(Psbt::deserialize(&psbt.serialize()), Err(Error::InvalidKey(_))),
+                    "output field {}",
+                    field
+                );
+            }
+
+            // Version 0 with the PSBT version set to 2.
+            let mut psbt = v0;
+            psbt.version = 2;
+            assert!(matches!(Psbt::deserialize(&psbt.serialize()), Err(Error::Version(_))));
+        }
+    }
+
+    #[test]
+    fn convert_versions() {
+        let psbt = sample_psbt();
+        let v0 = psbt.to_v0().unwrap();
+        assert_eq!(v0.version, 0);
+        assert_eq!(v0.unsigned_tx.lock_time, absolute::LockTime::from_consensus(800_020));
+        assert_eq!(v0.unsigned_tx.input[0].previous_output, outpoint(0));
+        assert_eq!(v0.unsigned_tx.input[0].sequence, Sequence::ENABLE_LOCKTIME_AND_RBF);
+        assert_eq!(v0.unsigned_tx.input[1].sequence, Sequence::MAX);
+        assert_eq!(v0.unsigned_tx.output[0].value, Amount::from_sat_u32(99_990_000));
+        assert_eq!(v0.inputs[0], psbt.inputs[0].input);
+        assert_eq!(Psbt::deserialize(&v0.serialize()).unwrap(), v0);
+
+        // Extraction uses the determined lock time.
+        let tx = v0.clone().extract_tx_unchecked_fee_rate();
+        assert_eq!(tx.lock_time, absolute::LockTime::from_consensus(800_020));
+        assert_eq!(tx.compute_txid(), psbt.unsigned_tx().unwrap().compute_txid());
+
+        // The lock time requirements are lost when converting to version 0.
+        let v2 = v0.to_v2();
+        assert_eq!(v2.fallback_lock_time, Some(absolute::LockTime::from_consensus(800_020)));
+        assert_eq!(v2.inputs[0].required_height_lock_time, None);
+        assert_eq!(v2.inputs[1].sequence, Some(Sequence::MAX));
+        assert_eq!(v2.outputs, psbt.outputs);
+        assert_eq!(v2.to_v0().unwrap(), v0);
+    }
+}
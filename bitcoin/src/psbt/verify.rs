@@ -0,0 +1,337 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! PSBT signature verification.
+//!
+//! Checks the signatures added by other signers before countersigning, so that an invalid
+//! signature can not be used to trick a signer into completing a transaction that can not be
+//! broadcast.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use internals::write_err;
+use secp256k1::{Secp256k1, Verification};
+
+use super::{Input, Psbt, PsbtSighashType, SignError};
+use crate::crypto::key::{PublicKey, XOnlyPublicKey};
+use crate::sighash::SighashCache;
+use crate::taproot::TapLeafHash;
+
+impl Psbt {
+    /// Verifies the signatures present in all inputs of this PSBT.
+    ///
+    /// The ECDSA signatures in `partial_sigs`, the Taproot key path signature in `tap_key_sig` and
+    /// the Taproot script path signatures in `tap_script_sigs` are checked against the sighash
+    /// computed from the UTXOs in the PSBT. Each signature is checked using the sighash type it
+    /// commits to, which must be the `sighash_type` of its input if one is set. Scripts that
+    /// contain `OP_CODESEPARATOR` are not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first invalid signature found, or the first input whose sighash could not be
+    /// computed.
+    pub fn verify_sigs<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+    ) -> Result<(), SigVerificationError> {
+        use SigVerificationError::*;
+
+        let mut cache = SighashCache::new(&self.unsigned_tx);
+
+        for (index, input) in self.inputs.iter().enumerate() {
+            let sighash_err = |e| SigVerificationError::from_sign_error(index, e);
+
+            for (key, sig) in &input.partial_sigs {
+                check_sighash_type(index, input, sig.sighash_type.into())?;
+                let (msg, _) = self
+                    .sighash_ecdsa_with_type(index, &mut cache, Some(sig.sighash_type))
+                    .map_err(sighash_err)?;
+                key.verify(secp, msg, *sig)
+                    .map_err(|_| InvalidEcdsaSignature { input: index, key: *key })?;
+            }
+
+            if let Some(sig) = input.tap_key_sig {
+                check_sighash_type(index, input, sig.sighash_type.into())?;
+                let (msg, _) = self
+                    .sighash_taproot(index, &mut cache, None, Some(sig.sighash_type))
+                    .map_err(sighash_err)?;
+                // The sighash is only computed for P2TR outputs, the output key follows the
+                // witness version and push opcodes.
+                let spk = &self.spend_utxo(index).map_err(sighash_err)?.script_pubkey;
+                let output_key = spk.as_bytes()[2..]
+                    .try_into()
+                    .ok()
+                    .and_then(|key| XOnlyPublicKey::from_byte_array(key).ok())
+                    .ok_or_else(|| sighash_err(SignError::UnknownOutputType))?;
+                secp.verify_schnorr(&sig.signature, msg.as_ref(), &output_key).map_err(|_| {
+                    InvalidSchnorrSignature { input: index, key: output_key, leaf_hash: None }
+                })?;
+            }
+
+            for (&(key, leaf_hash), sig) in &input.tap_script_sigs {
+                check_sighash_type(index, input, sig.sighash_type.into())?;
+                let (msg, _) = self
+                    .sighash_taproot(index, &mut cache, Some(leaf_hash), Some(sig.sighash_type))
+                    .map_err(sighash_err)?;
+                secp.verify_schnorr(&sig.signature, msg.as_ref(), &key).map_err(|_| {
+                    InvalidSchnorrSignature { input: index, key, leaf_hash: Some(leaf_hash) }
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a signature with sighash type `found` is allowed in `input`.
+fn check_sighash_type(
+    index: usize,
+    input: &Input,
+    found: PsbtSighashType,
+) -> Result<(), SigVerificationError> {
+    match input.sighash_type {
+        Some(required) if required != found =>
+            Err(SigVerificationError::SighashTypeMismatch { input: index, required, found }),
+        _ => Ok(()),
+    }
+}
+
+/// Error verifying the signatures of a PSBT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SigVerificationError {
+    /// The UTXO information needed to compute the sighash of an input is missing.
+    MissingUtxo {
+        /// The index of the input.
+        input: usize,
+    },
+    /// The sighash of an input could not be computed.
+    Sighash {
+        /// The index of the input.
+        input: usize,
+        /// The error computing the sighash.
+        error: SignError,
+    },
+    /// A signature uses a different sighash type than the `sighash_type` of its input.
+    SighashTypeMismatch {
+        /// The index of the input.
+        input: usize,
+        /// The sighash type of the input.
+        required: PsbtSighashType,
+        /// The sighash type of the signature.
+        found: PsbtSighashType,
+    },
+    /// An ECDSA signature in `partial_sigs` is invalid.
+    InvalidEcdsaSignature {
+        /// The index of the input.
+        input: usize,
+        /// The public key the signature is for.
+        key: PublicKey,
+    },
+    /// A Taproot signature is invalid.
+    InvalidSchnorrSignature {
+        /// The index of the input.
+        input: usize,
+        /// The output key for a key path signature, the signing key for a script path signature.
+        key: XOnlyPublicKey,
+        /// The leaf of a script path signature, `None` for the key path signature.
+        leaf_hash: Option<TapLeafHash>,
+    },
+}
+
+impl SigVerificationError {
+    /// Returns the index of the input that failed verification.
+    pub fn input(&self) -> usize {
+        use SigVerificationError::*;
+
+        match *self {
+            MissingUtxo { input }
+            | Sighash { input, .. }
+            | SighashTypeMismatch { input, .. }
+            | InvalidEcdsaSignature { input, .. }
+            | InvalidSchnorrSignature { input, .. } => input,
+        }
+    }
+
+    fn from_sign_error(input: usize, error: SignError) -> Self {
+        match error {
+            SignError::MissingSpendUtxo => SigVerificationError::MissingUtxo { input },
+            error => SigVerificationError::Sighash { input, error },
+        }
+    }
+}
+
+impl From<Infallible> for SigVerificationError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for SigVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SigVerificationError::*;
+
+        match *self {
+            MissingUtxo { input } =>
+                write!(f, "input {}: missing UTXO information needed for the sighash", input),
+            Sighash { input, ref error } => write_err!(f, "input {}: sighash", input; error),
+            SighashTypeMismatch { input, required, found } => write!(
+                f,
+                "input {}: signature uses sighash type {} but the input requires {}",
+                input, found, required
+            ),
+            InvalidEcdsaSignature { input, key } =>
+                write!(f, "input {}: invalid ECDSA signature for key {}", input, key),
+            InvalidSchnorrSignature { input, key, leaf_hash: None } =>
+                write!(f, "input {}: invalid key path signature for output key {}", input, key),
+            InvalidSchnorrSignature { input, key, leaf_hash: Some(leaf_hash) } => write!(
+                f,
+                "input {}: invalid script path signature for key {} in leaf {}",
+                input, key, leaf_hash
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SigVerificationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use SigVerificationError::*;
+
+        match *self {
+            Sighash { ref error, .. } => Some(error),
+            MissingUtxo { .. }
+            | SighashTypeMismatch { .. }
+            | InvalidEcdsaSignature { .. }
+            | InvalidSchnorrSignature { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::SecretKey;
+
+    use super::*;
+    use crate::address::script_pubkey::ScriptBufExt as _;
+    use crate::crypto::key::{CompressedPublicKey, PrivateKey};
+    use crate::network::NetworkKind;
+    use crate::prelude::BTreeMap;
+    use crate::script::ScriptBufExt as _;
+    use crate::sighash::{EcdsaSighashType, TapSighashType};
+    use crate::transaction::{self, Transaction, TxIn, TxOut};
+    use crate::{absolute, Amount, OutPoint, ScriptBuf, Sequence, Witness};
+
+    fn private_key(i: u8) -> PrivateKey {
+        PrivateKey::new(SecretKey::from_byte_array(&[i; 32]).unwrap(), NetworkKind::Test)
+    }
+
+    /// Returns a PSBT spending a P2WPKH output of key 1 and a P2TR output of key 2.
+    fn psbt() -> Psbt {
+        let secp = Secp256k1::new();
+        let wpkh_key = CompressedPublicKey::from_private_key(&secp, private_key(1)).unwrap();
+        let tr_key = private_key(2).inner.x_only_public_key(&secp).0;
+        let txin = |vout| TxIn {
+            previous_output: OutPoint { vout, ..OutPoint::COINBASE_PREVOUT },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::new(),
+        };
+        let tx = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![txin(0), txin(1)],
+            output: vec![TxOut {
+                value: Amount::from_sat_u32(190_000),
+                script_pubkey: ScriptBuf::new_op_return([]),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat_u32(100_000),
+            script_pubkey: ScriptBuf::new_p2wpkh(wpkh_key.wpubkey_hash()),
+        });
+        psbt.inputs[0].bip32_derivation.insert(wpkh_key.0, Default::default());
+        psbt.inputs[1].witness_utxo = Some(TxOut {
+            value: Amount::from_sat_u32(100_000),
+            script_pubkey: ScriptBuf::new_p2tr(&secp, tr_key, None),
+        });
+        psbt.inputs[1].tap_internal_key = Some(tr_key);
+        psbt.inputs[1].tap_key_origins.insert(tr_key, (vec![], Default::default()));
+        psbt
+    }
+
+    fn sign(psbt: &mut Psbt) {
+        let secp = Secp256k1::new();
+        let keys = [1, 2]
+            .into_iter()
+            .map(|i| (private_key(i).public_key(&secp), private_key(i)))
+            .collect::<BTreeMap<_, _>>();
+        psbt.sign(&keys, &secp).unwrap();
+    }
+
+    #[test]
+    fn verify_valid_sigs() {
+        let secp = Secp256k1::verification_only();
+        let mut psbt = psbt();
+        // Nothing to verify.
+        psbt.verify_sigs(&secp).unwrap();
+
+        sign(&mut psbt);
+        assert_eq!(psbt.inputs[0].partial_sigs.len(), 1);
+        assert!(psbt.inputs[1].tap_key_sig.is_some());
+        psbt.verify_sigs(&secp).unwrap();
+
+        // Signatures are checked with the sighash type they commit to.
+        let mut psbt = self::psbt();
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::SinglePlusAnyoneCanPay.into());
+        psbt.inputs[1].sighash_type = Some(TapSighashType::NonePlusAnyoneCanPay.into());
+        sign(&mut psbt);
+        psbt.verify_sigs(&secp).unwrap();
+    }
+
+    #[test]
+    fn verify_corrupted_sigs() {
+        let secp = Secp256k1::verification_only();
+        let mut signed = psbt();
+        sign(&mut signed);
+
+        // Flip a bit in the ECDSA signature.
+        let mut psbt = signed.clone();
+        let (&key, sig) = psbt.inputs[0].partial_sigs.iter_mut().next().unwrap();
+        let mut bytes = sig.signature.serialize_compact();
+        bytes[10] ^= 1;
+        sig.signature = secp256k1::ecdsa::Signature::from_compact(&bytes).unwrap();
+        assert_eq!(
+            psbt.verify_sigs(&secp),
+            Err(SigVerificationError::InvalidEcdsaSignature { input: 0, key })
+        );
+
+        // A valid signature with a different sighash type than it was made with.
+        let mut psbt = signed.clone();
+        let sig = psbt.inputs[1].tap_key_sig.as_mut().unwrap();
+        sig.sighash_type = TapSighashType::All;
+        let err = psbt.verify_sigs(&secp).unwrap_err();
+        assert!(matches!(
+            err,
+            SigVerificationError::InvalidSchnorrSignature { input: 1, leaf_hash: None, .. }
+        ));
+
+        // The signature does not use the sighash type required by the input.
+        let mut psbt = signed.clone();
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::None.into());
+        assert_eq!(
+            psbt.verify_sigs(&secp),
+            Err(SigVerificationError::SighashTypeMismatch {
+                input: 0,
+                required: EcdsaSighashType::None.into(),
+                found: EcdsaSighashType::All.into(),
+            })
+        );
+
+        // The Taproot sighash commits to all spent outputs.
+        let mut psbt = signed;
+        psbt.inputs[0].witness_utxo = None;
+        psbt.inputs[0].partial_sigs.clear();
+        let err = psbt.verify_sigs(&secp).unwrap_err();
+        assert_eq!(err, SigVerificationError::MissingUtxo { input: 1 });
+        assert_eq!(err.input(), 1);
+    }
+}
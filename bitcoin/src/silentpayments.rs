@@ -0,0 +1,577 @@
+// SPDX-License-Identifier: CC0-1.0
+
+//! BIP-352 Silent Payments.
+//!
+//! Silent payments allow a receiver to publish a single static [`SilentPaymentCode`] while every
+//! payment to it ends up in a unique Taproot output. The output key is derived from an ECDH shared
+//! secret between the sender's input keys and the receiver's scan key, so only the receiver can
+//! detect the payment.
+//!
+//! This module implements the basic (unlabelled) output key derivation for both sides. Selecting
+//! the inputs eligible for shared secret derivation and extracting their public keys is left to
+//! the caller.
+//!
+//! ### Relevant BIPS
+//!
+//! * [BIP 352 - Silent Payments](https://github.com/bitcoin/bips/blob/master/bip-0352.mediawiki)
+
+use core::convert::Infallible;
+use core::fmt;
+use core::str::FromStr;
+
+use bech32::primitives::decode::{CheckedHrpstring, CheckedHrpstringError};
+use bech32::primitives::gf32::Fe32;
+use bech32::primitives::hrp::Hrp;
+use bech32::primitives::iter::{ByteIterExt as _, Fe32IterExt as _};
+use bech32::Bech32m;
+use hashes::{sha256t, sha256t_tag, HashEngine};
+use internals::write_err;
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey, Signing, Verification, XOnlyPublicKey};
+
+use crate::network::NetworkKind;
+use crate::prelude::Vec;
+use crate::OutPoint;
+
+/// The human-readable part of mainnet silent payment codes.
+const HRP_MAINNET: Hrp = Hrp::parse_unchecked("sp");
+/// The human-readable part of silent payment codes for all test networks.
+const HRP_TESTNET: Hrp = Hrp::parse_unchecked("tsp");
+
+/// The length of the key material in a silent payment code (scan key followed by spend key).
+const KEYS_LEN: usize = 66;
+
+/// Silent payment code versions from this one on are not backwards compatible.
+const FIRST_INCOMPATIBLE_VERSION: u8 = 31;
+
+sha256t_tag! {
+    struct InputsTag = hash_str("BIP0352/Inputs");
+}
+
+sha256t_tag! {
+    struct SharedSecretTag = hash_str("BIP0352/SharedSecret");
+}
+
+/// A silent payment code (`sp1...` address) of a receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SilentPaymentCode {
+    network: NetworkKind,
+    version: u8,
+    scan_key: PublicKey,
+    spend_key: PublicKey,
+}
+
+impl SilentPaymentCode {
+    /// Constructs a new version 0 silent payment code.
+    pub fn new(scan_key: PublicKey, spend_key: PublicKey, network: impl Into<NetworkKind>) -> Self {
+        SilentPaymentCode { network: network.into(), version: 0, scan_key, spend_key }
+    }
+
+    /// Returns the public key the receiver uses to scan for payments (`B_scan`).
+    #[inline]
+    pub fn scan_key(&self) -> PublicKey { self.scan_key }
+
+    /// Returns the public key the outputs are derived from (`B_spend`).
+    #[inline]
+    pub fn spend_key(&self) -> PublicKey { self.spend_key }
+
+    /// Returns the kind of network this code is used on.
+    #[inline]
+    pub fn network_kind(&self) -> NetworkKind { self.network }
+
+    /// Returns the version of the code.
+    ///
+    /// Only version 0 is currently defined. Codes with versions 1 to 30 are parsed in a forward
+    /// compatible way by ignoring any data following the keys.
+    #[inline]
+    pub fn version(&self) -> u8 { self.version }
+
+    /// Computes the output key of the `k`-th output paying to this code.
+    ///
+    /// `input_secret_keys` are the secret keys of all inputs eligible for shared secret
+    /// derivation and `outpoints` are the outpoints of all inputs of the transaction. Secret keys
+    /// of Taproot inputs must be negated by the caller if the corresponding public key has an odd
+    /// Y coordinate.
+    pub fn output_key<C: Signing + Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        input_secret_keys: &[SecretKey],
+        outpoints: &[OutPoint],
+        k: u32,
+    ) -> Result<XOnlyPublicKey, Error> {
+        SharedSecret::new_sender(secp, input_secret_keys, outpoints, &self.scan_key)?.output_key(
+            secp,
+            &self.spend_key,
+            k,
+        )
+    }
+
+    fn hrp(&self) -> Hrp {
+        match self.network {
+            NetworkKind::Main => HRP_MAINNET,
+            NetworkKind::Test => HRP_TESTNET,
+        }
+    }
+}
+
+impl fmt::Display for SilentPaymentCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys = [0; KEYS_LEN];
+        keys[..33].copy_from_slice(&self.scan_key.serialize());
+        keys[33..].copy_from_slice(&self.spend_key.serialize());
+
+        let hrp = self.hrp();
+        let version = Fe32::try_from(self.version).expect("versions are always less than 32");
+        let chars = keys
+            .iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(version)
+            .chars();
+        for c in chars {
+            fmt::Write::write_char(f, c)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SilentPaymentCode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let checked =
+            CheckedHrpstring::new::<Bech32m>(s).map_err(|e| ParseError::Bech32(Bech32Error(e)))?;
+
+        let network = if checked.hrp() == HRP_MAINNET {
+            NetworkKind::Main
+        } else if checked.hrp() == HRP_TESTNET {
+            NetworkKind::Test
+        } else {
+            return Err(ParseError::UnknownHrp);
+        };
+
+        // The version can't be removed as a witness version since it may be larger than 16.
+        let (version, data) =
+            checked.data_part_ascii_no_checksum().split_first().ok_or(ParseError::InvalidLength)?;
+        let to_fe =
+            |c: &u8| Fe32::from_char(char::from(*c)).expect("validated by CheckedHrpstring");
+        let version = to_fe(version).to_u8();
+        if version >= FIRST_INCOMPATIBLE_VERSION {
+            return Err(ParseError::UnsupportedVersion(version));
+        }
+
+        // Any incomplete group at the end must be 4 bits or less and all zeroes.
+        let padding_len = data.len() * 5 % 8;
+        let padding_mask = (1 << padding_len) - 1;
+        if padding_len > 4 || data.last().map_or(false, |c| to_fe(c).to_u8() & padding_mask != 0) {
+            return Err(ParseError::InvalidPadding);
+        }
+
+        let mut data = data.iter().map(to_fe).fes_to_bytes();
+        let mut keys = [0; KEYS_LEN];
+        for byte in keys.iter_mut() {
+            *byte = data.next().ok_or(ParseError::InvalidLength)?;
+        }
+        // Future versions may append data which must be ignored.
+        if version == 0 && data.next().is_some() {
+            return Err(ParseError::InvalidLength);
+        }
+
+        let scan_key = PublicKey::from_slice(&keys[..33]).map_err(ParseError::InvalidPublicKey)?;
+        let spend_key = PublicKey::from_slice(&keys[33..]).map_err(ParseError::InvalidPublicKey)?;
+        Ok(SilentPaymentCode { network, version, scan_key, spend_key })
+    }
+}
+
+/// The ECDH shared secret between the sender's inputs and the receiver's scan key.
+///
+/// This is `input_hash·a·B_scan` for the sender and `input_hash·b_scan·A` for the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedSecret(PublicKey);
+
+impl SharedSecret {
+    /// Computes the shared secret on the sender side.
+    ///
+    /// `input_secret_keys` are the secret keys of all inputs eligible for shared secret
+    /// derivation and `outpoints` are the outpoints of all inputs of the transaction. Secret keys
+    /// of Taproot inputs must be negated by the caller if the corresponding public key has an odd
+    /// Y coordinate.
+    pub fn new_sender<C: Signing + Verification>(
+        secp: &Secp256k1<C>,
+        input_secret_keys: &[SecretKey],
+        outpoints: &[OutPoint],
+        scan_key: &PublicKey,
+    ) -> Result<Self, Error> {
+        let (first, rest) = input_secret_keys.split_first().ok_or(Error::NoInputKeys)?;
+        let secret_key = rest.iter().try_fold(*first, |sum, key| {
+            sum.add_tweak(&Scalar::from(*key)).map_err(|_| Error::InputKeysSumToZero)
+        })?;
+        let input_hash = input_hash(outpoints, &secret_key.public_key(secp))?;
+
+        let tweak = secret_key.mul_tweak(&input_hash).map_err(|_| Error::InvalidTweak)?;
+        let shared =
+            scan_key.mul_tweak(secp, &Scalar::from(tweak)).map_err(|_| Error::InvalidTweak)?;
+        Ok(SharedSecret(shared))
+    }
+
+    /// Computes the shared secret on the receiver side.
+    ///
+    /// `input_public_keys` are the public keys of all inputs eligible for shared secret
+    /// derivation and `outpoints` are the outpoints of all inputs of the transaction. The keys of
+    /// Taproot inputs must have an even Y coordinate.
+    pub fn new_receiver<C: Verification>(
+        secp: &Secp256k1<C>,
+        scan_key: &SecretKey,
+        input_public_keys: &[PublicKey],
+        outpoints: &[OutPoint],
+    ) -> Result<Self, Error> {
+        if input_public_keys.is_empty() {
+            return Err(Error::NoInputKeys);
+        }
+        let keys = input_public_keys.iter().collect::<Vec<_>>();
+        let public_key = PublicKey::combine_keys(&keys).map_err(|_| Error::InputKeysSumToZero)?;
+        let input_hash = input_hash(outpoints, &public_key)?;
+
+        let tweak = scan_key.mul_tweak(&input_hash).map_err(|_| Error::InvalidTweak)?;
+        let shared =
+            public_key.mul_tweak(secp, &Scalar::from(tweak)).map_err(|_| Error::InvalidTweak)?;
+        Ok(SharedSecret(shared))
+    }
+
+    /// Computes the tweak `t_k` of the `k`-th output.
+    ///
+    /// The receiver can spend the output using the secret key `b_spend + t_k`.
+    pub fn output_tweak(&self, k: u32) -> Result<SecretKey, Error> {
+        let mut engine = sha256t::Hash::<SharedSecretTag>::engine();
+        engine.input(&self.0.serialize());
+        engine.input(&k.to_be_bytes());
+        let hash = sha256t::Hash::<SharedSecretTag>::from_engine(engine);
+        SecretKey::from_byte_array(&hash.to_byte_array()).map_err(|_| Error::InvalidTweak)
+    }
+
+    /// Computes the output key `P_k = B_spend + t_k·G` of the `k`-th output.
+    pub fn output_key<C: Verification>(
+        &self,
+        secp: &Secp256k1<C>,
+        spend_key: &PublicKey,
+        k: u32,
+    ) -> Result<XOnlyPublicKey, Error> {
+        let tweak = self.output_tweak(k)?;
+        let output_key =
+            spend_key.add_exp_tweak(secp, &Scalar::from(tweak)).map_err(|_| Error::InvalidTweak)?;
+        Ok(output_key.x_only_public_key().0)
+    }
+}
+
+/// Computes `input_hash = hash_BIP0352/Inputs(outpoint_L || A)`.
+///
+/// `outpoint_L` is the lexicographically smallest outpoint in its serialized form.
+fn input_hash(outpoints: &[OutPoint], public_key: &PublicKey) -> Result<Scalar, Error> {
+    let smallest = outpoints
+        .iter()
+        .map(|outpoint| {
+            let mut serialized = [0; 36];
+            serialized[..32].copy_from_slice(outpoint.txid.as_byte_array());
+            serialized[32..].copy_from_slice(&outpoint.vout.to_le_bytes());
+            serialized
+        })
+        .min()
+        .ok_or(Error::NoOutpoints)?;
+
+    let mut engine = sha256t::Hash::<InputsTag>::engine();
+    engine.input(&smallest);
+    engine.input(&public_key.serialize());
+    let hash = sha256t::Hash::<InputsTag>::from_engine(engine);
+    Scalar::from_be_bytes(hash.to_byte_array()).map_err(|_| Error::InvalidTweak)
+}
+
+/// Error deriving silent payment keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// No input keys were provided.
+    NoInputKeys,
+    /// No outpoints were provided.
+    NoOutpoints,
+    /// The input keys sum to zero (the point at infinity).
+    InputKeysSumToZero,
+    /// A hash is not a valid secret key (statistically extremely unlikely).
+    InvalidTweak,
+}
+
+impl From<Infallible> for Error {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Error::*;
+
+        match *self {
+            NoInputKeys => f.write_str("no input keys eligible for silent payments"),
+            NoOutpoints => f.write_str("transaction has no outpoints"),
+            InputKeysSumToZero => f.write_str("input keys sum to the point at infinity"),
+            InvalidTweak => f.write_str("derived tweak is not a valid secret key"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use Error::*;
+
+        match *self {
+            NoInputKeys | NoOutpoints | InputKeysSumToZero | InvalidTweak => None,
+        }
+    }
+}
+
+/// Error parsing a [`SilentPaymentCode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// Invalid bech32m encoding.
+    Bech32(Bech32Error),
+    /// The human-readable part is neither `sp` nor `tsp`.
+    UnknownHrp,
+    /// The version is not backwards compatible.
+    UnsupportedVersion(u8),
+    /// The encoded data has an invalid length.
+    InvalidLength,
+    /// The padding of the encoded data is invalid.
+    InvalidPadding,
+    /// The scan or spend key is invalid.
+    InvalidPublicKey(secp256k1::Error),
+}
+
+impl From<Infallible> for ParseError {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ParseError::*;
+
+        match *self {
+            Bech32(ref e) => write_err!(f, "bech32m error"; e),
+            UnknownHrp => f.write_str("unknown silent payment code human-readable part"),
+            UnsupportedVersion(v) => write!(f, "unsupported silent payment code version {}", v),
+            InvalidLength => f.write_str("invalid silent payment code length"),
+            InvalidPadding => f.write_str("invalid silent payment code padding"),
+            InvalidPublicKey(ref e) => write_err!(f, "invalid public key"; e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        use ParseError::*;
+
+        match *self {
+            Bech32(ref e) => Some(e),
+            InvalidPublicKey(ref e) => Some(e),
+            UnknownHrp | UnsupportedVersion(_) | InvalidLength | InvalidPadding => None,
+        }
+    }
+}
+
+/// Bech32m decoding error.
+// This wrapper exists because we do not want to expose the `bech32` crate in our public API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bech32Error(CheckedHrpstringError);
+
+impl From<Infallible> for Bech32Error {
+    fn from(never: Infallible) -> Self { match never {} }
+}
+
+impl fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_err!(f, "bech32m decoding error"; self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Bech32Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { Some(&self.0) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::String;
+    use crate::Txid;
+
+    const CODE: &str = "sp1qqgste7k9hx0qftg6qmwlkqtwuy6cycyavzmzj85c6qdfhjdpdjtdgqjuexzk6murw56suy3e0rd2cgqvycxttddwsvgxe2usfpxumr70xc9pkqwv";
+
+    fn outpoint(txid: &str, vout: u32) -> OutPoint {
+        OutPoint { txid: txid.parse().unwrap(), vout }
+    }
+
+    // Encodes a code with arbitrary data following the version.
+    fn encode(hrp: Hrp, version: u8, data: &[u8]) -> String {
+        data.iter()
+            .copied()
+            .bytes_to_fes()
+            .with_checksum::<Bech32m>(&hrp)
+            .with_witness_version(Fe32::try_from(version).unwrap())
+            .chars()
+            .collect()
+    }
+
+    #[test]
+    fn bip352_output_keys() {
+        let secp = Secp256k1::new();
+
+        let scan_key = "0f694e068028a717f8af6b9411f9a133dd3565258714cc226594b34db90c1f2c"
+            .parse::<SecretKey>()
+            .unwrap();
+        let spend_key = "9d6ad855ce3417ef84e836892e5a56392bfba05fa5d97ccea30e266f540e08b3"
+            .parse::<SecretKey>()
+            .unwrap();
+        let code = CODE.parse::<SilentPaymentCode>().unwrap();
+        assert_eq!(
+            code,
+            SilentPaymentCode::new(
+                scan_key.public_key(&secp),
+                spend_key.public_key(&secp),
+                NetworkKind::Main
+            )
+        );
+
+        let input_secret_keys = [
+            "eadc78165ff1f8ea94ad7cfdc54990738a4c53f6e0507b42154201b8e5dff3b1"
+                .parse::<SecretKey>()
+                .unwrap(),
+            "93f5ed907ad5b2bdbbdcb5d9116ebc0a4e1f92f910d5260237fa45a9408aad16"
+                .parse::<SecretKey>()
+                .unwrap(),
+        ];
+        let input_public_keys = input_secret_keys.map(|key| key.public_key(&secp));
+
+        let txid_1 = "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16";
+        let txid_2 = "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d";
+        let vectors = [
+            // Simple send: two inputs
+            (
+                [outpoint(txid_1, 0), outpoint(txid_2, 0)],
+                "3e9fce73d4e77a4809908e3c3a2e54ee147b9312dc5044a193d1fc85de46e3c1",
+            ),
+            // Simple send: two inputs, order reversed
+            (
+                [outpoint(txid_2, 0), outpoint(txid_1, 0)],
+                "3e9fce73d4e77a4809908e3c3a2e54ee147b9312dc5044a193d1fc85de46e3c1",
+            ),
+            // Simple send: two inputs from the same transaction
+            (
+                [outpoint(txid_1, 3), outpoint(txid_1, 7)],
+                "79e71baa2ba3fc66396de3a04f168c7bf24d6870ec88ca877754790c1db357b6",
+            ),
+            // Outpoint ordering byte-lexicographically vs. vout-integer
+            (
+                [outpoint(txid_1, 1), outpoint(txid_1, 256)],
+                "a85ef8701394b517a4b35217c4bd37ac01ebeed4b008f8d0879f9e09ba95319c",
+            ),
+        ];
+
+        for (outpoints, expected) in &vectors {
+            let expected = expected.parse::<XOnlyPublicKey>().unwrap();
+            assert_eq!(code.output_key(&secp, &input_secret_keys, outpoints, 0).unwrap(), expected);
+
+            let shared_secret =
+                SharedSecret::new_receiver(&secp, &scan_key, &input_public_keys, outpoints)
+                    .unwrap();
+            assert_eq!(
+                shared_secret,
+                SharedSecret::new_sender(&secp, &input_secret_keys, outpoints, &code.scan_key())
+                    .unwrap()
+            );
+            assert_eq!(shared_secret.output_key(&secp, &code.spend_key(), 0).unwrap(), expected);
+
+            // The receiver can spend the output.
+            let tweak = shared_secret.output_tweak(0).unwrap();
+            let output_secret_key = spend_key.add_tweak(&Scalar::from(tweak)).unwrap();
+            assert_eq!(output_secret_key.x_only_public_key(&secp).0, expected);
+
+            // Subsequent outputs use different keys.
+            assert_ne!(shared_secret.output_key(&secp, &code.spend_key(), 1).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn derivation_errors() {
+        let secp = Secp256k1::new();
+        let code = CODE.parse::<SilentPaymentCode>().unwrap();
+        let key = SecretKey::from_byte_array(&[1; 32]).unwrap();
+        let outpoints = [OutPoint { txid: Txid::from_byte_array([1; 32]), vout: 0 }];
+
+        assert_eq!(code.output_key(&secp, &[], &outpoints, 0), Err(Error::NoInputKeys));
+        assert_eq!(code.output_key(&secp, &[key], &[], 0), Err(Error::NoOutpoints));
+        assert_eq!(
+            code.output_key(&secp, &[key, key.negate()], &outpoints, 0),
+            Err(Error::InputKeysSumToZero)
+        );
+        let public_keys = [key.public_key(&secp), key.negate().public_key(&secp)];
+        assert_eq!(
+            SharedSecret::new_receiver(&secp, &key, &public_keys, &outpoints),
+            Err(Error::InputKeysSumToZero)
+        );
+        assert_eq!(
+            SharedSecret::new_receiver(&secp, &key, &[], &outpoints),
+            Err(Error::NoInputKeys)
+        );
+    }
+
+    #[test]
+    fn code_encoding() {
+        let code = CODE.parse::<SilentPaymentCode>().unwrap();
+        assert_eq!(code.network_kind(), NetworkKind::Main);
+        assert_eq!(code.version(), 0);
+        assert_eq!(code.to_string(), CODE);
+        assert_eq!(CODE.to_uppercase().parse::<SilentPaymentCode>().unwrap(), code);
+
+        let testnet = SilentPaymentCode::new(code.scan_key(), code.spend_key(), NetworkKind::Test);
+        assert!(testnet.to_string().starts_with("tsp1q"));
+        assert_eq!(testnet.to_string().parse::<SilentPaymentCode>().unwrap(), testnet);
+
+        let mut keys = [0; KEYS_LEN];
+        keys[..33].copy_from_slice(&code.scan_key().serialize());
+        keys[33..].copy_from_slice(&code.spend_key().serialize());
+        let mut extended = keys.to_vec();
+        extended.extend_from_slice(&[0xab; 10]);
+
+        assert_eq!(encode(HRP_MAINNET, 0, &keys), CODE);
+        // Future versions may append data.
+        let v1 = encode(HRP_MAINNET, 1, &extended).parse::<SilentPaymentCode>().unwrap();
+        assert_eq!(v1.version(), 1);
+        assert_eq!((v1.scan_key(), v1.spend_key()), (code.scan_key(), code.spend_key()));
+
+        assert_eq!(
+            encode(HRP_MAINNET, 0, &extended).parse::<SilentPaymentCode>(),
+            Err(ParseError::InvalidLength)
+        );
+        assert_eq!(
+            encode(HRP_MAINNET, 1, &keys[..65]).parse::<SilentPaymentCode>(),
+            Err(ParseError::InvalidLength)
+        );
+        assert_eq!(
+            encode(HRP_MAINNET, 31, &keys).parse::<SilentPaymentCode>(),
+            Err(ParseError::UnsupportedVersion(31))
+        );
+        assert_eq!(
+            encode(Hrp::parse_unchecked("bc"), 0, &keys).parse::<SilentPaymentCode>(),
+            Err(ParseError::UnknownHrp)
+        );
+        let mut invalid_key = keys;
+        invalid_key[0] = 0x04;
+        assert!(matches!(
+            encode(HRP_MAINNET, 0, &invalid_key).parse::<SilentPaymentCode>(),
+            Err(ParseError::InvalidPublicKey(_))
+        ));
+        let mut bad_checksum = String::from(CODE);
+        bad_checksum.pop();
+        bad_checksum.push('q');
+        assert!(matches!(bad_checksum.parse::<SilentPaymentCode>(), Err(ParseError::Bech32(_))));
+    }
+}
@@ -30,6 +30,9 @@
 #[rustfmt::skip]
 #[doc(inline)]
 pub use crate::crypto::taproot::{SigFromSliceError, Signature};
+#[rustfmt::skip]
+#[doc(inline)]
+pub use crate::crypto::sighash::{Annex, AnnexError};
 #[doc(inline)]
 pub use merkle_branch::TaprootMerkleBranchBuf;
 #[doc(inline)]
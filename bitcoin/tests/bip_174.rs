@@ -6,13 +6,12 @@
 use bitcoin::bip32::{Fingerprint, IntoDerivationPath, KeySource, Xpriv, Xpub};
 use bitcoin::consensus::encode::{deserialize, serialize_hex};
 use bitcoin::hex::FromHex;
-use bitcoin::opcodes::OP_0;
 use bitcoin::psbt::{Psbt, PsbtSighashType};
-use bitcoin::script::{PushBytes, ScriptBufExt as _};
+use bitcoin::script::ScriptBufExt as _;
 use bitcoin::secp256k1::Secp256k1;
 use bitcoin::{
-    absolute, script, transaction, Amount, Denomination, NetworkKind, OutPoint, PrivateKey,
-    PublicKey, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    absolute, transaction, Amount, Denomination, NetworkKind, OutPoint, PrivateKey, PublicKey,
+    ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
 };
 
 #[track_caller]
@@ -412,58 +411,8 @@ fn sign(mut psbt: Psbt, keys: BTreeMap<bitcoin::PublicKey, PrivateKey>) -> Psbt
     psbt
 }
 
-/// Finalizes a PSBT accord to the Input Finalizer role described in BIP 174.
-/// This is just a test. For a production-ready PSBT Finalizer, use [rust-miniscript](https://docs.rs/miniscript/latest/miniscript/psbt/trait.PsbtExt.html#tymethod.finalize)
+/// Finalizes a PSBT according to the Input Finalizer role described in BIP 174.
 fn finalize_psbt(mut psbt: Psbt) -> Psbt {
-    // Input 0: legacy UTXO
-
-    let sigs: Vec<_> = psbt.inputs[0].partial_sigs.values().collect();
-    let script_sig = script::Builder::new()
-        .push_opcode(OP_0) // OP_CHECKMULTISIG bug pops +1 value when evaluating so push OP_0.
-        .push_slice(sigs[0].serialize())
-        .push_slice(sigs[1].serialize())
-        .push_slice(
-            <&PushBytes>::try_from(psbt.inputs[0].redeem_script.as_ref().unwrap().as_bytes())
-                .unwrap(),
-        )
-        .into_script();
-
-    psbt.inputs[0].final_script_sig = Some(script_sig);
-
-    psbt.inputs[0].partial_sigs = BTreeMap::new();
-    psbt.inputs[0].sighash_type = None;
-    psbt.inputs[0].redeem_script = None;
-    psbt.inputs[0].bip32_derivation = BTreeMap::new();
-
-    // Input 1: SegWit UTXO
-
-    let script_sig = script::Builder::new()
-        .push_slice(
-            <&PushBytes>::try_from(psbt.inputs[1].redeem_script.as_ref().unwrap().as_bytes())
-                .unwrap(),
-        )
-        .into_script();
-
-    psbt.inputs[1].final_script_sig = Some(script_sig);
-
-    let script_witness = {
-        let sigs: Vec<_> = psbt.inputs[1].partial_sigs.values().collect();
-        let mut script_witness = Witness::new();
-        script_witness.push([]); // Push 0x00 to the stack.
-        script_witness.push(sigs[1].serialize());
-        script_witness.push(sigs[0].serialize());
-        script_witness.push(psbt.inputs[1].witness_script.clone().unwrap().as_bytes());
-
-        script_witness
-    };
-
-    psbt.inputs[1].final_script_witness = Some(script_witness);
-
-    psbt.inputs[1].partial_sigs = BTreeMap::new();
-    psbt.inputs[1].sighash_type = None;
-    psbt.inputs[1].redeem_script = None;
-    psbt.inputs[1].witness_script = None;
-    psbt.inputs[1].bip32_derivation = BTreeMap::new();
-
+    psbt.finalize().unwrap();
     psbt
 }
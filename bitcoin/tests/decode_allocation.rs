@@ -0,0 +1,138 @@
+//! Tests that decoding truncated data which claims huge lengths fails cleanly without allocating
+//! memory the input could never fill.
+//!
+//! This file installs a global allocator so it must only contain a single test, running tests in
+//! parallel would skew the measured allocations.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bitcoin::bip152::BlockTransactionsRequest;
+use bitcoin::consensus::encode::{self, deserialize, CheckedData, Decodable};
+use bitcoin::merkle_tree::PartialMerkleTree;
+use bitcoin::p2p::message::RawNetworkMessage;
+use bitcoin::p2p::Magic;
+use bitcoin::psbt::Psbt;
+use bitcoin::{Transaction, TxOut, Witness};
+
+/// Upper bound for the memory used to decode any of the truncated inputs.
+const MAX_ALLOCATION: usize = 256 * 1024;
+
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+impl PeakAllocator {
+    fn allocated(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::SeqCst) + size;
+        PEAK.fetch_max(current, Ordering::SeqCst);
+    }
+
+    fn deallocated(size: usize) { CURRENT.fetch_sub(size, Ordering::SeqCst); }
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::deallocated(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::deallocated(layout.size());
+            Self::allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Decodes `data` as a `T`, asserts that it fails and returns the peak memory used meanwhile.
+#[track_caller]
+fn failed_decode_allocation<T: Decodable + std::fmt::Debug>(data: &[u8]) -> usize {
+    let start = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    let result = deserialize::<T>(data);
+    let peak = PEAK.load(Ordering::SeqCst) - start;
+    assert!(result.is_err(), "truncated data decoded as {:?}", result);
+    drop(result);
+    peak
+}
+
+/// Returns `parts` concatenated.
+fn concat(parts: &[&[u8]]) -> Vec<u8> { parts.concat() }
+
+#[test]
+fn truncated_input_allocation_is_bounded() {
+    // A compact size claiming 0xFFFF_FFFF items followed by a few bytes of data.
+    let huge = &[0xFE, 0xFF, 0xFF, 0xFF, 0xFF][..];
+    // A compact size just below `MAX_VEC_SIZE`, accepted by decoders that check the length.
+    let large = &[0xFE, 0xFF, 0x08, 0x3D, 0x00][..];
+    let data = &[0x01, 0x02, 0x03][..];
+
+    let cases = [
+        ("Vec<u8>", failed_decode_allocation::<Vec<u8>>(&concat(&[huge, data]))),
+        ("Vec<u64>", failed_decode_allocation::<Vec<u64>>(&concat(&[huge, data]))),
+        ("Vec<TxOut>", failed_decode_allocation::<Vec<TxOut>>(&concat(&[huge, data]))),
+        ("Vec<Transaction>", failed_decode_allocation::<Vec<Transaction>>(&concat(&[huge, data]))),
+        ("Witness elements", failed_decode_allocation::<Witness>(&concat(&[large, data]))),
+        (
+            "Witness element size",
+            failed_decode_allocation::<Witness>(&concat(&[&[0x01], large, data])),
+        ),
+        (
+            "PartialMerkleTree",
+            failed_decode_allocation::<PartialMerkleTree>(&concat(&[
+                &[1, 0, 0, 0, 0],
+                large,
+                data,
+            ])),
+        ),
+        (
+            "BlockTransactionsRequest",
+            failed_decode_allocation::<BlockTransactionsRequest>(&concat(&[
+                &[0; 32],
+                &[0xFD, 0xFF, 0xFF],
+                data,
+            ])),
+        ),
+        (
+            "CheckedData",
+            failed_decode_allocation::<CheckedData>(&concat(&[
+                &(encode::MAX_VEC_SIZE as u32 - 1).to_le_bytes(),
+                &[0; 4],
+                data,
+            ])),
+        ),
+    ];
+    for (name, allocation) in cases {
+        assert!(allocation < MAX_ALLOCATION, "decoding {} allocated {} bytes", name, allocation);
+    }
+
+    // A `headers` message with a valid checksum but a truncated payload.
+    let payload = CheckedData::new(concat(&[huge, data]));
+    let message =
+        concat(&[&Magic::BITCOIN.to_bytes(), b"headers\0\0\0\0\0", &encode::serialize(&payload)]);
+    let allocation = failed_decode_allocation::<RawNetworkMessage>(&message);
+    assert!(allocation < MAX_ALLOCATION, "decoding headers allocated {} bytes", allocation);
+
+    // A PSBT whose first global key claims to be almost `MAX_VEC_SIZE` bytes long.
+    let psbt = concat(&[b"psbt\xff", large, data]);
+    let start = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    assert!(Psbt::deserialize(&psbt).is_err());
+    let allocation = PEAK.load(Ordering::SeqCst) - start;
+    assert!(allocation < MAX_ALLOCATION, "decoding PSBT allocated {} bytes", allocation);
+}
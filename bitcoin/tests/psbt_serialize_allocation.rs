@@ -0,0 +1,121 @@
+//! Tests that serializing a PSBT into a writer doesn't collect the PSBT or its values in memory.
+//!
+//! This file installs a global allocator so it must only contain a single test, running tests in
+//! parallel would skew the measured allocations.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bitcoin::hashes::sha256;
+use bitcoin::psbt::Psbt;
+use bitcoin::script::ScriptBufExt as _;
+use bitcoin::{
+    absolute, transaction, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+
+/// Upper bound for the memory used to serialize the PSBT, a small fraction of its size.
+const MAX_ALLOCATION: usize = 64 * 1024;
+
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+impl PeakAllocator {
+    fn allocated(size: usize) {
+        let current = CURRENT.fetch_add(size, Ordering::SeqCst) + size;
+        PEAK.fetch_max(current, Ordering::SeqCst);
+    }
+
+    fn deallocated(size: usize) { CURRENT.fetch_sub(size, Ordering::SeqCst); }
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::deallocated(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::deallocated(layout.size());
+            Self::allocated(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Runs `f` and returns its result together with the peak memory allocated meanwhile.
+fn peak_allocation<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let start = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    let result = f();
+    (result, PEAK.load(Ordering::SeqCst) - start)
+}
+
+#[test]
+fn serialize_to_writer_allocation_is_bounded() {
+    // A non-witness UTXO of over 3MB.
+    let output = TxOut {
+        value: Amount::ONE_SAT,
+        script_pubkey: ScriptBuf::from_hex("a9143545e6e33b832c47050f24d3eeb93c9c03948bc787")
+            .unwrap(),
+    };
+    let utxo = Transaction {
+        version: transaction::Version::TWO,
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![],
+        output: vec![output.clone(); 100_000],
+    };
+    let unsigned_tx = Transaction {
+        version: transaction::Version::TWO,
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: utxo.compute_txid(), vout: 0 },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
+            witness: Witness::default(),
+        }],
+        output: vec![output],
+    };
+    let mut psbt = Psbt::from_unsigned_tx(unsigned_tx).unwrap();
+    psbt.inputs[0].non_witness_utxo = Some(utxo);
+    // A large final witness and redeem script.
+    psbt.inputs[0].final_script_witness = Some(Witness::from_slice(&[vec![1; 500_000]]));
+    psbt.inputs[0].redeem_script = Some(ScriptBuf::from_bytes(vec![0x51; 500_000]));
+
+    let serialized = psbt.serialize();
+    assert!(serialized.len() > 4_000_000);
+
+    let (result, allocation) = peak_allocation(|| {
+        let mut engine = sha256::Hash::engine();
+        let len = psbt.serialize_to_writer(&mut engine).unwrap();
+        (len, sha256::Hash::from_engine(engine))
+    });
+    assert_eq!(result, (serialized.len(), sha256::Hash::hash(&serialized)));
+    assert!(allocation < MAX_ALLOCATION, "serializing allocated {} bytes", allocation);
+
+    #[cfg(feature = "base64")]
+    {
+        let base64 = psbt.to_string();
+        let (result, allocation) = peak_allocation(|| {
+            let mut engine = sha256::Hash::engine();
+            let len = psbt.serialize_base64_to_writer(&mut engine).unwrap();
+            (len, sha256::Hash::from_engine(engine))
+        });
+        assert_eq!(result, (base64.len(), sha256::Hash::hash(base64.as_bytes())));
+        assert!(allocation < MAX_ALLOCATION, "base64 serializing allocated {} bytes", allocation);
+    }
+}
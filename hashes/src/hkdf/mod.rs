@@ -59,36 +59,31 @@ pub fn expand(&self, info: &[u8], okm: &mut [u8]) -> Result<(), MaxLengthError>
             return Err(MaxLengthError { max: MAX_OUTPUT_BLOCKS * T::Bytes::LEN });
         }
 
-        // Counter starts at "1" based on RFC5869 spec and is committed to in the hash.
-        let mut counter = 1u8;
         // Ceiling calculation for the total number of blocks (iterations) required for the expand.
         let total_blocks = (okm.len() + T::Bytes::LEN - 1) / T::Bytes::LEN;
 
-        while counter <= total_blocks as u8 {
+        // Counter starts at "1" based on RFC5869 spec and is committed to in the hash. The length
+        // check above ensures it fits in a byte.
+        for counter in 1..=total_blocks {
             let mut engine: HmacEngine<T> = HmacEngine::new(self.prk.as_ref());
 
             // First block does not have a previous block,
             // all other blocks include last block in the HMAC input.
-            if counter != 1u8 {
-                let previous_start_index = (counter as usize - 2) * T::Bytes::LEN;
-                let previous_end_index = (counter as usize - 1) * T::Bytes::LEN;
+            if counter != 1 {
+                let previous_start_index = (counter - 2) * T::Bytes::LEN;
+                let previous_end_index = (counter - 1) * T::Bytes::LEN;
                 engine.input(&okm[previous_start_index..previous_end_index]);
             }
             engine.input(info);
-            engine.input(&[counter]);
+            engine.input(&[counter as u8]);
 
             let t = engine.finalize();
-            let start_index = (counter as usize - 1) * T::Bytes::LEN;
+            let start_index = (counter - 1) * T::Bytes::LEN;
             // Last block might not take full hash length.
-            let end_index = if counter == (total_blocks as u8) {
-                okm.len()
-            } else {
-                counter as usize * T::Bytes::LEN
-            };
+            let end_index =
+                if counter == total_blocks { okm.len() } else { counter * T::Bytes::LEN };
 
             okm[start_index..end_index].copy_from_slice(&t.as_ref()[0..(end_index - start_index)]);
-
-            counter += 1;
         }
 
         Ok(())
@@ -173,6 +168,38 @@ fn rfc5869_longer_inputs_outputs() {
         );
     }
 
+    #[test]
+    fn rfc5869_zero_length_salt_info() {
+        let ikm = Vec::from_hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b").unwrap();
+
+        let hkdf = Hkdf::<sha256::HashEngine>::new(&[], &ikm);
+        let mut okm = [0u8; 42];
+        hkdf.expand(&[], &mut okm).unwrap();
+
+        assert_eq!(
+            okm.to_lower_hex_string(),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+    }
+
+    #[test]
+    fn max_length_okm() {
+        let salt = Vec::from_hex("000102030405060708090a0b0c").unwrap();
+        let ikm = Vec::from_hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b").unwrap();
+        let info = Vec::from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap();
+
+        let hkdf = Hkdf::<sha256::HashEngine>::new(&salt, &ikm);
+        let mut okm = [0u8; 255 * 32];
+        hkdf.expand(&info, &mut okm).unwrap();
+
+        // Output is a prefix of any longer output.
+        assert_eq!(okm[..42], hkdf.expand_to_len(&info, 42).unwrap()[..]);
+        assert_eq!(
+            hkdf.expand(&info, &mut [0u8; 255 * 32 + 1]),
+            Err(MaxLengthError { max: 255 * 32 })
+        );
+    }
+
     #[test]
     fn too_long_okm() {
         let salt = Vec::from_hex("000102030405060708090a0b0c").unwrap();
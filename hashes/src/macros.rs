@@ -27,6 +27,8 @@
 /// have the midstate already pre-computed and prefer **compiler** performance to readability you
 /// may use `raw(MIDSTATE_BYTES, HASHED_BYTES_LENGTH)` instead, note that HASHED_BYTES_LENGTH must
 /// be a multiple of 64.
+///
+/// In all cases the midstate is a constant so the tag is hashed at compile time, not per hash.
 #[macro_export]
 macro_rules! sha256t_tag {
     ($(#[$($tag_attr:tt)*])* $tag_vis:vis struct $tag:ident = $constructor:tt($($tag_value:tt)+);) => {
@@ -38,8 +38,13 @@ pub fn hash_byte_chunks<B, I, T>(byte_slices: I) -> Hash<T>
 }
 
 /// Trait representing a tag that can be used as a context for SHA256t hashes.
+///
+/// The tag is only ever hashed when computing [`Tag::MIDSTATE`], which is a constant so every
+/// engine starts from the precomputed midstate instead of hashing `sha256(tag) || sha256(tag)`.
 pub trait Tag {
     /// The [`Midstate`] after pre-tagging the hash engine.
+    ///
+    /// [`Midstate::hash_tag`] can be used to compute this in `const` context.
     const MIDSTATE: sha256::Midstate;
 }
 
@@ -208,9 +213,41 @@ macro_rules! sha256t_tag_constructor {
     };
 }
 
+#[cfg(bench)]
+mod benches {
+    use test::Bencher;
+
+    use crate::{sha256, sha256t, HashEngine as _};
+
+    sha256t_tag! {
+        struct BenchTag = hash_str("TapLeaf");
+    }
+
+    #[bench]
+    pub fn sha256t_precomputed_midstate_64(bh: &mut Bencher) {
+        let bytes = [1u8; 64];
+        bh.iter(|| sha256t::Hash::<BenchTag>::hash(&bytes));
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha256t_rehash_tag_64(bh: &mut Bencher) {
+        let bytes = [1u8; 64];
+        bh.iter(|| {
+            let tag = sha256::Hash::hash(b"TapLeaf");
+            let mut engine = sha256::Hash::engine();
+            engine.input(tag.as_ref());
+            engine.input(tag.as_ref());
+            engine.input(&bytes);
+            engine.finalize()
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{sha256, sha256t};
+    use crate::{sha256, sha256t, HashEngine as _};
 
     const TEST_MIDSTATE: [u8; 32] = [
         156, 224, 228, 230, 124, 17, 108, 57, 56, 179, 202, 242, 195, 15, 80, 137, 211, 243, 147,
@@ -235,6 +272,28 @@ impl sha256t::Tag for TestHashTag {
         const MIDSTATE: sha256::Midstate = sha256::Midstate::new(TEST_MIDSTATE, 64);
     }
 
+    sha256t_tag! {
+        struct TapLeafTag = hash_str("TapLeaf");
+    }
+
+    #[test]
+    fn midstate_is_precomputed() {
+        let tag = sha256::Hash::hash(b"TapLeaf");
+        let mut engine = sha256::Hash::engine();
+        engine.input(tag.as_ref());
+        engine.input(tag.as_ref());
+        assert_eq!(<TapLeafTag as sha256t::Tag>::MIDSTATE, engine.midstate().unwrap());
+
+        // The engine starts from the midstate so only the data is left to hash.
+        let tagged = sha256t::Hash::<TapLeafTag>::engine();
+        assert_eq!(tagged.n_bytes_hashed(), 64);
+
+        engine.input(&[0xab; 100]);
+        let want = sha256::Hash::from_engine(engine);
+        let got = sha256t::Hash::<TapLeafTag>::hash(&[0xab; 100]);
+        assert_eq!(got.to_byte_array(), want.to_byte_array());
+    }
+
     // We support manually implementing `Tag` and creating a tagged hash from it.
     #[cfg(feature = "alloc")]
     #[cfg(feature = "hex")]
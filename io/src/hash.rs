@@ -134,6 +134,13 @@ fn flush(&mut self) -> crate::Result<()> {
 );
 
 /// Hashes data from a reader.
+///
+/// The data is fed to the engine one buffer at a time so the input does not need to fit in memory.
+/// To hash a `std::io::Read` wrap it in a `std::io::BufReader` and pass it using [`from_std_mut`].
+/// The engines also implement [`Write`] so data can be hashed while it is being written.
+///
+/// [`from_std_mut`]: crate::from_std_mut
+/// [`Write`]: crate::Write
 pub fn hash_reader<T>(reader: &mut impl BufRead) -> Result<T::Hash, crate::Error>
 where
     T: hashes::HashEngine + Default,
@@ -158,11 +165,12 @@ pub fn hash_reader<T>(reader: &mut impl BufRead) -> Result<T::Hash, crate::Error
 #[cfg(feature = "alloc")]
 mod tests {
     use alloc::format;
+    use alloc::vec::Vec;
 
     use hashes::hmac;
 
     use super::*;
-    use crate::{Cursor, Write as _};
+    use crate::{Cursor, Read, Write as _};
 
     macro_rules! write_test {
         ($mod:ident, $exp_empty:expr, $exp_256:expr, $exp_64k:expr,) => {
@@ -313,6 +321,55 @@ fn $test_name() {
         hash_from_reader_sha512_256, sha512_256, "e204244c429b5bca037a2a8a6e7ed8a42b808ceaff182560840bb8c5c8e9a2ec";
     }
 
+    /// A reader which returns at most `chunk_len` bytes from each call to `fill_buf`.
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_len: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> crate::Result<usize> {
+            let len = self.fill_buf()?.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.consume(len);
+            Ok(len)
+        }
+    }
+
+    impl BufRead for ChunkedReader<'_> {
+        fn fill_buf(&mut self) -> crate::Result<&[u8]> {
+            Ok(&self.data[..self.chunk_len.min(self.data.len())])
+        }
+
+        fn consume(&mut self, amount: usize) { self.data = &self.data[amount..]; }
+    }
+
+    macro_rules! impl_large_reader_test {
+        ($($test_name:ident, $module:ident);* $(;)?) => {
+            $(
+                #[test]
+                fn $test_name() {
+                    let data = (0..3_000_000_u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+                    let want = $module::Hash::hash(&data);
+
+                    let mut reader = ChunkedReader { data: &data, chunk_len: 4096 };
+                    assert_eq!($crate::hash_reader::<$module::HashEngine>(&mut reader).unwrap(), want);
+
+                    let mut engine = $module::Hash::engine();
+                    for chunk in data.chunks(1000) {
+                        engine.write_all(chunk).unwrap();
+                    }
+                    assert_eq!($module::Hash::from_engine(engine), want);
+                }
+            )*
+        }
+    }
+
+    impl_large_reader_test! {
+        hash_large_reader_sha256, sha256;
+        hash_large_reader_sha256d, sha256d;
+    }
+
     #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
     pub struct RegHashTag; // Name comes from regression tests in `bitcoin_hashes`.
 
@@ -223,6 +223,10 @@ pub const fn is_block_time(self) -> bool { !self.is_block_height() }
     /// is satisfied if a transaction with `nLockTime` ([`Transaction::lock_time`]) set to
     /// `height`/`time` is valid.
     ///
+    /// Both comparisons are inclusive. Note that this differs from the consensus rule for the
+    /// finality of a transaction, which requires a time based `nLockTime` to be strictly less than
+    /// the median time past of the chain tip (see `TransactionExt::is_final` in `rust-bitcoin`).
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -132,6 +132,9 @@ pub fn from_sequence(n: Sequence) -> Result<Self, DisabledLockTimeError> {
     }
 
     /// Encodes the locktime as a sequence number.
+    ///
+    /// The returned sequence has the disable flag unset and the type flag set according to the
+    /// unit of this locktime, so [`Sequence::to_relative_lock_time`] returns `self` again.
     #[inline]
     pub fn to_sequence(self) -> Sequence { Sequence::from_consensus(self.to_consensus_u32()) }
 
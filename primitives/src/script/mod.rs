@@ -32,6 +32,8 @@
 pub const MAX_REDEEM_SCRIPT_SIZE: usize = 520;
 /// The maximum allowed redeem script size of the witness script.
 pub const MAX_WITNESS_SCRIPT_SIZE: usize = 10_000;
+/// The maximum size of a script, executing a larger script always fails.
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
 
 hashes::hash_newtype! {
     /// A 160-bit hash of Bitcoin Script bytecode.
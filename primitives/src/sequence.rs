@@ -185,6 +185,12 @@ pub fn from_consensus(n: u32) -> Self { Sequence(n) }
     pub fn to_consensus_u32(self) -> u32 { self.0 }
 
     /// Constructs a new [`relative::LockTime`] from this [`Sequence`] number.
+    ///
+    /// The type flag (bit 22) selects between a block height and a time in units of 512 seconds,
+    /// the lock value is the low 16 bits and all other bits are ignored as specified by BIP-68.
+    ///
+    /// Returns `None` if the disable flag (bit 31) is set. This is the inverse of
+    /// [`relative::LockTime::to_sequence`].
     #[inline]
     pub fn to_relative_lock_time(self) -> Option<relative::LockTime> {
         use crate::locktime::relative::{Height, LockTime, Time};
@@ -344,6 +350,45 @@ fn sequence_properties() {
         assert!(!seq_height_locked.is_time_locked());
     }
 
+    #[test]
+    fn sequence_to_relative_lock_time() {
+        use crate::locktime::relative::LockTime;
+
+        assert_eq!(Sequence(0).to_relative_lock_time(), Some(LockTime::from_height(0)));
+        assert_eq!(Sequence(144).to_relative_lock_time(), Some(LockTime::from_height(144)));
+        assert_eq!(
+            Sequence(0x0040_0002).to_relative_lock_time(),
+            Some(LockTime::from_512_second_intervals(2))
+        );
+        assert_eq!(
+            Sequence::from_512_second_intervals(u16::MAX).to_relative_lock_time(),
+            Some(LockTime::from_512_second_intervals(u16::MAX))
+        );
+
+        // Bits other than the disable flag, the type flag and the low 16 bits are ignored.
+        assert_eq!(Sequence(0x7fbf_0090).to_relative_lock_time(), Some(LockTime::from_height(144)));
+        assert_eq!(
+            Sequence(0x7fff_0090).to_relative_lock_time(),
+            Some(LockTime::from_512_second_intervals(144))
+        );
+
+        // The disable flag turns off relative lock time regardless of the other bits.
+        assert_eq!(Sequence::MAX.to_relative_lock_time(), None);
+        assert_eq!(Sequence::ENABLE_LOCKTIME_NO_RBF.to_relative_lock_time(), None);
+        assert_eq!(Sequence(0x8040_0090).to_relative_lock_time(), None);
+        assert_eq!(Sequence(0x8000_0000).to_relative_lock_time(), None);
+
+        for lock_time in [
+            LockTime::from_height(0),
+            LockTime::from_height(u16::MAX),
+            LockTime::from_512_second_intervals(0),
+            LockTime::from_512_second_intervals(u16::MAX),
+        ] {
+            assert_eq!(lock_time.to_sequence().to_relative_lock_time(), Some(lock_time));
+            assert_eq!(Sequence::from(lock_time), lock_time.to_sequence());
+        }
+    }
+
     #[test]
     fn sequence_formatting() {
         let sequence = Sequence(0x7FFF_FFFF);
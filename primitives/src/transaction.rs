@@ -20,6 +20,8 @@
 use arbitrary::{Arbitrary, Unstructured};
 use hashes::sha256d;
 #[cfg(feature = "alloc")]
+use hex::DisplayHex as _;
+#[cfg(feature = "alloc")]
 use internals::{compact_size, write_err};
 #[cfg(feature = "alloc")]
 use units::{parse, Amount, Weight};
@@ -27,7 +29,7 @@
 #[cfg(feature = "alloc")]
 use crate::locktime::absolute;
 #[cfg(feature = "alloc")]
-use crate::prelude::Vec;
+use crate::prelude::{String, Vec};
 #[cfg(feature = "alloc")]
 use crate::script::ScriptBuf;
 #[cfg(feature = "alloc")]
@@ -135,6 +137,11 @@ pub fn outputs_mut(&mut self) -> &mut [TxOut] { &mut self.output }
     ///
     /// This gives a way to identify a transaction that is "the same" as
     /// another in the sense of having same inputs and outputs.
+    ///
+    /// The hash is computed over the transaction with all scriptSigs and witnesses blanked, so
+    /// transactions malleated by a third party, e.g. by re-encoding a signature, share the same
+    /// ntxid. It is not a consensus identifier: it is not used anywhere in the Bitcoin protocol
+    /// and must not be used in place of a [`Txid`], which is why it is returned as a plain hash.
     #[doc(alias = "ntxid")]
     pub fn compute_ntxid(&self) -> sha256d::Hash {
         let cloned_tx = Transaction {
@@ -387,6 +394,16 @@ impl OutPoint {
     /// This is used as the dummy input for coinbase transactions because they don't have any
     /// previous outputs. In other words, does not point to a real transaction.
     pub const COINBASE_PREVOUT: Self = Self { txid: Txid::COINBASE_PREVOUT, vout: u32::MAX };
+
+    /// The "null" `OutPoint` used in a coinbase prevout, the same as [`OutPoint::COINBASE_PREVOUT`].
+    pub const COINBASE: Self = Self::COINBASE_PREVOUT;
+
+    /// Returns true if this is the "null" `OutPoint`, the sentinel used as a coinbase prevout.
+    ///
+    /// An `OutPoint` is null if its TXID is all zeros *and* its `vout` is `u32::MAX`, see
+    /// [`OutPoint::COINBASE`].
+    #[inline]
+    pub fn is_null(&self) -> bool { self.txid == Txid::COINBASE_PREVOUT && self.vout == u32::MAX }
 }
 
 impl fmt::Display for OutPoint {
@@ -510,6 +527,14 @@ impl Txid {
     /// This is used as the "txid" of the dummy input of a coinbase transaction. This is not a real
     /// TXID and should not be used in any other contexts. See [`OutPoint::COINBASE_PREVOUT`].
     pub const COINBASE_PREVOUT: Self = Self::from_byte_array([0; 32]);
+
+    /// Returns the hex encoding of the bytes in internal order, as returned by `to_byte_array`.
+    ///
+    /// The `Display` implementation (and thus `to_string`) prints the bytes in reverse order, as
+    /// Bitcoin Core and block explorers do. Use this only when the internal order is required,
+    /// e.g. to compare against a serialized transaction.
+    #[cfg(feature = "alloc")]
+    pub fn to_string_forward(self) -> String { self.as_byte_array().to_lower_hex_string() }
 }
 
 impl Wtxid {
@@ -519,6 +544,13 @@ impl Wtxid {
     /// witness commitment tree) since the coinbase transaction contains a commitment to all
     /// transactions' wTXIDs but naturally cannot commit to its own.
     pub const COINBASE: Self = Self::from_byte_array([0; 32]);
+
+    /// Returns the hex encoding of the bytes in internal order, as returned by `to_byte_array`.
+    ///
+    /// The `Display` implementation (and thus `to_string`) prints the bytes in reverse order, as
+    /// Bitcoin Core does.
+    #[cfg(feature = "alloc")]
+    pub fn to_string_forward(self) -> String { self.as_byte_array().to_lower_hex_string() }
 }
 
 /// The transaction version.
@@ -698,6 +730,20 @@ fn transaction_functions() {
         assert!(tx > tx_orig);
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn outpoint_is_null() {
+        let coinbase = TxIn::EMPTY_COINBASE;
+        assert!(coinbase.previous_output.is_null());
+        assert_eq!(OutPoint::COINBASE, OutPoint::COINBASE_PREVOUT);
+
+        // Both the TXID and the `vout` must match.
+        let outpoint = OutPoint { txid: Txid::COINBASE_PREVOUT, vout: 0 };
+        assert!(!outpoint.is_null());
+        let outpoint = OutPoint { txid: Txid::from_byte_array([0xAA; 32]), vout: u32::MAX };
+        assert!(!outpoint.is_null());
+    }
+
     #[test]
     fn outpoint_from_str() {
         // Check format errors
@@ -724,6 +770,30 @@ fn outpoint_from_str() {
         assert_eq!(outpoint, Err(ParseOutPointError::TooLong));
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn txid_display_is_reversed() {
+        use alloc::string::ToString;
+
+        use hex::FromHex as _;
+
+        let display = "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082";
+        let mut bytes = <[u8; 32]>::from_hex(display).unwrap();
+        bytes.reverse();
+
+        let txid = display.parse::<Txid>().unwrap();
+        assert_eq!(txid.to_byte_array(), bytes);
+        assert_eq!(txid.as_byte_array(), &bytes);
+        assert_eq!(txid.to_string(), display);
+        assert_eq!(txid.to_string_forward(), bytes.to_lower_hex_string());
+        assert_eq!(Txid::from_byte_array(bytes), txid);
+
+        let wtxid = Wtxid::from_byte_array(bytes);
+        assert_eq!(wtxid.to_string(), display);
+        assert_eq!(wtxid.to_string_forward(), txid.to_string_forward());
+        assert_eq!(display.parse::<Wtxid>().unwrap(), wtxid);
+    }
+
     #[test]
     fn canonical_vout() {
         assert_eq!(parse_vout("0").unwrap(), 0);
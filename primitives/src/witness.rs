@@ -58,6 +58,18 @@ pub const fn new() -> Self {
         Witness { content: Vec::new(), witness_elements: 0, indices_start: 0 }
     }
 
+    /// Constructs a new empty [`Witness`] with space for `elements` elements of `total_bytes`
+    /// bytes combined.
+    ///
+    /// Pushing up to that many elements and bytes does not reallocate, the space required for the
+    /// length prefixes and internal bookkeeping is accounted for.
+    pub fn with_capacity(elements: usize, total_bytes: usize) -> Self {
+        // No element can be longer than `total_bytes` so its prefix is at most this long.
+        let per_element = compact_size::encoded_size(total_bytes) + 4;
+        let capacity = elements.saturating_mul(per_element).saturating_add(total_bytes);
+        Witness { content: Vec::with_capacity(capacity), witness_elements: 0, indices_start: 0 }
+    }
+
     /// Constructs a new [`Witness`] from inner parts.
     ///
     /// This function leaks implementation details of the `Witness`, as such it is unstable and
@@ -119,6 +131,7 @@ pub fn iter(&self) -> Iter {
     pub fn len(&self) -> usize { self.witness_elements }
 
     /// Returns the number of bytes this witness contributes to a transactions total size.
+    #[doc(alias = "consensus_size")]
     pub fn size(&self) -> usize {
         let mut size: usize = 0;
 
@@ -454,33 +467,21 @@ fn visit_seq<A: serde::de::SeqAccess<'de>>(
                 self,
                 mut a: A,
             ) -> Result<Self::Value, A::Error> {
-                use hex::{FromHex, HexToBytesError as E};
-                use serde::de::{self, Unexpected};
-
-                let mut ret = match a.size_hint() {
-                    Some(len) => Vec::with_capacity(len),
-                    None => Vec::new(),
-                };
+                use hex::FromHex;
+                use serde::de;
 
+                let mut witness = Witness::new();
                 while let Some(elem) = a.next_element::<String>()? {
-                    let vec = Vec::<u8>::from_hex(&elem).map_err(|e| match e {
-                        E::InvalidChar(ref e) =>
-                            match core::char::from_u32(e.invalid_char().into()) {
-                                Some(c) => de::Error::invalid_value(
-                                    Unexpected::Char(c),
-                                    &"a valid hex character",
-                                ),
-                                None => de::Error::invalid_value(
-                                    Unexpected::Unsigned(e.invalid_char().into()),
-                                    &"a valid hex character",
-                                ),
-                            },
-                        E::OddLengthString(ref e) =>
-                            de::Error::invalid_length(e.length(), &"an even length string"),
+                    let vec = Vec::<u8>::from_hex(&elem).map_err(|e| {
+                        de::Error::custom(format_args!(
+                            "invalid hex in witness element {}: {}",
+                            witness.len(),
+                            e
+                        ))
                     })?;
-                    ret.push(vec);
+                    witness.push(vec);
                 }
-                Ok(Witness::from_slice(&ret))
+                Ok(witness)
             }
         }
 
@@ -493,6 +494,22 @@ fn visit_seq<A: serde::de::SeqAccess<'de>>(
     }
 }
 
+impl<T: AsRef<[u8]>> core::iter::FromIterator<T> for Witness {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut witness = Witness::new();
+        witness.extend(iter);
+        witness
+    }
+}
+
+impl<T: AsRef<[u8]>> Extend<T> for Witness {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
 impl From<Vec<Vec<u8>>> for Witness {
     #[inline]
     fn from(vec: Vec<Vec<u8>>) -> Self { Witness::from_slice(&vec) }
@@ -745,6 +762,47 @@ macro_rules! ck {
         ck!([[42u8], [21]]);
     }
 
+    #[test]
+    fn with_capacity_extend_push() {
+        let elements = [vec![], vec![1_u8; 2], vec![2_u8; 253], vec![3_u8; 10]];
+        let total_bytes = elements.iter().map(Vec::len).sum();
+        let expected = Witness::from_slice(&elements);
+
+        let mut witness = Witness::with_capacity(elements.len(), total_bytes);
+        let capacity = witness.content.capacity();
+        witness.push(&elements[0]);
+        witness.extend(&elements[1..3]);
+        witness.extend(core::iter::empty::<&[u8]>());
+        witness.push(&elements[3]);
+        assert_eq!(witness.content.capacity(), capacity);
+
+        assert_eq!(witness, expected);
+        assert_eq!(witness.indices_start, expected.indices_start);
+        for (i, element) in elements.iter().enumerate() {
+            assert_eq!(witness.get(i), Some(&element[..]));
+        }
+        assert_eq!(witness.iter().len(), elements.len());
+
+        // Elements can still be pushed past the capacity.
+        witness.push([4_u8; 1000]);
+        assert_eq!(witness.len(), 5);
+        assert_eq!(witness.last(), Some(&[4_u8; 1000][..]));
+    }
+
+    #[test]
+    fn from_iter() {
+        let elements = [vec![], vec![1_u8; 2], vec![2_u8; 253]];
+
+        let witness = elements.iter().collect::<Witness>();
+        assert_eq!(witness, Witness::from_slice(&elements));
+        let witness = elements.iter().map(Vec::as_slice).collect::<Witness>();
+        assert_eq!(witness, Witness::from_slice(&elements));
+        let witness = Witness::from_slice(&elements).iter().collect::<Witness>();
+        assert_eq!(witness, Witness::from_slice(&elements));
+
+        assert_eq!(core::iter::empty::<[u8; 1]>().collect::<Witness>(), Witness::new());
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn serde_bincode_backward_compatibility() {
@@ -786,6 +844,44 @@ fn serde_human_roundtrips() {
         assert_eq!(rinsed, original);
     }
 
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_human_core_rpc() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct Input {
+            txinwitness: Witness,
+        }
+
+        // The `txinwitness` of P2WPKH and 2-of-3 P2WSH inputs as returned by `getrawtransaction`.
+        let p2wpkh = r#"{"txinwitness":["3044022016243edad96b18c78b545325aaff80131689f681079fb107a67018cb7fb7830e02205520dae761d89728f73f1a7182157f6b5aecf653525855adb7ccb998c8e6143b01","03b9489bde92afbcfa85129a82ffa512897105d1a27ad9806bded27e0532fc84e7"]}"#;
+        let p2wsh = r#"{"txinwitness":["","30440220548f11130353b3a8f943d2f14260345fc7c20bde91704c9f1cbb5456355078cd0220383ed4ed39b079b618bcb279bbc1f2ca18cb028c4641cb522c9c5868c52a0dc201","304402203c332ecccb3181ca82c0600520ee51fee80d3b4a6ab110945e59475ec71e44ac0220679a11f3ca9993b04ccebda3c834876f353b065bb08f50076b25f5bb93c72ae101","52210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae"]}"#;
+
+        for (json, lens) in [(p2wpkh, &[71, 33][..]), (p2wsh, &[0, 71, 71, 105][..])] {
+            let input: Input = serde_json::from_str(json).unwrap();
+            let witness = input.txinwitness;
+            assert_eq!(witness.iter().map(<[u8]>::len).collect::<Vec<_>>(), lens);
+            assert_eq!(witness, Witness::from_slice(&witness.to_vec()));
+            assert_eq!(witness.last(), witness.get(lens.len() - 1));
+            assert_eq!(serde_json::to_string(&Input { txinwitness: witness }).unwrap(), json);
+        }
+
+        let empty: Witness = serde_json::from_str("[]").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "[]");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_human_invalid_hex() {
+        let err = serde_json::from_str::<Witness>(r#"["00", "0g"]"#).unwrap_err().to_string();
+        assert!(err.starts_with("invalid hex in witness element 1: "), "{}", err);
+
+        let err = serde_json::from_str::<Witness>(r#"["abc"]"#).unwrap_err().to_string();
+        assert!(err.starts_with("invalid hex in witness element 0: "), "{}", err);
+
+        assert!(serde_json::from_str::<Witness>(r#"["00", 1]"#).is_err());
+    }
+
     #[test]
     #[cfg(feature = "serde")]
     fn serde_human() {
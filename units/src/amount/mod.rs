@@ -537,6 +537,7 @@ fn fmt_satoshi_in(
 ///
 /// * Ability to select denomination
 /// * Show or hide denomination
+/// * Show or strip trailing decimal zeros
 /// * Dynamically-selected denomination - show in sats if less than 1 BTC.
 ///
 /// However, this can still be combined with [`fmt::Formatter`] options to precisely control zeros,
@@ -556,6 +557,8 @@ pub struct Display {
     is_negative: bool,
     /// How to display the value
     style: DisplayStyle,
+    /// Whether to show all the decimal places of the denomination
+    trailing_zeros: bool,
 }
 
 impl Display {
@@ -569,23 +572,50 @@ pub fn show_denomination(mut self) -> Self {
         }
         self
     }
+
+    /// Makes subsequent calls to `Display::fmt` hide denomination.
+    ///
+    /// This is a no-op for dynamic denomination which is always shown.
+    #[must_use]
+    pub fn hide_denomination(mut self) -> Self {
+        match &mut self.style {
+            DisplayStyle::FixedDenomination { show_denomination, .. } => *show_denomination = false,
+            DisplayStyle::DynamicDenomination => (),
+        }
+        self
+    }
+
+    /// Makes subsequent calls to `Display::fmt` show all the decimal places of the denomination.
+    ///
+    /// For example one BTC is displayed as `1.00000000` instead of `1`. Precision given to the
+    /// [`fmt::Formatter`] takes priority over this.
+    #[must_use]
+    pub fn show_trailing_zeros(mut self) -> Self {
+        self.trailing_zeros = true;
+        self
+    }
+
+    /// Makes subsequent calls to `Display::fmt` strip trailing decimal zeros, this is the default.
+    #[must_use]
+    pub fn strip_trailing_zeros(mut self) -> Self {
+        self.trailing_zeros = false;
+        self
+    }
 }
 
 impl fmt::Display for Display {
     #[rustfmt::skip]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let format_options = FormatOptions::from_formatter(f);
-        match &self.style {
-            DisplayStyle::FixedDenomination { show_denomination, denomination } => {
-                fmt_satoshi_in(self.sats_abs, self.is_negative, f, *denomination, *show_denomination, format_options)
-            },
-            DisplayStyle::DynamicDenomination if self.sats_abs >= Amount::ONE_BTC.to_sat() => {
-                fmt_satoshi_in(self.sats_abs, self.is_negative, f, Denomination::Bitcoin, true, format_options)
-            },
-            DisplayStyle::DynamicDenomination => {
-                fmt_satoshi_in(self.sats_abs, self.is_negative, f, Denomination::Satoshi, true, format_options)
-            },
+        let (denomination, show_denomination) = match self.style {
+            DisplayStyle::FixedDenomination { show_denomination, denomination } => (denomination, show_denomination),
+            DisplayStyle::DynamicDenomination if self.sats_abs >= Amount::ONE_BTC.to_sat() => (Denomination::Bitcoin, true),
+            DisplayStyle::DynamicDenomination => (Denomination::Satoshi, true),
+        };
+        let mut format_options = FormatOptions::from_formatter(f);
+        if self.trailing_zeros && format_options.precision.is_none() && denomination.precision() < 0 {
+            format_options.precision = Some(usize::from(denomination.precision().unsigned_abs()));
         }
+        fmt_satoshi_in(self.sats_abs, self.is_negative, f, denomination, show_denomination, format_options)
     }
 }
 
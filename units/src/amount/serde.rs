@@ -24,7 +24,11 @@
 
 #[cfg(feature = "alloc")]
 use core::fmt;
+#[cfg(feature = "alloc")]
+use core::marker::PhantomData;
 
+#[cfg(feature = "alloc")]
+use serde::de;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[cfg(feature = "alloc")] // This is because `to_float_in` uses `to_string`.
@@ -87,6 +91,69 @@ impl fmt::Display for DisplayFullError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(&self.0, f) }
 }
 
+/// Parses amounts denominated in BTC, used by [`VisitBtc`].
+#[cfg(feature = "alloc")]
+trait FromBtc: Sized {
+    fn from_btc_f64(btc: f64) -> Result<Self, ParseAmountError>;
+    fn from_btc_str(btc: &str) -> Result<Self, ParseAmountError>;
+}
+
+#[cfg(feature = "alloc")]
+impl FromBtc for Amount {
+    fn from_btc_f64(btc: f64) -> Result<Self, ParseAmountError> { Amount::from_btc(btc) }
+    fn from_btc_str(btc: &str) -> Result<Self, ParseAmountError> {
+        Amount::from_str_in(btc, Denomination::Bitcoin)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromBtc for SignedAmount {
+    fn from_btc_f64(btc: f64) -> Result<Self, ParseAmountError> { SignedAmount::from_btc(btc) }
+    fn from_btc_str(btc: &str) -> Result<Self, ParseAmountError> {
+        SignedAmount::from_str_in(btc, Denomination::Bitcoin)
+    }
+}
+
+/// Visits an amount in BTC given either as a decimal string or, for backwards compatibility, as
+/// a number.
+#[cfg(feature = "alloc")]
+struct VisitBtc<X>(PhantomData<X>);
+
+#[cfg(feature = "alloc")]
+impl<X: FromBtc> de::Visitor<'_> for VisitBtc<X> {
+    type Value = X;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an amount in BTC as a decimal string or a number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        X::from_btc_str(v).map_err(DisplayFullError).map_err(E::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        X::from_btc_f64(v).map_err(DisplayFullError).map_err(E::custom)
+    }
+
+    // Integers are whole bitcoins, any that lose precision are far outside the valid range.
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> { self.visit_f64(v as f64) }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> { self.visit_f64(v as f64) }
+}
+
+/// Serializes an amount with [`SerdeAmount::ser_btc`], used for the `Some` case of `as_btc::opt`.
+#[cfg(feature = "alloc")]
+struct SerBtc<A>(A);
+
+#[cfg(feature = "alloc")]
+impl<A: SerdeAmount> Serialize for SerBtc<A> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.0.ser_btc(s, private::Token)
+    }
+}
+
 impl SerdeAmount for Amount {
     fn ser_sat<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
         u64::serialize(&self.to_sat(), s)
@@ -96,13 +163,20 @@ fn des_sat<'d, D: Deserializer<'d>>(d: D, _: private::Token) -> Result<Self, D::
         Amount::from_sat(u64::deserialize(d)?).map_err(D::Error::custom)
     }
     #[cfg(feature = "alloc")]
-    fn ser_btc<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
-        f64::serialize(&self.to_float_in(Denomination::Bitcoin), s)
+    fn ser_btc<S: Serializer>(self, s: S, t: private::Token) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() {
+            self.ser_str(s, t)
+        } else {
+            self.ser_sat(s, t)
+        }
     }
     #[cfg(feature = "alloc")]
-    fn des_btc<'d, D: Deserializer<'d>>(d: D, _: private::Token) -> Result<Self, D::Error> {
-        use serde::de::Error;
-        Amount::from_btc(f64::deserialize(d)?).map_err(DisplayFullError).map_err(D::Error::custom)
+    fn des_btc<'d, D: Deserializer<'d>>(d: D, t: private::Token) -> Result<Self, D::Error> {
+        if d.is_human_readable() {
+            d.deserialize_any(VisitBtc(PhantomData))
+        } else {
+            Self::des_sat(d, t)
+        }
     }
     #[cfg(feature = "alloc")]
     fn ser_str<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
@@ -125,7 +199,7 @@ fn ser_sat_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S:
     }
     #[cfg(feature = "alloc")]
     fn ser_btc_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
-        s.serialize_some(&self.to_btc())
+        s.serialize_some(&SerBtc(self))
     }
     #[cfg(feature = "alloc")]
     fn ser_str_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
@@ -142,15 +216,20 @@ fn des_sat<'d, D: Deserializer<'d>>(d: D, _: private::Token) -> Result<Self, D::
         SignedAmount::from_sat(i64::deserialize(d)?).map_err(D::Error::custom)
     }
     #[cfg(feature = "alloc")]
-    fn ser_btc<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
-        f64::serialize(&self.to_float_in(Denomination::Bitcoin), s)
+    fn ser_btc<S: Serializer>(self, s: S, t: private::Token) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() {
+            self.ser_str(s, t)
+        } else {
+            self.ser_sat(s, t)
+        }
     }
     #[cfg(feature = "alloc")]
-    fn des_btc<'d, D: Deserializer<'d>>(d: D, _: private::Token) -> Result<Self, D::Error> {
-        use serde::de::Error;
-        SignedAmount::from_btc(f64::deserialize(d)?)
-            .map_err(DisplayFullError)
-            .map_err(D::Error::custom)
+    fn des_btc<'d, D: Deserializer<'d>>(d: D, t: private::Token) -> Result<Self, D::Error> {
+        if d.is_human_readable() {
+            d.deserialize_any(VisitBtc(PhantomData))
+        } else {
+            Self::des_sat(d, t)
+        }
     }
     #[cfg(feature = "alloc")]
     fn ser_str<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
@@ -173,7 +252,7 @@ fn ser_sat_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S:
     }
     #[cfg(feature = "alloc")]
     fn ser_btc_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
-        s.serialize_some(&self.to_btc())
+        s.serialize_some(&SerBtc(self))
     }
     #[cfg(feature = "alloc")]
     fn ser_str_opt<S: Serializer>(self, s: S, _: private::Token) -> Result<S::Ok, S::Error> {
@@ -253,7 +332,12 @@ fn visit_some<D>(self, d: D) -> Result<Self::Value, D::Error>
 
 #[cfg(feature = "alloc")]
 pub mod as_btc {
-    //! Serialize and deserialize [`Amount`](crate::Amount) as JSON numbers denominated in BTC.
+    //! Serialize and deserialize [`Amount`](crate::Amount) denominated in BTC.
+    //!
+    //! Human-readable formats use a decimal string in BTC, the same as [`as_str`](super::as_str),
+    //! binary formats use the number of satoshis. When deserializing a human-readable format a
+    //! JSON number denominated in BTC is also accepted.
+    //!
     //! Use with `#[serde(with = "amount::serde::as_btc")]`.
 
     use serde::{Deserializer, Serializer};
@@ -270,7 +354,7 @@ pub fn deserialize<'d, A: SerdeAmount, D: Deserializer<'d>>(d: D) -> Result<A, D
     }
 
     pub mod opt {
-        //! Serialize and deserialize `Option<Amount>` as JSON numbers denominated in BTC.
+        //! Serialize and deserialize `Option<Amount>` denominated in BTC.
         //! Use with `#[serde(default, with = "amount::serde::as_btc::opt")]`.
 
         use core::fmt;
@@ -301,7 +385,7 @@ impl<'de, X: SerdeAmountForOpt> de::Visitor<'de> for VisitOptAmt<X> {
                 type Value = Option<X>;
 
                 fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    write!(formatter, "An Option<f64>")
+                    write!(formatter, "An optional amount in BTC")
                 }
 
                 fn visit_none<E>(self) -> Result<Self::Value, E>
@@ -433,7 +517,7 @@ pub struct HasAmount {
         let orig = HasAmount { amount: Amount::ONE_BTC };
 
         let json = serde_json::to_string(&orig).expect("failed to ser");
-        let want = "{\"amount\":1.0}";
+        let want = "{\"amount\":\"1\"}";
         assert_eq!(json, want);
 
         let rinsed: HasAmount = serde_json::from_str(&json).expect("failed to deser");
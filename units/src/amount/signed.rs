@@ -263,6 +263,7 @@ pub fn display_in(self, denomination: Denomination) -> Display {
             sats_abs: self.unsigned_abs().to_sat(),
             is_negative: self.is_negative(),
             style: DisplayStyle::FixedDenomination { denomination, show_denomination: false },
+            trailing_zeros: false,
         }
     }
 
@@ -277,6 +278,7 @@ pub fn display_dynamic(self) -> Display {
             sats_abs: self.unsigned_abs().to_sat(),
             is_negative: self.is_negative(),
             style: DisplayStyle::DynamicDenomination,
+            trailing_zeros: false,
         }
     }
 
@@ -794,6 +794,19 @@ struct T {
     let value: serde_json::Value = serde_json::from_str(json).unwrap();
     assert_eq!(t, serde_json::from_value(value).unwrap());
 
+    // Human-readable formats use a decimal string.
+    let json = serde_json::to_string(&orig).unwrap();
+    assert_eq!(json, "{\"amt\":\"20000000.00000001\",\"samt\":\"-20000000.00000001\"}");
+    assert_eq!(serde_json::from_str::<T>(&json).unwrap(), orig);
+
+    // Binary formats use satoshis.
+    let bytes = bincode::serialize(&orig).unwrap();
+    assert_eq!(
+        bytes,
+        bincode::serialize(&(20_000_000__000_000_01_u64, -20_000_000__000_000_01_i64)).unwrap()
+    );
+    assert_eq!(bincode::deserialize::<T>(&bytes).unwrap(), orig);
+
     // errors
     let t: Result<T, serde_json::Error> =
         serde_json::from_str("{\"amt\": 1000000.000000001, \"samt\": 1}");
@@ -855,6 +868,9 @@ struct T {
         assert_eq!(w, *s);
     }
 
+    let json = serde_json::to_string(&with).unwrap();
+    assert_eq!(json, "{\"amt\":\"2.5\",\"samt\":\"-2.5\"}");
+
     let t: T = serde_json::from_str("{\"amt\": 2.5, \"samt\": -2.5}").unwrap();
     assert_eq!(t, with);
 
@@ -1078,6 +1094,66 @@ fn trailing_zeros_for_amount() {
     assert_eq!(format!("{}", sat(400_000_000_000_000)), "4000000 BTC");
 }
 
+#[test]
+#[cfg(feature = "alloc")]
+fn display_trailing_zeros_and_denomination() {
+    let amount = sat(10_000_000);
+    let display = amount.display_in(Denomination::Bitcoin).show_denomination();
+    assert_eq!(format!("{}", display), "0.1 BTC");
+    assert_eq!(format!("{}", display.clone().show_trailing_zeros()), "0.10000000 BTC");
+    assert_eq!(
+        format!("{}", display.clone().show_trailing_zeros().strip_trailing_zeros()),
+        "0.1 BTC"
+    );
+    assert_eq!(format!("{}", display.clone().hide_denomination()), "0.1");
+    assert_eq!(format!("{}", display.hide_denomination().show_trailing_zeros()), "0.10000000");
+    // Formatter precision takes priority.
+    assert_eq!(
+        format!("{:.2}", amount.display_in(Denomination::Bitcoin).show_trailing_zeros()),
+        "0.10"
+    );
+
+    assert_eq!(
+        format!("{}", ssat(-10_000_000).display_in(Denomination::Bitcoin).show_trailing_zeros()),
+        "-0.10000000"
+    );
+    assert_eq!(
+        format!("{}", sat(1_500).display_in(Denomination::Bit).show_trailing_zeros()),
+        "15.00"
+    );
+
+    // Satoshis have no decimal places.
+    let display = amount.display_in(Denomination::Satoshi).show_denomination();
+    assert_eq!(format!("{}", display), "10000000 satoshi");
+    assert_eq!(format!("{}", display.clone().show_trailing_zeros()), "10000000 satoshi");
+    assert_eq!(format!("{}", display.hide_denomination()), "10000000");
+
+    // Dynamic denomination is always shown.
+    assert_eq!(format!("{}", amount.display_dynamic().show_trailing_zeros()), "10000000 satoshi");
+    assert_eq!(
+        format!("{}", Amount::ONE_BTC.display_dynamic().show_trailing_zeros()),
+        "1.00000000 BTC"
+    );
+    assert_eq!(format!("{}", Amount::ONE_BTC.display_dynamic().hide_denomination()), "1 BTC");
+
+    // Showing all decimal places round trips.
+    for sat in [0, 1, 10_000_000, 123_456_789, Amount::MAX.to_sat()] {
+        let amount = Amount::from_sat(sat).unwrap();
+        for denom in [
+            Denomination::Bitcoin,
+            Denomination::MilliBitcoin,
+            Denomination::Bit,
+            Denomination::Satoshi,
+        ] {
+            let s =
+                format!("{}", amount.display_in(denom).show_denomination().show_trailing_zeros());
+            assert_eq!(s.parse::<Amount>().unwrap(), amount);
+            let s = format!("{}", amount.display_in(denom).show_trailing_zeros());
+            assert_eq!(Amount::from_str_in(&s, denom).unwrap(), amount);
+        }
+    }
+}
+
 #[test]
 fn add_sub_combos() {
     // Checks lhs op rhs for all reference combos.
@@ -268,6 +268,7 @@ pub fn display_in(self, denomination: Denomination) -> Display {
             sats_abs: self.to_sat(),
             is_negative: false,
             style: DisplayStyle::FixedDenomination { denomination, show_denomination: false },
+            trailing_zeros: false,
         }
     }
 
@@ -282,6 +283,7 @@ pub fn display_dynamic(self) -> Display {
             sats_abs: self.to_sat(),
             is_negative: false,
             style: DisplayStyle::DynamicDenomination,
+            trailing_zeros: false,
         }
     }
 